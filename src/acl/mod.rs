@@ -0,0 +1,55 @@
+// src/acl/mod.rs
+
+//! Per‑router access control lists.
+//!
+//! A router's ACL is an ordered list of rules, each a capture‑filter expression with a
+//! permit/deny action. The first matching rule decides; packets matching no rule are permitted.
+//! Every rule counts its hits, so the effect of a mitigation can be read off afterwards.
+
+use crate::capture::CaptureFilter;
+use crate::packet::PacketMeta;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AclAction {
+    Permit,
+    Deny,
+}
+
+/// One rule as written in a router table: `{ action = "deny", match = "udp and port 53" }`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AclRuleConfig {
+    pub action: AclAction,
+    #[serde(rename = "match", default)]
+    pub filter: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AclRule {
+    pub action: AclAction,
+    pub filter: CaptureFilter,
+    /// Packets this rule decided on.
+    pub hits: u64,
+}
+
+impl AclRule {
+    pub fn from_config(cfg: &AclRuleConfig) -> Result<Self, String> {
+        Ok(Self {
+            action: cfg.action,
+            filter: CaptureFilter::parse(&cfg.filter)?,
+            hits: 0,
+        })
+    }
+}
+
+/// Evaluate `rules` in order against `packet`, counting the hit on the deciding rule.
+pub fn evaluate(rules: &mut [AclRule], packet: &PacketMeta) -> AclAction {
+    for rule in rules.iter_mut() {
+        if rule.filter.matches(packet) {
+            rule.hits += 1;
+            return rule.action;
+        }
+    }
+    AclAction::Permit
+}
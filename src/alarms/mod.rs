@@ -0,0 +1,346 @@
+// src/alarms/mod.rs
+
+//! Link monitoring alarms.
+//!
+//! `[alarms]` rules watch the links once every `window_secs`: the loss percentage and the
+//! utilization over the window, and the egress queue depth at its end. An alarm is raised when
+//! the value goes above the rule's threshold and cleared once it is back at or below it. Every
+//! change is logged at the rule's severity, kept in the run's alarm log and counted in the
+//! Prometheus metrics, so a soak test can assert that no critical alarm was raised instead of
+//! reading raw counters.
+
+use crate::config::{AlarmRuleConfig, AlarmsConfig};
+use crate::topology::{Fabric, LinkId, RouterId};
+use std::collections::{HashMap, HashSet};
+use tokio::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Alarm severity, least severe first (ITU‑T X.733).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Minor,
+    Major,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [
+        Severity::Warning,
+        Severity::Minor,
+        Severity::Major,
+        Severity::Critical,
+    ];
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "warning" => Ok(Severity::Warning),
+            "minor" => Ok(Severity::Minor),
+            "major" => Ok(Severity::Major),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(format!(
+                "Invalid alarm severity '{}': expected warning, minor, major or critical",
+                s
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Minor => "minor",
+            Severity::Major => "major",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+/// What a rule watches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Metric {
+    /// Packets dropped on the link, in percent of those offered during the window.
+    LossPercent,
+    /// Bytes carried during the window, in percent of the link bandwidth.
+    UtilizationPercent,
+    /// Packets waiting in the egress queues at the end of the window.
+    QueueDepth,
+}
+
+impl Metric {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "loss_percent" => Ok(Metric::LossPercent),
+            "utilization_percent" => Ok(Metric::UtilizationPercent),
+            "queue_depth" => Ok(Metric::QueueDepth),
+            _ => Err(format!(
+                "Invalid alarm metric '{}': expected loss_percent, utilization_percent or queue_depth",
+                s
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::LossPercent => "loss_percent",
+            Metric::UtilizationPercent => "utilization_percent",
+            Metric::QueueDepth => "queue_depth",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    metric: Metric,
+    threshold: f64,
+    severity: Severity,
+    /// Only this link, or every link.
+    link: Option<LinkId>,
+}
+
+impl Rule {
+    fn from_config(cfg: &AlarmRuleConfig) -> Result<Self, String> {
+        if !cfg.threshold.is_finite() || cfg.threshold < 0.0 {
+            return Err(format!(
+                "Alarm threshold must be a non-negative number, got {}",
+                cfg.threshold
+            ));
+        }
+        let link = match &cfg.link {
+            Some(name) => {
+                let (a, b) = name
+                    .split_once('_')
+                    .ok_or_else(|| format!("Alarm link '{}' is not of the form A_B", name))?;
+                Some(LinkId::new(
+                    RouterId(a.to_string()),
+                    RouterId(b.to_string()),
+                ))
+            }
+            None => None,
+        };
+        Ok(Self {
+            metric: Metric::parse(&cfg.metric)?,
+            threshold: cfg.threshold,
+            severity: Severity::parse(&cfg.severity)?,
+            link,
+        })
+    }
+}
+
+/// Counters of one link at the end of a window.
+#[derive(Debug, Clone)]
+pub struct LinkSample {
+    pub id: LinkId,
+    /// Packets offered to the link that fit its MTU.
+    pub offered: u64,
+    pub lost: u64,
+    pub bytes: u64,
+    pub queue_depth: usize,
+    pub bandwidth_mbps: Option<f64>,
+}
+
+/// Current counters of every link.
+pub fn sample(fabric: &Fabric) -> Vec<LinkSample> {
+    fabric
+        .graph
+        .edge_weights()
+        .map(|link| LinkSample {
+            id: link.id.clone(),
+            offered: link.counter().saturating_sub(link.too_big()),
+            lost: link.lost(),
+            bytes: link.bytes(),
+            queue_depth: link.queue_depth(),
+            bandwidth_mbps: link.cfg.bandwidth_mbps,
+        })
+        .collect()
+}
+
+/// An alarm being raised or cleared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlarmEvent {
+    /// Seconds since the monitor started.
+    pub at_secs: f64,
+    /// Link name, e.g. "Rx0y0_Rx0y1".
+    pub link: String,
+    pub metric: Metric,
+    pub severity: Severity,
+    pub value: f64,
+    pub threshold: f64,
+    /// Raised (`true`) or cleared.
+    pub raised: bool,
+}
+
+impl AlarmEvent {
+    pub fn render(&self) -> String {
+        format!(
+            "{:.3}s {} {} {} on {}: {} = {:.2} (threshold {})",
+            self.at_secs,
+            self.severity.as_str(),
+            if self.raised { "raised" } else { "cleared" },
+            self.metric.as_str(),
+            self.link,
+            self.metric.as_str(),
+            self.value,
+            self.threshold
+        )
+    }
+}
+
+/// Evaluates the rules window by window and keeps the alarm log.
+#[derive(Debug)]
+pub struct AlarmMonitor {
+    rules: Vec<Rule>,
+    window: Duration,
+    started: Instant,
+    window_start: Instant,
+    /// `(offered, lost, bytes)` of each link at the start of the window.
+    baseline: HashMap<LinkId, (u64, u64, u64)>,
+    /// Rule index and link of each alarm currently raised.
+    active: HashSet<(usize, LinkId)>,
+    pub events: Vec<AlarmEvent>,
+}
+
+impl AlarmMonitor {
+    pub fn new(cfg: &AlarmsConfig) -> Result<Self, String> {
+        if !cfg.window_secs.is_finite() || cfg.window_secs <= 0.0 {
+            return Err(format!(
+                "alarms.window_secs must be a positive number, got {}",
+                cfg.window_secs
+            ));
+        }
+        let rules = cfg
+            .rules
+            .iter()
+            .map(Rule::from_config)
+            .collect::<Result<Vec<_>, _>>()?;
+        let now = Instant::now();
+        Ok(Self {
+            rules,
+            window: Duration::from_secs_f64(cfg.window_secs),
+            started: now,
+            window_start: now,
+            baseline: HashMap::new(),
+            active: HashSet::new(),
+            events: Vec::new(),
+        })
+    }
+
+    /// End of the current window.
+    pub fn next_evaluation(&self) -> Instant {
+        self.window_start + self.window
+    }
+
+    /// Close the window ending at `now`: raise and clear alarms from the link `samples`.
+    pub fn evaluate(&mut self, samples: &[LinkSample], now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.window_start)
+            .as_secs_f64();
+        for sample in samples {
+            let (offered, lost, bytes) = self.baseline.get(&sample.id).copied().unwrap_or_default();
+            let offered = sample.offered.saturating_sub(offered);
+            let lost = sample.lost.saturating_sub(lost);
+            let bytes = sample.bytes.saturating_sub(bytes);
+            for (idx, rule) in self.rules.iter().enumerate() {
+                if rule.link.as_ref().is_some_and(|l| *l != sample.id) {
+                    continue;
+                }
+                let value = match rule.metric {
+                    Metric::LossPercent if offered > 0 => lost as f64 * 100.0 / offered as f64,
+                    Metric::LossPercent => 0.0,
+                    Metric::UtilizationPercent => match sample.bandwidth_mbps {
+                        Some(mbps) if elapsed > 0.0 => {
+                            bytes as f64 * 8.0 * 100.0 / (mbps * 1e6 * elapsed)
+                        }
+                        Some(_) => 0.0,
+                        None => continue,
+                    },
+                    Metric::QueueDepth => sample.queue_depth as f64,
+                };
+                let key = (idx, sample.id.clone());
+                let above = value > rule.threshold;
+                if above == self.active.contains(&key) {
+                    continue;
+                }
+                if above {
+                    self.active.insert(key);
+                } else {
+                    self.active.remove(&key);
+                }
+                let event = AlarmEvent {
+                    at_secs: now.saturating_duration_since(self.started).as_secs_f64(),
+                    link: format!("{}_{}", sample.id.a.0, sample.id.b.0),
+                    metric: rule.metric,
+                    severity: rule.severity,
+                    value,
+                    threshold: rule.threshold,
+                    raised: above,
+                };
+                match (above, rule.severity) {
+                    (false, _) => info!("Alarm {}", event.render()),
+                    (true, Severity::Major | Severity::Critical) => {
+                        error!("Alarm {}", event.render())
+                    }
+                    (true, _) => warn!("Alarm {}", event.render()),
+                }
+                self.events.push(event);
+            }
+            self.baseline.insert(
+                sample.id.clone(),
+                (sample.offered, sample.lost, sample.bytes),
+            );
+        }
+        self.window_start = now;
+    }
+
+    /// Alarms raised so far with `severity`.
+    pub fn raised(&self, severity: Severity) -> usize {
+        self.events
+            .iter()
+            .filter(|e| e.raised && e.severity == severity)
+            .count()
+    }
+
+    /// Alarms currently raised.
+    pub fn active(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Forget the alarm log (end of the warm‑up phase); raised alarms stay raised.
+    pub fn reset(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn summary(&self) -> String {
+        let raised: Vec<String> = Severity::ALL
+            .iter()
+            .rev()
+            .map(|s| format!("{}={}", s.as_str(), self.raised(*s)))
+            .collect();
+        format!("raised {}, active={}", raised.join(", "), self.active())
+    }
+}
+
+/// Evaluate the alarm rules if the current window is over.
+pub fn tick(fabric: &mut Fabric) {
+    let now = Instant::now();
+    if fabric
+        .alarms
+        .as_ref()
+        .is_some_and(|a| now >= a.next_evaluation())
+    {
+        evaluate(fabric, now);
+    }
+}
+
+/// Evaluate the last, possibly shorter, window at the end of the run.
+pub fn finish(fabric: &mut Fabric) {
+    if fabric.alarms.is_some() {
+        evaluate(fabric, Instant::now());
+    }
+}
+
+fn evaluate(fabric: &mut Fabric, now: Instant) {
+    let samples = sample(fabric);
+    if let Some(alarms) = fabric.alarms.as_mut() {
+        alarms.evaluate(&samples, now);
+    }
+}
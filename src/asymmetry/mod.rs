@@ -0,0 +1,240 @@
+// src/asymmetry/mod.rs
+
+//! Asymmetric route verification.
+//!
+//! Stateful middleboxes (firewalls, NAT, load balancers) have to see both directions of a flow,
+//! so they can only be placed on routers that every forward path and its reverse path share.
+//! For every pair of an edge prefix behind tun_a and one behind tun_b of the same family, this
+//! walks the computed path from the tun_a ingress router towards tun_b and the path back,
+//! following policy‑based routing for a representative host of each prefix and the routing
+//! tables otherwise. A pair is flagged when the two paths cross different routers or add up
+//! to different costs.
+
+use crate::config::SimulatorConfig;
+use crate::packet::PacketMeta;
+use crate::pbr::PbrAction;
+use crate::routing::{cost_across, Destination, RoutingTable};
+use crate::topology::{Fabric, RouterId};
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::net::IpAddr;
+
+/// Hops walked before a path is declared looping, as in the processor.
+const MAX_HOPS: usize = 100;
+
+/// One direction of a prefix pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgePath {
+    /// Routers in the order the packet crosses them.
+    pub routers: Vec<RouterId>,
+    /// Sum of the routing costs of the links crossed.
+    pub cost: u32,
+    /// Whether the walk reached the egress router.
+    pub reached: bool,
+}
+
+/// Forward (tun_a → tun_b) and reverse path between two edge prefixes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPair {
+    pub a_prefix: IpNet,
+    pub b_prefix: IpNet,
+    pub forward: EdgePath,
+    pub reverse: EdgePath,
+}
+
+impl PathPair {
+    /// Why the pair is asymmetric, or `None` if both directions cross the same routers at the
+    /// same cost.
+    pub fn asymmetry(&self) -> Option<String> {
+        if !self.forward.reached || !self.reverse.reached {
+            return Some("a direction does not reach its edge".to_string());
+        }
+        let back: Vec<&RouterId> = self.reverse.routers.iter().rev().collect();
+        let routers_differ = self.forward.routers.iter().collect::<Vec<_>>() != back;
+        let costs_differ = self.forward.cost != self.reverse.cost;
+        match (routers_differ, costs_differ) {
+            (false, false) => None,
+            (true, false) => Some("different routers".to_string()),
+            (false, true) => Some(format!(
+                "different costs ({} vs {})",
+                self.forward.cost, self.reverse.cost
+            )),
+            (true, true) => Some(format!(
+                "different routers and costs ({} vs {})",
+                self.forward.cost, self.reverse.cost
+            )),
+        }
+    }
+}
+
+/// Representative host of `net`: its first address, or the address itself for host routes.
+fn host(net: &IpNet) -> IpAddr {
+    net.hosts().next().unwrap_or(net.addr())
+}
+
+/// Edge prefixes behind tun_a and tun_b: the configured edge prefixes that are valid CIDR
+/// prefixes (the catch‑all `::/0` default excluded) and the `[[destination_map]]` prefixes,
+/// on the side of their egress edge.
+pub fn edge_prefixes(cfg: &SimulatorConfig) -> (Vec<IpNet>, Vec<IpNet>) {
+    let parse = |s: &str| s.parse::<IpNet>().ok().filter(|n| n.prefix_len() > 0);
+    let t = &cfg.tun_ingress;
+    let mut a: Vec<IpNet> = [&t.tun_a_prefix, &t.tun_a_ipv6_prefix]
+        .into_iter()
+        .filter_map(|s| parse(s))
+        .collect();
+    let mut b: Vec<IpNet> = [&t.tun_b_prefix, &t.tun_b_ipv6_prefix]
+        .into_iter()
+        .filter_map(|s| parse(s))
+        .collect();
+    for entry in &cfg.destination_map {
+        let side = match entry.egress.as_str() {
+            "tun_a" => &mut a,
+            _ => &mut b,
+        };
+        if let Some(net) = parse(&entry.prefix) {
+            if !side.contains(&net) {
+                side.push(net);
+            }
+        }
+    }
+    (a, b)
+}
+
+/// Routing cost of the link between `a` and `b`, if there is one.
+fn cost_between(fabric: &Fabric, a: &RouterId, b: &RouterId) -> Option<u32> {
+    let (ia, ib) = (*fabric.router_index.get(a)?, *fabric.router_index.get(b)?);
+    let edge = fabric.graph.edges_connecting(ia, ib).next()?;
+    Some(cost_across(fabric, edge.weight(), ia, ib))
+}
+
+/// Walk the path `packet` takes from `start` towards `destination`.
+fn walk(
+    fabric: &mut Fabric,
+    tables: &HashMap<RouterId, RoutingTable>,
+    start: &RouterId,
+    mut destination: Destination,
+    packet: &PacketMeta,
+) -> EdgePath {
+    let mut path = EdgePath {
+        routers: vec![start.clone()],
+        cost: 0,
+        reached: false,
+    };
+    let mut at = start.clone();
+    for _ in 0..MAX_HOPS {
+        let policy_hop = match fabric
+            .get_router_mut(&at)
+            .and_then(|r| r.policy_route(packet))
+        {
+            Some(PbrAction::Destination(d)) => {
+                destination = d;
+                None
+            }
+            Some(PbrAction::NextHop(hop)) if fabric.get_link(&at, &hop).is_some() => Some(hop),
+            _ => None,
+        };
+        let next = match policy_hop {
+            Some(hop) => hop,
+            None => {
+                let Some(table) = tables.get(&at) else {
+                    break;
+                };
+                let next = match destination {
+                    Destination::TunA => &table.tun_a.next_hop,
+                    Destination::TunB => &table.tun_b.next_hop,
+                };
+                if *next == at {
+                    path.reached = true;
+                    break;
+                }
+                next.clone()
+            }
+        };
+        let Some(cost) = cost_between(fabric, &at, &next) else {
+            break;
+        };
+        path.cost = path.cost.saturating_add(cost);
+        path.routers.push(next.clone());
+        at = next;
+    }
+    path
+}
+
+/// Compare the forward and reverse path of every pair of edge prefixes of `cfg`.
+pub fn analyze(cfg: &SimulatorConfig) -> Result<Vec<PathPair>, String> {
+    let (a_prefixes, b_prefixes) = edge_prefixes(cfg);
+    if a_prefixes.is_empty() || b_prefixes.is_empty() {
+        return Err(
+            "asymmetry analysis needs CIDR edge prefixes behind both tun_a and tun_b".to_string(),
+        );
+    }
+    let mut fabric = crate::build_fabric(cfg);
+    let tables = crate::compute_routing_tables(cfg);
+    let ingress_a = RouterId(cfg.tun_ingress.tun_a_ingress.clone());
+    let ingress_b = RouterId(cfg.tun_ingress.tun_b_ingress.clone());
+    let map = crate::routing::destination_map::DestinationMap::new(&cfg.destination_map)?;
+    let mut pairs = Vec::new();
+    for a in &a_prefixes {
+        for b in b_prefixes
+            .iter()
+            .filter(|b| b.addr().is_ipv4() == a.addr().is_ipv4())
+        {
+            let probe = |src: &IpNet, dst: &IpNet| PacketMeta {
+                src_ip: host(src),
+                dst_ip: host(dst),
+                src_port: 0,
+                dst_port: 0,
+                protocol: 17,
+                ttl: 64,
+                dscp: 0,
+                ecn: 0,
+                transport: None,
+                annotation: None,
+                mpls: Vec::new(),
+                vlan: None,
+                raw: bytes::Bytes::new(),
+            };
+            let to_b = probe(a, b);
+            let to_a = probe(b, a);
+            let forward_edge = map.egress(Destination::TunA, &to_b.dst_ip);
+            let reverse_edge = map.egress(Destination::TunB, &to_a.dst_ip);
+            pairs.push(PathPair {
+                a_prefix: *a,
+                b_prefix: *b,
+                forward: walk(&mut fabric, &tables, &ingress_a, forward_edge, &to_b),
+                reverse: walk(&mut fabric, &tables, &ingress_b, reverse_edge, &to_a),
+            });
+        }
+    }
+    Ok(pairs)
+}
+
+/// Human‑readable report of [`analyze`].
+pub fn render(pairs: &[PathPair]) -> String {
+    let names = |p: &EdgePath| {
+        p.routers
+            .iter()
+            .map(|r| r.0.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut out = String::new();
+    for pair in pairs {
+        let verdict = match pair.asymmetry() {
+            Some(reason) => format!("ASYMMETRIC: {}", reason),
+            None => format!("symmetric, cost {}", pair.forward.cost),
+        };
+        let _ = writeln!(out, "{} <-> {}: {}", pair.a_prefix, pair.b_prefix, verdict);
+        let _ = writeln!(out, "  forward: {}", names(&pair.forward));
+        let _ = writeln!(out, "  reverse: {}", names(&pair.reverse));
+    }
+    let asymmetric = pairs.iter().filter(|p| p.asymmetry().is_some()).count();
+    let _ = writeln!(
+        out,
+        "{} of {} prefix pairs asymmetric",
+        asymmetric,
+        pairs.len()
+    );
+    out
+}
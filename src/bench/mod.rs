@@ -0,0 +1,446 @@
+// src/bench/mod.rs
+
+//! iperf3‑style throughput test (`network-simulator bench`).
+//!
+//! A sink (TCP listener and UDP socket) and `parallel` sender streams run inside the simulator
+//! process. The sink sits in the network namespace of edge B and the senders in that of edge A
+//! (see [`crate::netns`]), so the traffic crosses the simulated fabric instead of the host's
+//! loopback, and the report shows per‑interval rates, TCP retransmits and UDP loss in the
+//! familiar iperf layout.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, sleep, Duration, Instant, MissedTickBehavior};
+use tracing::{debug, info};
+
+/// Default buffer length for TCP writes (iperf3 uses 128 KiB).
+pub const DEFAULT_TCP_LEN: usize = 128 * 1024;
+/// Default UDP datagram payload length.
+pub const DEFAULT_UDP_LEN: usize = 1400;
+
+/// Parameters of one benchmark run.
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub duration_secs: u64,
+    pub parallel: u32,
+    pub udp: bool,
+    /// Target rate per UDP stream in Mbit/s (ignored for TCP).
+    pub bitrate_mbps: f64,
+    pub interval_secs: f64,
+    /// Write / datagram length; protocol default when `None`.
+    pub length: Option<usize>,
+    /// Address the sink listens on.
+    pub listen: SocketAddr,
+    /// Address the sender streams connect to.
+    pub target: SocketAddr,
+    /// Source address of the sender streams.
+    pub bind: Option<IpAddr>,
+    /// Namespace the sink runs in (host sockets when `None`).
+    pub sink_netns: Option<String>,
+    /// Namespace the sender streams run in (host sockets when `None`).
+    pub sender_netns: Option<String>,
+}
+
+impl BenchOptions {
+    fn length(&self) -> usize {
+        self.length.unwrap_or(if self.udp {
+            DEFAULT_UDP_LEN
+        } else {
+            DEFAULT_TCP_LEN
+        })
+    }
+}
+
+/// Bytes sent per stream (and TCP retransmits) during one reporting interval.
+#[derive(Debug, Clone, Default)]
+pub struct BenchInterval {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub stream_bytes: Vec<u64>,
+    pub retransmits: u64,
+}
+
+impl BenchInterval {
+    pub fn bytes(&self) -> u64 {
+        self.stream_bytes.iter().sum()
+    }
+}
+
+/// Result of a benchmark run.
+#[derive(Debug, Clone, Default)]
+pub struct BenchReport {
+    pub udp: bool,
+    pub intervals: Vec<BenchInterval>,
+    pub duration_secs: f64,
+    pub sent_bytes: u64,
+    pub received_bytes: u64,
+    pub retransmits: u64,
+    pub datagrams_sent: u64,
+    pub datagrams_received: u64,
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let b = bytes as f64;
+    if b >= 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.2} GBytes", b / (1024.0 * 1024.0 * 1024.0))
+    } else if b >= 1024.0 * 1024.0 {
+        format!("{:.2} MBytes", b / (1024.0 * 1024.0))
+    } else {
+        format!("{:.2} KBytes", b / 1024.0)
+    }
+}
+
+fn format_rate(bytes: u64, secs: f64) -> String {
+    let bits = if secs > 0.0 {
+        bytes as f64 * 8.0 / secs
+    } else {
+        0.0
+    };
+    if bits >= 1e9 {
+        format!("{:.2} Gbits/sec", bits / 1e9)
+    } else if bits >= 1e6 {
+        format!("{:.2} Mbits/sec", bits / 1e6)
+    } else {
+        format!("{:.2} Kbits/sec", bits / 1e3)
+    }
+}
+
+impl BenchReport {
+    /// UDP loss in percent (0 for TCP).
+    pub fn loss_percent(&self) -> f64 {
+        if self.datagrams_sent == 0 {
+            return 0.0;
+        }
+        let lost = self.datagrams_sent.saturating_sub(self.datagrams_received);
+        lost as f64 * 100.0 / self.datagrams_sent as f64
+    }
+
+    /// Render the report in iperf3's text layout.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("[ ID] Interval           Transfer     Bitrate         Retr\n");
+        for iv in &self.intervals {
+            let secs = iv.end_secs - iv.start_secs;
+            let label = format!("{:6.2}-{:<6.2} sec", iv.start_secs, iv.end_secs);
+            if iv.stream_bytes.len() > 1 {
+                for (id, bytes) in iv.stream_bytes.iter().enumerate() {
+                    out.push_str(&format!(
+                        "[{:>3}] {}  {:>12}  {:>15}\n",
+                        id + 1,
+                        label,
+                        format_bytes(*bytes),
+                        format_rate(*bytes, secs)
+                    ));
+                }
+                out.push_str(&format!(
+                    "[SUM] {}  {:>12}  {:>15}  {:>4}\n",
+                    label,
+                    format_bytes(iv.bytes()),
+                    format_rate(iv.bytes(), secs),
+                    iv.retransmits
+                ));
+            } else {
+                out.push_str(&format!(
+                    "[  1] {}  {:>12}  {:>15}  {:>4}\n",
+                    label,
+                    format_bytes(iv.bytes()),
+                    format_rate(iv.bytes(), secs),
+                    iv.retransmits
+                ));
+            }
+        }
+        out.push_str("- - - - - - - - - - - - - - - - - - - - - - - - -\n");
+        let label = format!("{:6.2}-{:<6.2} sec", 0.0, self.duration_secs);
+        out.push_str(&format!(
+            "[SUM] {}  {:>12}  {:>15}  {:>4}  sender\n",
+            label,
+            format_bytes(self.sent_bytes),
+            format_rate(self.sent_bytes, self.duration_secs),
+            self.retransmits
+        ));
+        if self.udp {
+            out.push_str(&format!(
+                "[SUM] {}  {:>12}  {:>15}  {}/{} ({:.2}%)  receiver\n",
+                label,
+                format_bytes(self.received_bytes),
+                format_rate(self.received_bytes, self.duration_secs),
+                self.datagrams_sent.saturating_sub(self.datagrams_received),
+                self.datagrams_sent,
+                self.loss_percent()
+            ));
+        } else {
+            out.push_str(&format!(
+                "[SUM] {}  {:>12}  {:>15}        receiver\n",
+                label,
+                format_bytes(self.received_bytes),
+                format_rate(self.received_bytes, self.duration_secs)
+            ));
+        }
+        out
+    }
+}
+
+/// Total retransmitted segments of a TCP connection (Linux `TCP_INFO`).
+#[cfg(target_os = "linux")]
+fn tcp_retransmits(stream: &TcpStream) -> u64 {
+    use std::os::fd::AsRawFd;
+    let mut info: libc::tcp_info = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+    // SAFETY: `info` is a properly sized, writable `tcp_info` and `len` holds its size.
+    let rc = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+            &mut info as *mut _ as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if rc == 0 {
+        info.tcpi_total_retrans as u64
+    } else {
+        0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn tcp_retransmits(_stream: &TcpStream) -> u64 {
+    0
+}
+
+/// Counters shared between the sink, the senders and the interval reporter.
+#[derive(Default)]
+struct Counters {
+    stream_bytes: Vec<AtomicU64>,
+    stream_retrans: Vec<AtomicU64>,
+    datagrams_sent: AtomicU64,
+    received_bytes: AtomicU64,
+    datagrams_received: AtomicU64,
+}
+
+async fn start_sink(
+    opts: &BenchOptions,
+    counters: Arc<Counters>,
+) -> Result<JoinHandle<()>, String> {
+    let handle = if opts.udp {
+        let socket = crate::netns::udp_socket(opts.sink_netns.as_deref(), opts.listen)
+            .map_err(|e| format!("Failed to bind bench UDP sink to {}: {}", opts.listen, e))?;
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 65536];
+            while let Ok(n) = socket.recv(&mut buf).await {
+                counters
+                    .received_bytes
+                    .fetch_add(n as u64, Ordering::Relaxed);
+                counters.datagrams_received.fetch_add(1, Ordering::Relaxed);
+            }
+        })
+    } else {
+        let listener = crate::netns::tcp_listener(opts.sink_netns.as_deref(), opts.listen)
+            .map_err(|e| format!("Failed to bind bench TCP sink to {}: {}", opts.listen, e))?;
+        tokio::spawn(async move {
+            while let Ok((mut stream, peer)) = listener.accept().await {
+                debug!("Bench sink accepted {}", peer);
+                let counters = counters.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 65536];
+                    while let Ok(n) = stream.read(&mut buf).await {
+                        if n == 0 {
+                            break;
+                        }
+                        counters
+                            .received_bytes
+                            .fetch_add(n as u64, Ordering::Relaxed);
+                    }
+                });
+            }
+        })
+    };
+    Ok(handle)
+}
+
+async fn tcp_stream(
+    opts: &BenchOptions,
+    id: usize,
+    counters: Arc<Counters>,
+    stop: Arc<AtomicBool>,
+) -> Result<(), String> {
+    let socket = crate::netns::tcp_socket(opts.sender_netns.as_deref(), &opts.target)
+        .map_err(|e| e.to_string())?;
+    if let Some(ip) = opts.bind {
+        socket
+            .bind(SocketAddr::new(ip, 0))
+            .map_err(|e| format!("Failed to bind bench stream to {}: {}", ip, e))?;
+    }
+    let mut stream = socket
+        .connect(opts.target)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", opts.target, e))?;
+    let buf = vec![0u8; opts.length()];
+    let mut last_check = Instant::now();
+    while !stop.load(Ordering::Relaxed) {
+        if stream.write_all(&buf).await.is_err() {
+            break;
+        }
+        counters.stream_bytes[id].fetch_add(buf.len() as u64, Ordering::Relaxed);
+        if last_check.elapsed() >= Duration::from_millis(100) {
+            counters.stream_retrans[id].store(tcp_retransmits(&stream), Ordering::Relaxed);
+            last_check = Instant::now();
+        }
+    }
+    counters.stream_retrans[id].store(tcp_retransmits(&stream), Ordering::Relaxed);
+    let _ = stream.shutdown().await;
+    Ok(())
+}
+
+async fn udp_stream(
+    opts: &BenchOptions,
+    id: usize,
+    counters: Arc<Counters>,
+    stop: Arc<AtomicBool>,
+) -> Result<(), String> {
+    let local = SocketAddr::new(
+        opts.bind.unwrap_or(match opts.target {
+            SocketAddr::V4(_) => IpAddr::from([0, 0, 0, 0]),
+            SocketAddr::V6(_) => IpAddr::from([0u16; 8]),
+        }),
+        0,
+    );
+    let socket = crate::netns::udp_socket(opts.sender_netns.as_deref(), local)
+        .map_err(|e| format!("Failed to bind bench stream to {}: {}", local, e))?;
+    socket
+        .connect(opts.target)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", opts.target, e))?;
+    let mut buf = vec![0u8; opts.length().max(8)];
+    // Pace datagrams to the requested per‑stream bitrate.
+    let per_sec = (opts.bitrate_mbps * 1e6 / 8.0 / buf.len() as f64).max(1.0);
+    let gap = Duration::from_secs_f64(1.0 / per_sec);
+    let start = Instant::now();
+    let mut seq: u64 = 0;
+    while !stop.load(Ordering::Relaxed) {
+        buf[..8].copy_from_slice(&seq.to_be_bytes());
+        if socket.send(&buf).await.is_ok() {
+            counters.stream_bytes[id].fetch_add(buf.len() as u64, Ordering::Relaxed);
+            counters.datagrams_sent.fetch_add(1, Ordering::Relaxed);
+        }
+        seq += 1;
+        let due = start + gap.mul_f64(seq as f64);
+        let now = Instant::now();
+        if due > now {
+            sleep(due - now).await;
+        }
+    }
+    Ok(())
+}
+
+/// Run the benchmark: start the sink, drive `parallel` sender streams for `duration_secs`
+/// and collect per‑interval statistics.
+pub async fn run(opts: &BenchOptions) -> Result<BenchReport, String> {
+    // The simulator configures the namespaces as it opens the edges; wait for it.
+    if let Some(ref ns) = opts.sink_netns {
+        crate::netns::wait_for_address(ns, opts.target.ip(), Duration::from_secs(10)).await?;
+    }
+    if let (Some(ns), Some(bind)) = (&opts.sender_netns, opts.bind) {
+        crate::netns::wait_for_address(ns, bind, Duration::from_secs(10)).await?;
+    }
+    let streams = opts.parallel.max(1) as usize;
+    let counters = Arc::new(Counters {
+        stream_bytes: (0..streams).map(|_| AtomicU64::new(0)).collect(),
+        stream_retrans: (0..streams).map(|_| AtomicU64::new(0)).collect(),
+        ..Default::default()
+    });
+    let sink = start_sink(opts, counters.clone()).await?;
+    info!(
+        "Bench: {} {} stream(s) to {} for {} s",
+        streams,
+        if opts.udp { "UDP" } else { "TCP" },
+        opts.target,
+        opts.duration_secs
+    );
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut tasks = Vec::new();
+    for id in 0..streams {
+        let (opts, counters, stop) = (opts.clone(), counters.clone(), stop.clone());
+        tasks.push(tokio::spawn(async move {
+            if opts.udp {
+                udp_stream(&opts, id, counters, stop).await
+            } else {
+                tcp_stream(&opts, id, counters, stop).await
+            }
+        }));
+    }
+
+    let start = Instant::now();
+    let total = Duration::from_secs(opts.duration_secs);
+    let mut ticker = interval(Duration::from_secs_f64(opts.interval_secs.max(0.1)));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ticker.tick().await;
+    let mut report = BenchReport {
+        udp: opts.udp,
+        ..Default::default()
+    };
+    let snapshot = |c: &Counters| -> (Vec<u64>, u64) {
+        (
+            c.stream_bytes
+                .iter()
+                .map(|b| b.load(Ordering::Relaxed))
+                .collect(),
+            c.stream_retrans
+                .iter()
+                .map(|r| r.load(Ordering::Relaxed))
+                .sum(),
+        )
+    };
+    let (mut prev_bytes, mut prev_retrans) = snapshot(&counters);
+    let mut prev_time = 0.0;
+    while start.elapsed() < total {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = sleep(total.saturating_sub(start.elapsed())) => {}
+        }
+        if tasks.iter().all(|t| t.is_finished()) {
+            break;
+        }
+        let now = start.elapsed().as_secs_f64().min(total.as_secs_f64());
+        let (bytes, retrans) = snapshot(&counters);
+        report.intervals.push(BenchInterval {
+            start_secs: prev_time,
+            end_secs: now,
+            stream_bytes: bytes.iter().zip(&prev_bytes).map(|(b, p)| b - p).collect(),
+            retransmits: retrans.saturating_sub(prev_retrans),
+        });
+        (prev_bytes, prev_retrans, prev_time) = (bytes, retrans, now);
+    }
+    stop.store(true, Ordering::Relaxed);
+    let mut first_error = None;
+    for task in tasks {
+        match task.await {
+            Ok(Err(e)) => {
+                first_error.get_or_insert(e);
+            }
+            Err(e) => {
+                first_error.get_or_insert(e.to_string());
+            }
+            Ok(Ok(())) => {}
+        }
+    }
+    if let Some(e) = first_error {
+        sink.abort();
+        return Err(e);
+    }
+    report.duration_secs = start.elapsed().as_secs_f64().min(total.as_secs_f64());
+    // Give in‑flight data a moment to reach the sink before reading receiver counters.
+    sleep(Duration::from_millis(200)).await;
+    let (bytes, retrans) = snapshot(&counters);
+    report.sent_bytes = bytes.iter().sum();
+    report.retransmits = retrans;
+    report.received_bytes = counters.received_bytes.load(Ordering::Relaxed);
+    report.datagrams_sent = counters.datagrams_sent.load(Ordering::Relaxed);
+    report.datagrams_received = counters.datagrams_received.load(Ordering::Relaxed);
+    sink.abort();
+    Ok(report)
+}
@@ -0,0 +1,161 @@
+// src/capture/mirror.rs
+
+//! SPAN‑style mirroring of the packets arriving at a router.
+//!
+//! A `[[mirror]]` port copies every packet that arrives at its router and matches its filter
+//! (the capture filter syntax) to a monitor sink: a file of hex lines, like a capture point, or
+//! an extra TUN interface an IDS or analyzer can listen on. Packets are mirrored as they
+//! arrive, before the router's checks, so the copies include what the router then drops. With
+//! `truncate` only the first bytes of each packet are copied.
+
+use super::CaptureFilter;
+use crate::config::MirrorConfig;
+use crate::packet::PacketMeta;
+use crate::topology::RouterId;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+/// Copies waiting for a TUN monitor interface before new ones are dropped.
+pub const TUN_BACKLOG: usize = 1024;
+
+/// One mirror port and its sink.
+#[derive(Debug)]
+pub struct MirrorPort {
+    pub router: RouterId,
+    pub filter: CaptureFilter,
+    /// Bytes kept of each copy; `None` keeps whole packets.
+    pub truncate: Option<usize>,
+    /// File the copies are written to, if the sink is a file.
+    pub file: Option<String>,
+    /// Name of the monitor TUN interface, if the sink is one.
+    pub tun: Option<String>,
+    /// Copies handed to the sink.
+    pub mirrored: u64,
+    /// Packets with raw bytes that arrived at the router, matching or not.
+    pub seen: u64,
+    /// Copies lost because the monitor interface fell behind or is missing.
+    pub dropped: u64,
+    writer: Option<BufWriter<File>>,
+    tx: Option<mpsc::Sender<Vec<u8>>>,
+}
+
+impl MirrorPort {
+    pub fn from_config(cfg: &MirrorConfig) -> Result<Self, String> {
+        if cfg.file.is_some() == cfg.tun.is_some() {
+            return Err(format!(
+                "Mirror on {}: set exactly one of file and tun",
+                cfg.router
+            ));
+        }
+        if cfg.truncate == Some(0) {
+            return Err(format!(
+                "Mirror on {}: truncate must be at least 1",
+                cfg.router
+            ));
+        }
+        let filter = CaptureFilter::parse(&cfg.filter)
+            .map_err(|e| format!("Mirror on {}: {}", cfg.router, e))?;
+        Ok(Self {
+            router: RouterId(cfg.router.clone()),
+            filter,
+            truncate: cfg.truncate,
+            file: cfg.file.clone(),
+            tun: cfg.tun.clone(),
+            mirrored: 0,
+            seen: 0,
+            dropped: 0,
+            writer: None,
+            tx: None,
+        })
+    }
+
+    /// (Re)create the mirror file, if the sink is one, and reset the counters.
+    pub fn open(&mut self) -> Result<(), String> {
+        if let Some(path) = &self.file {
+            let file = File::create(path)
+                .map_err(|e| format!("Failed to create mirror file {}: {}", path, e))?;
+            let mut writer = BufWriter::new(file);
+            writeln!(
+                writer,
+                "# mirror of {} filter \"{}\"",
+                self.router.0,
+                self.filter.source()
+            )
+            .map_err(|e| format!("Failed to write mirror file {}: {}", path, e))?;
+            self.writer = Some(writer);
+        }
+        self.mirrored = 0;
+        self.seen = 0;
+        self.dropped = 0;
+        Ok(())
+    }
+
+    /// Send the copies to a monitor interface through `tx` from now on.
+    pub fn attach(&mut self, tx: mpsc::Sender<Vec<u8>>) {
+        self.tx = Some(tx);
+    }
+
+    /// Offer a packet that arrived at `router`.
+    pub fn offer(&mut self, router: &RouterId, packet: &PacketMeta) {
+        if *router != self.router || packet.raw.is_empty() {
+            return;
+        }
+        self.seen += 1;
+        if !self.filter.matches(packet) {
+            return;
+        }
+        let len = self
+            .truncate
+            .map_or(packet.raw.len(), |n| n.min(packet.raw.len()));
+        let copy = &packet.raw[..len];
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(e) = writeln!(writer, "{}", hex::encode(copy)) {
+                error!("Failed to write mirror file {:?}: {}", self.file, e);
+                self.writer = None;
+                return;
+            }
+        } else if self.tun.is_some() {
+            match &self.tx {
+                Some(tx) if tx.try_send(copy.to_vec()).is_ok() => {}
+                _ => {
+                    self.dropped += 1;
+                    return;
+                }
+            }
+        } else {
+            return;
+        }
+        self.mirrored += 1;
+    }
+
+    /// Write the closing summary line and flush the file.
+    pub fn finish(&mut self) {
+        let Some(mut writer) = self.writer.take() else {
+            return;
+        };
+        let result = writeln!(writer, "# {} of {} packets", self.mirrored, self.seen)
+            .and_then(|_| writer.flush());
+        match result {
+            Ok(()) => info!(
+                "Wrote {} mirrored packets to {}",
+                self.mirrored,
+                self.file.as_deref().unwrap_or_default()
+            ),
+            Err(e) => error!("Failed to write mirror file {:?}: {}", self.file, e),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let sink = match (&self.file, &self.tun) {
+            (Some(file), _) => format!("file {}", file),
+            (_, Some(tun)) => format!("tun {}", tun),
+            _ => String::new(),
+        };
+        format!(
+            "{} -> {}: mirrored={}, seen={}, dropped={}",
+            self.router.0, sink, self.mirrored, self.seen, self.dropped
+        )
+    }
+}
@@ -0,0 +1,323 @@
+// src/capture/mod.rs
+
+//! Per‑link packet capture with BPF‑like filter expressions.
+//!
+//! A capture point is attached to one link and records every packet that is successfully
+//! carried over it and matches the point's filter. Captured packets are streamed to the file as
+//! hex lines (the `packet_file` format, so a capture can be replayed) while the run goes on,
+//! so a long capture does not accumulate in memory.
+//!
+//! Filter syntax, a subset of tcpdump's:
+//! `ip`, `ip6`, `tcp`, `udp`, `icmp`, `icmp6`, `proto N`, `[src|dst] port N`,
+//! `[src|dst] host ADDR`, `[src|dst] net PREFIX`, combined with `and`/`&&`, `or`/`||`,
+//! `not`/`!` and parentheses. An empty filter matches every packet.
+
+pub mod mirror;
+
+use crate::config::CaptureConfig;
+use crate::packet::PacketMeta;
+use crate::topology::{LinkId, RouterId};
+use ipnet::IpNet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::IpAddr;
+use tracing::{error, info};
+
+/// Which address or port of the packet a primitive looks at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dir {
+    Src,
+    Dst,
+    Either,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    V4,
+    V6,
+    Proto(u8),
+    Port(Dir, u16),
+    Net(Dir, IpNet),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn matches(&self, p: &PacketMeta) -> bool {
+        let by_dir = |dir: Dir, f: &dyn Fn(bool) -> bool| match dir {
+            Dir::Src => f(true),
+            Dir::Dst => f(false),
+            Dir::Either => f(true) || f(false),
+        };
+        match self {
+            Expr::V4 => p.src_ip.is_ipv4(),
+            Expr::V6 => p.src_ip.is_ipv6(),
+            Expr::Proto(n) => p.protocol == *n,
+            Expr::Port(dir, port) => {
+                matches!(p.protocol, 6 | 17)
+                    && by_dir(*dir, &|src| {
+                        (if src { p.src_port } else { p.dst_port }) == *port
+                    })
+            }
+            Expr::Net(dir, net) => by_dir(*dir, &|src| {
+                net.contains(if src { &p.src_ip } else { &p.dst_ip })
+            }),
+            Expr::Not(e) => !e.matches(p),
+            Expr::And(a, b) => a.matches(p) && b.matches(p),
+            Expr::Or(a, b) => a.matches(p) || b.matches(p),
+        }
+    }
+}
+
+/// A compiled filter expression.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CaptureFilter {
+    source: String,
+    expr: Option<Expr>,
+}
+
+impl CaptureFilter {
+    /// Compile `source`; an empty (or whitespace‑only) expression matches everything.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source);
+        let expr = if tokens.is_empty() {
+            None
+        } else {
+            let mut parser = Parser { tokens, pos: 0 };
+            let expr = parser.or_expr()?;
+            if let Some(tok) = parser.peek() {
+                return Err(format!(
+                    "Unexpected '{}' in capture filter '{}'",
+                    tok, source
+                ));
+            }
+            Some(expr)
+        };
+        Ok(Self {
+            source: source.trim().to_string(),
+            expr,
+        })
+    }
+
+    pub fn matches(&self, packet: &PacketMeta) -> bool {
+        self.expr.as_ref().is_none_or(|e| e.matches(packet))
+    }
+
+    /// The expression as written in the configuration.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+fn tokenize(source: &str) -> Vec<String> {
+    source
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace('!', " ! ")
+        .split_whitespace()
+        .map(|t| t.to_ascii_lowercase())
+        .collect()
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let tok = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or("Capture filter ends unexpectedly")?;
+        self.pos += 1;
+        Ok(tok)
+    }
+
+    fn or_expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.and_expr()?;
+        while matches!(self.peek(), Some("or" | "||")) {
+            self.pos += 1;
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and_expr()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        while matches!(self.peek(), Some("and" | "&&")) {
+            self.pos += 1;
+            lhs = Expr::And(Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some("not" | "!") => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some("(") => {
+                self.pos += 1;
+                let inner = self.or_expr()?;
+                match self.next()?.as_str() {
+                    ")" => Ok(inner),
+                    other => Err(format!("Expected ')' in capture filter, found '{}'", other)),
+                }
+            }
+            _ => self.primitive(),
+        }
+    }
+
+    fn primitive(&mut self) -> Result<Expr, String> {
+        let tok = self.next()?;
+        let dir = match tok.as_str() {
+            "src" => Some(Dir::Src),
+            "dst" => Some(Dir::Dst),
+            _ => None,
+        };
+        let keyword = if dir.is_some() { self.next()? } else { tok };
+        let dir_or_either = dir.unwrap_or(Dir::Either);
+        let expr = match keyword.as_str() {
+            "ip" if dir.is_none() => Expr::V4,
+            "ip6" if dir.is_none() => Expr::V6,
+            "tcp" if dir.is_none() => Expr::Proto(6),
+            "udp" if dir.is_none() => Expr::Proto(17),
+            "icmp" if dir.is_none() => Expr::Proto(1),
+            "icmp6" if dir.is_none() => Expr::Proto(58),
+            "proto" if dir.is_none() => {
+                let raw = self.next()?;
+                Expr::Proto(
+                    raw.parse()
+                        .map_err(|_| format!("Invalid protocol number '{}'", raw))?,
+                )
+            }
+            "port" => {
+                let raw = self.next()?;
+                Expr::Port(
+                    dir_or_either,
+                    raw.parse().map_err(|_| format!("Invalid port '{}'", raw))?,
+                )
+            }
+            "host" => {
+                let raw = self.next()?;
+                let ip: IpAddr = raw
+                    .parse()
+                    .map_err(|_| format!("Invalid host address '{}'", raw))?;
+                Expr::Net(dir_or_either, IpNet::from(ip))
+            }
+            "net" => {
+                let raw = self.next()?;
+                Expr::Net(
+                    dir_or_either,
+                    raw.parse()
+                        .map_err(|_| format!("Invalid network prefix '{}'", raw))?,
+                )
+            }
+            other => return Err(format!("Unknown capture filter primitive '{}'", other)),
+        };
+        Ok(expr)
+    }
+}
+
+/// One capture point and the file its packets are written to.
+#[derive(Debug)]
+pub struct CapturePoint {
+    pub link: LinkId,
+    pub filter: CaptureFilter,
+    pub file: String,
+    /// Matching packets written so far.
+    pub captured: u64,
+    /// Packets with raw bytes that crossed the link, matching or not.
+    pub seen: u64,
+    /// Open capture file; `None` before [`CapturePoint::open`] or after a write error.
+    writer: Option<BufWriter<File>>,
+}
+
+impl CapturePoint {
+    pub fn from_config(cfg: &CaptureConfig) -> Result<Self, String> {
+        let (a, b) = cfg
+            .link
+            .split_once('_')
+            .ok_or_else(|| format!("Capture link '{}' is not of the form A_B", cfg.link))?;
+        let filter = CaptureFilter::parse(&cfg.filter)
+            .map_err(|e| format!("Capture on {}: {}", cfg.link, e))?;
+        Ok(Self {
+            link: LinkId::new(RouterId(a.to_string()), RouterId(b.to_string())),
+            filter,
+            file: cfg.file.clone(),
+            captured: 0,
+            seen: 0,
+            writer: None,
+        })
+    }
+
+    /// (Re)create the capture file with its comment header and reset the counters.
+    pub fn open(&mut self) -> Result<(), String> {
+        let file = File::create(&self.file)
+            .map_err(|e| format!("Failed to create capture file {}: {}", self.file, e))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(
+            writer,
+            "# capture on {}_{} filter \"{}\"",
+            self.link.a.0,
+            self.link.b.0,
+            self.filter.source()
+        )
+        .map_err(|e| format!("Failed to write capture file {}: {}", self.file, e))?;
+        self.writer = Some(writer);
+        self.captured = 0;
+        self.seen = 0;
+        Ok(())
+    }
+
+    /// Offer a packet that was carried over `link`.
+    pub fn offer(&mut self, link: &LinkId, packet: &PacketMeta) {
+        if *link != self.link {
+            return;
+        }
+        // Packets without raw bytes (the start‑up demonstration packet) cannot be written out.
+        if packet.raw.is_empty() {
+            return;
+        }
+        self.seen += 1;
+        if !self.filter.matches(packet) {
+            return;
+        }
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(e) = writeln!(writer, "{}", hex::encode(&packet.raw)) {
+                error!("Failed to write capture file {}: {}", self.file, e);
+                self.writer = None;
+                return;
+            }
+        }
+        self.captured += 1;
+    }
+
+    /// Write the closing summary line and flush the file.
+    pub fn finish(&mut self) {
+        let Some(mut writer) = self.writer.take() else {
+            return;
+        };
+        let result = writeln!(writer, "# {} of {} packets", self.captured, self.seen)
+            .and_then(|_| writer.flush());
+        match result {
+            Ok(()) => info!("Wrote {} captured packets to {}", self.captured, self.file),
+            Err(e) => error!("Failed to write capture file {}: {}", self.file, e),
+        }
+    }
+}
+
+/// Finish every capture point; failures are logged and do not abort the run.
+pub fn finish_all(points: &mut [CapturePoint]) {
+    for point in points {
+        point.finish();
+    }
+}
@@ -0,0 +1,356 @@
+// src/clab/mod.rs
+
+//! containerlab export and import.
+//!
+//! [`export`] turns the topology of a configuration into a containerlab topology: one `linux`
+//! node per router, one veth link per fabric link (interfaces `eth1`, `eth2`, … in link order),
+//! and on both ends of every link the tc/netem command reproducing its delay, jitter, loss and
+//! bandwidth, plus the MTU, as node `exec` commands. netem shapes egress only, so each direction
+//! of a link is impaired once, as in the simulator. The tun_a and tun_b ingress routers carry
+//! the `network-simulator.edge` label. Link settings netem has no equivalent for (cost, queues,
+//! encapsulation, VLANs, …) are left out and reported.
+//!
+//! [`import`] reads such a topology back, taking the impairments from the `tc … netem` and
+//! `ip link set … mtu` commands, into the `[topology]` and `[tun_ingress]` sections of a
+//! configuration. Nodes must be named like routers (`Rx0y0` … `Rx5y5`).
+
+use crate::config::SimulatorConfig;
+use crate::topology::{LinkConfig, RouterId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Label marking the ingress routers of the edges.
+pub const EDGE_LABEL: &str = "network-simulator.edge";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lab {
+    pub name: String,
+    pub topology: LabTopology,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LabTopology {
+    pub nodes: BTreeMap<String, LabNode>,
+    #[serde(default)]
+    pub links: Vec<LabLink>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LabNode {
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exec: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LabLink {
+    /// `node:interface` of both ends.
+    pub endpoints: Vec<String>,
+}
+
+/// A converted topology and what could not be carried over.
+#[derive(Debug, Clone, Default)]
+pub struct Converted<T> {
+    pub output: T,
+    pub notes: Vec<String>,
+}
+
+/// The netem command reproducing the impairments of `link` on `interface`, if it has any.
+pub fn netem_command(interface: &str, link: &LinkConfig) -> Option<String> {
+    let mut args = String::new();
+    if link.delay_ms > 0 || link.jitter_ms > 0 {
+        args.push_str(&format!(" delay {}ms", link.delay_ms));
+        if link.jitter_ms > 0 {
+            args.push_str(&format!(" {}ms", link.jitter_ms));
+        }
+    }
+    if link.loss_percent > 0.0 {
+        args.push_str(&format!(" loss {}%", link.loss_percent));
+    }
+    if let Some(mbps) = link.bandwidth_mbps {
+        args.push_str(&format!(" rate {}kbit", (mbps * 1000.0).round() as u64));
+    }
+    (!args.is_empty()).then(|| format!("tc qdisc replace dev {} root netem{}", interface, args))
+}
+
+/// Settings of `link` that a veth pair with netem cannot reproduce.
+fn unexported(link: &LinkConfig) -> Vec<&'static str> {
+    let mut lost = Vec::new();
+    if link.cost.is_some() || link.cost_ab.is_some() || link.cost_ba.is_some() {
+        lost.push("cost");
+    }
+    if link.load_balance {
+        lost.push("load_balance");
+    }
+    if link.fragment_train_loss_percent.is_some() {
+        lost.push("fragment_train_loss_percent");
+    }
+    if !link.queues.is_empty() {
+        lost.push("queues");
+    }
+    if link.pool.is_some() {
+        lost.push("pool");
+    }
+    if link.encap.is_some() {
+        lost.push("encap");
+    }
+    if link.bringup_delay_ms > 0 {
+        lost.push("bringup_delay_ms");
+    }
+    if !link.vlans.is_empty() || !link.vlan_rewrite.is_empty() {
+        lost.push("vlans");
+    }
+    lost
+}
+
+/// containerlab topology named `name` of the routers and links of `cfg`, with nodes running
+/// `image`.
+pub fn export(cfg: &SimulatorConfig, name: &str, image: &str) -> Converted<Lab> {
+    let mut lab = Lab {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    for router in cfg.topology.routers.keys() {
+        let mut node = LabNode {
+            kind: "linux".to_string(),
+            image: Some(image.to_string()),
+            ..Default::default()
+        };
+        if *router == cfg.tun_ingress.tun_a_ingress {
+            node.labels
+                .insert(EDGE_LABEL.to_string(), "tun_a".to_string());
+        } else if *router == cfg.tun_ingress.tun_b_ingress {
+            node.labels
+                .insert(EDGE_LABEL.to_string(), "tun_b".to_string());
+        }
+        lab.topology.nodes.insert(router.clone(), node);
+    }
+    let mut notes = Vec::new();
+    // Interfaces assigned so far per node; eth0 is the management interface.
+    let mut assigned: BTreeMap<String, usize> = BTreeMap::new();
+    let mut links: Vec<_> = cfg.topology.links.iter().collect();
+    links.sort_by(|x, y| x.0.cmp(y.0));
+    for (link_name, link) in links {
+        let Some((a, b)) = link_name.split_once('_') else {
+            continue;
+        };
+        if !(lab.topology.nodes.contains_key(a) && lab.topology.nodes.contains_key(b)) {
+            notes.push(format!("Link {} references unknown router(s)", link_name));
+            continue;
+        }
+        let mut endpoints = Vec::new();
+        // netem delays what leaves an interface, so each end gets its own direction's delay.
+        for (router, delay) in [(a, link.delay_ms_ab), (b, link.delay_ms_ba)] {
+            let count = assigned.entry(router.to_string()).or_default();
+            *count += 1;
+            let interface = format!("eth{}", count);
+            let node = lab.topology.nodes.get_mut(router).expect("node");
+            let directed = LinkConfig {
+                delay_ms: delay.unwrap_or(link.delay_ms),
+                ..link.clone()
+            };
+            node.exec.extend(netem_command(&interface, &directed));
+            if let Some(mtu) = link.mtu {
+                node.exec
+                    .push(format!("ip link set dev {} mtu {}", interface, mtu));
+            }
+            endpoints.push(format!("{}:{}", router, interface));
+        }
+        let lost = unexported(link);
+        if !lost.is_empty() {
+            notes.push(format!(
+                "Link {}: {} not exported",
+                link_name,
+                lost.join(", ")
+            ));
+        }
+        lab.topology.links.push(LabLink { endpoints });
+    }
+    Converted { output: lab, notes }
+}
+
+/// Milliseconds of a tc time value such as `10ms`, `1.5ms`, `200us` or `1s`.
+fn parse_ms(value: &str) -> Option<f64> {
+    let (number, scale) = if let Some(n) = value.strip_suffix("us") {
+        (n, 0.001)
+    } else if let Some(n) = value.strip_suffix("ms") {
+        (n, 1.0)
+    } else if let Some(n) = value.strip_suffix('s') {
+        (n, 1000.0)
+    } else {
+        (value, 0.001)
+    };
+    Some(number.parse::<f64>().ok()? * scale)
+}
+
+/// Megabits per second of a tc rate such as `100mbit`, `2500kbit` or `1gbit`.
+fn parse_mbps(value: &str) -> Option<f64> {
+    let units = [
+        ("gbit", 1000.0),
+        ("mbit", 1.0),
+        ("kbit", 0.001),
+        ("bit", 1e-6),
+    ];
+    units.iter().find_map(|(unit, scale)| {
+        let number = value.strip_suffix(unit)?;
+        Some(number.parse::<f64>().ok()? * scale)
+    })
+}
+
+/// Interface a `tc … netem` or `ip link set … mtu` command configures, and the settings it
+/// applies to `link`; `None` for any other command.
+fn apply_command<'a>(command: &'a str, link: &mut LinkConfig) -> Option<&'a str> {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let after = |key: &str| {
+        words
+            .iter()
+            .position(|w| *w == key)
+            .and_then(|i| words.get(i + 1).copied())
+    };
+    let interface = after("dev")?;
+    match words.first() {
+        Some(&"tc") if words.contains(&"netem") => {
+            if let Some(i) = words.iter().position(|w| *w == "delay") {
+                let delay = words.get(i + 1).and_then(|v| parse_ms(v))?;
+                link.delay_ms = delay.round() as u32;
+                if let Some(jitter) = words.get(i + 2).and_then(|v| parse_ms(v)) {
+                    link.jitter_ms = jitter.round() as u32;
+                }
+            }
+            if let Some(loss) = after("loss") {
+                link.loss_percent = loss.trim_end_matches('%').parse().ok()?;
+            }
+            if let Some(rate) = after("rate") {
+                link.bandwidth_mbps = Some(parse_mbps(rate)?);
+            }
+        }
+        Some(&"ip") if words.contains(&"mtu") => link.mtu = Some(after("mtu")?.parse().ok()?),
+        _ => return None,
+    }
+    Some(interface)
+}
+
+/// The settings of `link` [`export`] writes, as a TOML table.
+fn link_table(link: &LinkConfig) -> toml::Value {
+    let mut table = toml::map::Map::new();
+    if link.delay_ms > 0 {
+        table.insert("delay_ms".to_string(), (link.delay_ms as i64).into());
+    }
+    if link.jitter_ms > 0 {
+        table.insert("jitter_ms".to_string(), (link.jitter_ms as i64).into());
+    }
+    if link.loss_percent > 0.0 {
+        table.insert(
+            "loss_percent".to_string(),
+            (link.loss_percent as f64).into(),
+        );
+    }
+    if let Some(mbps) = link.bandwidth_mbps {
+        table.insert("bandwidth_mbps".to_string(), mbps.into());
+    }
+    if let Some(mtu) = link.mtu {
+        table.insert("mtu".to_string(), (mtu as i64).into());
+    }
+    toml::Value::Table(table)
+}
+
+fn same_impairments(x: &LinkConfig, y: &LinkConfig) -> bool {
+    (
+        x.delay_ms,
+        x.jitter_ms,
+        x.loss_percent,
+        x.bandwidth_mbps,
+        x.mtu,
+    ) == (
+        y.delay_ms,
+        y.jitter_ms,
+        y.loss_percent,
+        y.bandwidth_mbps,
+        y.mtu,
+    )
+}
+
+/// Configuration (`[tun_ingress]` and `[topology]`, as TOML) of the containerlab topology
+/// `lab`. Each link gets the impairments of the commands on its first end.
+pub fn import(lab: &Lab) -> Result<Converted<String>, String> {
+    let mut notes = Vec::new();
+    let mut routers = toml::map::Map::new();
+    let mut ingress = toml::map::Map::new();
+    for (name, node) in &lab.topology.nodes {
+        RouterId(name.clone()).validate()?;
+        routers.insert(name.clone(), toml::Value::Table(Default::default()));
+        match node.labels.get(EDGE_LABEL).map(String::as_str) {
+            Some(edge @ ("tun_a" | "tun_b")) => {
+                ingress.insert(format!("{}_ingress", edge), name.clone().into());
+            }
+            Some(other) => notes.push(format!("Node {}: unknown edge label {}", name, other)),
+            None => {}
+        }
+    }
+    // Settings of every link end, by `(node, interface)`.
+    let mut ends: BTreeMap<(&str, &str), LinkConfig> = BTreeMap::new();
+    let mut names = Vec::new();
+    for link in &lab.topology.links {
+        let points: Vec<(&str, &str)> = link
+            .endpoints
+            .iter()
+            .filter_map(|e| e.split_once(':'))
+            .collect();
+        let [a, b] = points[..] else {
+            return Err(format!(
+                "Link {:?} needs two node:interface endpoints",
+                link.endpoints
+            ));
+        };
+        for (node, _) in [a, b] {
+            if !lab.topology.nodes.contains_key(node) {
+                return Err(format!("Link endpoint node '{}' is not defined", node));
+            }
+        }
+        ends.insert(a, LinkConfig::default());
+        ends.insert(b, LinkConfig::default());
+        names.push((format!("{}_{}", a.0.min(b.0), a.0.max(b.0)), a, b));
+    }
+    for (name, node) in &lab.topology.nodes {
+        for command in &node.exec {
+            let mut settings = LinkConfig::default();
+            let end = apply_command(command, &mut settings)
+                .and_then(|interface| ends.get_mut(&(name.as_str(), interface)));
+            let Some(end) = end else {
+                notes.push(format!("Node {}: ignored command: {}", name, command));
+                continue;
+            };
+            if command.starts_with("ip") {
+                end.mtu = settings.mtu;
+            } else {
+                settings.mtu = end.mtu;
+                *end = settings;
+            }
+        }
+    }
+    let mut links = toml::map::Map::new();
+    for (name, a, b) in names {
+        let (first, second) = (&ends[&a], &ends[&b]);
+        if !same_impairments(first, second) {
+            notes.push(format!(
+                "Link {}: the ends are impaired differently, kept {}:{}",
+                name, a.0, a.1
+            ));
+        }
+        links.insert(name, link_table(first));
+    }
+    let mut topology = toml::map::Map::new();
+    topology.insert("routers".to_string(), toml::Value::Table(routers));
+    topology.insert("links".to_string(), toml::Value::Table(links));
+    let mut root = toml::map::Map::new();
+    if !ingress.is_empty() {
+        root.insert("tun_ingress".to_string(), toml::Value::Table(ingress));
+    }
+    root.insert("topology".to_string(), toml::Value::Table(topology));
+    let output = toml::to_string(&toml::Value::Table(root)).map_err(|e| e.to_string())?;
+    Ok(Converted { output, notes })
+}
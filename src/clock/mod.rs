@@ -0,0 +1,96 @@
+// src/clock/mod.rs
+
+//! Clocks of the two edges, with configurable skew.
+//!
+//! Timestamps the simulator embeds are read from the clock of the edge that takes them: a TWAMP
+//! test packet carries the send time by the clock of the sender (tun_a) or the reflector
+//! (tun_b), and its one‑way delay is the receiver's clock at arrival minus that stamp; the packet
+//! trace records when a packet left one edge and reached the other by their clocks. A perfect
+//! clock reads the time since the run started. `[clock_skew.tun_a]` and `[clock_skew.tun_b]` add
+//! an `offset_ms` and a `drift_ppm`, so a clock reads `t + offset + t * drift / 10^6` at `t`.
+//! Offsets shift one‑way delays in opposite directions and cancel out of round trips; drift
+//! makes the error grow over the run.
+
+use crate::config::{ClockSkewConfig, EdgeClockConfig};
+use crate::routing::Destination;
+use tokio::time::{Duration, Instant};
+
+/// Skew of one edge clock.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EdgeClock {
+    pub offset_ms: f64,
+    pub drift_ppm: f64,
+}
+
+impl EdgeClock {
+    fn from_config(cfg: &EdgeClockConfig, name: &str) -> Result<Self, String> {
+        if !cfg.offset_ms.is_finite() {
+            return Err(format!("clock_skew.{}.offset_ms must be finite", name));
+        }
+        if !cfg.drift_ppm.is_finite() || cfg.drift_ppm.abs() >= 1e6 {
+            return Err(format!(
+                "clock_skew.{}.drift_ppm must be between -1000000 and 1000000, got {}",
+                name, cfg.drift_ppm
+            ));
+        }
+        Ok(Self {
+            offset_ms: cfg.offset_ms,
+            drift_ppm: cfg.drift_ppm,
+        })
+    }
+
+    /// Reading in milliseconds `elapsed` after the start of the run.
+    pub fn read_ms(&self, elapsed: Duration) -> f64 {
+        let t = elapsed.as_secs_f64() * 1000.0;
+        t + self.offset_ms + t * self.drift_ppm * 1e-6
+    }
+}
+
+/// The clocks of both edges, started together.
+#[derive(Debug, Clone, Copy)]
+pub struct EdgeClocks {
+    pub start: Instant,
+    pub tun_a: EdgeClock,
+    pub tun_b: EdgeClock,
+}
+
+impl Default for EdgeClocks {
+    /// Perfect clocks started now.
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            tun_a: EdgeClock::default(),
+            tun_b: EdgeClock::default(),
+        }
+    }
+}
+
+impl EdgeClocks {
+    pub fn from_config(cfg: &ClockSkewConfig) -> Result<Self, String> {
+        Ok(Self {
+            tun_a: EdgeClock::from_config(&cfg.tun_a, "tun_a")?,
+            tun_b: EdgeClock::from_config(&cfg.tun_b, "tun_b")?,
+            ..Self::default()
+        })
+    }
+
+    pub fn edge(&self, edge: Destination) -> &EdgeClock {
+        match edge {
+            Destination::TunA => &self.tun_a,
+            Destination::TunB => &self.tun_b,
+        }
+    }
+
+    /// Reading of the clock of `edge` at `at`, in milliseconds.
+    pub fn read_ms(&self, edge: Destination, at: Instant) -> f64 {
+        self.edge(edge)
+            .read_ms(at.saturating_duration_since(self.start))
+    }
+
+    /// Reading of the clock of `edge` at `at` as a timestamp in nanoseconds. A clock running
+    /// behind the start of the run wraps around, so the difference of two stamps (taken with
+    /// wrapping arithmetic) is still the difference of the readings.
+    pub fn stamp_ns(&self, edge: Destination, at: Instant) -> u64 {
+        (self.read_ms(edge, at) * 1e6).round() as i64 as u64
+    }
+}
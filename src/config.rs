@@ -3,7 +3,8 @@
 
 //! Configuration for the network simulator. Includes a flag to enable multipath routing.
 
-use crate::topology::router::RouterId;
+use crate::routing::RoutingAlgorithm;
+use crate::topology::router::{RouterConfig, RouterId};
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 
@@ -19,8 +20,19 @@ pub struct SimulatorConfig {
     pub topology: TopologyConfig,
     #[serde(default = "default_enable_multipath")]
     pub enable_multipath: bool,
+    /// Provenance of the run, stamped on its outputs; set by the CLI, never read from the file.
+    #[serde(skip)]
+    pub run: Option<crate::provenance::RunMetadata>,
+    /// Start `counter_state` over instead of adding to it; set by `--fresh`, never read from the
+    /// file.
+    #[serde(skip)]
+    pub fresh: bool,
     #[serde(default)]
-    pub packet_file: Option<String>, // Optional path to a file containing hex‑encoded mock packets for the TUN interface (overridden by CLI flag)
+    pub counter_state: Option<String>, // Optional JSON file every run adds its counters to, so repeated runs accumulate them (also `--counter-state`)
+    #[serde(default)]
+    pub paranoid: bool, // Abort when a hop does not bring a packet closer to its destination (also `--paranoid`)
+    #[serde(default)]
+    pub packet_file: Option<String>, // Optional path to a file of hex‑encoded mock packets or a pcap/pcapng capture for the TUN interface (overridden by CLI flag)
     #[serde(default)]
     pub packet_files: Option<Vec<String>>, // Optional multiple packet files for mock TUNs
     #[serde(default)]
@@ -29,6 +41,80 @@ pub struct SimulatorConfig {
     pub packet_inject_tuns: Option<Vec<String>>, // Optional injection directions per file
     #[serde(default)]
     pub virtual_customer: Option<VirtualCustomerConfig>, // Optional virtual customer configuration
+    #[serde(default)]
+    pub twamp: Option<TwampConfig>, // Optional TWAMP‑light measurement between the two ingress routers
+    #[serde(default)]
+    pub dhcp: Option<DhcpConfig>, // Optional DHCPv4 server on one edge interface
+    #[serde(default)]
+    pub dns: Option<DnsConfig>, // Optional DNS interception answered from a zone file
+    #[serde(default)]
+    pub http_test: Option<HttpTestConfig>, // Optional HTTP echo origin / load client (feature `http-test`)
+    #[serde(default)]
+    pub marking: Option<MarkingConfig>, // Optional sequence stamping of edge traffic
+    #[serde(default, rename = "capture")]
+    pub captures: Vec<CaptureConfig>, // Per‑link capture points (`[[capture]]` tables)
+    #[serde(default, rename = "mirror")]
+    pub mirrors: Vec<MirrorConfig>, // Per‑router mirror ports copying packets to a monitor sink (`[[mirror]]` tables)
+    #[serde(default)]
+    pub ddos: Option<DdosConfig>, // Optional spoofed‑source flood generator
+    #[serde(default)]
+    pub pseudowire: Option<PseudowireConfig>, // Optional L2TPv3 pseudowire between the edge routers
+    #[serde(default)]
+    pub wireguard: Option<WireguardConfig>, // Optional encrypted overlay between the edge routers
+    #[serde(default)]
+    pub srv6: Option<Srv6Config>, // Optional SRv6 segment lists inserted at the edges
+    #[serde(default)]
+    pub ttl: Option<TtlConfig>, // Optional per‑edge TTL rewrite and uniform/pipe model
+    #[serde(default)]
+    pub packet_trace: Option<String>, // Optional CSV file of per‑packet sojourn‑time records
+    #[serde(default)]
+    pub packet_trace_format: crate::sojourn::TraceFormat, // "csv" (default) or "json": one JSON object per packet
+    #[serde(default)]
+    pub reverse_traffic: Option<ReverseTrafficConfig>, // Optional synthesized replies to one‑sided packet files
+    #[serde(default)]
+    pub alarms: Option<AlarmsConfig>, // Optional link monitoring alarms with thresholds
+    #[serde(default)]
+    pub reassembly: Option<ReassemblyConfig>, // Optional reassembly of fragments before they leave the fabric
+    #[serde(default)]
+    pub checksum: Option<ChecksumConfig>, // Optional checksum verification of packets entering the fabric
+    #[serde(default)]
+    pub dedupe: Option<DedupeConfig>, // Optional removal of duplicate packets before they leave the fabric
+    #[serde(default)]
+    pub drop_capture: Option<DropCaptureConfig>, // Optional per‑router ring of the last dropped packets
+    #[serde(default)]
+    pub replay_output: Option<ReplayOutputConfig>, // Optional queue and throughput of the packet file output sink
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>, // Optional streaming telemetry of the counters (gNMI with feature `gnmi`)
+    #[serde(default)]
+    pub ecmp_hash: Option<EcmpHashConfig>, // Optional choice of the packet fields hashed for ECMP (default 5-tuple)
+    #[serde(default, rename = "destination_map")]
+    pub destination_map: Vec<DestinationMapConfig>, // Egress edge per ingress edge and destination prefix (`[[destination_map]]` tables)
+    #[serde(default, rename = "route")]
+    pub routes: Vec<RouteConfig>, // Destination prefixes attached to routers (`[[route]]` tables), routed by longest prefix match
+    #[serde(default)]
+    pub nat44: Option<Nat44Config>, // Optional NAT44 hiding the hosts behind one edge
+    #[serde(default)]
+    pub nat64: Option<Nat64Config>, // Optional NAT64 translating between IPv6 and IPv4 at one router
+    #[serde(default, rename = "event")]
+    pub events: Vec<EventConfig>, // Scheduled link failures and recoveries and router drains (`[[event]]` tables)
+    #[serde(default)]
+    pub clock_skew: Option<ClockSkewConfig>, // Optional offset and drift of the edge clocks taking timestamps
+    #[serde(default)]
+    pub link_state: Option<LinkStateConfig>, // Optional link-state flooding: after a topology change each router installs new tables when the change reaches it
+    #[serde(default)]
+    pub distance_vector: Option<DistanceVectorConfig>, // Optional RIP-like routing: edge routes learned from periodic distance-vector updates
+    #[serde(default)]
+    pub bgp: Option<BgpConfig>, // Optional BGP-like routing between the AS groups of the routers
+    #[serde(default)]
+    pub routing: RoutingConfig, // What the routing tables optimise: delay, bottleneck bandwidth or hop count
+    #[serde(default, rename = "tenant")]
+    pub tenants: Vec<TenantConfig>, // Tenants (VRFs) packets are classified to at ingress (`[[tenant]]` tables), with their own prefix routes and counters
+    #[serde(default, rename = "explicit_path")]
+    pub explicit_paths: Vec<ExplicitPathConfig>, // Router hops matching packets follow instead of the routing tables (`[[explicit_path]]` tables)
+    #[serde(default)]
+    pub snapshot: Option<SnapshotConfig>, // Optional periodic TOML snapshots of the link parameters and admin states
+    #[serde(default)]
+    pub exit_report: Option<ExitReportConfig>, // Optional JSON report of the run and its verdicts for CI (also `--exit-report`)
 }
 
 impl SimulatorConfig {
@@ -82,6 +168,13 @@ impl SimulatorConfig {
                 return Err("packet_files list cannot be empty".to_string());
             }
         }
+        // IPv4 needs 68 bytes; beyond jumbo frames nothing is sized.
+        if !(68..=MAX_MTU).contains(&self.simulation.mtu) {
+            return Err(format!(
+                "simulation.mtu must be between 68 and {}, got {}",
+                MAX_MTU, self.simulation.mtu
+            ));
+        }
         // Validate existence of packet file(s) if provided.
         use std::net::Ipv4Addr;
         use std::path::Path;
@@ -105,6 +198,14 @@ impl SimulatorConfig {
         if router_ids.is_empty() {
             return Err("Topology must define at least one router".to_string());
         }
+        if let Some(reference) = self.topology.reference_bandwidth_mbps {
+            if !reference.is_finite() || reference <= 0.0 {
+                return Err(format!(
+                    "reference_bandwidth_mbps must be a positive number, got {}",
+                    reference
+                ));
+            }
+        }
         for link_name in self.topology.links.keys() {
             let parts: Vec<&str> = link_name.split('_').collect();
             if parts.len() != 2 {
@@ -141,6 +242,148 @@ impl SimulatorConfig {
                 ));
             }
             seen.insert(key);
+            let link_cfg = &self.topology.links[link_name];
+            if [link_cfg.cost, link_cfg.cost_ab, link_cfg.cost_ba].contains(&Some(0)) {
+                return Err(format!("Link '{}': cost must be at least 1", link_name));
+            }
+            if link_cfg.weight == Some(0) {
+                return Err(format!("Link '{}': weight must be at least 1", link_name));
+            }
+            if let Some(mbps) = link_cfg.bandwidth_mbps {
+                if !mbps.is_finite() || mbps <= 0.0 {
+                    return Err(format!(
+                        "Link '{}': bandwidth_mbps must be a positive number, got {}",
+                        link_name, mbps
+                    ));
+                }
+            } else if !link_cfg.queues.is_empty() {
+                return Err(format!(
+                    "Link '{}': queues require bandwidth_mbps",
+                    link_name
+                ));
+            }
+            if let Some(mtu) = link_cfg.mtu.filter(|&mtu| mtu > MAX_MTU) {
+                return Err(format!(
+                    "Link '{}': mtu {} exceeds the largest supported MTU {}",
+                    link_name, mtu, MAX_MTU
+                ));
+            }
+            if let (Some(encap), Some(mtu)) = (link_cfg.encap, link_cfg.mtu) {
+                if mtu as usize <= crate::gre::max_overhead(encap) + 20 {
+                    return Err(format!(
+                        "Link '{}': mtu {} leaves no room inside the tunnel",
+                        link_name, mtu
+                    ));
+                }
+            }
+            let vids = link_cfg.vlan_rewrite.iter().flat_map(|r| [r.from, r.to]);
+            if let Some(vid) = link_cfg
+                .vlans
+                .iter()
+                .copied()
+                .chain(vids)
+                .find(|vid| !(1..=crate::packet::vlan::MAX_VID).contains(vid))
+            {
+                return Err(format!("Link '{}': invalid VLAN ID {}", link_name, vid));
+            }
+            check_queues(&format!("Link '{}'", link_name), &link_cfg.queues)?;
+            if let Some(ref pool) = link_cfg.pool {
+                if !self.topology.pools.contains_key(pool) {
+                    return Err(format!(
+                        "Link '{}' references unknown pool '{}'",
+                        link_name, pool
+                    ));
+                }
+            }
+        }
+        for (name, pool) in &self.topology.pools {
+            if !pool.bandwidth_mbps.is_finite() || pool.bandwidth_mbps <= 0.0 {
+                return Err(format!(
+                    "Pool '{}': bandwidth_mbps must be a positive number, got {}",
+                    name, pool.bandwidth_mbps
+                ));
+            }
+            check_queues(&format!("Pool '{}'", name), &pool.queues)?;
+        }
+        for id in self.topology.routers.keys() {
+            let router_cfg = self.topology.router_config(id)?;
+            for rule in &router_cfg.acl {
+                crate::acl::AclRule::from_config(rule)
+                    .map_err(|e| format!("Router '{}': invalid ACL rule: {}", id, e))?;
+            }
+            for rule in &router_cfg.pbr {
+                let compiled = crate::pbr::PbrRule::from_config(rule)
+                    .map_err(|e| format!("Router '{}': invalid PBR rule: {}", id, e))?;
+                if let crate::pbr::PbrAction::NextHop(hop) = compiled.action {
+                    let key = if *id < hop.0 {
+                        (id.clone(), hop.0.clone())
+                    } else {
+                        (hop.0.clone(), id.clone())
+                    };
+                    if !seen.contains(&key) {
+                        return Err(format!(
+                            "Router '{}': PBR next hop '{}' is not a neighbour",
+                            id, hop.0
+                        ));
+                    }
+                }
+            }
+            if self.enable_multipath && !router_cfg.static_routes.is_empty() {
+                return Err(format!(
+                    "Router '{}': static routes are not supported with enable_multipath",
+                    id
+                ));
+            }
+            match (router_cfg.asn, &self.bgp) {
+                (Some(_), None) => {
+                    return Err(format!("Router '{}': asn requires a [bgp] section", id));
+                }
+                (None, Some(_)) => {
+                    return Err(format!(
+                        "Router '{}': every router needs an asn with [bgp]",
+                        id
+                    ));
+                }
+                _ => {}
+            }
+            if self.distance_vector.is_some()
+                && (router_cfg.static_routes.contains_key("tun_a")
+                    || router_cfg.static_routes.contains_key("tun_b"))
+            {
+                return Err(format!(
+                    "Router '{}': static edge routes are not supported with distance_vector",
+                    id
+                ));
+            }
+            for (destination, route) in &router_cfg.static_routes {
+                crate::routing::static_routes::StaticRoute::new(destination, route)
+                    .map_err(|e| format!("Router '{}': {}", id, e))?;
+                let hop = &route.via().to_string();
+                let key = if id < hop {
+                    (id.clone(), hop.clone())
+                } else {
+                    (hop.clone(), id.clone())
+                };
+                if !seen.contains(&key) {
+                    return Err(format!(
+                        "Router '{}': static route next hop '{}' for {} is not a neighbour",
+                        id, hop, destination
+                    ));
+                }
+            }
+            for policer in &router_cfg.policers {
+                policer
+                    .validate()
+                    .map_err(|e| format!("Router '{}': invalid policer: {}", id, e))?;
+            }
+            if let Some(pps) = router_cfg.max_pps {
+                if !pps.is_finite() || pps <= 0.0 {
+                    return Err(format!(
+                        "Router '{}': max_pps must be a positive number, got {}",
+                        id, pps
+                    ));
+                }
+            }
         }
         // Validate ingress routers exist in topology
         if !router_ids.contains(&self.tun_ingress.tun_a_ingress) {
@@ -200,145 +443,1596 @@ impl SimulatorConfig {
                 }
             }
         }
-        Ok(())
-    }
-}
-
-impl Default for SimulatorConfig {
-    fn default() -> Self {
-        Self {
-            simulation: SimulationConfig::default(),
-            interfaces: InterfacesConfig::default(),
-            tun_ingress: TunIngressConfig::default(),
-            topology: TopologyConfig::default(),
-            enable_multipath: false,
-            packet_file: None,
-            packet_files: None,
-            packet_inject_tun: None,
-            packet_inject_tuns: None,
-            virtual_customer: None,
+        // Validate DHCP server settings: a TAP edge with an IPv4 edge prefix containing the
+        // gateway address to serve from.
+        if let Some(ref dhcp) = self.dhcp {
+            let (prefix, iface) = match dhcp.edge.as_str() {
+                "tun_a" => (&self.tun_ingress.tun_a_prefix, rt_a),
+                "tun_b" => (&self.tun_ingress.tun_b_prefix, rt_b),
+                other => {
+                    return Err(format!(
+                        "Invalid dhcp.edge value '{}', expected 'tun_a' or 'tun_b'",
+                        other
+                    ))
+                }
+            };
+            if !iface.tap {
+                return Err(format!(
+                    "DHCP on {} requires a TAP edge (interfaces.real_{}.tap = true)",
+                    dhcp.edge, dhcp.edge
+                ));
+            }
+            let network = prefix.parse::<ipnet::Ipv4Net>().map_err(|_| {
+                format!(
+                    "DHCP on {} requires an IPv4 CIDR edge prefix, got '{}'",
+                    dhcp.edge, prefix
+                )
+            })?;
+            let gateway = iface.address.parse::<Ipv4Addr>().map_err(|_| {
+                format!(
+                    "DHCP on {} requires an IPv4 interface address, got '{}'",
+                    dhcp.edge, iface.address
+                )
+            })?;
+            if !network.contains(&gateway) {
+                return Err(format!(
+                    "DHCP gateway {} on {} is outside the pool {}",
+                    gateway, dhcp.edge, network
+                ));
+            }
         }
-    }
-}
-
-#[derive(Debug, Deserialize, Default)]
-pub struct SimulationConfig {
-    #[serde(default = "default_mtu")]
-    pub mtu: u32,
-    #[serde(default)]
-    pub seed: Option<u64>,
-}
-
-fn default_enable_multipath() -> bool {
-    false
-}
-fn default_mtu() -> u32 {
-    1500
-}
-
-#[derive(Debug, Deserialize, Default)]
-pub struct InterfacesConfig {
-    #[serde(default = "default_tun_a")]
-    pub tun_a: String,
-    #[serde(default = "default_tun_b")]
-    pub tun_b: String,
-    #[serde(default = "default_real_tun_a")]
-    pub real_tun_a: RealTunConfig,
-    #[serde(default = "default_real_tun_b")]
-    pub real_tun_b: RealTunConfig,
-}
-
-#[derive(Debug, Deserialize, Default)]
-pub struct RealTunConfig {
-    #[serde(default = "default_real_tun_name")]
-    pub name: String,
-    #[serde(default = "default_real_tun_addr")]
-    pub address: String,
-    #[serde(default = "default_real_tun_netmask")]
-    pub netmask: String,
-}
-
-fn default_tun_a() -> String {
-    "tunA".to_string()
-}
-fn default_tun_b() -> String {
-    "tunB".to_string()
-}
-
-fn default_real_tun_name() -> String {
-    "tun0".to_string()
-}
-fn default_real_tun_addr() -> String {
-    "10.0.0.1".to_string()
-}
-fn default_real_tun_netmask() -> String {
-    "255.255.255.0".to_string()
-}
-fn default_real_tun_a() -> RealTunConfig {
-    RealTunConfig {
-        name: "tun0a".to_string(),
-        address: "10.0.0.1".to_string(),
-        netmask: "255.255.255.0".to_string(),
-    }
-}
-
-fn default_real_tun_b() -> RealTunConfig {
-    RealTunConfig {
-        name: "tun0b".to_string(),
-        address: "10.0.1.1".to_string(),
-        netmask: "255.255.255.0".to_string(),
-    }
-}
-
-#[derive(Debug, Deserialize, Default)]
-pub struct TunIngressConfig {
-    #[serde(default = "default_ingress_a")]
-    pub tun_a_ingress: String,
-    #[serde(default = "default_ingress_b")]
-    pub tun_b_ingress: String,
-    #[serde(default = "default_prefix_a")]
-    pub tun_a_prefix: String,
-    #[serde(default = "default_prefix_b")]
-    pub tun_b_prefix: String,
-    #[serde(default = "default_ipv6_prefix_a")]
-    pub tun_a_ipv6_prefix: String,
-    #[serde(default = "default_ipv6_prefix_b")]
-    pub tun_b_ipv6_prefix: String,
-}
-
-fn default_ingress_a() -> String {
-    "Rx0y0".to_string()
-}
-fn default_ingress_b() -> String {
-    "Rx5y5".to_string()
-}
-fn default_prefix_a() -> String {
-    "10.".to_string()
-}
-fn default_prefix_b() -> String {
-    "".to_string()
-}
-fn default_ipv6_prefix_a() -> String {
-    "::/0".to_string()
-}
-fn default_ipv6_prefix_b() -> String {
-    "::/0".to_string()
-}
-
-#[derive(Debug, Deserialize, Default)]
-pub struct VirtualCustomerConfig {
-    // Example fields for a virtual traffic generator
-    pub src_ip: Option<String>,
-    pub dst_ip: Option<String>,
-    pub protocol: Option<u8>, // e.g., 6 for TCP, 17 for UDP
-    pub size: Option<usize>,  // packet size in bytes
-    pub rate: Option<u64>,    // packets per second
-}
-
-#[derive(Debug, Deserialize, Default)]
-pub struct TopologyConfig {
-    #[serde(default)]
-    pub routers: HashMap<String, toml::Value>, // empty tables just indicate existence
-    #[serde(default)]
-    pub links: HashMap<String, super::topology::link::LinkConfig>,
+        if !self.simulation.warmup_secs.is_finite() || self.simulation.warmup_secs < 0.0 {
+            return Err(format!(
+                "simulation.warmup_secs must be a non-negative number, got {}",
+                self.simulation.warmup_secs
+            ));
+        }
+        if let Some(ref dns) = self.dns {
+            if !Path::new(&dns.zone_file).exists() {
+                return Err(format!("dns.zone_file '{}' does not exist", dns.zone_file));
+            }
+        }
+        if let Some(ref http) = self.http_test {
+            if !cfg!(feature = "http-test") {
+                return Err(
+                    "[http_test] requires the simulator to be built with the 'http-test' feature"
+                        .to_string(),
+                );
+            }
+            if http.server_bind.is_none() && http.target.is_none() {
+                return Err("[http_test] needs 'server_bind', 'target' or both".to_string());
+            }
+            for addr in [&http.server_bind, &http.target].into_iter().flatten() {
+                if addr.parse::<std::net::SocketAddr>().is_err() {
+                    return Err(format!("Invalid http_test socket address '{}'", addr));
+                }
+            }
+            if let Some(ref bind) = http.client_bind {
+                if bind.parse::<std::net::IpAddr>().is_err() {
+                    return Err(format!("Invalid http_test client_bind address '{}'", bind));
+                }
+            }
+            if self.interfaces.real_tun_a.netns.is_none()
+                || self.interfaces.real_tun_b.netns.is_none()
+            {
+                // Without namespaces both ends are local and the kernel bypasses the fabric.
+                return Err(
+                    "[http_test] requires interfaces.real_tun_a.netns and real_tun_b.netns"
+                        .to_string(),
+                );
+            }
+            if http.concurrency == 0 {
+                return Err("http_test.concurrency must be at least 1".to_string());
+            }
+        }
+        for capture in &self.captures {
+            if !self.topology.links.contains_key(&capture.link) {
+                return Err(format!(
+                    "Capture link '{}' is not defined in topology.links",
+                    capture.link
+                ));
+            }
+            crate::capture::CapturePoint::from_config(capture)?;
+        }
+        for mirror in &self.mirrors {
+            if !self.topology.routers.contains_key(&mirror.router) {
+                return Err(format!(
+                    "Mirror router '{}' is not defined in topology.routers",
+                    mirror.router
+                ));
+            }
+            crate::capture::mirror::MirrorPort::from_config(mirror)?;
+        }
+        if let Some(ref ddos) = self.ddos {
+            let target: ipnet::IpNet = ddos
+                .target_prefix
+                .parse()
+                .map_err(|_| format!("Invalid ddos.target_prefix '{}'", ddos.target_prefix))?;
+            let spoof: ipnet::IpNet = ddos
+                .spoof_prefix
+                .parse()
+                .map_err(|_| format!("Invalid ddos.spoof_prefix '{}'", ddos.spoof_prefix))?;
+            if target.addr().is_ipv4() != spoof.addr().is_ipv4() {
+                return Err(
+                    "ddos.target_prefix and ddos.spoof_prefix must be of the same address family"
+                        .to_string(),
+                );
+            }
+            for ingress in &ddos.ingresses {
+                if !router_ids.contains(ingress) {
+                    return Err(format!("ddos ingress router '{}' not found", ingress));
+                }
+            }
+            if !ddos.rate_pps.is_finite() || ddos.rate_pps <= 0.0 {
+                return Err(format!(
+                    "ddos.rate_pps must be a positive number, got {}",
+                    ddos.rate_pps
+                ));
+            }
+            if !ddos.duration_secs.is_finite() || ddos.duration_secs <= 0.0 {
+                return Err(format!(
+                    "ddos.duration_secs must be a positive number, got {}",
+                    ddos.duration_secs
+                ));
+            }
+            if !matches!(ddos.protocol.as_str(), "udp" | "tcp-syn" | "icmp") {
+                return Err(format!(
+                    "ddos.protocol must be one of udp, tcp-syn, icmp; got '{}'",
+                    ddos.protocol
+                ));
+            }
+        }
+        if let Some(ref pw) = self.pseudowire {
+            for (end, name) in [(&pw.a, "a"), (&pw.b, "b")] {
+                if end.local_session_id == 0 || end.remote_session_id == 0 {
+                    return Err(format!(
+                        "pseudowire.{} session IDs must be non-zero (0 is reserved)",
+                        name
+                    ));
+                }
+                crate::pseudowire::parse_cookie(end.local_cookie.as_deref())?;
+                crate::pseudowire::parse_cookie(end.remote_cookie.as_deref())?;
+            }
+            if pw.udp_port == 0 {
+                return Err("pseudowire.udp_port must be non-zero".to_string());
+            }
+        }
+        if let Some(ref wg) = self.wireguard {
+            for (peer, name) in [(&wg.a, "a"), (&wg.b, "b")] {
+                crate::wireguard::parse_key(&peer.key)
+                    .map_err(|e| format!("wireguard.{}.key: {}", name, e))?;
+                if let Some(ref key) = peer.peer_key {
+                    crate::wireguard::parse_key(key)
+                        .map_err(|e| format!("wireguard.{}.peer_key: {}", name, e))?;
+                }
+            }
+            if wg.udp_port == 0 {
+                return Err("wireguard.udp_port must be non-zero".to_string());
+            }
+        }
+        if let Some(ref srv6) = self.srv6 {
+            crate::srv6::parse_segments(&srv6.a_to_b)?;
+            crate::srv6::parse_segments(&srv6.b_to_a)?;
+        }
+        if let Some(ref ttl) = self.ttl {
+            for (edge, name) in [(&ttl.tun_a, "tun_a"), (&ttl.tun_b, "tun_b")] {
+                if edge.set == Some(0) {
+                    return Err(format!("ttl.{}.set must be at least 1", name));
+                }
+                crate::ttl::TtlMode::parse(&edge.mode)?;
+            }
+        }
+        if let Some(ref vc) = self.virtual_customer {
+            crate::customer::validate(vc)?;
+        }
+        if let Some(ref reverse) = self.reverse_traffic {
+            if self.packet_file.is_none() && self.packet_files.is_none() {
+                return Err(
+                    "[reverse_traffic] requires a 'packet_file' or 'packet_files' to answer"
+                        .to_string(),
+                );
+            }
+            if !reverse.ratio.is_finite() || reverse.ratio <= 0.0 {
+                return Err(format!(
+                    "reverse_traffic.ratio must be a positive number, got {}",
+                    reverse.ratio
+                ));
+            }
+        }
+        if let Some(ref alarms) = self.alarms {
+            crate::alarms::AlarmMonitor::new(alarms)?;
+            for link in alarms.rules.iter().filter_map(|r| r.link.as_ref()) {
+                if !self.topology.links.contains_key(link) {
+                    return Err(format!(
+                        "Alarm link '{}' is not defined in topology.links",
+                        link
+                    ));
+                }
+            }
+        }
+        if let Some(ref reassembly) = self.reassembly {
+            if reassembly.timeout_ms == 0 || reassembly.max_buffer_bytes == 0 {
+                return Err(
+                    "reassembly.timeout_ms and reassembly.max_buffer_bytes must be positive"
+                        .to_string(),
+                );
+            }
+        }
+        if let Some(ref dedupe) = self.dedupe {
+            if dedupe.window_ms == 0 || dedupe.max_entries == 0 {
+                return Err("dedupe.window_ms and dedupe.max_entries must be positive".to_string());
+            }
+        }
+        if self
+            .drop_capture
+            .as_ref()
+            .is_some_and(|d| d.per_router == 0)
+        {
+            return Err("drop_capture.per_router must be positive".to_string());
+        }
+        if let Some(ref output) = self.replay_output {
+            if output.queue == 0 || output.rate_bps == Some(0) || output.flush_ms == 0 {
+                return Err(
+                    "replay_output.queue, replay_output.rate_bps and replay_output.flush_ms must be positive"
+                        .to_string(),
+                );
+            }
+            if !["hex", "pcapng"].contains(&output.format.as_str()) {
+                return Err(format!(
+                    "replay_output.format must be \"hex\" or \"pcapng\", got \"{}\"",
+                    output.format
+                ));
+            }
+        }
+        if let Some(ref telemetry) = self.telemetry {
+            if telemetry.interval_ms == 0 {
+                return Err("telemetry.interval_ms must be positive".to_string());
+            }
+            if let Some(ref listen) = telemetry.gnmi_listen {
+                if !cfg!(feature = "gnmi") {
+                    return Err(
+                        "telemetry.gnmi_listen requires the simulator to be built with the 'gnmi' feature"
+                            .to_string(),
+                    );
+                }
+                if listen.parse::<std::net::SocketAddr>().is_err() {
+                    return Err(format!(
+                        "Invalid telemetry.gnmi_listen address '{}'",
+                        listen
+                    ));
+                }
+            }
+            if let Some(ref listen) = telemetry.dashboard_listen {
+                if listen.parse::<std::net::SocketAddr>().is_err() {
+                    return Err(format!(
+                        "Invalid telemetry.dashboard_listen address '{}'",
+                        listen
+                    ));
+                }
+            }
+        }
+        if let Some(ref snapshot) = self.snapshot {
+            if snapshot.dir.is_empty() {
+                return Err("snapshot.dir must not be empty".to_string());
+            }
+            if !(snapshot.interval_secs > 0.0 && snapshot.interval_secs.is_finite()) {
+                return Err("snapshot.interval_secs must be positive".to_string());
+            }
+        }
+        if let Some(ref report) = self.exit_report {
+            if report.path.is_empty() {
+                return Err("exit_report.path must not be empty".to_string());
+            }
+            crate::report::fail_on_alarm(report).map_err(|e| format!("exit_report: {}", e))?;
+        }
+        if let Some(ref hash) = self.ecmp_hash {
+            if !matches!(hash.fields.as_str(), "5-tuple" | "3-tuple") {
+                return Err(format!(
+                    "ecmp_hash.fields must be \"5-tuple\" or \"3-tuple\", got \"{}\"",
+                    hash.fields
+                ));
+            }
+        }
+        crate::routing::destination_map::DestinationMap::new(&self.destination_map)?;
+        if let Some(ref nat) = self.nat44 {
+            crate::nat::Nat44::new(nat)?;
+        }
+        if let Some(ref nat) = self.nat64 {
+            crate::nat::nat64::Nat64::new(nat)?;
+            if !router_ids.contains(&nat.router) {
+                return Err(format!(
+                    "NAT64 router '{}' is not defined in topology.routers",
+                    nat.router
+                ));
+            }
+        }
+        if let Some(ref skew) = self.clock_skew {
+            crate::clock::EdgeClocks::from_config(skew)?;
+        }
+        if let Some(ref ls) = self.link_state {
+            for (name, value) in [
+                ("flood_delay_ms", ls.flood_delay_ms),
+                ("spf_delay_ms", ls.spf_delay_ms),
+            ] {
+                if !value.is_finite() || value < 0.0 {
+                    return Err(format!("link_state.{} must be >= 0, got {}", name, value));
+                }
+            }
+        }
+        if let Some(ref dv) = self.distance_vector {
+            if !dv.update_interval_ms.is_finite() || dv.update_interval_ms <= 0.0 {
+                return Err(format!(
+                    "distance_vector.update_interval_ms must be > 0, got {}",
+                    dv.update_interval_ms
+                ));
+            }
+            if dv.infinity < 2 {
+                return Err(format!(
+                    "distance_vector.infinity must be at least 2, got {}",
+                    dv.infinity
+                ));
+            }
+            if self.link_state.is_some() || self.enable_multipath {
+                return Err(
+                    "distance_vector cannot be combined with link_state or enable_multipath"
+                        .to_string(),
+                );
+            }
+        }
+        if let Some(ref bgp) = self.bgp {
+            if self.enable_multipath || self.link_state.is_some() || self.distance_vector.is_some()
+            {
+                return Err(
+                    "bgp cannot be combined with enable_multipath, link_state or distance_vector"
+                        .to_string(),
+                );
+            }
+            let mut asns = HashSet::new();
+            for id in self.topology.routers.keys() {
+                asns.extend(self.topology.router_config(id)?.asn);
+            }
+            for policy in &bgp.policies {
+                for asn in [policy.asn, policy.neighbor_as] {
+                    if !asns.contains(&asn) {
+                        return Err(format!(
+                            "bgp policy refers to AS {}, which has no routers",
+                            asn
+                        ));
+                    }
+                }
+            }
+        }
+        if self.routing.distance == Some(0) {
+            return Err("routing.distance must be between 1 and 255".to_string());
+        }
+        if self.routing.algorithm == RoutingAlgorithm::WidestPath
+            && (self.enable_multipath || self.bgp.is_some() || self.distance_vector.is_some())
+        {
+            return Err(
+                "routing.algorithm = \"widest-path\" cannot be combined with enable_multipath, bgp or distance_vector"
+                    .to_string(),
+            );
+        }
+        if self.enable_multipath && !self.routes.is_empty() {
+            return Err("[[route]] prefixes are not supported with enable_multipath".to_string());
+        }
+        for path in &self.explicit_paths {
+            crate::explicit_path::ExplicitPath::from_config(path)?;
+            for (i, hop) in path.hops.iter().enumerate() {
+                if !router_ids.contains(hop) {
+                    return Err(format!(
+                        "Explicit path '{}': router '{}' is not defined in topology.routers",
+                        path.filter, hop
+                    ));
+                }
+                let Some(prev) = i.checked_sub(1).map(|i| &path.hops[i]) else {
+                    continue;
+                };
+                if !self
+                    .topology
+                    .links
+                    .contains_key(&format!("{}_{}", prev, hop))
+                    && !self
+                        .topology
+                        .links
+                        .contains_key(&format!("{}_{}", hop, prev))
+                {
+                    return Err(format!(
+                        "Explicit path '{}': no link between {} and {}",
+                        path.filter, prev, hop
+                    ));
+                }
+            }
+        }
+        let mut tenants = HashSet::new();
+        for tenant in &self.tenants {
+            if tenant.name.is_empty() || !tenants.insert(tenant.name.as_str()) {
+                return Err(format!(
+                    "Tenant names must be unique and not empty, got '{}'",
+                    tenant.name
+                ));
+            }
+            if tenant.prefixes.is_empty() && tenant.vlans.is_empty() {
+                return Err(format!(
+                    "Tenant '{}' needs prefixes or vlans to classify packets by",
+                    tenant.name
+                ));
+            }
+            crate::tenant::Tenant::from_config(tenant)?;
+            if let Some(vid) = tenant.vlans.iter().find(|v| !(1..=4094).contains(*v)) {
+                return Err(format!(
+                    "Tenant '{}': VLAN {} is not between 1 and 4094",
+                    tenant.name, vid
+                ));
+            }
+        }
+        for route in &self.routes {
+            crate::routing::fib::PrefixRoute::new(route)?;
+            if !router_ids.contains(&route.router) {
+                return Err(format!(
+                    "Route router '{}' for {} is not defined in topology.routers",
+                    route.router, route.prefix
+                ));
+            }
+            if let Some(tenant) = route.tenant.as_deref().filter(|t| !tenants.contains(t)) {
+                return Err(format!(
+                    "Route {} refers to tenant '{}', which is not defined",
+                    route.prefix, tenant
+                ));
+            }
+        }
+        for event in &self.events {
+            use crate::events::Action;
+            match Action::from_config(event)? {
+                Action::Drain(router) | Action::Undrain(router)
+                    if !router_ids.contains(&router.0) =>
+                {
+                    return Err(format!(
+                        "Event router '{}' is not defined in topology.routers",
+                        router.0
+                    ));
+                }
+                Action::LinkDown(..) | Action::LinkUp(..)
+                    if !self.topology.links.contains_key(&event.link) =>
+                {
+                    return Err(format!(
+                        "Event link '{}' is not defined in topology.links",
+                        event.link
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            simulation: SimulationConfig::default(),
+            interfaces: InterfacesConfig::default(),
+            tun_ingress: TunIngressConfig::default(),
+            topology: TopologyConfig::default(),
+            enable_multipath: false,
+            run: None,
+            fresh: false,
+            counter_state: None,
+            paranoid: false,
+            packet_file: None,
+            packet_files: None,
+            packet_inject_tun: None,
+            packet_inject_tuns: None,
+            virtual_customer: None,
+            twamp: None,
+            dhcp: None,
+            dns: None,
+            http_test: None,
+            marking: None,
+            captures: Vec::new(),
+            mirrors: Vec::new(),
+            ddos: None,
+            pseudowire: None,
+            wireguard: None,
+            srv6: None,
+            ttl: None,
+            packet_trace: None,
+            packet_trace_format: Default::default(),
+            reverse_traffic: None,
+            alarms: None,
+            reassembly: None,
+            checksum: None,
+            dedupe: None,
+            drop_capture: None,
+            replay_output: None,
+            telemetry: None,
+            ecmp_hash: None,
+            destination_map: Vec::new(),
+            nat44: None,
+            nat64: None,
+            events: Vec::new(),
+            routes: Vec::new(),
+            clock_skew: None,
+            link_state: None,
+            distance_vector: None,
+            bgp: None,
+            routing: RoutingConfig::default(),
+            tenants: Vec::new(),
+            explicit_paths: Vec::new(),
+            snapshot: None,
+            exit_report: None,
+        }
+    }
+}
+
+/// Check the traffic classes of a link or pool; `owner` prefixes the errors ("Link 'A_B'").
+fn check_queues(owner: &str, queues: &[crate::qos::QueueConfig]) -> Result<(), String> {
+    for queue in queues {
+        if queue.limit == 0 {
+            return Err(format!("{}: queue limit must be at least 1", owner));
+        }
+        if let Some(dscp) = queue.dscp.iter().find(|&&d| d > 63) {
+            return Err(format!("{}: invalid DSCP value {}", owner, dscp));
+        }
+        if let Some(ref wred) = queue.wred {
+            if !(wred.min_threshold >= 0.0 && wred.min_threshold < wred.max_threshold) {
+                return Err(format!(
+                    "{}: WRED needs 0 <= min_threshold < max_threshold",
+                    owner
+                ));
+            }
+            if !(0.0..=100.0).contains(&wred.max_drop_percent) {
+                return Err(format!(
+                    "{}: WRED max_drop_percent must be within 0..=100",
+                    owner
+                ));
+            }
+            if !(wred.ewma_weight > 0.0 && wred.ewma_weight <= 1.0) {
+                return Err(format!("{}: WRED ewma_weight must be within (0, 1]", owner));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimulationConfig {
+    #[serde(default = "default_mtu")]
+    pub mtu: u32,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub warmup_secs: f64, // counters are reset once this much time has passed since start
+    #[serde(default)]
+    pub tie_break_seed: Option<u64>, // selects among equal-cost next hops (lowest RouterId if unset)
+    #[serde(default)]
+    pub egress_pacing: bool, // release real TUN writes at their simulated egress time instead of sleeping per packet
+}
+
+fn default_enable_multipath() -> bool {
+    false
+}
+fn default_mtu() -> u32 {
+    1500
+}
+
+/// Largest MTU the edges and links support (jumbo frames).
+pub const MAX_MTU: u32 = 9216;
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            mtu: default_mtu(),
+            seed: None,
+            tie_break_seed: None,
+            warmup_secs: 0.0,
+            egress_pacing: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct InterfacesConfig {
+    #[serde(default = "default_tun_a")]
+    pub tun_a: String,
+    #[serde(default = "default_tun_b")]
+    pub tun_b: String,
+    #[serde(default = "default_real_tun_a")]
+    pub real_tun_a: RealTunConfig,
+    #[serde(default = "default_real_tun_b")]
+    pub real_tun_b: RealTunConfig,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RealTunConfig {
+    #[serde(default = "default_real_tun_name")]
+    pub name: String,
+    #[serde(default = "default_real_tun_addr")]
+    pub address: String,
+    #[serde(default = "default_real_tun_netmask")]
+    pub netmask: String,
+    /// Open the edge as an L2 TAP device; `address` then belongs to the simulated router on
+    /// that segment instead of the host.
+    #[serde(default)]
+    pub tap: bool,
+    /// Move the device into this network namespace (created if missing) and configure the edge
+    /// address there, so hosts in the namespace reach the other edge only through the fabric.
+    #[serde(default)]
+    pub netns: Option<String>,
+}
+
+fn default_tun_a() -> String {
+    "tunA".to_string()
+}
+fn default_tun_b() -> String {
+    "tunB".to_string()
+}
+
+fn default_real_tun_name() -> String {
+    "tun0".to_string()
+}
+fn default_real_tun_addr() -> String {
+    "10.0.0.1".to_string()
+}
+fn default_real_tun_netmask() -> String {
+    "255.255.255.0".to_string()
+}
+fn default_real_tun_a() -> RealTunConfig {
+    RealTunConfig {
+        name: "tun0a".to_string(),
+        address: "10.0.0.1".to_string(),
+        netmask: "255.255.255.0".to_string(),
+        tap: false,
+        netns: None,
+    }
+}
+
+fn default_real_tun_b() -> RealTunConfig {
+    RealTunConfig {
+        name: "tun0b".to_string(),
+        address: "10.0.1.1".to_string(),
+        netmask: "255.255.255.0".to_string(),
+        tap: false,
+        netns: None,
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct TunIngressConfig {
+    #[serde(default = "default_ingress_a")]
+    pub tun_a_ingress: String,
+    #[serde(default = "default_ingress_b")]
+    pub tun_b_ingress: String,
+    #[serde(default = "default_prefix_a")]
+    pub tun_a_prefix: String,
+    #[serde(default = "default_prefix_b")]
+    pub tun_b_prefix: String,
+    #[serde(default = "default_ipv6_prefix_a")]
+    pub tun_a_ipv6_prefix: String,
+    #[serde(default = "default_ipv6_prefix_b")]
+    pub tun_b_ipv6_prefix: String,
+}
+
+fn default_ingress_a() -> String {
+    "Rx0y0".to_string()
+}
+fn default_ingress_b() -> String {
+    "Rx5y5".to_string()
+}
+fn default_prefix_a() -> String {
+    "10.".to_string()
+}
+fn default_prefix_b() -> String {
+    "".to_string()
+}
+fn default_ipv6_prefix_a() -> String {
+    "::/0".to_string()
+}
+fn default_ipv6_prefix_b() -> String {
+    "::/0".to_string()
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct VirtualCustomerConfig {
+    // Example fields for a virtual traffic generator
+    pub src_ip: Option<String>,
+    pub dst_ip: Option<String>,
+    pub protocol: Option<u8>,  // e.g., 6 for TCP, 17 for UDP
+    pub size: Option<usize>,   // payload bytes after the UDP/TCP header
+    pub src_port: Option<u16>, // UDP/TCP source port (49152 if unset)
+    pub dst_port: Option<u16>, // UDP/TCP destination port (9, discard, if unset)
+    #[serde(default)]
+    pub ipv6_extension_headers: Vec<String>, // "hop_by_hop" and/or "destination_options", in order
+    pub payload_pattern: Option<String>, // "zero" (default), "sequence" or "hex:<bytes>"
+    pub template: Option<String>, // packet to re-stamp: "hex:<bytes>" or "pcap:<file>[#<n>]"
+    pub rate: Option<u64>,     // packets per second
+    pub name: Option<String>,  // customer name used in reports
+    pub sla: Option<SlaConfig>,
+    #[serde(default)]
+    pub reverse: bool, // also send the mirror flow back from the far edge, named "<name>-reverse"
+}
+
+impl VirtualCustomerConfig {
+    /// Name used to key flow metrics and SLA results.
+    pub fn customer_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("virtual_customer")
+    }
+
+    /// The mirror flow of a customer with `reverse`: addresses and ports swapped, everything
+    /// else (size, rate, pattern, SLA) the same. A template keeps its own ports unless they are
+    /// configured.
+    pub fn mirrored(&self) -> Option<VirtualCustomerConfig> {
+        if !self.reverse {
+            return None;
+        }
+        let (src_port, dst_port) = if self.template.is_some() {
+            (self.dst_port, self.src_port)
+        } else {
+            (
+                Some(self.dst_port.unwrap_or(crate::customer::DEFAULT_DST_PORT)),
+                Some(self.src_port.unwrap_or(crate::customer::DEFAULT_SRC_PORT)),
+            )
+        };
+        Some(VirtualCustomerConfig {
+            src_ip: self.dst_ip.clone(),
+            dst_ip: self.src_ip.clone(),
+            src_port,
+            dst_port,
+            name: Some(format!("{}-reverse", self.customer_name())),
+            reverse: false,
+            ..self.clone()
+        })
+    }
+
+    /// The flows the customer sends: its own and, with `reverse`, the mirror flow.
+    pub fn flows(&self) -> Vec<VirtualCustomerConfig> {
+        std::iter::once(self.clone())
+            .chain(self.mirrored())
+            .collect()
+    }
+}
+
+/// SLA targets of a virtual customer; unset targets are not checked.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SlaConfig {
+    pub max_loss_percent: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub min_throughput_mbps: Option<f64>,
+}
+
+/// TWAMP‑light sender/reflector settings. The sender sits on the tun_a ingress router and the
+/// reflector on the tun_b ingress router.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TwampConfig {
+    #[serde(default = "default_twamp_count")]
+    pub count: u32, // number of test packets per session
+    #[serde(default = "default_twamp_interval_ms")]
+    pub interval_ms: u64, // spacing between test packets
+    #[serde(default = "default_twamp_port")]
+    pub port: u16, // UDP port of the reflector
+    #[serde(default = "default_twamp_period_secs")]
+    pub period_secs: u64, // a new session starts this often while the TUNs run; 0 = startup only
+}
+
+impl Default for TwampConfig {
+    fn default() -> Self {
+        Self {
+            count: default_twamp_count(),
+            interval_ms: default_twamp_interval_ms(),
+            port: default_twamp_port(),
+            period_secs: default_twamp_period_secs(),
+        }
+    }
+}
+
+fn default_twamp_count() -> u32 {
+    10
+}
+fn default_twamp_interval_ms() -> u64 {
+    100
+}
+fn default_twamp_port() -> u16 {
+    862
+}
+fn default_twamp_period_secs() -> u64 {
+    60
+}
+
+/// DHCPv4 server emulation on an edge. Addresses are leased from the edge prefix
+/// (`tun_a_prefix` / `tun_b_prefix`) with the edge interface address as gateway.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DhcpConfig {
+    #[serde(default = "default_dhcp_edge")]
+    pub edge: String, // "tun_a" or "tun_b"
+    #[serde(default = "default_dhcp_lease_secs")]
+    pub lease_secs: u32,
+}
+
+impl Default for DhcpConfig {
+    fn default() -> Self {
+        Self {
+            edge: default_dhcp_edge(),
+            lease_secs: default_dhcp_lease_secs(),
+        }
+    }
+}
+
+fn default_dhcp_edge() -> String {
+    "tun_a".to_string()
+}
+fn default_dhcp_lease_secs() -> u32 {
+    3600
+}
+
+/// DNS interception: queries to UDP port 53 are answered from `zone_file` after `delay_ms`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct DnsConfig {
+    pub zone_file: String,
+    #[serde(default)]
+    pub delay_ms: u64, // simulated resolution delay
+    #[serde(default = "default_dns_ttl")]
+    pub ttl: u32, // TTL placed in answer records
+}
+
+fn default_dns_ttl() -> u32 {
+    300
+}
+
+/// Packet coloring: edge packets are stamped with a sequence number at ingress (IPv4 ID or an
+/// IPv6 destination option) and checked at egress; `strip` restores the original packet.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MarkingConfig {
+    #[serde(default = "default_marking_strip")]
+    pub strip: bool,
+}
+
+impl Default for MarkingConfig {
+    fn default() -> Self {
+        Self {
+            strip: default_marking_strip(),
+        }
+    }
+}
+
+fn default_marking_strip() -> bool {
+    true
+}
+
+/// Capture point on one link: packets crossing `link` that match `filter` are written to
+/// `file` as hex lines at the end of the run.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct CaptureConfig {
+    pub link: String, // link name as in topology.links, e.g. "Rx0y0_Rx0y1"
+    #[serde(default)]
+    pub filter: String, // BPF‑like expression, empty = everything
+    pub file: String,
+}
+
+/// Mirror port at one router: packets arriving at `router` that match `filter` are copied to
+/// `file` (hex lines) or to the monitor TUN interface `tun`, cut to `truncate` bytes if set.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct MirrorConfig {
+    pub router: String,
+    #[serde(default)]
+    pub filter: String, // BPF‑like expression, empty = everything
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub tun: Option<String>,
+    #[serde(default)]
+    pub truncate: Option<usize>,
+}
+
+/// Spoofed‑source flood towards `target_prefix`, sent from `ingresses` (default: the tun_a
+/// ingress router) at `rate_pps` for `duration_secs`. Sources are drawn from `spoof_prefix`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DdosConfig {
+    pub target_prefix: String, // e.g. "10.0.1.0/24"
+    #[serde(default)]
+    pub ingresses: Vec<String>, // routers the flood enters the fabric at
+    #[serde(default = "default_ddos_rate_pps")]
+    pub rate_pps: f64, // combined rate over all ingresses
+    #[serde(default = "default_ddos_duration_secs")]
+    pub duration_secs: f64,
+    #[serde(default = "default_ddos_protocol")]
+    pub protocol: String, // "udp", "tcp-syn" or "icmp"
+    #[serde(default = "default_ddos_dst_port")]
+    pub dst_port: u16,
+    #[serde(default = "default_ddos_packet_size")]
+    pub packet_size: usize, // total IP packet size in bytes
+    #[serde(default = "default_ddos_spoof_prefix")]
+    pub spoof_prefix: String,
+}
+
+impl Default for DdosConfig {
+    fn default() -> Self {
+        Self {
+            target_prefix: String::new(),
+            ingresses: Vec::new(),
+            rate_pps: default_ddos_rate_pps(),
+            duration_secs: default_ddos_duration_secs(),
+            protocol: default_ddos_protocol(),
+            dst_port: default_ddos_dst_port(),
+            packet_size: default_ddos_packet_size(),
+            spoof_prefix: default_ddos_spoof_prefix(),
+        }
+    }
+}
+
+fn default_ddos_rate_pps() -> f64 {
+    1000.0
+}
+fn default_ddos_duration_secs() -> f64 {
+    1.0
+}
+fn default_ddos_protocol() -> String {
+    "udp".to_string()
+}
+fn default_ddos_dst_port() -> u16 {
+    53
+}
+fn default_ddos_packet_size() -> usize {
+    64
+}
+fn default_ddos_spoof_prefix() -> String {
+    "198.18.0.0/15".to_string()
+}
+
+/// L2TPv3‑over‑UDP pseudowire between the tun_a (`a`) and tun_b (`b`) edge routers. As on real
+/// routers, each end has the session ID and cookie it expects (`local_*`) and the ones it sends
+/// (`remote_*`), so a mismatched configuration can be emulated.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PseudowireConfig {
+    pub a: PseudowireEndConfig,
+    pub b: PseudowireEndConfig,
+    #[serde(default = "default_pseudowire_udp_port")]
+    pub udp_port: u16,
+    #[serde(default = "default_pseudowire_sequencing")]
+    pub sequencing: bool, // default L2‑Specific Sublayer with sequence numbers
+}
+
+impl Default for PseudowireConfig {
+    fn default() -> Self {
+        Self {
+            a: PseudowireEndConfig::default(),
+            b: PseudowireEndConfig::default(),
+            udp_port: default_pseudowire_udp_port(),
+            sequencing: default_pseudowire_sequencing(),
+        }
+    }
+}
+
+fn default_pseudowire_udp_port() -> u16 {
+    crate::pseudowire::L2TP_UDP_PORT
+}
+fn default_pseudowire_sequencing() -> bool {
+    true
+}
+
+/// One end of the pseudowire: `{ local_session_id = 100, remote_session_id = 200 }`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct PseudowireEndConfig {
+    pub local_session_id: u32,
+    pub remote_session_id: u32,
+    #[serde(default)]
+    pub local_cookie: Option<String>, // hex, 4 or 8 bytes
+    #[serde(default)]
+    pub remote_cookie: Option<String>,
+}
+
+/// WireGuard‑style encrypted overlay between the tun_a (`a`) and tun_b (`b`) edge routers.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WireguardConfig {
+    pub a: WireguardPeerConfig,
+    pub b: WireguardPeerConfig,
+    #[serde(default = "default_wireguard_udp_port")]
+    pub udp_port: u16,
+}
+
+fn default_wireguard_udp_port() -> u16 {
+    crate::wireguard::WIREGUARD_UDP_PORT
+}
+
+/// One edge of the overlay: `{ key = "<64 hex digits>", index = 1 }`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WireguardPeerConfig {
+    pub key: String, // ChaCha20‑Poly1305 key for the packets this edge sends
+    pub index: u32,  // receiver index the peer puts in packets for this edge
+    #[serde(default)]
+    pub peer_key: Option<String>, // key this edge opens the peer's packets with; defaults to the peer's `key`
+}
+
+/// SRv6 segment lists (router IPv6 addresses, in travel order) inserted into IPv6 packets at
+/// the ingress edge, per direction.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Srv6Config {
+    #[serde(default)]
+    pub a_to_b: Vec<String>, // e.g. ["fd00::1:1", "fd00::2:0"]
+    #[serde(default)]
+    pub b_to_a: Vec<String>,
+}
+
+/// TTL treatment per ingress edge: `[ttl.tun_a]` applies to packets entering at tun_a.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TtlConfig {
+    #[serde(default)]
+    pub tun_a: EdgeTtlConfig,
+    #[serde(default)]
+    pub tun_b: EdgeTtlConfig,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EdgeTtlConfig {
+    #[serde(default)]
+    pub set: Option<u8>, // rewrite the TTL / Hop Limit at ingress, e.g. 64
+    #[serde(default = "default_ttl_mode")]
+    pub mode: String, // "uniform" (decrement per router) or "pipe" (fabric is TTL‑transparent)
+}
+
+impl Default for EdgeTtlConfig {
+    fn default() -> Self {
+        Self {
+            set: None,
+            mode: default_ttl_mode(),
+        }
+    }
+}
+
+fn default_ttl_mode() -> String {
+    "uniform".to_string()
+}
+
+/// Link‑state convergence (`[link_state]`): a topology change is flooded from the routers
+/// that detect it, taking each link's delay plus `flood_delay_ms` per hop, and every router
+/// runs its SPF `spf_delay_ms` after the change reaches it.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LinkStateConfig {
+    #[serde(default = "default_flood_delay_ms")]
+    pub flood_delay_ms: f64, // processing of an LSA at every router it passes
+    #[serde(default = "default_spf_delay_ms")]
+    pub spf_delay_ms: f64, // hold-down before the SPF run installing the new tables
+}
+
+fn default_flood_delay_ms() -> f64 {
+    10.0
+}
+
+fn default_spf_delay_ms() -> f64 {
+    50.0
+}
+
+/// RIP‑like distance‑vector routing (`[distance_vector]`): every `update_interval_ms` each
+/// router advertises its distances to the edges to its neighbours, which take the best one
+/// plus the link's cost; a distance of `infinity` or more means unreachable.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DistanceVectorConfig {
+    #[serde(default = "default_update_interval_ms")]
+    pub update_interval_ms: f64, // period of the updates
+    #[serde(default = "default_dv_infinity")]
+    pub infinity: u32, // metric counted as unreachable, 16 as in RIP
+    #[serde(default = "default_split_horizon")]
+    pub split_horizon: bool, // leave routes out of the updates to the neighbour they were learned from
+}
+
+fn default_update_interval_ms() -> f64 {
+    1000.0
+}
+
+fn default_dv_infinity() -> u32 {
+    16
+}
+
+fn default_split_horizon() -> bool {
+    true
+}
+
+/// BGP‑like routing between AS groups (`[bgp]`): every router needs an `asn`, and each
+/// `[[bgp.policy]]` sets what AS `asn` does with the routes it exchanges with `neighbor_as`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct BgpConfig {
+    #[serde(default, rename = "policy")]
+    pub policies: Vec<BgpPolicyConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct BgpPolicyConfig {
+    pub asn: u32,
+    pub neighbor_as: u32,
+    #[serde(default)]
+    pub local_pref: Option<u32>, // of the routes learned from the neighbour (default 100)
+    #[serde(default)]
+    pub prepend: u32, // extra copies of `asn` on the routes advertised to the neighbour
+    #[serde(default)]
+    pub deny: bool, // ignore the routes learned from the neighbour
+}
+
+/// `[routing]`: `algorithm` is "spf" (the default), "widest-path" or "min-hop".
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RoutingConfig {
+    #[serde(default)]
+    pub algorithm: RoutingAlgorithm,
+    #[serde(default)]
+    pub distance: Option<u8>, // administrative distance of the computed routes (default 110)
+}
+
+/// Skew of the edge clocks: `[clock_skew.tun_a]` is the clock of the tun_a edge.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ClockSkewConfig {
+    #[serde(default)]
+    pub tun_a: EdgeClockConfig,
+    #[serde(default)]
+    pub tun_b: EdgeClockConfig,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct EdgeClockConfig {
+    #[serde(default)]
+    pub offset_ms: f64, // constant offset from the true time, may be negative
+    #[serde(default)]
+    pub drift_ppm: f64, // rate error: the clock gains this many microseconds per second
+}
+
+/// Synthesized reverse traffic for one‑sided packet files: every TCP or UDP packet that reaches
+/// the far edge earns `ratio` replies (0.5 = one ACK per two segments), sent back from there.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReverseTrafficConfig {
+    #[serde(default = "default_reverse_ratio")]
+    pub ratio: f64,
+    #[serde(default)]
+    pub payload_bytes: usize, // zero bytes carried by each reply, to size the reverse load
+}
+
+impl Default for ReverseTrafficConfig {
+    fn default() -> Self {
+        Self {
+            ratio: default_reverse_ratio(),
+            payload_bytes: 0,
+        }
+    }
+}
+
+fn default_reverse_ratio() -> f64 {
+    0.5
+}
+
+/// HTTP test origin and load client. The echo server listens on `server_bind` (an address behind
+/// one edge) and the client, bound to `client_bind` behind the other edge, sends `requests` POSTs
+/// of `body_bytes` each to `target` over `concurrency` keep‑alive connections.
+#[derive(Debug, Deserialize, Clone)]
+pub struct HttpTestConfig {
+    #[serde(default)]
+    pub server_bind: Option<String>, // e.g. "10.0.1.2:8080"
+    #[serde(default)]
+    pub target: Option<String>, // e.g. "10.0.1.2:8080"
+    #[serde(default)]
+    pub client_bind: Option<String>, // source address for client connections
+    #[serde(default = "default_http_requests")]
+    pub requests: u32,
+    #[serde(default = "default_http_concurrency")]
+    pub concurrency: u32,
+    #[serde(default = "default_http_body_bytes")]
+    pub body_bytes: usize,
+}
+
+impl Default for HttpTestConfig {
+    fn default() -> Self {
+        Self {
+            server_bind: None,
+            target: None,
+            client_bind: None,
+            requests: default_http_requests(),
+            concurrency: default_http_concurrency(),
+            body_bytes: default_http_body_bytes(),
+        }
+    }
+}
+
+fn default_http_requests() -> u32 {
+    100
+}
+fn default_http_concurrency() -> u32 {
+    4
+}
+fn default_http_body_bytes() -> usize {
+    1024
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct TopologyConfig {
+    #[serde(default)]
+    pub routers: HashMap<String, toml::Value>, // empty tables just indicate existence
+    #[serde(default)]
+    pub links: HashMap<String, super::topology::link::LinkConfig>,
+    /// OSPF‑style reference bandwidth: links with `bandwidth_mbps` and no manual `cost` get
+    /// `cost = reference_bandwidth_mbps / bandwidth_mbps` (at least 1).
+    #[serde(default)]
+    pub reference_bandwidth_mbps: Option<f64>,
+    /// Shared capacity pools links can name with `pool`.
+    #[serde(default)]
+    pub pools: HashMap<String, crate::qos::PoolConfig>,
+}
+
+impl TopologyConfig {
+    /// Options set in a router's table (`Rx0y0 = { max_pps = 1000 }`).
+    pub fn router_config(&self, id: &str) -> Result<RouterConfig, String> {
+        match self.routers.get(id) {
+            Some(value) => value
+                .clone()
+                .try_into()
+                .map_err(|e| format!("Invalid options for router '{}': {}", id, e)),
+            None => Ok(RouterConfig::default()),
+        }
+    }
+
+    /// Routing cost derived from the reference bandwidth, if one is configured and the link
+    /// has a bandwidth but no manual cost.
+    pub fn derived_cost(&self, link: &super::topology::link::LinkConfig) -> Option<u32> {
+        if link.cost.is_some() {
+            return None;
+        }
+        let reference = self.reference_bandwidth_mbps?;
+        let bandwidth = link.bandwidth_mbps?;
+        Some(((reference / bandwidth) as u32).max(1))
+    }
+}
+
+/// Link monitoring: every `window_secs` each `[[alarms.rule]]` is checked against the links.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AlarmsConfig {
+    #[serde(default = "default_alarm_window_secs")]
+    pub window_secs: f64,
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<AlarmRuleConfig>,
+}
+
+fn default_alarm_window_secs() -> f64 {
+    1.0
+}
+
+impl Default for AlarmsConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: default_alarm_window_secs(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// Alarm raised while `metric` of a link is above `threshold`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AlarmRuleConfig {
+    pub metric: String, // "loss_percent", "utilization_percent" or "queue_depth"
+    pub threshold: f64,
+    #[serde(default = "default_alarm_severity")]
+    pub severity: String, // "warning", "minor", "major" or "critical"
+    #[serde(default)]
+    pub link: Option<String>, // link name as in topology.links; every link if unset
+}
+
+fn default_alarm_severity() -> String {
+    "warning".to_string()
+}
+
+/// Reassembly of fragments at the egress edges: datagrams not complete within `timeout_ms` are
+/// dropped, as are fragments that would hold more than `max_buffer_bytes` of data at once.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReassemblyConfig {
+    #[serde(default = "default_reassembly_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_reassembly_max_buffer_bytes")]
+    pub max_buffer_bytes: usize,
+}
+
+fn default_reassembly_timeout_ms() -> u64 {
+    30_000
+}
+fn default_reassembly_max_buffer_bytes() -> usize {
+    1 << 20
+}
+
+impl Default for ReassemblyConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_reassembly_timeout_ms(),
+            max_buffer_bytes: default_reassembly_max_buffer_bytes(),
+        }
+    }
+}
+
+/// Checksum verification of packets entering the fabric: the IPv4 header checksum and, with
+/// `transport`, TCP/UDP checksums. Bad packets are counted per router and dropped unless `drop`
+/// is false.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ChecksumConfig {
+    #[serde(default = "default_checksum_drop")]
+    pub drop: bool,
+    #[serde(default)]
+    pub transport: bool,
+}
+
+fn default_checksum_drop() -> bool {
+    true
+}
+
+impl Default for ChecksumConfig {
+    fn default() -> Self {
+        Self {
+            drop: default_checksum_drop(),
+            transport: false,
+        }
+    }
+}
+
+/// Duplicate removal at the egress edges: a packet equal to one that left within `window_ms`
+/// is dropped; at most `max_entries` packets are remembered.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DedupeConfig {
+    #[serde(default = "default_dedupe_window_ms")]
+    pub window_ms: u64,
+    #[serde(default = "default_dedupe_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_dedupe_window_ms() -> u64 {
+    50
+}
+fn default_dedupe_max_entries() -> usize {
+    65536
+}
+
+impl Default for DedupeConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: default_dedupe_window_ms(),
+            max_entries: default_dedupe_max_entries(),
+        }
+    }
+}
+
+/// Last `per_router` dropped packets of every router, written to `pcap` at the end of the run
+/// if set.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DropCaptureConfig {
+    #[serde(default = "default_drop_capture_per_router")]
+    pub per_router: usize,
+    #[serde(default)]
+    pub pcap: Option<String>,
+}
+
+fn default_drop_capture_per_router() -> usize {
+    32
+}
+
+impl Default for DropCaptureConfig {
+    fn default() -> Self {
+        Self {
+            per_router: default_drop_capture_per_router(),
+            pcap: None,
+        }
+    }
+}
+
+/// Output sink of packet files: at most `queue` processed packets wait for the writer, which
+/// writes at `rate_bps` if set; once the queue is full the replay waits for it to drain.
+/// `format` is "hex" (`<packet_file>_out.txt`, one packet per line) or "pcapng"
+/// (`<packet_file>_out.pcapng`, one interface per TUN direction, with timestamps). The writer
+/// buffers its output and flushes it every `flush_ms`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReplayOutputConfig {
+    #[serde(default = "default_replay_output_queue")]
+    pub queue: usize,
+    #[serde(default)]
+    pub rate_bps: Option<u64>, // e.g. a slow disk or collector; unset writes as fast as possible
+    #[serde(default = "default_replay_output_format")]
+    pub format: String,
+    #[serde(default = "default_replay_output_flush_ms")]
+    pub flush_ms: u64,
+}
+
+fn default_replay_output_flush_ms() -> u64 {
+    1000
+}
+
+fn default_replay_output_queue() -> usize {
+    1024
+}
+
+fn default_replay_output_format() -> String {
+    "hex".to_string()
+}
+
+impl Default for ReplayOutputConfig {
+    fn default() -> Self {
+        Self {
+            queue: default_replay_output_queue(),
+            rate_bps: None,
+            format: default_replay_output_format(),
+            flush_ms: default_replay_output_flush_ms(),
+        }
+    }
+}
+
+/// Streaming telemetry: the router and link counters are sampled every `interval_ms` and, with
+/// the `gnmi` feature, streamed to gNMI Subscribe clients connecting to `gnmi_listen`.
+/// `dashboard_listen` serves a live topology page colored by link utilization and loss.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TelemetryConfig {
+    #[serde(default = "default_telemetry_interval_ms")]
+    pub interval_ms: u64,
+    #[serde(default)]
+    pub gnmi_listen: Option<String>, // e.g. "127.0.0.1:57400"
+    #[serde(default)]
+    pub dashboard_listen: Option<String>, // e.g. "127.0.0.1:8080"
+}
+
+fn default_telemetry_interval_ms() -> u64 {
+    1000
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: default_telemetry_interval_ms(),
+            gnmi_listen: None,
+            dashboard_listen: None,
+        }
+    }
+}
+
+/// Packet fields fed into the ECMP hash: the 5-tuple (addresses, protocol and ports) or the
+/// 3-tuple (addresses and protocol), optionally with the DSCP and the IPv6 flow label. With
+/// `flow_label_replaces_ports` an IPv6 packet with a nonzero flow label hashes its addresses and
+/// the label only (RFC 6438), so fragments and ESP packets stay with their flow. `seed` makes every router hash with the same seed instead of one derived from its ID, which
+/// polarizes flows across consecutive ECMP stages; a router's own `ecmp_seed` overrides it.
+/// `bandwidth_weights` weights the next hops by the bandwidth of their links (WCMP), unless a
+/// link sets its own `weight`. `consistent` picks next hops by rendezvous hashing, so when the
+/// routing tables are recomputed only the flows of next hops that went away move, even those
+/// the flow table holds no pin for.
+#[derive(Debug, Deserialize, Clone)]
+pub struct EcmpHashConfig {
+    #[serde(default = "default_ecmp_hash_fields")]
+    pub fields: String, // "5-tuple" or "3-tuple"
+    #[serde(default)]
+    pub include_dscp: bool,
+    #[serde(default)]
+    pub include_flow_label: bool,
+    #[serde(default)]
+    pub flow_label_replaces_ports: bool,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub bandwidth_weights: bool,
+    #[serde(default)]
+    pub consistent: bool,
+}
+
+fn default_ecmp_hash_fields() -> String {
+    "5-tuple".to_string()
+}
+
+impl Default for EcmpHashConfig {
+    fn default() -> Self {
+        Self {
+            fields: default_ecmp_hash_fields(),
+            include_dscp: false,
+            include_flow_label: false,
+            flow_label_replaces_ports: false,
+            seed: None,
+            bandwidth_weights: false,
+            consistent: false,
+        }
+    }
+}
+
+/// Egress edge for packets that entered at `ingress` ("tun_a" or "tun_b") towards `prefix`;
+/// without a matching entry a packet leaves at the opposite edge.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DestinationMapConfig {
+    pub ingress: String,
+    pub prefix: String, // IPv4 or IPv6 destination prefix, e.g. "10.0.2.0/24"
+    pub egress: String,
+}
+
+/// Destination `prefix` attached to `router`, where packets to it leave the fabric towards
+/// `edge` ("tun_a" or "tun_b"). A route of a `tenant` is only seen by that tenant's packets.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RouteConfig {
+    pub prefix: String, // IPv4 or IPv6 prefix, e.g. "198.51.100.0/24"
+    pub router: String,
+    pub edge: String,
+    #[serde(default)]
+    pub tenant: Option<String>, // name of a `[[tenant]]`; every packet sees the route if unset
+}
+
+/// Snapshots of the links' effective parameters and admin states and the routers' maintenance
+/// states, written to `dir` every `interval_secs` and at the end of the run.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SnapshotConfig {
+    pub dir: String, // created if missing; files are named snapshot-<ms since the start>.toml
+    #[serde(default = "default_snapshot_interval_secs")]
+    pub interval_secs: f64,
+}
+
+fn default_snapshot_interval_secs() -> f64 {
+    60.0
+}
+
+/// Routers packets matching the capture filter `match` cross, from the one they enter the
+/// fabric at to the one they leave it at, regardless of the routing tables.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ExplicitPathConfig {
+    #[serde(rename = "match", default)]
+    pub filter: String, // e.g. "udp and dst port 4000"; every packet if empty
+    pub hops: Vec<String>, // e.g. ["Rx0y0", "Rx1y0", "Rx1y1"]
+}
+
+/// A tenant (VRF) sharing the fabric: its address space and the VLANs its packets may arrive
+/// tagged with.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TenantConfig {
+    pub name: String,
+    #[serde(default)]
+    pub prefixes: Vec<String>, // e.g. ["10.0.0.0/24", "10.0.1.0/24"]
+    #[serde(default)]
+    pub vlans: Vec<u16>,
+}
+
+/// Topology change during the run: `action` ("link_down" or "link_up") on `link`, or ("drain"
+/// or "undrain") on `router`, either `at_secs` after the start or once `after_packets` mock
+/// packets have been processed.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct EventConfig {
+    #[serde(default)]
+    pub at_secs: Option<f64>,
+    #[serde(default)]
+    pub after_packets: Option<u64>, // packets read from the packet file(s), counted over all files
+    pub action: String,
+    #[serde(default)]
+    pub link: String, // link name as in topology.links, e.g. "Rx0y0_Rx0y1"
+    #[serde(default)]
+    pub router: String, // router to drain or undrain
+}
+
+/// NAT44 hiding the hosts behind the `inside` edge ("tun_a" or "tun_b") behind
+/// `external_address`; ports (and ICMP identifiers) are allocated from `first_port` up.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Nat44Config {
+    #[serde(default = "default_nat44_inside")]
+    pub inside: String,
+    pub external_address: String, // e.g. "203.0.113.1"
+    #[serde(default = "default_nat44_first_port")]
+    pub first_port: u16,
+}
+
+fn default_nat44_inside() -> String {
+    "tun_a".to_string()
+}
+
+fn default_nat44_first_port() -> u16 {
+    1024
+}
+
+/// Stateful NAT64 at `router`: IPv6 packets towards `prefix` (a /96) leave it as IPv4 from
+/// `pool_address`, with ports (and ICMP identifiers) allocated from `first_port` up.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Nat64Config {
+    pub router: String,
+    #[serde(default = "default_nat64_prefix")]
+    pub prefix: String, // e.g. "64:ff9b::/96"
+    pub pool_address: String, // e.g. "192.0.2.1"
+    #[serde(default = "default_nat44_first_port")]
+    pub first_port: u16,
+}
+
+fn default_nat64_prefix() -> String {
+    "64:ff9b::/96".to_string()
+}
+
+/// `[exit_report]`: the JSON report written to `path` at the end of the run. A raised alarm of
+/// at least `fail_on_alarm` severity fails the run like a violated SLA.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ExitReportConfig {
+    pub path: String,
+    #[serde(default)]
+    pub fail_on_alarm: Option<String>, // "warning", "minor", "major" or "critical"; alarms never fail the run if unset
 }
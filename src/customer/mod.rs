@@ -0,0 +1,300 @@
+// src/customer/mod.rs
+
+//! Virtual‑customer packet builder.
+//!
+//! Each generated packet carries a real UDP or TCP header with a valid checksum (other protocol
+//! numbers get the payload directly after the IP header), optional IPv6 Hop‑by‑Hop and
+//! Destination Options headers, and `size` payload bytes filled from `payload_pattern`. The
+//! pattern depends only on the packet's sequence number in the flow, so the far edge can
+//! rebuild the expected payload and count packets that arrive altered.
+//!
+//! With `template` the customer instead sends a captured packet — given as hex, or taken from a
+//! packet file — re‑stamped for every send: the configured addresses and ports, the sequence
+//! number as IPv4 identification, the TCP sequence number advanced by the payload length, and
+//! fresh checksums. The template's payload is sent as is, so the far edge checks it unchanged.
+
+use crate::config::VirtualCustomerConfig;
+use crate::packet::builder::{PacketBuilder, TCP_ACK, TCP_PSH};
+use crate::packet::{
+    parse, transport_offset, update_ipv4_checksum, update_tcp_checksum, update_udp_checksum,
+    PacketMeta,
+};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+pub const DEFAULT_SRC_PORT: u16 = 49152;
+/// The discard port.
+pub const DEFAULT_DST_PORT: u16 = 9;
+
+const NEXT_HEADER_HOP_BY_HOP: u8 = 0;
+const NEXT_HEADER_DEST_OPTIONS: u8 = 60;
+
+/// Loaded templates by their `template` spec, so packet files are read once.
+static TEMPLATES: Lazy<Mutex<HashMap<String, PacketMeta>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// How payload bytes are filled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    Zero,
+    /// The 64‑bit sequence number, then bytes counting up from it.
+    Sequence,
+    /// The given bytes, repeated.
+    Repeat(Vec<u8>),
+}
+
+impl Pattern {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "zero" => Ok(Pattern::Zero),
+            "sequence" => Ok(Pattern::Sequence),
+            _ => match s.strip_prefix("hex:") {
+                Some(hex_str) => match hex::decode(hex_str) {
+                    Ok(bytes) if !bytes.is_empty() => Ok(Pattern::Repeat(bytes)),
+                    _ => Err(format!("Invalid payload pattern '{}': bad hex bytes", s)),
+                },
+                None => Err(format!(
+                    "Invalid payload pattern '{}': expected zero, sequence or hex:<bytes>",
+                    s
+                )),
+            },
+        }
+    }
+
+    /// Payload of `len` bytes for the packet with sequence number `seq`.
+    pub fn fill(&self, seq: u64, len: usize) -> Vec<u8> {
+        match self {
+            Pattern::Zero => vec![0; len],
+            Pattern::Sequence => seq
+                .to_be_bytes()
+                .into_iter()
+                .chain((0..).map(|i: u64| seq.wrapping_add(i) as u8))
+                .take(len)
+                .collect(),
+            Pattern::Repeat(bytes) => bytes.iter().copied().cycle().take(len).collect(),
+        }
+    }
+}
+
+/// IPv6 extension header kind as named in `ipv6_extension_headers`.
+fn extension_header(name: &str) -> Result<u8, String> {
+    match name {
+        "hop_by_hop" => Ok(NEXT_HEADER_HOP_BY_HOP),
+        "destination_options" => Ok(NEXT_HEADER_DEST_OPTIONS),
+        _ => Err(format!(
+            "Unknown IPv6 extension header '{}': expected hop_by_hop or destination_options",
+            name
+        )),
+    }
+}
+
+/// Load the template packet named by `spec`: `hex:<bytes>`, or `pcap:<file>` for the first
+/// packet of a packet file (pcap, pcapng or hex lines) and `pcap:<file>#<n>` for its `n`th.
+pub fn load_template(spec: &str) -> Result<PacketMeta, String> {
+    let raw = if let Some(hex_str) = spec.strip_prefix("hex:") {
+        hex::decode(hex_str.trim())
+            .map_err(|e| format!("Invalid template '{}': bad hex bytes: {}", spec, e))?
+    } else if let Some(file) = spec.strip_prefix("pcap:") {
+        let (path, index) = match file.rsplit_once('#') {
+            Some((path, n)) => match n.parse::<usize>() {
+                Ok(n) if n > 0 => (path, n),
+                _ => return Err(format!("Invalid template '{}': bad packet number", spec)),
+            },
+            None => (file, 1),
+        };
+        match crate::replay::open(path)?.nth(index - 1) {
+            Some((_, packet)) => packet.map_err(|e| format!("Template '{}': {}", spec, e))?,
+            None => return Err(format!("Template '{}': no packet {}", spec, index)),
+        }
+    } else {
+        return Err(format!(
+            "Invalid template '{}': expected hex:<bytes> or pcap:<file>[#<n>]",
+            spec
+        ));
+    };
+    parse(&raw).map_err(|e| format!("Template '{}': {}", spec, e))
+}
+
+/// The template of `vc`, loaded on first use.
+fn template(vc: &VirtualCustomerConfig) -> Option<PacketMeta> {
+    let spec = vc.template.as_deref()?;
+    let mut templates = TEMPLATES.lock().unwrap();
+    if let Some(packet) = templates.get(spec) {
+        return Some(packet.clone());
+    }
+    let packet = load_template(spec).ok()?;
+    templates.insert(spec.to_string(), packet.clone());
+    Some(packet)
+}
+
+/// Check the packet options of a virtual customer.
+pub fn validate(vc: &VirtualCustomerConfig) -> Result<(), String> {
+    if let Some(ref spec) = vc.template {
+        if vc.size.is_some()
+            || vc.payload_pattern.is_some()
+            || vc.protocol.is_some()
+            || !vc.ipv6_extension_headers.is_empty()
+        {
+            return Err(
+                "virtual_customer: template cannot be combined with protocol, size, \
+                 payload_pattern or ipv6_extension_headers"
+                    .into(),
+            );
+        }
+        let packet = load_template(spec)?;
+        let v6 = matches!(
+            vc.src_ip.as_deref().map(str::parse),
+            Some(Ok(IpAddr::V6(_)))
+        );
+        if packet.src_ip.is_ipv6() != v6 {
+            return Err(format!(
+                "virtual_customer: template '{}' and src_ip are of different address families",
+                spec
+            ));
+        }
+    }
+    if let Some(ref pattern) = vc.payload_pattern {
+        Pattern::parse(pattern)?;
+    }
+    for (i, name) in vc.ipv6_extension_headers.iter().enumerate() {
+        // RFC 8200: the Hop‑by‑Hop header may only come first.
+        if extension_header(name)? == NEXT_HEADER_HOP_BY_HOP && i > 0 {
+            return Err("virtual_customer: hop_by_hop must be the first extension header".into());
+        }
+    }
+    if !vc.ipv6_extension_headers.is_empty()
+        && !matches!(
+            vc.src_ip.as_deref().map(str::parse),
+            Some(Ok(IpAddr::V6(_)))
+        )
+    {
+        return Err("virtual_customer: ipv6_extension_headers need IPv6 addresses".into());
+    }
+    Ok(())
+}
+
+fn pattern(vc: &VirtualCustomerConfig) -> Pattern {
+    vc.payload_pattern
+        .as_deref()
+        .and_then(|p| Pattern::parse(p).ok())
+        .unwrap_or(Pattern::Zero)
+}
+
+/// Length of the transport header placed before the payload.
+fn transport_header_len(protocol: u8) -> usize {
+    match protocol {
+        6 => 20,
+        17 => 8,
+        _ => 0,
+    }
+}
+
+/// Packet number `seq` of the virtual customer, or `None` if its addresses are missing or
+/// invalid.
+pub fn build_packet(vc: &VirtualCustomerConfig, seq: u64) -> Option<PacketMeta> {
+    let src: IpAddr = vc.src_ip.as_deref()?.parse().ok()?;
+    let dst: IpAddr = vc.dst_ip.as_deref()?.parse().ok()?;
+    if vc.template.is_some() {
+        return restamp(&template(vc)?, vc, src, dst, seq);
+    }
+    let protocol = vc.protocol.unwrap_or(6);
+    let payload = pattern(vc).fill(seq, vc.size.unwrap_or(0));
+    let src_port = vc.src_port.unwrap_or(DEFAULT_SRC_PORT);
+    let dst_port = vc.dst_port.unwrap_or(DEFAULT_DST_PORT);
+
+    let mut builder = PacketBuilder::new(src, dst).payload(payload);
+    builder = match protocol {
+        6 => {
+            let tcp_seq = (seq as u32).wrapping_mul(vc.size.unwrap_or(0) as u32);
+            builder
+                .tcp(src_port, dst_port)
+                .tcp_seq(tcp_seq, 0)
+                .tcp_flags(TCP_PSH | TCP_ACK, 0xFFFF)
+        }
+        17 => builder.udp(src_port, dst_port),
+        _ => builder.protocol(protocol),
+    };
+    if src.is_ipv6() {
+        for kind in vc
+            .ipv6_extension_headers
+            .iter()
+            .filter_map(|name| extension_header(name).ok())
+        {
+            builder = builder.extension_header(kind);
+        }
+    }
+    builder.build().ok()
+}
+
+/// Offset of the payload after the UDP or TCP header (with options) of `raw`.
+fn payload_offset(raw: &[u8], protocol: u8) -> Option<usize> {
+    let offset = transport_offset(raw)?;
+    match protocol {
+        6 => Some(offset + (*raw.get(offset + 12)? >> 4) as usize * 4),
+        _ => Some(offset + transport_header_len(protocol)),
+    }
+}
+
+/// `template` re‑stamped as packet number `seq` from `src` to `dst`.
+fn restamp(
+    template: &PacketMeta,
+    vc: &VirtualCustomerConfig,
+    src: IpAddr,
+    dst: IpAddr,
+    seq: u64,
+) -> Option<PacketMeta> {
+    let mut raw = template.raw.to_vec();
+    match (src, dst) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) if raw.first()? >> 4 == 4 => {
+            raw.get_mut(12..16)?.copy_from_slice(&src.octets());
+            raw.get_mut(16..20)?.copy_from_slice(&dst.octets());
+            raw[4..6].copy_from_slice(&(seq as u16).to_be_bytes());
+            update_ipv4_checksum(&mut raw);
+        }
+        (IpAddr::V6(src), IpAddr::V6(dst)) if raw.first()? >> 4 == 6 => {
+            raw.get_mut(8..24)?.copy_from_slice(&src.octets());
+            raw.get_mut(24..40)?.copy_from_slice(&dst.octets());
+        }
+        _ => return None,
+    }
+    if let (6 | 17, Some(offset)) = (template.protocol, transport_offset(&raw)) {
+        let ports = raw.get_mut(offset..offset + 4)?;
+        if let Some(port) = vc.src_port {
+            ports[0..2].copy_from_slice(&port.to_be_bytes());
+        }
+        if let Some(port) = vc.dst_port {
+            ports[2..4].copy_from_slice(&port.to_be_bytes());
+        }
+        if template.protocol == 6 {
+            let len = raw.len() - payload_offset(&raw, 6)?;
+            let field = raw.get_mut(offset + 4..offset + 8)?;
+            let first = u32::from_be_bytes(field.try_into().ok()?);
+            let tcp_seq = first.wrapping_add((seq as u32).wrapping_mul(len as u32));
+            field.copy_from_slice(&tcp_seq.to_be_bytes());
+        }
+    }
+    update_tcp_checksum(&mut raw);
+    update_udp_checksum(&mut raw);
+    parse(&raw).ok()
+}
+
+/// Whether `packet`, as it left the fabric, still carries the payload of packet number `seq`.
+pub fn payload_intact(vc: &VirtualCustomerConfig, seq: u64, packet: &PacketMeta) -> bool {
+    if vc.template.is_some() {
+        let Some(template) = template(vc) else {
+            return false;
+        };
+        let expected =
+            payload_offset(&template.raw, template.protocol).and_then(|o| template.raw.get(o..));
+        let got = payload_offset(&packet.raw, packet.protocol).and_then(|o| packet.raw.get(o..));
+        return expected.is_some() && expected == got;
+    }
+    let len = vc.size.unwrap_or(0);
+    let start = transport_offset(&packet.raw).map(|o| o + transport_header_len(packet.protocol));
+    match start.and_then(|s| packet.raw.get(s..s + len)) {
+        Some(payload) => payload == pattern(vc).fill(seq, len).as_slice(),
+        None => false,
+    }
+}
@@ -0,0 +1,269 @@
+// src/ddos/mod.rs
+
+//! Spoofed‑source flood generator.
+//!
+//! A `[ddos]` section turns the simulator into a test bench for mitigation settings: packets with
+//! random sources from `spoof_prefix` are sent towards random hosts in `target_prefix` from one or
+//! more ingress routers, round‑robin, at a fixed rate. Combined with router CPU limits
+//! (`max_pps`/`cpu_queue`) and router ACLs, the resulting [`DdosReport`] shows how much of the
+//! flood was filtered, shed by overloaded routers or still reached the target edge.
+
+use crate::config::{DdosConfig, SimulatorConfig};
+use crate::icmp::{calculate_icmp_checksum, icmpv6_checksum};
+use crate::packet::{transport_detail, update_ipv4_checksum, PacketMeta};
+use crate::processor::{process_packet, process_packet_multi};
+use crate::routing::{Destination, MultiPathTable, RoutingTable};
+use crate::topology::{Fabric, RouterId};
+use ipnet::IpNet;
+use rand::Rng;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tokio::time::{sleep_until, Duration, Instant};
+use tracing::debug;
+
+/// Flood protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloodKind {
+    Udp,
+    TcpSyn,
+    Icmp,
+}
+
+impl FloodKind {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "udp" => Ok(Self::Udp),
+            "tcp-syn" => Ok(Self::TcpSyn),
+            "icmp" => Ok(Self::Icmp),
+            other => Err(format!("Unknown flood protocol '{}'", other)),
+        }
+    }
+}
+
+/// Outcome of one flood.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DdosReport {
+    pub sent: u64,
+    /// Flood packets that reached the target edge.
+    pub delivered: u64,
+    /// Packets dropped by router ACL deny rules.
+    pub acl_drops: u64,
+    /// Packets shed because a router's CPU queue was full.
+    pub cpu_drops: u64,
+    /// Packets lost on links.
+    pub link_lost: u64,
+    /// Wall time between the first and the last packet sent.
+    pub elapsed_secs: f64,
+}
+
+impl DdosReport {
+    /// Share of the flood that reached the target, in percent.
+    pub fn delivered_percent(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        self.delivered as f64 * 100.0 / self.sent as f64
+    }
+
+    /// Rate the flood was actually sent at. Packets are processed one after another, so a slow
+    /// path (link delays, CPU queueing) can hold this below the configured `rate_pps`.
+    pub fn achieved_pps(&self) -> f64 {
+        if self.sent < 2 || self.elapsed_secs <= 0.0 {
+            return 0.0;
+        }
+        (self.sent - 1) as f64 / self.elapsed_secs
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "sent={}, delivered={} ({:.1}%), acl_drops={}, cpu_drops={}, link_lost={}, elapsed={:.3} s ({:.1} pps)",
+            self.sent,
+            self.delivered,
+            self.delivered_percent(),
+            self.acl_drops,
+            self.cpu_drops,
+            self.link_lost,
+            self.elapsed_secs,
+            self.achieved_pps()
+        )
+    }
+}
+
+/// Random address inside `net`.
+fn random_in(net: &IpNet, rng: &mut impl Rng) -> IpAddr {
+    match net {
+        IpNet::V4(n) => {
+            let host = !u32::from(n.netmask());
+            let base = u32::from(n.network());
+            IpAddr::V4(Ipv4Addr::from(base | (rng.gen::<u32>() & host)))
+        }
+        IpNet::V6(n) => {
+            let host = !u128::from(n.netmask());
+            let base = u128::from(n.network());
+            IpAddr::V6(Ipv6Addr::from(base | (rng.gen::<u128>() & host)))
+        }
+    }
+}
+
+/// Build one flood packet of `size` bytes (at least the headers). Transport checksums are only
+/// filled in for ICMP; UDP and TCP floods carry a zero checksum, as many flood tools do.
+pub fn build_flood_packet(
+    kind: FloodKind,
+    src: IpAddr,
+    dst: IpAddr,
+    src_port: u16,
+    dst_port: u16,
+    size: usize,
+) -> PacketMeta {
+    let ip_len = if src.is_ipv4() { 20 } else { 40 };
+    let (protocol, mut l4) = match (kind, src) {
+        (FloodKind::Udp, _) => {
+            let mut udp = Vec::with_capacity(8);
+            udp.extend_from_slice(&src_port.to_be_bytes());
+            udp.extend_from_slice(&dst_port.to_be_bytes());
+            udp.extend_from_slice(&[0, 0, 0, 0]);
+            (17u8, udp)
+        }
+        (FloodKind::TcpSyn, _) => {
+            let mut tcp = Vec::with_capacity(20);
+            tcp.extend_from_slice(&src_port.to_be_bytes());
+            tcp.extend_from_slice(&dst_port.to_be_bytes());
+            tcp.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xFF, 0xFF, 0, 0, 0, 0]);
+            (6u8, tcp)
+        }
+        (FloodKind::Icmp, IpAddr::V4(_)) => (1u8, vec![8, 0, 0, 0, 0x44, 0x44, 0, 1]),
+        (FloodKind::Icmp, IpAddr::V6(_)) => (58u8, vec![128, 0, 0, 0, 0x44, 0x44, 0, 1]),
+    };
+    l4.resize(size.saturating_sub(ip_len).max(l4.len()), 0);
+    if protocol == 17 {
+        let len = l4.len() as u16;
+        l4[4..6].copy_from_slice(&len.to_be_bytes());
+    }
+    let raw = match (src, dst) {
+        (IpAddr::V4(s), IpAddr::V4(d)) => {
+            if protocol == 1 {
+                let c = calculate_icmp_checksum(&l4);
+                l4[2..4].copy_from_slice(&c.to_be_bytes());
+            }
+            let mut raw = vec![0x45, 0];
+            raw.extend_from_slice(&((20 + l4.len()) as u16).to_be_bytes());
+            raw.extend_from_slice(&[0, 0, 0, 0, 64, protocol, 0, 0]);
+            raw.extend_from_slice(&s.octets());
+            raw.extend_from_slice(&d.octets());
+            update_ipv4_checksum(&mut raw);
+            raw.extend_from_slice(&l4);
+            raw
+        }
+        (IpAddr::V6(s), IpAddr::V6(d)) => {
+            if protocol == 58 {
+                let c = icmpv6_checksum(s, d, &l4);
+                l4[2..4].copy_from_slice(&c.to_be_bytes());
+            }
+            let mut raw = vec![0x60, 0, 0, 0];
+            raw.extend_from_slice(&(l4.len() as u16).to_be_bytes());
+            raw.push(protocol);
+            raw.push(64);
+            raw.extend_from_slice(&s.octets());
+            raw.extend_from_slice(&d.octets());
+            raw.extend_from_slice(&l4);
+            raw
+        }
+        _ => Vec::new(),
+    };
+    let ports = protocol == 6 || protocol == 17;
+    PacketMeta {
+        src_ip: src,
+        dst_ip: dst,
+        src_port: if ports { src_port } else { 0 },
+        dst_port: if ports { dst_port } else { 0 },
+        protocol,
+        ttl: 64,
+        dscp: 0,
+        ecn: 0,
+        transport: transport_detail(&raw),
+        annotation: None,
+        mpls: Vec::new(),
+        vlan: None,
+        raw: raw.into(),
+    }
+}
+
+/// Sum of (acl_drops, cpu_drops, packets_lost) over all routers.
+fn drop_counters(fabric: &Fabric) -> (u64, u64, u64) {
+    fabric.graph.node_weights().fold((0, 0, 0), |acc, r| {
+        (
+            acc.0 + r.stats.acl_drops,
+            acc.1 + r.stats.cpu_drops,
+            acc.2 + r.stats.packets_lost,
+        )
+    })
+}
+
+/// Send the flood described by `ddos` through the fabric.
+pub async fn run_flood(
+    cfg: &SimulatorConfig,
+    ddos: &DdosConfig,
+    fabric: &mut Fabric,
+    routing_tables: &HashMap<RouterId, RoutingTable>,
+    multipath_tables: &HashMap<RouterId, MultiPathTable>,
+) -> Result<DdosReport, String> {
+    let kind = FloodKind::parse(&ddos.protocol)?;
+    let target: IpNet = ddos
+        .target_prefix
+        .parse()
+        .map_err(|_| format!("Invalid ddos.target_prefix '{}'", ddos.target_prefix))?;
+    let spoof: IpNet = ddos
+        .spoof_prefix
+        .parse()
+        .map_err(|_| format!("Invalid ddos.spoof_prefix '{}'", ddos.spoof_prefix))?;
+    let ingresses: Vec<RouterId> = if ddos.ingresses.is_empty() {
+        vec![RouterId(cfg.tun_ingress.tun_a_ingress.clone())]
+    } else {
+        ddos.ingresses.iter().cloned().map(RouterId).collect()
+    };
+    let destination = crate::traceroute::destination_for(cfg, target.addr());
+    let egress = RouterId(match destination {
+        Destination::TunA => cfg.tun_ingress.tun_a_ingress.clone(),
+        Destination::TunB => cfg.tun_ingress.tun_b_ingress.clone(),
+    });
+    let count = (ddos.rate_pps * ddos.duration_secs).round() as u64;
+    let (acl_before, cpu_before, lost_before) = drop_counters(fabric);
+    let mut report = DdosReport::default();
+    let start = Instant::now();
+    for i in 0..count {
+        // The schedule is absolute, so a slow path delays later packets but not the rate.
+        sleep_until(start + Duration::from_secs_f64(i as f64 / ddos.rate_pps)).await;
+        report.elapsed_secs = start.elapsed().as_secs_f64();
+        let (src, dst, src_port) = crate::simulation::with_rng(|rng| {
+            (
+                random_in(&spoof, rng),
+                random_in(&target, rng),
+                rng.gen_range(1024..=u16::MAX),
+            )
+        });
+        let packet = build_flood_packet(kind, src, dst, src_port, ddos.dst_port, ddos.packet_size);
+        let ingress = ingresses[i as usize % ingresses.len()].clone();
+        let delivered_before = fabric
+            .get_router(&egress)
+            .map(|r| r.stats.packets_delivered)
+            .unwrap_or(0);
+        if cfg.enable_multipath {
+            process_packet_multi(fabric, multipath_tables, ingress, packet, destination).await;
+        } else {
+            process_packet(fabric, routing_tables, ingress, packet, destination).await;
+        }
+        report.sent += 1;
+        if fabric
+            .get_router(&egress)
+            .is_some_and(|r| r.stats.packets_delivered > delivered_before)
+        {
+            report.delivered += 1;
+        }
+    }
+    let (acl_after, cpu_after, lost_after) = drop_counters(fabric);
+    report.acl_drops = acl_after - acl_before;
+    report.cpu_drops = cpu_after - cpu_before;
+    report.link_lost = lost_after - lost_before;
+    debug!("Flood finished: {}", report.summary());
+    Ok(report)
+}
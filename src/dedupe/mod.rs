@@ -0,0 +1,116 @@
+// src/dedupe/mod.rs
+
+//! Duplicate removal at the egress edges.
+//!
+//! Some receivers cannot cope with the same packet arriving twice. With `[dedupe]` every packet
+//! leaving the fabric (after reassembly) is hashed, and a packet whose hash was already seen
+//! within the last `window_ms` is dropped and counted. The hash covers the whole packet except
+//! the fields routers change on the way — the IPv4 TTL and header checksum, the IPv6 hop limit
+//! — so copies that took different paths still match. A coloring stamp left in place
+//! (`[marking] strip = false`) is hashed like any other field and tells copies of one packet
+//! apart from identical packets sent separately. At most `max_entries` hashes are
+//! remembered; beyond that the oldest are forgotten early.
+
+use crate::config::DedupeConfig;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hasher;
+use tokio::time::{Duration, Instant};
+use tracing::debug;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DedupeStats {
+    /// Packets offered.
+    pub inspected: u64,
+    /// Packets dropped as duplicates.
+    pub duplicates: u64,
+}
+
+impl DedupeStats {
+    pub fn summary(&self) -> String {
+        format!(
+            "inspected={}, duplicates={}",
+            self.inspected, self.duplicates
+        )
+    }
+}
+
+/// Hash of `raw` without the fields that change hop by hop.
+fn key(raw: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // Byte ranges left out, in order: the IPv4 TTL and header checksum, the IPv6 hop limit.
+    let skip: &[(usize, usize)] = match raw.first().map(|b| b >> 4) {
+        Some(4) if raw.len() >= 20 => &[(8, 9), (10, 12)],
+        Some(6) if raw.len() >= 40 => &[(7, 8)],
+        _ => &[],
+    };
+    let mut at = 0;
+    for &(start, end) in skip {
+        hasher.write(&raw[at..start]);
+        at = end;
+    }
+    hasher.write(&raw[at..]);
+    hasher.finish()
+}
+
+#[derive(Debug)]
+pub struct Deduplicator {
+    window: Duration,
+    max_entries: usize,
+    seen: HashSet<u64>,
+    /// Hashes in `seen` with the time they were first seen, oldest first.
+    order: VecDeque<(Instant, u64)>,
+    pub stats: DedupeStats,
+}
+
+impl Deduplicator {
+    pub fn new(cfg: &DedupeConfig) -> Self {
+        Self {
+            window: Duration::from_millis(cfg.window_ms),
+            max_entries: cfg.max_entries,
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            stats: DedupeStats::default(),
+        }
+    }
+
+    /// Offer a packet leaving the fabric at `now`. Returns `false` if it duplicates a packet
+    /// that left within the window and must be dropped.
+    pub fn offer(&mut self, raw: &[u8], now: Instant) -> bool {
+        while let Some(&(first, key)) = self.order.front() {
+            if first + self.window > now {
+                break;
+            }
+            self.order.pop_front();
+            self.seen.remove(&key);
+        }
+        self.stats.inspected += 1;
+        let key = key(raw);
+        if self.seen.contains(&key) {
+            debug!("Dedupe: dropping duplicate packet of {} bytes", raw.len());
+            self.stats.duplicates += 1;
+            return false;
+        }
+        if self.order.len() >= self.max_entries {
+            if let Some((_, oldest)) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.seen.insert(key);
+        self.order.push_back((now, key));
+        true
+    }
+
+    /// Hashes currently remembered.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    pub fn reset(&mut self) {
+        self.stats = DedupeStats::default();
+    }
+}
@@ -0,0 +1,308 @@
+// src/dhcp/mod.rs
+
+//! Minimal DHCPv4 server for TAP edges.
+//!
+//! The server hands out addresses from the edge prefix (`tun_a_prefix` / `tun_b_prefix`) and
+//! announces the simulated router's edge address as default gateway. DISCOVER, REQUEST and
+//! RELEASE are handled. An offer reserves its address for a short while; only a REQUEST turns
+//! it into a lease, which expires after `lease_secs` unless renewed.
+
+use crate::packet::{update_ipv4_checksum, PacketMeta};
+use ipnet::Ipv4Net;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+pub const DHCP_SERVER_PORT: u16 = 67;
+pub const DHCP_CLIENT_PORT: u16 = 68;
+
+const BOOTP_FIXED_LEN: usize = 236;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+
+// DHCP message types (option 53).
+const DHCPDISCOVER: u8 = 1;
+const DHCPOFFER: u8 = 2;
+const DHCPREQUEST: u8 = 3;
+const DHCPACK: u8 = 5;
+const DHCPNAK: u8 = 6;
+const DHCPRELEASE: u8 = 7;
+
+/// How long an offered address stays reserved for a client that has not requested it yet.
+const OFFER_HOLD: Duration = Duration::from_secs(60);
+
+/// Fields of a client message the server cares about.
+#[derive(Debug, Clone)]
+pub struct DhcpRequest {
+    pub xid: u32,
+    pub flags: u16,
+    /// Client address of a renewing client, unspecified otherwise.
+    pub ciaddr: Ipv4Addr,
+    pub chaddr: [u8; 16],
+    pub message_type: u8,
+    /// Option 50 (requested IP address), if present.
+    pub requested_ip: Option<Ipv4Addr>,
+}
+
+/// Parse the BOOTP/DHCP payload of a UDP datagram sent to the server port.
+pub fn parse_request(payload: &[u8]) -> Option<DhcpRequest> {
+    if payload.len() < BOOTP_FIXED_LEN + 4 || payload[0] != 1 {
+        return None;
+    }
+    if payload[BOOTP_FIXED_LEN..BOOTP_FIXED_LEN + 4] != MAGIC_COOKIE {
+        return None;
+    }
+    let xid = u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]);
+    let flags = u16::from_be_bytes([payload[10], payload[11]]);
+    let ciaddr = Ipv4Addr::new(payload[12], payload[13], payload[14], payload[15]);
+    let mut chaddr = [0u8; 16];
+    chaddr.copy_from_slice(&payload[28..44]);
+    let mut message_type = 0;
+    let mut requested_ip = None;
+    let mut i = BOOTP_FIXED_LEN + 4;
+    while i < payload.len() {
+        let code = payload[i];
+        if code == 255 {
+            break;
+        }
+        if code == 0 {
+            i += 1;
+            continue;
+        }
+        if i + 1 >= payload.len() {
+            break;
+        }
+        let len = payload[i + 1] as usize;
+        let start = i + 2;
+        if start + len > payload.len() {
+            break;
+        }
+        let value = &payload[start..start + len];
+        match code {
+            53 if len == 1 => message_type = value[0],
+            50 if len == 4 => {
+                requested_ip = Some(Ipv4Addr::new(value[0], value[1], value[2], value[3]))
+            }
+            _ => {}
+        }
+        i = start + len;
+    }
+    if message_type == 0 {
+        return None;
+    }
+    Some(DhcpRequest {
+        xid,
+        flags,
+        ciaddr,
+        chaddr,
+        message_type,
+        requested_ip,
+    })
+}
+
+/// An address bound to a client until `expires`.
+#[derive(Debug, Clone, Copy)]
+struct Binding {
+    addr: Ipv4Addr,
+    expires: Instant,
+    /// `false` while the address is only offered.
+    leased: bool,
+}
+
+/// In‑memory DHCP server bound to one edge prefix.
+#[derive(Debug)]
+pub struct DhcpServer {
+    pub network: Ipv4Net,
+    pub gateway: Ipv4Addr,
+    pub lease_secs: u32,
+    bindings: HashMap<[u8; 16], Binding>,
+}
+
+impl DhcpServer {
+    /// Create a server for `prefix` (CIDR notation) announcing `gateway` as router.
+    pub fn new(prefix: &str, gateway: Ipv4Addr, lease_secs: u32) -> Result<Self, String> {
+        let network = prefix
+            .parse::<Ipv4Net>()
+            .map_err(|_| format!("Invalid DHCP edge prefix '{}'", prefix))?;
+        if !network.contains(&gateway) {
+            return Err(format!(
+                "DHCP gateway {} is outside the pool {}",
+                gateway, network
+            ));
+        }
+        Ok(Self {
+            network,
+            gateway,
+            lease_secs,
+            bindings: HashMap::new(),
+        })
+    }
+
+    /// Address currently leased (not just offered) to the given client hardware address.
+    pub fn lease_for(&self, chaddr: &[u8; 16]) -> Option<Ipv4Addr> {
+        self.lease_at(chaddr, Instant::now())
+    }
+
+    pub fn lease_at(&self, chaddr: &[u8; 16], now: Instant) -> Option<Ipv4Addr> {
+        self.bindings
+            .get(chaddr)
+            .filter(|b| b.leased && b.expires > now)
+            .map(|b| b.addr)
+    }
+
+    /// Number of addresses currently offered or leased.
+    pub fn active_bindings(&self, now: Instant) -> usize {
+        self.bindings.values().filter(|b| b.expires > now).count()
+    }
+
+    /// `addr` can be bound to `chaddr`: it is a pool host other than the gateway and no other
+    /// client holds an unexpired binding for it.
+    fn available(&self, addr: Ipv4Addr, chaddr: &[u8; 16], now: Instant) -> bool {
+        addr != self.gateway
+            && addr != self.network.network()
+            && addr != self.network.broadcast()
+            && self.network.contains(&addr)
+            && !self
+                .bindings
+                .iter()
+                .any(|(owner, b)| owner != chaddr && b.addr == addr && b.expires > now)
+    }
+
+    /// Address to offer: the client's current binding, else the first free pool host.
+    fn offer(&mut self, chaddr: &[u8; 16], now: Instant) -> Option<Ipv4Addr> {
+        self.bindings.retain(|_, b| b.expires > now);
+        if let Some(binding) = self.bindings.get_mut(chaddr) {
+            binding.expires = binding.expires.max(now + OFFER_HOLD);
+            return Some(binding.addr);
+        }
+        let addr = self
+            .network
+            .hosts()
+            .find(|a| self.available(*a, chaddr, now))?;
+        self.bindings.insert(
+            *chaddr,
+            Binding {
+                addr,
+                expires: now + OFFER_HOLD,
+                leased: false,
+            },
+        );
+        Some(addr)
+    }
+
+    /// Lease the requested address (or the client's current binding); `None` means NAK.
+    fn lease(
+        &mut self,
+        chaddr: &[u8; 16],
+        requested: Option<Ipv4Addr>,
+        now: Instant,
+    ) -> Option<Ipv4Addr> {
+        let current = self
+            .bindings
+            .get(chaddr)
+            .filter(|b| b.expires > now)
+            .map(|b| b.addr);
+        let addr = requested.or(current)?;
+        if !self.available(addr, chaddr, now) {
+            return None;
+        }
+        self.bindings.insert(
+            *chaddr,
+            Binding {
+                addr,
+                expires: now + Duration::from_secs(self.lease_secs as u64),
+                leased: true,
+            },
+        );
+        Some(addr)
+    }
+
+    /// Handle a packet received on the edge. Returns the raw reply packet if it was a DHCP
+    /// DISCOVER or REQUEST addressed to the server port, `None` otherwise.
+    pub fn handle(&mut self, packet: &PacketMeta) -> Option<Vec<u8>> {
+        self.handle_at(packet, Instant::now())
+    }
+
+    /// [`DhcpServer::handle`] with an explicit current time.
+    pub fn handle_at(&mut self, packet: &PacketMeta, now: Instant) -> Option<Vec<u8>> {
+        if packet.protocol != 17 || packet.dst_port != DHCP_SERVER_PORT {
+            return None;
+        }
+        if !matches!(packet.src_ip, IpAddr::V4(_)) {
+            return None;
+        }
+        let ihl = (packet.raw.first()? & 0x0F) as usize * 4;
+        let request = parse_request(packet.raw.get(ihl + 8..)?)?;
+        let (reply_type, yiaddr) = match request.message_type {
+            DHCPDISCOVER => (DHCPOFFER, self.offer(&request.chaddr, now)?),
+            DHCPREQUEST => {
+                // Option 50 in SELECTING/INIT‑REBOOT, `ciaddr` when renewing.
+                let requested = request
+                    .requested_ip
+                    .or((!request.ciaddr.is_unspecified()).then_some(request.ciaddr));
+                match self.lease(&request.chaddr, requested, now) {
+                    Some(addr) => (DHCPACK, addr),
+                    None => (DHCPNAK, Ipv4Addr::UNSPECIFIED),
+                }
+            }
+            DHCPRELEASE => {
+                debug!("DHCP release from {:02x?}", &request.chaddr[..6]);
+                self.bindings.remove(&request.chaddr);
+                return None;
+            }
+            _ => return None,
+        };
+        debug!(
+            "DHCP message type {} from {:02x?}, replying with type {} ({})",
+            request.message_type,
+            &request.chaddr[..6],
+            reply_type,
+            yiaddr
+        );
+        Some(self.build_reply(&request, reply_type, yiaddr))
+    }
+
+    fn build_reply(&self, request: &DhcpRequest, message_type: u8, yiaddr: Ipv4Addr) -> Vec<u8> {
+        let mut bootp = vec![0u8; BOOTP_FIXED_LEN];
+        bootp[0] = 2; // BOOTREPLY
+        bootp[1] = 1; // Ethernet
+        bootp[2] = 6; // hardware address length
+        bootp[4..8].copy_from_slice(&request.xid.to_be_bytes());
+        bootp[10..12].copy_from_slice(&request.flags.to_be_bytes());
+        bootp[16..20].copy_from_slice(&yiaddr.octets());
+        bootp[20..24].copy_from_slice(&self.gateway.octets());
+        bootp[28..44].copy_from_slice(&request.chaddr);
+        bootp.extend_from_slice(&MAGIC_COOKIE);
+        bootp.extend_from_slice(&[53, 1, message_type]);
+        bootp.extend_from_slice(&[54, 4]);
+        bootp.extend_from_slice(&self.gateway.octets());
+        if message_type != DHCPNAK {
+            bootp.extend_from_slice(&[51, 4]);
+            bootp.extend_from_slice(&self.lease_secs.to_be_bytes());
+            bootp.extend_from_slice(&[1, 4]);
+            bootp.extend_from_slice(&self.network.netmask().octets());
+            bootp.extend_from_slice(&[3, 4]);
+            bootp.extend_from_slice(&self.gateway.octets());
+        }
+        bootp.push(255);
+        // IPv4 + UDP, broadcast to the client port.
+        let udp_len = 8 + bootp.len();
+        let total_len = 20 + udp_len;
+        let mut raw = Vec::with_capacity(total_len);
+        raw.extend_from_slice(&[0x45, 0x00]);
+        raw.extend_from_slice(&(total_len as u16).to_be_bytes());
+        raw.extend_from_slice(&[0, 0, 0, 0]);
+        raw.push(64);
+        raw.push(17);
+        raw.extend_from_slice(&[0, 0]);
+        raw.extend_from_slice(&self.gateway.octets());
+        raw.extend_from_slice(&Ipv4Addr::BROADCAST.octets());
+        update_ipv4_checksum(&mut raw);
+        raw.extend_from_slice(&DHCP_SERVER_PORT.to_be_bytes());
+        raw.extend_from_slice(&DHCP_CLIENT_PORT.to_be_bytes());
+        raw.extend_from_slice(&(udp_len as u16).to_be_bytes());
+        raw.extend_from_slice(&[0, 0]); // UDP checksum (optional for IPv4)
+        raw.extend_from_slice(&bootp);
+        raw
+    }
+}
@@ -0,0 +1,303 @@
+// src/dns/mod.rs
+
+//! DNS interception with simulated resolution latency.
+//!
+//! When enabled, UDP queries to port 53 cross the fabric like any other packet, but instead of
+//! leaving it at the far edge they are answered from a local zone file. Each answer is held
+//! back for the configured resolution time in [`PendingReplies`] and then sent back across the
+//! fabric, so application tests see realistic lookup latency without external DNS
+//! infrastructure and without pausing other traffic while a lookup is "in progress".
+
+use crate::config::DnsConfig;
+use crate::packet::{
+    transport_detail, transport_offset, update_ipv4_checksum, update_udp_checksum, PacketMeta,
+};
+use crate::routing::Destination;
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::time::{Duration, Instant};
+use tracing::debug;
+
+pub const DNS_PORT: u16 = 53;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const RCODE_NXDOMAIN: u16 = 3;
+
+/// A parsed DNS question (only the first question of a query is used).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsQuestion {
+    pub id: u16,
+    pub flags: u16,
+    pub name: String,
+    pub qtype: u16,
+    pub qclass: u16,
+    /// Raw bytes of the question section, copied into the response.
+    pub raw_question: Vec<u8>,
+}
+
+/// Parse a DNS query message (UDP payload).
+pub fn parse_query(msg: &[u8]) -> Option<DnsQuestion> {
+    if msg.len() < 12 {
+        return None;
+    }
+    let id = u16::from_be_bytes([msg[0], msg[1]]);
+    let flags = u16::from_be_bytes([msg[2], msg[3]]);
+    // Must be a query (QR = 0) with at least one question.
+    if flags & 0x8000 != 0 || u16::from_be_bytes([msg[4], msg[5]]) == 0 {
+        return None;
+    }
+    let mut labels = Vec::new();
+    let mut i = 12;
+    loop {
+        let len = *msg.get(i)? as usize;
+        i += 1;
+        if len == 0 {
+            break;
+        }
+        // Compression pointers are not expected in a question of a query.
+        if len & 0xC0 != 0 {
+            return None;
+        }
+        let label = msg.get(i..i + len)?;
+        labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+        i += len;
+    }
+    let tail = msg.get(i..i + 4)?;
+    Some(DnsQuestion {
+        id,
+        flags,
+        name: labels.join("."),
+        qtype: u16::from_be_bytes([tail[0], tail[1]]),
+        qclass: u16::from_be_bytes([tail[2], tail[3]]),
+        raw_question: msg[12..i + 4].to_vec(),
+    })
+}
+
+/// Load a zone file. Each non‑comment line holds a name, optional TTL and class (in either
+/// order), a record type (`A` or `AAAA`) and the address, e.g. `www.example.com A 192.0.2.10`
+/// or `www.example.com. 300 IN AAAA 2001:db8::10`.
+pub fn load_zone(contents: &str) -> Result<HashMap<String, Vec<IpAddr>>, String> {
+    let mut zone: HashMap<String, Vec<IpAddr>> = HashMap::new();
+    for (idx, raw_line) in contents.lines().enumerate() {
+        let line = raw_line.split(';').next().unwrap_or("").trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || format!("Invalid zone line {}: '{}'", idx + 1, line);
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        // Fields by position: owner name, then up to two of TTL/class, then type and address.
+        let mut type_pos = 1;
+        while type_pos < 3
+            && tokens.get(type_pos).is_some_and(|t| {
+                t.parse::<u32>().is_ok()
+                    || ["IN", "CH", "HS", "CS"]
+                        .iter()
+                        .any(|c| t.eq_ignore_ascii_case(c))
+            })
+        {
+            type_pos += 1;
+        }
+        if tokens.len() != type_pos + 2 {
+            return Err(invalid());
+        }
+        let is_aaaa = match tokens[type_pos] {
+            t if t.eq_ignore_ascii_case("A") => false,
+            t if t.eq_ignore_ascii_case("AAAA") => true,
+            _ => return Err(invalid()),
+        };
+        let name = tokens[0].trim_end_matches('.').to_ascii_lowercase();
+        let addr: IpAddr = tokens[type_pos + 1]
+            .parse()
+            .map_err(|_| format!("Invalid address on zone line {}: '{}'", idx + 1, line))?;
+        if is_aaaa != addr.is_ipv6() {
+            return Err(format!(
+                "Record type does not match address on zone line {}: '{}'",
+                idx + 1,
+                line
+            ));
+        }
+        zone.entry(name).or_default().push(addr);
+    }
+    Ok(zone)
+}
+
+/// Answers intercepted DNS queries from a static zone.
+#[derive(Debug, Clone)]
+pub struct DnsInterceptor {
+    pub zone: HashMap<String, Vec<IpAddr>>,
+    pub delay_ms: u64,
+    pub ttl: u32,
+}
+
+impl DnsInterceptor {
+    /// Build an interceptor from the `[dns]` configuration, reading the zone file from disk.
+    pub fn from_config(cfg: &DnsConfig) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(&cfg.zone_file)
+            .map_err(|e| format!("Failed to read zone file {}: {}", cfg.zone_file, e))?;
+        Ok(Self {
+            zone: load_zone(&contents)?,
+            delay_ms: cfg.delay_ms,
+            ttl: cfg.ttl,
+        })
+    }
+
+    /// UDP packet to the DNS port, i.e. a candidate for interception.
+    pub fn is_query(packet: &PacketMeta) -> bool {
+        packet.protocol == 17 && packet.dst_port == DNS_PORT
+    }
+
+    /// If `packet` is a DNS query, return the response packet addressed back to the querier.
+    /// Returns `None` for any other packet.
+    pub fn respond(&self, packet: &PacketMeta) -> Option<PacketMeta> {
+        if !Self::is_query(packet) {
+            return None;
+        }
+        let payload_offset = transport_offset(&packet.raw)? + 8;
+        let question = parse_query(packet.raw.get(payload_offset..)?)?;
+        debug!(
+            "Intercepted DNS query for {} (type {})",
+            question.name, question.qtype
+        );
+        Some(build_udp_reply(packet, &self.answer(&question)))
+    }
+
+    /// Build the DNS response message for a question.
+    pub fn answer(&self, q: &DnsQuestion) -> Vec<u8> {
+        let records = self.zone.get(&q.name);
+        let answers: Vec<&IpAddr> = records
+            .map(|r| {
+                r.iter()
+                    .filter(|a| match q.qtype {
+                        TYPE_A => a.is_ipv4(),
+                        TYPE_AAAA => a.is_ipv6(),
+                        _ => false,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let rcode = if records.is_none() { RCODE_NXDOMAIN } else { 0 };
+        // QR, copied opcode and RD, AA and RA set.
+        let flags = 0x8000 | (q.flags & 0x7900) | 0x0400 | 0x0080 | rcode;
+        let mut msg = Vec::with_capacity(12 + q.raw_question.len() + answers.len() * 28);
+        msg.extend_from_slice(&q.id.to_be_bytes());
+        msg.extend_from_slice(&flags.to_be_bytes());
+        msg.extend_from_slice(&1u16.to_be_bytes());
+        msg.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+        msg.extend_from_slice(&[0, 0, 0, 0]); // NSCOUNT, ARCOUNT
+        msg.extend_from_slice(&q.raw_question);
+        for addr in answers {
+            msg.extend_from_slice(&[0xC0, 0x0C]); // pointer to the question name
+            let (rtype, rdata) = match addr {
+                IpAddr::V4(a) => (TYPE_A, a.octets().to_vec()),
+                IpAddr::V6(a) => (TYPE_AAAA, a.octets().to_vec()),
+            };
+            msg.extend_from_slice(&rtype.to_be_bytes());
+            msg.extend_from_slice(&q.qclass.to_be_bytes());
+            msg.extend_from_slice(&self.ttl.to_be_bytes());
+            msg.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            msg.extend_from_slice(&rdata);
+        }
+        msg
+    }
+}
+
+/// Replies held back for the simulated resolution delay, oldest first.
+#[derive(Debug)]
+pub struct PendingReplies {
+    delay: Duration,
+    queue: VecDeque<(Instant, PacketMeta, Destination)>,
+}
+
+impl PendingReplies {
+    pub fn new(delay_ms: u64) -> Self {
+        Self {
+            delay: Duration::from_millis(delay_ms),
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Queue `reply`, to be sent towards `destination` once the delay has passed.
+    pub fn push(&mut self, reply: PacketMeta, destination: Destination) {
+        // The delay is constant, so deadlines are queued in order.
+        self.queue
+            .push_back((Instant::now() + self.delay, reply, destination));
+    }
+
+    /// When the oldest reply becomes due.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.queue.front().map(|(deadline, _, _)| *deadline)
+    }
+
+    /// Take the oldest reply if it is due at `now`.
+    pub fn pop_due(&mut self, now: Instant) -> Option<(PacketMeta, Destination)> {
+        if self.next_deadline()? > now {
+            return None;
+        }
+        self.queue
+            .pop_front()
+            .map(|(_, reply, destination)| (reply, destination))
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// Wrap a DNS message in a UDP/IP packet travelling back to the sender of `query`.
+fn build_udp_reply(query: &PacketMeta, message: &[u8]) -> PacketMeta {
+    let udp_len = 8 + message.len();
+    let mut udp = Vec::with_capacity(udp_len);
+    udp.extend_from_slice(&query.dst_port.to_be_bytes());
+    udp.extend_from_slice(&query.src_port.to_be_bytes());
+    udp.extend_from_slice(&(udp_len as u16).to_be_bytes());
+    udp.extend_from_slice(&[0, 0]);
+    udp.extend_from_slice(message);
+    let mut raw = match (query.dst_ip, query.src_ip) {
+        (IpAddr::V6(src), IpAddr::V6(dst)) => {
+            let mut raw = vec![0x60, 0, 0, 0];
+            raw.extend_from_slice(&(udp_len as u16).to_be_bytes());
+            raw.push(17);
+            raw.push(64);
+            raw.extend_from_slice(&src.octets());
+            raw.extend_from_slice(&dst.octets());
+            raw.extend_from_slice(&udp);
+            raw
+        }
+        (src, dst) => {
+            let to_v4 = |a: IpAddr| match a {
+                IpAddr::V4(v4) => v4,
+                _ => Ipv4Addr::UNSPECIFIED,
+            };
+            let total_len = 20 + udp_len;
+            let mut raw = vec![0x45, 0];
+            raw.extend_from_slice(&(total_len as u16).to_be_bytes());
+            raw.extend_from_slice(&[0, 0, 0, 0, 64, 17, 0, 0]);
+            raw.extend_from_slice(&to_v4(src).octets());
+            raw.extend_from_slice(&to_v4(dst).octets());
+            update_ipv4_checksum(&mut raw);
+            raw.extend_from_slice(&udp);
+            raw
+        }
+    };
+    update_udp_checksum(&mut raw);
+    PacketMeta {
+        src_ip: query.dst_ip,
+        dst_ip: query.src_ip,
+        src_port: query.dst_port,
+        dst_port: query.src_port,
+        protocol: 17,
+        ttl: 64,
+        dscp: 0,
+        ecn: 0,
+        transport: transport_detail(&raw),
+        annotation: None,
+        mpls: Vec::new(),
+        vlan: None,
+        raw: raw.into(),
+    }
+}
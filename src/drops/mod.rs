@@ -0,0 +1,235 @@
+// src/drops/mod.rs
+
+//! Capture of dropped packets.
+//!
+//! Router counters say how many packets were dropped, but not which ones. With `[drop_capture]`
+//! every router keeps its last `per_router` dropped packets in a ring, with the drop reason and
+//! the raw bytes, so the offending packets can be inspected after the fact — from the fabric
+//! (`--stats` lists them) or, with `pcap` set, in a classic raw‑IP pcap file written when the run
+//! ends.
+
+use crate::config::DropCaptureConfig;
+use crate::packet::PacketMeta;
+use crate::provenance::RunMetadata;
+use crate::topology::RouterId;
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info};
+
+const PCAP_MAGIC_USEC: u32 = 0xa1b2_c3d4;
+const LINKTYPE_RAW: u32 = 101;
+const PCAP_SNAPLEN: u32 = 65535;
+
+/// Why a packet was dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DropReason {
+    /// More than the processor's hop limit; most likely a forwarding loop.
+    HopLimit,
+    /// The router's CPU queue was full.
+    CpuOverload,
+    /// Denied by the router's ACL.
+    AclDenied,
+    /// Dropped by an ingress policer.
+    Policed,
+    /// Entered the fabric with a bad checksum.
+    BadChecksum,
+    /// TTL or Hop Limit expired (an ICMP Time Exceeded was sent back).
+    TtlExpired,
+    /// No routing table at the router (an ICMP Destination Unreachable was sent back).
+    NoRoute,
+    /// No egress link towards the next hop.
+    NoEgressLink,
+    /// Larger than the link MTU and not fragmentable (an ICMP Packet Too Big was sent back).
+    MtuExceeded,
+    /// Lost on the link by its loss model.
+    LinkLoss,
+    /// The link's egress queue was full.
+    QueueFull,
+    /// The link was down or still coming up.
+    LinkDown,
+    /// Tagged with a VLAN the link does not carry.
+    VlanFiltered,
+    /// `--paranoid`: the next hop is not closer to the destination than the router.
+    RoutingMismatch,
+    /// Could not be translated by the router's NAT64.
+    Nat64,
+    /// Addressed into another tenant's address space.
+    TenantLeak,
+}
+
+impl DropReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DropReason::HopLimit => "hop_limit",
+            DropReason::CpuOverload => "cpu_overload",
+            DropReason::AclDenied => "acl_denied",
+            DropReason::Policed => "policed",
+            DropReason::BadChecksum => "bad_checksum",
+            DropReason::TtlExpired => "ttl_expired",
+            DropReason::NoRoute => "no_route",
+            DropReason::NoEgressLink => "no_egress_link",
+            DropReason::MtuExceeded => "mtu_exceeded",
+            DropReason::LinkLoss => "link_loss",
+            DropReason::QueueFull => "queue_full",
+            DropReason::LinkDown => "link_down",
+            DropReason::VlanFiltered => "vlan_filtered",
+            DropReason::RoutingMismatch => "routing_mismatch",
+            DropReason::Nat64 => "nat64",
+            DropReason::TenantLeak => "tenant_leak",
+        }
+    }
+
+    /// Whether the packet was lost on a link (and counted in `packets_lost`) rather than
+    /// dropped by a router.
+    pub fn on_link(&self) -> bool {
+        matches!(
+            self,
+            DropReason::LinkLoss | DropReason::QueueFull | DropReason::LinkDown
+        )
+    }
+}
+
+/// One dropped packet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroppedPacket {
+    /// Wall‑clock time of the drop.
+    pub at: SystemTime,
+    pub reason: DropReason,
+    /// The packet as it was when dropped; shares the buffer of the forwarded packet.
+    pub raw: Bytes,
+}
+
+impl DroppedPacket {
+    pub fn render(&self, router: &RouterId) -> String {
+        let summary = match crate::packet::parse(&self.raw) {
+            Ok(p) => format!("{} -> {} proto {}", p.src_ip, p.dst_ip, p.protocol),
+            Err(_) => "undecodable".to_string(),
+        };
+        format!(
+            "{} {}: {} ({} bytes)",
+            router.0,
+            self.reason.as_str(),
+            summary,
+            self.raw.len()
+        )
+    }
+}
+
+/// The rings of dropped packets, one per router.
+#[derive(Debug, Default)]
+pub struct DropCapture {
+    per_router: usize,
+    pcap: Option<String>,
+    rings: HashMap<RouterId, VecDeque<DroppedPacket>>,
+    /// Drops seen per reason, including those pushed out of the rings.
+    pub counts: BTreeMap<DropReason, u64>,
+}
+
+impl DropCapture {
+    pub fn new(cfg: &DropCaptureConfig) -> Self {
+        Self {
+            per_router: cfg.per_router,
+            pcap: cfg.pcap.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Keep `packet`, dropped at `router` for `reason`, pushing out that router's oldest drop if
+    /// its ring is full.
+    pub fn record(&mut self, router: &RouterId, reason: DropReason, packet: &PacketMeta) {
+        *self.counts.entry(reason).or_default() += 1;
+        let ring = self.rings.entry(router.clone()).or_default();
+        if ring.len() == self.per_router {
+            ring.pop_front();
+        }
+        ring.push_back(DroppedPacket {
+            at: SystemTime::now(),
+            reason,
+            raw: packet.raw.clone(),
+        });
+    }
+
+    /// The drops kept for `router`, oldest first.
+    pub fn recent(&self, router: &RouterId) -> impl Iterator<Item = &DroppedPacket> {
+        self.rings.get(router).into_iter().flatten()
+    }
+
+    /// Every drop kept, with its router, oldest first.
+    pub fn all(&self) -> Vec<(&RouterId, &DroppedPacket)> {
+        let mut all: Vec<_> = self
+            .rings
+            .iter()
+            .flat_map(|(router, ring)| ring.iter().map(move |d| (router, d)))
+            .collect();
+        all.sort_by(|a, b| a.1.at.cmp(&b.1.at).then_with(|| a.0 .0.cmp(&b.0 .0)));
+        all
+    }
+
+    /// Forget the drops (end of the warm‑up phase).
+    pub fn reset(&mut self) {
+        self.rings.clear();
+        self.counts.clear();
+    }
+
+    pub fn summary(&self) -> String {
+        let counts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(reason, n)| format!("{}={}", reason.as_str(), n))
+            .collect();
+        format!(
+            "kept={}, {}",
+            self.rings.values().map(VecDeque::len).sum::<usize>(),
+            if counts.is_empty() {
+                "no drops".to_string()
+            } else {
+                counts.join(", ")
+            }
+        )
+    }
+
+    /// Write every drop kept to `path` as a raw‑IP pcap file.
+    pub fn write_pcap(&self, path: &str) -> Result<usize, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create drop capture '{}': {}", path, e))?;
+        let mut out = BufWriter::new(file);
+        let all = self.all();
+        let mut write = || -> std::io::Result<()> {
+            out.write_all(&PCAP_MAGIC_USEC.to_le_bytes())?;
+            out.write_all(&2u16.to_le_bytes())?;
+            out.write_all(&4u16.to_le_bytes())?;
+            out.write_all(&[0; 8])?;
+            out.write_all(&PCAP_SNAPLEN.to_le_bytes())?;
+            out.write_all(&LINKTYPE_RAW.to_le_bytes())?;
+            for (_, drop) in &all {
+                let since = drop.at.duration_since(UNIX_EPOCH).unwrap_or_default();
+                let len = drop.raw.len() as u32;
+                out.write_all(&(since.as_secs() as u32).to_le_bytes())?;
+                out.write_all(&since.subsec_micros().to_le_bytes())?;
+                out.write_all(&len.to_le_bytes())?;
+                out.write_all(&len.to_le_bytes())?;
+                out.write_all(&drop.raw)?;
+            }
+            out.flush()
+        };
+        write().map_err(|e| format!("Failed to write drop capture '{}': {}", path, e))?;
+        Ok(all.len())
+    }
+
+    /// Write the pcap file at the end of the run, if one is configured, with the sidecar of
+    /// `run`.
+    pub fn finish(&self, run: Option<&RunMetadata>) {
+        if let Some(path) = &self.pcap {
+            match self.write_pcap(path) {
+                Ok(n) => info!("Drop capture: wrote {} dropped packets to {}", n, path),
+                Err(e) => error!("{}", e),
+            }
+            if let Some(Err(e)) = run.map(|run| run.write_sidecar(path)) {
+                error!("{}", e);
+            }
+        }
+    }
+}
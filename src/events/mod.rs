@@ -0,0 +1,259 @@
+// src/events/mod.rs
+
+//! Scheduled topology events.
+//!
+//! `[[event]]` tables fail and recover links and drain routers for maintenance while the
+//! simulation runs. An event fires
+//! `at_secs` after the start or, for packet files (whose packets are read as fast as they can
+//! be processed), once `after_packets` packets have been processed. Whenever an event changed
+//! the topology the caller recomputes the routing tables. A recovered link is only used after
+//! its bring‑up delay, so the schedule is also due when that delay ends.
+//!
+//! Faults can also be injected while the simulation runs, through the [`FaultInjector`] of
+//! [`fault_injector`] (see `run_with_faults`). They are applied like events that are due at
+//! once: between two mock packets, or as soon as they arrive with real TUN devices.
+
+use crate::config::EventConfig;
+use crate::topology::{Fabric, RouterId};
+use std::future::pending;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// What an event does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    LinkDown(RouterId, RouterId),
+    LinkUp(RouterId, RouterId),
+    Drain(RouterId),
+    Undrain(RouterId),
+}
+
+impl Action {
+    /// Parse and check the action of an `[[event]]` table.
+    pub fn from_config(cfg: &EventConfig) -> Result<Self, String> {
+        match (cfg.at_secs, cfg.after_packets) {
+            (Some(secs), None) if secs >= 0.0 && secs.is_finite() => {}
+            (None, Some(_)) => {}
+            (Some(secs), None) => {
+                return Err(format!("Event at_secs must be >= 0, got {}", secs));
+            }
+            _ => {
+                return Err(format!(
+                    "Event '{}' needs exactly one of at_secs and after_packets",
+                    cfg.action
+                ));
+            }
+        }
+        let link = || {
+            cfg.link
+                .split_once('_')
+                .map(|(a, b)| (RouterId(a.to_string()), RouterId(b.to_string())))
+                .ok_or_else(|| format!("Event link '{}' is not of the form A_B", cfg.link))
+        };
+        match cfg.action.as_str() {
+            "link_down" => link().map(|(a, b)| Action::LinkDown(a, b)),
+            "link_up" => link().map(|(a, b)| Action::LinkUp(a, b)),
+            "drain" => Ok(Action::Drain(RouterId(cfg.router.clone()))),
+            "undrain" => Ok(Action::Undrain(RouterId(cfg.router.clone()))),
+            other => Err(format!(
+                "Unknown event action '{}', expected \"link_down\", \"link_up\", \"drain\" or \"undrain\"",
+                other
+            )),
+        }
+    }
+
+    /// Apply the action to `fabric`; returns whether the topology changed.
+    fn apply(&self, fabric: &mut Fabric) -> Result<bool, String> {
+        match self {
+            Action::LinkDown(a, b) => fabric.set_link_up(a, b, false),
+            Action::LinkUp(a, b) => fabric.set_link_up(a, b, true),
+            Action::Drain(router) => fabric.set_maintenance(router, true),
+            Action::Undrain(router) => fabric.set_maintenance(router, false),
+        }
+    }
+}
+
+/// When an event fires.
+#[derive(Debug, Clone, Copy)]
+enum Trigger {
+    At(Instant),
+    AfterPackets(u64),
+}
+
+/// Handle injecting faults into a running simulation; clones inject into the same one.
+#[derive(Debug, Clone)]
+pub struct FaultInjector {
+    tx: mpsc::UnboundedSender<Action>,
+}
+
+/// Faults queued by a [`FaultInjector`], for the simulation to apply.
+#[derive(Debug)]
+pub struct Faults {
+    rx: mpsc::UnboundedReceiver<Action>,
+}
+
+/// A fault injector and the queue its faults go to.
+pub fn fault_injector() -> (FaultInjector, Faults) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (FaultInjector { tx }, Faults { rx })
+}
+
+impl FaultInjector {
+    /// Queue `action`. Returns false if the simulation is over.
+    pub fn inject(&self, action: Action) -> bool {
+        self.tx.send(action).is_ok()
+    }
+
+    pub fn link_down(&self, a: &str, b: &str) -> bool {
+        self.inject(Action::LinkDown(
+            RouterId(a.to_string()),
+            RouterId(b.to_string()),
+        ))
+    }
+
+    pub fn link_up(&self, a: &str, b: &str) -> bool {
+        self.inject(Action::LinkUp(
+            RouterId(a.to_string()),
+            RouterId(b.to_string()),
+        ))
+    }
+
+    pub fn drain(&self, router: &str) -> bool {
+        self.inject(Action::Drain(RouterId(router.to_string())))
+    }
+
+    pub fn undrain(&self, router: &str) -> bool {
+        self.inject(Action::Undrain(RouterId(router.to_string())))
+    }
+}
+
+/// Events that have not fired yet, in configuration order.
+#[derive(Debug)]
+pub struct EventSchedule {
+    pending: Vec<(Trigger, Action)>,
+    /// Mock packets processed so far.
+    packets: u64,
+    /// Ends of bring‑up delays of recovered links.
+    bringups: Vec<Instant>,
+    /// Faults injected at runtime.
+    faults: Option<Faults>,
+}
+
+impl EventSchedule {
+    /// Schedule `events` relative to now. Events were checked by config validation; invalid
+    /// ones are skipped with a warning.
+    pub fn new(events: &[EventConfig]) -> Self {
+        let start = Instant::now();
+        let pending = events
+            .iter()
+            .filter_map(|cfg| match Action::from_config(cfg) {
+                Ok(action) => {
+                    let trigger = match cfg.after_packets {
+                        Some(n) => Trigger::AfterPackets(n),
+                        None => Trigger::At(
+                            start + Duration::from_secs_f64(cfg.at_secs.unwrap_or_default()),
+                        ),
+                    };
+                    Some((trigger, action))
+                }
+                Err(e) => {
+                    warn!("Skipping event: {}", e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            pending,
+            packets: 0,
+            bringups: Vec::new(),
+            faults: None,
+        }
+    }
+
+    /// Also apply the faults injected through `faults`.
+    pub fn with_faults(mut self, faults: Option<Faults>) -> Self {
+        self.faults = faults;
+        self
+    }
+
+    /// Earliest time a timed event or the end of a bring‑up delay is due.
+    pub fn deadline(&self) -> Option<Instant> {
+        let timed = self
+            .pending
+            .iter()
+            .filter_map(|(trigger, _)| match trigger {
+                Trigger::At(at) => Some(*at),
+                Trigger::AfterPackets(_) => None,
+            });
+        timed.chain(self.bringups.iter().copied()).min()
+    }
+
+    /// Count one processed mock packet.
+    pub fn count_packet(&mut self) {
+        self.packets += 1;
+    }
+
+    /// Fire the due events on `fabric`. Returns whether the routing tables need to be
+    /// recomputed: an event changed the topology or a link finished its bring‑up.
+    pub fn apply_due(&mut self, fabric: &mut Fabric) -> bool {
+        let now = Instant::now();
+        let before = self.bringups.len();
+        self.bringups.retain(|&at| at > now);
+        let mut changed = self.bringups.len() != before;
+        let packets = self.packets;
+        let (due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(trigger, _)| match trigger {
+                Trigger::At(at) => *at <= now,
+                Trigger::AfterPackets(n) => *n <= packets,
+            });
+        self.pending = pending;
+        for (_, action) in due {
+            changed |= self.fire(fabric, action);
+        }
+        while let Some(action) = self.faults.as_mut().and_then(|f| f.rx.try_recv().ok()) {
+            changed |= self.fire(fabric, action);
+        }
+        changed
+    }
+
+    /// Wait for the next injected fault; never returns without a [`FaultInjector`] left.
+    pub async fn next_fault(&mut self) -> Action {
+        match self.faults.as_mut() {
+            Some(faults) => match faults.rx.recv().await {
+                Some(action) => action,
+                None => pending().await,
+            },
+            None => pending().await,
+        }
+    }
+
+    /// Apply an injected fault at once. Returns whether the routing tables need to be
+    /// recomputed.
+    pub fn inject(&mut self, fabric: &mut Fabric, action: Action) -> bool {
+        self.fire(fabric, action)
+    }
+
+    fn fire(&mut self, fabric: &mut Fabric, action: Action) -> bool {
+        match action.apply(fabric) {
+            Ok(true) => {
+                info!("Event {:?} applied", action);
+                if let Action::LinkUp(a, b) = &action {
+                    if let Some(at) = fabric.get_link(a, b).and_then(|l| l.up_at) {
+                        self.bringups.push(at);
+                    }
+                }
+                true
+            }
+            Ok(false) => {
+                info!("Event {:?} changed nothing", action);
+                false
+            }
+            Err(e) => {
+                warn!("Event {:?} failed: {}", action, e);
+                false
+            }
+        }
+    }
+}
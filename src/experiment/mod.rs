@@ -0,0 +1,310 @@
+// src/experiment/mod.rs
+
+//! Experiment helpers: run a scenario from a (possibly overridden) configuration and
+//! summarise the outcome, so that several runs can be compared side by side.
+
+use crate::config::SimulatorConfig;
+use crate::sla::FlowMetrics;
+use crate::topology::Fabric;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Parse the right‑hand side of an override as a TOML literal, falling back to a string.
+fn parse_value(raw: &str) -> toml::Value {
+    let raw = raw.trim();
+    match toml::from_str::<toml::Table>(&format!("v = {}", raw)) {
+        Ok(mut t) => t
+            .remove("v")
+            .unwrap_or(toml::Value::String(raw.to_string())),
+        Err(_) => toml::Value::String(raw.to_string()),
+    }
+}
+
+/// Set `dotted.key` in a parsed configuration, creating missing intermediate tables.
+pub fn set_value(root: &mut toml::Value, key: &str, value: toml::Value) -> Result<(), String> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err("Override has an empty key".to_string());
+    }
+    let parts: Vec<&str> = key.split('.').collect();
+    let mut node = root;
+    for part in &parts[..parts.len() - 1] {
+        let table = node
+            .as_table_mut()
+            .ok_or_else(|| format!("Override '{}': '{}' is not a table", key, part))?;
+        node = table
+            .entry(part.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    }
+    node.as_table_mut()
+        .ok_or_else(|| format!("Override '{}': parent is not a table", key))?
+        .insert(parts[parts.len() - 1].to_string(), value);
+    Ok(())
+}
+
+/// Apply one `dotted.key=value` override to a parsed configuration. The value is parsed as a
+/// TOML literal (`20`, `0.5`, `true`, `"text"`); anything else is taken as a plain string.
+/// Missing intermediate tables are created.
+pub fn apply_override(root: &mut toml::Value, assignment: &str) -> Result<(), String> {
+    let (key, raw) = assignment
+        .split_once('=')
+        .ok_or_else(|| format!("Override '{}' is not of the form key=value", assignment))?;
+    set_value(root, key, parse_value(raw)).map_err(|e| format!("{} (in '{}')", e, assignment))
+}
+
+/// Headline metrics of one run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunSummary {
+    pub delivered: u64,
+    pub lost: u64,
+    /// Router‑level loss: lost / (lost + delivered).
+    pub loss_percent: f64,
+    /// Virtual‑customer latency (all customers combined).
+    pub latency_avg_ms: Option<f64>,
+    pub latency_p95_ms: Option<f64>,
+    pub throughput_mbps: Option<f64>,
+    pub twamp_rtt_avg_ms: Option<f64>,
+}
+
+impl RunSummary {
+    pub fn from_fabric(fabric: &Fabric) -> Self {
+        let (mut delivered, mut lost) = (0, 0);
+        for stats in fabric.get_statistics().values() {
+            delivered += stats.packets_delivered;
+            lost += stats.packets_lost;
+        }
+        let loss_percent = if delivered + lost == 0 {
+            0.0
+        } else {
+            lost as f64 * 100.0 / (delivered + lost) as f64
+        };
+        // Merge the flows of all customers into one set of metrics.
+        let mut flows = FlowMetrics::default();
+        for f in fabric.customer_flows.values() {
+            flows.sent += f.sent;
+            flows.delivered += f.delivered;
+            flows.bytes_delivered += f.bytes_delivered;
+            flows.payload_errors += f.payload_errors;
+            flows.latencies_ms.extend_from_slice(&f.latencies_ms);
+            flows.first_sent = match (flows.first_sent, f.first_sent) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            flows.last_delivered = flows.last_delivered.max(f.last_delivered);
+        }
+        let latency_avg_ms = if flows.latencies_ms.is_empty() {
+            None
+        } else {
+            Some(flows.latencies_ms.iter().sum::<f64>() / flows.latencies_ms.len() as f64)
+        };
+        Self {
+            delivered,
+            lost,
+            loss_percent,
+            latency_avg_ms,
+            latency_p95_ms: flows.latency_percentile_ms(95.0),
+            throughput_mbps: (flows.sent > 0).then(|| flows.throughput_mbps()),
+            twamp_rtt_avg_ms: fabric.twamp_report.as_ref().and_then(|r| r.rtt_avg_ms()),
+        }
+    }
+
+    /// Metrics as `(name, value)` pairs in report order.
+    pub fn metrics(&self) -> Vec<(&'static str, Option<f64>)> {
+        vec![
+            ("delivered", Some(self.delivered as f64)),
+            ("lost", Some(self.lost as f64)),
+            ("loss_percent", Some(self.loss_percent)),
+            ("latency_avg_ms", self.latency_avg_ms),
+            ("latency_p95_ms", self.latency_p95_ms),
+            ("throughput_mbps", self.throughput_mbps),
+            ("twamp_rtt_avg_ms", self.twamp_rtt_avg_ms),
+        ]
+    }
+}
+
+/// Deserialize, validate and run a scenario, returning its summary.
+pub async fn run_scenario(
+    value: toml::Value,
+    enable_multipath: bool,
+) -> Result<RunSummary, String> {
+    let mut cfg: SimulatorConfig = value
+        .try_into()
+        .map_err(|e| format!("Invalid configuration: {}", e))?;
+    cfg.enable_multipath = enable_multipath;
+    cfg.validate()?;
+    // Each run draws from its own generator, so both sides of a comparison see the same random
+    // sequence and concurrent sweep runs do not disturb each other.
+    let seed = cfg.simulation.seed;
+    let fabric = crate::simulation::with_run_rng(seed, crate::run(cfg))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(RunSummary::from_fabric(&fabric))
+}
+
+/// Side‑by‑side table of two runs with absolute and relative deltas (B − A).
+pub fn compare_report(label_a: &str, a: &RunSummary, label_b: &str, b: &RunSummary) -> String {
+    let fmt = |v: Option<f64>| v.map_or_else(|| "-".to_string(), |x| format!("{:.3}", x));
+    let mut out = format!(
+        "{:<18} {:>14} {:>14} {:>14} {:>9}\n",
+        "metric", label_a, label_b, "delta", "delta%"
+    );
+    for ((name, va), (_, vb)) in a.metrics().into_iter().zip(b.metrics()) {
+        let (delta, pct) = match (va, vb) {
+            (Some(x), Some(y)) => (
+                Some(y - x),
+                (x != 0.0).then(|| format!("{:+.1}%", (y - x) * 100.0 / x.abs())),
+            ),
+            _ => (None, None),
+        };
+        out.push_str(&format!(
+            "{:<18} {:>14} {:>14} {:>14} {:>9}\n",
+            name,
+            fmt(va),
+            fmt(vb),
+            delta.map_or_else(|| "-".to_string(), |d| format!("{:+.3}", d)),
+            pct.unwrap_or_else(|| "-".to_string())
+        ));
+    }
+    out
+}
+
+/// One swept configuration key and the values it takes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepParam {
+    pub key: String,
+    pub values: Vec<toml::Value>,
+}
+
+/// Parse `key=start:end:step` (inclusive range) or `key=v1,v2,...`.
+pub fn parse_sweep_param(spec: &str) -> Result<SweepParam, String> {
+    let (key, raw) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("Sweep parameter '{}' is not of the form key=values", spec))?;
+    let raw = raw.trim();
+    let range: Vec<&str> = raw.split(':').collect();
+    let values = if range.len() == 3 {
+        let ints: Result<Vec<i64>, _> = range.iter().map(|v| v.trim().parse::<i64>()).collect();
+        if let Ok(r) = ints {
+            if r[2] <= 0 || r[1] < r[0] {
+                return Err(format!("Invalid sweep range '{}'", raw));
+            }
+            (r[0]..=r[1])
+                .step_by(r[2] as usize)
+                .map(toml::Value::Integer)
+                .collect()
+        } else {
+            let r: Vec<f64> = range
+                .iter()
+                .map(|v| v.trim().parse::<f64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("Invalid sweep range '{}'", raw))?;
+            if r[2] <= 0.0 || r[1] < r[0] {
+                return Err(format!("Invalid sweep range '{}'", raw));
+            }
+            let steps = ((r[1] - r[0]) / r[2] + 1e-9).floor() as usize;
+            (0..=steps)
+                .map(|i| toml::Value::Float(r[0] + i as f64 * r[2]))
+                .collect()
+        }
+    } else {
+        raw.split(',').map(parse_value).collect()
+    };
+    Ok(SweepParam {
+        key: key.trim().to_string(),
+        values,
+    })
+}
+
+/// Cartesian product of all parameter values, first parameter varying slowest.
+pub fn combinations(params: &[SweepParam]) -> Vec<Vec<toml::Value>> {
+    params.iter().fold(vec![Vec::new()], |acc, p| {
+        acc.iter()
+            .flat_map(|prefix| {
+                p.values.iter().map(move |v| {
+                    let mut combo = prefix.clone();
+                    combo.push(v.clone());
+                    combo
+                })
+            })
+            .collect()
+    })
+}
+
+/// Run the scenario for every combination, up to `jobs` runs in parallel tasks. Results are
+/// returned in combination order.
+pub async fn run_sweep(
+    base: &toml::Value,
+    params: &[SweepParam],
+    jobs: usize,
+    enable_multipath: bool,
+) -> Result<Vec<(Vec<toml::Value>, RunSummary)>, String> {
+    let slots = Arc::new(Semaphore::new(jobs.max(1)));
+    let mut tasks = Vec::new();
+    for combo in combinations(params) {
+        let mut value = base.clone();
+        for (param, v) in params.iter().zip(&combo) {
+            set_value(&mut value, &param.key, v.clone())?;
+        }
+        let slots = slots.clone();
+        tasks.push(tokio::spawn(async move {
+            let _slot = slots.acquire_owned().await.map_err(|e| e.to_string())?;
+            run_scenario(value, enable_multipath)
+                .await
+                .map(|summary| (combo, summary))
+        }));
+    }
+    let mut results = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        results.push(task.await.map_err(|e| e.to_string())??);
+    }
+    Ok(results)
+}
+
+/// Like [`run_sweep`], but on a dedicated runtime with a paused clock: simulated delays
+/// complete instantly while all measured times stay consistent. Blocks the calling thread.
+/// The paused clock needs tokio's `test-util`, hence the `virtual-time` feature.
+#[cfg(feature = "virtual-time")]
+pub fn run_sweep_virtual_time(
+    base: &toml::Value,
+    params: &[SweepParam],
+    jobs: usize,
+    enable_multipath: bool,
+) -> Result<Vec<(Vec<toml::Value>, RunSummary)>, String> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .map_err(|e| format!("Failed to build virtual-time runtime: {}", e))?;
+    rt.block_on(run_sweep(base, params, jobs, enable_multipath))
+}
+
+/// CSV with one column per swept key followed by the run metrics.
+pub fn sweep_csv(params: &[SweepParam], results: &[(Vec<toml::Value>, RunSummary)]) -> String {
+    let mut header: Vec<String> = params.iter().map(|p| p.key.clone()).collect();
+    header.extend(
+        RunSummary::default()
+            .metrics()
+            .into_iter()
+            .map(|(name, _)| name.to_string()),
+    );
+    let mut out = header.join(",");
+    out.push('\n');
+    for (combo, summary) in results {
+        let mut row: Vec<String> = combo
+            .iter()
+            .map(|v| match v {
+                toml::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect();
+        row.extend(
+            summary
+                .metrics()
+                .into_iter()
+                .map(|(_, v)| v.map_or_else(String::new, |x| format!("{:.3}", x))),
+        );
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,78 @@
+// src/explicit_path/mod.rs
+
+//! Explicit source‑routed paths.
+//!
+//! An `[[explicit_path]]` lists the routers packets matching its capture‑filter `match`
+//! expression cross, from the router they enter the fabric at (the first hop) to the one they
+//! leave it at (the last). Such packets follow the list instead of the routing tables, SRv6
+//! segments and policy‑based routing, so corner paths can be exercised deterministically. The
+//! first path matching a packet and starting at its ingress router wins. ICMP errors the
+//! packet causes on the way are routed normally. [`crate::processor::process_packet_along`]
+//! sends a single packet along a path given by the caller.
+
+use crate::capture::CaptureFilter;
+use crate::config::ExplicitPathConfig;
+use crate::packet::PacketMeta;
+use crate::topology::RouterId;
+
+#[derive(Debug, Clone)]
+pub struct ExplicitPath {
+    pub filter: CaptureFilter,
+    pub hops: Vec<RouterId>,
+    /// Packets that followed this path.
+    pub hits: u64,
+}
+
+impl ExplicitPath {
+    /// Path along `hops` for the packets matching `filter` (every packet if empty).
+    pub fn new(filter: &str, hops: Vec<RouterId>) -> Result<Self, String> {
+        if hops.is_empty() {
+            return Err(format!("Explicit path '{}' has no hops", filter));
+        }
+        Ok(Self {
+            filter: CaptureFilter::parse(filter)?,
+            hops,
+            hits: 0,
+        })
+    }
+
+    pub fn from_config(cfg: &ExplicitPathConfig) -> Result<Self, String> {
+        Self::new(
+            &cfg.filter,
+            cfg.hops.iter().cloned().map(RouterId).collect(),
+        )
+    }
+}
+
+/// Where a packet is on its explicit path.
+#[derive(Debug, Clone)]
+pub struct PathCursor {
+    hops: Vec<RouterId>,
+    at: usize,
+}
+
+impl PathCursor {
+    /// The hop after the packet's current one, moving on to it; `None` at the last hop.
+    pub fn advance(&mut self) -> Option<RouterId> {
+        let next = self.hops.get(self.at + 1)?.clone();
+        self.at += 1;
+        Some(next)
+    }
+}
+
+/// Cursor at the start of the first of `paths` that starts at `ingress` and matches `packet`,
+/// counting the hit.
+pub fn lookup(
+    paths: &mut [ExplicitPath],
+    ingress: &RouterId,
+    packet: &PacketMeta,
+) -> Option<PathCursor> {
+    let path = paths
+        .iter_mut()
+        .find(|p| p.hops[0] == *ingress && p.filter.matches(packet))?;
+    path.hits += 1;
+    Some(PathCursor {
+        hops: path.hops.clone(),
+        at: 0,
+    })
+}
@@ -0,0 +1,129 @@
+// src/forwarding/flow_table.rs
+
+//! Flow-to-next-hop pins for ECMP.
+//!
+//! Load balancing hashes a flow's fields (the 5-tuple by default) modulo the number of equal-cost links, so when the
+//! routing tables change and a next hop comes or goes, most flows move even if their path
+//! was not affected. This reorders their packets while the network reconverges. The flow table
+//! remembers the next hop each flow was given at each router. That next hop is kept as long as it
+//! is still one of the equal-cost next hops, so only flows whose path went away are hashed again.
+//! With `consistent` hashing the flows the table holds no pin for (it is full, or was reset) keep
+//! their next hops too.
+
+use super::{EcmpHash, FlowFields};
+use crate::routing::{Destination, MultiPathTable};
+use crate::topology::RouterId;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Flows pinned at most; once the table is full, new flows are only hashed.
+const MAX_FLOWS: usize = 65536;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FlowKey {
+    router: RouterId,
+    destination: Destination,
+    flow: FlowFields,
+}
+
+impl FlowKey {
+    fn new(router: &RouterId, destination: Destination, flow: &FlowFields) -> Self {
+        Self {
+            router: router.clone(),
+            destination,
+            flow: flow.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Pins {
+    next_hops: HashMap<FlowKey, RouterId>,
+    /// Flows whose pinned next hop was no longer valid and that were hashed again.
+    remapped: u64,
+}
+
+/// Next hop of every flow at every router it was load-balanced at.
+#[derive(Debug, Default)]
+pub struct FlowTable {
+    // Behind a lock so that it can be consulted while links of the fabric are borrowed.
+    pins: Mutex<Pins>,
+}
+
+impl FlowTable {
+    /// Next hop for `flow` at `router` among `candidates`, with their `weights`. The flow stays
+    /// on its pinned next hop if that is still a candidate. Otherwise `hash` picks a new
+    /// candidate, each getting a share of the flows proportional to its weight, and the flow is
+    /// pinned to it.
+    pub fn select(
+        &self,
+        router: &RouterId,
+        destination: Destination,
+        flow: &FlowFields,
+        hash: &EcmpHash,
+        (candidates, weights): (&[RouterId], &[u32]),
+    ) -> Option<usize> {
+        if candidates.is_empty() {
+            return None;
+        }
+        let key = FlowKey::new(router, destination, flow);
+        let mut pins = self.pins.lock().unwrap();
+        if let Some(hop) = pins.next_hops.get(&key) {
+            if let Some(idx) = candidates.iter().position(|c| c == hop) {
+                return Some(idx);
+            }
+            pins.next_hops.remove(&key);
+            pins.remapped += 1;
+        }
+        let idx = hash.index(router, flow, (candidates, weights));
+        if pins.next_hops.len() < MAX_FLOWS {
+            pins.next_hops.insert(key, candidates[idx].clone());
+        }
+        Some(idx)
+    }
+
+    /// Next hop `flow` is pinned to at `router`, if any.
+    pub fn next_hop(
+        &self,
+        router: &RouterId,
+        destination: Destination,
+        flow: &FlowFields,
+    ) -> Option<RouterId> {
+        let key = FlowKey::new(router, destination, flow);
+        self.pins.lock().unwrap().next_hops.get(&key).cloned()
+    }
+
+    /// Check the pins against freshly computed `tables`. A pin whose next hop is no longer one
+    /// of the router's equal-cost next hops is dropped, so that its flow is hashed again. Returns
+    /// the number of flows that were dropped.
+    pub fn revalidate(&self, tables: &HashMap<RouterId, MultiPathTable>) -> usize {
+        let mut pins = self.pins.lock().unwrap();
+        let before = pins.next_hops.len();
+        pins.next_hops.retain(|key, hop| {
+            tables.get(&key.router).is_some_and(|t| {
+                let entries = match key.destination {
+                    Destination::TunA => &t.tun_a,
+                    Destination::TunB => &t.tun_b,
+                };
+                entries.iter().any(|e| e.next_hop == *hop)
+            })
+        });
+        let dropped = before - pins.next_hops.len();
+        pins.remapped += dropped as u64;
+        dropped
+    }
+
+    /// Flows currently pinned.
+    pub fn len(&self) -> usize {
+        self.pins.lock().unwrap().next_hops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Flows moved to another next hop because theirs was no longer valid.
+    pub fn remapped(&self) -> u64 {
+        self.pins.lock().unwrap().remapped
+    }
+}
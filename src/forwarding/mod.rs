@@ -1,12 +1,218 @@
 // src/forwarding/mod.rs
 
+use crate::config::EcmpHashConfig;
 use crate::packet::PacketMeta;
 use crate::topology::{Link, RouterId};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::net::IpAddr;
 use tracing::debug;
 
+pub mod flow_table;
 pub mod multipath;
 
+/// Packet fields fed into the ECMP hash and the seed each router hashes them with; see
+/// [`EcmpHashConfig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcmpHash {
+    pub ports: bool,
+    pub dscp: bool,
+    pub flow_label: bool,
+    /// A nonzero IPv6 flow label is hashed instead of the ports.
+    pub label_replaces_ports: bool,
+    /// Seed of every router without its own; `None` derives each router's seed from its ID.
+    pub seed: Option<u64>,
+    /// Seeds set on individual routers (`ecmp_seed`).
+    pub router_seeds: HashMap<RouterId, u64>,
+    /// Links without a `weight` are weighted by their bandwidth.
+    pub bandwidth_weights: bool,
+    /// Next hops are picked by rendezvous hashing rather than by the hash modulo their weights.
+    pub consistent: bool,
+}
+
+impl Default for EcmpHash {
+    /// The 5-tuple.
+    fn default() -> Self {
+        Self {
+            ports: true,
+            dscp: false,
+            flow_label: false,
+            label_replaces_ports: false,
+            seed: None,
+            router_seeds: HashMap::new(),
+            bandwidth_weights: false,
+            consistent: false,
+        }
+    }
+}
+
+impl EcmpHash {
+    pub fn from_config(cfg: &EcmpHashConfig) -> Self {
+        Self {
+            ports: cfg.fields != "3-tuple",
+            dscp: cfg.include_dscp,
+            flow_label: cfg.include_flow_label || cfg.flow_label_replaces_ports,
+            label_replaces_ports: cfg.flow_label_replaces_ports,
+            seed: cfg.seed,
+            router_seeds: HashMap::new(),
+            bandwidth_weights: cfg.bandwidth_weights,
+            consistent: cfg.consistent,
+        }
+    }
+
+    /// WCMP weight of `link` as a next hop: its `weight`, else its bandwidth in Mbit/s (at
+    /// least 1) with `bandwidth_weights`, else 1.
+    pub fn weight(&self, link: &Link) -> u32 {
+        match (link.cfg.weight, link.cfg.bandwidth_mbps) {
+            (Some(weight), _) => weight,
+            (None, Some(mbps)) if self.bandwidth_weights => mbps.round().max(1.0) as u32,
+            _ => 1,
+        }
+    }
+
+    /// Seed `router` hashes flows with. Routers hashing with different seeds split the same
+    /// flows differently, so the flows one router sent down a link are spread again by the next;
+    /// with one seed everywhere they all take the same branch at every hop (polarization).
+    pub fn seed(&self, router: &RouterId) -> u64 {
+        if let Some(seed) = self.router_seeds.get(router).copied().or(self.seed) {
+            return seed;
+        }
+        let mut hasher = DefaultHasher::new();
+        router.0.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The hashed fields of `packet`; packets with equal fields are one flow.
+    pub fn flow(&self, packet: &PacketMeta) -> FlowFields {
+        let flow_label = packet.flow_label().filter(|_| self.flow_label);
+        // The label is set by the source for the whole flow, so it identifies the flow in every
+        // packet, including fragments past the first and ESP, whose ports are not visible. Like
+        // RFC 6438, only the addresses are hashed with it: the fragments' next header is 44.
+        let labelled = self.label_replaces_ports && flow_label.is_some_and(|l| l != 0);
+        FlowFields {
+            src_ip: packet.src_ip,
+            dst_ip: packet.dst_ip,
+            // Echo requests and replies of one ping share the identifier; it stands in for the
+            // ports, which ICMP has none of.
+            ports: (self.ports && !labelled).then(|| match packet.icmp_echo() {
+                Some(echo) => (echo.id, 0),
+                None => (packet.src_port, packet.dst_port),
+            }),
+            protocol: if labelled { 0 } else { packet.protocol },
+            dscp: self.dscp.then_some(packet.dscp),
+            flow_label,
+        }
+    }
+
+    /// ECMP hash of `packet`.
+    pub fn hash(&self, packet: &PacketMeta) -> u64 {
+        self.flow(packet).hash_value()
+    }
+
+    /// ECMP hash of `packet` at `router`, with the router's seed.
+    pub fn hash_at(&self, router: &RouterId, packet: &PacketMeta) -> u64 {
+        self.flow(packet).seeded_hash(self.seed(router))
+    }
+
+    /// Index of the next hop `flow` takes at `router` among `candidates`, with their
+    /// `weights`: by [`rendezvous_index`] if `consistent`, else by [`weighted_index`].
+    pub fn index(
+        &self,
+        router: &RouterId,
+        flow: &FlowFields,
+        (candidates, weights): (&[RouterId], &[u32]),
+    ) -> usize {
+        let hash = flow.seeded_hash(self.seed(router));
+        if self.consistent {
+            rendezvous_index(hash, candidates, weights)
+        } else {
+            weighted_index(hash, weights)
+        }
+    }
+}
+
+/// Fields of a packet that the ECMP hash covers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FlowFields {
+    pub src_ip: IpAddr,
+    pub dst_ip: IpAddr,
+    pub ports: Option<(u16, u16)>,
+    pub protocol: u8,
+    pub dscp: Option<u8>,
+    pub flow_label: Option<u32>,
+}
+
+impl FlowFields {
+    pub fn hash_value(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.write(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Hash of the fields preceded by `seed`.
+    pub fn seeded_hash(&self, seed: u64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        self.write(&mut hasher);
+        hasher.finish()
+    }
+
+    fn write(&self, hasher: &mut DefaultHasher) {
+        self.src_ip.hash(hasher);
+        self.dst_ip.hash(hasher);
+        if let Some((src_port, dst_port)) = self.ports {
+            src_port.hash(hasher);
+            dst_port.hash(hasher);
+        }
+        self.protocol.hash(hasher);
+        if let Some(dscp) = self.dscp {
+            dscp.hash(hasher);
+        }
+        if let Some(label) = self.flow_label {
+            label.hash(hasher);
+        }
+    }
+}
+
+/// Index of the candidate a flow with `hash` goes to, each candidate getting a share of the
+/// hashes proportional to its weight. With equal weights this is `hash % weights.len()`.
+pub fn weighted_index(hash: u64, weights: &[u32]) -> usize {
+    let total: u64 = weights.iter().map(|&w| w as u64).sum();
+    if total == 0 {
+        return (hash % weights.len().max(1) as u64) as usize;
+    }
+    let mut point = hash % total;
+    for (idx, &weight) in weights.iter().enumerate() {
+        if point < weight as u64 {
+            return idx;
+        }
+        point -= weight as u64;
+    }
+    weights.len() - 1
+}
+
+/// Index of the candidate a flow with `hash` goes to by weighted rendezvous (highest random
+/// weight) hashing: each candidate scores the flow by a hash of the flow and its own ID, and the
+/// best score wins, each candidate winning a share of the flows proportional to its weight. The
+/// choice does not depend on the order of the candidates, so when one goes away only its flows
+/// move, and a new one takes its share from all the others without moving any flow between them.
+pub fn rendezvous_index(hash: u64, candidates: &[RouterId], weights: &[u32]) -> usize {
+    let mut best = (f64::NEG_INFINITY, 0);
+    for (idx, (candidate, &weight)) in candidates.iter().zip(weights).enumerate() {
+        let mut hasher = DefaultHasher::new();
+        hash.hash(&mut hasher);
+        candidate.hash(&mut hasher);
+        // Uniform in (0, 1]; the score's distribution makes the shares follow the weights.
+        let u = ((hasher.finish() >> 11) + 1) as f64 / (1u64 << 53) as f64;
+        let score = weight as f64 / -u.ln();
+        if score > best.0 {
+            best = (score, idx);
+        }
+    }
+    best.1
+}
+
 /// Choose the egress link for a packet based on routing tables and optional load‑balancing.
 /// Returns a reference to a link from the provided slice that leads to the next hop.
 pub fn select_egress_link<'a>(
@@ -15,6 +221,7 @@ pub fn select_egress_link<'a>(
     links: &'a [&Link],
     tables: &HashMap<RouterId, crate::routing::RoutingTable>,
     destination: crate::routing::Destination,
+    hash: &EcmpHash,
 ) -> Option<&'a Link> {
     debug!("Selecting egress link for router {}", router_id.0);
     let routing = tables.get(router_id)?;
@@ -39,23 +246,13 @@ pub fn select_egress_link<'a>(
     }
 
     // Load balancing among links with load_balance enabled.
-    // Issue 104 fix: Use only the 5-tuple hash for consistent flow affinity (no counter).
+    // Issue 104 fix: Use only the flow hash for consistent flow affinity (no counter).
     let lb_links: Vec<&&Link> = candidates.iter().filter(|&&l| l.cfg.load_balance).collect();
     if !lb_links.is_empty() {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        let mut hasher = DefaultHasher::new();
-        // Hash the 5‑tuple
-        packet.src_ip.hash(&mut hasher);
-        packet.dst_ip.hash(&mut hasher);
-        packet.src_port.hash(&mut hasher);
-        packet.dst_port.hash(&mut hasher);
-        packet.protocol.hash(&mut hasher);
-        let hash = hasher.finish();
-        let idx = (hash as usize) % lb_links.len();
+        let idx = (hash.hash_at(router_id, packet) as usize) % lb_links.len();
         let chosen = *lb_links[idx];
         debug!(
-            "Load‑balanced selection of link {:?} for router {} (5-tuple hash)",
+            "Load‑balanced selection of link {:?} for router {} (flow hash)",
             chosen.id, router_id.0
         );
         return Some(chosen);
@@ -1,5 +1,6 @@
 // src/forwarding/multipath.rs
 
+use super::EcmpHash;
 use crate::packet::PacketMeta;
 use crate::routing::{Destination, MultiPathTable};
 use crate::topology::{Link, RouterId};
@@ -8,13 +9,15 @@ use tracing::debug;
 
 /// Select egress link using multipath routing tables.
 /// Chooses a next hop from the list of equal‑cost candidates based on the requested destination.
-/// Load‑balances among equal‑cost next hops using a hash of packet fields.
+/// Load‑balances among equal‑cost next hops using a hash of packet fields, in proportion to the
+/// weights of their entries.
 pub fn select_egress_link_multi<'a>(
     router_id: &RouterId,
     packet: &PacketMeta,
     links: &'a [&Link],
     tables: &HashMap<RouterId, MultiPathTable>,
     destination: Destination,
+    hash: &EcmpHash,
 ) -> Option<&'a Link> {
     // Retrieve routing entries for the given destination.
     let routing = tables.get(router_id)?;
@@ -43,19 +46,34 @@ pub fn select_egress_link_multi<'a>(
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
         use std::sync::atomic::Ordering;
-        let mut hasher = DefaultHasher::new();
-        packet.src_ip.hash(&mut hasher);
-        packet.dst_ip.hash(&mut hasher);
-        packet.src_port.hash(&mut hasher);
-        packet.dst_port.hash(&mut hasher);
-        packet.protocol.hash(&mut hasher);
-        let total_counter: u64 = lb_links
+        let hops: Vec<RouterId> = lb_links
             .iter()
-            .map(|l| l.counter.load(Ordering::Relaxed))
-            .sum();
-        total_counter.hash(&mut hasher);
-        let hash = hasher.finish();
-        let idx = (hash as usize) % lb_links.len();
+            .map(|l| {
+                if l.id.a == *router_id {
+                    l.id.b.clone()
+                } else {
+                    l.id.a.clone()
+                }
+            })
+            .collect();
+        // Each next hop gets a share of the flows proportional to its weight.
+        let weights: Vec<u32> = hops
+            .iter()
+            .map(|hop| routing.weight(destination, hop))
+            .collect();
+        let idx = if hash.consistent {
+            hash.index(router_id, &hash.flow(packet), (&hops, &weights))
+        } else {
+            let mut hasher = DefaultHasher::new();
+            hash.seed(router_id).hash(&mut hasher);
+            hash.flow(packet).hash(&mut hasher);
+            let total_counter: u64 = lb_links
+                .iter()
+                .map(|l| l.counter.load(Ordering::Relaxed))
+                .sum();
+            total_counter.hash(&mut hasher);
+            super::weighted_index(hasher.finish(), &weights)
+        };
         let chosen = *lb_links[idx];
         debug!(
             "Load‑balanced (multipath) selection of link {:?}",
@@ -0,0 +1,118 @@
+// src/gre/mod.rs
+
+//! GRE tunnels on designated links (RFC 2784).
+//!
+//! A link with `encap = "gre"` carries every packet inside an outer IPv4 header from the
+//! sending router's address to the receiving router's, followed by a four‑byte GRE header
+//! naming the inner protocol. The outer header copies the inner TOS / Traffic Class, so queueing
+//! still classifies by DSCP. The wrapped size is what the link's MTU, queues and byte counters
+//! see; the receiving router strips the outer headers and forwards the inner packet unchanged.
+//! An inner packet that no longer fits is handled against the tunnel MTU, the link MTU minus
+//! [`OVERHEAD`]: fragmented, or answered with Fragmentation Needed / Packet Too Big.
+
+use crate::packet::{parse_ethertype, traffic_class, update_ipv4_checksum, PacketMeta};
+use crate::topology::{Link, LinkEncap, Router, RouterId};
+use std::borrow::Cow;
+use std::net::Ipv4Addr;
+
+/// IP protocol number of GRE.
+pub const IPPROTO_GRE: u8 = 47;
+/// Outer IPv4 header and GRE header without optional fields.
+pub const OVERHEAD: usize = 24;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+
+/// Wrap the IPv4 or IPv6 packet `inner` in an outer IPv4 + GRE header from `src` to `dst`.
+pub fn encapsulate(inner: &[u8], src: Ipv4Addr, dst: Ipv4Addr) -> Vec<u8> {
+    let ethertype = match inner.first().map(|b| b >> 4) {
+        Some(6) => ETHERTYPE_IPV6,
+        _ => ETHERTYPE_IPV4,
+    };
+    let total = OVERHEAD + inner.len();
+    let mut outer = Vec::with_capacity(total);
+    outer.extend_from_slice(&[0x45, traffic_class(inner).unwrap_or(0)]);
+    outer.extend_from_slice(&(total.min(u16::MAX as usize) as u16).to_be_bytes());
+    outer.extend_from_slice(&[0, 0, 0, 0, 64, IPPROTO_GRE, 0, 0]);
+    outer.extend_from_slice(&src.octets());
+    outer.extend_from_slice(&dst.octets());
+    update_ipv4_checksum(&mut outer);
+    outer.extend_from_slice(&[0, 0]);
+    outer.extend_from_slice(&ethertype.to_be_bytes());
+    outer.extend_from_slice(inner);
+    outer
+}
+
+/// The packet carried by the IPv4 + GRE packet `outer`, or `None` if it is not one. GRE
+/// checksum, key and sequence number fields are skipped.
+pub fn decapsulate(outer: &[u8]) -> Option<&[u8]> {
+    payload(outer).map(|(_, inner)| inner)
+}
+
+/// The packet carried by `outer`, parsed by the GRE protocol type: IP, or MPLS‑labeled IP.
+pub fn parse_inner(outer: &[u8]) -> Result<PacketMeta, &'static str> {
+    let (ethertype, inner) = payload(outer).ok_or("not an IPv4 GRE packet")?;
+    parse_ethertype(ethertype, inner)
+}
+
+/// Protocol type and payload of the IPv4 + GRE packet `outer`.
+fn payload(outer: &[u8]) -> Option<(u16, &[u8])> {
+    if outer.len() < 20 || outer[0] >> 4 != 4 || outer[9] != IPPROTO_GRE {
+        return None;
+    }
+    let ihl = (outer[0] & 0x0F) as usize * 4;
+    let gre = outer.get(ihl..)?;
+    let (flags, version) = (*gre.first()?, gre.get(1)? & 0x07);
+    if version != 0 {
+        return None;
+    }
+    // Checksum (with its reserved half), key and sequence number take four bytes each.
+    let optional = [0x80, 0x20, 0x10]
+        .iter()
+        .filter(|&&bit| flags & bit != 0)
+        .count();
+    let start = ihl + 4 + 4 * optional;
+    let end = (u16::from_be_bytes([outer[2], outer[3]]) as usize).min(outer.len());
+    let ethertype = u16::from_be_bytes([*gre.get(2)?, *gre.get(3)?]);
+    Some((ethertype, outer.get(start..end)?))
+}
+
+/// What `packet` looks like on `link` when sent by `from`: wrapped on GRE links and on
+/// IP‑in‑IP links for the family they tunnel, as is otherwise.
+pub fn on_wire<'a>(link: &Link, from: &RouterId, packet: &'a [u8]) -> Cow<'a, [u8]> {
+    if overhead(link, packet) == 0 {
+        return Cow::Borrowed(packet);
+    }
+    let to = if *from == link.id.a {
+        &link.id.b
+    } else {
+        &link.id.a
+    };
+    let (src4, src6) = Router::generate_addresses(from);
+    let (dst4, dst6) = Router::generate_addresses(to);
+    Cow::Owned(match link.cfg.encap {
+        Some(LinkEncap::SixInFour) => crate::ipip::encapsulate_6in4(packet, src4, dst4),
+        Some(LinkEncap::FourInSix) => crate::ipip::encapsulate_4in6(packet, src6, dst6),
+        _ => encapsulate(packet, src4, dst4),
+    })
+}
+
+/// Bytes `link` adds to `packet`.
+pub fn overhead(link: &Link, packet: &[u8]) -> usize {
+    let version = packet.first().map(|b| b >> 4);
+    match link.cfg.encap {
+        Some(LinkEncap::Gre) => OVERHEAD,
+        Some(LinkEncap::SixInFour) if version == Some(6) => crate::ipip::OVERHEAD_6IN4,
+        Some(LinkEncap::FourInSix) if version == Some(4) => crate::ipip::OVERHEAD_4IN6,
+        _ => 0,
+    }
+}
+
+/// The most `encap` adds to a packet.
+pub fn max_overhead(encap: LinkEncap) -> usize {
+    match encap {
+        LinkEncap::Gre => OVERHEAD,
+        LinkEncap::SixInFour => crate::ipip::OVERHEAD_6IN4,
+        LinkEncap::FourInSix => crate::ipip::OVERHEAD_4IN6,
+    }
+}
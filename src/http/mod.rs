@@ -0,0 +1,277 @@
+// src/http/mod.rs
+
+//! Built‑in HTTP/1.1 test origin and load client (feature `http-test`).
+//!
+//! The origin is a keep‑alive echo server: every request body is returned unchanged. The client
+//! opens `concurrency` connections from an address behind one edge to the origin behind the
+//! other edge and issues POST requests, so real TCP traffic crosses the simulated fabric and
+//! its throughput and latency can be measured without external tools.
+//!
+//! Both ends are host sockets, so the origin runs in the namespace of `real_tun_b` and the client
+//! in that of `real_tun_a` (see [`crate::netns`]); otherwise the kernel would short‑circuit the
+//! connections over loopback.
+
+use crate::config::HttpTestConfig;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// Outcome of one load run.
+#[derive(Debug, Clone, Default)]
+pub struct HttpLoadReport {
+    /// Latency of each successful request, in milliseconds.
+    pub latencies_ms: Vec<f64>,
+    pub errors: u32,
+    /// Response body bytes received.
+    pub bytes: u64,
+    pub elapsed_secs: f64,
+}
+
+impl HttpLoadReport {
+    pub fn completed(&self) -> usize {
+        self.latencies_ms.len()
+    }
+
+    pub fn latency_avg_ms(&self) -> Option<f64> {
+        if self.latencies_ms.is_empty() {
+            None
+        } else {
+            Some(self.latencies_ms.iter().sum::<f64>() / self.latencies_ms.len() as f64)
+        }
+    }
+
+    /// Latency percentile (nearest rank), `p` in 0..=100.
+    pub fn latency_percentile_ms(&self, p: f64) -> Option<f64> {
+        if self.latencies_ms.is_empty() {
+            return None;
+        }
+        let mut sorted = self.latencies_ms.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+
+    /// Goodput of the response bodies in megabits per second.
+    pub fn throughput_mbps(&self) -> f64 {
+        if self.elapsed_secs <= 0.0 {
+            return 0.0;
+        }
+        self.bytes as f64 * 8.0 / self.elapsed_secs / 1_000_000.0
+    }
+
+    /// Requests completed per second.
+    pub fn requests_per_sec(&self) -> f64 {
+        if self.elapsed_secs <= 0.0 {
+            return 0.0;
+        }
+        self.completed() as f64 / self.elapsed_secs
+    }
+
+    pub fn summary(&self) -> String {
+        let fmt = |v: Option<f64>| v.map_or_else(|| "-".to_string(), |x| format!("{:.3}", x));
+        format!(
+            "completed={}, errors={}, rps={:.1}, throughput={:.3} Mbit/s, latency avg/p50/p99={}/{}/{} ms",
+            self.completed(),
+            self.errors,
+            self.requests_per_sec(),
+            self.throughput_mbps(),
+            fmt(self.latency_avg_ms()),
+            fmt(self.latency_percentile_ms(50.0)),
+            fmt(self.latency_percentile_ms(99.0))
+        )
+    }
+}
+
+/// Bind the echo origin to `addr`, inside namespace `netns` if given, and serve connections in
+/// the background. Returns the bound address (useful when `addr` uses port 0) and the server task.
+pub async fn start_server(
+    addr: &str,
+    netns: Option<&str>,
+) -> Result<(SocketAddr, JoinHandle<()>), String> {
+    let bind: SocketAddr = addr
+        .parse()
+        .map_err(|e| format!("Invalid HTTP test origin address {}: {}", addr, e))?;
+    let listener = crate::netns::tcp_listener(netns, bind)
+        .map_err(|e| format!("Failed to bind HTTP test origin to {}: {}", addr, e))?;
+    let local = listener.local_addr().map_err(|e| e.to_string())?;
+    info!("HTTP test origin listening on {}", local);
+    let handle = tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    debug!("HTTP test origin accepted connection from {}", peer);
+                    tokio::spawn(async move {
+                        if let Err(e) = serve_connection(stream).await {
+                            debug!("HTTP test connection from {} ended: {}", peer, e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    warn!("HTTP test origin accept failed: {}", e);
+                }
+            }
+        }
+    });
+    Ok((local, handle))
+}
+
+/// Read one request head; returns the Content-Length and whether the client asked to close.
+/// `Ok(None)` means the peer closed the connection cleanly.
+async fn read_head(
+    reader: &mut BufReader<TcpStream>,
+    expect_status: bool,
+) -> std::io::Result<Option<(usize, bool)>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    if expect_status && !line.starts_with("HTTP/1.1 200") {
+        return Err(std::io::Error::other(format!(
+            "unexpected status line '{}'",
+            line.trim_end()
+        )));
+    }
+    let (mut content_length, mut close) = (0usize, false);
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, "bad Content-Length")
+                })?;
+            } else if name.eq_ignore_ascii_case("connection") {
+                close = value.eq_ignore_ascii_case("close");
+            }
+        }
+    }
+    Ok(Some((content_length, close)))
+}
+
+async fn serve_connection(stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    while let Some((len, close)) = read_head(&mut reader, false).await? {
+        let mut body = vec![0u8; len];
+        reader.read_exact(&mut body).await?;
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\n{}\r\n",
+            len,
+            if close { "Connection: close\r\n" } else { "" }
+        );
+        let stream = reader.get_mut();
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&body).await?;
+        if close {
+            break;
+        }
+    }
+    Ok(())
+}
+
+async fn connect(
+    target: SocketAddr,
+    client_bind: Option<IpAddr>,
+    netns: Option<&str>,
+) -> std::io::Result<TcpStream> {
+    let socket = crate::netns::tcp_socket(netns, &target)?;
+    if let Some(ip) = client_bind {
+        socket.bind(SocketAddr::new(ip, 0))?;
+    }
+    socket.connect(target).await
+}
+
+/// Run the load client described by `cfg` against `cfg.target`, connecting from namespace
+/// `netns` if given.
+pub async fn run_load(cfg: &HttpTestConfig, netns: Option<&str>) -> Result<HttpLoadReport, String> {
+    let target: SocketAddr = cfg
+        .target
+        .as_deref()
+        .ok_or("http_test.target is not set")?
+        .parse()
+        .map_err(|e| format!("Invalid http_test target: {}", e))?;
+    let client_bind = match cfg.client_bind {
+        Some(ref s) => Some(
+            s.parse::<IpAddr>()
+                .map_err(|e| format!("Invalid http_test client_bind: {}", e))?,
+        ),
+        None => None,
+    };
+    let remaining = Arc::new(AtomicU32::new(cfg.requests));
+    let body = Arc::new(vec![0x5au8; cfg.body_bytes]);
+    let start = Instant::now();
+    let mut workers = Vec::new();
+    for _ in 0..cfg.concurrency.max(1) {
+        let remaining = remaining.clone();
+        let body = body.clone();
+        let netns = netns.map(str::to_string);
+        workers.push(tokio::spawn(async move {
+            let mut report = HttpLoadReport::default();
+            let mut conn: Option<BufReader<TcpStream>> = None;
+            // Claim one request at a time until the shared budget is exhausted.
+            while remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                let t0 = Instant::now();
+                let result = async {
+                    if conn.is_none() {
+                        conn = Some(BufReader::new(
+                            connect(target, client_bind, netns.as_deref()).await?,
+                        ));
+                    }
+                    let reader = conn.as_mut().unwrap();
+                    let head = format!(
+                        "POST /echo HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n\r\n",
+                        target,
+                        body.len()
+                    );
+                    reader.get_mut().write_all(head.as_bytes()).await?;
+                    reader.get_mut().write_all(&body).await?;
+                    let (len, _) = read_head(reader, true)
+                        .await?
+                        .ok_or(std::io::ErrorKind::UnexpectedEof)?;
+                    let mut resp = vec![0u8; len];
+                    reader.read_exact(&mut resp).await?;
+                    Ok::<usize, std::io::Error>(len)
+                }
+                .await;
+                match result {
+                    Ok(len) => {
+                        report
+                            .latencies_ms
+                            .push(t0.elapsed().as_secs_f64() * 1000.0);
+                        report.bytes += len as u64;
+                    }
+                    Err(e) => {
+                        debug!("HTTP test request failed: {}", e);
+                        report.errors += 1;
+                        // Reconnect for the next request.
+                        conn = None;
+                    }
+                }
+            }
+            report
+        }));
+    }
+    let mut total = HttpLoadReport::default();
+    for worker in workers {
+        let report = worker.await.map_err(|e| e.to_string())?;
+        total.latencies_ms.extend(report.latencies_ms);
+        total.errors += report.errors;
+        total.bytes += report.bytes;
+    }
+    total.elapsed_secs = start.elapsed().as_secs_f64();
+    Ok(total)
+}
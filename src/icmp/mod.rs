@@ -5,7 +5,7 @@ use std::net::{Ipv4Addr, Ipv6Addr};
 use tracing::debug;
 
 /// Compute ICMPv6 checksum with pseudo‑header.
-fn icmpv6_checksum(src: Ipv6Addr, dst: Ipv6Addr, icmp: &[u8]) -> u16 {
+pub(crate) fn icmpv6_checksum(src: Ipv6Addr, dst: Ipv6Addr, icmp: &[u8]) -> u16 {
     // Pseudo‑header: src (16), dst (16), payload length (4), zeros (3), next header (58 for ICMPv6)
     let mut sum: u32 = 0;
     for chunk in src.octets().chunks(2) {
@@ -32,6 +32,23 @@ fn icmpv6_checksum(src: Ipv6Addr, dst: Ipv6Addr, icmp: &[u8]) -> u16 {
     !(sum as u16)
 }
 
+/// Whether `packet` is an ICMP or ICMPv6 error message (as opposed to a query such as an
+/// echo), which RFC 1122 and RFC 4443 forbid answering with another error.
+pub fn is_error(packet: &PacketMeta) -> bool {
+    let Some(offset) = crate::packet::transport_offset(&packet.raw) else {
+        return false;
+    };
+    match (packet.protocol, packet.raw.get(offset)) {
+        // Later IPv4 fragments carry no ICMP header.
+        (1, Some(3 | 4 | 5 | 11 | 12)) => {
+            packet.src_ip.is_ipv4()
+                && u16::from_be_bytes([packet.raw[6], packet.raw[7]]) & 0x1fff == 0
+        }
+        (58, Some(&kind)) => kind < 128 && packet.src_ip.is_ipv6(),
+        _ => false,
+    }
+}
+
 /// Generate a minimal ICMPv6 error packet.
 /// `error_type` and `code` follow the ICMPv6 specification.
 /// `router_addr` is the IPv6 address of the router generating the error.
@@ -103,7 +120,7 @@ pub fn generate_icmpv6_error(
 }
 
 /// Compute ICMP checksum (RFC 792).
-fn calculate_icmp_checksum(data: &[u8]) -> u16 {
+pub(crate) fn calculate_icmp_checksum(data: &[u8]) -> u16 {
     let mut sum: u32 = 0;
     let mut i = 0;
     while i + 1 < data.len() {
@@ -223,3 +240,100 @@ pub fn generate_fragmentation_needed(
     packet[icmp_start + 3] = (icmp_checksum & 0xFF) as u8;
     packet
 }
+
+/// Which end of the packet quoted in an ICMP error a translation applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotedEnd {
+    /// The quoted packet's source, which is also the error's destination: an error coming back
+    /// for a packet whose source was translated.
+    Source,
+    /// The quoted packet's destination, which is also the error's source: an error sent by the
+    /// host a translated packet was addressed to.
+    Destination,
+}
+
+/// One's‑complement checksum `checksum` updated for `old` bytes replaced by `new` (RFC 1624).
+/// Both slices must have the same, even length.
+fn adjust_checksum(checksum: u16, old: &[u8], new: &[u8]) -> u16 {
+    let mut sum = !checksum as u32;
+    for (o, n) in old.chunks(2).zip(new.chunks(2)) {
+        sum += !u16::from_be_bytes([o[0], o[1]]) as u32;
+        sum += u16::from_be_bytes([n[0], n[1]]) as u32;
+    }
+    while (sum >> 16) != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Translate an IPv4 ICMP error (Destination Unreachable, Time Exceeded, Parameter Problem) for
+/// an address/port mapping, as a NAT44 has to: `end` of the quoted packet gets `addr` and
+/// `port` (the identifier for a quoted ICMP query), and so does the matching outer address. The
+/// quoted IP header checksum, the quoted TCP/UDP checksum (incrementally, since the quote is
+/// truncated), the ICMP checksum and the outer header checksum are updated. Without this a
+/// Fragmentation Needed for a translated flow never reaches the host behind the NAT, and path
+/// MTU discovery through it breaks.
+pub fn translate_error(
+    raw: &mut [u8],
+    end: QuotedEnd,
+    addr: Ipv4Addr,
+    port: u16,
+) -> Result<(), &'static str> {
+    if raw.len() < 20 || raw[0] >> 4 != 4 || raw[9] != 1 {
+        return Err("not an IPv4 ICMP packet");
+    }
+    let icmp = (raw[0] & 0x0F) as usize * 4;
+    if !matches!(raw.get(icmp), Some(3 | 11 | 12)) {
+        return Err("not an ICMP error");
+    }
+    let quoted = icmp + 8;
+    if raw.len() < quoted + 20 || raw[quoted] >> 4 != 4 {
+        return Err("ICMP error does not quote an IPv4 header");
+    }
+    let quoted_ihl = (raw[quoted] & 0x0F) as usize * 4;
+    if quoted_ihl < 20 || raw.len() < quoted + quoted_ihl {
+        return Err("quoted IPv4 header truncated");
+    }
+    let (outer_at, quoted_addr_at, port_field) = match end {
+        QuotedEnd::Source => (16, quoted + 12, 0),
+        QuotedEnd::Destination => (12, quoted + 16, 2),
+    };
+    let transport = quoted + quoted_ihl;
+    let old_addr: [u8; 4] = raw[quoted_addr_at..quoted_addr_at + 4].try_into().unwrap();
+    let new_addr = addr.octets();
+    raw[outer_at..outer_at + 4].copy_from_slice(&new_addr);
+    raw[quoted_addr_at..quoted_addr_at + 4].copy_from_slice(&new_addr);
+    let protocol = raw[quoted + 9];
+    // Only a first fragment quotes the transport header.
+    let first_fragment = u16::from_be_bytes([raw[quoted + 6], raw[quoted + 7]]) & 0x1FFF == 0;
+    let (port_at, checksum_at) = match protocol {
+        6 => (Some(transport + port_field), Some(transport + 16)),
+        17 => (Some(transport + port_field), Some(transport + 6)),
+        // ICMP query: the identifier stands in for the port at either end.
+        1 => (Some(transport + 4), Some(transport + 2)),
+        // Other protocols have no port to translate; only the addresses change.
+        _ => (None, None),
+    };
+    if let Some(port_at) = port_at.filter(|at| first_fragment && at + 2 <= raw.len()) {
+        let old_port = [raw[port_at], raw[port_at + 1]];
+        raw[port_at..port_at + 2].copy_from_slice(&port.to_be_bytes());
+        if let Some(at) = checksum_at.filter(|at| at + 2 <= raw.len()) {
+            let checksum = u16::from_be_bytes([raw[at], raw[at + 1]]);
+            // A zero UDP checksum means "none" and stays zero.
+            if !(protocol == 17 && checksum == 0) {
+                let mut adjusted = adjust_checksum(checksum, &old_port, &port.to_be_bytes());
+                if protocol != 1 {
+                    // The pseudo‑header covers the address too; ICMP has none.
+                    adjusted = adjust_checksum(adjusted, &old_addr, &new_addr);
+                }
+                raw[at..at + 2].copy_from_slice(&adjusted.to_be_bytes());
+            }
+        }
+    }
+    crate::packet::update_ipv4_checksum(&mut raw[quoted..quoted + quoted_ihl]);
+    raw[icmp + 2..icmp + 4].copy_from_slice(&[0, 0]);
+    let checksum = calculate_icmp_checksum(&raw[icmp..]);
+    raw[icmp + 2..icmp + 4].copy_from_slice(&checksum.to_be_bytes());
+    crate::packet::update_ipv4_checksum(&mut raw[..icmp]);
+    Ok(())
+}
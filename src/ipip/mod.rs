@@ -0,0 +1,66 @@
+// src/ipip/mod.rs
+
+//! IP‑in‑IP tunnels on designated links: 6in4 (RFC 4213) and 4in6 (RFC 2473).
+//!
+//! A link with `encap = "6in4"` stands for an IPv4‑only segment: IPv6 packets cross it inside an
+//! outer IPv4 header (protocol 41) from the sending router's IPv4 address to the receiving
+//! router's, while IPv4 packets cross natively. `encap = "4in6"` is the reverse: IPv4 packets are
+//! carried in an outer IPv6 header (next header 4) between the routers' IPv6 addresses. As on
+//! GRE links ([`crate::gre`]), the outer header copies the inner TOS / Traffic Class, the wrapped
+//! size is what the link's MTU, queues and byte counters see, and an inner packet that no longer
+//! fits is handled against the link MTU minus the outer header.
+
+use crate::packet::{traffic_class, update_ipv4_checksum};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// IP protocol number of an encapsulated IPv4 packet.
+pub const IPPROTO_IPIP: u8 = 4;
+/// IP protocol number of an encapsulated IPv6 packet.
+pub const IPPROTO_IPV6: u8 = 41;
+/// Outer IPv4 header of a 6in4 packet.
+pub const OVERHEAD_6IN4: usize = 20;
+/// Outer IPv6 header of a 4in6 packet.
+pub const OVERHEAD_4IN6: usize = 40;
+
+/// Wrap the IPv6 packet `inner` in an outer IPv4 header from `src` to `dst`.
+pub fn encapsulate_6in4(inner: &[u8], src: Ipv4Addr, dst: Ipv4Addr) -> Vec<u8> {
+    let total = OVERHEAD_6IN4 + inner.len();
+    let mut outer = Vec::with_capacity(total);
+    outer.extend_from_slice(&[0x45, traffic_class(inner).unwrap_or(0)]);
+    outer.extend_from_slice(&(total.min(u16::MAX as usize) as u16).to_be_bytes());
+    outer.extend_from_slice(&[0, 0, 0, 0, 64, IPPROTO_IPV6, 0, 0]);
+    outer.extend_from_slice(&src.octets());
+    outer.extend_from_slice(&dst.octets());
+    update_ipv4_checksum(&mut outer);
+    outer.extend_from_slice(inner);
+    outer
+}
+
+/// Wrap the IPv4 packet `inner` in an outer IPv6 header from `src` to `dst`.
+pub fn encapsulate_4in6(inner: &[u8], src: Ipv6Addr, dst: Ipv6Addr) -> Vec<u8> {
+    let tc = traffic_class(inner).unwrap_or(0);
+    let mut outer = Vec::with_capacity(OVERHEAD_4IN6 + inner.len());
+    outer.extend_from_slice(&[0x60 | (tc >> 4), tc << 4, 0, 0]);
+    outer.extend_from_slice(&(inner.len().min(u16::MAX as usize) as u16).to_be_bytes());
+    outer.extend_from_slice(&[IPPROTO_IPIP, 64]);
+    outer.extend_from_slice(&src.octets());
+    outer.extend_from_slice(&dst.octets());
+    outer.extend_from_slice(inner);
+    outer
+}
+
+/// The packet carried by the 6in4 or 4in6 packet `outer`, or `None` if it is not one.
+pub fn decapsulate(outer: &[u8]) -> Option<&[u8]> {
+    match outer.first()? >> 4 {
+        4 if outer.len() >= 20 && matches!(outer[9], IPPROTO_IPIP | IPPROTO_IPV6) => {
+            let ihl = (outer[0] & 0x0F) as usize * 4;
+            let end = (u16::from_be_bytes([outer[2], outer[3]]) as usize).min(outer.len());
+            outer.get(ihl..end)
+        }
+        6 if outer.len() >= 40 && matches!(outer[6], IPPROTO_IPIP | IPPROTO_IPV6) => {
+            let end = 40 + u16::from_be_bytes([outer[4], outer[5]]) as usize;
+            outer.get(40..end.min(outer.len()))
+        }
+        _ => None,
+    }
+}
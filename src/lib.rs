@@ -1,15 +1,63 @@
 // src/lib.rs
 
+pub mod acl;
+pub mod alarms;
+pub mod asymmetry;
+pub mod bench;
+pub mod capture;
+pub mod clab;
+pub mod clock;
 pub mod config;
+pub mod customer;
+pub mod ddos;
+pub mod dedupe;
+pub mod dhcp;
+pub mod dns;
+pub mod drops;
+pub mod events;
+pub mod experiment;
+pub mod explicit_path;
 pub mod routing;
 pub mod topology;
 pub use routing::Destination;
 pub mod forwarding;
+pub mod gre;
+#[cfg(feature = "http-test")]
+pub mod http;
 pub mod icmp;
+pub mod ipip;
+pub mod marking;
+pub mod metrics;
+pub mod multi;
+pub mod nat;
+pub mod netns;
+pub mod pacing;
 pub mod packet;
+pub mod pbr;
+pub mod pmtu;
+pub mod policer;
 pub mod processor;
+pub mod provenance;
+pub mod pseudowire;
+pub mod qos;
+pub mod reassembly;
+pub mod replay;
+pub mod report;
 pub mod simulation;
+pub mod sla;
+pub mod snapshot;
+pub mod sojourn;
+pub mod srv6;
+pub mod stats;
+pub mod stretch;
+pub mod tap;
+pub mod telemetry;
+pub mod tenant;
+pub mod traceroute;
+pub mod ttl;
 pub mod tun;
+pub mod twamp;
+pub mod wireguard;
 
 use crate::config::SimulatorConfig;
 use crate::processor::{process_packet, process_packet_multi};
@@ -18,15 +66,62 @@ use crate::topology::Fabric;
 use std::collections::HashMap;
 use tracing::{debug, error, info};
 
-/// Compute routing tables (single‑path) for the given configuration.
-pub fn compute_routing_tables(cfg: &SimulatorConfig) -> HashMap<RouterId, routing::RoutingTable> {
+/// Build the fabric (routers and links) described by the configuration.
+/// Links referring to unknown routers are skipped with an error log.
+pub fn build_fabric(cfg: &SimulatorConfig) -> Fabric {
     let mut fabric = Fabric::new();
-    // Populate fabric as in `run` (routers and links)
+    if let Some(ref hash) = cfg.ecmp_hash {
+        fabric.ecmp_hash = forwarding::EcmpHash::from_config(hash);
+    }
     for router_id in cfg.topology.routers.keys() {
-        let router = topology::router::Router::new(RouterId(router_id.clone()));
+        let mut router = topology::router::Router::new(RouterId(router_id.clone()));
+        match cfg.topology.router_config(router_id) {
+            Ok(router_cfg) => {
+                router.cpu = router_cfg
+                    .max_pps
+                    .map(|pps| topology::CpuModel::new(pps, router_cfg.cpu_queue));
+                router.maintenance = router_cfg.maintenance;
+                router.asn = router_cfg.asn;
+                if let Some(seed) = router_cfg.ecmp_seed {
+                    fabric
+                        .ecmp_hash
+                        .router_seeds
+                        .insert(RouterId(router_id.clone()), seed);
+                }
+                for rule in &router_cfg.acl {
+                    match acl::AclRule::from_config(rule) {
+                        Ok(rule) => router.acl.push(rule),
+                        Err(e) => error!("Router {}: invalid ACL rule: {}", router_id, e),
+                    }
+                }
+                for rule in &router_cfg.pbr {
+                    match pbr::PbrRule::from_config(rule) {
+                        Ok(rule) => router.pbr.push(rule),
+                        Err(e) => error!("Router {}: invalid PBR rule: {}", router_id, e),
+                    }
+                }
+                for (destination, route) in &router_cfg.static_routes {
+                    match routing::static_routes::StaticRoute::new(destination, route) {
+                        Ok(route) => router.static_routes.push(route),
+                        Err(e) => error!("Router {}: {}", router_id, e),
+                    }
+                }
+                for policer in &router_cfg.policers {
+                    match policer::Policer::from_config(policer) {
+                        Ok(policer) => router.policers.push(policer),
+                        Err(e) => error!("Router {}: invalid policer: {}", router_id, e),
+                    }
+                }
+            }
+            Err(e) => error!("{}", e),
+        }
         fabric.add_router(router);
     }
+    for (name, pool_cfg) in &cfg.topology.pools {
+        fabric.add_pool(name, pool_cfg);
+    }
     for (link_name, link_cfg) in cfg.topology.links.iter() {
+        // split on '_' to get the two router ids
         let parts: Vec<&str> = link_name.split('_').collect();
         if parts.len() != 2 {
             continue;
@@ -34,12 +129,74 @@ pub fn compute_routing_tables(cfg: &SimulatorConfig) -> HashMap<RouterId, routin
         let a = RouterId(parts[0].to_string());
         let b = RouterId(parts[1].to_string());
         if fabric.router_index.contains_key(&a) && fabric.router_index.contains_key(&b) {
-            fabric.add_link(&a, &b, link_cfg.clone());
+            let mut link_cfg = link_cfg.clone();
+            if let Some(cost) = cfg.topology.derived_cost(&link_cfg) {
+                link_cfg.cost = Some(cost);
+            }
+            fabric.add_link(&a, &b, link_cfg);
+        } else {
+            error!("Link {} references unknown router(s)", link_name);
+        }
+    }
+    fabric.paranoid = cfg.paranoid;
+    fabric.checksum = cfg.checksum.clone();
+    fabric.bgp = cfg.bgp.as_ref().map(routing::bgp::Bgp::from_config);
+    fabric.routing_algorithm = cfg.routing.algorithm;
+    if let Some(distance) = cfg.routing.distance {
+        fabric.routing_distance = distance;
+    }
+    for path in &cfg.explicit_paths {
+        match explicit_path::ExplicitPath::from_config(path) {
+            Ok(path) => fabric.explicit_paths.push(path),
+            Err(e) => error!("{}", e),
         }
     }
+    if !cfg.tenants.is_empty() {
+        match tenant::Tenants::from_config(&cfg.tenants) {
+            Ok(tenants) => fabric.tenants = Some(tenants),
+            Err(e) => error!("{}", e),
+        }
+    }
+    for route in &cfg.routes {
+        match routing::fib::PrefixRoute::new(route) {
+            Ok(route) => fabric.routes.push(route),
+            Err(e) => error!("{}", e),
+        }
+    }
+    fabric
+}
+
+/// Compute routing tables (single‑path) for the given configuration.
+pub fn compute_routing_tables(cfg: &SimulatorConfig) -> HashMap<RouterId, routing::RoutingTable> {
+    let fabric = build_fabric(cfg);
     let ingress_a = RouterId(cfg.tun_ingress.tun_a_ingress.clone());
     let ingress_b = RouterId(cfg.tun_ingress.tun_b_ingress.clone());
-    routing::compute_routing(&fabric, ingress_a, ingress_b)
+    let mut tables = routing::compute_routing_seeded(
+        &fabric,
+        ingress_a,
+        ingress_b,
+        cfg.simulation.tie_break_seed,
+    );
+    // The edge routes the run starts with come from the settled distance vector, if any.
+    if let Some(dv) = distance_vector(cfg, &fabric) {
+        dv.install(&mut tables);
+    }
+    tables
+}
+
+/// The distance-vector routing of `cfg` on `fabric`, settled, if `[distance_vector]` is set.
+fn distance_vector(
+    cfg: &SimulatorConfig,
+    fabric: &Fabric,
+) -> Option<routing::distance_vector::DistanceVector> {
+    cfg.distance_vector.as_ref().map(|dv| {
+        routing::distance_vector::DistanceVector::new(
+            dv,
+            fabric,
+            &RouterId(cfg.tun_ingress.tun_a_ingress.clone()),
+            &RouterId(cfg.tun_ingress.tun_b_ingress.clone()),
+        )
+    })
 }
 
 /// Compute multipath routing tables (if enabled) for the given configuration.
@@ -49,22 +206,7 @@ pub fn compute_multipath_tables(
     if !cfg.enable_multipath {
         return HashMap::new();
     }
-    let mut fabric = Fabric::new();
-    for router_id in cfg.topology.routers.keys() {
-        let router = topology::router::Router::new(RouterId(router_id.clone()));
-        fabric.add_router(router);
-    }
-    for (link_name, link_cfg) in cfg.topology.links.iter() {
-        let parts: Vec<&str> = link_name.split('_').collect();
-        if parts.len() != 2 {
-            continue;
-        }
-        let a = RouterId(parts[0].to_string());
-        let b = RouterId(parts[1].to_string());
-        if fabric.router_index.contains_key(&a) && fabric.router_index.contains_key(&b) {
-            fabric.add_link(&a, &b, link_cfg.clone());
-        }
-    }
+    let fabric = build_fabric(cfg);
     let ingress_a = RouterId(cfg.tun_ingress.tun_a_ingress.clone());
     let ingress_b = RouterId(cfg.tun_ingress.tun_b_ingress.clone());
     routing::compute_multi_path_routing(&fabric, ingress_a, ingress_b)
@@ -73,28 +215,124 @@ pub fn compute_multipath_tables(
 /// Entry point called from `main.rs`. Parses the configuration, builds the fabric,
 /// computes routing tables and (for now) immediately shuts down.
 pub async fn run(cfg: SimulatorConfig) -> Result<Fabric, Box<dyn std::error::Error>> {
-    // Build fabric (stub implementation for now)
-    let mut fabric = Fabric::new();
-    // Add routers from config
-    for router_id in cfg.topology.routers.keys() {
-        let router = topology::router::Router::new(RouterId(router_id.clone()));
-        fabric.add_router(router);
+    run_with_faults(cfg, None).await
+}
+
+/// [`run`], also applying the faults injected through the [`events::FaultInjector`] of
+/// `faults` while the simulation runs; the routing tables are recomputed after each.
+pub async fn run_with_faults(
+    cfg: SimulatorConfig,
+    faults: Option<events::Faults>,
+) -> Result<Fabric, Box<dyn std::error::Error>> {
+    // Build fabric from the configured routers and links.
+    let mut fabric = build_fabric(&cfg);
+    fabric.faults = faults;
+    fabric.run = cfg.run.clone();
+    if let Some(ref path) = cfg.counter_state {
+        fabric.counter_state = Some(stats::CounterState::open(path, cfg.fresh)?);
     }
-    // Add links from config (very simplified – only adds if both ends exist)
-    for (link_name, link_cfg) in cfg.topology.links.iter() {
-        // split on '_' to get the two router ids
-        let parts: Vec<&str> = link_name.split('_').collect();
-        if parts.len() != 2 {
-            continue;
+    if let Some(ref marking) = cfg.marking {
+        fabric.marking = Some(marking::Marking::new(marking.strip));
+    }
+    // Tunnels between the edges run from one ingress router's address to the other's.
+    let edge_address = |fabric: &Fabric, id: &str| {
+        fabric
+            .get_router(&RouterId(id.to_string()))
+            .map(|r| r.ipv4_addr)
+            .ok_or_else(|| format!("Tunnel end router '{}' not found", id))
+    };
+    let edges = |fabric: &Fabric| -> Result<_, String> {
+        Ok((
+            edge_address(fabric, &cfg.tun_ingress.tun_a_ingress)?,
+            edge_address(fabric, &cfg.tun_ingress.tun_b_ingress)?,
+        ))
+    };
+    if let Some(ref pw) = cfg.pseudowire {
+        let (a, b) = edges(&fabric)?;
+        fabric.pseudowire = Some(pseudowire::Pseudowire::new(pw, a, b)?);
+    }
+    if let Some(ref wg) = cfg.wireguard {
+        let (a, b) = edges(&fabric)?;
+        fabric.wireguard = Some(wireguard::Wireguard::new(wg, a, b)?);
+    }
+    if let Some(ref alarms) = cfg.alarms {
+        fabric.alarms = Some(alarms::AlarmMonitor::new(alarms)?);
+    }
+    if let Some(ref ls) = cfg.link_state {
+        fabric.convergence = Some(routing::link_state::Convergence::new(ls, &fabric));
+    }
+    fabric.distance_vector = distance_vector(&cfg, &fabric);
+    if let Some(ref reassembly) = cfg.reassembly {
+        fabric.reassembly = Some(reassembly::Reassembler::new(reassembly));
+    }
+    if let Some(ref dedupe) = cfg.dedupe {
+        fabric.dedupe = Some(dedupe::Deduplicator::new(dedupe));
+    }
+    if let Some(ref drops) = cfg.drop_capture {
+        fabric.drops = Some(drops::DropCapture::new(drops));
+    }
+    fabric.destination_map = routing::destination_map::DestinationMap::new(&cfg.destination_map)?;
+    if let Some(ref nat) = cfg.nat44 {
+        fabric.nat44 = Some(nat::Nat44::new(nat)?);
+    }
+    if let Some(ref nat) = cfg.nat64 {
+        fabric.nat64 = Some(nat::nat64::Nat64::new(nat)?);
+    }
+    if let Some(ref skew) = cfg.clock_skew {
+        fabric.clocks = clock::EdgeClocks::from_config(skew)?;
+    }
+    if let Some(ref ttl) = cfg.ttl {
+        fabric.ttl = Some(ttl::TtlPolicy::from_config(ttl)?);
+    }
+    if let Some(ref srv6) = cfg.srv6 {
+        fabric.srv6 = Some(srv6::Srv6::new(srv6, &fabric)?);
+    }
+    if let Some(ref path) = cfg.packet_trace {
+        let mut trace = sojourn::PacketTrace::new(path, cfg.packet_trace_format);
+        trace.run = fabric.run.clone();
+        trace.open()?;
+        fabric.packet_trace = Some(trace);
+    }
+    if let Some(ref t) = cfg.telemetry {
+        fabric.telemetry = Some(telemetry::Telemetry::new(t));
+        if let (Some(addr), Some(publisher)) = (&t.dashboard_listen, &fabric.telemetry) {
+            let listener = tokio::net::TcpListener::bind(addr)
+                .await
+                .map_err(|e| format!("Dashboard listen address {}: {}", addr, e))?;
+            let topology = telemetry::dashboard::Topology::of(&fabric);
+            let samples = publisher.subscribe();
+            tokio::spawn(async move {
+                if let Err(e) = telemetry::dashboard::serve(listener, topology, samples).await {
+                    error!("{}", e);
+                }
+            });
         }
-        let a = RouterId(parts[0].to_string());
-        let b = RouterId(parts[1].to_string());
-        if fabric.router_index.contains_key(&a) && fabric.router_index.contains_key(&b) {
-            fabric.add_link(&a, &b, link_cfg.clone());
-        } else {
-            error!("Link {} references unknown router(s)", link_name);
+        #[cfg(feature = "gnmi")]
+        if let (Some(addr), Some(publisher)) = (&t.gnmi_listen, &fabric.telemetry) {
+            let listener = tokio::net::TcpListener::bind(addr)
+                .await
+                .map_err(|e| format!("gNMI listen address {}: {}", addr, e))?;
+            let samples = publisher.subscribe();
+            tokio::spawn(async move {
+                if let Err(e) = telemetry::gnmi::serve(listener, samples).await {
+                    error!("{}", e);
+                }
+            });
         }
     }
+    if let Some(ref snapshot) = cfg.snapshot {
+        fabric.snapshots = Some(snapshot::Snapshots::new(snapshot)?);
+    }
+    for capture in &cfg.captures {
+        let mut point = capture::CapturePoint::from_config(capture)?;
+        point.open()?;
+        fabric.captures.push(point);
+    }
+    for mirror in &cfg.mirrors {
+        let mut port = capture::mirror::MirrorPort::from_config(mirror)?;
+        port.open()?;
+        fabric.mirrors.push(port);
+    }
     info!(
         "Fabric built with {} routers and {} links",
         fabric.router_index.len(),
@@ -104,7 +342,15 @@ pub async fn run(cfg: SimulatorConfig) -> Result<Fabric, Box<dyn std::error::Err
     // Compute routing tables (stub – just logs)
     let ingress_a = RouterId(cfg.tun_ingress.tun_a_ingress.clone());
     let ingress_b = RouterId(cfg.tun_ingress.tun_b_ingress.clone());
-    let tables = routing::compute_routing(&fabric, ingress_a.clone(), ingress_b.clone());
+    let mut tables = routing::compute_routing_seeded(
+        &fabric,
+        ingress_a.clone(),
+        ingress_b.clone(),
+        cfg.simulation.tie_break_seed,
+    );
+    if let Some(dv) = &fabric.distance_vector {
+        dv.install(&mut tables);
+    }
     let multi_tables = if cfg.enable_multipath {
         routing::compute_multi_path_routing(&fabric, ingress_a.clone(), ingress_b.clone())
     } else {
@@ -116,17 +362,25 @@ pub async fn run(cfg: SimulatorConfig) -> Result<Fabric, Box<dyn std::error::Err
         info!("Multipath routing disabled");
     }
 
-    // Demonstration: process a dummy packet at the first router (if any).
-    if let Some((first_router_id, _)) = fabric.router_index.iter().next() {
-        let first_router_id = first_router_id.clone();
+    // Demonstration: process a dummy packet at the tun_a ingress router (or, if that is not
+    // part of the topology, the first router by name) so that runs are reproducible.
+    let first_router = if fabric.router_index.contains_key(&ingress_a) {
+        Some(ingress_a.clone())
+    } else {
+        fabric
+            .router_index
+            .keys()
+            .min_by(|a, b| a.0.cmp(&b.0))
+            .cloned()
+    };
+    if let Some(first_router_id) = first_router {
         let dummy_packet = packet::PacketMeta {
             src_ip: "10.0.0.1".parse().unwrap(),
             dst_ip: "10.0.1.1".parse().unwrap(),
             src_port: 12345,
             dst_port: 80,
             protocol: 6, // TCP
-            ttl: 64,
-            raw: vec![],
+            ..Default::default()
         };
         debug!("Processing dummy packet at router {}", first_router_id.0);
         // Determine destination based on which ingress the router is (simplified):
@@ -135,6 +389,7 @@ pub async fn run(cfg: SimulatorConfig) -> Result<Fabric, Box<dyn std::error::Err
         } else {
             Destination::TunA
         };
+        let before = report::PacketTotals::of(&fabric);
         // Use single-path forwarding for now
         process_packet(
             &mut fabric,
@@ -155,12 +410,50 @@ pub async fn run(cfg: SimulatorConfig) -> Result<Fabric, Box<dyn std::error::Err
             )
             .await;
         }
+        fabric.demonstration = report::PacketTotals::of(&fabric).less(&before);
     }
 
     // Start TUN handling (stub)
     if let Err(e) = tun::start(&cfg, &mut fabric).await {
         error!("Failed to start TUN handling: {}", e);
     }
+    alarms::finish(&mut fabric);
+    snapshot::take(&mut fabric);
+    // Publish the final counters, then end the telemetry subscriptions.
+    telemetry::publish(&mut fabric);
+    if let Some(t) = fabric.telemetry.as_mut() {
+        t.close();
+    }
+    // Evaluate SLA targets against the collected customer flow metrics.
+    for vc in cfg.virtual_customer.iter().flat_map(|vc| vc.flows()) {
+        if let Some(sla_cfg) = &vc.sla {
+            let name = vc.customer_name();
+            let metrics = fabric.customer_flows.get(name).cloned().unwrap_or_default();
+            let result = sla::evaluate(name, sla_cfg, &metrics);
+            info!(
+                "SLA for {}: {}",
+                name,
+                if result.pass() { "PASS" } else { "FAIL" }
+            );
+            fabric.sla_results.push(result);
+        }
+    }
+    capture::finish_all(&mut fabric.captures);
+    for port in &mut fabric.mirrors {
+        port.finish();
+    }
+    if let Some(drops) = &fabric.drops {
+        drops.finish(fabric.run.as_ref());
+    }
+    if let Some(trace) = fabric.packet_trace.as_mut() {
+        trace.finish();
+    }
+    if let (Some(path), Some(mut state)) = (&cfg.counter_state, fabric.counter_state.take()) {
+        state.add(&stats::StatsDump::from_fabric(&fabric));
+        state.write(path)?;
+        info!("Counters of {} run(s) accumulated in {}", state.runs, path);
+        fabric.counter_state = Some(state);
+    }
     info!("Exiting");
     // Print final statistics (always printed; CLI flag may control additional output)
     fabric.print_statistics();
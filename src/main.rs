@@ -1,8 +1,25 @@
 // src/main.rs
 
-use clap::Parser;
-use network_simulator::config::SimulatorConfig;
+use clap::{Parser, Subcommand};
+use network_simulator::asymmetry;
+use network_simulator::bench::{self, BenchOptions};
+use network_simulator::clab;
+use network_simulator::config::{ExitReportConfig, RealTunConfig, SimulatorConfig};
+use network_simulator::experiment;
+use network_simulator::multi;
+use network_simulator::netns;
+use network_simulator::packet;
+use network_simulator::pmtu::{self, PmtuOptions};
+use network_simulator::provenance::{self, RunMetadata};
+use network_simulator::report::{self, ExitReport};
+use network_simulator::routing::export::{self, RoutingExport};
+use network_simulator::stats::{self, StatsDump};
+use network_simulator::stretch;
+use network_simulator::topology::RouterId;
+use network_simulator::traceroute::{self, TraceOptions};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::net::{IpAddr, SocketAddr};
 use std::process;
 use tracing_subscriber::{fmt, EnvFilter};
 
@@ -44,6 +61,311 @@ struct Args {
     /// Print router statistics after simulation ends
     #[arg(long, action = clap::ArgAction::SetTrue, help = "Print router statistics after simulation ends")]
     stats: bool,
+    /// Write router and link queue counters in Prometheus text format after simulation ends
+    #[arg(long, value_name = "FILE")]
+    metrics: Option<String>,
+    /// Write router and link counters as JSON after simulation ends (input of `stats diff`)
+    #[arg(long, value_name = "FILE")]
+    stats_json: Option<String>,
+    /// Write a CSV record per packet with its latency breakdown (overrides config)
+    #[arg(long, value_name = "FILE")]
+    packet_trace: Option<String>,
+    /// Format of the packet trace: `csv`, or `json` for one JSON object per packet (overrides
+    /// config)
+    #[arg(long, value_name = "FORMAT", value_parser = ["csv", "json"])]
+    packet_trace_format: Option<String>,
+    /// Abort with a report when forwarding sends a packet to a next hop that is not closer to
+    /// its destination by the routing tables' distances
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    paranoid: bool,
+    /// Start from the link parameters, link states and router maintenance states of a snapshot
+    /// written with `[snapshot]`
+    #[arg(long, value_name = "FILE")]
+    restore_snapshot: Option<String>,
+    /// Add the run's counters to this JSON file, so repeated runs accumulate them (overrides
+    /// config)
+    #[arg(long, value_name = "FILE")]
+    counter_state: Option<String>,
+    /// Start the counter state file over instead of adding to the counters it holds
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    fresh: bool,
+    /// Write a JSON report of the packets, SLA verdicts, alarms and failures of the run
+    /// (overrides config)
+    #[arg(long, value_name = "FILE")]
+    exit_report: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run an iperf3‑style throughput test across the fabric via the real TUN devices
+    Bench(BenchArgs),
+    /// Run the scenario twice (A = --config, B = --config-b and/or --set overrides) and
+    /// compare latency, loss and throughput
+    Compare(CompareArgs),
+    /// Run the scenario for every combination of the swept parameters and emit CSV
+    Sweep(SweepArgs),
+    /// Originate traceroute probes from a router and print the hops that answered
+    Traceroute(TracerouteArgs),
+    /// Discover the path MTU towards an address behind one of the edges
+    Pmtu(PmtuArgs),
+    /// Compare the forward and reverse path between every pair of edge prefixes and flag
+    /// pairs that cross different routers or costs
+    Asymmetry,
+    /// Compare the path of every router towards each edge with the cheapest and the shortest
+    /// one and report the stretch
+    Stretch,
+    /// Decode hex‑encoded packets (arguments, or the lines of a packet file) and print them
+    Decode(DecodeArgs),
+    /// Compare two packet files (e.g. a packet file and its `_out.txt`) packet by packet and
+    /// print the header fields and payload bytes that changed
+    Diff(DiffArgs),
+    /// Run every `[fabrics.<name>]` instance of the --config scenario file side by side, each
+    /// with its own statistics, routing and random sequence
+    Multi,
+    /// Work with JSON statistics dumps written by --stats-json
+    Stats(StatsArgs),
+    /// Convert the --config topology to a containerlab topology with tc/netem impairments, or
+    /// a containerlab topology back to a configuration
+    Clab(ClabArgs),
+    /// Index the outputs of past runs by the run metadata stamped on them
+    Runs(RunsArgs),
+    /// Dump the routing tables of the --config scenario, or compare two dumps
+    Routes(RoutesArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct RoutesArgs {
+    #[command(subcommand)]
+    command: RoutesCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum RoutesCommand {
+    /// Write every router's single‑path, static and multipath (with --multipath) routes
+    Export {
+        #[arg(long, default_value = "json", value_parser = ["json", "csv"])]
+        format: String,
+        /// Write to FILE instead of standard output
+        #[arg(long, value_name = "FILE")]
+        output: Option<String>,
+    },
+    /// Print the routes whose next hops differ from export A to export B
+    Diff {
+        #[arg(value_name = "A")]
+        a: String,
+        #[arg(value_name = "B")]
+        b: String,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct RunsArgs {
+    #[command(subcommand)]
+    command: RunsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum RunsCommand {
+    /// List the stamped outputs in a directory grouped by run, oldest first
+    List {
+        #[arg(value_name = "DIR", default_value = ".")]
+        dir: String,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct ClabArgs {
+    #[command(subcommand)]
+    command: ClabCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ClabCommand {
+    /// Write the containerlab topology of the --config topology
+    Export {
+        /// Lab name
+        #[arg(long, default_value = "network-simulator")]
+        name: String,
+        /// Container image of the router nodes (needs `tc` and `ip`)
+        #[arg(long, default_value = "frrouting/frr:latest")]
+        image: String,
+        /// Write the topology to this file instead of stdout
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Print the `[tun_ingress]` and `[topology]` configuration of a containerlab topology
+    Import {
+        #[arg(value_name = "FILE")]
+        file: String,
+        /// Write the configuration to this file instead of stdout
+        #[arg(long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct StatsArgs {
+    #[command(subcommand)]
+    command: StatsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum StatsCommand {
+    /// Print per‑router and per‑link deltas and percentage changes from dump A to dump B
+    Diff {
+        #[arg(value_name = "A")]
+        a: String,
+        #[arg(value_name = "B")]
+        b: String,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct DecodeArgs {
+    /// Hex‑encoded packets
+    #[arg(value_name = "HEX")]
+    packets: Vec<String>,
+    /// Packet file to decode line by line (comments and blank lines are skipped)
+    #[arg(long, value_name = "FILE")]
+    file: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// Packet file with the packets before
+    #[arg(value_name = "A")]
+    a: String,
+    /// Packet file with the packets after
+    #[arg(value_name = "B")]
+    b: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct TracerouteArgs {
+    /// Router that originates the probes, e.g. `Rx1y1`
+    #[arg(long)]
+    from: String,
+    /// Destination address (behind tun_a or tun_b)
+    #[arg(long)]
+    to: IpAddr,
+    /// Highest TTL probed
+    #[arg(long, default_value_t = 16)]
+    max_ttl: u8,
+    /// Probe with ICMP echo requests instead of UDP
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    icmp: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct PmtuArgs {
+    /// Destination address (behind tun_a or tun_b); probes start at the other edge
+    #[arg(long)]
+    to: IpAddr,
+    /// Smallest probe size (default: 68 for IPv4, 1280 for IPv6)
+    #[arg(long)]
+    min: Option<u32>,
+    /// Largest probe size
+    #[arg(long, default_value_t = 9216)]
+    max: u32,
+}
+
+#[derive(clap::Args, Debug)]
+struct SweepArgs {
+    /// Swept key, e.g. `topology.links.Rx0y0_Rx0y1.delay_ms=0:50:10` or `...loss_percent=0,1,5`
+    #[arg(long = "param", value_name = "KEY=RANGE", required = true, action = clap::ArgAction::Append)]
+    params: Vec<String>,
+    /// Number of runs executed concurrently
+    #[arg(long, default_value_t = 1)]
+    jobs: usize,
+    /// Run on a paused (virtual) clock so simulated delays take no wall time
+    #[cfg(feature = "virtual-time")]
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    virtual_time: bool,
+    /// Write the CSV to this file instead of stdout
+    #[arg(long)]
+    output: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct CompareArgs {
+    /// Configuration for run B (defaults to the run A configuration)
+    #[arg(long)]
+    config_b: Option<String>,
+    /// Override applied to run B, e.g. `topology.links.Rx0y0_Rx0y1.delay_ms=20` (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE", action = clap::ArgAction::Append)]
+    overrides: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct BenchArgs {
+    /// Test duration in seconds
+    #[arg(long, default_value_t = 10)]
+    duration: u64,
+    /// Number of parallel streams
+    #[arg(long, default_value_t = 1)]
+    parallel: u32,
+    /// Use UDP instead of TCP
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    udp: bool,
+    /// Target bitrate per UDP stream in Mbit/s
+    #[arg(long, default_value_t = 10.0)]
+    bitrate: f64,
+    /// Seconds between periodic reports
+    #[arg(long, default_value_t = 1.0)]
+    interval: f64,
+    /// Length of the write buffer / UDP datagram
+    #[arg(long)]
+    length: Option<usize>,
+    /// Port used by the sink and the streams
+    #[arg(long, default_value_t = 5201)]
+    port: u16,
+}
+
+/// Namespaces the bench endpoints use when the edges do not name their own.
+const BENCH_NETNS: [&str; 2] = ["nsim-bench-a", "nsim-bench-b"];
+
+/// Write the exit report of a run stopped by `error` to `path`, if one was asked for.
+fn report_error(path: Option<&str>, error: &dyn std::fmt::Display, run: Option<RunMetadata>) {
+    let Some(path) = path.filter(|p| !p.is_empty()) else {
+        return;
+    };
+    if let Err(e) = ExitReport::error(error, run).write(path) {
+        eprintln!("Error: {}", e);
+    }
+}
+
+impl BenchArgs {
+    /// The streams start at the real_tun_a address and the sink listens on the real_tun_b
+    /// address, each inside its edge's namespace, so the traffic has to cross the fabric.
+    fn to_options(&self, cfg: &SimulatorConfig) -> Result<BenchOptions, String> {
+        let address = |iface: &RealTunConfig| {
+            if iface.tap {
+                return Err(format!("bench cannot run on TAP edge {}", iface.name));
+            }
+            iface
+                .address
+                .parse::<IpAddr>()
+                .map_err(|_| format!("bench needs an IP address on {}", iface.name))
+        };
+        let bind = address(&cfg.interfaces.real_tun_a)?;
+        let target = SocketAddr::new(address(&cfg.interfaces.real_tun_b)?, self.port);
+        Ok(BenchOptions {
+            duration_secs: self.duration,
+            parallel: self.parallel,
+            udp: self.udp,
+            bitrate_mbps: self.bitrate,
+            interval_secs: self.interval,
+            length: self.length,
+            listen: target,
+            target,
+            bind: Some(bind),
+            sink_netns: cfg.interfaces.real_tun_b.netns.clone(),
+            sender_netns: cfg.interfaces.real_tun_a.netns.clone(),
+        })
+    }
 }
 
 #[tokio::main]
@@ -58,8 +380,169 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
     fmt::Subscriber::builder().with_env_filter(filter).init();
 
-    let cfg_str = fs::read_to_string(&args.config)?;
-    let mut cfg: SimulatorConfig = toml::from_str(&cfg_str)?;
+    // Decoding needs no configuration.
+    if let Some(Command::Decode(ref decode)) = args.command {
+        let mut lines = decode.packets.clone();
+        if let Some(ref path) = decode.file {
+            lines.extend(fs::read_to_string(path)?.lines().map(str::to_string));
+        }
+        let mut failed = false;
+        for (idx, line) in lines.iter().map(|l| l.trim()).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match hex::decode(line) {
+                Ok(bytes) => println!("#{}\n{}", idx + 1, packet::explain(&bytes)),
+                Err(e) => {
+                    eprintln!("#{}: invalid hex: {}", idx + 1, e);
+                    failed = true;
+                }
+            }
+        }
+        if failed {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Neither does comparing packet files.
+    if let Some(Command::Diff(ref d)) = args.command {
+        let load = |path: &str| -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+            let mut packets = Vec::new();
+            for line in fs::read_to_string(path)?.lines().map(str::trim) {
+                if !line.is_empty() && !line.starts_with('#') {
+                    packets.push(hex::decode(line).map_err(|e| format!("{}: {}", path, e))?);
+                }
+            }
+            Ok(packets)
+        };
+        let (a, b) = (load(&d.a)?, load(&d.b)?);
+        for (idx, (before, after)) in a.iter().zip(&b).enumerate() {
+            print!("#{}\n{}", idx + 1, packet::diff(before, after));
+        }
+        if a.len() != b.len() {
+            println!("{} packets in {}, {} in {}", a.len(), d.a, b.len(), d.b);
+        }
+        return Ok(());
+    }
+
+    // Comparing statistics dumps needs no configuration either.
+    if let Some(Command::Stats(ref st)) = args.command {
+        let StatsCommand::Diff { ref a, ref b } = st.command;
+        let (a, b) = (StatsDump::load(a)?, StatsDump::load(b)?);
+        print!("{}", stats::diff(&a, &b));
+        return Ok(());
+    }
+
+    // Nor does comparing routing table exports.
+    if let Some(Command::Routes(RoutesArgs {
+        command: RoutesCommand::Diff { ref a, ref b },
+    })) = args.command
+    {
+        let (a, b) = (RoutingExport::load(a)?, RoutingExport::load(b)?);
+        print!("{}", export::render_diff(&export::diff(&a, &b)));
+        return Ok(());
+    }
+
+    // Neither does indexing past outputs.
+    if let Some(Command::Runs(ref runs)) = args.command {
+        let RunsCommand::List { ref dir } = runs.command;
+        let runs = provenance::index(std::path::Path::new(dir))?;
+        if runs.is_empty() {
+            println!("No stamped outputs in {}", dir);
+        }
+        print!("{}", provenance::render_index(&runs));
+        return Ok(());
+    }
+
+    // Importing a containerlab topology produces a configuration rather than reading one.
+    if let Some(Command::Clab(ClabArgs {
+        command: ClabCommand::Import {
+            ref file,
+            ref output,
+        },
+    })) = args.command
+    {
+        let lab: clab::Lab = serde_yaml::from_str(&fs::read_to_string(file)?)?;
+        let imported = clab::import(&lab)?;
+        for note in &imported.notes {
+            eprintln!("Warning: {}", note);
+        }
+        match output {
+            Some(path) => fs::write(path, &imported.output)?,
+            None => print!("{}", imported.output),
+        }
+        return Ok(());
+    }
+
+    let cfg_str = fs::read_to_string(&args.config)
+        .inspect_err(|e| report_error(args.exit_report.as_deref(), e, None))?;
+    // A/B comparison works on the raw configuration so overrides can be applied before parsing.
+    if let Some(Command::Compare(ref compare)) = args.command {
+        let value_a: toml::Value = toml::from_str(&cfg_str)?;
+        let mut value_b = match compare.config_b {
+            Some(ref path) => toml::from_str(&fs::read_to_string(path)?)?,
+            None => value_a.clone(),
+        };
+        for assignment in &compare.overrides {
+            experiment::apply_override(&mut value_b, assignment)?;
+        }
+        let a = experiment::run_scenario(value_a, args.multipath).await?;
+        let b = experiment::run_scenario(value_b, args.multipath).await?;
+        print!("{}", experiment::compare_report("A", &a, "B", &b));
+        return Ok(());
+    }
+    // Multi‑fabric scenario files hold complete configurations in named sections.
+    // Each fabric writes the exit report its `[exit_report]` asks for; `--exit-report` gets
+    // one of them all.
+    if let Some(Command::Multi) = args.command {
+        let report_path = args.exit_report.as_deref();
+        let instances = multi::parse_scenario(&cfg_str)
+            .and_then(|instances| multi::validate(&instances).map(|_| instances))
+            .inspect_err(|e| report_error(report_path, e, None))?;
+        let reports: HashMap<String, Option<ExitReportConfig>> = instances
+            .iter()
+            .map(|i| (i.name.clone(), i.cfg.exit_report.clone()))
+            .collect();
+        let results = multi::run(instances, args.multipath)
+            .await
+            .inspect_err(|e| report_error(report_path, e, None))?;
+        print!("{}", multi::report(&results));
+        let mut outcomes = BTreeMap::new();
+        for (name, fabric) in &results {
+            for result in &fabric.sla_results {
+                print!("{}", result.render());
+            }
+            for report in &fabric.paranoid_violations {
+                println!("{}", report);
+            }
+            let fail_on_alarm = match reports[name] {
+                Some(ref cfg) => report::fail_on_alarm(cfg)?,
+                None => None,
+            };
+            let outcome = ExitReport::of(fabric, fail_on_alarm);
+            if let Some(ref cfg) = reports[name] {
+                if let Err(e) = outcome.write(&cfg.path) {
+                    eprintln!("Error: {}", e);
+                    process::exit(report::EXIT_ERROR);
+                }
+            }
+            outcomes.insert(name.clone(), outcome);
+        }
+        let outcome = ExitReport::combine(outcomes);
+        if let Some(path) = report_path {
+            if let Err(e) = outcome.write(path) {
+                eprintln!("Error: {}", e);
+                process::exit(report::EXIT_ERROR);
+            }
+        }
+        if !outcome.passed {
+            process::exit(outcome.exit_code);
+        }
+        return Ok(());
+    }
+    let mut cfg: SimulatorConfig = toml::from_str(&cfg_str)
+        .inspect_err(|e| report_error(args.exit_report.as_deref(), e, None))?;
     cfg.enable_multipath = args.multipath;
     // Override real TUN config if CLI options provided
     if let Some(name) = args.tun_name {
@@ -79,33 +562,332 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if let Some(pfs) = args.packet_files {
         cfg.packet_files = Some(pfs);
     }
+    if let Some(path) = args.packet_trace {
+        cfg.packet_trace = Some(path);
+    }
+    if let Some(format) = args.packet_trace_format {
+        cfg.packet_trace_format = toml::Value::String(format).try_into()?;
+    }
+    if args.paranoid {
+        cfg.paranoid = true;
+    }
+    if let Some(path) = args.counter_state {
+        cfg.counter_state = Some(path);
+    }
+    cfg.fresh = args.fresh;
+    if let Some(path) = args.exit_report {
+        let report = cfg.exit_report.get_or_insert_with(Default::default);
+        report.path = path;
+    }
+    let report_path = cfg.exit_report.as_ref().map(|r| r.path.clone());
+    if let Some(path) = args.restore_snapshot {
+        network_simulator::snapshot::Snapshot::load(std::path::Path::new(&path))
+            .and_then(|snapshot| snapshot.apply(&mut cfg))
+            .inspect_err(|e| report_error(report_path.as_deref(), e, None))?;
+    }
     // Validate configuration
-    cfg.validate()?;
+    cfg.validate()
+        .inspect_err(|e| report_error(report_path.as_deref(), e, None))?;
     // Initialize RNG with seed if provided
     if let Some(seed) = cfg.simulation.seed {
         network_simulator::simulation::init_rng(seed);
     }
+    // Stamp the outputs with where they came from.
+    cfg.run = Some(RunMetadata::new(
+        &args.config,
+        &cfg_str,
+        cfg.simulation.seed,
+        std::env::args().collect(),
+    ));
+    // Benchmark mode: run the simulator and the throughput test side by side.
+    if let Some(Command::Bench(ref bench_args)) = args.command {
+        // Edges without a namespace get a temporary one for the duration of the test.
+        let mut created = Vec::new();
+        for (iface, ns) in [
+            &mut cfg.interfaces.real_tun_a,
+            &mut cfg.interfaces.real_tun_b,
+        ]
+        .into_iter()
+        .zip(BENCH_NETNS)
+        {
+            let ns = iface.netns.get_or_insert_with(|| ns.to_string()).clone();
+            if netns::ensure(&ns)? {
+                created.push(ns);
+            }
+        }
+        let opts = bench_args.to_options(&cfg)?;
+        let result = tokio::select! {
+            res = network_simulator::run(cfg) => Err(match res {
+                Err(e) => format!("{}; simulator stopped before the benchmark completed", e),
+                Ok(_) => "simulator stopped before the benchmark completed".to_string(),
+            }),
+            res = bench::run(&opts) => res.map_err(|e| format!("benchmark failed: {}", e)),
+        };
+        for ns in &created {
+            if let Err(e) = netns::remove(ns) {
+                eprintln!("Warning: {}", e);
+            }
+        }
+        match result {
+            Ok(report) => print!("{}", report.render()),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    if let Some(Command::Traceroute(ref tr)) = args.command {
+        let origin = RouterId(tr.from.clone());
+        let opts = TraceOptions {
+            max_ttl: tr.max_ttl,
+            icmp: tr.icmp,
+        };
+        let hops = traceroute::run(&cfg, &origin, tr.to, &opts).await?;
+        print!("{}", traceroute::render(&origin, tr.to, tr.max_ttl, &hops));
+        return Ok(());
+    }
+    if let Some(Command::Pmtu(ref pm)) = args.command {
+        let opts = PmtuOptions {
+            min: pm.min,
+            max: pm.max,
+        };
+        let report = pmtu::run(&cfg, pm.to, &opts).await?;
+        print!("{}", report.render(pm.to));
+        return Ok(());
+    }
+    if let Some(Command::Clab(ClabArgs {
+        command:
+            ClabCommand::Export {
+                ref name,
+                ref image,
+                ref output,
+            },
+    })) = args.command
+    {
+        let exported = clab::export(&cfg, name, image);
+        for note in &exported.notes {
+            eprintln!("Warning: {}", note);
+        }
+        let yaml = serde_yaml::to_string(&exported.output)?;
+        match output {
+            Some(path) => fs::write(path, yaml)?,
+            None => print!("{}", yaml),
+        }
+        return Ok(());
+    }
+    if let Some(Command::Asymmetry) = args.command {
+        let pairs = asymmetry::analyze(&cfg)?;
+        print!("{}", asymmetry::render(&pairs));
+        return Ok(());
+    }
+    if let Some(Command::Stretch) = args.command {
+        print!("{}", stretch::render(&stretch::analyze(&cfg)));
+        return Ok(());
+    }
+    if let Some(Command::Routes(RoutesArgs {
+        command: RoutesCommand::Export {
+            ref format,
+            ref output,
+        },
+    })) = args.command
+    {
+        let exported = RoutingExport::new(
+            &network_simulator::compute_routing_tables(&cfg),
+            &network_simulator::compute_multipath_tables(&cfg),
+        );
+        let text = match format.as_str() {
+            "csv" => exported.to_csv(),
+            _ => exported.to_json(),
+        };
+        match output {
+            Some(path) => fs::write(path, text)?,
+            None => print!("{}", text),
+        }
+        return Ok(());
+    }
+    if let Some(Command::Sweep(sweep)) = args.command {
+        let base: toml::Value = toml::from_str(&cfg_str)?;
+        let params = sweep
+            .params
+            .iter()
+            .map(|p| experiment::parse_sweep_param(p))
+            .collect::<Result<Vec<_>, _>>()?;
+        let multipath = args.multipath;
+        #[cfg(feature = "virtual-time")]
+        let results = if sweep.virtual_time {
+            let params = params.clone();
+            tokio::task::spawn_blocking(move || {
+                experiment::run_sweep_virtual_time(&base, &params, sweep.jobs, multipath)
+            })
+            .await??
+        } else {
+            experiment::run_sweep(&base, &params, sweep.jobs, multipath).await?
+        };
+        #[cfg(not(feature = "virtual-time"))]
+        let results = experiment::run_sweep(&base, &params, sweep.jobs, multipath).await?;
+        let csv = experiment::sweep_csv(&params, &results);
+        match sweep.output {
+            Some(ref path) => fs::write(path, csv)?,
+            None => print!("{}", csv),
+        }
+        return Ok(());
+    }
     // Run simulation and capture the fabric
+    let exit_report = cfg.exit_report.clone();
+    let run = cfg.run.clone();
     let fabric = match network_simulator::run(cfg).await {
         Ok(fab) => fab,
         Err(e) => {
             eprintln!("Error: {}", e);
-            process::exit(1);
+            report_error(report_path.as_deref(), &e, run);
+            process::exit(report::EXIT_ERROR);
         }
     };
+    if let Some(ref path) = args.metrics {
+        if let Err(e) = network_simulator::metrics::write(&fabric, path) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+    if let Some(ref path) = args.stats_json {
+        if let Err(e) = stats::write(&fabric, path) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
     // If --stats flag is set, print router statistics
     if args.stats {
         println!("Router statistics after simulation:");
         for (router_id, stats) in fabric.get_statistics() {
             println!(
-                "Router {}: recv={}, fwd={}, icmp={}, lost={}",
+                "Router {}: injected={}, recv={} ({} bytes; tcp={}, udp={}, icmp={}, other={}), fwd={} ({} bytes), icmp={}, icmp_suppressed={}, lost={}, delivered={}, cpu_drops={}, acl_drops={}, policer_drops={}, bad_checksums={}",
                 router_id.0,
+                stats.packets_injected,
                 stats.packets_received,
+                stats.bytes_received,
+                stats.tcp_received,
+                stats.udp_received,
+                stats.icmp_received,
+                stats.other_received,
                 stats.packets_forwarded,
+                stats.bytes_forwarded,
                 stats.icmp_generated,
-                stats.packets_lost
+                stats.icmp_suppressed,
+                stats.packets_lost,
+                stats.packets_delivered,
+                stats.cpu_drops,
+                stats.acl_drops,
+                stats.policer_drops,
+                stats.bad_checksums
             );
         }
+        for link in fabric.graph.edge_weights() {
+            if link.flaps > 0 || !link.is_up() {
+                println!(
+                    "Link {}_{}: state={}, flaps={}",
+                    link.id.a.0,
+                    link.id.b.0,
+                    link.state().as_str(),
+                    link.flaps
+                );
+            }
+            for (direction, queues) in link.queue_stats() {
+                for queue in queues {
+                    println!("Link {} queue {}", direction, queue.summary());
+                }
+            }
+        }
+        for (name, iface) in fabric.tun_counters.iter().flat_map(|c| c.all()) {
+            println!("Interface {}: {}", name, iface.summary());
+        }
+        for port in &fabric.mirrors {
+            println!("Mirror {}", port.summary());
+        }
+        for pool in fabric.pools.values() {
+            for queue in pool.stats() {
+                println!("Pool {} queue {}", pool.name, queue.summary());
+            }
+        }
+        if let Some(ref report) = fabric.twamp_report {
+            println!("TWAMP: {}", report.summary());
+        }
+        if let Some(ref report) = fabric.ddos_report {
+            println!("DDoS flood: {}", report.summary());
+        }
+        if let Some(ref marking) = fabric.marking {
+            println!("Marking: {}", marking.summary());
+        }
+        if let Some(ref pw) = fabric.pseudowire {
+            println!("Pseudowire: {}", pw.summary());
+        }
+        if let Some(ref wg) = fabric.wireguard {
+            println!("WireGuard: {}", wg.summary());
+        }
+        if let Some(ref srv6) = fabric.srv6 {
+            println!("SRv6: {}", srv6.stats.summary());
+        }
+        if let Some(ref reassembly) = fabric.reassembly {
+            println!("Reassembly: {}", reassembly.stats.summary());
+        }
+        if let Some(ref dedupe) = fabric.dedupe {
+            println!("Dedupe: {}", dedupe.stats.summary());
+        }
+        if let Some(ref nat) = fabric.nat44 {
+            println!("NAT44: {}", nat.stats.summary());
+        }
+        if let Some(ref nat) = fabric.nat64 {
+            println!("NAT64: {}", nat.stats.summary());
+        }
+        if let Some(ref convergence) = fabric.convergence {
+            for episode in &convergence.episodes {
+                println!("Convergence: {}", episode.summary());
+            }
+        }
+        if let Some(ref dv) = fabric.distance_vector {
+            for episode in &dv.episodes {
+                println!("Convergence: {}", episode.summary());
+            }
+        }
+        if let Some(ref drops) = fabric.drops {
+            println!("Drops: {}", drops.summary());
+            for (router, drop) in drops.all() {
+                println!("Drop {}", drop.render(router));
+            }
+        }
+        if let Some(ref alarms) = fabric.alarms {
+            println!("Alarms: {}", alarms.summary());
+            for event in &alarms.events {
+                println!("Alarm {}", event.render());
+            }
+        }
+        #[cfg(feature = "http-test")]
+        if let Some(ref report) = fabric.http_report {
+            println!("HTTP load: {}", report.summary());
+        }
+    }
+    // SLA report is always printed when targets were declared; a violation fails the run, as
+    // does a hop `--paranoid` found contradicting the routing tables or an alarm the exit
+    // report is told to fail on.
+    for result in &fabric.sla_results {
+        print!("{}", result.render());
+    }
+    for violation in &fabric.paranoid_violations {
+        println!("{}", violation);
+    }
+    let fail_on_alarm = match exit_report {
+        Some(ref cfg) => report::fail_on_alarm(cfg)?,
+        None => None,
+    };
+    let outcome = ExitReport::of(&fabric, fail_on_alarm);
+    if let Some(ref cfg) = exit_report {
+        if let Err(e) = outcome.write(&cfg.path) {
+            eprintln!("Error: {}", e);
+            process::exit(report::EXIT_ERROR);
+        }
+    }
+    if !outcome.passed {
+        process::exit(outcome.exit_code);
     }
     Ok(())
 }
@@ -0,0 +1,238 @@
+// src/marking/mod.rs
+
+//! Packet coloring: sequence‑number stamps applied at ingress and verified at egress.
+//!
+//! Every packet entering the fabric from an edge gets a per‑direction sequence number, written
+//! into the IPv4 Identification field or into an IPv6 Destination Options header (experimental
+//! option type 0x1E, RFC 4727). When the packet leaves the fabric the stamp is read back and,
+//! unless disabled, removed again (the original IPv4 ID is restored). This gives loss accounting
+//! for any traffic, not only for packets the simulator generated itself. IPv4 fragments are left
+//! alone, since their ID ties the fragments together.
+//!
+//! Stamps wrap (16 bits on IPv4), so a stamp still outstanding [`WINDOW`] packets later is
+//! written off as lost; this also bounds the state kept per direction.
+
+use crate::packet::{update_ipv4_checksum, PacketMeta};
+use crate::routing::Destination;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+
+/// IPv6 Destination Options next‑header value.
+const NH_DEST_OPTS: u8 = 60;
+/// Experimental option type (action bits 00: skip if unrecognised).
+const OPT_TYPE: u8 = 0x1E;
+/// Size of the inserted Destination Options header: 2 header + 2 option TLV + 4 sequence.
+const DEST_OPTS_LEN: usize = 8;
+/// Stamps outstanding for this many newer packets count as lost (the IPv4 stamp space).
+pub const WINDOW: u64 = 1 << 16;
+
+/// Counters for one direction of travel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarkingStats {
+    pub stamped: u64,
+    pub delivered: u64,
+    /// Stamped packets that never reached the far edge.
+    pub lost: u64,
+}
+
+impl MarkingStats {
+    pub fn summary(&self) -> String {
+        format!(
+            "stamped={}, delivered={}, lost={}",
+            self.stamped, self.delivered, self.lost
+        )
+    }
+}
+
+/// Stamp state of one direction.
+#[derive(Debug, Default)]
+pub struct Marker {
+    next_seq: u64,
+    /// On‑wire stamp -> (full sequence number, original IPv4 ID).
+    in_flight: HashMap<u32, (u64, u16)>,
+    /// Stamps handed out within the last `WINDOW` packets, oldest first.
+    recent: VecDeque<(u64, u32)>,
+    stats: MarkingStats,
+}
+
+fn key_for(packet: &PacketMeta, seq: u64) -> u32 {
+    match packet.src_ip {
+        IpAddr::V4(_) => (seq & 0xFFFF) as u32,
+        IpAddr::V6(_) => seq as u32,
+    }
+}
+
+/// Offset of the header after the IPv6 fixed header (and a Hop‑by‑Hop header, if present),
+/// together with the offset of the next‑header byte that points at it.
+fn ipv6_insert_point(raw: &[u8]) -> Option<(usize, usize)> {
+    if raw.len() < 40 {
+        return None;
+    }
+    if raw[6] == 0 {
+        let len = (*raw.get(41)? as usize + 1) * 8;
+        (raw.len() >= 40 + len).then_some((40 + len, 40))
+    } else {
+        Some((40, 6))
+    }
+}
+
+impl Marker {
+    /// Stamp `packet` with the next sequence number. Returns `false` (and leaves the packet
+    /// untouched) if it is not a well‑formed IPv4/IPv6 packet.
+    pub fn stamp(&mut self, packet: &mut PacketMeta) -> bool {
+        let seq = self.next_seq;
+        let key = key_for(packet, seq);
+        let src_ip = packet.src_ip;
+        let mut raw = packet.raw_mut();
+        let raw = &mut *raw;
+        let original_id = match src_ip {
+            IpAddr::V4(_) => {
+                // More‑fragments set or a non‑zero offset: the ID must stay as it is.
+                if raw.len() < 20 || u16::from_be_bytes([raw[6], raw[7]]) & 0x3FFF != 0 {
+                    return false;
+                }
+                let id = u16::from_be_bytes([raw[4], raw[5]]);
+                raw[4..6].copy_from_slice(&(key as u16).to_be_bytes());
+                update_ipv4_checksum(raw);
+                id
+            }
+            IpAddr::V6(_) => {
+                let Some((at, nh_at)) = ipv6_insert_point(raw) else {
+                    return false;
+                };
+                let mut opts = vec![raw[nh_at], 0, OPT_TYPE, 4];
+                opts.extend_from_slice(&key.to_be_bytes());
+                raw[nh_at] = NH_DEST_OPTS;
+                let payload = u16::from_be_bytes([raw[4], raw[5]]) as usize + DEST_OPTS_LEN;
+                raw[4..6].copy_from_slice(&(payload as u16).to_be_bytes());
+                raw.splice(at..at, opts);
+                0
+            }
+        };
+        self.next_seq += 1;
+        self.expire(seq);
+        self.in_flight.insert(key, (seq, original_id));
+        self.recent.push_back((seq, key));
+        self.stats.stamped += 1;
+        true
+    }
+
+    /// Write off stamps that are `WINDOW` or more packets older than `seq`, before their
+    /// on‑wire value can be handed out again.
+    fn expire(&mut self, seq: u64) {
+        while let Some(&(old, key)) = self.recent.front() {
+            if old + WINDOW > seq {
+                break;
+            }
+            self.recent.pop_front();
+            if self.in_flight.get(&key).is_some_and(|(s, _)| *s == old) {
+                self.in_flight.remove(&key);
+                self.stats.lost += 1;
+            }
+        }
+    }
+
+    /// Read the stamp of a packet that reached the far edge and account for it. With `strip`
+    /// the stamp is removed again. Returns the sequence number if the packet carried a stamp
+    /// handed out by this marker.
+    pub fn verify(&mut self, packet: &mut PacketMeta, strip: bool) -> Option<u64> {
+        let raw = &packet.raw;
+        let key = match packet.src_ip {
+            IpAddr::V4(_) if raw.len() >= 20 => u16::from_be_bytes([raw[4], raw[5]]) as u32,
+            IpAddr::V6(_) => {
+                let (at, _) = ipv6_insert_point(raw)?;
+                let nh_at = if at == 40 { 6 } else { 40 };
+                if raw[nh_at] != NH_DEST_OPTS
+                    || raw.len() < at + DEST_OPTS_LEN
+                    || raw[at + 1] != 0
+                    || raw[at + 2] != OPT_TYPE
+                    || raw[at + 3] != 4
+                {
+                    return None;
+                }
+                u32::from_be_bytes([raw[at + 4], raw[at + 5], raw[at + 6], raw[at + 7]])
+            }
+            _ => return None,
+        };
+        let (seq, original_id) = self.in_flight.remove(&key)?;
+        self.stats.delivered += 1;
+        if strip {
+            let src_ip = packet.src_ip;
+            let mut raw = packet.raw_mut();
+            let raw = &mut *raw;
+            match src_ip {
+                IpAddr::V4(_) => {
+                    raw[4..6].copy_from_slice(&original_id.to_be_bytes());
+                    update_ipv4_checksum(raw);
+                }
+                IpAddr::V6(_) => {
+                    if let Some((at, _)) = ipv6_insert_point(raw) {
+                        let nh_at = if at == 40 { 6 } else { 40 };
+                        raw[nh_at] = raw[at];
+                        let payload =
+                            (u16::from_be_bytes([raw[4], raw[5]]) as usize).saturating_sub(8);
+                        raw[4..6].copy_from_slice(&(payload as u16).to_be_bytes());
+                        raw.drain(at..at + DEST_OPTS_LEN);
+                    }
+                }
+            }
+        }
+        Some(seq)
+    }
+
+    /// Counters so far; stamps still outstanding count as lost.
+    pub fn stats(&self) -> MarkingStats {
+        MarkingStats {
+            lost: self.stats.lost + self.in_flight.len() as u64,
+            ..self.stats.clone()
+        }
+    }
+
+    /// Stamps handed out and neither delivered nor written off yet.
+    pub fn outstanding(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Forget all counters and outstanding stamps (end of the warm‑up phase).
+    pub fn reset(&mut self) {
+        self.in_flight.clear();
+        self.recent.clear();
+        self.stats = MarkingStats::default();
+    }
+}
+
+/// Markers for both directions of travel.
+#[derive(Debug, Default)]
+pub struct Marking {
+    /// Restore the original packet at egress.
+    pub strip: bool,
+    /// Packets travelling from tun_a to tun_b.
+    pub a_to_b: Marker,
+    /// Packets travelling from tun_b to tun_a.
+    pub b_to_a: Marker,
+}
+
+impl Marking {
+    pub fn new(strip: bool) -> Self {
+        Self {
+            strip,
+            ..Self::default()
+        }
+    }
+
+    /// Marker for packets heading towards `destination`.
+    pub fn marker_mut(&mut self, destination: Destination) -> &mut Marker {
+        match destination {
+            Destination::TunB => &mut self.a_to_b,
+            Destination::TunA => &mut self.b_to_a,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "a->b: {}; b->a: {}",
+            self.a_to_b.stats().summary(),
+            self.b_to_a.stats().summary()
+        )
+    }
+}
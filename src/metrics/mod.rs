@@ -0,0 +1,202 @@
+// src/metrics/mod.rs
+
+//! Prometheus text exposition of the run's counters.
+//!
+//! `--metrics <FILE>` writes the router and per‑class link queue counters (and, with
+//! `[alarms]`, the alarms raised per severity) after the simulation ends, in the format read by
+//! the node_exporter textfile collector, so a finished run can be scraped alongside live systems.
+
+use crate::alarms::Severity;
+use crate::qos::QueueStats;
+use crate::topology::{Fabric, RouterStats};
+use std::fmt::Write;
+
+/// Metric name, help text and the counter it reads.
+type Family<S> = (&'static str, &'static str, fn(&S) -> u64);
+
+/// Append one metric family with its samples.
+fn family(out: &mut String, name: &str, help: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+/// Label values may not contain unescaped quotes or backslashes.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Counters of `fabric` in Prometheus text format.
+pub fn render(fabric: &Fabric) -> String {
+    let mut routers: Vec<_> = fabric.get_statistics().into_iter().collect();
+    routers.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+    let router_metric = |get: fn(&RouterStats) -> u64| -> Vec<(String, f64)> {
+        routers
+            .iter()
+            .map(|(id, stats)| (format!("router=\"{}\"", escape(&id.0)), get(stats) as f64))
+            .collect()
+    };
+
+    let mut queues = Vec::new();
+    for link in fabric.graph.edge_weights() {
+        for (direction, classes) in link.queue_stats() {
+            for class in classes {
+                let labels = format!(
+                    "link=\"{}\",class=\"{}\"",
+                    escape(&direction),
+                    escape(&class.name)
+                );
+                queues.push((labels, class));
+            }
+        }
+    }
+    queues.sort_by(|a, b| a.0.cmp(&b.0));
+    let queue_metric = |get: fn(&QueueStats) -> u64| -> Vec<(String, f64)> {
+        queues
+            .iter()
+            .map(|(labels, stats)| (labels.clone(), get(stats) as f64))
+            .collect()
+    };
+
+    let router_families: [Family<RouterStats>; 16] = [
+        (
+            "nsim_router_packets_received_total",
+            "Packets received by the router.",
+            |s| s.packets_received,
+        ),
+        (
+            "nsim_router_packets_forwarded_total",
+            "Packets forwarded by the router.",
+            |s| s.packets_forwarded,
+        ),
+        (
+            "nsim_router_packets_lost_total",
+            "Packets lost on the router's egress links.",
+            |s| s.packets_lost,
+        ),
+        (
+            "nsim_router_packets_delivered_total",
+            "Packets delivered to the edge behind the router.",
+            |s| s.packets_delivered,
+        ),
+        (
+            "nsim_router_icmp_generated_total",
+            "ICMP errors generated by the router.",
+            |s| s.icmp_generated,
+        ),
+        (
+            "nsim_router_icmp_suppressed_total",
+            "ICMP errors not generated: about an ICMP error, or over the per-packet cap.",
+            |s| s.icmp_suppressed,
+        ),
+        (
+            "nsim_router_cpu_drops_total",
+            "Packets shed because the router's CPU queue was full.",
+            |s| s.cpu_drops,
+        ),
+        (
+            "nsim_router_acl_drops_total",
+            "Packets dropped by an ACL deny rule.",
+            |s| s.acl_drops,
+        ),
+        (
+            "nsim_router_policer_drops_total",
+            "Packets dropped by an ingress policer.",
+            |s| s.policer_drops,
+        ),
+        (
+            "nsim_router_bad_checksums_total",
+            "Packets entering the fabric with a bad checksum.",
+            |s| s.bad_checksums,
+        ),
+        (
+            "nsim_router_bytes_received_total",
+            "Bytes of the packets received by the router.",
+            |s| s.bytes_received,
+        ),
+        (
+            "nsim_router_bytes_forwarded_total",
+            "Bytes of the packets forwarded by the router.",
+            |s| s.bytes_forwarded,
+        ),
+        (
+            "nsim_router_tcp_received_total",
+            "TCP packets received by the router.",
+            |s| s.tcp_received,
+        ),
+        (
+            "nsim_router_udp_received_total",
+            "UDP packets received by the router.",
+            |s| s.udp_received,
+        ),
+        (
+            "nsim_router_icmp_received_total",
+            "ICMP and ICMPv6 packets received by the router.",
+            |s| s.icmp_received,
+        ),
+        (
+            "nsim_router_other_received_total",
+            "Packets of other protocols received by the router.",
+            |s| s.other_received,
+        ),
+    ];
+    let queue_families: [Family<QueueStats>; 4] = [
+        (
+            "nsim_queue_enqueued_total",
+            "Packets enqueued on a link traffic class.",
+            |s| s.enqueued,
+        ),
+        (
+            "nsim_queue_dropped_total",
+            "Packets tail-dropped at the queue limit.",
+            |s| s.dropped,
+        ),
+        (
+            "nsim_queue_wred_dropped_total",
+            "Packets dropped early by WRED.",
+            |s| s.wred_dropped,
+        ),
+        (
+            "nsim_queue_bytes_total",
+            "Bytes enqueued on a link traffic class.",
+            |s| s.bytes,
+        ),
+    ];
+
+    let mut out = String::new();
+    for (name, help, get) in router_families {
+        family(&mut out, name, help, &router_metric(get));
+    }
+    // Links without a bandwidth have no queues; leave their families out entirely.
+    if !queues.is_empty() {
+        for (name, help, get) in queue_families {
+            family(&mut out, name, help, &queue_metric(get));
+        }
+    }
+    if let Some(alarms) = &fabric.alarms {
+        let samples: Vec<(String, f64)> = Severity::ALL
+            .iter()
+            .map(|s| {
+                (
+                    format!("severity=\"{}\"", s.as_str()),
+                    alarms.raised(*s) as f64,
+                )
+            })
+            .collect();
+        family(
+            &mut out,
+            "nsim_alarms_raised_total",
+            "Link monitoring alarms raised.",
+            &samples,
+        );
+    }
+    out
+}
+
+/// Write [`render`] to `path`.
+pub fn write(fabric: &Fabric, path: &str) -> Result<(), String> {
+    std::fs::write(path, render(fabric))
+        .map_err(|e| format!("Failed to write metrics to {}: {}", path, e))
+}
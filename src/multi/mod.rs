@@ -0,0 +1,140 @@
+// src/multi/mod.rs
+
+//! Several independent fabrics in one process.
+//!
+//! A scenario file holds one `[fabrics.<name>]` table per instance, each a complete simulator
+//! configuration. Every instance is built and routed on its own, draws from its own random
+//! generator and runs as a task of its own, so parallel test cases can share one daemon without
+//! seeing each other's statistics. Instances must not share TUN devices or files.
+
+use crate::config::SimulatorConfig;
+use crate::experiment::RunSummary;
+use crate::topology::Fabric;
+use std::collections::HashMap;
+
+/// One named fabric of a scenario file.
+#[derive(Debug)]
+pub struct Instance {
+    pub name: String,
+    pub cfg: SimulatorConfig,
+}
+
+/// Parse a scenario file into its instances, ordered by name.
+pub fn parse_scenario(text: &str) -> Result<Vec<Instance>, String> {
+    let mut root: toml::Table =
+        toml::from_str(text).map_err(|e| format!("Invalid scenario file: {}", e))?;
+    let fabrics = root
+        .remove("fabrics")
+        .ok_or("Scenario file has no [fabrics.<name>] sections")?;
+    if let Some(key) = root.keys().next() {
+        return Err(format!(
+            "Unknown top-level key '{}' in scenario file, expected only [fabrics.<name>] sections",
+            key
+        ));
+    }
+    let toml::Value::Table(fabrics) = fabrics else {
+        return Err("'fabrics' must be a table of [fabrics.<name>] sections".to_string());
+    };
+    if fabrics.is_empty() {
+        return Err("Scenario file has no [fabrics.<name>] sections".to_string());
+    }
+    fabrics
+        .into_iter()
+        .map(|(name, value)| {
+            let cfg = value
+                .try_into()
+                .map_err(|e| format!("Fabric '{}': invalid configuration: {}", name, e))?;
+            Ok(Instance { name, cfg })
+        })
+        .collect()
+}
+
+/// Files and devices an instance opens; no two instances may claim the same one.
+fn resources(cfg: &SimulatorConfig) -> Vec<(&'static str, String)> {
+    let mut claims = Vec::new();
+    let files = cfg
+        .packet_file
+        .iter()
+        .chain(cfg.packet_files.iter().flatten());
+    for file in files {
+        claims.push(("packet file", file.clone()));
+    }
+    // Real devices are only opened when no packet file stands in for them.
+    if claims.is_empty() {
+        for iface in [&cfg.interfaces.real_tun_a, &cfg.interfaces.real_tun_b] {
+            claims.push(("TUN device", iface.name.clone()));
+        }
+    }
+    if let Some(ref trace) = cfg.packet_trace {
+        claims.push(("packet trace", trace.clone()));
+    }
+    for capture in &cfg.captures {
+        claims.push(("capture file", capture.file.clone()));
+    }
+    if let Some(ref report) = cfg.exit_report {
+        claims.push(("exit report", report.path.clone()));
+    }
+    claims
+}
+
+/// Validate every instance and check that they are isolated from each other.
+pub fn validate(instances: &[Instance]) -> Result<(), String> {
+    let mut owners: HashMap<(&str, String), &str> = HashMap::new();
+    for instance in instances {
+        instance
+            .cfg
+            .validate()
+            .map_err(|e| format!("Fabric '{}': {}", instance.name, e))?;
+        for (kind, what) in resources(&instance.cfg) {
+            if let Some(owner) = owners.insert((kind, what.clone()), &instance.name) {
+                return Err(format!(
+                    "Fabrics '{}' and '{}' both use {} '{}'",
+                    owner, instance.name, kind, what
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run all instances concurrently, each with its own RNG (seeded from its own
+/// `simulation.seed`). Results are returned in instance order.
+pub async fn run(
+    instances: Vec<Instance>,
+    enable_multipath: bool,
+) -> Result<Vec<(String, Fabric)>, String> {
+    let mut tasks = Vec::with_capacity(instances.len());
+    for Instance { name, mut cfg } in instances {
+        cfg.enable_multipath |= enable_multipath;
+        tasks.push(tokio::spawn(async move {
+            let seed = cfg.simulation.seed;
+            let fabric = crate::simulation::with_run_rng(seed, crate::run(cfg))
+                .await
+                .map_err(|e| format!("Fabric '{}': {}", name, e))?;
+            Ok::<_, String>((name, fabric))
+        }));
+    }
+    let mut results = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        results.push(task.await.map_err(|e| e.to_string())??);
+    }
+    Ok(results)
+}
+
+/// One line per instance with its delivery and loss counters.
+pub fn report(results: &[(String, Fabric)]) -> String {
+    let mut out = String::new();
+    for (name, fabric) in results {
+        let summary = RunSummary::from_fabric(fabric);
+        out.push_str(&format!(
+            "Fabric {}: routers={}, links={}, delivered={}, lost={}, loss={:.2}%\n",
+            name,
+            fabric.router_index.len(),
+            fabric.link_index.len(),
+            summary.delivered,
+            summary.lost,
+            summary.loss_percent
+        ));
+    }
+    out
+}
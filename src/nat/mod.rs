@@ -0,0 +1,258 @@
+// src/nat/mod.rs
+
+//! NAT44 at one edge.
+//!
+//! With `[nat44]` the hosts behind the `inside` edge ("tun_a" by default) are hidden behind
+//! `external_address`. An IPv4 packet entering at that edge leaves it with the external
+//! address as its source and a port of its own (for an ICMP query, an identifier), allocated
+//! upwards from `first_port`; a packet leaving at the edge towards the external address gets
+//! the host's address and port back. ICMP errors about translated packets are translated with
+//! [`icmp::translate_error`](crate::icmp::translate_error): a Fragmentation Needed coming back
+//! for a translated flow reaches the host, and an error the host sends about a translated
+//! packet quotes the external mapping, so path MTU discovery works through the edge. Packets
+//! towards the external address without a mapping, fragments and other protocols are dropped.
+
+pub mod nat64;
+
+use crate::config::Nat44Config;
+use crate::icmp::{translate_error, QuotedEnd};
+use crate::packet::PacketMeta;
+use crate::routing::Destination;
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use tracing::debug;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NatStats {
+    /// Packets translated on the way out.
+    pub outbound: u64,
+    /// Packets translated on the way in.
+    pub inbound: u64,
+    /// ICMP errors (either way) whose quoted packet was translated.
+    pub icmp_errors: u64,
+    /// Packets dropped: no mapping, no free port, fragment or unsupported protocol.
+    pub dropped: u64,
+}
+
+impl NatStats {
+    pub fn summary(&self) -> String {
+        format!(
+            "outbound={}, inbound={}, icmp_errors={}, dropped={}",
+            self.outbound, self.inbound, self.icmp_errors, self.dropped
+        )
+    }
+}
+
+/// Host side of a mapping: protocol, address and port (or ICMP identifier).
+type Inside = (u8, Ipv4Addr, u16);
+
+#[derive(Debug)]
+pub struct Nat44 {
+    /// Edge the translated hosts are behind.
+    pub inside: Destination,
+    pub external: Ipv4Addr,
+    next_port: u16,
+    outward: HashMap<Inside, u16>,
+    inward: HashMap<(u8, u16), (Ipv4Addr, u16)>,
+    pub stats: NatStats,
+}
+
+/// Offset of the flow's port (or ICMP query identifier) in `raw`: the source side or the
+/// destination side. `None` for anything but TCP, UDP and ICMP echo.
+fn port_offset(raw: &[u8], transport: usize, source: bool) -> Option<usize> {
+    let offset = match raw[9] {
+        6 | 17 if source => transport,
+        6 | 17 => transport + 2,
+        // Echo request and reply carry the identifier in place of both ports.
+        1 if matches!(raw.get(transport), Some(0 | 8)) => transport + 4,
+        _ => return None,
+    };
+    (offset + 2 <= raw.len()).then_some(offset)
+}
+
+/// Whether `raw` is an ICMP error: Destination Unreachable, Time Exceeded or Parameter
+/// Problem.
+fn is_icmp_error(raw: &[u8], transport: usize) -> bool {
+    raw[9] == 1 && matches!(raw.get(transport), Some(3 | 11 | 12))
+}
+
+fn word(raw: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([raw[at], raw[at + 1]])
+}
+
+fn addr(raw: &[u8], at: usize) -> Ipv4Addr {
+    Ipv4Addr::new(raw[at], raw[at + 1], raw[at + 2], raw[at + 3])
+}
+
+/// Protocol, address and port of one end of the packet quoted in the ICMP error at
+/// `transport`.
+fn quoted_end(raw: &[u8], transport: usize, source: bool) -> Option<Inside> {
+    let quoted = transport + 8;
+    let ihl = (*raw.get(quoted)? & 0x0F) as usize * 4;
+    if raw.len() < quoted + ihl.max(20) {
+        return None;
+    }
+    let at = port_offset(&raw[quoted..], ihl, source)?;
+    let address = addr(raw, quoted + if source { 12 } else { 16 });
+    Some((raw[quoted + 9], address, word(raw, quoted + at)))
+}
+
+/// Set the address at `addr_at` and the port at `port_at` and fix the checksums.
+fn rewrite(raw: &mut [u8], transport: usize, addr_at: usize, port_at: usize, to: (Ipv4Addr, u16)) {
+    raw[addr_at..addr_at + 4].copy_from_slice(&to.0.octets());
+    raw[port_at..port_at + 2].copy_from_slice(&to.1.to_be_bytes());
+    crate::packet::update_ipv4_checksum(raw);
+    match raw[9] {
+        6 => crate::packet::update_tcp_checksum(raw),
+        // A zero UDP checksum means "none" and stays zero.
+        17 if word(raw, transport + 6) != 0 => crate::packet::update_udp_checksum(raw),
+        1 => {
+            raw[transport + 2..transport + 4].copy_from_slice(&[0, 0]);
+            let checksum = crate::icmp::calculate_icmp_checksum(&raw[transport..]);
+            raw[transport + 2..transport + 4].copy_from_slice(&checksum.to_be_bytes());
+        }
+        _ => {}
+    }
+}
+
+impl Nat44 {
+    pub fn new(cfg: &Nat44Config) -> Result<Self, String> {
+        let inside = match cfg.inside.as_str() {
+            "tun_a" => Destination::TunA,
+            "tun_b" => Destination::TunB,
+            other => {
+                return Err(format!(
+                    "nat44.inside must be \"tun_a\" or \"tun_b\", got \"{}\"",
+                    other
+                ))
+            }
+        };
+        let external = cfg.external_address.parse().map_err(|_| {
+            format!(
+                "Invalid nat44.external_address '{}', expected an IPv4 address",
+                cfg.external_address
+            )
+        })?;
+        if cfg.first_port == 0 {
+            return Err("nat44.first_port must be at least 1".to_string());
+        }
+        Ok(Self {
+            inside,
+            external,
+            next_port: cfg.first_port,
+            outward: HashMap::new(),
+            inward: HashMap::new(),
+            stats: NatStats::default(),
+        })
+    }
+
+    /// Active mappings.
+    pub fn len(&self) -> usize {
+        self.outward.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outward.is_empty()
+    }
+
+    /// External port of the host's `inside` end, allocated on first use. `None` once all ports
+    /// from `first_port` up are taken.
+    fn map(&mut self, inside: Inside) -> Option<u16> {
+        if let Some(&port) = self.outward.get(&inside) {
+            return Some(port);
+        }
+        let port = self.next_port;
+        if port == 0 {
+            return None;
+        }
+        self.next_port = port.checked_add(1).unwrap_or(0);
+        self.outward.insert(inside, port);
+        self.inward.insert((inside.0, port), (inside.1, inside.2));
+        debug!(
+            "NAT44 mapping {}:{} ({}) -> {}:{}",
+            inside.1, inside.2, inside.0, self.external, port
+        );
+        Some(port)
+    }
+
+    /// Translate a packet leaving the inside edge into the fabric. Non-IPv4 packets pass
+    /// unchanged; returns `false` if the packet must be dropped.
+    pub fn outbound(&mut self, packet: &mut PacketMeta) -> bool {
+        if !packet.src_ip.is_ipv4() {
+            return true;
+        }
+        let translated = self.translate(packet, true);
+        match translated {
+            Some(icmp_error) => {
+                self.stats.outbound += 1;
+                self.stats.icmp_errors += icmp_error as u64;
+            }
+            None => self.stats.dropped += 1,
+        }
+        translated.is_some()
+    }
+
+    /// Translate a packet that reached the inside edge. Packets not addressed to the external
+    /// address pass unchanged; returns `false` if the packet must be dropped.
+    pub fn inbound(&mut self, packet: &mut PacketMeta) -> bool {
+        if packet.dst_ip != self.external {
+            return true;
+        }
+        let translated = self.translate(packet, false);
+        match translated {
+            Some(icmp_error) => {
+                self.stats.inbound += 1;
+                self.stats.icmp_errors += icmp_error as u64;
+            }
+            None => self.stats.dropped += 1,
+        }
+        translated.is_some()
+    }
+
+    /// Translate `packet` outbound (source side) or inbound (destination side). Returns
+    /// whether it was an ICMP error, or `None` if it cannot be translated.
+    fn translate(&mut self, packet: &mut PacketMeta, outbound: bool) -> Option<bool> {
+        let mut raw = packet.raw.to_vec();
+        if raw.len() < 20 || raw[0] >> 4 != 4 || crate::packet::is_fragment(&raw) {
+            return None;
+        }
+        let transport = crate::packet::transport_offset(&raw)?;
+        let icmp_error = is_icmp_error(&raw, transport);
+        if icmp_error {
+            // The quoted packet went the other way: on the way out the host quotes a packet
+            // it received (its destination is the host), on the way in the error quotes a
+            // packet the host sent (its source is the external address).
+            let (protocol, address, port) = quoted_end(&raw, transport, !outbound)?;
+            let (end, to) = if outbound {
+                let external = *self.outward.get(&(protocol, address, port))?;
+                (QuotedEnd::Destination, (self.external, external))
+            } else {
+                if address != self.external {
+                    return None;
+                }
+                (QuotedEnd::Source, *self.inward.get(&(protocol, port))?)
+            };
+            translate_error(&mut raw, end, to.0, to.1).ok()?;
+        } else {
+            let port_at = port_offset(&raw, transport, outbound)?;
+            let protocol = raw[9];
+            if outbound {
+                let port = self.map((protocol, addr(&raw, 12), word(&raw, port_at)))?;
+                rewrite(&mut raw, transport, 12, port_at, (self.external, port));
+            } else {
+                let to = *self.inward.get(&(protocol, word(&raw, port_at)))?;
+                rewrite(&mut raw, transport, 16, port_at, to);
+            }
+        }
+        let mut translated = crate::packet::parse(&raw).ok()?;
+        translated.vlan = packet.vlan;
+        translated.annotation = packet.annotation.take();
+        *packet = translated;
+        Some(icmp_error)
+    }
+
+    /// Reset the counters; the mappings stay.
+    pub fn reset(&mut self) {
+        self.stats = NatStats::default();
+    }
+}
@@ -0,0 +1,282 @@
+// src/nat/nat64.rs
+
+//! Stateful NAT64 at one router (RFC 6146, without ICMP error translation).
+//!
+//! With `[nat64]` the designated router translates IPv6 packets towards `prefix` (a /96, the
+//! well‑known `64:ff9b::/96` by default) into IPv4 packets towards the address embedded in the
+//! last 32 bits, from `pool_address` and a port (or ICMP identifier) allocated upwards from
+//! `first_port`. IPv4 packets towards the pool address that reach the router are translated
+//! back: their source becomes the IPv4 source embedded in the prefix and their destination the
+//! IPv6 host and port of the mapping. Headers are rebuilt (traffic class and TOS, hop limit and
+//! TTL carry over), ICMPv6 echo becomes ICMP echo and back, and every checksum is recomputed.
+//! Fragments, extension headers, other protocols and ICMP errors cannot be translated and are dropped, as are
+//! IPv4 packets towards the pool address without a mapping.
+//!
+//! The router has to be on the path both ways: from the IPv6 edge towards the IPv4 one and
+//! back.
+
+use crate::config::Nat64Config;
+use crate::packet::PacketMeta;
+use crate::topology::RouterId;
+use ipnet::Ipv6Net;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tracing::debug;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Nat64Stats {
+    /// IPv6 packets translated to IPv4.
+    pub to_v4: u64,
+    /// IPv4 packets translated back to IPv6.
+    pub to_v6: u64,
+    /// Packets that could not be translated.
+    pub dropped: u64,
+}
+
+impl Nat64Stats {
+    pub fn summary(&self) -> String {
+        format!(
+            "to_v4={}, to_v6={}, dropped={}",
+            self.to_v4, self.to_v6, self.dropped
+        )
+    }
+}
+
+/// IPv6 side of a mapping: protocol (6, 17 or 58), address and port (or ICMPv6 identifier).
+type Inside = (u8, Ipv6Addr, u16);
+
+#[derive(Debug)]
+pub struct Nat64 {
+    /// Router doing the translation.
+    pub router: RouterId,
+    pub prefix: Ipv6Net,
+    pub pool: Ipv4Addr,
+    next_port: u16,
+    outward: HashMap<Inside, u16>,
+    inward: HashMap<(u8, u16), (Ipv6Addr, u16)>,
+    pub stats: Nat64Stats,
+}
+
+/// Offset into the transport header of the port (or echo identifier) that identifies the
+/// IPv6 host: the source side on the way out, the destination side on the way back.
+fn port_offset(protocol: u8, to_v4: bool) -> usize {
+    match protocol {
+        6 | 17 if to_v4 => 0,
+        6 | 17 => 2,
+        // Echo request and reply carry the identifier in place of both ports.
+        _ => 4,
+    }
+}
+
+fn word(raw: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([raw[at], raw[at + 1]])
+}
+
+impl Nat64 {
+    pub fn new(cfg: &Nat64Config) -> Result<Self, String> {
+        let prefix: Ipv6Net = cfg.prefix.parse().map_err(|_| {
+            format!(
+                "Invalid nat64.prefix '{}', expected an IPv6 prefix",
+                cfg.prefix
+            )
+        })?;
+        if prefix.prefix_len() != 96 {
+            return Err(format!(
+                "nat64.prefix must be a /96, got /{}",
+                prefix.prefix_len()
+            ));
+        }
+        let pool = cfg.pool_address.parse().map_err(|_| {
+            format!(
+                "Invalid nat64.pool_address '{}', expected an IPv4 address",
+                cfg.pool_address
+            )
+        })?;
+        if cfg.first_port == 0 {
+            return Err("nat64.first_port must be at least 1".to_string());
+        }
+        Ok(Self {
+            router: RouterId(cfg.router.clone()),
+            prefix: prefix.trunc(),
+            pool,
+            next_port: cfg.first_port,
+            outward: HashMap::new(),
+            inward: HashMap::new(),
+            stats: Nat64Stats::default(),
+        })
+    }
+
+    /// Active mappings.
+    pub fn len(&self) -> usize {
+        self.outward.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outward.is_empty()
+    }
+
+    /// The IPv6 address representing `v4` under the prefix.
+    pub fn embed(&self, v4: Ipv4Addr) -> Ipv6Addr {
+        let mut octets = self.prefix.network().octets();
+        octets[12..].copy_from_slice(&v4.octets());
+        Ipv6Addr::from(octets)
+    }
+
+    /// Pool port of the IPv6 host's `inside` end, allocated on first use. `None` once all
+    /// ports from `first_port` up are taken.
+    fn map(&mut self, inside: Inside) -> Option<u16> {
+        if let Some(&port) = self.outward.get(&inside) {
+            return Some(port);
+        }
+        let port = self.next_port;
+        if port == 0 {
+            return None;
+        }
+        self.next_port = port.checked_add(1).unwrap_or(0);
+        self.outward.insert(inside, port);
+        self.inward.insert((inside.0, port), (inside.1, inside.2));
+        debug!(
+            "NAT64 mapping [{}]:{} ({}) -> {}:{}",
+            inside.1, inside.2, inside.0, self.pool, port
+        );
+        Some(port)
+    }
+
+    /// Translate `packet` if it is to be: IPv6 towards the prefix or IPv4 towards the pool
+    /// address. Other packets pass unchanged; returns `false` if the packet must be dropped.
+    pub fn translate(&mut self, packet: &mut PacketMeta) -> bool {
+        let translated = match (packet.src_ip, packet.dst_ip) {
+            (IpAddr::V6(_), IpAddr::V6(dst)) if self.prefix.contains(&dst) => {
+                let raw = self.v4_packet(packet);
+                self.stats.to_v4 += raw.is_some() as u64;
+                raw
+            }
+            (IpAddr::V4(_), IpAddr::V4(dst)) if dst == self.pool => {
+                let raw = self.v6_packet(packet);
+                self.stats.to_v6 += raw.is_some() as u64;
+                raw
+            }
+            _ => return true,
+        };
+        let Some(mut translated) = translated.and_then(|raw| crate::packet::parse(&raw).ok())
+        else {
+            self.stats.dropped += 1;
+            return false;
+        };
+        translated.vlan = packet.vlan;
+        translated.mpls = std::mem::take(&mut packet.mpls);
+        translated.annotation = packet.annotation.take();
+        *packet = translated;
+        true
+    }
+
+    /// The IPv4 packet for an IPv6 `packet` towards the prefix.
+    fn v4_packet(&mut self, packet: &PacketMeta) -> Option<Vec<u8>> {
+        let raw = &packet.raw;
+        let offset = crate::packet::transport_offset(raw)?;
+        if raw.len() < 40 {
+            return None;
+        }
+        let end = (40 + word(raw, 4) as usize).min(raw.len());
+        let (IpAddr::V6(src), IpAddr::V6(dst)) = (packet.src_ip, packet.dst_ip) else {
+            return None;
+        };
+        // Extension headers (fragments in particular) are not translated.
+        if offset != 40 {
+            return None;
+        }
+        let protocol = raw[6];
+        let mut segment = raw.get(offset..end)?.to_vec();
+        let v4_protocol = match protocol {
+            6 | 17 => protocol,
+            58 if segment.len() >= 8 && matches!(segment[0], 128 | 129) => {
+                segment[0] = if segment[0] == 128 { 8 } else { 0 };
+                1
+            }
+            _ => return None,
+        };
+        let at = port_offset(v4_protocol, true);
+        if segment.len() < at + 2 {
+            return None;
+        }
+        let port = self.map((protocol, src, word(&segment, at)))?;
+        segment[at..at + 2].copy_from_slice(&port.to_be_bytes());
+
+        let total = 20 + segment.len();
+        let mut out = Vec::with_capacity(total);
+        let tc = crate::packet::traffic_class(raw).unwrap_or(0);
+        out.extend_from_slice(&[0x45, tc]);
+        out.extend_from_slice(&(total as u16).to_be_bytes());
+        // IPv6 is not fragmented on the path, so neither is the translated packet.
+        out.extend_from_slice(&[0, 0, 0x40, 0, raw[7], v4_protocol, 0, 0]);
+        out.extend_from_slice(&self.pool.octets());
+        out.extend_from_slice(&dst.octets()[12..]);
+        crate::packet::update_ipv4_checksum(&mut out);
+        out.extend_from_slice(&segment);
+        match v4_protocol {
+            6 => crate::packet::update_tcp_checksum(&mut out),
+            17 => crate::packet::update_udp_checksum(&mut out),
+            _ => {
+                out[22..24].copy_from_slice(&[0, 0]);
+                let checksum = crate::icmp::calculate_icmp_checksum(&out[20..]);
+                out[22..24].copy_from_slice(&checksum.to_be_bytes());
+            }
+        }
+        Some(out)
+    }
+
+    /// The IPv6 packet for an IPv4 `packet` towards the pool address.
+    fn v6_packet(&mut self, packet: &PacketMeta) -> Option<Vec<u8>> {
+        let raw = &packet.raw;
+        if raw.len() < 20 || crate::packet::is_fragment(raw) {
+            return None;
+        }
+        let offset = crate::packet::transport_offset(raw)?;
+        let end = (word(raw, 2) as usize).clamp(offset, raw.len());
+        let IpAddr::V4(src) = packet.src_ip else {
+            return None;
+        };
+        let mut segment = raw[offset..end].to_vec();
+        let protocol = match raw[9] {
+            6 | 17 => raw[9],
+            1 if segment.len() >= 8 && matches!(segment[0], 0 | 8) => {
+                segment[0] = if segment[0] == 8 { 128 } else { 129 };
+                58
+            }
+            _ => return None,
+        };
+        let at = port_offset(protocol, false);
+        if segment.len() < at + 2 {
+            return None;
+        }
+        let (host, port) = *self.inward.get(&(protocol, word(&segment, at)))?;
+        segment[at..at + 2].copy_from_slice(&port.to_be_bytes());
+
+        let source = self.embed(src);
+        let tc = raw[1];
+        let mut out = Vec::with_capacity(40 + segment.len());
+        out.extend_from_slice(&[0x60 | (tc >> 4), tc << 4, 0, 0]);
+        out.extend_from_slice(&(segment.len() as u16).to_be_bytes());
+        out.extend_from_slice(&[protocol, raw[8]]);
+        out.extend_from_slice(&source.octets());
+        out.extend_from_slice(&host.octets());
+        out.extend_from_slice(&segment);
+        match protocol {
+            6 => crate::packet::update_tcp_checksum(&mut out),
+            // IPv6 requires the UDP checksum that IPv4 may leave out.
+            17 => crate::packet::update_udp_checksum(&mut out),
+            _ => {
+                out[42..44].copy_from_slice(&[0, 0]);
+                let checksum =
+                    crate::packet::transport_checksum(source.into(), host.into(), 58, &out[40..]);
+                out[42..44].copy_from_slice(&checksum.to_be_bytes());
+            }
+        }
+        Some(out)
+    }
+
+    /// Reset the counters; the mappings stay.
+    pub fn reset(&mut self) {
+        self.stats = Nat64Stats::default();
+    }
+}
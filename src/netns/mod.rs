@@ -0,0 +1,155 @@
+// src/netns/mod.rs
+
+//! Network namespaces for the hosts behind the real edges.
+//!
+//! A single host cannot send traffic through its own TUN devices: both edge addresses are local,
+//! so the kernel delivers the packets over loopback without ever handing them to the simulator.
+//! Setting `netns` on an edge moves its device into that namespace once it is opened, with the
+//! edge address and a default route configured there. Sockets created in the namespace then
+//! reach the other edge only through the fabric.
+//!
+//! Namespaces are managed with `ip netns` (iproute2); sockets are opened on a helper thread that
+//! has joined the namespace, since a socket stays in the namespace it was created in.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpSocket, UdpSocket};
+use tokio::time::Instant;
+use tracing::{debug, info};
+
+fn ip(args: &[&str]) -> Result<(), String> {
+    debug!("ip {}", args.join(" "));
+    let output = Command::new("ip")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run ip: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "ip {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn path(ns: &str) -> String {
+    format!("/run/netns/{}", ns)
+}
+
+/// Create namespace `ns` unless it exists; returns whether it was created.
+pub fn ensure(ns: &str) -> Result<bool, String> {
+    if Path::new(&path(ns)).exists() {
+        return Ok(false);
+    }
+    ip(&["netns", "add", ns])?;
+    Ok(true)
+}
+
+/// Delete namespace `ns`.
+pub fn remove(ns: &str) -> Result<(), String> {
+    ip(&["netns", "del", ns])
+}
+
+/// Move device `dev` into `ns` and bring it up there. With an address, it is assigned to the
+/// device and everything else is routed through it, so the namespace only reaches the other
+/// edge via the fabric. TAP edges pass `None` and leave addressing to the hosts (e.g. DHCP).
+pub fn attach(ns: &str, dev: &str, address: Option<(IpAddr, u8)>) -> Result<(), String> {
+    ensure(ns)?;
+    ip(&["link", "set", dev, "netns", ns])?;
+    ip(&["-n", ns, "link", "set", "lo", "up"])?;
+    ip(&["-n", ns, "link", "set", dev, "up"])?;
+    if let Some((addr, prefix)) = address {
+        let family = if addr.is_ipv4() { "-4" } else { "-6" };
+        let cidr = format!("{}/{}", addr, prefix);
+        // The route goes in first so that once the address shows up the edge is usable.
+        ip(&["-n", ns, family, "route", "add", "default", "dev", dev])?;
+        ip(&["-n", ns, family, "addr", "add", &cidr, "dev", dev])?;
+    }
+    info!("Moved {} into network namespace {}", dev, ns);
+    Ok(())
+}
+
+/// Run `f` on a thread that has joined namespace `ns`.
+#[cfg(target_os = "linux")]
+pub fn within<T: Send>(ns: &str, f: impl FnOnce() -> io::Result<T> + Send) -> io::Result<T> {
+    use std::os::fd::AsRawFd;
+    let handle = std::fs::File::open(path(ns))?;
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                // SAFETY: `handle` is an open namespace file and setns only affects this thread.
+                if unsafe { libc::setns(handle.as_raw_fd(), libc::CLONE_NEWNET) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                f()
+            })
+            .join()
+            .map_err(|_| io::Error::other("namespace thread panicked"))?
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn within<T: Send>(_ns: &str, _f: impl FnOnce() -> io::Result<T> + Send) -> io::Result<T> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "network namespaces are only available on Linux",
+    ))
+}
+
+fn in_ns<T: Send>(ns: Option<&str>, f: impl FnOnce() -> io::Result<T> + Send) -> io::Result<T> {
+    match ns {
+        Some(ns) => within(ns, f),
+        None => f(),
+    }
+}
+
+/// TCP listener bound to `addr`, in namespace `ns` if given.
+pub fn tcp_listener(ns: Option<&str>, addr: SocketAddr) -> io::Result<TcpListener> {
+    let listener = in_ns(ns, || std::net::TcpListener::bind(addr))?;
+    listener.set_nonblocking(true)?;
+    TcpListener::from_std(listener)
+}
+
+/// UDP socket bound to `addr`, in namespace `ns` if given.
+pub fn udp_socket(ns: Option<&str>, addr: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = in_ns(ns, || std::net::UdpSocket::bind(addr))?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket)
+}
+
+/// Unconnected TCP socket for reaching `target`, in namespace `ns` if given.
+pub fn tcp_socket(ns: Option<&str>, target: &SocketAddr) -> io::Result<TcpSocket> {
+    in_ns(ns, || match target {
+        SocketAddr::V4(_) => TcpSocket::new_v4(),
+        SocketAddr::V6(_) => TcpSocket::new_v6(),
+    })
+}
+
+/// Whether `addr` is assigned to an interface in `ns`.
+pub fn has_address(ns: &str, addr: IpAddr) -> bool {
+    Command::new("ip")
+        .args(["-n", ns, "-o", "addr", "show"])
+        .output()
+        .map(|out| {
+            let needle = format!(" {}/", addr);
+            String::from_utf8_lossy(&out.stdout).contains(&needle)
+        })
+        .unwrap_or(false)
+}
+
+/// Wait until `addr` is configured in `ns`, i.e. the simulator has attached the edge device.
+pub async fn wait_for_address(ns: &str, addr: IpAddr, timeout: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    while !has_address(ns, addr) {
+        if Instant::now() >= deadline {
+            return Err(format!("{} did not appear in namespace {}", addr, ns));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Ok(())
+}
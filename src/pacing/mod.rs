@@ -0,0 +1,75 @@
+// src/pacing/mod.rs
+
+//! Egress pacing towards the real TUN devices.
+//!
+//! The processor normally sleeps through every delay a packet meets, so packets leave the fabric
+//! one full latency apart no matter how closely the host sent them. With
+//! `simulation.egress_pacing` the delays are only accounted for (see
+//! [`crate::simulation::with_paced_delays`]) and each packet is held here until its simulated
+//! egress time — the time it was read plus its sojourn — so the receiving host sees the latency
+//! and the inter‑packet spacing the links shaped.
+
+use crate::sojourn::Sojourn;
+use bytes::Bytes;
+use std::collections::VecDeque;
+use tokio::time::{Duration, Instant};
+
+/// Simulated egress time of a packet read at `arrival` that spent `sojourn` in the fabric.
+pub fn egress_time(arrival: Instant, sojourn: &Sojourn) -> Instant {
+    arrival + Duration::from_secs_f64(sojourn.total_ms().max(0.0) / 1000.0)
+}
+
+/// Packets of one edge waiting for their egress time, earliest first.
+#[derive(Debug, Default)]
+pub struct EgressPacer {
+    pending: VecDeque<(Instant, Bytes)>,
+    /// Packets handed back by [`EgressPacer::due`].
+    pub released: u64,
+    /// Most packets held at once.
+    pub max_backlog: usize,
+}
+
+impl EgressPacer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold `frame` until `at`. Packets due at the same time keep their order; a packet whose
+    /// jitter made it faster overtakes the ones still held.
+    pub fn schedule(&mut self, at: Instant, frame: Bytes) {
+        let idx = self.pending.partition_point(|(t, _)| *t <= at);
+        self.pending.insert(idx, (at, frame));
+        self.max_backlog = self.max_backlog.max(self.pending.len());
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.front().map(|(at, _)| *at)
+    }
+
+    /// Take the packets due at `now`, in release order.
+    pub fn due(&mut self, now: Instant) -> Vec<Bytes> {
+        let count = self.pending.partition_point(|(t, _)| *t <= now);
+        self.released += count as u64;
+        self.pending
+            .drain(..count)
+            .map(|(_, frame)| frame)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "released={}, held={}, max_backlog={}",
+            self.released,
+            self.pending.len(),
+            self.max_backlog
+        )
+    }
+}
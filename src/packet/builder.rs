@@ -0,0 +1,323 @@
+// src/packet/builder.rs
+
+//! Builder for well‑formed test traffic.
+//!
+//! [`PacketBuilder`] assembles an IPv4 or IPv6 packet with a TCP, UDP or ICMP/ICMPv6 header
+//! (or any other protocol number followed by the payload). It fills in the lengths and the IPv4
+//! header, TCP, UDP and ICMP checksums, so traffic generators and tests do not have to write raw
+//! bytes by hand.
+//!
+//! ```
+//! use network_simulator::packet::builder::PacketBuilder;
+//!
+//! let packet = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+//!     .udp(5000, 53)
+//!     .payload(b"query".to_vec())
+//!     .build()
+//!     .unwrap();
+//! assert_eq!((packet.protocol, packet.dst_port, packet.raw.len()), (17, 53, 33));
+//! ```
+
+use super::{
+    transport_detail, update_ipv4_checksum, update_tcp_checksum, update_udp_checksum, PacketMeta,
+};
+use crate::icmp::{calculate_icmp_checksum, icmpv6_checksum};
+use std::net::IpAddr;
+
+pub const PROTO_ICMP: u8 = 1;
+pub const PROTO_TCP: u8 = 6;
+pub const PROTO_UDP: u8 = 17;
+pub const PROTO_ICMPV6: u8 = 58;
+
+/// TCP flag bits.
+pub const TCP_FIN: u8 = 0x01;
+pub const TCP_SYN: u8 = 0x02;
+pub const TCP_RST: u8 = 0x04;
+pub const TCP_PSH: u8 = 0x08;
+pub const TCP_ACK: u8 = 0x10;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Transport {
+    Tcp {
+        src_port: u16,
+        dst_port: u16,
+        seq: u32,
+        ack: u32,
+        flags: u8,
+        window: u16,
+    },
+    Udp {
+        src_port: u16,
+        dst_port: u16,
+    },
+    /// ICMP (IPv4) or ICMPv6 (IPv6) with the four bytes after the checksum.
+    Icmp {
+        kind: u8,
+        code: u8,
+        rest: [u8; 4],
+    },
+    /// Any protocol number; the payload follows the IP headers directly.
+    Raw(u8),
+}
+
+/// Builder of an IPv4 or IPv6 packet; see the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacketBuilder {
+    src: IpAddr,
+    dst: IpAddr,
+    ttl: u8,
+    tos: u8,
+    identification: u16,
+    dont_fragment: bool,
+    flow_label: u32,
+    extension_headers: Vec<u8>,
+    transport: Transport,
+    payload: Vec<u8>,
+}
+
+impl PacketBuilder {
+    /// A packet from `src` to `dst` with TTL 64 and, until a transport is chosen, UDP from and to
+    /// port 0.
+    pub fn new(src: IpAddr, dst: IpAddr) -> Self {
+        Self {
+            src,
+            dst,
+            ttl: 64,
+            tos: 0,
+            identification: 0,
+            dont_fragment: false,
+            flow_label: 0,
+            extension_headers: Vec::new(),
+            transport: Transport::Udp {
+                src_port: 0,
+                dst_port: 0,
+            },
+            payload: Vec::new(),
+        }
+    }
+
+    /// TTL (IPv4) or Hop Limit (IPv6).
+    pub fn ttl(mut self, ttl: u8) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Type of Service (IPv4) or Traffic Class (IPv6), DSCP and ECN together.
+    pub fn tos(mut self, tos: u8) -> Self {
+        self.tos = tos;
+        self
+    }
+
+    /// IPv4 identification.
+    pub fn identification(mut self, id: u16) -> Self {
+        self.identification = id;
+        self
+    }
+
+    /// IPv4 Don't Fragment flag.
+    pub fn dont_fragment(mut self, df: bool) -> Self {
+        self.dont_fragment = df;
+        self
+    }
+
+    /// IPv6 flow label (20 bits).
+    pub fn flow_label(mut self, label: u32) -> Self {
+        self.flow_label = label & 0xF_FFFF;
+        self
+    }
+
+    /// Append an empty IPv6 Hop‑by‑Hop (0) or Destination Options (60) header, padded to eight
+    /// bytes.
+    pub fn extension_header(mut self, kind: u8) -> Self {
+        self.extension_headers.push(kind);
+        self
+    }
+
+    /// TCP segment from `src_port` to `dst_port`; sequence and acknowledgement numbers start at
+    /// zero, flags at ACK and the window at 65535.
+    pub fn tcp(mut self, src_port: u16, dst_port: u16) -> Self {
+        self.transport = Transport::Tcp {
+            src_port,
+            dst_port,
+            seq: 0,
+            ack: 0,
+            flags: TCP_ACK,
+            window: 0xFFFF,
+        };
+        self
+    }
+
+    /// TCP sequence and acknowledgement numbers; ignored unless the transport is TCP.
+    pub fn tcp_seq(mut self, seq: u32, ack: u32) -> Self {
+        if let Transport::Tcp { seq: s, ack: a, .. } = &mut self.transport {
+            (*s, *a) = (seq, ack);
+        }
+        self
+    }
+
+    /// TCP flags and window; ignored unless the transport is TCP.
+    pub fn tcp_flags(mut self, flags: u8, window: u16) -> Self {
+        if let Transport::Tcp {
+            flags: f,
+            window: w,
+            ..
+        } = &mut self.transport
+        {
+            (*f, *w) = (flags, window);
+        }
+        self
+    }
+
+    /// UDP datagram from `src_port` to `dst_port`.
+    pub fn udp(mut self, src_port: u16, dst_port: u16) -> Self {
+        self.transport = Transport::Udp { src_port, dst_port };
+        self
+    }
+
+    /// ICMP (IPv4) or ICMPv6 (IPv6) message of `kind` and `code`; `rest` is the word after the
+    /// checksum, e.g. the identifier and sequence number of an echo request.
+    pub fn icmp(mut self, kind: u8, code: u8, rest: [u8; 4]) -> Self {
+        self.transport = Transport::Icmp { kind, code, rest };
+        self
+    }
+
+    /// Echo request with `id` and `seq`: ICMP type 8 or ICMPv6 type 128, by address family.
+    pub fn echo_request(self, id: u16, seq: u16) -> Self {
+        let kind = if self.src.is_ipv6() { 128 } else { 8 };
+        let [i0, i1] = id.to_be_bytes();
+        let [s0, s1] = seq.to_be_bytes();
+        self.icmp(kind, 0, [i0, i1, s0, s1])
+    }
+
+    /// Protocol number `protocol` without a transport header of its own.
+    pub fn protocol(mut self, protocol: u8) -> Self {
+        self.transport = Transport::Raw(protocol);
+        self
+    }
+
+    pub fn payload(mut self, payload: Vec<u8>) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    fn protocol_number(&self) -> u8 {
+        match self.transport {
+            Transport::Tcp { .. } => PROTO_TCP,
+            Transport::Udp { .. } => PROTO_UDP,
+            Transport::Icmp { .. } if self.src.is_ipv6() => PROTO_ICMPV6,
+            Transport::Icmp { .. } => PROTO_ICMP,
+            Transport::Raw(p) => p,
+        }
+    }
+
+    /// The packet, or an error if the addresses are of different families, extension headers
+    /// are given for IPv4 or the packet would exceed 65535 bytes.
+    pub fn build(&self) -> Result<PacketMeta, String> {
+        let protocol = self.protocol_number();
+        let mut l4 = Vec::with_capacity(20 + self.payload.len());
+        let (src_port, dst_port) = match self.transport {
+            Transport::Tcp {
+                src_port,
+                dst_port,
+                seq,
+                ack,
+                flags,
+                window,
+            } => {
+                l4.extend_from_slice(&src_port.to_be_bytes());
+                l4.extend_from_slice(&dst_port.to_be_bytes());
+                l4.extend_from_slice(&seq.to_be_bytes());
+                l4.extend_from_slice(&ack.to_be_bytes());
+                l4.extend_from_slice(&[5 << 4, flags]);
+                l4.extend_from_slice(&window.to_be_bytes());
+                l4.extend_from_slice(&[0, 0, 0, 0]);
+                (src_port, dst_port)
+            }
+            Transport::Udp { src_port, dst_port } => {
+                l4.extend_from_slice(&src_port.to_be_bytes());
+                l4.extend_from_slice(&dst_port.to_be_bytes());
+                l4.extend_from_slice(&((8 + self.payload.len()) as u16).to_be_bytes());
+                l4.extend_from_slice(&[0, 0]);
+                (src_port, dst_port)
+            }
+            Transport::Icmp { kind, code, rest } => {
+                l4.extend_from_slice(&[kind, code, 0, 0]);
+                l4.extend_from_slice(&rest);
+                (0, 0)
+            }
+            Transport::Raw(_) => (0, 0),
+        };
+        l4.extend_from_slice(&self.payload);
+
+        let mut raw = Vec::with_capacity(40 + 8 * self.extension_headers.len() + l4.len());
+        match (self.src, self.dst) {
+            (IpAddr::V4(s), IpAddr::V4(d)) => {
+                if !self.extension_headers.is_empty() {
+                    return Err("IPv6 extension headers on an IPv4 packet".to_string());
+                }
+                let total = u16::try_from(20 + l4.len())
+                    .map_err(|_| format!("packet of {} bytes is too long", 20 + l4.len()))?;
+                let flags: u16 = if self.dont_fragment { 0x4000 } else { 0 };
+                raw.extend_from_slice(&[0x45, self.tos]);
+                raw.extend_from_slice(&total.to_be_bytes());
+                raw.extend_from_slice(&self.identification.to_be_bytes());
+                raw.extend_from_slice(&flags.to_be_bytes());
+                raw.extend_from_slice(&[self.ttl, protocol, 0, 0]);
+                raw.extend_from_slice(&s.octets());
+                raw.extend_from_slice(&d.octets());
+                update_ipv4_checksum(&mut raw);
+            }
+            (IpAddr::V6(s), IpAddr::V6(d)) => {
+                let length = 8 * self.extension_headers.len() + l4.len();
+                let length = u16::try_from(length)
+                    .map_err(|_| format!("payload of {} bytes is too long", length))?;
+                let first = self.extension_headers.first().copied().unwrap_or(protocol);
+                let word = (6u32 << 28) | ((self.tos as u32) << 20) | self.flow_label;
+                raw.extend_from_slice(&word.to_be_bytes());
+                raw.extend_from_slice(&length.to_be_bytes());
+                raw.extend_from_slice(&[first, self.ttl]);
+                raw.extend_from_slice(&s.octets());
+                raw.extend_from_slice(&d.octets());
+                for (i, _) in self.extension_headers.iter().enumerate() {
+                    let next = self
+                        .extension_headers
+                        .get(i + 1)
+                        .copied()
+                        .unwrap_or(protocol);
+                    // Eight bytes: Next Header, length 0 and a PadN option filling the rest.
+                    raw.extend_from_slice(&[next, 0, 1, 4, 0, 0, 0, 0]);
+                }
+            }
+            _ => return Err("source and destination of different address families".to_string()),
+        }
+        let offset = raw.len();
+        raw.extend_from_slice(&l4);
+        match self.transport {
+            Transport::Tcp { .. } => update_tcp_checksum(&mut raw),
+            Transport::Udp { .. } => update_udp_checksum(&mut raw),
+            Transport::Icmp { .. } => {
+                let checksum = match (self.src, self.dst) {
+                    (IpAddr::V6(s), IpAddr::V6(d)) => icmpv6_checksum(s, d, &l4),
+                    _ => calculate_icmp_checksum(&l4),
+                };
+                raw[offset + 2..offset + 4].copy_from_slice(&checksum.to_be_bytes());
+            }
+            Transport::Raw(_) => {}
+        }
+        Ok(PacketMeta {
+            src_ip: self.src,
+            dst_ip: self.dst,
+            src_port,
+            dst_port,
+            protocol,
+            ttl: self.ttl,
+            dscp: self.tos >> 2,
+            ecn: self.tos & 0x3,
+            transport: transport_detail(&raw),
+            annotation: None,
+            mpls: Vec::new(),
+            vlan: None,
+            raw: raw.into(),
+        })
+    }
+}
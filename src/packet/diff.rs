@@ -0,0 +1,232 @@
+// src/packet/diff.rs
+
+//! Field‑by‑field comparison of two raw packets, e.g. a packet of the input file against the
+//! same packet in `_out.txt`, or a packet before and after a corrupting link.
+//!
+//! Both packets are split into named header fields (IPv4 or IPv6, then TCP, UDP or ICMP) and
+//! the payload behind them. Fields are compared by name, so a field only one of the packets
+//! has (e.g. after an IPv4/IPv6 translation) shows up with an empty side; payloads are
+//! compared byte by byte.
+
+use super::transport_offset;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// A header field whose value differs between the two packets. A side that lacks the field
+/// is empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+/// Differences between two packets; see [`diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PacketDiff {
+    /// Changed header fields, in header order.
+    pub fields: Vec<FieldChange>,
+    /// Payload offsets (from the start of the payload) whose bytes differ.
+    pub payload_bytes: Vec<usize>,
+    /// Payload lengths, if they differ.
+    pub payload_len: Option<(usize, usize)>,
+}
+
+impl PacketDiff {
+    /// Whether the packets are equal.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.payload_bytes.is_empty() && self.payload_len.is_none()
+    }
+
+    /// The change of `field`, if it changed.
+    pub fn field(&self, field: &str) -> Option<&FieldChange> {
+        self.fields.iter().find(|c| c.field == field)
+    }
+}
+
+impl fmt::Display for PacketDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "identical");
+        }
+        for change in &self.fields {
+            writeln!(f, "{}: {} -> {}", change.field, change.before, change.after)?;
+        }
+        if let Some((before, after)) = self.payload_len {
+            writeln!(f, "payload length: {} -> {}", before, after)?;
+        }
+        if !self.payload_bytes.is_empty() {
+            let offsets: Vec<String> = self.payload_bytes.iter().map(|o| o.to_string()).collect();
+            writeln!(f, "payload bytes differ at offsets {}", offsets.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+type Fields = Vec<(&'static str, String)>;
+
+fn hex16(value: u16) -> String {
+    format!("0x{:04x}", value)
+}
+
+fn word(raw: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([raw[at], raw[at + 1]])
+}
+
+fn dword(raw: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([raw[at], raw[at + 1], raw[at + 2], raw[at + 3]])
+}
+
+/// IP header fields of `raw` and the transport protocol and offset, or `None` if it is no
+/// IPv4/IPv6 packet.
+fn ip_fields(raw: &[u8], fields: &mut Fields) -> Option<(u8, usize)> {
+    match raw.first()? >> 4 {
+        4 if raw.len() >= 20 => {
+            let offset = transport_offset(raw)?;
+            fields.extend([
+                ("version", "4".to_string()),
+                ("ihl", (offset / 4).to_string()),
+                ("tos", format!("0x{:02x}", raw[1])),
+                ("total_length", word(raw, 2).to_string()),
+                ("identification", hex16(word(raw, 4))),
+                ("flags_fragment", hex16(word(raw, 6))),
+                ("ttl", raw[8].to_string()),
+                ("protocol", raw[9].to_string()),
+                ("header_checksum", hex16(word(raw, 10))),
+                (
+                    "src",
+                    Ipv4Addr::new(raw[12], raw[13], raw[14], raw[15]).to_string(),
+                ),
+                (
+                    "dst",
+                    Ipv4Addr::new(raw[16], raw[17], raw[18], raw[19]).to_string(),
+                ),
+                ("options", hex::encode(&raw[20..offset])),
+            ]);
+            Some((raw[9], offset))
+        }
+        6 if raw.len() >= 40 => {
+            let offset = transport_offset(raw)?;
+            let addr = |at: usize| {
+                let octets: [u8; 16] = raw[at..at + 16].try_into().unwrap();
+                Ipv6Addr::from(octets).to_string()
+            };
+            let first = dword(raw, 0);
+            let protocol = if offset == 40 {
+                raw[6]
+            } else {
+                super::next_header_before(raw, offset)?
+            };
+            fields.extend([
+                ("version", "6".to_string()),
+                ("traffic_class", format!("0x{:02x}", (first >> 20) as u8)),
+                ("flow_label", format!("0x{:05x}", first & 0xF_FFFF)),
+                ("payload_length", word(raw, 4).to_string()),
+                ("next_header", raw[6].to_string()),
+                ("hop_limit", raw[7].to_string()),
+                ("src", addr(8)),
+                ("dst", addr(24)),
+                ("extension_headers", hex::encode(&raw[40..offset])),
+            ]);
+            Some((protocol, offset))
+        }
+        _ => None,
+    }
+}
+
+/// Transport header fields of the segment at `offset`; returns where the payload starts.
+fn transport_fields(raw: &[u8], protocol: u8, offset: usize, fields: &mut Fields) -> usize {
+    let segment = &raw[offset..];
+    match protocol {
+        6 if segment.len() >= 20 => {
+            fields.extend([
+                ("src_port", word(segment, 0).to_string()),
+                ("dst_port", word(segment, 2).to_string()),
+                ("seq", dword(segment, 4).to_string()),
+                ("ack", dword(segment, 8).to_string()),
+                ("tcp_flags", format!("0x{:02x}", segment[13])),
+                ("window", word(segment, 14).to_string()),
+                ("transport_checksum", hex16(word(segment, 16))),
+            ]);
+            let header = (segment[12] >> 4) as usize * 4;
+            offset + header.clamp(20, segment.len())
+        }
+        17 if segment.len() >= 8 => {
+            fields.extend([
+                ("src_port", word(segment, 0).to_string()),
+                ("dst_port", word(segment, 2).to_string()),
+                ("udp_length", word(segment, 4).to_string()),
+                ("transport_checksum", hex16(word(segment, 6))),
+            ]);
+            offset + 8
+        }
+        1 | 58 if segment.len() >= 8 => {
+            fields.extend([
+                ("icmp_type", segment[0].to_string()),
+                ("icmp_code", segment[1].to_string()),
+                ("transport_checksum", hex16(word(segment, 2))),
+                ("icmp_rest", hex::encode(&segment[4..8])),
+            ]);
+            offset + 8
+        }
+        _ => offset,
+    }
+}
+
+/// Header fields of `raw` and the offset of its payload. Bytes that are no IP packet are all
+/// payload.
+fn fields(raw: &[u8]) -> (Fields, usize) {
+    let mut fields = Vec::new();
+    let payload = match ip_fields(raw, &mut fields) {
+        // Later fragments carry no transport header.
+        Some((_, offset)) if super::later_fragment(raw) => offset,
+        Some((protocol, offset)) => transport_fields(raw, protocol, offset, &mut fields),
+        None => 0,
+    };
+    (fields, payload)
+}
+
+/// Compare two raw packets field by field. Field names are those of the IPv4 (`ttl`,
+/// `header_checksum`, `src`, `dst`, …), IPv6 (`hop_limit`, `flow_label`, …), TCP, UDP and
+/// ICMP headers; see [`PacketDiff`].
+pub fn diff(before: &[u8], after: &[u8]) -> PacketDiff {
+    let (before_fields, before_payload) = fields(before);
+    let (after_fields, after_payload) = fields(after);
+    let value = |fields: &Fields, name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default()
+    };
+    let mut changes = PacketDiff::default();
+    let names = before_fields
+        .iter()
+        .chain(&after_fields)
+        .map(|(name, _)| *name);
+    for name in names {
+        if changes.field(name).is_some() {
+            continue;
+        }
+        let (b, a) = (value(&before_fields, name), value(&after_fields, name));
+        if b != a {
+            changes.fields.push(FieldChange {
+                field: name,
+                before: b,
+                after: a,
+            });
+        }
+    }
+    let (b, a) = (&before[before_payload..], &after[after_payload..]);
+    changes.payload_bytes = b
+        .iter()
+        .zip(a)
+        .enumerate()
+        .filter(|(_, (x, y))| x != y)
+        .map(|(i, _)| i)
+        .collect();
+    if b.len() != a.len() {
+        changes.payload_len = Some((b.len(), a.len()));
+    }
+    changes
+}
@@ -0,0 +1,350 @@
+// src/packet/explain.rs
+
+//! Human‑readable decode of raw packets, for the trace log and the `decode` subcommand.
+
+use super::{calculate_ipv4_checksum, transport_offset};
+use std::fmt::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+fn protocol_name(protocol: u8) -> &'static str {
+    match protocol {
+        0 => "Hop-by-Hop",
+        1 => "ICMP",
+        4 => "IPv4",
+        6 => "TCP",
+        17 => "UDP",
+        41 => "IPv6",
+        43 => "Routing",
+        44 => "Fragment",
+        47 => "GRE",
+        50 => "ESP",
+        51 => "AH",
+        58 => "ICMPv6",
+        59 => "No Next Header",
+        60 => "Destination Options",
+        _ => "unknown",
+    }
+}
+
+/// One's‑complement sum of `data`, folded to 16 bits.
+fn sum16(data: &[u8], mut sum: u32) -> u32 {
+    for chunk in data.chunks(2) {
+        let word = u16::from_be_bytes([chunk[0], *chunk.get(1).unwrap_or(&0)]);
+        sum += word as u32;
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum
+}
+
+/// Whether the transport checksum of `segment` (including its checksum field) verifies.
+fn transport_checksum_valid(src: IpAddr, dst: IpAddr, protocol: u8, segment: &[u8]) -> bool {
+    let mut pseudo = Vec::with_capacity(40);
+    match (src, dst) {
+        (IpAddr::V4(s), IpAddr::V4(d)) => {
+            if protocol == 1 {
+                // ICMP has no pseudo‑header.
+                return sum16(segment, 0) == 0xFFFF;
+            }
+            pseudo.extend_from_slice(&s.octets());
+            pseudo.extend_from_slice(&d.octets());
+            pseudo.extend_from_slice(&[0, protocol]);
+            pseudo.extend_from_slice(&(segment.len() as u16).to_be_bytes());
+        }
+        (IpAddr::V6(s), IpAddr::V6(d)) => {
+            pseudo.extend_from_slice(&s.octets());
+            pseudo.extend_from_slice(&d.octets());
+            pseudo.extend_from_slice(&(segment.len() as u32).to_be_bytes());
+            pseudo.extend_from_slice(&[0, 0, 0, protocol]);
+        }
+        _ => return false,
+    }
+    sum16(segment, sum16(&pseudo, 0)) == 0xFFFF
+}
+
+fn validity(valid: bool) -> &'static str {
+    if valid {
+        "valid"
+    } else {
+        "INVALID"
+    }
+}
+
+/// Classic hex dump: offset, 16 bytes in hex and their printable ASCII.
+pub fn hex_dump(data: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, chunk) in data.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        let _ = writeln!(out, "{:04x}  {:<47}  {}", i * 16, hex.join(" "), ascii);
+    }
+    out
+}
+
+/// Multi‑line decode of a raw IPv4/IPv6 packet: IP header fields, extension headers, the
+/// TCP/UDP/ICMP header with checksum validity, and a hex dump of the whole packet.
+/// Truncated or unknown data is reported rather than rejected.
+pub fn explain(raw: &[u8]) -> String {
+    let mut out = String::new();
+    let ip = match raw.first().map(|b| b >> 4) {
+        Some(4) => explain_ipv4(raw, &mut out),
+        Some(6) => explain_ipv6(raw, &mut out),
+        Some(v) => {
+            let _ = writeln!(out, "unsupported IP version {}", v);
+            None
+        }
+        None => {
+            let _ = writeln!(out, "empty packet");
+            None
+        }
+    };
+    if let Some((src, dst, protocol, offset)) = ip {
+        explain_transport(raw, src, dst, protocol, offset, &mut out);
+    }
+    let _ = writeln!(out, "{} bytes:", raw.len());
+    out.push_str(&hex_dump(raw));
+    out
+}
+
+/// Returns source, destination, transport protocol and its offset (if not a later fragment).
+fn explain_ipv4(raw: &[u8], out: &mut String) -> Option<(IpAddr, IpAddr, u8, usize)> {
+    if raw.len() < 20 {
+        let _ = writeln!(out, "IPv4 truncated: {} of 20 header bytes", raw.len());
+        return None;
+    }
+    let ihl = (raw[0] & 0x0F) as usize * 4;
+    let total_len = u16::from_be_bytes([raw[2], raw[3]]);
+    let flags_offset = u16::from_be_bytes([raw[6], raw[7]]);
+    let src = Ipv4Addr::new(raw[12], raw[13], raw[14], raw[15]);
+    let dst = Ipv4Addr::new(raw[16], raw[17], raw[18], raw[19]);
+    let checksum = u16::from_be_bytes([raw[10], raw[11]]);
+    let mut flags = Vec::new();
+    if flags_offset & 0x4000 != 0 {
+        flags.push("DF");
+    }
+    if flags_offset & 0x2000 != 0 {
+        flags.push("MF");
+    }
+    let _ = writeln!(out, "IPv4 {} -> {}", src, dst);
+    let _ = writeln!(
+        out,
+        "  header length {}, tos 0x{:02x} (dscp {}, ecn {}), total length {}",
+        ihl,
+        raw[1],
+        raw[1] >> 2,
+        raw[1] & 0x03,
+        total_len
+    );
+    let _ = writeln!(
+        out,
+        "  id 0x{:04x}, flags [{}], fragment offset {}",
+        u16::from_be_bytes([raw[4], raw[5]]),
+        flags.join(" "),
+        (flags_offset & 0x1FFF) as usize * 8
+    );
+    let _ = writeln!(
+        out,
+        "  ttl {}, protocol {} ({}), header checksum 0x{:04x} ({})",
+        raw[8],
+        raw[9],
+        protocol_name(raw[9]),
+        checksum,
+        validity(ihl >= 20 && ihl <= raw.len() && calculate_ipv4_checksum(raw) == checksum)
+    );
+    if ihl > 20 && ihl <= raw.len() {
+        let _ = writeln!(out, "  options {} bytes", ihl - 20);
+    }
+    if total_len as usize != raw.len() {
+        let _ = writeln!(
+            out,
+            "  total length {} does not match {} captured bytes",
+            total_len,
+            raw.len()
+        );
+    }
+    if flags_offset & 0x1FFF != 0 {
+        // Later fragments carry no transport header.
+        return None;
+    }
+    let end = (total_len as usize).clamp(ihl, raw.len());
+    transport_offset(&raw[..end]).map(|offset| (src.into(), dst.into(), raw[9], offset))
+}
+
+fn explain_ipv6(raw: &[u8], out: &mut String) -> Option<(IpAddr, IpAddr, u8, usize)> {
+    if raw.len() < 40 {
+        let _ = writeln!(out, "IPv6 truncated: {} of 40 header bytes", raw.len());
+        return None;
+    }
+    let octets = |at: usize| -> [u8; 16] { raw[at..at + 16].try_into().unwrap() };
+    let src = Ipv6Addr::from(octets(8));
+    let dst = Ipv6Addr::from(octets(24));
+    let traffic_class = ((raw[0] & 0x0F) << 4) | (raw[1] >> 4);
+    let flow_label = u32::from_be_bytes([0, raw[1] & 0x0F, raw[2], raw[3]]);
+    let _ = writeln!(out, "IPv6 {} -> {}", src, dst);
+    let _ = writeln!(
+        out,
+        "  traffic class 0x{:02x} (dscp {}, ecn {}), flow label 0x{:05x}, payload length {}",
+        traffic_class,
+        traffic_class >> 2,
+        traffic_class & 0x03,
+        flow_label,
+        u16::from_be_bytes([raw[4], raw[5]])
+    );
+    let _ = writeln!(
+        out,
+        "  hop limit {}, next header {} ({})",
+        raw[7],
+        raw[6],
+        protocol_name(raw[6])
+    );
+    // Walk the extension headers the same way `transport_offset` skips them.
+    let (mut next, mut offset) = (raw[6], 40usize);
+    while matches!(next, 0 | 43 | 44 | 51 | 60) {
+        let len = match (next, raw.get(offset + 1)) {
+            (44, Some(_)) => 8,
+            (51, Some(&l)) => (l as usize + 2) * 4,
+            (_, Some(&l)) => (l as usize + 1) * 8,
+            (_, None) => {
+                let _ = writeln!(out, "  {} header truncated", protocol_name(next));
+                return None;
+            }
+        };
+        let _ = writeln!(
+            out,
+            "  extension {} ({} bytes), next header {} ({})",
+            protocol_name(next),
+            len,
+            raw[offset],
+            protocol_name(raw[offset])
+        );
+        if next == 44 && raw.len() >= offset + 4 {
+            let frag = u16::from_be_bytes([raw[offset + 2], raw[offset + 3]]);
+            if frag & 0xFFF8 != 0 {
+                // Later fragments carry no transport header.
+                let _ = writeln!(out, "  fragment offset {}", (frag >> 3) as usize * 8);
+                return None;
+            }
+        }
+        next = raw[offset];
+        offset += len;
+    }
+    (offset <= raw.len()).then_some((src.into(), dst.into(), next, offset))
+}
+
+fn explain_transport(
+    raw: &[u8],
+    src: IpAddr,
+    dst: IpAddr,
+    protocol: u8,
+    offset: usize,
+    out: &mut String,
+) {
+    let segment = &raw[offset..];
+    let checksum = |at: usize| u16::from_be_bytes([segment[at], segment[at + 1]]);
+    match protocol {
+        6 if segment.len() >= 20 => {
+            let flags = segment[13];
+            let names: Vec<&str> = [
+                (0x80, "CWR"),
+                (0x40, "ECE"),
+                (0x20, "URG"),
+                (0x10, "ACK"),
+                (0x08, "PSH"),
+                (0x04, "RST"),
+                (0x02, "SYN"),
+                (0x01, "FIN"),
+            ]
+            .iter()
+            .filter(|(bit, _)| flags & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+            let _ = writeln!(
+                out,
+                "TCP {} -> {}, flags [{}]",
+                u16::from_be_bytes([segment[0], segment[1]]),
+                u16::from_be_bytes([segment[2], segment[3]]),
+                names.join(" ")
+            );
+            let _ = writeln!(
+                out,
+                "  seq {}, ack {}, header length {}, window {}",
+                u32::from_be_bytes(segment[4..8].try_into().unwrap()),
+                u32::from_be_bytes(segment[8..12].try_into().unwrap()),
+                (segment[12] >> 4) as usize * 4,
+                u16::from_be_bytes([segment[14], segment[15]])
+            );
+            let _ = writeln!(
+                out,
+                "  checksum 0x{:04x} ({})",
+                checksum(16),
+                validity(transport_checksum_valid(src, dst, protocol, segment))
+            );
+        }
+        17 if segment.len() >= 8 => {
+            let _ = writeln!(
+                out,
+                "UDP {} -> {}, length {}",
+                u16::from_be_bytes([segment[0], segment[1]]),
+                u16::from_be_bytes([segment[2], segment[3]]),
+                u16::from_be_bytes([segment[4], segment[5]])
+            );
+            let sum = checksum(6);
+            let state = if sum == 0 && src.is_ipv4() {
+                "not computed"
+            } else {
+                validity(transport_checksum_valid(src, dst, protocol, segment))
+            };
+            let _ = writeln!(out, "  checksum 0x{:04x} ({})", sum, state);
+        }
+        1 | 58 if segment.len() >= 4 => {
+            let _ = writeln!(
+                out,
+                "{} type {}, code {}",
+                protocol_name(protocol),
+                segment[0],
+                segment[1]
+            );
+            if matches!((protocol, segment[0]), (1, 0 | 8) | (58, 128 | 129)) && segment.len() >= 8
+            {
+                let _ = writeln!(
+                    out,
+                    "  echo id {}, sequence {}",
+                    u16::from_be_bytes([segment[4], segment[5]]),
+                    u16::from_be_bytes([segment[6], segment[7]])
+                );
+            }
+            let _ = writeln!(
+                out,
+                "  checksum 0x{:04x} ({})",
+                checksum(2),
+                validity(transport_checksum_valid(src, dst, protocol, segment))
+            );
+        }
+        6 | 17 | 1 | 58 => {
+            let _ = writeln!(
+                out,
+                "{} header truncated: {} bytes",
+                protocol_name(protocol),
+                segment.len()
+            );
+        }
+        _ => {
+            let _ = writeln!(
+                out,
+                "{} payload, {} bytes",
+                protocol_name(protocol),
+                segment.len()
+            );
+        }
+    }
+}
@@ -1,6 +1,20 @@
 // src/packet/mod.rs
 
+pub mod builder;
+mod diff;
+mod explain;
+pub mod mpls;
+pub mod options;
+pub mod vlan;
+
+use crate::sojourn::Annotation;
+use bytes::Bytes;
+pub use diff::{diff, FieldChange, PacketDiff};
+pub use explain::{explain, hex_dump};
+use mpls::{MplsLabel, ETHERTYPE_MPLS, ETHERTYPE_MPLS_MULTICAST, MAX_LABEL};
+use serde::{Serialize, Serializer};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::{Deref, DerefMut};
 
 /// Calculate IPv4 header checksum (RFC 791).
 pub fn calculate_ipv4_checksum(header: &[u8]) -> u16 {
@@ -44,8 +58,329 @@ pub fn update_ipv4_checksum(packet: &mut [u8]) {
     packet[11] = (checksum & 0xFF) as u8;
 }
 
-/// Minimal packet metadata used by the simulator.
-#[derive(Debug, Clone)]
+/// TCP/UDP checksum of `segment` (with its checksum field zeroed) over the IPv4 or IPv6
+/// pseudo‑header of `src` → `dst`. A computed zero is returned as 0xFFFF, since a zero UDP
+/// checksum means "none".
+pub fn transport_checksum(src: IpAddr, dst: IpAddr, protocol: u8, segment: &[u8]) -> u16 {
+    let mut pseudo = Vec::with_capacity(40);
+    match (src, dst) {
+        (IpAddr::V4(s), IpAddr::V4(d)) => {
+            pseudo.extend_from_slice(&s.octets());
+            pseudo.extend_from_slice(&d.octets());
+            pseudo.extend_from_slice(&[0, protocol]);
+            pseudo.extend_from_slice(&(segment.len() as u16).to_be_bytes());
+        }
+        (IpAddr::V6(s), IpAddr::V6(d)) => {
+            pseudo.extend_from_slice(&s.octets());
+            pseudo.extend_from_slice(&d.octets());
+            pseudo.extend_from_slice(&(segment.len() as u32).to_be_bytes());
+            pseudo.extend_from_slice(&[0, 0, 0, protocol]);
+        }
+        _ => return 0,
+    }
+    let mut sum: u32 = 0;
+    for chunk in pseudo.chunks(2).chain(segment.chunks(2)) {
+        sum += u16::from_be_bytes([chunk[0], *chunk.get(1).unwrap_or(&0)]) as u32;
+    }
+    while (sum >> 16) != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    match !(sum as u16) {
+        0 => 0xFFFF,
+        c => c,
+    }
+}
+
+/// Addresses, protocol, transport offset and end of the IP payload of a raw IPv4/IPv6 packet.
+fn transport_header(packet: &[u8]) -> Option<(IpAddr, IpAddr, u8, usize, usize)> {
+    let offset = transport_offset(packet)?;
+    match packet[0] >> 4 {
+        4 => {
+            let length = u16::from_be_bytes([packet[2], packet[3]]) as usize;
+            let src: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+            let end = length.clamp(offset, packet.len());
+            Some((src.into(), dst.into(), packet[9], offset, end))
+        }
+        _ => {
+            let src: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+            // The protocol is the Next Header of the last extension header.
+            let protocol = if offset == 40 {
+                packet[6]
+            } else {
+                next_header_before(packet, offset)?
+            };
+            let length = u16::from_be_bytes([packet[4], packet[5]]) as usize;
+            let end = (40 + length).clamp(offset, packet.len());
+            Some((src.into(), dst.into(), protocol, offset, end))
+        }
+    }
+}
+
+/// Next Header value of the IPv6 extension header chain that ends at `end`.
+fn next_header_before(packet: &[u8], end: usize) -> Option<u8> {
+    let mut next = *packet.get(6)?;
+    let mut offset = 40;
+    while offset < end {
+        let len = match next {
+            44 => 8,
+            51 => (*packet.get(offset + 1)? as usize + 2) * 4,
+            _ => (*packet.get(offset + 1)? as usize + 1) * 8,
+        };
+        next = *packet.get(offset)?;
+        offset += len;
+    }
+    Some(next)
+}
+
+/// Recompute the checksum of the transport header at `field` bytes into the segment, if the
+/// packet carries `protocol`.
+fn update_transport_checksum(packet: &mut [u8], protocol: u8, field: usize) {
+    let Some((src, dst, found, offset, end)) = transport_header(packet) else {
+        return;
+    };
+    if found != protocol || end < offset + field + 2 {
+        return;
+    }
+    packet[offset + field..offset + field + 2].copy_from_slice(&[0, 0]);
+    let checksum = transport_checksum(src, dst, protocol, &packet[offset..end]);
+    packet[offset + field..offset + field + 2].copy_from_slice(&checksum.to_be_bytes());
+}
+
+/// Update the TCP checksum in-place after the addresses, ports or payload changed. Packets
+/// that are not TCP are left alone.
+pub fn update_tcp_checksum(packet: &mut [u8]) {
+    update_transport_checksum(packet, 6, 16);
+}
+
+/// Update the UDP checksum in-place after the addresses, ports or payload changed. Packets
+/// that are not UDP are left alone.
+pub fn update_udp_checksum(packet: &mut [u8]) {
+    update_transport_checksum(packet, 17, 6);
+}
+
+/// Whether the transport header of `packet` carries a correct checksum. Packets that are not
+/// TCP or UDP, fragments (the checksum covers the whole datagram) and IPv4 UDP without a
+/// checksum (zero) count as correct.
+fn transport_checksum_ok(packet: &[u8]) -> bool {
+    let Some((src, dst, protocol, offset, end)) = transport_header(packet) else {
+        return true;
+    };
+    let field = match protocol {
+        6 => 16,
+        17 => 6,
+        _ => return true,
+    };
+    if crate::simulation::fragment_key(packet).is_some() || end < offset + field + 2 {
+        return true;
+    }
+    let stored = u16::from_be_bytes([packet[offset + field], packet[offset + field + 1]]);
+    if protocol == 17 && stored == 0 && src.is_ipv4() {
+        return true;
+    }
+    let mut segment = packet[offset..end].to_vec();
+    segment[field..field + 2].copy_from_slice(&[0, 0]);
+    let expected = transport_checksum(src, dst, protocol, &segment);
+    // TCP may carry a computed zero as 0x0000 rather than 0xFFFF.
+    stored == expected || (protocol == 6 && expected == 0xFFFF && stored == 0)
+}
+
+/// Verify the IPv4 header checksum of `data` and, with `transport`, the TCP/UDP checksum of
+/// IPv4 and IPv6 packets. `parse` accepts any header; this is the check for callers that
+/// must not.
+pub fn verify_checksums(data: &[u8], transport: bool) -> Result<(), &'static str> {
+    if data.first().map(|b| b >> 4) == Some(4) && data.len() >= 20 {
+        let stored = u16::from_be_bytes([data[10], data[11]]);
+        if stored != calculate_ipv4_checksum(data) {
+            return Err("bad IPv4 header checksum");
+        }
+    }
+    if transport && !transport_checksum_ok(data) {
+        return Err("bad transport checksum");
+    }
+    Ok(())
+}
+
+/// IPv4/UDP packet from `src` to `dst` carrying `payload`, with `port` as both UDP ports, as
+/// used by the tunnels between the edge routers. The UDP checksum is left zero, which IPv4 allows.
+pub fn ipv4_udp(src: Ipv4Addr, dst: Ipv4Addr, port: u16, payload: &[u8]) -> PacketMeta {
+    let total = 28 + payload.len();
+    let mut raw = Vec::with_capacity(total);
+    raw.extend_from_slice(&[0x45, 0x00]);
+    raw.extend_from_slice(&(total as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0, 0, 0, 64, 17, 0, 0]);
+    raw.extend_from_slice(&src.octets());
+    raw.extend_from_slice(&dst.octets());
+    update_ipv4_checksum(&mut raw);
+    raw.extend_from_slice(&port.to_be_bytes());
+    raw.extend_from_slice(&port.to_be_bytes());
+    raw.extend_from_slice(&((total - 20) as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0]);
+    raw.extend_from_slice(payload);
+    PacketMeta {
+        src_ip: src.into(),
+        dst_ip: dst.into(),
+        src_port: port,
+        dst_port: port,
+        protocol: 17,
+        ttl: 64,
+        dscp: 0,
+        ecn: 0,
+        transport: transport_detail(&raw),
+        annotation: None,
+        mpls: Vec::new(),
+        vlan: None,
+        raw: raw.into(),
+    }
+}
+
+/// Offset of the transport header in a raw IPv4/IPv6 packet, skipping IPv4 options and the
+/// common IPv6 extension headers (hop‑by‑hop, routing, fragment, destination options, AH).
+pub fn transport_offset(raw: &[u8]) -> Option<usize> {
+    match raw.first()? >> 4 {
+        4 => Some((raw[0] & 0x0F) as usize * 4).filter(|ihl| *ihl >= 20 && *ihl <= raw.len()),
+        6 => {
+            let mut next = *raw.get(6)?;
+            let mut offset = 40;
+            loop {
+                let len = match next {
+                    0 | 43 | 60 => (*raw.get(offset + 1)? as usize + 1) * 8,
+                    44 => 8,
+                    51 => (*raw.get(offset + 1)? as usize + 2) * 4,
+                    _ => return (offset <= raw.len()).then_some(offset),
+                };
+                next = *raw.get(offset)?;
+                offset += len;
+            }
+        }
+        _ => None,
+    }
+}
+
+/// IPv4 TOS or IPv6 Traffic Class byte of a raw packet: DSCP in the upper six bits, ECN in the
+/// lower two.
+pub fn traffic_class(raw: &[u8]) -> Option<u8> {
+    match raw.first().map(|b| b >> 4) {
+        Some(4) => raw.get(1).copied(),
+        Some(6) if raw.len() >= 2 => Some((raw[0] << 4) | (raw[1] >> 4)),
+        _ => None,
+    }
+}
+
+/// Transport‑layer detail of a packet beyond its ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportDetail {
+    Tcp {
+        flags: u8,
+        seq: u32,
+        ack: u32,
+    },
+    /// Length of the UDP header and payload.
+    Udp {
+        length: u16,
+    },
+    /// ICMP or ICMPv6 type and code, with the identifier and sequence number of an echo
+    /// request or reply.
+    Icmp {
+        icmp_type: u8,
+        code: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        echo: Option<IcmpEcho>,
+    },
+}
+
+/// Identifier and sequence number of an ICMP or ICMPv6 echo request or reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct IcmpEcho {
+    pub id: u16,
+    pub seq: u16,
+}
+
+/// Whether `raw` is a fragment other than the first, which carries no transport header.
+fn later_fragment(raw: &[u8]) -> bool {
+    match raw.first().map(|b| b >> 4) {
+        Some(4) => raw.len() >= 20 && u16::from_be_bytes([raw[6], raw[7]]) & 0x1FFF != 0,
+        Some(6) => {
+            let (Some(&first), Some(end)) = (raw.get(6), transport_offset(raw)) else {
+                return false;
+            };
+            let (mut next, mut offset) = (first, 40);
+            while offset < end {
+                if next == 44 {
+                    return raw
+                        .get(offset + 2..offset + 4)
+                        .is_some_and(|b| u16::from_be_bytes([b[0], b[1]]) & 0xFFF8 != 0);
+                }
+                let Some(&len) = raw.get(offset + 1) else {
+                    return false;
+                };
+                let len = match next {
+                    51 => (len as usize + 2) * 4,
+                    _ => (len as usize + 1) * 8,
+                };
+                next = raw[offset];
+                offset += len;
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+/// TCP flags and sequence numbers, UDP length or ICMP type, code and echo fields of a raw
+/// IPv4/IPv6 packet, behind IPv4 options and IPv6 extension headers.
+pub fn transport_detail(raw: &[u8]) -> Option<TransportDetail> {
+    if later_fragment(raw) {
+        return None;
+    }
+    let (_, _, protocol, offset, _) = transport_header(raw)?;
+    let segment = &raw[offset..];
+    let word = |at: usize| {
+        u32::from_be_bytes([
+            segment[at],
+            segment[at + 1],
+            segment[at + 2],
+            segment[at + 3],
+        ])
+    };
+    match protocol {
+        6 if segment.len() >= 14 => Some(TransportDetail::Tcp {
+            flags: segment[13],
+            seq: word(4),
+            ack: word(8),
+        }),
+        17 if segment.len() >= 6 => Some(TransportDetail::Udp {
+            length: u16::from_be_bytes([segment[4], segment[5]]),
+        }),
+        1 | 58 if segment.len() >= 2 => {
+            let half = |at: usize| u16::from_be_bytes([segment[at], segment[at + 1]]);
+            let is_echo = match protocol {
+                1 => matches!(segment[0], 0 | 8),
+                _ => matches!(segment[0], 128 | 129),
+            };
+            Some(TransportDetail::Icmp {
+                icmp_type: segment[0],
+                code: segment[1],
+                echo: (is_echo && segment.len() >= 8).then(|| IcmpEcho {
+                    id: half(4),
+                    seq: half(6),
+                }),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Hex string of the raw bytes, as in packet files.
+fn serialize_hex<S: Serializer>(raw: &Bytes, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(raw))
+}
+
+/// Minimal packet metadata used by the simulator. Serializes with `raw` as a hex string and
+/// without the fields that are unset or empty.
+#[derive(Debug, Clone, Serialize)]
 pub struct PacketMeta {
     pub src_ip: IpAddr,
     pub dst_ip: IpAddr,
@@ -53,11 +388,91 @@ pub struct PacketMeta {
     pub dst_port: u16,
     pub protocol: u8, // TCP=6, UDP=17, ICMP=1, ICMPv6=58
     pub ttl: u8,
-    // Original raw bytes of the packet, preserved for write‑back.
-    pub raw: Vec<u8>,
+    // Upper six bits of the IPv4 TOS / IPv6 Traffic Class byte.
+    pub dscp: u8,
+    // Lower two bits of the same byte: 0 Not-ECT, 1 ECT(1), 2 ECT(0), 3 CE.
+    pub ecn: u8,
+    // TCP or UDP header detail, when the packet carries one (not for later fragments).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transport: Option<TransportDetail>,
+    // Path taken through the fabric, set on the packet `process_packet` returns. When an ICMP
+    // error replaced the packet, the path includes the hops of the original.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<Box<Annotation>>,
+    // MPLS label stack the packet arrived with, outermost first (see `mpls`); `raw` is the IP
+    // packet under it.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mpls: Vec<MplsLabel>,
+    // 802.1Q tag of the frame the packet arrived in on a TAP edge (see `vlan`); links may
+    // filter or rewrite it and it is put back on the frame leaving the fabric.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vlan: Option<vlan::VlanTag>,
+    // Original raw bytes of the packet, preserved for write‑back. Clones share the buffer;
+    // `raw_mut` copies it only if it is shared.
+    #[serde(serialize_with = "serialize_hex")]
+    pub raw: Bytes,
+}
+
+/// An empty IPv4 packet between unspecified addresses, with a TTL of 64 and no header
+/// detail; literals set the fields they need and take the rest from here.
+impl Default for PacketMeta {
+    fn default() -> Self {
+        Self {
+            src_ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            dst_ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            src_port: 0,
+            dst_port: 0,
+            protocol: 0,
+            ttl: 64,
+            dscp: 0,
+            ecn: 0,
+            transport: None,
+            annotation: None,
+            mpls: Vec::new(),
+            vlan: None,
+            raw: Bytes::new(),
+        }
+    }
+}
+
+/// Mutable view of the raw bytes of a packet, written back when dropped; see
+/// [`PacketMeta::raw_mut`].
+pub struct RawMut<'a> {
+    raw: &'a mut Bytes,
+    buf: Vec<u8>,
+}
+
+impl Deref for RawMut<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buf
+    }
+}
+
+impl DerefMut for RawMut<'_> {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+}
+
+impl Drop for RawMut<'_> {
+    fn drop(&mut self) {
+        *self.raw = Bytes::from(std::mem::take(&mut self.buf));
+    }
 }
 
 impl PacketMeta {
+    /// Mutable access to the raw bytes. The buffer is taken over without a copy unless a clone
+    /// of the packet (a capture, a drop record, a queued copy) still shares it.
+    pub fn raw_mut(&mut self) -> RawMut<'_> {
+        let buf = Vec::from(std::mem::take(&mut self.raw));
+        RawMut {
+            raw: &mut self.raw,
+            buf,
+        }
+    }
+
     /// Decrement the TTL (or Hop Limit) of the packet.
     ///
     /// This updates both the `ttl` field and the corresponding byte in the raw packet data.
@@ -73,22 +488,250 @@ impl PacketMeta {
             IpAddr::V4(_) => {
                 // IPv4 TTL is at offset 8. If raw data is present and long enough, update it.
                 if self.raw.len() > 8 {
-                    let ttl_byte = self.raw[8];
-                    self.raw[8] = ttl_byte.saturating_sub(1);
+                    let mut raw = self.raw_mut();
+                    raw[8] = raw[8].saturating_sub(1);
                     // Recalculate IPv4 header checksum after TTL change.
-                    update_ipv4_checksum(&mut self.raw);
+                    update_ipv4_checksum(&mut raw);
                 }
             }
             IpAddr::V6(_) => {
                 // IPv6 Hop Limit is at offset 7.
                 if self.raw.len() > 7 {
-                    let hl = self.raw[7];
-                    self.raw[7] = hl.saturating_sub(1);
+                    let mut raw = self.raw_mut();
+                    raw[7] = raw[7].saturating_sub(1);
                 }
             }
         }
         Ok(())
     }
+
+    /// TCP flags of the packet, if it carries a TCP header.
+    pub fn tcp_flags(&self) -> Option<u8> {
+        match self.transport {
+            Some(TransportDetail::Tcp { flags, .. }) => Some(flags),
+            _ => None,
+        }
+    }
+
+    /// ICMP or ICMPv6 type and code, if the packet carries an ICMP header.
+    pub fn icmp_type_code(&self) -> Option<(u8, u8)> {
+        match self.transport {
+            Some(TransportDetail::Icmp {
+                icmp_type, code, ..
+            }) => Some((icmp_type, code)),
+            _ => None,
+        }
+    }
+
+    /// Identifier and sequence number, if the packet is an echo request or reply.
+    pub fn icmp_echo(&self) -> Option<IcmpEcho> {
+        match self.transport {
+            Some(TransportDetail::Icmp { echo, .. }) => echo,
+            _ => None,
+        }
+    }
+
+    /// IPv6 flow label (20 bits); `None` for IPv4.
+    pub fn flow_label(&self) -> Option<u32> {
+        match self.raw.get(..4) {
+            Some(&[b0, b1, b2, b3]) if b0 >> 4 == 6 => {
+                Some(u32::from_be_bytes([0, b1 & 0x0F, b2, b3]))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the packet opens a TCP connection: SYN set, ACK, RST and FIN clear.
+    pub fn is_tcp_syn(&self) -> bool {
+        self.tcp_flags().is_some_and(|f| f & 0x17 == 0x02)
+    }
+
+    /// Rewrite the DSCP, keeping the ECN bits and the IPv4 header checksum valid.
+    pub fn set_dscp(&mut self, dscp: u8) {
+        self.dscp = dscp & 0x3F;
+        self.set_traffic_class((self.dscp << 2) | self.ecn);
+    }
+
+    /// Rewrite the ECN bits (e.g. 3 to mark Congestion Experienced), keeping the DSCP and the
+    /// IPv4 header checksum valid.
+    pub fn set_ecn(&mut self, ecn: u8) {
+        self.ecn = ecn & 0x3;
+        self.set_traffic_class((self.dscp << 2) | self.ecn);
+    }
+
+    /// Write the IPv4 TOS / IPv6 Traffic Class byte of the raw packet.
+    fn set_traffic_class(&mut self, tc: u8) {
+        match self.raw.first().map(|b| b >> 4) {
+            Some(4) if self.raw.len() >= 20 => {
+                let mut raw = self.raw_mut();
+                raw[1] = tc;
+                update_ipv4_checksum(&mut raw);
+            }
+            Some(6) if self.raw.len() >= 2 => {
+                let mut raw = self.raw_mut();
+                raw[0] = (raw[0] & 0xF0) | (tc >> 4);
+                raw[1] = (raw[1] & 0x0F) | (tc << 4);
+            }
+            _ => {}
+        }
+    }
+
+    /// Write the router with `address` into the IPv4 Record Route and Timestamp options, if the
+    /// packet has them, keeping the header checksum valid.
+    pub fn record_hop(&mut self, address: Ipv4Addr) {
+        if !options::has_options(&self.raw) {
+            return;
+        }
+        let mut raw = self.raw_mut();
+        if options::record_hop(&mut raw, address, options::timestamp_now()) {
+            update_ipv4_checksum(&mut raw);
+        }
+    }
+
+    /// Rewrite the TTL (or Hop Limit), keeping the IPv4 header checksum valid.
+    pub fn set_ttl(&mut self, ttl: u8) {
+        self.ttl = ttl;
+        match self.src_ip {
+            IpAddr::V4(_) if self.raw.len() > 8 => {
+                let mut raw = self.raw_mut();
+                raw[8] = ttl;
+                update_ipv4_checksum(&mut raw);
+            }
+            IpAddr::V6(_) if self.raw.len() > 7 => self.raw_mut()[7] = ttl,
+            _ => {}
+        }
+    }
+
+    /// Push `label` on top of the MPLS label stack.
+    pub fn push_label(&mut self, label: MplsLabel) -> Result<(), String> {
+        if label.label > MAX_LABEL {
+            return Err(format!("MPLS label {} exceeds {}", label.label, MAX_LABEL));
+        }
+        self.mpls.insert(0, label);
+        Ok(())
+    }
+
+    /// Pop the top label, if the packet is labeled.
+    pub fn pop_label(&mut self) -> Option<MplsLabel> {
+        (!self.mpls.is_empty()).then(|| self.mpls.remove(0))
+    }
+
+    /// Swap the top label for `label` and decrement its TTL, as a label switching router does.
+    pub fn swap_label(&mut self, label: u32) -> Result<(), String> {
+        if label > MAX_LABEL {
+            return Err(format!("MPLS label {} exceeds {}", label, MAX_LABEL));
+        }
+        let top = self.mpls.first_mut().ok_or("packet is not labeled")?;
+        if top.ttl <= 1 {
+            return Err("MPLS TTL expired".to_string());
+        }
+        top.label = label;
+        top.ttl -= 1;
+        Ok(())
+    }
+
+    /// The packet with its label stack in front, as it goes on the wire.
+    pub fn labeled(&self) -> Vec<u8> {
+        let mut out = mpls::encode_stack(&self.mpls);
+        out.extend_from_slice(&self.raw);
+        out
+    }
+}
+
+/// Parse an MPLS‑labeled packet: the label stack, then the IPv4 or IPv6 packet under it.
+pub fn parse_labeled(data: &[u8]) -> Result<PacketMeta, &'static str> {
+    let (stack, len) = mpls::parse_stack(data)?;
+    let mut meta = parse(&data[len..])?;
+    meta.mpls = stack;
+    Ok(meta)
+}
+
+/// Parse the payload of a frame or tunnel carrying `ethertype`: labeled for the MPLS types, IP
+/// otherwise.
+pub fn parse_ethertype(ethertype: u16, data: &[u8]) -> Result<PacketMeta, &'static str> {
+    match ethertype {
+        ETHERTYPE_MPLS | ETHERTYPE_MPLS_MULTICAST => parse_labeled(data),
+        _ => parse(data),
+    }
+}
+
+/// Split an IPv4 packet into fragments of at most `mtu` bytes (RFC 791). Options are kept in
+/// the first fragment; later fragments only carry the options whose copied flag is set. A packet
+/// that is itself a fragment is split further, keeping its offset and More Fragments flag.
+/// Fails for IPv6 packets, packets with Don't Fragment set and MTUs too small for 8 data bytes.
+pub fn fragment_ipv4(raw: &[u8], mtu: usize) -> Result<Vec<Vec<u8>>, &'static str> {
+    if raw.first().map(|b| b >> 4) != Some(4) || raw.len() < 20 {
+        return Err("not an IPv4 packet");
+    }
+    let ihl = (raw[0] & 0x0F) as usize * 4;
+    let total = (u16::from_be_bytes([raw[2], raw[3]]) as usize).min(raw.len());
+    if ihl < 20 || ihl > total {
+        return Err("malformed IPv4 header");
+    }
+    let flags = u16::from_be_bytes([raw[6], raw[7]]);
+    if flags & 0x4000 != 0 {
+        return Err("Don't Fragment is set");
+    }
+    if total <= mtu {
+        return Ok(vec![raw[..total].to_vec()]);
+    }
+    let later_header = copied_options_header(&raw[..ihl]);
+    let max_header = ihl.max(later_header.len());
+    if mtu < max_header + 8 {
+        return Err("MTU too small to fragment");
+    }
+    let base_offset = (flags & 0x1FFF) as usize * 8;
+    let more = flags & 0x2000 != 0;
+    let data = &raw[ihl..total];
+    let mut fragments = Vec::new();
+    let mut at = 0;
+    while at < data.len() {
+        let header: &[u8] = if at == 0 { &raw[..ihl] } else { &later_header };
+        let room = (mtu - header.len()) / 8 * 8;
+        let end = (at + room).min(data.len());
+        let last = end == data.len();
+        let mut fragment = header.to_vec();
+        fragment.extend_from_slice(&data[at..end]);
+        let len = fragment.len() as u16;
+        fragment[2..4].copy_from_slice(&len.to_be_bytes());
+        let offset = ((base_offset + at) / 8) as u16;
+        let mf = if !last || more { 0x2000 } else { 0 };
+        fragment[6..8].copy_from_slice(&(mf | offset).to_be_bytes());
+        update_ipv4_checksum(&mut fragment);
+        fragments.push(fragment);
+        at = end;
+    }
+    Ok(fragments)
+}
+
+/// Whether an IPv4 packet is a fragment: More Fragments is set or the offset is not zero.
+pub fn is_fragment(raw: &[u8]) -> bool {
+    raw.len() >= 20 && raw[0] >> 4 == 4 && u16::from_be_bytes([raw[6], raw[7]]) & 0x3FFF != 0
+}
+
+/// IPv4 header for the second and later fragments: only options with the copied flag stay,
+/// padded to a multiple of four bytes.
+fn copied_options_header(header: &[u8]) -> Vec<u8> {
+    let mut out = header[..20].to_vec();
+    let mut i = 20;
+    while i < header.len() {
+        let kind = header[i];
+        let len = match kind {
+            0 => break,
+            1 => 1,
+            _ => (*header.get(i + 1).unwrap_or(&2) as usize).max(2),
+        };
+        let option = &header[i..(i + len).min(header.len())];
+        if kind & 0x80 != 0 {
+            out.extend_from_slice(option);
+        }
+        i += len;
+    }
+    while !out.len().is_multiple_of(4) {
+        out.push(0);
+    }
+    out[0] = 0x40 | (out.len() / 4) as u8;
+    out
 }
 
 /// Stub parser – in the full version this would decode raw bytes using the `pnet` crate.
@@ -113,14 +756,16 @@ pub fn parse(data: &[u8]) -> Result<PacketMeta, &'static str> {
         let protocol = data[9];
         let src_ip = Ipv4Addr::new(data[12], data[13], data[14], data[15]);
         let dst_ip = Ipv4Addr::new(data[16], data[17], data[18], data[19]);
-        // Extract ports for TCP/UDP if possible
-        let (src_port, dst_port) = if (protocol == 6 || protocol == 17) && data.len() >= ihl + 4 {
-            let sp = u16::from_be_bytes([data[ihl], data[ihl + 1]]);
-            let dp = u16::from_be_bytes([data[ihl + 2], data[ihl + 3]]);
-            (sp, dp)
-        } else {
-            (0, 0)
-        };
+        // Extract ports for TCP/UDP if possible; only the first fragment carries them.
+        let first_fragment = u16::from_be_bytes([data[6], data[7]]) & 0x1FFF == 0;
+        let (src_port, dst_port) =
+            if (protocol == 6 || protocol == 17) && first_fragment && data.len() >= ihl + 4 {
+                let sp = u16::from_be_bytes([data[ihl], data[ihl + 1]]);
+                let dp = u16::from_be_bytes([data[ihl + 2], data[ihl + 3]]);
+                (sp, dp)
+            } else {
+                (0, 0)
+            };
         Ok(PacketMeta {
             src_ip: IpAddr::V4(src_ip),
             dst_ip: IpAddr::V4(dst_ip),
@@ -128,7 +773,13 @@ pub fn parse(data: &[u8]) -> Result<PacketMeta, &'static str> {
             dst_port,
             protocol,
             ttl,
-            raw: data.to_vec(),
+            dscp: data[1] >> 2,
+            ecn: data[1] & 0x3,
+            transport: transport_detail(data),
+            annotation: None,
+            mpls: Vec::new(),
+            vlan: None,
+            raw: Bytes::copy_from_slice(data),
         })
     } else if version == 6 {
         // IPv6 parsing with optional Hop-by-Hop extension header handling
@@ -138,6 +789,7 @@ pub fn parse(data: &[u8]) -> Result<PacketMeta, &'static str> {
         // Next Header field at offset 6, Hop Limit at offset 7
         let mut next_header = data[6];
         let hop_limit = data[7];
+        let traffic_class = traffic_class(data).unwrap_or(0);
         let src_ip = Ipv6Addr::new(
             u16::from_be_bytes([data[8], data[9]]),
             u16::from_be_bytes([data[10], data[11]]),
@@ -190,7 +842,13 @@ pub fn parse(data: &[u8]) -> Result<PacketMeta, &'static str> {
             dst_port,
             protocol: next_header,
             ttl: hop_limit,
-            raw: data.to_vec(),
+            dscp: traffic_class >> 2,
+            ecn: traffic_class & 0x3,
+            transport: transport_detail(data),
+            annotation: None,
+            mpls: Vec::new(),
+            vlan: None,
+            raw: Bytes::copy_from_slice(data),
         })
     } else {
         Err("unsupported IP version")
@@ -0,0 +1,82 @@
+// src/packet/mpls.rs
+
+//! MPLS label stacks (RFC 3032).
+//!
+//! A labeled packet carries a stack of four‑byte entries (20‑bit label, 3‑bit traffic class,
+//! bottom‑of‑stack bit, TTL) in front of its IP packet: in frames with the MPLS EtherType on a
+//! TAP edge, or inside a GRE tunnel. [`parse_labeled`](super::parse_labeled) keeps the stack in
+//! `PacketMeta::mpls`, outermost label first, and leaves the IP packet alone in `raw`, so
+//! everything reading IP headers works unchanged; [`PacketMeta::labeled`](super::PacketMeta::labeled)
+//! puts the stack back in front.
+
+use serde::Serialize;
+
+pub const ETHERTYPE_MPLS: u16 = 0x8847;
+pub const ETHERTYPE_MPLS_MULTICAST: u16 = 0x8848;
+/// Largest label value (20 bits).
+pub const MAX_LABEL: u32 = 0xF_FFFF;
+/// Deeper stacks are rejected as malformed.
+const MAX_DEPTH: usize = 16;
+
+/// One label stack entry; the bottom‑of‑stack bit follows from its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MplsLabel {
+    pub label: u32,
+    /// Traffic class (the former EXP bits).
+    pub tc: u8,
+    pub ttl: u8,
+}
+
+impl MplsLabel {
+    pub fn new(label: u32, ttl: u8) -> Self {
+        Self { label, tc: 0, ttl }
+    }
+
+    fn encode(&self, bottom: bool) -> [u8; 4] {
+        let entry = (self.label & MAX_LABEL) << 12
+            | ((self.tc & 0x07) as u32) << 9
+            | (bottom as u32) << 8
+            | self.ttl as u32;
+        entry.to_be_bytes()
+    }
+
+    /// Entry from four bytes, with its bottom‑of‑stack bit.
+    fn decode(bytes: [u8; 4]) -> (Self, bool) {
+        let entry = u32::from_be_bytes(bytes);
+        let label = Self {
+            label: entry >> 12,
+            tc: ((entry >> 9) & 0x07) as u8,
+            ttl: entry as u8,
+        };
+        (label, entry & 0x100 != 0)
+    }
+}
+
+/// The label stack at the start of `data` and its length in bytes.
+pub fn parse_stack(data: &[u8]) -> Result<(Vec<MplsLabel>, usize), &'static str> {
+    let mut stack = Vec::new();
+    loop {
+        let at = 4 * stack.len();
+        let bytes: [u8; 4] = data
+            .get(at..at + 4)
+            .and_then(|entry| entry.try_into().ok())
+            .ok_or("packet too short for MPLS label stack")?;
+        let (label, bottom) = MplsLabel::decode(bytes);
+        stack.push(label);
+        if bottom {
+            return Ok((stack, at + 4));
+        }
+        if stack.len() == MAX_DEPTH {
+            return Err("MPLS label stack too deep");
+        }
+    }
+}
+
+/// Wire form of `stack`, outermost label first.
+pub fn encode_stack(stack: &[MplsLabel]) -> Vec<u8> {
+    stack
+        .iter()
+        .enumerate()
+        .flat_map(|(i, label)| label.encode(i + 1 == stack.len()))
+        .collect()
+}
@@ -0,0 +1,148 @@
+// src/packet/options.rs
+
+//! IPv4 Record Route and Timestamp options (RFC 791).
+//!
+//! Every router that forwards an IPv4 packet carrying one of these options writes itself into
+//! it: Record Route gets the router's address, Timestamp the milliseconds since midnight UTC,
+//! preceded by the address when the sender asked for both, or only in the slots naming the
+//! router when the addresses were prespecified. Once an option is full the router leaves it
+//! alone, except that Timestamp counts the routers that found no room in its overflow field.
+
+use std::net::Ipv4Addr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const IPOPT_END: u8 = 0;
+pub const IPOPT_NOP: u8 = 1;
+pub const IPOPT_RR: u8 = 7;
+pub const IPOPT_TS: u8 = 68;
+
+/// Timestamp flags: timestamps only, address and timestamp, prespecified addresses.
+const TS_ONLY: u8 = 0;
+const TS_ADDRESS: u8 = 1;
+const TS_PRESPECIFIED: u8 = 3;
+
+/// Milliseconds since midnight UTC, the standard Timestamp value.
+pub fn timestamp_now() -> u32 {
+    let ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    (ms % 86_400_000) as u32
+}
+
+/// Offset and length of every option of the IPv4 header `raw`, up to End of Options.
+fn options(raw: &[u8]) -> Vec<(usize, usize)> {
+    let ihl = (raw[0] & 0x0F) as usize * 4;
+    let end = ihl.min(raw.len());
+    let mut found = Vec::new();
+    let mut i = 20;
+    while i < end {
+        match raw[i] {
+            IPOPT_END => break,
+            IPOPT_NOP => i += 1,
+            _ => {
+                let len = *raw.get(i + 1).unwrap_or(&0) as usize;
+                if len < 2 || i + len > end {
+                    break;
+                }
+                found.push((i, len));
+                i += len;
+            }
+        }
+    }
+    found
+}
+
+/// Record Route `option`: write `address` into the next free slot.
+fn record_route(option: &mut [u8], address: Ipv4Addr) -> bool {
+    let pointer = option.get(2).copied().unwrap_or(0) as usize;
+    if pointer < 4 || pointer + 3 > option.len() {
+        return false;
+    }
+    option[pointer - 1..pointer + 3].copy_from_slice(&address.octets());
+    option[2] += 4;
+    true
+}
+
+/// Timestamp `option`: write `now` (and `address`) into the next free slot, or count the
+/// overflow.
+fn timestamp(option: &mut [u8], address: Ipv4Addr, now: u32) -> bool {
+    if option.len() < 4 {
+        return false;
+    }
+    let pointer = option[2] as usize;
+    let flag = option[3] & 0x0F;
+    let slot = match flag {
+        TS_ONLY => 4,
+        TS_ADDRESS | TS_PRESPECIFIED => 8,
+        _ => return false,
+    };
+    if pointer < 5 || pointer + slot - 1 > option.len() {
+        // No room: count this router in the overflow field, which saturates at 15.
+        let overflow = option[3] >> 4;
+        if overflow == 15 {
+            return false;
+        }
+        option[3] = ((overflow + 1) << 4) | flag;
+        return true;
+    }
+    let at = pointer - 1;
+    match flag {
+        TS_ONLY => option[at..at + 4].copy_from_slice(&now.to_be_bytes()),
+        TS_ADDRESS => {
+            option[at..at + 4].copy_from_slice(&address.octets());
+            option[at + 4..at + 8].copy_from_slice(&now.to_be_bytes());
+        }
+        _ => {
+            // Prespecified: only the router named by the next slot fills it in.
+            if option[at..at + 4] != address.octets() {
+                return false;
+            }
+            option[at + 4..at + 8].copy_from_slice(&now.to_be_bytes());
+        }
+    }
+    option[2] += slot as u8;
+    true
+}
+
+/// Whether the IPv4 header of `raw` has options at all.
+pub fn has_options(raw: &[u8]) -> bool {
+    raw.len() > 20 && raw[0] >> 4 == 4 && raw[0] & 0x0F > 5
+}
+
+/// Let the router with `address` update the Record Route and Timestamp options of the IPv4
+/// packet `raw` at time `now`. Returns whether anything was written; the header checksum is
+/// left to the caller.
+pub fn record_hop(raw: &mut [u8], address: Ipv4Addr, now: u32) -> bool {
+    if !has_options(raw) {
+        return false;
+    }
+    let mut changed = false;
+    for (offset, len) in options(raw) {
+        let option = &mut raw[offset..offset + len];
+        changed |= match option[0] {
+            IPOPT_RR => record_route(option, address),
+            IPOPT_TS => timestamp(option, address, now),
+            _ => false,
+        };
+    }
+    changed
+}
+
+/// Addresses recorded so far by the Record Route option of `raw`.
+pub fn recorded_route(raw: &[u8]) -> Option<Vec<Ipv4Addr>> {
+    if !has_options(raw) {
+        return None;
+    }
+    let (offset, len) = options(raw)
+        .into_iter()
+        .find(|(offset, _)| raw[*offset] == IPOPT_RR)?;
+    let option = &raw[offset..offset + len];
+    let filled = (option[2] as usize).clamp(4, len + 1) - 1;
+    Some(
+        option[3..filled]
+            .chunks_exact(4)
+            .map(|a| Ipv4Addr::new(a[0], a[1], a[2], a[3]))
+            .collect(),
+    )
+}
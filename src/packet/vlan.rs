@@ -0,0 +1,102 @@
+// src/packet/vlan.rs
+
+//! 802.1Q VLAN tags.
+//!
+//! A tagged Ethernet frame carries a four‑byte tag (TPID 0x8100, then the PCP, DEI and 12‑bit
+//! VLAN ID) between the source MAC and the EtherType. TAP edges take the tag off with [`untag`]
+//! and keep it in `PacketMeta::vlan`; links may restrict which VLANs they carry and rewrite
+//! VLAN IDs ([`VlanPolicy`]), and the frame leaving the fabric is tagged again with [`tag`].
+//! Untagged packets pass every link unchanged.
+
+use serde::{Deserialize, Serialize};
+
+pub const ETHERTYPE_VLAN: u16 = 0x8100;
+/// Largest usable VLAN ID; 0 means "priority tag only" and 4095 is reserved.
+pub const MAX_VID: u16 = 4094;
+
+/// Tag control information of one 802.1Q tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct VlanTag {
+    pub vid: u16,
+    /// Priority code point.
+    pub pcp: u8,
+    /// Drop eligible indicator.
+    pub dei: bool,
+}
+
+impl VlanTag {
+    pub fn new(vid: u16) -> Self {
+        Self {
+            vid,
+            pcp: 0,
+            dei: false,
+        }
+    }
+
+    pub fn from_tci(tci: u16) -> Self {
+        Self {
+            vid: tci & 0x0FFF,
+            pcp: (tci >> 13) as u8,
+            dei: tci & 0x1000 != 0,
+        }
+    }
+
+    pub fn tci(&self) -> u16 {
+        ((self.pcp & 0x07) as u16) << 13 | (self.dei as u16) << 12 | (self.vid & 0x0FFF)
+    }
+}
+
+/// Take the 802.1Q tag off the Ethernet frame `frame` in place. Returns the tag and the offset
+/// at which the untagged frame now starts (4 for a tagged frame, 0 otherwise).
+pub fn untag(frame: &mut [u8]) -> (Option<VlanTag>, usize) {
+    if frame.len() < 18 || u16::from_be_bytes([frame[12], frame[13]]) != ETHERTYPE_VLAN {
+        return (None, 0);
+    }
+    let tag = VlanTag::from_tci(u16::from_be_bytes([frame[14], frame[15]]));
+    frame.copy_within(0..12, 4);
+    (Some(tag), 4)
+}
+
+/// The Ethernet frame `frame` with `tag` inserted after its MAC addresses.
+pub fn tag(frame: &[u8], tag: VlanTag) -> Vec<u8> {
+    let split = frame.len().min(12);
+    let mut out = Vec::with_capacity(frame.len() + 4);
+    out.extend_from_slice(&frame[..split]);
+    out.extend_from_slice(&ETHERTYPE_VLAN.to_be_bytes());
+    out.extend_from_slice(&tag.tci().to_be_bytes());
+    out.extend_from_slice(&frame[split..]);
+    out
+}
+
+/// One VLAN ID translation on a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VlanRewrite {
+    pub from: u16,
+    pub to: u16,
+}
+
+/// VLANs a link carries and the VLAN IDs it translates, in both directions.
+#[derive(Debug, Clone, Copy)]
+pub struct VlanPolicy<'a> {
+    /// Tagged packets outside this list are dropped; empty carries every VLAN.
+    pub allowed: &'a [u16],
+    pub rewrite: &'a [VlanRewrite],
+}
+
+impl VlanPolicy<'_> {
+    /// Apply the policy to the tag of a packet entering the link: `false` if the packet must be
+    /// dropped, otherwise the tag is rewritten in place. The allowed list is checked against
+    /// the VLAN ID the packet arrives with.
+    pub fn admit(&self, vlan: &mut Option<VlanTag>) -> bool {
+        let Some(tag) = vlan.as_mut() else {
+            return true;
+        };
+        if !self.allowed.is_empty() && !self.allowed.contains(&tag.vid) {
+            return false;
+        }
+        if let Some(rewrite) = self.rewrite.iter().find(|r| r.from == tag.vid) {
+            tag.vid = rewrite.to;
+        }
+        true
+    }
+}
@@ -0,0 +1,81 @@
+// src/pbr/mod.rs
+
+//! Policy‑based routing.
+//!
+//! Routers may carry an ordered list of PBR rules that are consulted before the routing table.
+//! Each rule has a capture‑filter `match` expression and one action: forward to a fixed
+//! neighbouring router (`next_hop`) or steer the packet towards the other edge
+//! (`destination = "tun_a" | "tun_b"`). The first matching rule wins.
+
+use crate::capture::CaptureFilter;
+use crate::packet::PacketMeta;
+use crate::routing::Destination;
+use crate::topology::RouterId;
+use serde::Deserialize;
+
+/// One rule as written in a router table:
+/// `{ match = "udp and dst port 5060", next_hop = "Rx1y0" }`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PbrRuleConfig {
+    #[serde(rename = "match", default)]
+    pub filter: String,
+    #[serde(default)]
+    pub next_hop: Option<String>,
+    #[serde(default)]
+    pub destination: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PbrAction {
+    /// Forward over the link to this neighbour instead of the routing table's choice.
+    NextHop(RouterId),
+    /// Route towards this edge from here on.
+    Destination(Destination),
+}
+
+#[derive(Debug, Clone)]
+pub struct PbrRule {
+    pub filter: CaptureFilter,
+    pub action: PbrAction,
+    /// Packets this rule steered.
+    pub hits: u64,
+}
+
+/// Parse an edge name as used in configuration files.
+pub fn parse_destination(name: &str) -> Result<Destination, String> {
+    match name {
+        "tun_a" => Ok(Destination::TunA),
+        "tun_b" => Ok(Destination::TunB),
+        other => Err(format!(
+            "Unknown destination '{}', expected 'tun_a' or 'tun_b'",
+            other
+        )),
+    }
+}
+
+impl PbrRule {
+    pub fn from_config(cfg: &PbrRuleConfig) -> Result<Self, String> {
+        let action = match (&cfg.next_hop, &cfg.destination) {
+            (Some(hop), None) => PbrAction::NextHop(RouterId(hop.clone())),
+            (None, Some(dest)) => PbrAction::Destination(parse_destination(dest)?),
+            _ => {
+                return Err(format!(
+                    "PBR rule '{}' needs exactly one of 'next_hop' or 'destination'",
+                    cfg.filter
+                ))
+            }
+        };
+        Ok(Self {
+            filter: CaptureFilter::parse(&cfg.filter)?,
+            action,
+            hits: 0,
+        })
+    }
+}
+
+/// Action of the first rule in `rules` matching `packet`, counting the hit.
+pub fn lookup(rules: &mut [PbrRule], packet: &PacketMeta) -> Option<PbrAction> {
+    let rule = rules.iter_mut().find(|r| r.filter.matches(packet))?;
+    rule.hits += 1;
+    Some(rule.action.clone())
+}
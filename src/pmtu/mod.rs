@@ -0,0 +1,324 @@
+// src/pmtu/mod.rs
+
+//! Path MTU discovery between the two edges.
+//!
+//! The ingress router of one edge sends DF‑set UDP probes of varying size towards an address
+//! behind the other edge and binary‑searches the largest size that is delivered.
+//! Oversized probes come back as ICMP Fragmentation Needed / Packet Too Big; the final
+//! too‑big report names the router, and from its advertised MTU the bottleneck link.
+
+use crate::config::SimulatorConfig;
+use crate::ddos::{build_flood_packet, FloodKind};
+use crate::packet::{update_ipv4_checksum, PacketMeta};
+use crate::processor::{process_packet, process_packet_multi};
+use crate::routing::{Destination, MultiPathTable, RoutingTable};
+use crate::topology::{Fabric, LinkId, RouterId};
+use crate::traceroute::{destination_for, router_by_address, total_lost};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// UDP port the probes are sent to.
+pub const PROBE_PORT: u16 = 33435;
+
+/// Search bounds; `min` defaults to the protocol minimum (68 for IPv4, 1280 for IPv6).
+#[derive(Debug, Clone, Default)]
+pub struct PmtuOptions {
+    pub min: Option<u32>,
+    pub max: u32,
+}
+
+/// What happened to one probe.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeOutcome {
+    Delivered,
+    /// An ICMP error advertising `mtu`, sent by `router` after `link` refused the probe.
+    TooBig {
+        mtu: u32,
+        router: Option<RouterId>,
+        link: Option<LinkId>,
+    },
+    /// Lost on a link, or answered with some other error.
+    Lost,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PmtuReport {
+    pub path_mtu: u32,
+    /// Router that reported the smallest MTU on the path, if any probe was too big.
+    pub reported_by: Option<RouterId>,
+    /// Link of `reported_by` that refused the probe, i.e. the one limiting the path.
+    pub bottleneck: Option<LinkId>,
+    /// Every probe in order: size and whether it was delivered.
+    pub probes: Vec<(u32, bool)>,
+}
+
+impl PmtuReport {
+    pub fn render(&self, dst: IpAddr) -> String {
+        let mut out = String::new();
+        for (size, fits) in &self.probes {
+            out.push_str(&format!(
+                "probe {:>5} bytes: {}\n",
+                size,
+                if *fits { "delivered" } else { "too big" }
+            ));
+        }
+        out.push_str(&format!("path MTU to {}: {}\n", dst, self.path_mtu));
+        if let (Some(router), Some(link)) = (&self.reported_by, &self.bottleneck) {
+            out.push_str(&format!(
+                "bottleneck: link {}_{} (reported by {})\n",
+                link.a.0, link.b.0, router.0
+            ));
+        }
+        out
+    }
+}
+
+/// DF‑set UDP probe of exactly `size` bytes.
+pub fn build_probe(src: IpAddr, dst: IpAddr, size: u32) -> PacketMeta {
+    let mut probe = build_flood_packet(
+        FloodKind::Udp,
+        src,
+        dst,
+        PROBE_PORT,
+        PROBE_PORT,
+        size as usize,
+    );
+    if src.is_ipv4() && probe.raw.len() >= 20 {
+        let mut raw = probe.raw_mut();
+        raw[6] |= 0x40;
+        update_ipv4_checksum(&mut raw);
+    }
+    probe
+}
+
+/// MTU advertised by an ICMP Fragmentation Needed (IPv4) or Packet Too Big (IPv6) message.
+fn advertised_mtu(packet: &PacketMeta) -> Option<u32> {
+    let raw = &packet.raw;
+    match packet.src_ip {
+        IpAddr::V4(_) if packet.protocol == 1 => {
+            let ihl = (raw.first()? & 0x0F) as usize * 4;
+            let icmp = raw.get(ihl..ihl + 8)?;
+            (icmp[0] == 3 && icmp[1] == 4).then(|| u16::from_be_bytes([icmp[6], icmp[7]]) as u32)
+        }
+        IpAddr::V6(_) if packet.protocol == 58 => {
+            let icmp = raw.get(40..48)?;
+            (icmp[0] == 2).then(|| u32::from_be_bytes([icmp[4], icmp[5], icmp[6], icmp[7]]))
+        }
+        _ => None,
+    }
+}
+
+/// MTU refusals so far, per link.
+fn too_big_counts(fabric: &Fabric) -> HashMap<LinkId, u64> {
+    fabric
+        .graph
+        .edge_weights()
+        .map(|l| (l.id.clone(), l.too_big()))
+        .collect()
+}
+
+/// Everything needed to send probes along one path.
+struct Prober<'a> {
+    routing_tables: &'a HashMap<RouterId, RoutingTable>,
+    multipath_tables: &'a HashMap<RouterId, MultiPathTable>,
+    enable_multipath: bool,
+    origin: &'a RouterId,
+    egress: &'a RouterId,
+    src: IpAddr,
+    dst: IpAddr,
+    destination: Destination,
+}
+
+impl Prober<'_> {
+    async fn probe(&self, fabric: &mut Fabric, size: u32) -> ProbeOutcome {
+        let packet = build_probe(self.src, self.dst, size);
+        let delivered_before = fabric
+            .get_router(self.egress)
+            .map(|r| r.stats.packets_delivered)
+            .unwrap_or(0);
+        let lost_before = total_lost(fabric);
+        let too_big_before = too_big_counts(fabric);
+        let origin = self.origin.clone();
+        let result = if self.enable_multipath {
+            process_packet_multi(
+                fabric,
+                self.multipath_tables,
+                origin,
+                packet,
+                self.destination,
+            )
+            .await
+        } else {
+            process_packet(
+                fabric,
+                self.routing_tables,
+                origin,
+                packet,
+                self.destination,
+            )
+            .await
+        };
+        let delivered = fabric
+            .get_router(self.egress)
+            .map(|r| r.stats.packets_delivered)
+            .unwrap_or(0)
+            > delivered_before;
+        if total_lost(fabric) > lost_before {
+            return ProbeOutcome::Lost;
+        }
+        match advertised_mtu(&result) {
+            // The origin's own egress link may be the narrow one, so its reports count too.
+            Some(mtu) => ProbeOutcome::TooBig {
+                mtu,
+                router: router_by_address(fabric, result.src_ip),
+                link: fabric
+                    .graph
+                    .edge_weights()
+                    .find(|l| l.too_big() > too_big_before.get(&l.id).copied().unwrap_or(0))
+                    .map(|l| l.id.clone()),
+            },
+            _ if delivered => ProbeOutcome::Delivered,
+            _ => ProbeOutcome::Lost,
+        }
+    }
+
+    /// Link loss makes a probe inconclusive; retry a few times before giving up.
+    async fn attempt(&self, fabric: &mut Fabric, size: u32) -> Result<ProbeOutcome, String> {
+        for _ in 0..3 {
+            match self.probe(fabric, size).await {
+                ProbeOutcome::Lost => continue,
+                outcome => return Ok(outcome),
+            }
+        }
+        Err(format!("Probe of {} bytes lost three times", size))
+    }
+}
+
+/// Path MTU search from `origin` towards `dst` (behind `destination`, delivered at `egress`).
+#[allow(clippy::too_many_arguments)]
+pub async fn discover(
+    fabric: &mut Fabric,
+    routing_tables: &HashMap<RouterId, RoutingTable>,
+    multipath_tables: &HashMap<RouterId, MultiPathTable>,
+    enable_multipath: bool,
+    origin: &RouterId,
+    egress: &RouterId,
+    dst: IpAddr,
+    destination: Destination,
+    opts: &PmtuOptions,
+) -> Result<PmtuReport, String> {
+    let router = fabric
+        .get_router(origin)
+        .ok_or_else(|| format!("Unknown router {}", origin.0))?;
+    let src = match dst {
+        IpAddr::V4(_) => IpAddr::V4(router.ipv4_addr),
+        IpAddr::V6(_) => IpAddr::V6(router.ipv6_addr),
+    };
+    let floor = if dst.is_ipv4() { 68 } else { 1280 };
+    let min = opts.min.unwrap_or(floor).max(floor);
+    if opts.max < min {
+        return Err(format!("Maximum probe size {} is below {}", opts.max, min));
+    }
+    if opts.max > u16::MAX as u32 {
+        return Err(format!(
+            "Maximum probe size {} exceeds the largest IP packet ({})",
+            opts.max,
+            u16::MAX
+        ));
+    }
+    let mut report = PmtuReport {
+        path_mtu: 0,
+        reported_by: None,
+        bottleneck: None,
+        probes: Vec::new(),
+    };
+    let prober = Prober {
+        routing_tables,
+        multipath_tables,
+        enable_multipath,
+        origin,
+        egress,
+        src,
+        dst,
+        destination,
+    };
+    let (mut lo, mut hi) = (min, opts.max);
+    let mut last_too_big = None;
+    match prober.attempt(fabric, hi).await? {
+        ProbeOutcome::Delivered => {
+            report.probes.push((hi, true));
+            lo = hi;
+        }
+        outcome => {
+            report.probes.push((hi, false));
+            last_too_big = Some((hi, outcome));
+            hi -= 1;
+        }
+    }
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        match prober.attempt(fabric, mid).await? {
+            ProbeOutcome::Delivered => {
+                report.probes.push((mid, true));
+                lo = mid;
+            }
+            outcome => {
+                report.probes.push((mid, false));
+                last_too_big = Some((mid, outcome));
+                hi = mid - 1;
+            }
+        }
+    }
+    if !report.probes.iter().any(|(_, fits)| *fits) {
+        // Nothing was delivered yet: the lower bound itself still has to be confirmed.
+        let fits = prober.attempt(fabric, lo).await? == ProbeOutcome::Delivered;
+        report.probes.push((lo, fits));
+        if !fits {
+            return Err(format!("Even {}-byte probes do not reach {}", lo, dst));
+        }
+    }
+    report.path_mtu = lo;
+    // The smallest rejected size is one above the path MTU; its report names the bottleneck.
+    if let Some((size, ProbeOutcome::TooBig { mtu, router, link })) = last_too_big {
+        if size == lo + 1 || mtu == lo {
+            report.bottleneck = link;
+            report.reported_by = router;
+        }
+    }
+    Ok(report)
+}
+
+/// Build the fabric described by `cfg` and discover the path MTU towards `dst`, probing from
+/// the ingress router of the other edge.
+pub async fn run(
+    cfg: &SimulatorConfig,
+    dst: IpAddr,
+    opts: &PmtuOptions,
+) -> Result<PmtuReport, String> {
+    let mut fabric = crate::build_fabric(cfg);
+    let routing_tables = crate::compute_routing_tables(cfg);
+    let multipath_tables = crate::compute_multipath_tables(cfg);
+    let destination = destination_for(cfg, dst);
+    let (origin, egress) = match destination {
+        Destination::TunA => (
+            &cfg.tun_ingress.tun_b_ingress,
+            &cfg.tun_ingress.tun_a_ingress,
+        ),
+        Destination::TunB => (
+            &cfg.tun_ingress.tun_a_ingress,
+            &cfg.tun_ingress.tun_b_ingress,
+        ),
+    };
+    discover(
+        &mut fabric,
+        &routing_tables,
+        &multipath_tables,
+        cfg.enable_multipath,
+        &RouterId(origin.clone()),
+        &RouterId(egress.clone()),
+        dst,
+        destination,
+        opts,
+    )
+    .await
+}
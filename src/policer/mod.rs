@@ -0,0 +1,262 @@
+// src/policer/mod.rs
+
+//! Per‑class ingress policers.
+//!
+//! A router's `policers` meter the traffic entering the fabric at that router, one policer per
+//! traffic class. Each is a two‑rate three‑color marker (RFC 2698, color‑blind): a committed
+//! bucket filled at `cir_mbps` up to `cbs_bytes` and a peak bucket filled at `pir_mbps` up to
+//! `pbs_bytes`. A packet the peak bucket cannot cover violates; one only the peak bucket covers
+//! exceeds; anything else conforms. Without `pir_mbps` the policer is single‑rate: packets
+//! beyond the committed bucket exceed and none violate. Each color has its action — transmit,
+//! remark the DSCP, or drop — so the remarked traffic can be followed through the link queues
+//! downstream.
+
+use crate::packet::PacketMeta;
+use serde::Deserialize;
+use tokio::time::Instant;
+
+/// What to do with a packet of one color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicerAction {
+    Transmit,
+    /// Transmit with this DSCP: `{ remark = 10 }`.
+    Remark(u8),
+    Drop,
+}
+
+/// One class as written in a router table:
+/// `{ dscp = [0], cir_mbps = 10, cbs_bytes = 15000, exceed = { remark = 8 }, violate = "drop" }`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicerConfig {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// DSCP values of the class; an empty list matches every packet.
+    #[serde(default)]
+    pub dscp: Vec<u8>,
+    pub cir_mbps: f64,
+    #[serde(default = "default_burst_bytes")]
+    pub cbs_bytes: u32,
+    #[serde(default)]
+    pub pir_mbps: Option<f64>,
+    #[serde(default = "default_burst_bytes")]
+    pub pbs_bytes: u32,
+    #[serde(default = "default_conform")]
+    pub conform: PolicerAction,
+    #[serde(default = "default_exceed")]
+    pub exceed: PolicerAction,
+    #[serde(default = "default_exceed")]
+    pub violate: PolicerAction,
+}
+
+fn default_burst_bytes() -> u32 {
+    // Ten full‑size packets.
+    15000
+}
+fn default_conform() -> PolicerAction {
+    PolicerAction::Transmit
+}
+fn default_exceed() -> PolicerAction {
+    PolicerAction::Drop
+}
+
+impl PolicerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.cir_mbps.is_finite() || self.cir_mbps <= 0.0 {
+            return Err(format!(
+                "cir_mbps must be a positive number, got {}",
+                self.cir_mbps
+            ));
+        }
+        if let Some(pir) = self.pir_mbps {
+            if !pir.is_finite() || pir < self.cir_mbps {
+                return Err(format!(
+                    "pir_mbps must be at least cir_mbps ({}), got {}",
+                    self.cir_mbps, pir
+                ));
+            }
+        }
+        if self.cbs_bytes == 0 || self.pbs_bytes == 0 {
+            return Err("burst sizes must be at least 1 byte".to_string());
+        }
+        let remarks = [self.conform, self.exceed, self.violate];
+        if let Some(dscp) = self
+            .dscp
+            .iter()
+            .copied()
+            .chain(remarks.iter().filter_map(|a| match a {
+                PolicerAction::Remark(dscp) => Some(*dscp),
+                _ => None,
+            }))
+            .find(|&d| d > 63)
+        {
+            return Err(format!("invalid DSCP value {}", dscp));
+        }
+        Ok(())
+    }
+}
+
+/// Color a policer gave a packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Conform,
+    Exceed,
+    Violate,
+}
+
+/// Packets and bytes of one color.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColorCount {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+/// Token bucket filled at `rate` bytes per second up to `size` bytes.
+#[derive(Debug, Clone)]
+struct Bucket {
+    rate: f64,
+    size: f64,
+    tokens: f64,
+}
+
+impl Bucket {
+    fn new(mbps: f64, size: u32) -> Self {
+        Self {
+            rate: mbps * 1_000_000.0 / 8.0,
+            size: size as f64,
+            tokens: size as f64,
+        }
+    }
+
+    fn fill(&mut self, elapsed: f64) {
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.size);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Policer {
+    pub name: String,
+    pub dscp: Vec<u8>,
+    pub conform: PolicerAction,
+    pub exceed: PolicerAction,
+    pub violate: PolicerAction,
+    committed: Bucket,
+    /// `None` for a single‑rate policer.
+    peak: Option<Bucket>,
+    last: Option<Instant>,
+    pub conformed: ColorCount,
+    pub exceeded: ColorCount,
+    pub violated: ColorCount,
+}
+
+impl Policer {
+    pub fn from_config(cfg: &PolicerConfig) -> Result<Self, String> {
+        cfg.validate()?;
+        let name = cfg.name.clone().unwrap_or_else(|| {
+            if cfg.dscp.is_empty() {
+                "default".to_string()
+            } else {
+                let dscp: Vec<String> = cfg.dscp.iter().map(u8::to_string).collect();
+                format!("dscp{}", dscp.join(","))
+            }
+        });
+        Ok(Self {
+            name,
+            dscp: cfg.dscp.clone(),
+            conform: cfg.conform,
+            exceed: cfg.exceed,
+            violate: cfg.violate,
+            committed: Bucket::new(cfg.cir_mbps, cfg.cbs_bytes),
+            peak: cfg.pir_mbps.map(|pir| Bucket::new(pir, cfg.pbs_bytes)),
+            last: None,
+            conformed: ColorCount::default(),
+            exceeded: ColorCount::default(),
+            violated: ColorCount::default(),
+        })
+    }
+
+    pub fn matches(&self, packet: &PacketMeta) -> bool {
+        self.dscp.is_empty() || self.dscp.contains(&packet.dscp)
+    }
+
+    /// Meter a packet of `len` bytes arriving now and count it under its color.
+    pub fn meter(&mut self, len: usize) -> Color {
+        let now = Instant::now();
+        let elapsed = self
+            .last
+            .map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        self.last = Some(now);
+        self.committed.fill(elapsed);
+        if let Some(peak) = self.peak.as_mut() {
+            peak.fill(elapsed);
+        }
+        let len = len as f64;
+        let color = if self.peak.as_ref().is_some_and(|peak| peak.tokens < len) {
+            Color::Violate
+        } else if self.committed.tokens < len {
+            Color::Exceed
+        } else {
+            self.committed.tokens -= len;
+            Color::Conform
+        };
+        if color != Color::Violate {
+            if let Some(peak) = self.peak.as_mut() {
+                peak.tokens -= len;
+            }
+        }
+        let count = match color {
+            Color::Conform => &mut self.conformed,
+            Color::Exceed => &mut self.exceeded,
+            Color::Violate => &mut self.violated,
+        };
+        count.packets += 1;
+        count.bytes += len as u64;
+        color
+    }
+
+    /// Action configured for `color`.
+    pub fn action(&self, color: Color) -> PolicerAction {
+        match color {
+            Color::Conform => self.conform,
+            Color::Exceed => self.exceed,
+            Color::Violate => self.violate,
+        }
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.conformed = ColorCount::default();
+        self.exceeded = ColorCount::default();
+        self.violated = ColorCount::default();
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: conform={} ({} B), exceed={} ({} B), violate={} ({} B)",
+            self.name,
+            self.conformed.packets,
+            self.conformed.bytes,
+            self.exceeded.packets,
+            self.exceeded.bytes,
+            self.violated.packets,
+            self.violated.bytes
+        )
+    }
+}
+
+/// Police `packet` with the first policer of `policers` whose class it belongs to, applying
+/// the action for its color. Returns `false` if the packet is to be dropped; packets of no
+/// class pass unmetered.
+pub fn police(policers: &mut [Policer], packet: &mut PacketMeta) -> bool {
+    let Some(policer) = policers.iter_mut().find(|p| p.matches(packet)) else {
+        return true;
+    };
+    let color = policer.meter(packet.raw.len());
+    match policer.action(color) {
+        PolicerAction::Transmit => true,
+        PolicerAction::Remark(dscp) => {
+            packet.set_dscp(dscp);
+            true
+        }
+        PolicerAction::Drop => false,
+    }
+}
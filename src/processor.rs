@@ -1,16 +1,22 @@
 // src/processor.rs
 
+use crate::packet::vlan::VlanTag;
 use crate::packet::{self, PacketMeta};
+use crate::pbr::PbrAction;
 use crate::routing::multipath::MultiPathTable;
 use crate::routing::{Destination, RoutingTable};
 use crate::topology::{Fabric, Link, RouterId};
 
+use crate::drops::DropReason;
+use crate::explicit_path;
 use crate::forwarding::select_egress_link;
 use crate::icmp;
-use crate::simulation::{simulate_link, SimulationError};
+use crate::simulation::{delays_paced, simulate_link_timed, SimulationError};
+use crate::sojourn::{Annotation, Sojourn, TraceRecord};
 use std::collections::HashMap;
 use std::net::{Ipv4Addr, Ipv6Addr};
-use tracing::{debug, error};
+use tokio::time::{sleep, Instant};
+use tracing::{debug, error, trace};
 
 /// Get the IPv4 and IPv6 addresses for a router from the fabric.
 fn get_router_addresses(fabric: &Fabric, router_id: &RouterId) -> (Ipv4Addr, Ipv6Addr) {
@@ -28,6 +34,149 @@ fn is_ipv6(packet: &PacketMeta) -> bool {
     matches!(packet.src_ip, std::net::IpAddr::V6(_))
 }
 
+/// SRv6 endpoint processing at `router` and the next hop towards the packet's active segment.
+fn segment_next_hop(
+    fabric: &mut Fabric,
+    router: &RouterId,
+    packet: &mut PacketMeta,
+) -> Option<RouterId> {
+    let (_, address) = get_router_addresses(fabric, router);
+    fabric.srv6.as_mut()?.steer(router, address, packet)
+}
+
+/// Remember the packet's sojourn for egress pacing and write its record, if a packet trace is
+/// open.
+fn record_outcome(fabric: &mut Fabric, record: TraceRecord) {
+    fabric.last_sojourn = Some(record.sojourn);
+    // The start‑up demonstration packet has no bytes to describe.
+    if record.packet.raw.is_empty() {
+        return;
+    }
+    if let Some(trace) = fabric.packet_trace.as_mut() {
+        trace.record(&record, &fabric.clocks);
+    }
+}
+
+/// The first fragment of `packet` for a link of `mtu` bytes and the remaining raw fragments, or
+/// `None` if the packet may not be fragmented (IPv6, or Don't Fragment set).
+fn fragment(packet: &PacketMeta, mtu: u32) -> Option<(PacketMeta, Vec<Vec<u8>>)> {
+    let mut fragments = packet::fragment_ipv4(&packet.raw, mtu as usize).ok()?;
+    let rest = fragments.split_off(1);
+    let mut first = packet::parse(&fragments[0]).ok()?;
+    first.vlan = packet.vlan;
+    debug!("Fragmented packet into {} for MTU {}", rest.len() + 1, mtu);
+    Some((first, rest))
+}
+
+/// Send a later fragment over the link from `from` to `to` that the first fragment took, with
+/// the hop loop's accounting. Returns the fragment, tagged with the first fragment's `vlan`, if
+/// it made it across.
+async fn cross_link(
+    fabric: &mut Fabric,
+    from: &RouterId,
+    to: &RouterId,
+    raw: &[u8],
+    vlan: Option<VlanTag>,
+) -> Option<PacketMeta> {
+    let link = fabric.get_link(from, to)?;
+    let link_id = link.id.clone();
+    let result = simulate_link_timed(link, from, raw).await;
+    let crossed = result.is_ok();
+    if let Some(router) = fabric.get_router_mut(from) {
+        if crossed {
+            router.account_forwarded(raw.len());
+        } else {
+            router.increment_lost();
+        }
+    }
+    let mut fragment = packet::parse(raw).ok()?;
+    fragment.vlan = vlan;
+    if let Err(e) = result {
+        let reason = match e {
+            SimulationError::QueueFull => DropReason::QueueFull,
+            SimulationError::LinkDown => DropReason::LinkDown,
+            _ => DropReason::LinkLoss,
+        };
+        fabric.record_drop(from, reason, &fragment);
+        return None;
+    }
+    fabric.capture(&link_id, &fragment);
+    Some(fragment)
+}
+
+/// `--paranoid`: the hop from `here` to `next` must bring `packet` closer to `destination` by
+/// the distances stored in the routing tables. Anything else is a mismatch between routing and
+/// forwarding; the returned report names the hop, the packet and the path taken so far.
+fn check_progress(
+    here: &RouterId,
+    next: &RouterId,
+    destination: Destination,
+    (from, to): (u32, u32),
+    packet: &PacketMeta,
+    routers: &[RouterId],
+) -> Result<(), String> {
+    if to < from {
+        return Ok(());
+    }
+    let distance = |d: u32| {
+        if d == u32::MAX {
+            "unreachable".to_string()
+        } else {
+            d.to_string()
+        }
+    };
+    let path: Vec<&str> = routers.iter().map(|r| r.0.as_str()).collect();
+    Err(format!(
+        "paranoid: {} forwarded to {} towards {:?}, but the distance does not decrease ({} -> {})\n  packet: {} -> {} protocol {} ttl {} ({} bytes)\n  path: {}",
+        here.0,
+        next.0,
+        destination,
+        distance(from),
+        distance(to),
+        packet.src_ip,
+        packet.dst_ip,
+        packet.protocol,
+        packet.ttl,
+        packet.raw.len(),
+        path.join(" -> ")
+    ))
+}
+
+/// Drop a packet `--paranoid` caught on a hop that violates the routing tables, keeping the
+/// report for the end of the run.
+fn record_violation(fabric: &mut Fabric, router: &RouterId, packet: &PacketMeta, report: String) {
+    error!("{}", report);
+    fabric.record_drop(router, DropReason::RoutingMismatch, packet);
+    fabric.paranoid_violations.push(report);
+}
+
+/// Most ICMP errors one `process_packet` call may generate. Errors are never sent about errors,
+/// but every fragment of a packet can draw its own, and each is forwarded in turn.
+pub const MAX_ICMP_ERRORS: u32 = 8;
+
+/// Whether `router` may answer the packet with an ICMP error: not if the packet `is_error`
+/// itself (it arrived as one or was generated in this call), nor once `errors` has reached
+/// [`MAX_ICMP_ERRORS`]. A refused error is counted in the router's `icmp_suppressed`.
+fn may_send_error(
+    fabric: &mut Fabric,
+    router: &RouterId,
+    is_error: bool,
+    errors: &mut u32,
+) -> bool {
+    if is_error || *errors >= MAX_ICMP_ERRORS {
+        debug!(
+            "Router {} suppresses an ICMP error ({} generated, packet is an error: {})",
+            router.0, errors, is_error
+        );
+        if let Some(router) = fabric.get_router_mut(router) {
+            router.stats.icmp_suppressed += 1;
+        }
+        return false;
+    }
+    *errors += 1;
+    true
+}
+
 // Returns the opposite destination (used for ICMP replies).
 fn opposite_destination(dest: Destination) -> Destination {
     match dest {
@@ -36,31 +185,218 @@ fn opposite_destination(dest: Destination) -> Destination {
     }
 }
 
-// Process a packet using single‑path routing tables.
+/// `[checksum]`: verify a packet entering the fabric at `router`, counting a bad one in the
+/// router's `bad_checksums`. Returns `false` if the packet is to be dropped.
+fn checksum_ok(fabric: &mut Fabric, router: &RouterId, packet: &PacketMeta) -> bool {
+    let Some(cfg) = &fabric.checksum else {
+        return true;
+    };
+    let Err(e) = packet::verify_checksums(&packet.raw, cfg.transport) else {
+        return true;
+    };
+    let drop = cfg.drop;
+    debug!("Packet entering at {}: {}", router.0, e);
+    if let Some(r) = fabric.get_router_mut(router) {
+        r.stats.bad_checksums += 1;
+    }
+    !drop
+}
+
+/// Checks a packet passes at `router` before it is routed, recording the drop if it fails
+/// one. Packets `entering` the fabric there first have their checksums verified; every packet
+/// then waits for (or is refused) a CPU slot and is matched against the ACL; entering packets
+/// are then metered by the ingress policers, and those of a `tenant` addressed into another
+/// tenant's address space dropped as leaks. The NAT64 router finally translates the packet.
+/// Returns `false` if the packet was dropped.
+async fn admit(
+    fabric: &mut Fabric,
+    router: &RouterId,
+    packet: &mut PacketMeta,
+    entering: bool,
+    tenant: Option<&str>,
+    sojourn: &mut Sojourn,
+) -> bool {
+    // Mirror ports see everything that arrives, including what the checks below drop.
+    fabric.mirror(router, packet);
+    if entering && !checksum_ok(fabric, router, packet) {
+        fabric.record_drop(router, DropReason::BadChecksum, packet);
+        return false;
+    }
+    // Router CPU capacity: wait for a processing slot, or drop when the queue is full.
+    match fabric.get_router_mut(router).map(|r| r.admit_cpu()) {
+        Some(None) => {
+            debug!("Router {} CPU overloaded, dropping packet", router.0);
+            fabric.record_drop(router, DropReason::CpuOverload, packet);
+            return false;
+        }
+        Some(Some(wait)) if !wait.is_zero() => {
+            sojourn.processing_ms += wait.as_secs_f64() * 1000.0;
+            if !delays_paced() {
+                sleep(wait).await
+            }
+        }
+        _ => {}
+    }
+    // Router ACL: the first matching rule decides, denied packets are dropped.
+    if fabric
+        .get_router_mut(router)
+        .is_some_and(|r| !r.permit(packet))
+    {
+        debug!("Packet denied by ACL on router {}", router.0);
+        fabric.record_drop(router, DropReason::AclDenied, packet);
+        return false;
+    }
+    if entering
+        && fabric
+            .get_router_mut(router)
+            .is_some_and(|r| !r.police(packet))
+    {
+        debug!("Packet dropped by policer on router {}", router.0);
+        fabric.record_drop(router, DropReason::Policed, packet);
+        return false;
+    }
+    if let (true, Some(tenant), Some(tenants)) = (entering, tenant, fabric.tenants.as_mut()) {
+        if tenants.leaked(tenant, packet) {
+            debug!(
+                "Packet of tenant {} to {} leaks into another tenant",
+                tenant, packet.dst_ip
+            );
+            fabric.record_drop(router, DropReason::TenantLeak, packet);
+            return false;
+        }
+    }
+    // NAT64 at this router: translate between the IPv6 and IPv4 sides.
+    if let Some(nat) = fabric.nat64.as_mut().filter(|nat| nat.router == *router) {
+        if !nat.translate(packet) {
+            debug!("Packet not translatable by NAT64 on router {}", router.0);
+            fabric.record_drop(router, DropReason::Nat64, packet);
+            return false;
+        }
+    }
+    true
+}
+
+// Process a packet using single‑path routing tables. The returned packet carries the path
+// it took as its `annotation`.
 pub async fn process_packet(
+    fabric: &mut Fabric,
+    tables: &HashMap<RouterId, RoutingTable>,
+    ingress: RouterId,
+    packet: PacketMeta,
+    destination: Destination,
+) -> PacketMeta {
+    process_packet_to_edge(fabric, tables, ingress, packet, destination)
+        .await
+        .0
+}
+
+/// [`process_packet`], also returning the edge the packet left the fabric towards if it was
+/// delivered.
+pub async fn process_packet_to_edge(
+    fabric: &mut Fabric,
+    tables: &HashMap<RouterId, RoutingTable>,
+    ingress: RouterId,
+    packet: PacketMeta,
+    destination: Destination,
+) -> (PacketMeta, Option<Destination>) {
+    fabric.fragments_out.clear();
+    if let Some(router) = fabric.get_router_mut(&ingress) {
+        router.increment_injected();
+    }
+    forward(fabric, tables, ingress, packet, destination, true, &mut 0).await
+}
+
+/// [`process_packet_to_edge`] for a packet that enters the fabric at the first of `hops` and
+/// follows them instead of the routing tables, leaving it at the last one towards
+/// `destination`.
+pub async fn process_packet_along(
+    fabric: &mut Fabric,
+    tables: &HashMap<RouterId, RoutingTable>,
+    hops: &[RouterId],
+    packet: PacketMeta,
+    destination: Destination,
+) -> Result<(PacketMeta, Option<Destination>), String> {
+    let path = explicit_path::ExplicitPath::new("", hops.to_vec())?;
+    let ingress = path.hops[0].clone();
+    fabric.explicit_paths.insert(0, path);
+    let out = process_packet_to_edge(fabric, tables, ingress, packet, destination).await;
+    fabric.explicit_paths.remove(0);
+    Ok(out)
+}
+
+/// The hop loop of [`process_packet`]. Also returns the edge the packet left the fabric
+/// towards, if it was delivered; fragments split off on the way are forwarded after it and,
+/// when delivered, collected in `fabric.fragments_out`. `entering` marks a packet that enters
+/// the fabric at `ingress`, to have its checksums verified and be metered by its policers;
+/// fragments split off inside it are not. `errors` counts the ICMP errors generated so far in
+/// this `process_packet` call, fragments included.
+async fn forward(
     fabric: &mut Fabric,
     tables: &HashMap<RouterId, RoutingTable>,
     mut ingress: RouterId,
     mut packet: PacketMeta,
     mut destination: Destination,
-) -> PacketMeta {
+    entering: bool,
+    errors: &mut u32,
+) -> (PacketMeta, Option<Destination>) {
+    trace!(
+        "Packet entering at {}:\n{}",
+        ingress.0,
+        packet::explain(&packet.raw)
+    );
+    let entry = ingress.clone();
+    let started = Instant::now();
+    let heading = destination;
+    let mut sojourn = Sojourn::default();
+    let (mut hops, mut delivered) = (0u32, false);
+    let (mut routers, mut links) = (vec![entry.clone()], Vec::new());
+    let mut trailing = Vec::new();
+    // An ICMP error is never answered with another one.
+    let mut is_error = icmp::is_error(&packet);
+    // The tenant the packet belongs to stays the same when it is replaced by an ICMP error.
+    let tenant = fabric
+        .tenants
+        .as_ref()
+        .and_then(|t| t.classify(&packet))
+        .map(str::to_string);
+    let size = packet.raw.len();
+    let mut explicit = entering
+        .then(|| explicit_path::lookup(&mut fabric.explicit_paths, &ingress, &packet))
+        .flatten();
     // Loop forwarding hop‑by‑hop until we cannot forward further.
     let mut hop_count = 0usize;
     loop {
         hop_count += 1;
         if hop_count > 100 {
             debug!("Hop limit exceeded, breaking to avoid infinite loop");
+            fabric.record_drop(&ingress, DropReason::HopLimit, &packet);
             break;
         }
         // Increment received packet counter for the current router.
         if let Some(node_idx) = fabric.router_index.get(&ingress) {
             if let Some(router) = fabric.graph.node_weight_mut(*node_idx) {
-                router.increment_received();
+                router.account_received(&packet);
             }
         }
+        if !admit(
+            fabric,
+            &ingress,
+            &mut packet,
+            entering && hop_count == 1,
+            tenant.as_deref(),
+            &mut sojourn,
+        )
+        .await
+        {
+            break;
+        }
         // Check for TTL expiration before decrementing.
         if packet.ttl <= 1 {
+            fabric.record_drop(&ingress, DropReason::TtlExpired, &packet);
             // TTL will expire; generate ICMP Time Exceeded (IPv4 type 11, code 0) or ICMPv6 Time Exceeded (type 3, code 0).
+            if !may_send_error(fabric, &ingress, is_error, errors) {
+                break;
+            }
             let (ipv4_addr, ipv6_addr) = get_router_addresses(fabric, &ingress);
             let icmp_bytes = if is_ipv6(&packet) {
                 icmp::generate_icmpv6_error(&packet, 3, 0, ipv6_addr, None)
@@ -76,6 +412,7 @@ pub async fn process_packet(
             // Parse ICMP packet and set up reverse routing.
             if let Ok(icmp_packet) = packet::parse(&icmp_bytes) {
                 packet = icmp_packet;
+                is_error = true;
                 destination = opposite_destination(destination);
                 // Do not decrement TTL for the original packet; continue processing the ICMP reply.
                 continue;
@@ -84,12 +421,57 @@ pub async fn process_packet(
                 break;
             }
         }
+        // An explicit path takes precedence over SRv6 segments, policy‑based routing and the
+        // routing table; the packet leaves the fabric at its last hop.
+        let explicit_hop = match explicit.as_mut().filter(|_| !is_error) {
+            Some(path) => match path.advance() {
+                Some(hop) => Some(hop),
+                None => {
+                    debug!(
+                        "Packet reached the end of its explicit path at {}",
+                        ingress.0
+                    );
+                    if let Some(router) = fabric.get_router_mut(&ingress) {
+                        router.increment_delivered();
+                    }
+                    delivered = true;
+                    break;
+                }
+            },
+            None => None,
+        };
+        // Policy‑based routing is consulted before the routing table.
+        // An active SRv6 segment takes precedence over policy‑based routing.
+        let segment_hop = explicit_hop.or_else(|| segment_next_hop(fabric, &ingress, &mut packet));
+        let policy_next_hop = match fabric
+            .get_router_mut(&ingress)
+            .filter(|_| segment_hop.is_none())
+            .and_then(|r| r.policy_route(&packet))
+        {
+            Some(PbrAction::Destination(d)) => {
+                destination = d;
+                None
+            }
+            Some(PbrAction::NextHop(hop)) if fabric.get_link(&ingress, &hop).is_some() => Some(hop),
+            Some(PbrAction::NextHop(hop)) => {
+                debug!(
+                    "PBR next hop {} is not adjacent to {}, using routing table",
+                    hop.0, ingress.0
+                );
+                None
+            }
+            None => segment_hop,
+        };
         // Get routing table for current router.
         let table = match tables.get(&ingress) {
             Some(t) => t,
             None => {
                 debug!("No routing table for router {}", ingress.0);
+                fabric.record_drop(&ingress, DropReason::NoRoute, &packet);
                 // Generate ICMP Destination Unreachable (type 3 code 0)
+                if !may_send_error(fabric, &ingress, is_error, errors) {
+                    break;
+                }
                 let (ipv4_addr, ipv6_addr) = get_router_addresses(fabric, &ingress);
                 let icmp_bytes = if is_ipv6(&packet) {
                     icmp::generate_icmpv6_error(&packet, 1, 0, ipv6_addr, None)
@@ -103,6 +485,7 @@ pub async fn process_packet(
                 }
                 if let Ok(icmp_packet) = packet::parse(&icmp_bytes) {
                     packet = icmp_packet;
+                    is_error = true;
                     destination = opposite_destination(destination);
                     continue;
                 } else {
@@ -110,13 +493,31 @@ pub async fn process_packet(
                 }
             }
         };
-        let next_hop = match destination {
-            Destination::TunA => &table.tun_a.next_hop,
-            Destination::TunB => &table.tun_b.next_hop,
+        // A static prefix route pins the next hop if it wins over the FIB; otherwise the most
+        // specific prefix route takes precedence over the edge routes, and the packet then
+        // leaves the fabric at the edge of the prefix.
+        let pinned_hop = table
+            .static_hop(&packet.dst_ip, tenant.as_deref(), fabric.routing_distance)
+            .filter(|_| policy_next_hop.is_none());
+        let fib_route = table
+            .fib
+            .lookup_for(&packet.dst_ip, tenant.as_deref())
+            .filter(|_| policy_next_hop.is_none() && pinned_hop.is_none());
+        if let Some(route) = fib_route {
+            destination = route.edge;
+        }
+        let next_hop = match (fib_route, destination) {
+            (Some(route), _) => &route.next_hop,
+            (None, Destination::TunA) => &table.tun_a.next_hop,
+            (None, Destination::TunB) => &table.tun_b.next_hop,
         };
         // Destination detection: if next hop is the current router, packet has arrived at its destination.
-        if next_hop == &ingress {
+        if policy_next_hop.is_none() && pinned_hop.is_none() && next_hop == &ingress {
             debug!("Packet reached destination router {}", ingress.0);
+            if let Some(router) = fabric.get_router_mut(&ingress) {
+                router.increment_delivered();
+            }
+            delivered = true;
             break;
         }
         // Decrement TTL / Hop Limit after confirming we are not at destination.
@@ -124,13 +525,32 @@ pub async fn process_packet(
             error!("Failed to decrement TTL: {}", e);
             break;
         }
+        // IPv4 Record Route / Timestamp options get this router's address.
+        if !is_ipv6(&packet) {
+            let (ipv4_addr, _) = get_router_addresses(fabric, &ingress);
+            packet.record_hop(ipv4_addr);
+        }
         // Select egress link using forwarding engine (supports load‑balancing).
         let incident_links = fabric.incident_links(&ingress);
-        let link_opt = select_egress_link(&ingress, &packet, &incident_links, tables, destination);
+        let policy_next_hop = policy_next_hop
+            .or(pinned_hop.cloned())
+            .or(fib_route.map(|r| r.next_hop.clone()));
+        let link_opt = match &policy_next_hop {
+            Some(hop) => fabric.get_link(&ingress, hop),
+            None => select_egress_link(
+                &ingress,
+                &packet,
+                &incident_links,
+                tables,
+                destination,
+                &fabric.ecmp_hash,
+            ),
+        };
         let link = match link_opt {
             Some(l) => l,
             None => {
                 debug!("No egress link selected for router {}", ingress.0);
+                fabric.record_drop(&ingress, DropReason::NoEgressLink, &packet);
                 break;
             }
         };
@@ -140,9 +560,61 @@ pub async fn process_packet(
         } else {
             link.id.a.clone()
         };
-        if let Err(e) = simulate_link(link, &packet.raw).await {
+        if fabric.paranoid && policy_next_hop.is_none() {
+            let distance =
+                |r: &RouterId| tables.get(r).map_or(u32::MAX, |t| t.distance(destination));
+            let distances = (distance(&ingress), distance(&next_hop));
+            if let Err(report) = check_progress(
+                &ingress,
+                &next_hop,
+                destination,
+                distances,
+                &packet,
+                &routers,
+            ) {
+                record_violation(fabric, &ingress, &packet, report);
+                break;
+            }
+        }
+        // 802.1Q: the link may not carry the packet's VLAN, or translate its VLAN ID.
+        if !link.cfg.vlan_policy().admit(&mut packet.vlan) {
+            debug!(
+                "VLAN {:?} not carried on link between {} and {}",
+                packet.vlan.map(|tag| tag.vid),
+                ingress.0,
+                next_hop.0
+            );
+            fabric.record_drop(&ingress, DropReason::VlanFiltered, &packet);
+            break;
+        }
+        let link_id = link.id.clone();
+        let mut result = simulate_link_timed(link, &ingress, &packet.raw).await;
+        // Without Don't Fragment an oversized IPv4 packet is fragmented: the first fragment
+        // takes its place on the link and the others follow once it is done.
+        if let Err(SimulationError::MtuExceeded { mtu, .. }) = result {
+            if let Some((first, rest)) = fragment(&packet, mtu) {
+                packet = first;
+                result = simulate_link_timed(link, &ingress, &packet.raw).await;
+                let vlan = packet.vlan;
+                trailing.extend(
+                    rest.into_iter()
+                        .map(|raw| (ingress.clone(), next_hop.clone(), destination, raw, vlan)),
+                );
+            }
+        }
+        if let Ok(link_time) = &result {
+            sojourn.add(link_time);
+            hops += 1;
+            routers.push(next_hop.clone());
+            links.push(link_id.clone());
+        }
+        if let Err(e) = result {
             match e {
                 SimulationError::MtuExceeded { mtu, .. } => {
+                    fabric.record_drop(&ingress, DropReason::MtuExceeded, &packet);
+                    if !may_send_error(fabric, &ingress, is_error, errors) {
+                        break;
+                    }
                     let (ipv4_addr, ipv6_addr) = get_router_addresses(fabric, &ingress);
                     let icmp_bytes = if is_ipv6(&packet) {
                         icmp::generate_icmpv6_error(&packet, 2, 0, ipv6_addr, Some(mtu))
@@ -156,17 +628,26 @@ pub async fn process_packet(
                     }
                     if let Ok(icmp_packet) = packet::parse(&icmp_bytes) {
                         packet = icmp_packet;
+                        is_error = true;
                         destination = opposite_destination(destination);
                         continue;
                     } else {
-                        return packet;
+                        break;
                     }
                 }
-                SimulationError::PacketLost => {
+                SimulationError::PacketLost
+                | SimulationError::QueueFull
+                | SimulationError::LinkDown => {
                     debug!(
                         "Packet lost on link between {} and {}",
                         ingress.0, next_hop.0
                     );
+                    let reason = match e {
+                        SimulationError::QueueFull => DropReason::QueueFull,
+                        SimulationError::LinkDown => DropReason::LinkDown,
+                        _ => DropReason::LinkLoss,
+                    };
+                    fabric.record_drop(&ingress, reason, &packet);
                     if let Some(node_idx) = fabric.router_index.get(&ingress) {
                         if let Some(router) = fabric.graph.node_weight_mut(*node_idx) {
                             router.increment_lost();
@@ -182,25 +663,124 @@ pub async fn process_packet(
             // Successful forwarding – increment forwarded counter.
             if let Some(node_idx) = fabric.router_index.get(&ingress) {
                 if let Some(router) = fabric.graph.node_weight_mut(*node_idx) {
-                    router.increment_forwarded();
+                    router.account_forwarded(packet.raw.len());
                 }
             }
+            fabric.capture(&link_id, &packet);
             // Move to next router for next hop.
             ingress = next_hop.clone();
             continue;
         }
     }
-    packet
+    if let (true, Some(tenant), Some(tenants)) = (entering, &tenant, fabric.tenants.as_mut()) {
+        tenants.account(tenant, size, delivered);
+    }
+    packet.annotation = Some(Box::new(Annotation {
+        ingress_at: started,
+        routers,
+        links,
+        delay: sojourn,
+    }));
+    record_outcome(
+        fabric,
+        TraceRecord {
+            ingress: &entry,
+            last: &ingress,
+            delivered,
+            hops,
+            sojourn,
+            elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+            sent: (started, opposite_destination(heading)),
+            received: delivered.then_some(destination),
+            packet: &packet,
+        },
+    );
+    for (from, to, destination, raw, vlan) in trailing {
+        if let Some(fragment) = cross_link(fabric, &from, &to, &raw, vlan).await {
+            let (out, edge) = Box::pin(forward(
+                fabric,
+                tables,
+                to,
+                fragment,
+                destination,
+                false,
+                errors,
+            ))
+            .await;
+            if let Some(edge) = edge {
+                fabric.fragments_out.push((out, edge));
+            }
+        }
+    }
+    (packet, delivered.then_some(destination))
 }
 
-// Process a packet using multipath routing tables.
+// Process a packet using multipath routing tables; annotated like [`process_packet`].
 pub async fn process_packet_multi(
+    fabric: &mut Fabric,
+    tables: &HashMap<RouterId, MultiPathTable>,
+    ingress: RouterId,
+    packet: PacketMeta,
+    destination: Destination,
+) -> PacketMeta {
+    process_packet_multi_to_edge(fabric, tables, ingress, packet, destination)
+        .await
+        .0
+}
+
+/// [`process_packet_multi`], also returning the edge the packet left the fabric towards if it
+/// was delivered.
+pub async fn process_packet_multi_to_edge(
+    fabric: &mut Fabric,
+    tables: &HashMap<RouterId, MultiPathTable>,
+    ingress: RouterId,
+    packet: PacketMeta,
+    destination: Destination,
+) -> (PacketMeta, Option<Destination>) {
+    fabric.fragments_out.clear();
+    if let Some(router) = fabric.get_router_mut(&ingress) {
+        router.increment_injected();
+    }
+    forward_multi(fabric, tables, ingress, packet, destination, true, &mut 0).await
+}
+
+/// The hop loop of [`process_packet_multi`]. Also returns the edge the packet left the fabric
+/// towards, if it was delivered; fragments split off on the way are forwarded after it and,
+/// when delivered, collected in `fabric.fragments_out`. `entering` and `errors` as for
+/// [`forward`].
+async fn forward_multi(
     fabric: &mut Fabric,
     tables: &HashMap<RouterId, MultiPathTable>,
     mut ingress: RouterId,
     mut packet: PacketMeta,
     mut destination: Destination,
-) -> PacketMeta {
+    entering: bool,
+    errors: &mut u32,
+) -> (PacketMeta, Option<Destination>) {
+    trace!(
+        "Packet entering at {}:\n{}",
+        ingress.0,
+        packet::explain(&packet.raw)
+    );
+    let entry = ingress.clone();
+    let started = Instant::now();
+    let heading = destination;
+    let mut sojourn = Sojourn::default();
+    let (mut hops, mut delivered) = (0u32, false);
+    let (mut routers, mut links) = (vec![entry.clone()], Vec::new());
+    let mut trailing = Vec::new();
+    // An ICMP error is never answered with another one.
+    let mut is_error = icmp::is_error(&packet);
+    // The tenant the packet belongs to stays the same when it is replaced by an ICMP error.
+    let tenant = fabric
+        .tenants
+        .as_ref()
+        .and_then(|t| t.classify(&packet))
+        .map(str::to_string);
+    let size = packet.raw.len();
+    let mut explicit = entering
+        .then(|| explicit_path::lookup(&mut fabric.explicit_paths, &ingress, &packet))
+        .flatten();
     // Multipath processing loop similar to single‑path but selects from equal‑cost next hops.
     let mut hop_count = 0usize;
     loop {
@@ -208,16 +788,33 @@ pub async fn process_packet_multi(
         hop_count += 1;
         if hop_count > 100 {
             debug!("Hop limit exceeded in multipath processing, breaking to avoid infinite loop");
+            fabric.record_drop(&ingress, DropReason::HopLimit, &packet);
             break;
         }
         // Increment received counter for the current router.
         if let Some(node_idx) = fabric.router_index.get(&ingress) {
             if let Some(router) = fabric.graph.node_weight_mut(*node_idx) {
-                router.increment_received();
+                router.account_received(&packet);
             }
         }
+        if !admit(
+            fabric,
+            &ingress,
+            &mut packet,
+            entering && hop_count == 1,
+            tenant.as_deref(),
+            &mut sojourn,
+        )
+        .await
+        {
+            break;
+        }
         // TTL expiration handling (same as single‑path).
         if packet.ttl <= 1 {
+            fabric.record_drop(&ingress, DropReason::TtlExpired, &packet);
+            if !may_send_error(fabric, &ingress, is_error, errors) {
+                break;
+            }
             let (ipv4_addr, ipv6_addr) = get_router_addresses(fabric, &ingress);
             let icmp_bytes = if is_ipv6(&packet) {
                 icmp::generate_icmpv6_error(&packet, 3, 0, ipv6_addr, None)
@@ -231,18 +828,64 @@ pub async fn process_packet_multi(
             }
             if let Ok(icmp_packet) = packet::parse(&icmp_bytes) {
                 packet = icmp_packet;
+                is_error = true;
                 destination = opposite_destination(destination);
                 continue;
             } else {
                 break;
             }
         }
+        // An explicit path takes precedence over SRv6 segments, policy‑based routing and the
+        // routing table; the packet leaves the fabric at its last hop.
+        let explicit_hop = match explicit.as_mut().filter(|_| !is_error) {
+            Some(path) => match path.advance() {
+                Some(hop) => Some(hop),
+                None => {
+                    debug!(
+                        "Packet reached the end of its explicit path at {}",
+                        ingress.0
+                    );
+                    if let Some(router) = fabric.get_router_mut(&ingress) {
+                        router.increment_delivered();
+                    }
+                    delivered = true;
+                    break;
+                }
+            },
+            None => None,
+        };
+        // Policy‑based routing (same as single‑path).
+        // An active SRv6 segment takes precedence over policy‑based routing.
+        let segment_hop = explicit_hop.or_else(|| segment_next_hop(fabric, &ingress, &mut packet));
+        let policy_next_hop = match fabric
+            .get_router_mut(&ingress)
+            .filter(|_| segment_hop.is_none())
+            .and_then(|r| r.policy_route(&packet))
+        {
+            Some(PbrAction::Destination(d)) => {
+                destination = d;
+                None
+            }
+            Some(PbrAction::NextHop(hop)) if fabric.get_link(&ingress, &hop).is_some() => Some(hop),
+            Some(PbrAction::NextHop(hop)) => {
+                debug!(
+                    "PBR next hop {} is not adjacent to {}, using routing table",
+                    hop.0, ingress.0
+                );
+                None
+            }
+            None => segment_hop,
+        };
         // Retrieve multipath table for current router.
         let mtable = match tables.get(&ingress) {
             Some(t) => t,
             None => {
                 debug!("No multipath table for router {}", ingress.0);
+                fabric.record_drop(&ingress, DropReason::NoRoute, &packet);
                 // Generate ICMP Destination Unreachable similar to single‑path handling.
+                if !may_send_error(fabric, &ingress, is_error, errors) {
+                    break;
+                }
                 let (ipv4_addr, ipv6_addr) = get_router_addresses(fabric, &ingress);
                 let icmp_bytes = if is_ipv6(&packet) {
                     icmp::generate_icmpv6_error(&packet, 1, 0, ipv6_addr, None)
@@ -256,6 +899,7 @@ pub async fn process_packet_multi(
                 }
                 if let Ok(icmp_packet) = packet::parse(&icmp_bytes) {
                     packet = icmp_packet;
+                    is_error = true;
                     destination = opposite_destination(destination);
                     continue;
                 } else {
@@ -270,15 +914,20 @@ pub async fn process_packet_multi(
         };
         if entries.is_empty() {
             debug!("No multipath entries for router {}", ingress.0);
+            fabric.record_drop(&ingress, DropReason::NoEgressLink, &packet);
             break;
         }
         // Check if we've reached the destination (Issue 102 fix: check BEFORE TTL decrement)
         // If any entry points back to ourselves, we're at the destination.
-        if entries.iter().any(|e| e.next_hop == ingress) {
+        if policy_next_hop.is_none() && entries.iter().any(|e| e.next_hop == ingress) {
             debug!(
                 "Packet reached destination router {} (multipath)",
                 ingress.0
             );
+            if let Some(router) = fabric.get_router_mut(&ingress) {
+                router.increment_delivered();
+            }
+            delivered = true;
             break;
         }
         // Decrement TTL only after confirming we're not at destination.
@@ -286,38 +935,63 @@ pub async fn process_packet_multi(
             error!("Failed to decrement TTL: {}", e);
             break;
         }
+        // IPv4 Record Route / Timestamp options get this router's address.
+        if !is_ipv6(&packet) {
+            let (ipv4_addr, _) = get_router_addresses(fabric, &ingress);
+            packet.record_hop(ipv4_addr);
+        }
         // Determine candidate links that connect to any of the equal‑cost next hops.
         let incident_links = fabric.incident_links(&ingress);
-        let mut candidate_links: Vec<&Link> = incident_links
-            .iter()
-            .filter(|&&link| {
-                entries
-                    .iter()
-                    .any(|e| e.next_hop == link.id.a || e.next_hop == link.id.b)
-            })
-            .cloned()
-            .collect();
+        let mut candidate_links: Vec<&Link> = match &policy_next_hop {
+            Some(hop) => fabric.get_link(&ingress, hop).into_iter().collect(),
+            None => incident_links
+                .iter()
+                .filter(|&&link| {
+                    entries
+                        .iter()
+                        .any(|e| e.next_hop == link.id.a || e.next_hop == link.id.b)
+                })
+                .cloned()
+                .collect(),
+        };
         if candidate_links.is_empty() {
             // Fallback to any incident link.
             candidate_links = incident_links;
         }
         // Load‑balance among candidate links with load_balance enabled.
-        // Issue 104 fix: Use only the 5-tuple hash for consistent flow affinity (no counter).
+        // Issue 104 fix: Use only the flow hash for consistent flow affinity (no counter).
         let lb_links: Vec<&&Link> = candidate_links
             .iter()
             .filter(|&&l| l.cfg.load_balance)
             .collect();
         let chosen_link = if !lb_links.is_empty() {
-            use std::collections::hash_map::DefaultHasher;
-            use std::hash::{Hash, Hasher};
-            let mut hasher = DefaultHasher::new();
-            packet.src_ip.hash(&mut hasher);
-            packet.dst_ip.hash(&mut hasher);
-            packet.src_port.hash(&mut hasher);
-            packet.dst_port.hash(&mut hasher);
-            packet.protocol.hash(&mut hasher);
-            let hash = hasher.finish();
-            let idx = (hash as usize) % lb_links.len();
+            let flow = fabric.ecmp_hash.flow(&packet);
+            // Keep the flow on the next hop it was pinned to, if that is still a candidate;
+            // new flows are spread by the next hops' weights.
+            let hops: Vec<RouterId> = lb_links
+                .iter()
+                .map(|l| {
+                    if l.id.a == ingress {
+                        l.id.b.clone()
+                    } else {
+                        l.id.a.clone()
+                    }
+                })
+                .collect();
+            let weights: Vec<u32> = hops
+                .iter()
+                .map(|hop| mtable.weight(destination, hop))
+                .collect();
+            let idx = fabric
+                .flows
+                .select(
+                    &ingress,
+                    destination,
+                    &flow,
+                    &fabric.ecmp_hash,
+                    (&hops, &weights),
+                )
+                .unwrap_or(0);
             *lb_links[idx]
         } else {
             // Default: pick first candidate link.
@@ -329,10 +1003,65 @@ pub async fn process_packet_multi(
         } else {
             chosen_link.id.a.clone()
         };
+        if fabric.paranoid && policy_next_hop.is_none() {
+            let distance = |r: &RouterId| {
+                tables
+                    .get(r)
+                    .map_or(u32::MAX, |t| t.distance(r, destination))
+            };
+            let distances = (distance(&ingress), distance(&next_hop));
+            if let Err(report) = check_progress(
+                &ingress,
+                &next_hop,
+                destination,
+                distances,
+                &packet,
+                &routers,
+            ) {
+                record_violation(fabric, &ingress, &packet, report);
+                break;
+            }
+        }
+        // 802.1Q: the link may not carry the packet's VLAN, or translate its VLAN ID.
+        if !chosen_link.cfg.vlan_policy().admit(&mut packet.vlan) {
+            debug!(
+                "VLAN {:?} not carried on link between {} and {}",
+                packet.vlan.map(|tag| tag.vid),
+                ingress.0,
+                next_hop.0
+            );
+            fabric.record_drop(&ingress, DropReason::VlanFiltered, &packet);
+            break;
+        }
         // Simulate the link.
-        if let Err(e) = simulate_link(chosen_link, &packet.raw).await {
+        let link_id = chosen_link.id.clone();
+        let mut result = simulate_link_timed(chosen_link, &ingress, &packet.raw).await;
+        // Without Don't Fragment an oversized IPv4 packet is fragmented: the first fragment
+        // takes its place on the link and the others follow once it is done.
+        if let Err(SimulationError::MtuExceeded { mtu, .. }) = result {
+            if let Some((first, rest)) = fragment(&packet, mtu) {
+                packet = first;
+                result = simulate_link_timed(chosen_link, &ingress, &packet.raw).await;
+                let vlan = packet.vlan;
+                trailing.extend(
+                    rest.into_iter()
+                        .map(|raw| (ingress.clone(), next_hop.clone(), destination, raw, vlan)),
+                );
+            }
+        }
+        if let Ok(link_time) = &result {
+            sojourn.add(link_time);
+            hops += 1;
+            routers.push(next_hop.clone());
+            links.push(link_id.clone());
+        }
+        if let Err(e) = result {
             match e {
                 SimulationError::MtuExceeded { mtu, .. } => {
+                    fabric.record_drop(&ingress, DropReason::MtuExceeded, &packet);
+                    if !may_send_error(fabric, &ingress, is_error, errors) {
+                        break;
+                    }
                     let (ipv4_addr, ipv6_addr) = get_router_addresses(fabric, &ingress);
                     let icmp_bytes = if is_ipv6(&packet) {
                         icmp::generate_icmpv6_error(&packet, 2, 0, ipv6_addr, Some(mtu))
@@ -346,17 +1075,26 @@ pub async fn process_packet_multi(
                     }
                     if let Ok(icmp_packet) = packet::parse(&icmp_bytes) {
                         packet = icmp_packet;
+                        is_error = true;
                         destination = opposite_destination(destination);
                         continue;
                     } else {
-                        return packet;
+                        break;
                     }
                 }
-                SimulationError::PacketLost => {
+                SimulationError::PacketLost
+                | SimulationError::QueueFull
+                | SimulationError::LinkDown => {
                     debug!(
                         "Packet lost on link between {} and {}",
                         ingress.0, next_hop.0
                     );
+                    let reason = match e {
+                        SimulationError::QueueFull => DropReason::QueueFull,
+                        SimulationError::LinkDown => DropReason::LinkDown,
+                        _ => DropReason::LinkLoss,
+                    };
+                    fabric.record_drop(&ingress, reason, &packet);
                     if let Some(node_idx) = fabric.router_index.get(&ingress) {
                         if let Some(router) = fabric.graph.node_weight_mut(*node_idx) {
                             router.increment_lost();
@@ -372,12 +1110,53 @@ pub async fn process_packet_multi(
             // Successful forwarding – increment forwarded counter.
             if let Some(node_idx) = fabric.router_index.get(&ingress) {
                 if let Some(router) = fabric.graph.node_weight_mut(*node_idx) {
-                    router.increment_forwarded();
+                    router.account_forwarded(packet.raw.len());
                 }
             }
+            fabric.capture(&link_id, &packet);
         }
         // Move to next router.
         ingress = next_hop.clone();
     }
-    packet
+    if let (true, Some(tenant), Some(tenants)) = (entering, &tenant, fabric.tenants.as_mut()) {
+        tenants.account(tenant, size, delivered);
+    }
+    packet.annotation = Some(Box::new(Annotation {
+        ingress_at: started,
+        routers,
+        links,
+        delay: sojourn,
+    }));
+    record_outcome(
+        fabric,
+        TraceRecord {
+            ingress: &entry,
+            last: &ingress,
+            delivered,
+            hops,
+            sojourn,
+            elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+            sent: (started, opposite_destination(heading)),
+            received: delivered.then_some(destination),
+            packet: &packet,
+        },
+    );
+    for (from, to, destination, raw, vlan) in trailing {
+        if let Some(fragment) = cross_link(fabric, &from, &to, &raw, vlan).await {
+            let (out, edge) = Box::pin(forward_multi(
+                fabric,
+                tables,
+                to,
+                fragment,
+                destination,
+                false,
+                errors,
+            ))
+            .await;
+            if let Some(edge) = edge {
+                fabric.fragments_out.push((out, edge));
+            }
+        }
+    }
+    (packet, delivered.then_some(destination))
 }
@@ -0,0 +1,266 @@
+// src/provenance/mod.rs
+
+//! Run metadata stamped on the outputs of a run, and the index of past outputs.
+//!
+//! The CLI describes every simulation run with a [`RunMetadata`]: a run id, the start time, the
+//! crate version, the configuration file and a hash of its text, the seed and the command line.
+//! Each output carries it where its format has room: the `run` field of a `--stats-json` dump, a
+//! `# run {...}` first line of a CSV packet trace or a hex packet output (readers skip `#`
+//! lines), a `{"run": {...}}` first line of a JSON packet trace, and the section comment of a
+//! pcapng output. A classic pcap has no room, so the drop capture gets a `<file>.run.json`
+//! sidecar. `runs list [DIR]` reads the stamps back and prints the outputs in `DIR` grouped by
+//! run, oldest first, so a result can be traced to the exact configuration and seed that
+//! produced it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Suffix of the sidecar file of outputs that cannot carry the metadata themselves.
+pub const SIDECAR_SUFFIX: &str = ".run.json";
+
+/// Prefix of the comment lines and pcapng comments carrying the metadata.
+const COMMENT_PREFIX: &str = "run ";
+
+/// Larger files are not parsed whole when looking for a stamp.
+const MAX_INDEXED_JSON: u64 = 16 << 20;
+
+/// Where an output came from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    /// Start time and configuration hash, e.g. `20261017T120000Z-1f2e3d4c`.
+    pub run_id: String,
+    /// Start time in UTC, RFC 3339.
+    pub started: String,
+    pub crate_version: String,
+    /// Path of the configuration file as given.
+    pub config: String,
+    /// FNV‑1a hash of the configuration text, `fnv1a64:<hex>`.
+    pub config_hash: String,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Command line, program name first.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// 64‑bit FNV‑1a of `data`: stable across builds and platforms, unlike the std hasher.
+fn fnv1a64(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Civil date and time in UTC of `secs` since the Unix epoch.
+fn utc(secs: u64) -> (i64, u32, u32, u64, u64, u64) {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Days to civil date (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// `secs` since the Unix epoch in UTC, RFC 3339.
+pub(crate) fn rfc3339(secs: u64) -> String {
+    let (y, mo, d, h, mi, s) = utc(secs);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", y, mo, d, h, mi, s)
+}
+
+impl RunMetadata {
+    /// Metadata of a run of the configuration `config_text`, read from `config`, starting now.
+    pub fn new(config: &str, config_text: &str, seed: Option<u64>, args: Vec<String>) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self::at(now, config, config_text, seed, args)
+    }
+
+    /// [`new`](Self::new), starting `secs` after the Unix epoch.
+    pub fn at(
+        secs: u64,
+        config: &str,
+        config_text: &str,
+        seed: Option<u64>,
+        args: Vec<String>,
+    ) -> Self {
+        let hash = fnv1a64(config_text.as_bytes());
+        let (y, mo, d, h, mi, s) = utc(secs);
+        Self {
+            run_id: format!(
+                "{:04}{:02}{:02}T{:02}{:02}{:02}Z-{:08x}",
+                y,
+                mo,
+                d,
+                h,
+                mi,
+                s,
+                hash >> 32
+            ),
+            started: rfc3339(secs),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            config: config.to_string(),
+            config_hash: format!("fnv1a64:{:016x}", hash),
+            seed,
+            args,
+        }
+    }
+
+    /// `run {...}`: the text of a comment line or pcapng comment carrying the metadata.
+    pub fn comment(&self) -> String {
+        format!(
+            "{}{}",
+            COMMENT_PREFIX,
+            serde_json::to_string(self).unwrap_or_default()
+        )
+    }
+
+    /// The metadata of a [`comment`](Self::comment), with or without a leading `#`.
+    pub fn from_comment(line: &str) -> Option<Self> {
+        let text = line.trim().trim_start_matches('#').trim_start();
+        serde_json::from_str(text.strip_prefix(COMMENT_PREFIX)?).ok()
+    }
+
+    /// Write the sidecar of `output`.
+    pub fn write_sidecar(&self, output: &str) -> Result<(), String> {
+        let path = format!("{}{}", output, SIDECAR_SUFFIX);
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to encode run metadata: {}", e))?;
+        std::fs::write(&path, json + "\n")
+            .map_err(|e| format!("Failed to write run metadata to {}: {}", path, e))
+    }
+}
+
+/// The metadata stamped on the output at `path`, if any.
+pub fn read_stamp(path: &Path) -> Option<RunMetadata> {
+    let name = path.file_name()?.to_str()?;
+    if name.ends_with(SIDECAR_SUFFIX) {
+        return serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok();
+    }
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let head = reader.fill_buf().ok()?;
+    // The section header block type reads the same in both byte orders.
+    if head.starts_with(&crate::replay::PCAPNG_MAGIC.to_le_bytes()) {
+        let mut section = vec![0; head.len().min(64 << 10)];
+        reader.read_exact(&mut section).ok()?;
+        return pcapng_comment(&section).and_then(|c| RunMetadata::from_comment(&c));
+    }
+    let mut first = String::new();
+    reader.by_ref().take(64 << 10).read_line(&mut first).ok()?;
+    if let Some(run) = RunMetadata::from_comment(&first) {
+        return Some(run);
+    }
+    #[derive(Deserialize)]
+    struct Stamped {
+        run: RunMetadata,
+    }
+    if let Ok(stamped) = serde_json::from_str::<Stamped>(first.trim()) {
+        return Some(stamped.run);
+    }
+    // A pretty‑printed statistics dump spans many lines.
+    if first.trim() == "{" && std::fs::metadata(path).ok()?.len() <= MAX_INDEXED_JSON {
+        let text = std::fs::read_to_string(path).ok()?;
+        return serde_json::from_str::<Stamped>(&text).ok().map(|s| s.run);
+    }
+    None
+}
+
+/// The first comment option of the pcapng section header block at the start of `data`.
+fn pcapng_comment(data: &[u8]) -> Option<String> {
+    let order = data.get(8..12)?;
+    let little = order == crate::replay::PCAPNG_BYTE_ORDER.to_le_bytes();
+    let u16_at = |at: usize| -> Option<u16> {
+        let b = [*data.get(at)?, *data.get(at + 1)?];
+        Some(if little {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    };
+    let len_bytes: [u8; 4] = data.get(4..8)?.try_into().ok()?;
+    let len = if little {
+        u32::from_le_bytes(len_bytes)
+    } else {
+        u32::from_be_bytes(len_bytes)
+    } as usize;
+    // Options follow the byte‑order magic, version and section length.
+    let mut at = 24;
+    while at + 4 <= len.saturating_sub(4) {
+        let (code, size) = (u16_at(at)?, u16_at(at + 2)? as usize);
+        match code {
+            0 => return None,
+            1 => {
+                let value = data.get(at + 4..at + 4 + size)?;
+                return String::from_utf8(value.to_vec()).ok();
+            }
+            _ => at += 4 + size.div_ceil(4) * 4,
+        }
+    }
+    None
+}
+
+/// The stamped outputs in `dir` (not descending), grouped by run and sorted by start time.
+pub fn index(dir: &Path) -> Result<Vec<(RunMetadata, Vec<String>)>, String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+    let mut runs: BTreeMap<(String, String), (RunMetadata, Vec<String>)> = BTreeMap::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some(run) = read_stamp(&path) else {
+            continue;
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let output = name
+            .strip_suffix(SIDECAR_SUFFIX)
+            .unwrap_or(&name)
+            .to_string();
+        runs.entry((run.started.clone(), run.run_id.clone()))
+            .or_insert_with(|| (run, Vec::new()))
+            .1
+            .push(output);
+    }
+    Ok(runs
+        .into_values()
+        .map(|(run, mut outputs)| {
+            outputs.sort();
+            (run, outputs)
+        })
+        .collect())
+}
+
+/// The runs of [`index`], one block each: the metadata, then the outputs indented.
+pub fn render_index(runs: &[(RunMetadata, Vec<String>)]) -> String {
+    let mut out = String::new();
+    for (run, outputs) in runs {
+        out.push_str(&format!(
+            "{}  started {}  v{}  seed {}  {} ({})\n",
+            run.run_id,
+            run.started,
+            run.crate_version,
+            run.seed.map_or("-".to_string(), |s| s.to_string()),
+            run.config,
+            run.config_hash
+        ));
+        if !run.args.is_empty() {
+            out.push_str(&format!("  args: {}\n", run.args.join(" ")));
+        }
+        for output in outputs {
+            out.push_str(&format!("  {}\n", output));
+        }
+    }
+    out
+}
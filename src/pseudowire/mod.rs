@@ -0,0 +1,239 @@
+// src/pseudowire/mod.rs
+
+//! L2TPv3‑over‑UDP pseudowire between the two edge routers (RFC 3931).
+//!
+//! With a `[pseudowire]` section, every edge packet is carried across the fabric inside an
+//! outer IPv4/UDP packet from its ingress edge router to the far edge router: UDP port 1701,
+//! the L2TPv3 data header with the session ID and optional cookie the far end expects, and (with
+//! `sequencing`) the default L2‑Specific Sublayer carrying a 24‑bit sequence number. The far end
+//! validates session ID and cookie, drops packets arriving out of order, and hands the inner
+//! packet on. Link MTUs, queues and loss all apply to the larger outer packet.
+
+use crate::config::{PseudowireConfig, PseudowireEndConfig};
+use crate::packet::{ipv4_udp, parse, PacketMeta};
+use crate::routing::Destination;
+use std::net::Ipv4Addr;
+use tracing::debug;
+
+/// L2TP's registered UDP port.
+pub const L2TP_UDP_PORT: u16 = 1701;
+/// First word of an L2TPv3 data message over UDP: T bit clear, version 3.
+const L2TP_DATA_HEADER: [u8; 4] = [0x00, 0x03, 0x00, 0x00];
+/// S bit of the default L2‑Specific Sublayer.
+const L2SS_SEQUENCE: u8 = 0x40;
+const SEQ_MASK: u32 = 0x00FF_FFFF;
+
+/// Counters for one direction of the pseudowire.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PseudowireStats {
+    pub encapsulated: u64,
+    pub decapsulated: u64,
+    /// Packets whose session ID was not the one the receiving end expects.
+    pub session_mismatch: u64,
+    /// Packets whose cookie did not match the receiving end's.
+    pub cookie_mismatch: u64,
+    /// Packets dropped for arriving behind a later sequence number.
+    pub out_of_order: u64,
+}
+
+impl PseudowireStats {
+    pub fn summary(&self) -> String {
+        format!(
+            "encapsulated={}, decapsulated={}, session_mismatch={}, cookie_mismatch={}, out_of_order={}",
+            self.encapsulated,
+            self.decapsulated,
+            self.session_mismatch,
+            self.cookie_mismatch,
+            self.out_of_order
+        )
+    }
+}
+
+/// Why the receiving end refused a packet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reject {
+    /// Not an L2TPv3 data message for this pseudowire.
+    Malformed,
+    SessionMismatch(u32),
+    CookieMismatch,
+    OutOfOrder {
+        seq: u32,
+        expected: u32,
+    },
+}
+
+/// Decode a hex cookie of 0, 4 or 8 bytes.
+pub fn parse_cookie(cookie: Option<&str>) -> Result<Vec<u8>, String> {
+    let Some(cookie) = cookie else {
+        return Ok(Vec::new());
+    };
+    let bytes =
+        hex::decode(cookie).map_err(|_| format!("Invalid pseudowire cookie '{}'", cookie))?;
+    if !matches!(bytes.len(), 4 | 8) {
+        return Err(format!(
+            "Pseudowire cookie '{}' must be 4 or 8 bytes, got {}",
+            cookie,
+            bytes.len()
+        ));
+    }
+    Ok(bytes)
+}
+
+/// One direction: what the sending end puts on the wire and what the receiving end expects.
+#[derive(Debug)]
+pub struct Session {
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    udp_port: u16,
+    sequencing: bool,
+    send_session_id: u32,
+    send_cookie: Vec<u8>,
+    expect_session_id: u32,
+    expect_cookie: Vec<u8>,
+    next_seq: u32,
+    expected_seq: Option<u32>,
+    stats: PseudowireStats,
+}
+
+impl Session {
+    fn new(
+        sender: &PseudowireEndConfig,
+        receiver: &PseudowireEndConfig,
+        (src, dst): (Ipv4Addr, Ipv4Addr),
+        cfg: &PseudowireConfig,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            src,
+            dst,
+            udp_port: cfg.udp_port,
+            sequencing: cfg.sequencing,
+            send_session_id: sender.remote_session_id,
+            send_cookie: parse_cookie(sender.remote_cookie.as_deref())?,
+            expect_session_id: receiver.local_session_id,
+            expect_cookie: parse_cookie(receiver.local_cookie.as_deref())?,
+            next_seq: 0,
+            expected_seq: None,
+            stats: PseudowireStats::default(),
+        })
+    }
+
+    /// Bytes added to every packet: outer IPv4 and UDP headers plus the L2TPv3 header.
+    pub fn overhead(&self) -> usize {
+        20 + 8 + 8 + self.send_cookie.len() + if self.sequencing { 4 } else { 0 }
+    }
+
+    /// Wrap `packet` in the outer IPv4/UDP/L2TPv3 headers.
+    pub fn encapsulate(&mut self, packet: &PacketMeta) -> PacketMeta {
+        // The zero UDP checksum is also what RFC 3931 section 4.1.2.2 permits over IPv4.
+        let mut payload = Vec::with_capacity(self.overhead() - 28 + packet.raw.len());
+        payload.extend_from_slice(&L2TP_DATA_HEADER);
+        payload.extend_from_slice(&self.send_session_id.to_be_bytes());
+        payload.extend_from_slice(&self.send_cookie);
+        if self.sequencing {
+            let seq = self.next_seq.to_be_bytes();
+            payload.extend_from_slice(&[L2SS_SEQUENCE, seq[1], seq[2], seq[3]]);
+            self.next_seq = (self.next_seq + 1) & SEQ_MASK;
+        }
+        payload.extend_from_slice(&packet.raw);
+        self.stats.encapsulated += 1;
+        ipv4_udp(self.src, self.dst, self.udp_port, &payload)
+    }
+
+    /// Validate an outer packet at the receiving end and return the inner packet.
+    pub fn decapsulate(&mut self, outer: &PacketMeta) -> Result<PacketMeta, Reject> {
+        let result = self.unwrap(outer);
+        match &result {
+            Ok(_) => self.stats.decapsulated += 1,
+            Err(Reject::SessionMismatch(_)) => self.stats.session_mismatch += 1,
+            Err(Reject::CookieMismatch) => self.stats.cookie_mismatch += 1,
+            Err(Reject::OutOfOrder { .. }) => self.stats.out_of_order += 1,
+            Err(Reject::Malformed) => {}
+        }
+        if let Err(reject) = &result {
+            debug!("Pseudowire dropped packet: {:?}", reject);
+        }
+        result
+    }
+
+    fn unwrap(&mut self, outer: &PacketMeta) -> Result<PacketMeta, Reject> {
+        let raw = &outer.raw;
+        let ihl = (*raw.first().ok_or(Reject::Malformed)? & 0x0F) as usize * 4;
+        if raw[0] >> 4 != 4 || outer.protocol != 17 || outer.dst_port != self.udp_port {
+            return Err(Reject::Malformed);
+        }
+        let l2tp = raw.get(ihl + 8..).ok_or(Reject::Malformed)?;
+        if l2tp.len() < 8 || l2tp[..2] != L2TP_DATA_HEADER[..2] {
+            return Err(Reject::Malformed);
+        }
+        let session_id = u32::from_be_bytes([l2tp[4], l2tp[5], l2tp[6], l2tp[7]]);
+        if session_id != self.expect_session_id {
+            return Err(Reject::SessionMismatch(session_id));
+        }
+        let mut at = 8;
+        let cookie = l2tp.get(at..at + self.expect_cookie.len());
+        if cookie != Some(&self.expect_cookie[..]) {
+            return Err(Reject::CookieMismatch);
+        }
+        at += self.expect_cookie.len();
+        if self.sequencing {
+            let sublayer = l2tp.get(at..at + 4).ok_or(Reject::Malformed)?;
+            at += 4;
+            if sublayer[0] & L2SS_SEQUENCE != 0 {
+                let seq = u32::from_be_bytes([0, sublayer[1], sublayer[2], sublayer[3]]);
+                if let Some(expected) = self.expected_seq {
+                    // Sequence numbers wrap; anything in the half behind `expected` is late.
+                    let behind = expected.wrapping_sub(seq) & SEQ_MASK;
+                    if behind != 0 && behind <= SEQ_MASK / 2 {
+                        return Err(Reject::OutOfOrder { seq, expected });
+                    }
+                }
+                self.expected_seq = Some((seq + 1) & SEQ_MASK);
+            }
+        }
+        parse(&l2tp[at..]).map_err(|_| Reject::Malformed)
+    }
+
+    pub fn stats(&self) -> &PseudowireStats {
+        &self.stats
+    }
+
+    /// Forget the counters (end of the warm‑up phase); sequence state is kept.
+    pub fn reset(&mut self) {
+        self.stats = PseudowireStats::default();
+    }
+}
+
+/// Both directions of the pseudowire.
+#[derive(Debug)]
+pub struct Pseudowire {
+    /// Packets travelling from tun_a to tun_b.
+    pub a_to_b: Session,
+    /// Packets travelling from tun_b to tun_a.
+    pub b_to_a: Session,
+}
+
+impl Pseudowire {
+    /// Sessions between the tun_a edge router at `a` and the tun_b edge router at `b`.
+    pub fn new(cfg: &PseudowireConfig, a: Ipv4Addr, b: Ipv4Addr) -> Result<Self, String> {
+        Ok(Self {
+            a_to_b: Session::new(&cfg.a, &cfg.b, (a, b), cfg)?,
+            b_to_a: Session::new(&cfg.b, &cfg.a, (b, a), cfg)?,
+        })
+    }
+
+    /// Session for packets heading towards `destination`.
+    pub fn session_mut(&mut self, destination: Destination) -> &mut Session {
+        match destination {
+            Destination::TunB => &mut self.a_to_b,
+            Destination::TunA => &mut self.b_to_a,
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "a->b: {}; b->a: {}",
+            self.a_to_b.stats().summary(),
+            self.b_to_a.stats().summary()
+        )
+    }
+}
@@ -0,0 +1,411 @@
+// src/qos/mod.rs
+
+//! Per‑class egress queuing on links.
+//!
+//! A link with `bandwidth_mbps` set serialises packets at that rate. Its `queues` list defines
+//! traffic classes in priority order; packets are classified by DSCP and served either in
+//! strict priority (`scheduler = "strict"`, the default) or weighted‑fair
+//! (`scheduler = "wfq"`). Every direction of a link has its own set of queues. A class may set
+//! WRED thresholds to drop early, protecting the other classes before its queue is full.
+//!
+//! Forwarding is sequential, so queues are modelled virtually: each queue keeps the sizes of the
+//! packets still waiting, drained over time at the link rate, and a packet is delayed by the
+//! time needed to serve the traffic ahead of it plus its own serialisation. As with the router
+//! CPU model, the clock used for arrivals excludes the delays the queues impose, so the offered
+//! load is not throttled by the very congestion being measured.
+//!
+//! Links naming the same `pool` also share a [`Pool`] declared under `[topology.pools]`: a
+//! second set of queues at the pool's rate that every packet of every member link (in either
+//! direction) goes through after its own link's queues, like customer tails behind one
+//! backhaul. The pool delays and drops on aggregate load even when each link is within its own
+//! bandwidth.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+/// How the queues of one link direction share the bandwidth.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulerKind {
+    /// Lower‑numbered queues are always served first.
+    #[default]
+    Strict,
+    /// Non‑empty queues share the bandwidth in proportion to their weights.
+    Wfq,
+}
+
+/// One traffic class: `{ name = "voice", dscp = [46], limit = 20 }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueConfig {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// DSCP values mapped to this queue; an empty list makes it the default class.
+    #[serde(default)]
+    pub dscp: Vec<u8>,
+    /// Packets the queue can hold (including the one in service) before tail drop.
+    #[serde(default = "default_queue_limit")]
+    pub limit: u32,
+    /// Share of the bandwidth under weighted‑fair scheduling.
+    #[serde(default = "default_queue_weight")]
+    pub weight: u32,
+    /// Early random drop ahead of the tail‑drop limit.
+    #[serde(default)]
+    pub wred: Option<WredConfig>,
+}
+
+/// Weighted RED thresholds of one class, in packets of average queue depth:
+/// `wred = { min_threshold = 10, max_threshold = 30, max_drop_percent = 10 }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WredConfig {
+    pub min_threshold: f64,
+    pub max_threshold: f64,
+    /// Drop probability reached at `max_threshold`; beyond it every packet is dropped.
+    #[serde(default = "default_wred_max_drop_percent")]
+    pub max_drop_percent: f64,
+    /// Weight of the newest sample in the average queue depth.
+    #[serde(default = "default_wred_ewma_weight")]
+    pub ewma_weight: f64,
+}
+
+fn default_wred_max_drop_percent() -> f64 {
+    10.0
+}
+fn default_wred_ewma_weight() -> f64 {
+    // Exponential weighting constant 9, the usual router default.
+    1.0 / 512.0
+}
+
+impl WredConfig {
+    /// Drop probability (0..=1) for an average queue depth.
+    pub fn drop_probability(&self, avg_depth: f64) -> f64 {
+        if avg_depth < self.min_threshold {
+            0.0
+        } else if avg_depth >= self.max_threshold {
+            1.0
+        } else {
+            (avg_depth - self.min_threshold) / (self.max_threshold - self.min_threshold)
+                * self.max_drop_percent
+                / 100.0
+        }
+    }
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            name: None,
+            dscp: Vec::new(),
+            limit: default_queue_limit(),
+            weight: default_queue_weight(),
+            wred: None,
+        }
+    }
+}
+
+fn default_queue_limit() -> u32 {
+    64
+}
+fn default_queue_weight() -> u32 {
+    1
+}
+
+/// DSCP of a raw IPv4/IPv6 packet (0 if it cannot be read).
+pub fn dscp_of(raw: &[u8]) -> u8 {
+    crate::packet::traffic_class(raw).map_or(0, |tc| tc >> 2)
+}
+
+/// Counters of one queue.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueueStats {
+    pub name: String,
+    pub enqueued: u64,
+    /// Tail drops at the queue limit.
+    pub dropped: u64,
+    /// Early drops by WRED.
+    pub wred_dropped: u64,
+    pub bytes: u64,
+    /// Deepest backlog seen, in packets.
+    pub max_depth: u32,
+    /// Sum of queueing delays (excluding serialisation) of enqueued packets.
+    pub total_wait_ms: f64,
+}
+
+impl QueueStats {
+    pub fn avg_wait_ms(&self) -> f64 {
+        if self.enqueued == 0 {
+            0.0
+        } else {
+            self.total_wait_ms / self.enqueued as f64
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: enqueued={}, dropped={}, wred_dropped={}, bytes={}, max_depth={}, avg_wait={:.3} ms",
+            self.name,
+            self.enqueued,
+            self.dropped,
+            self.wred_dropped,
+            self.bytes,
+            self.max_depth,
+            self.avg_wait_ms()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ClassQueue {
+    cfg: QueueConfig,
+    /// Bits still to be sent of each waiting packet, head first.
+    backlog: VecDeque<f64>,
+    /// Moving average of the backlog in packets, for WRED.
+    avg_depth: f64,
+    /// When the last admitted packet will have been sent, on the arrival clock.
+    busy_until: Option<Instant>,
+    stats: QueueStats,
+}
+
+impl ClassQueue {
+    fn backlog_bits(&self) -> f64 {
+        self.backlog.iter().sum()
+    }
+
+    /// Serve up to `bits`; returns what was left over.
+    fn serve(&mut self, mut bits: f64) -> f64 {
+        while let Some(head) = self.backlog.front_mut() {
+            if *head <= bits + 1e-9 {
+                bits -= *head;
+                self.backlog.pop_front();
+            } else {
+                *head -= bits;
+                return 0.0;
+            }
+        }
+        bits.max(0.0)
+    }
+}
+
+/// The queues of one link direction.
+#[derive(Debug, Clone)]
+pub struct QueueSet {
+    rate_bps: f64,
+    scheduler: SchedulerKind,
+    queues: Vec<ClassQueue>,
+    last: Option<Instant>,
+    /// Total delay imposed so far; arrivals are measured on a clock that excludes it.
+    stalled: Duration,
+}
+
+impl QueueSet {
+    /// Queues for a link of `bandwidth_mbps`; without configured classes a single FIFO is used.
+    pub fn new(bandwidth_mbps: f64, scheduler: SchedulerKind, queues: &[QueueConfig]) -> Self {
+        let configs = if queues.is_empty() {
+            vec![QueueConfig::default()]
+        } else {
+            queues.to_vec()
+        };
+        Self {
+            rate_bps: bandwidth_mbps * 1_000_000.0,
+            scheduler,
+            queues: configs
+                .into_iter()
+                .enumerate()
+                .map(|(i, cfg)| ClassQueue {
+                    stats: QueueStats {
+                        name: cfg.name.clone().unwrap_or_else(|| format!("q{}", i)),
+                        ..QueueStats::default()
+                    },
+                    cfg,
+                    backlog: VecDeque::new(),
+                    avg_depth: 0.0,
+                    busy_until: None,
+                })
+                .collect(),
+            last: None,
+            stalled: Duration::ZERO,
+        }
+    }
+
+    /// Queue index for a DSCP value: the first queue listing it, else the first default class,
+    /// else the last queue.
+    pub fn classify(&self, dscp: u8) -> usize {
+        self.queues
+            .iter()
+            .position(|q| q.cfg.dscp.contains(&dscp))
+            .or_else(|| self.queues.iter().position(|q| q.cfg.dscp.is_empty()))
+            .unwrap_or(self.queues.len() - 1)
+    }
+
+    /// Send `bits` worth of backlog according to the scheduler.
+    fn drain(&mut self, mut bits: f64) {
+        match self.scheduler {
+            SchedulerKind::Strict => {
+                for q in &mut self.queues {
+                    bits = q.serve(bits);
+                }
+            }
+            SchedulerKind::Wfq => {
+                // Hand out the budget by weight among busy queues; whatever a queue cannot use
+                // is redistributed until the budget or the backlog is exhausted.
+                while bits > 1e-9 {
+                    let total: u32 = self
+                        .queues
+                        .iter()
+                        .filter(|q| !q.backlog.is_empty())
+                        .map(|q| q.cfg.weight.max(1))
+                        .sum();
+                    if total == 0 {
+                        break;
+                    }
+                    let budget = bits;
+                    bits = 0.0;
+                    for q in self.queues.iter_mut().filter(|q| !q.backlog.is_empty()) {
+                        let share = budget * q.cfg.weight.max(1) as f64 / total as f64;
+                        bits += q.serve(share);
+                    }
+                    if (budget - bits).abs() < 1e-9 {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Offer a packet of `bytes` with the given DSCP. Returns the time until it has been sent
+    /// (queueing plus serialisation), or `None` if its queue is full.
+    pub fn admit(&mut self, dscp: u8, bytes: usize) -> Option<Duration> {
+        let now = Instant::now();
+        let now = now.checked_sub(self.stalled).unwrap_or(now);
+        if let Some(last) = self.last {
+            let elapsed = now.saturating_duration_since(last).as_secs_f64();
+            self.drain(elapsed * self.rate_bps);
+        }
+        self.last = Some(now);
+        let class = self.classify(dscp);
+        let q = &mut self.queues[class];
+        let bits = bytes as f64 * 8.0;
+        if let Some(wred) = &q.cfg.wred {
+            // As in RED, an idle queue's average decays as if `m` small packets had arrived to
+            // an empty queue, `m` being the idle time over one packet's transmission time.
+            if let Some(busy_until) = q.busy_until.filter(|_| q.backlog.is_empty()) {
+                let idle = now.saturating_duration_since(busy_until).as_secs_f64();
+                let m = idle * self.rate_bps / bits.max(1.0);
+                q.avg_depth *= (1.0 - wred.ewma_weight).powf(m);
+            }
+            let depth = q.backlog.len() as f64;
+            q.avg_depth += wred.ewma_weight * (depth - q.avg_depth);
+            let p = wred.drop_probability(q.avg_depth);
+            if p > 0.0 && crate::simulation::with_rng(|rng| rng.gen_bool(p.min(1.0))) {
+                q.stats.wred_dropped += 1;
+                return None;
+            }
+        }
+        if q.backlog.len() as u32 >= q.cfg.limit {
+            q.stats.dropped += 1;
+            return None;
+        }
+        let ahead_bits = match self.scheduler {
+            SchedulerKind::Strict => self.queues[..=class].iter().map(|q| q.backlog_bits()).sum(),
+            SchedulerKind::Wfq => {
+                let own = &self.queues[class];
+                let busy: u32 = self
+                    .queues
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, q)| *i == class || !q.backlog.is_empty())
+                    .map(|(_, q)| q.cfg.weight.max(1))
+                    .sum();
+                own.backlog_bits() * busy as f64 / own.cfg.weight.max(1) as f64
+            }
+        };
+        let wait = Duration::from_secs_f64(ahead_bits / self.rate_bps);
+        let delay = wait + Duration::from_secs_f64(bits / self.rate_bps);
+        let q = &mut self.queues[class];
+        q.backlog.push_back(bits);
+        q.busy_until = Some(now + delay);
+        q.stats.enqueued += 1;
+        q.stats.bytes += bytes as u64;
+        q.stats.max_depth = q.stats.max_depth.max(q.backlog.len() as u32);
+        q.stats.total_wait_ms += wait.as_secs_f64() * 1000.0;
+        if !crate::simulation::delays_paced() {
+            self.stalled += delay;
+        }
+        Some(delay)
+    }
+
+    /// Packets in all queues.
+    pub fn depth(&self) -> usize {
+        self.queues.iter().map(|q| q.backlog.len()).sum()
+    }
+
+    pub fn stats(&self) -> Vec<QueueStats> {
+        self.queues.iter().map(|q| q.stats.clone()).collect()
+    }
+
+    pub fn reset_stats(&mut self) {
+        for q in &mut self.queues {
+            q.stats = QueueStats {
+                name: q.stats.name.clone(),
+                ..QueueStats::default()
+            };
+        }
+    }
+}
+
+/// Queues for both directions of a link.
+#[derive(Debug, Clone)]
+pub struct LinkQueues {
+    /// Packets sent from `LinkId::a` towards `LinkId::b`.
+    pub a_to_b: QueueSet,
+    /// Packets sent from `LinkId::b` towards `LinkId::a`.
+    pub b_to_a: QueueSet,
+}
+
+impl LinkQueues {
+    pub fn new(bandwidth_mbps: f64, scheduler: SchedulerKind, queues: &[QueueConfig]) -> Self {
+        let set = QueueSet::new(bandwidth_mbps, scheduler, queues);
+        Self {
+            a_to_b: set.clone(),
+            b_to_a: set,
+        }
+    }
+}
+
+/// A shared capacity pool: `[topology.pools.backhaul] bandwidth_mbps = 100`, referenced by
+/// links with `pool = "backhaul"`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolConfig {
+    pub bandwidth_mbps: f64,
+    /// Traffic classes of the pool, as for a link (a single FIFO when empty).
+    #[serde(default)]
+    pub queues: Vec<QueueConfig>,
+    #[serde(default)]
+    pub scheduler: SchedulerKind,
+}
+
+/// Queues shared by the links of one pool.
+#[derive(Debug)]
+pub struct Pool {
+    pub name: String,
+    pub queues: Mutex<QueueSet>,
+}
+
+impl Pool {
+    pub fn new(name: &str, cfg: &PoolConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            queues: Mutex::new(QueueSet::new(
+                cfg.bandwidth_mbps,
+                cfg.scheduler,
+                &cfg.queues,
+            )),
+        }
+    }
+
+    pub fn stats(&self) -> Vec<QueueStats> {
+        self.queues.lock().unwrap().stats()
+    }
+}
@@ -0,0 +1,323 @@
+// src/reassembly/mod.rs
+
+//! Reassembly of fragments at the egress edges.
+//!
+//! Packets fragmented inside the fabric (see [`crate::packet::fragment_ipv4`]) would otherwise
+//! reach the hosts as fragments. With `[reassembly]` every packet leaving the fabric is offered
+//! here first: fragments are held per datagram — keyed by source, destination, protocol and
+//! identification (RFC 791), or source, destination and the Fragment header's identification
+//! for IPv6 (RFC 8200) — and the datagram is written out once complete. Datagrams not complete
+//! within `timeout_ms`, fragments that would push the held bytes past `max_buffer_bytes`, and
+//! overlapping or oversized fragments are dropped and counted.
+
+use crate::config::ReassemblyConfig;
+use crate::packet::update_ipv4_checksum;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tokio::time::{Duration, Instant};
+use tracing::debug;
+
+const NEXT_HEADER_HOP_BY_HOP: u8 = 0;
+const NEXT_HEADER_ROUTING: u8 = 43;
+const NEXT_HEADER_FRAGMENT: u8 = 44;
+const NEXT_HEADER_DEST_OPTIONS: u8 = 60;
+
+/// Largest datagram an IP header can describe.
+const MAX_DATAGRAM: usize = 65535;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReassemblyStats {
+    /// Fragments taken in.
+    pub fragments: u64,
+    /// Datagrams put back together.
+    pub reassembled: u64,
+    /// Datagrams dropped because they were not complete in time.
+    pub timed_out: u64,
+    /// Datagrams dropped because their fragments did not fit into the buffer.
+    pub buffer_overflow: u64,
+    /// Datagrams dropped for overlapping, inconsistent or oversized fragments.
+    pub invalid: u64,
+}
+
+impl ReassemblyStats {
+    /// Datagrams that could not be reassembled.
+    pub fn failures(&self) -> u64 {
+        self.timed_out + self.buffer_overflow + self.invalid
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "fragments={}, reassembled={}, timed_out={}, buffer_overflow={}, invalid={}",
+            self.fragments, self.reassembled, self.timed_out, self.buffer_overflow, self.invalid
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    src: IpAddr,
+    dst: IpAddr,
+    protocol: u8,
+    id: u32,
+}
+
+/// One fragment as found in a packet.
+struct Fragment {
+    key: Key,
+    /// Byte offset of the data in the datagram.
+    offset: usize,
+    more: bool,
+    /// Header to put in front of the reassembled data: the IPv4 header, or the IPv6 header and
+    /// extension headers before the Fragment header, chained past it.
+    header: Vec<u8>,
+    data: Vec<u8>,
+}
+
+/// The fragment in `raw`, if it is one.
+fn fragment(raw: &[u8]) -> Option<Fragment> {
+    match raw.first()? >> 4 {
+        4 => {
+            let ihl = (raw[0] & 0x0F) as usize * 4;
+            let total = u16::from_be_bytes([*raw.get(2)?, *raw.get(3)?]) as usize;
+            if ihl < 20 || total < ihl || raw.len() < total {
+                return None;
+            }
+            let flags = u16::from_be_bytes([raw[6], raw[7]]);
+            if flags & 0x3FFF == 0 {
+                return None;
+            }
+            let src = Ipv4Addr::new(raw[12], raw[13], raw[14], raw[15]);
+            let dst = Ipv4Addr::new(raw[16], raw[17], raw[18], raw[19]);
+            Some(Fragment {
+                key: Key {
+                    src: IpAddr::V4(src),
+                    dst: IpAddr::V4(dst),
+                    protocol: raw[9],
+                    id: u16::from_be_bytes([raw[4], raw[5]]) as u32,
+                },
+                offset: (flags & 0x1FFF) as usize * 8,
+                more: flags & 0x2000 != 0,
+                header: raw[..ihl].to_vec(),
+                data: raw[ihl..total].to_vec(),
+            })
+        }
+        6 => {
+            let total = 40 + u16::from_be_bytes([*raw.get(4)?, *raw.get(5)?]) as usize;
+            if raw.len() < total {
+                return None;
+            }
+            // Walk the extension headers that may precede the Fragment header.
+            let (mut next, mut next_at, mut at) = (raw[6], 6, 40);
+            while matches!(
+                next,
+                NEXT_HEADER_HOP_BY_HOP | NEXT_HEADER_ROUTING | NEXT_HEADER_DEST_OPTIONS
+            ) {
+                let len = (*raw.get(at + 1)? as usize + 1) * 8;
+                (next, next_at, at) = (raw[at], at, at + len);
+            }
+            if next != NEXT_HEADER_FRAGMENT {
+                return None;
+            }
+            let header = raw.get(at..at + 8)?;
+            let word = u16::from_be_bytes([header[2], header[3]]);
+            let mut unfragmentable = raw[..at].to_vec();
+            unfragmentable[next_at] = header[0];
+            let src: [u8; 16] = raw[8..24].try_into().ok()?;
+            let dst: [u8; 16] = raw[24..40].try_into().ok()?;
+            Some(Fragment {
+                key: Key {
+                    src: IpAddr::V6(Ipv6Addr::from(src)),
+                    dst: IpAddr::V6(Ipv6Addr::from(dst)),
+                    protocol: NEXT_HEADER_FRAGMENT,
+                    id: u32::from_be_bytes([header[4], header[5], header[6], header[7]]),
+                },
+                offset: (word & 0xFFF8) as usize,
+                more: word & 1 != 0,
+                header: unfragmentable,
+                data: raw.get(at + 8..total)?.to_vec(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Fragments of one datagram received so far.
+#[derive(Debug)]
+struct Pending {
+    started: Instant,
+    /// Header of the first fragment, once it arrived.
+    header: Option<Vec<u8>>,
+    /// `(offset, data)`, ordered by offset.
+    parts: Vec<(usize, Vec<u8>)>,
+    /// Datagram data length, once the last fragment arrived.
+    length: Option<usize>,
+    bytes: usize,
+}
+
+impl Pending {
+    /// Add a fragment; `false` if it overlaps the ones held or contradicts the length.
+    fn add(&mut self, fragment: Fragment) -> bool {
+        let end = fragment.offset + fragment.data.len();
+        if !fragment.more {
+            if self.length.is_some_and(|l| l != end)
+                || self.parts.iter().any(|(o, d)| o + d.len() > end)
+            {
+                return false;
+            }
+            self.length = Some(end);
+        } else if self.length.is_some_and(|l| end > l) {
+            return false;
+        }
+        let idx = self.parts.partition_point(|(o, _)| *o < fragment.offset);
+        if let Some((o, d)) = self.parts.get(idx) {
+            // An exact duplicate is harmless; anything else overlapping is not (RFC 5722).
+            if *o == fragment.offset && *d == fragment.data {
+                return true;
+            }
+            if *o < end {
+                return false;
+            }
+        }
+        if idx > 0 && self.parts[idx - 1].0 + self.parts[idx - 1].1.len() > fragment.offset {
+            return false;
+        }
+        if fragment.offset == 0 {
+            self.header = Some(fragment.header);
+        }
+        self.bytes += fragment.data.len();
+        self.parts.insert(idx, (fragment.offset, fragment.data));
+        true
+    }
+
+    /// The whole datagram, if no fragment is missing.
+    fn complete(&self) -> Option<Vec<u8>> {
+        let length = self.length?;
+        let mut covered = 0;
+        for (offset, data) in &self.parts {
+            if *offset != covered {
+                return None;
+            }
+            covered += data.len();
+        }
+        if covered != length {
+            return None;
+        }
+        let mut out = self.header.clone()?;
+        for (_, data) in &self.parts {
+            out.extend_from_slice(data);
+        }
+        if out[0] >> 4 == 4 {
+            let total = out.len() as u16;
+            out[2..4].copy_from_slice(&total.to_be_bytes());
+            out[6..8].copy_from_slice(&0u16.to_be_bytes());
+            update_ipv4_checksum(&mut out);
+        } else {
+            let payload = (out.len() - 40) as u16;
+            out[4..6].copy_from_slice(&payload.to_be_bytes());
+        }
+        Some(out)
+    }
+}
+
+/// Reassembly cache shared by both egress edges.
+#[derive(Debug)]
+pub struct Reassembler {
+    timeout: Duration,
+    max_buffer_bytes: usize,
+    pending: HashMap<Key, Pending>,
+    /// Fragment data bytes held over all pending datagrams.
+    buffered: usize,
+    pub stats: ReassemblyStats,
+}
+
+impl Reassembler {
+    pub fn new(cfg: &ReassemblyConfig) -> Self {
+        Self {
+            timeout: Duration::from_millis(cfg.timeout_ms),
+            max_buffer_bytes: cfg.max_buffer_bytes,
+            pending: HashMap::new(),
+            buffered: 0,
+            stats: ReassemblyStats::default(),
+        }
+    }
+
+    /// Offer a packet leaving the fabric at `now`. Packets that are not fragments come straight
+    /// back; a fragment is held and the datagram returned once its last missing fragment came.
+    pub fn offer(&mut self, raw: Vec<u8>, now: Instant) -> Option<Vec<u8>> {
+        self.expire(now);
+        let Some(fragment) = fragment(&raw) else {
+            return Some(raw);
+        };
+        self.stats.fragments += 1;
+        let key = fragment.key;
+        if fragment.offset + fragment.data.len() + fragment.header.len() > MAX_DATAGRAM {
+            debug!(
+                "Reassembly: fragment beyond the largest datagram, dropping {:?}",
+                key
+            );
+            self.drop_datagram(&key);
+            self.stats.invalid += 1;
+            return None;
+        }
+        if self.buffered + fragment.data.len() > self.max_buffer_bytes {
+            debug!("Reassembly buffer full, dropping datagram {:?}", key);
+            self.drop_datagram(&key);
+            self.stats.buffer_overflow += 1;
+            return None;
+        }
+        let pending = self.pending.entry(key).or_insert_with(|| Pending {
+            started: now,
+            header: None,
+            parts: Vec::new(),
+            length: None,
+            bytes: 0,
+        });
+        let before = pending.bytes;
+        if !pending.add(fragment) {
+            debug!(
+                "Reassembly: inconsistent fragment, dropping datagram {:?}",
+                key
+            );
+            self.drop_datagram(&key);
+            self.stats.invalid += 1;
+            return None;
+        }
+        self.buffered += pending.bytes - before;
+        let datagram = pending.complete()?;
+        self.drop_datagram(&key);
+        self.stats.reassembled += 1;
+        Some(datagram)
+    }
+
+    /// Drop the datagrams that were not complete within the timeout.
+    pub fn expire(&mut self, now: Instant) {
+        let timeout = self.timeout;
+        let expired: Vec<Key> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| now.duration_since(p.started) >= timeout)
+            .map(|(k, _)| *k)
+            .collect();
+        for key in expired {
+            debug!("Reassembly of {:?} timed out", key);
+            self.drop_datagram(&key);
+            self.stats.timed_out += 1;
+        }
+    }
+
+    /// Datagrams waiting for fragments.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn drop_datagram(&mut self, key: &Key) {
+        if let Some(pending) = self.pending.remove(key) {
+            self.buffered -= pending.bytes;
+        }
+    }
+
+    /// Forget the counters (end of the warm‑up phase); pending datagrams are kept.
+    pub fn reset(&mut self) {
+        self.stats = ReassemblyStats::default();
+    }
+}
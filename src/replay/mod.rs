@@ -0,0 +1,458 @@
+// src/replay/mod.rs
+
+//! Replay of recorded traffic: pcap input and synthesized reverse traffic.
+//!
+//! `packet_file` and `packet_files` accept either the hex line format or a classic pcap file
+//! or pcapng capture (raw IP, Ethernet, Linux cooked or BSD loopback link types). Packets are
+//! read as they are replayed, in file order; capture timestamps are not used. Processed packets
+//! go to the output file, as hex lines or pcapng, through the bounded queue of
+//! [`sink::EgressSink`].
+//!
+//! A capture taken on one side often holds only one direction. With `[reverse_traffic]` every
+//! TCP or UDP packet that reaches the far edge earns `ratio` plausible replies — an ACK for the
+//! bytes it carried, or an empty datagram back to the sender — which are sent back across the
+//! fabric from there, so the reverse direction carries load as well.
+
+use crate::config::ReverseTrafficConfig;
+use crate::packet::{
+    parse, transport_offset, update_ipv4_checksum, update_tcp_checksum, update_udp_checksum,
+    PacketMeta,
+};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::net::IpAddr;
+
+pub mod sink;
+
+const PCAP_MAGIC_USEC: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NSEC: u32 = 0xa1b2_3c4d;
+pub(crate) const PCAPNG_MAGIC: u32 = 0x0a0d_0d0a;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+
+const SUPPORTED_LINKTYPES: [u32; 6] = [
+    LINKTYPE_NULL,
+    LINKTYPE_ETHERNET,
+    LINKTYPE_RAW,
+    LINKTYPE_LINUX_SLL,
+    LINKTYPE_IPV4,
+    LINKTYPE_IPV6,
+];
+
+pub(crate) const PCAPNG_BYTE_ORDER: u32 = 0x1a2b_3c4d;
+const PCAPNG_INTERFACE: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+const TCP_ACK: u8 = 0x10;
+
+/// Packets of a packet file with their line (hex) or record (pcap) number; packets that cannot
+/// be decoded carry the reason instead.
+pub type Packets = Box<dyn Iterator<Item = (usize, Result<Vec<u8>, String>)> + Send>;
+
+/// Open a packet file in either format, telling them apart by the pcap magic number.
+pub fn open(path: &str) -> Result<Packets, String> {
+    let mut magic = [0u8; 4];
+    let is_capture = File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok()
+        && [PCAP_MAGIC_USEC, PCAP_MAGIC_NSEC, PCAPNG_MAGIC]
+            .iter()
+            .any(|m| magic == m.to_le_bytes() || magic == m.to_be_bytes());
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    if is_capture {
+        let packets =
+            PcapReader::new(BufReader::new(file)).map_err(|e| format!("{}: {}", path, e))?;
+        return Ok(Box::new(
+            packets
+                .map(|p| p.and_then(std::convert::identity))
+                .enumerate()
+                .map(|(i, p)| (i + 1, p)),
+        ));
+    }
+    Ok(Box::new(
+        BufReader::new(file)
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                let packet = match line {
+                    Ok(line) => {
+                        let line = line.trim();
+                        if line.is_empty() || line.starts_with('#') {
+                            return None;
+                        }
+                        hex::decode(line).map_err(|e| format!("invalid hex: {}", e))
+                    }
+                    Err(e) => Err(format!("read error: {}", e)),
+                };
+                Some((idx + 1, packet))
+            }),
+    ))
+}
+
+/// IP packets of a classic pcap or pcapng file, with the link‑layer header removed.
+pub fn read_pcap(data: &[u8]) -> Result<Vec<Result<Vec<u8>, String>>, String> {
+    PcapReader::new(data)?.collect()
+}
+
+/// Largest record or block accepted, so a corrupt length cannot exhaust memory.
+const MAX_RECORD: usize = 16 << 20;
+
+enum Format {
+    Classic {
+        linktype: u32,
+    },
+    /// Link type and snap length of every interface of the current section.
+    Ng {
+        interfaces: Vec<(u32, u32)>,
+    },
+}
+
+/// Streaming reader of the IP packets of a classic pcap or pcapng capture, one record or block
+/// at a time. Every item is a packet or the reason it cannot be decoded; an outer error means
+/// the capture is truncated or corrupt, and ends the stream.
+pub struct PcapReader<R> {
+    reader: R,
+    little: bool,
+    format: Format,
+    done: bool,
+}
+
+/// Exactly `len` bytes of `reader`, or `None` at a clean end of file.
+fn read_chunk<R: Read>(reader: &mut R, len: usize) -> Result<Option<Vec<u8>>, String> {
+    let mut buf = vec![0u8; len];
+    let mut filled = 0;
+    while filled < len {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err("truncated capture".to_string()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("read error: {}", e)),
+        }
+    }
+    Ok(Some(buf))
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Read the file header (classic) or first section header (pcapng) of `reader`.
+    pub fn new(mut reader: R) -> Result<Self, String> {
+        let head = read_chunk(&mut reader, 12)?.ok_or("truncated pcap header")?;
+        let magic = [head[0], head[1], head[2], head[3]];
+        if magic == PCAPNG_MAGIC.to_le_bytes() {
+            let mut pcap = Self {
+                reader,
+                little: true,
+                format: Format::Ng {
+                    interfaces: Vec::new(),
+                },
+                done: false,
+            };
+            pcap.block(head)?;
+            return Ok(pcap);
+        }
+        let little = match magic {
+            m if m == PCAP_MAGIC_USEC.to_le_bytes() || m == PCAP_MAGIC_NSEC.to_le_bytes() => true,
+            m if m == PCAP_MAGIC_USEC.to_be_bytes() || m == PCAP_MAGIC_NSEC.to_be_bytes() => false,
+            _ => return Err("not a pcap file".to_string()),
+        };
+        let rest = read_chunk(&mut reader, 12)?.ok_or("truncated pcap header")?;
+        let linktype = word(little, &rest[8..12]) & 0x0FFF_FFFF;
+        if !SUPPORTED_LINKTYPES.contains(&linktype) {
+            return Err(format!("unsupported pcap link type {}", linktype));
+        }
+        Ok(Self {
+            reader,
+            little,
+            format: Format::Classic { linktype },
+            done: false,
+        })
+    }
+
+    /// The next classic pcap record.
+    fn record(&mut self) -> Result<Option<Result<Vec<u8>, String>>, String> {
+        let Format::Classic { linktype } = self.format else {
+            unreachable!()
+        };
+        let Some(header) = read_chunk(&mut self.reader, 16)? else {
+            return Ok(None);
+        };
+        let len = word(self.little, &header[8..12]) as usize;
+        if len > MAX_RECORD {
+            return Err(format!("bad pcap record length {}", len));
+        }
+        let frame = read_chunk(&mut self.reader, len)?.ok_or("truncated pcap record")?;
+        Ok(Some(
+            strip_link_header(linktype, &frame).map(<[u8]>::to_vec),
+        ))
+    }
+
+    /// Read the rest of the pcapng block starting with the 12 bytes `head` and return the
+    /// packet it carries, if any. Every section header sets its own byte order and starts a fresh list of
+    /// interfaces; packets captured on an interface of an unsupported link type are reported as
+    /// undecodable.
+    fn block(&mut self, head: Vec<u8>) -> Result<Option<Result<Vec<u8>, String>>, String> {
+        if head[..4] == PCAPNG_MAGIC.to_le_bytes() {
+            self.little = match [head[8], head[9], head[10], head[11]] {
+                m if m == PCAPNG_BYTE_ORDER.to_le_bytes() => true,
+                m if m == PCAPNG_BYTE_ORDER.to_be_bytes() => false,
+                _ => return Err("bad pcapng byte-order magic".to_string()),
+            };
+            self.format = Format::Ng {
+                interfaces: Vec::new(),
+            };
+        }
+        let little = self.little;
+        let kind = word(little, &head[..4]);
+        let len = word(little, &head[4..8]) as usize;
+        if !(12..=MAX_RECORD).contains(&len) || !len.is_multiple_of(4) {
+            return Err(format!("bad pcapng block length {}", len));
+        }
+        let mut block = head;
+        block.extend(read_chunk(&mut self.reader, len - 12)?.ok_or("truncated pcapng block")?);
+        let body = &block[8..len - 4];
+        let Format::Ng { interfaces } = &mut self.format else {
+            unreachable!()
+        };
+        let (interface, frame) = match kind {
+            PCAPNG_INTERFACE => {
+                let fields = body.get(..8).ok_or("truncated pcapng interface block")?;
+                let linktype = [fields[0], fields[1]];
+                let linktype = if little {
+                    u16::from_le_bytes(linktype)
+                } else {
+                    u16::from_be_bytes(linktype)
+                } as u32;
+                interfaces.push((linktype, word(little, &fields[4..8])));
+                return Ok(None);
+            }
+            PCAPNG_ENHANCED_PACKET => {
+                let fields = body.get(..20).ok_or("truncated pcapng packet block")?;
+                let interface = word(little, &fields[..4]) as usize;
+                let captured = word(little, &fields[12..16]) as usize;
+                let frame = body
+                    .get(20..20 + captured)
+                    .ok_or("truncated pcapng packet block")?;
+                (interfaces.get(interface), frame)
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                let fields = body.get(..4).ok_or("truncated pcapng packet block")?;
+                let original = word(little, fields) as usize;
+                // The captured length is implied by the snap length of the first interface.
+                let captured = match interfaces.first() {
+                    Some(&(_, snaplen)) if snaplen > 0 => original.min(snaplen as usize),
+                    _ => original,
+                };
+                let frame = body
+                    .get(4..4 + captured)
+                    .ok_or("truncated pcapng packet block")?;
+                (interfaces.first(), frame)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(pcapng_packet(interface, frame)))
+    }
+
+    /// The packet of the next pcapng block that carries one.
+    fn next_block(&mut self) -> Result<Option<Result<Vec<u8>, String>>, String> {
+        while let Some(head) = read_chunk(&mut self.reader, 12)? {
+            if let Some(packet) = self.block(head)? {
+                return Ok(Some(packet));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<Result<Vec<u8>, String>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let packet = match self.format {
+            Format::Classic { .. } => self.record(),
+            Format::Ng { .. } => self.next_block(),
+        };
+        match packet {
+            Ok(Some(packet)) => Some(Ok(packet)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Unsigned 32‑bit field in the byte order of the capture.
+fn word(little: bool, b: &[u8]) -> u32 {
+    let b = [b[0], b[1], b[2], b[3]];
+    if little {
+        u32::from_le_bytes(b)
+    } else {
+        u32::from_be_bytes(b)
+    }
+}
+
+/// The IP packet of a pcapng `frame` captured on `interface` (link type, snap length).
+fn pcapng_packet(interface: Option<&(u32, u32)>, frame: &[u8]) -> Result<Vec<u8>, String> {
+    let &(linktype, _) = interface.ok_or("packet on an undeclared pcapng interface")?;
+    if !SUPPORTED_LINKTYPES.contains(&linktype) {
+        return Err(format!("unsupported pcap link type {}", linktype));
+    }
+    strip_link_header(linktype, frame).map(<[u8]>::to_vec)
+}
+
+/// The IP packet inside a captured frame.
+fn strip_link_header(linktype: u32, frame: &[u8]) -> Result<&[u8], String> {
+    let short = || "frame too short".to_string();
+    let (ethertype, start) = match linktype {
+        LINKTYPE_ETHERNET => {
+            let mut at = 12;
+            let mut ethertype =
+                u16::from_be_bytes(frame.get(at..at + 2).ok_or_else(short)?.try_into().unwrap());
+            // Skip 802.1Q / 802.1ad tags.
+            while ethertype == 0x8100 || ethertype == 0x88a8 {
+                at += 4;
+                ethertype = u16::from_be_bytes(
+                    frame.get(at..at + 2).ok_or_else(short)?.try_into().unwrap(),
+                );
+            }
+            (Some(ethertype), at + 2)
+        }
+        LINKTYPE_LINUX_SLL => {
+            let ethertype = frame.get(14..16).ok_or_else(short)?;
+            (Some(u16::from_be_bytes([ethertype[0], ethertype[1]])), 16)
+        }
+        // The address family is in host byte order; the IP version tells the same.
+        LINKTYPE_NULL => (None, 4),
+        _ => (None, 0),
+    };
+    if let Some(ethertype) = ethertype.filter(|t| *t != 0x0800 && *t != 0x86dd) {
+        return Err(format!("not an IP frame (ethertype 0x{:04x})", ethertype));
+    }
+    frame.get(start..).ok_or_else(short)
+}
+
+/// Reply a host behind the far edge could send for `packet`, carrying `payload` zero bytes:
+/// for TCP an ACK of the segment (a SYN‑ACK for a SYN), for UDP a datagram back to the
+/// sender. Other protocols and TCP resets get none.
+pub fn reverse_packet(packet: &PacketMeta, payload: usize) -> Option<PacketMeta> {
+    let raw = &packet.raw;
+    let offset = transport_offset(raw)?;
+    let mut segment = match packet.protocol {
+        6 => {
+            let tcp = raw.get(offset..offset + 20)?;
+            let flags = tcp[13];
+            if flags & TCP_RST != 0 {
+                return None;
+            }
+            let seq = u32::from_be_bytes([tcp[4], tcp[5], tcp[6], tcp[7]]);
+            let acked = u32::from_be_bytes([tcp[8], tcp[9], tcp[10], tcp[11]]);
+            let data = raw
+                .len()
+                .saturating_sub(offset + (tcp[12] >> 4) as usize * 4);
+            let consumed =
+                data as u32 + (flags & TCP_SYN != 0) as u32 + (flags & TCP_FIN != 0) as u32;
+            let reply_flags = if flags & (TCP_SYN | TCP_ACK) == TCP_SYN {
+                TCP_SYN | TCP_ACK
+            } else {
+                TCP_ACK
+            };
+            let mut out = Vec::with_capacity(20 + payload);
+            out.extend_from_slice(&packet.dst_port.to_be_bytes());
+            out.extend_from_slice(&packet.src_port.to_be_bytes());
+            out.extend_from_slice(&(if flags & TCP_ACK != 0 { acked } else { 0 }).to_be_bytes());
+            out.extend_from_slice(&seq.wrapping_add(consumed).to_be_bytes());
+            out.extend_from_slice(&[5 << 4, reply_flags, 0xff, 0xff, 0, 0, 0, 0]);
+            out
+        }
+        17 => {
+            let mut out = Vec::with_capacity(8 + payload);
+            out.extend_from_slice(&packet.dst_port.to_be_bytes());
+            out.extend_from_slice(&packet.src_port.to_be_bytes());
+            out.extend_from_slice(&((8 + payload) as u16).to_be_bytes());
+            out.extend_from_slice(&[0, 0]);
+            out
+        }
+        _ => return None,
+    };
+    segment.resize(segment.len() + payload, 0);
+
+    let mut out = Vec::with_capacity(40 + segment.len());
+    match (packet.dst_ip, packet.src_ip) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => {
+            out.extend_from_slice(&[0x45, 0x00]);
+            out.extend_from_slice(&((20 + segment.len()) as u16).to_be_bytes());
+            out.extend_from_slice(&[0, 0, 0x40, 0, 64, packet.protocol, 0, 0]);
+            out.extend_from_slice(&src.octets());
+            out.extend_from_slice(&dst.octets());
+            update_ipv4_checksum(&mut out);
+        }
+        (IpAddr::V6(src), IpAddr::V6(dst)) => {
+            out.extend_from_slice(&[0x60, 0, 0, 0]);
+            out.extend_from_slice(&(segment.len() as u16).to_be_bytes());
+            out.extend_from_slice(&[packet.protocol, 64]);
+            out.extend_from_slice(&src.octets());
+            out.extend_from_slice(&dst.octets());
+        }
+        _ => return None,
+    }
+    out.extend_from_slice(&segment);
+    update_tcp_checksum(&mut out);
+    update_udp_checksum(&mut out);
+    parse(&out).ok()
+}
+
+/// Reply generator of `[reverse_traffic]`.
+#[derive(Debug)]
+pub struct ReverseTraffic {
+    ratio: f64,
+    payload_bytes: usize,
+    /// Replies earned but not yet sent; a reply goes out whenever it reaches one.
+    credit: f64,
+    pub generated: u64,
+    /// Replies that left the fabric at the near edge.
+    pub delivered: u64,
+}
+
+impl ReverseTraffic {
+    pub fn new(cfg: &ReverseTrafficConfig) -> Self {
+        Self {
+            ratio: cfg.ratio,
+            payload_bytes: cfg.payload_bytes,
+            credit: 0.0,
+            generated: 0,
+            delivered: 0,
+        }
+    }
+
+    /// Replies to `packet`, which has just left the fabric at the far edge.
+    pub fn respond(&mut self, packet: &PacketMeta) -> Vec<PacketMeta> {
+        let Some(reply) = reverse_packet(packet, self.payload_bytes) else {
+            return Vec::new();
+        };
+        self.credit += self.ratio;
+        let count = self.credit.floor();
+        self.credit -= count;
+        self.generated += count as u64;
+        vec![reply; count as usize]
+    }
+
+    pub fn summary(&self) -> String {
+        format!("generated={}, delivered={}", self.generated, self.delivered)
+    }
+}
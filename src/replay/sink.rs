@@ -0,0 +1,246 @@
+// src/replay/sink.rs
+
+//! Output sink of packet files.
+//!
+//! Processed packets are handed to a writer task through a bounded queue instead of being
+//! written inline. The task takes them off the queue in batches and writes them through a
+//! buffered async writer, which is flushed every `flush_ms` and when the sink is finished, so
+//! long replays do not wait on the disk line by line. When the sink is slower than the fabric (a spinning disk, a collector behind
+//! a slow link, modelled by `rate_bps`) the queue fills up and [`EgressSink::send`] waits for
+//! room, which holds back the packet file reader as well, so memory stays bounded however long
+//! the replay runs.
+//!
+//! The sink writes hex lines, or a pcapng section with one raw‑IP interface per TUN direction
+//! (`tun_a`, `tun_b`) whose packets carry the time they left the fabric, ready for Wireshark.
+//! Appending to an existing pcapng file starts a new section.
+
+use super::{
+    LINKTYPE_RAW, PCAPNG_BYTE_ORDER, PCAPNG_ENHANCED_PACKET, PCAPNG_INTERFACE, PCAPNG_MAGIC,
+};
+use crate::config::ReplayOutputConfig;
+use crate::provenance::RunMetadata;
+use crate::routing::Destination;
+use bytes::Bytes;
+use std::fs::OpenOptions;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Packets the writer takes off the queue at once.
+const BATCH: usize = 256;
+
+/// Size of the writer's buffer.
+const BUFFER: usize = 256 << 10;
+
+/// A packet leaving the fabric towards one of the TUNs.
+struct Egress {
+    at: SystemTime,
+    destination: Destination,
+    raw: Bytes,
+}
+
+/// Output file of the packet file `input`: `<input>_out.txt` for hex lines, `<input>_out.pcapng`
+/// for pcapng.
+pub fn output_path(input: &str, cfg: &ReplayOutputConfig) -> String {
+    match cfg.format.as_str() {
+        "pcapng" => format!("{}_out.pcapng", input),
+        _ => format!("{}_out.txt", input),
+    }
+}
+
+/// pcapng block of `kind` around `body`, padded to four bytes, in host byte order.
+fn block(kind: u32, body: &[u8]) -> Vec<u8> {
+    let padded = body.len().div_ceil(4) * 4;
+    let len = (12 + padded) as u32;
+    let mut out = Vec::with_capacity(len as usize);
+    out.extend_from_slice(&kind.to_ne_bytes());
+    out.extend_from_slice(&len.to_ne_bytes());
+    out.extend_from_slice(body);
+    out.resize(8 + padded, 0);
+    out.extend_from_slice(&len.to_ne_bytes());
+    out
+}
+
+/// Section header, with `comment` if set, and the interface of each TUN direction: 0 is
+/// `tun_a`, 1 is `tun_b`.
+fn pcapng_header(comment: Option<&str>) -> Vec<u8> {
+    let mut section = PCAPNG_BYTE_ORDER.to_ne_bytes().to_vec();
+    section.extend_from_slice(&1u16.to_ne_bytes());
+    section.extend_from_slice(&0u16.to_ne_bytes());
+    // Section length unknown.
+    section.extend_from_slice(&u64::MAX.to_ne_bytes());
+    if let Some(comment) = comment {
+        // opt_comment, then opt_endofopt.
+        section.extend_from_slice(&1u16.to_ne_bytes());
+        section.extend_from_slice(&(comment.len() as u16).to_ne_bytes());
+        section.extend_from_slice(comment.as_bytes());
+        section.resize(section.len().div_ceil(4) * 4, 0);
+        section.extend_from_slice(&[0, 0, 0, 0]);
+    }
+    let mut out = block(PCAPNG_MAGIC, &section);
+    for name in ["tun_a", "tun_b"] {
+        let mut interface = (LINKTYPE_RAW as u16).to_ne_bytes().to_vec();
+        interface.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        // if_name option, then opt_endofopt.
+        interface.extend_from_slice(&2u16.to_ne_bytes());
+        interface.extend_from_slice(&(name.len() as u16).to_ne_bytes());
+        interface.extend_from_slice(name.as_bytes());
+        interface.resize(interface.len().div_ceil(4) * 4, 0);
+        interface.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend(block(PCAPNG_INTERFACE, &interface));
+    }
+    out
+}
+
+/// `packet` as it goes into the file: a hex line or a pcapng block.
+fn encode(packet: &Egress, pcapng: bool) -> Vec<u8> {
+    if pcapng {
+        pcapng_packet(packet)
+    } else {
+        let mut line = hex::encode(&packet.raw).into_bytes();
+        line.push(b'\n');
+        line
+    }
+}
+
+/// Enhanced packet block of `packet` with a microsecond timestamp.
+fn pcapng_packet(packet: &Egress) -> Vec<u8> {
+    let interface: u32 = match packet.destination {
+        Destination::TunA => 0,
+        Destination::TunB => 1,
+    };
+    let micros = packet
+        .at
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0);
+    let mut body = interface.to_ne_bytes().to_vec();
+    body.extend_from_slice(&((micros >> 32) as u32).to_ne_bytes());
+    body.extend_from_slice(&(micros as u32).to_ne_bytes());
+    body.extend_from_slice(&(packet.raw.len() as u32).to_ne_bytes());
+    body.extend_from_slice(&(packet.raw.len() as u32).to_ne_bytes());
+    body.extend_from_slice(&packet.raw);
+    block(PCAPNG_ENHANCED_PACKET, &body)
+}
+
+/// Writer task feeding one output file.
+pub struct EgressSink {
+    path: String,
+    tx: mpsc::Sender<Egress>,
+    writer: JoinHandle<Result<u64, String>>,
+    /// Packets sent so far.
+    pub sent: u64,
+    /// Sends that found the queue full and had to wait for the writer.
+    pub stalls: u64,
+}
+
+impl EgressSink {
+    /// Append to `path` in the format of `cfg`, creating it if needed, starting with the stamp
+    /// of `run`: a comment line, or the comment of the pcapng section.
+    pub fn open(
+        path: &str,
+        cfg: &ReplayOutputConfig,
+        run: Option<&RunMetadata>,
+    ) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open output file {}: {}", path, e))?;
+        let (tx, mut rx) = mpsc::channel::<Egress>(cfg.queue);
+        let rate_bps = cfg.rate_bps;
+        let pcapng = cfg.format == "pcapng";
+        let owned = path.to_string();
+        let stamp = run.map(RunMetadata::comment);
+        let flush_every = Duration::from_millis(cfg.flush_ms);
+        let writer = tokio::spawn(async move {
+            let failed = |e: std::io::Error| format!("Failed to write to {}: {}", owned, e);
+            let mut out = BufWriter::with_capacity(BUFFER, tokio::fs::File::from_std(file));
+            if pcapng {
+                out.write_all(&pcapng_header(stamp.as_deref()))
+                    .await
+                    .map_err(failed)?;
+            } else if let Some(stamp) = stamp {
+                out.write_all(format!("# {}\n", stamp).as_bytes())
+                    .await
+                    .map_err(failed)?;
+            }
+            let mut flush =
+                tokio::time::interval_at(tokio::time::Instant::now() + flush_every, flush_every);
+            flush.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let (mut written, mut batch) = (0, Vec::with_capacity(BATCH));
+            loop {
+                tokio::select! {
+                    n = rx.recv_many(&mut batch, BATCH) => {
+                        if n == 0 {
+                            break;
+                        }
+                        for packet in batch.drain(..) {
+                            out.write_all(&encode(&packet, pcapng)).await.map_err(failed)?;
+                            written += 1;
+                            if let Some(rate) = rate_bps {
+                                let secs = (packet.raw.len() * 8) as f64 / rate as f64;
+                                tokio::time::sleep(Duration::from_secs_f64(secs)).await;
+                            }
+                        }
+                    }
+                    _ = flush.tick() => out.flush().await.map_err(failed)?,
+                }
+            }
+            out.flush().await.map_err(failed)?;
+            Ok(written)
+        });
+        Ok(Self {
+            path: path.to_string(),
+            tx,
+            writer,
+            sent: 0,
+            stalls: 0,
+        })
+    }
+
+    /// Queue `raw`, leaving the fabric towards `destination`, for writing; waits while the queue
+    /// is full.
+    pub async fn send(&mut self, raw: Bytes, destination: Destination) {
+        let packet = Egress {
+            at: SystemTime::now(),
+            destination,
+            raw,
+        };
+        let packet = match self.tx.try_send(packet) {
+            Ok(()) => {
+                self.sent += 1;
+                return;
+            }
+            Err(mpsc::error::TrySendError::Full(packet)) => {
+                self.stalls += 1;
+                packet
+            }
+            // The writer failed; the error is reported by `finish`.
+            Err(mpsc::error::TrySendError::Closed(_)) => return,
+        };
+        if self.tx.send(packet).await.is_ok() {
+            self.sent += 1;
+        }
+    }
+
+    /// Wait for the queued packets to be written and close the file. Returns the number of
+    /// packets written.
+    pub async fn finish(self) -> Result<u64, String> {
+        drop(self.tx);
+        let written = self
+            .writer
+            .await
+            .map_err(|e| format!("Output writer for {} failed: {}", self.path, e))??;
+        if self.stalls > 0 {
+            tracing::info!(
+                "{}: {} packets written, replay waited for the sink {} times",
+                self.path,
+                written,
+                self.stalls
+            );
+        }
+        Ok(written)
+    }
+}
@@ -0,0 +1,245 @@
+// src/report/mod.rs
+
+//! Machine‑readable exit report.
+//!
+//! With `[exit_report]` (or `--exit-report FILE`) a run ends by writing one JSON document that
+//! sums it up for a CI pipeline: the packets injected into the fabric and delivered out of it,
+//! the SLA verdicts, the alarms, the `--paranoid` violations, and why the run failed. The
+//! process exit code matches the report's: 0 when every check passed, [`EXIT_CHECKS_FAILED`]
+//! when one did not and [`EXIT_ERROR`] when the simulator could not run at all, so a pipeline
+//! can gate on the exit code and read the details from the report.
+//!
+//! An alarm only fails the run if `fail_on_alarm` names a severity and an alarm at least that
+//! severe was raised.
+
+use crate::alarms::Severity;
+use crate::config::ExitReportConfig;
+use crate::provenance::RunMetadata;
+use crate::topology::Fabric;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Exit code of a run that could not be set up or stopped with an error.
+pub const EXIT_ERROR: i32 = 1;
+
+/// Exit code of a run in which an SLA, an alarm or a `--paranoid` check failed.
+pub const EXIT_CHECKS_FAILED: i32 = 2;
+
+/// Packet totals of the routers, without the start‑up demonstration packet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PacketTotals {
+    /// Packets that entered the fabric at an edge router.
+    pub injected: u64,
+    /// Packets that reached their destination edge.
+    pub delivered: u64,
+    /// Packets lost on the links.
+    pub lost: u64,
+    /// Packets dropped by the routers, for any reason but a link's.
+    pub dropped: u64,
+}
+
+impl PacketTotals {
+    /// The totals of the router counters and drops of `fabric`.
+    pub fn of(fabric: &Fabric) -> Self {
+        let mut packets = Self {
+            dropped: fabric
+                .drop_counts
+                .iter()
+                .filter(|(reason, _)| !reason.on_link())
+                .map(|(_, count)| count)
+                .sum(),
+            ..Self::default()
+        };
+        for (_, stats) in fabric.get_statistics() {
+            packets.injected += stats.packets_injected;
+            packets.delivered += stats.packets_delivered;
+            packets.lost += stats.packets_lost;
+        }
+        packets
+    }
+
+    /// These totals less `other`.
+    pub fn less(&self, other: &Self) -> Self {
+        Self {
+            injected: self.injected.saturating_sub(other.injected),
+            delivered: self.delivered.saturating_sub(other.delivered),
+            lost: self.lost.saturating_sub(other.lost),
+            dropped: self.dropped.saturating_sub(other.dropped),
+        }
+    }
+}
+
+/// Outcome of one SLA target.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckVerdict {
+    pub metric: String,
+    pub target: f64,
+    pub actual: Option<f64>,
+    pub pass: bool,
+}
+
+/// SLA verdict of one customer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlaVerdict {
+    pub customer: String,
+    pub pass: bool,
+    pub checks: Vec<CheckVerdict>,
+}
+
+/// The alarm log of the run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlarmTotals {
+    /// Alarms raised, by severity.
+    pub raised: BTreeMap<String, usize>,
+    /// Alarms still raised at the end of the run.
+    pub active: usize,
+    /// Every alarm raised or cleared, as printed by `--stats`.
+    pub events: Vec<String>,
+}
+
+/// What a run did and whether it passed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExitReport {
+    pub passed: bool,
+    pub exit_code: i32,
+    /// Why the run failed, one entry per failed check or the error that stopped it.
+    pub failures: Vec<String>,
+    pub packets: PacketTotals,
+    pub sla: Vec<SlaVerdict>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alarms: Option<AlarmTotals>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paranoid_violations: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<RunMetadata>,
+    /// Reports of the fabrics of a multi‑fabric scenario, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fabrics: BTreeMap<String, ExitReport>,
+}
+
+impl ExitReport {
+    /// The report of the finished run on `fabric`; `fail_on_alarm` is the least severe alarm
+    /// that fails it, if alarms fail it at all.
+    pub fn of(fabric: &Fabric, fail_on_alarm: Option<Severity>) -> Self {
+        let packets = PacketTotals::of(fabric).less(&fabric.demonstration);
+        let mut failures = Vec::new();
+        let sla = fabric
+            .sla_results
+            .iter()
+            .map(|result| {
+                if !result.pass() {
+                    failures.push(format!("SLA of {} failed", result.customer));
+                }
+                SlaVerdict {
+                    customer: result.customer.clone(),
+                    pass: result.pass(),
+                    checks: result
+                        .checks
+                        .iter()
+                        .map(|check| CheckVerdict {
+                            metric: check.metric.to_string(),
+                            target: check.target,
+                            actual: check.actual,
+                            pass: check.pass,
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        let alarms = fabric.alarms.as_ref().map(|alarms| {
+            if let Some(least) = fail_on_alarm {
+                for event in alarms
+                    .events
+                    .iter()
+                    .filter(|e| e.raised && e.severity >= least)
+                {
+                    failures.push(format!("Alarm {}", event.render()));
+                }
+            }
+            AlarmTotals {
+                raised: Severity::ALL
+                    .iter()
+                    .map(|s| (s.as_str().to_string(), alarms.raised(*s)))
+                    .collect(),
+                active: alarms.active(),
+                events: alarms.events.iter().map(|e| e.render()).collect(),
+            }
+        });
+        if !fabric.paranoid_violations.is_empty() {
+            failures.push(format!(
+                "{} --paranoid violations",
+                fabric.paranoid_violations.len()
+            ));
+        }
+        let passed = failures.is_empty();
+        Self {
+            passed,
+            exit_code: if passed { 0 } else { EXIT_CHECKS_FAILED },
+            failures,
+            packets,
+            sla,
+            alarms,
+            paranoid_violations: fabric.paranoid_violations.clone(),
+            run: fabric.run.clone(),
+            fabrics: BTreeMap::new(),
+        }
+    }
+
+    /// One report of the fabrics of a multi‑fabric scenario: it passes if all of them did,
+    /// with their failures (prefixed by the fabric) and packet totals summed up.
+    pub fn combine(fabrics: BTreeMap<String, ExitReport>) -> Self {
+        let mut failures = Vec::new();
+        let mut packets = PacketTotals::default();
+        for (name, fabric) in &fabrics {
+            for failure in &fabric.failures {
+                failures.push(format!("Fabric '{}': {}", name, failure));
+            }
+            packets.injected += fabric.packets.injected;
+            packets.delivered += fabric.packets.delivered;
+            packets.lost += fabric.packets.lost;
+            packets.dropped += fabric.packets.dropped;
+        }
+        Self {
+            passed: fabrics.values().all(|f| f.passed),
+            // Passed reports have code 0, so that of any failure wins.
+            exit_code: fabrics.values().map(|f| f.exit_code).max().unwrap_or(0),
+            failures,
+            packets,
+            sla: Vec::new(),
+            alarms: None,
+            paranoid_violations: Vec::new(),
+            run: None,
+            fabrics,
+        }
+    }
+
+    /// The report of a run that stopped with `error`.
+    pub fn error(error: impl std::fmt::Display, run: Option<RunMetadata>) -> Self {
+        Self {
+            passed: false,
+            exit_code: EXIT_ERROR,
+            failures: vec![error.to_string()],
+            packets: PacketTotals::default(),
+            sla: Vec::new(),
+            alarms: None,
+            paranoid_violations: Vec::new(),
+            run,
+            fabrics: BTreeMap::new(),
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to encode exit report: {}", e))?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| format!("Failed to write exit report to {}: {}", path, e))
+    }
+}
+
+/// Least severe alarm that fails the run under `cfg`.
+pub fn fail_on_alarm(cfg: &ExitReportConfig) -> Result<Option<Severity>, String> {
+    cfg.fail_on_alarm
+        .as_deref()
+        .map(Severity::parse)
+        .transpose()
+}
@@ -0,0 +1,355 @@
+// src/routing/bgp.rs
+
+//! BGP‑like routing between AS groups.
+//!
+//! With `[bgp]` every router belongs to an autonomous system (its `asn`). Inside an AS the
+//! routes follow the shortest paths over the AS's own links (the IGP); between ASes they follow
+//! a simplified BGP. The AS of an edge router originates the route towards that edge, and every
+//! AS advertises its best route over the links to its neighbouring ASes (eBGP sessions),
+//! prepending its ASN. An AS drops routes whose AS path already contains it, and picks the best
+//! of the others by:
+//!
+//! 1. highest local preference (100 unless a `[[bgp.policy]]` sets it for the neighbour AS),
+//! 2. shortest AS path (a policy may make an AS prepend itself more often towards a neighbour),
+//! 3. lowest AS path, compared number by number, so the choice is deterministic.
+//!
+//! Each router then leaves its AS through the nearest session whose route is as good on 1. and
+//! 2. (hot‑potato routing). A policy may also deny a neighbour's routes altogether. The edge
+//! routes' costs are those of the resulting paths.
+
+use super::{cost_across, Destination, RouteEntry, RoutingTable};
+use crate::config::BgpConfig;
+use crate::topology::{Fabric, RouterId};
+use petgraph::algo::dijkstra;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::{EdgeFiltered, EdgeRef};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use tracing::warn;
+
+/// Local preference of routes no policy sets one for.
+pub const DEFAULT_LOCAL_PREF: u32 = 100;
+
+/// Rounds of advertisements after which routes that still change are left as they are.
+const MAX_ROUNDS: usize = 64;
+
+/// What AS `asn` does with the routes it exchanges with AS `neighbor_as`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BgpPolicy {
+    pub asn: u32,
+    pub neighbor_as: u32,
+    /// Local preference of the routes learned from the neighbour.
+    pub local_pref: Option<u32>,
+    /// Extra copies of `asn` prepended to the routes advertised to the neighbour.
+    pub prepend: u32,
+    /// Ignore the routes learned from the neighbour.
+    pub deny: bool,
+}
+
+/// The `[bgp]` policies.
+#[derive(Debug, Clone, Default)]
+pub struct Bgp {
+    pub policies: Vec<BgpPolicy>,
+}
+
+/// An AS's route towards an edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AsRoute {
+    local_pref: u32,
+    path: Vec<u32>,
+}
+
+impl AsRoute {
+    /// Preference by local preference and AS path length alone; lower is better.
+    fn key(&self) -> (Reverse<u32>, usize) {
+        (Reverse(self.local_pref), self.path.len())
+    }
+}
+
+/// A link between routers of different ASes, from the `local` side.
+#[derive(Debug, Clone, Copy)]
+struct Session {
+    local: NodeIndex,
+    peer: NodeIndex,
+    cost: u32,
+}
+
+fn asn(fabric: &Fabric, node: NodeIndex) -> u32 {
+    fabric.graph[node].asn.unwrap_or(0)
+}
+
+impl Bgp {
+    pub fn from_config(cfg: &BgpConfig) -> Self {
+        Self {
+            policies: cfg
+                .policies
+                .iter()
+                .map(|p| BgpPolicy {
+                    asn: p.asn,
+                    neighbor_as: p.neighbor_as,
+                    local_pref: p.local_pref,
+                    prepend: p.prepend,
+                    deny: p.deny,
+                })
+                .collect(),
+        }
+    }
+
+    fn policy(&self, asn: u32, neighbor_as: u32) -> Option<&BgpPolicy> {
+        self.policies
+            .iter()
+            .find(|p| p.asn == asn && p.neighbor_as == neighbor_as)
+    }
+
+    /// Local preference AS `asn` gives the routes from `from`, `None` if it denies them.
+    fn import(&self, asn: u32, from: u32) -> Option<u32> {
+        match self.policy(asn, from) {
+            Some(p) if p.deny => None,
+            Some(p) => Some(p.local_pref.unwrap_or(DEFAULT_LOCAL_PREF)),
+            None => Some(DEFAULT_LOCAL_PREF),
+        }
+    }
+
+    /// Replace the edge routes of `tables` with the ones BGP between the ASes of `fabric`
+    /// selects; `edges` are the edge routers.
+    pub(crate) fn apply(
+        &self,
+        fabric: &Fabric,
+        edges: [(Destination, NodeIndex); 2],
+        tables: &mut HashMap<RouterId, RoutingTable>,
+    ) {
+        let mut sessions: Vec<Session> = Vec::new();
+        for edge in fabric.graph.edge_references() {
+            let (a, b) = (edge.source(), edge.target());
+            if !edge.weight().is_up() || asn(fabric, a) == asn(fabric, b) {
+                continue;
+            }
+            for (local, peer) in [(a, b), (b, a)] {
+                sessions.push(Session {
+                    local,
+                    peer,
+                    cost: cost_across(fabric, edge.weight(), local, peer),
+                });
+            }
+        }
+        let mut igp = Igp::new(fabric);
+        for (destination, edge) in edges {
+            let best = self.select(fabric, &sessions, asn(fabric, edge));
+            let mut hops: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+            for node in fabric.graph.node_indices() {
+                let hop = if node == edge {
+                    Some(node)
+                } else if asn(fabric, node) == asn(fabric, edge) {
+                    igp.next_hop(node, edge)
+                } else {
+                    self.exit(&mut igp, &sessions, &best, node)
+                };
+                if let Some(hop) = hop {
+                    hops.insert(node, hop);
+                }
+            }
+            for node in fabric.graph.node_indices() {
+                let id = &fabric.graph[node].id;
+                let entry = match hops.get(&node) {
+                    Some(&hop) => RouteEntry {
+                        next_hop: fabric.graph[hop].id.clone(),
+                        total_cost: path_cost(fabric, &hops, node, edge),
+                    },
+                    None => RouteEntry {
+                        next_hop: id.clone(),
+                        total_cost: u32::MAX,
+                    },
+                };
+                let table = tables.entry(id.clone()).or_default();
+                match destination {
+                    Destination::TunA => table.tun_a = entry,
+                    Destination::TunB => table.tun_b = entry,
+                }
+            }
+        }
+    }
+
+    /// The route `session` brings its local AS, given every AS's `best` route.
+    fn session_route(
+        &self,
+        fabric: &Fabric,
+        session: &Session,
+        best: &BTreeMap<u32, AsRoute>,
+    ) -> Option<AsRoute> {
+        let (local, peer) = (asn(fabric, session.local), asn(fabric, session.peer));
+        let advertised = best.get(&peer)?;
+        if advertised.path.contains(&local) {
+            return None;
+        }
+        let local_pref = self.import(local, peer)?;
+        let copies = 1 + self.policy(peer, local).map_or(0, |p| p.prepend as usize);
+        let mut path = vec![peer; copies];
+        path.extend(&advertised.path);
+        Some(AsRoute { local_pref, path })
+    }
+
+    /// Every AS's best route towards an edge in AS `origin`, exchanging advertisements until
+    /// they settle.
+    fn select(&self, fabric: &Fabric, sessions: &[Session], origin: u32) -> BTreeMap<u32, AsRoute> {
+        let originated = AsRoute {
+            local_pref: u32::MAX,
+            path: Vec::new(),
+        };
+        let mut best = BTreeMap::from([(origin, originated.clone())]);
+        for _ in 0..MAX_ROUNDS {
+            let mut next = BTreeMap::from([(origin, originated.clone())]);
+            for session in sessions {
+                let local = asn(fabric, session.local);
+                if local == origin {
+                    continue;
+                }
+                let Some(route) = self.session_route(fabric, session, &best) else {
+                    continue;
+                };
+                let current = next.entry(local).or_insert_with(|| route.clone());
+                if (route.key(), &route.path) < (current.key(), &current.path) {
+                    *current = route;
+                }
+            }
+            if next == best {
+                return best;
+            }
+            best = next;
+        }
+        warn!(
+            "BGP routes towards AS {} still change after {} rounds",
+            origin, MAX_ROUNDS
+        );
+        best
+    }
+
+    /// Next hop of `node` towards the nearest session out of its AS whose route is as good as
+    /// the AS's best by local preference and path length.
+    fn exit(
+        &self,
+        igp: &mut Igp,
+        sessions: &[Session],
+        best: &BTreeMap<u32, AsRoute>,
+        node: NodeIndex,
+    ) -> Option<NodeIndex> {
+        let fabric = igp.fabric;
+        let local = asn(fabric, node);
+        let key = best.get(&local)?.key();
+        // (distance, exit router, peer, next hop); the lowest wins.
+        let mut nearest: Option<(u32, &RouterId, &RouterId, NodeIndex)> = None;
+        for session in sessions.iter().filter(|s| asn(fabric, s.local) == local) {
+            if self
+                .session_route(fabric, session, best)
+                .is_none_or(|route| route.key() != key)
+            {
+                continue;
+            }
+            let (distance, hop) = if session.local == node {
+                (0, session.peer)
+            } else {
+                match (
+                    igp.distance(node, session.local),
+                    igp.next_hop(node, session.local),
+                ) {
+                    (Some(distance), Some(hop)) => (distance, hop),
+                    _ => continue,
+                }
+            };
+            let candidate = (
+                distance.saturating_add(session.cost),
+                &fabric.graph[session.local].id,
+                &fabric.graph[session.peer].id,
+                hop,
+            );
+            if nearest
+                .as_ref()
+                .is_none_or(|n| (candidate.0, candidate.1, candidate.2) < (n.0, n.1, n.2))
+            {
+                nearest = Some(candidate);
+            }
+        }
+        nearest.map(|n| n.3)
+    }
+}
+
+/// Shortest paths inside each AS, over its own links that are up.
+struct Igp<'a> {
+    fabric: &'a Fabric,
+    /// Distances to each target from the routers of its AS.
+    distances: HashMap<NodeIndex, HashMap<NodeIndex, u32>>,
+}
+
+impl<'a> Igp<'a> {
+    fn new(fabric: &'a Fabric) -> Self {
+        Self {
+            fabric,
+            distances: HashMap::new(),
+        }
+    }
+
+    fn distances_to(&mut self, target: NodeIndex) -> &HashMap<NodeIndex, u32> {
+        let fabric = self.fabric;
+        self.distances.entry(target).or_insert_with(|| {
+            let inside = EdgeFiltered::from_fn(&fabric.graph, |e| {
+                e.weight().is_up() && asn(fabric, e.source()) == asn(fabric, e.target())
+            });
+            // Walking outwards from `target` crosses every link against the packets' direction.
+            dijkstra(&inside, target, None, |e| {
+                cost_across(fabric, e.weight(), e.target(), e.source())
+            })
+        })
+    }
+
+    fn distance(&mut self, from: NodeIndex, target: NodeIndex) -> Option<u32> {
+        self.distances_to(target).get(&from).copied()
+    }
+
+    /// Next hop from `from` towards `target` inside their AS, the lowest RouterId on ties.
+    fn next_hop(&mut self, from: NodeIndex, target: NodeIndex) -> Option<NodeIndex> {
+        let fabric = self.fabric;
+        let distances = self.distances_to(target);
+        let total = *distances.get(&from)?;
+        fabric
+            .graph
+            .edges(from)
+            .filter(|e| e.weight().is_up() && asn(fabric, e.target()) == asn(fabric, from))
+            .filter(|e| {
+                distances.get(&e.target()).is_some_and(|d| {
+                    d.saturating_add(cost_across(fabric, e.weight(), from, e.target())) == total
+                })
+            })
+            .map(|e| e.target())
+            .min_by(|a, b| fabric.graph[*a].id.cmp(&fabric.graph[*b].id))
+    }
+}
+
+/// Cost of following `hops` from `node` to `edge`, `u32::MAX` if they do not get there.
+fn path_cost(
+    fabric: &Fabric,
+    hops: &HashMap<NodeIndex, NodeIndex>,
+    node: NodeIndex,
+    edge: NodeIndex,
+) -> u32 {
+    let mut cost: u32 = 0;
+    let mut at = node;
+    for _ in 0..fabric.graph.node_count() {
+        if at == edge {
+            return cost;
+        }
+        let Some(&hop) = hops.get(&at) else {
+            return u32::MAX;
+        };
+        let crossing = fabric
+            .graph
+            .edges_connecting(at, hop)
+            .filter(|e| e.weight().is_up())
+            .map(|e| cost_across(fabric, e.weight(), at, hop))
+            .min();
+        let Some(crossing) = crossing else {
+            return u32::MAX;
+        };
+        cost = cost.saturating_add(crossing);
+        at = hop;
+    }
+    u32::MAX
+}
@@ -0,0 +1,75 @@
+// src/routing/destination_map.rs
+
+//! Egress edge per ingress edge and destination prefix.
+//!
+//! By default a packet leaves the fabric at the edge opposite to the one it entered. With
+//! `[[destination_map]]` entries traffic from one edge to several downstream sites can be sent
+//! to the right edge instead, including back out of the edge it came in on. The longest
+//! matching prefix among the entries of the packet's ingress edge wins.
+
+use crate::config::DestinationMapConfig;
+use crate::routing::Destination;
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// Edge named in the configuration.
+fn edge(name: &str) -> Result<Destination, String> {
+    match name {
+        "tun_a" => Ok(Destination::TunA),
+        "tun_b" => Ok(Destination::TunB),
+        _ => Err(format!(
+            "Invalid destination_map edge '{}': expected tun_a or tun_b",
+            name
+        )),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    ingress: Destination,
+    prefix: IpNet,
+    egress: Destination,
+}
+
+/// The parsed `[[destination_map]]` entries.
+#[derive(Debug, Clone, Default)]
+pub struct DestinationMap {
+    entries: Vec<Entry>,
+}
+
+impl DestinationMap {
+    pub fn new(cfg: &[DestinationMapConfig]) -> Result<Self, String> {
+        let entries =
+            cfg.iter()
+                .map(|e| {
+                    Ok(Entry {
+                        ingress: edge(&e.ingress)?,
+                        prefix: e.prefix.parse().map_err(|_| {
+                            format!("Invalid destination_map prefix '{}'", e.prefix)
+                        })?,
+                        egress: edge(&e.egress)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { entries })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Egress edge of a packet to `dst` that entered the fabric at the `ingress` edge.
+    pub fn egress(&self, ingress: Destination, dst: &IpAddr) -> Destination {
+        self.entries
+            .iter()
+            .filter(|e| e.ingress == ingress && e.prefix.contains(dst))
+            .max_by_key(|e| e.prefix.prefix_len())
+            .map_or(
+                match ingress {
+                    Destination::TunA => Destination::TunB,
+                    Destination::TunB => Destination::TunA,
+                },
+                |e| e.egress,
+            )
+    }
+}
@@ -0,0 +1,329 @@
+// src/routing/distance_vector.rs
+
+//! RIP‑like distance‑vector routing of the edge routes.
+//!
+//! With `[distance_vector]` the routers' routes towards tun_a and tun_b are not computed from
+//! the whole topology but learned. Every `update_interval_ms` each router advertises its
+//! distances to its neighbours; the routers' timers are spread evenly over the interval. A
+//! neighbour takes an advertised distance plus the cost of the link to the advertiser if that
+//! is better than its route, or whatever it is if the advertiser is its next hop. Distances of
+//! `infinity` or more are unreachable. With split horizon (with poisoned reverse) a router
+//! advertises a route as unreachable to the neighbour it learned it from.
+//!
+//! When a link fails, only the routers at its ends notice at once and drop the routes over it;
+//! the others learn from the following updates. There are no triggered updates, so without
+//! split horizon (and around loops even with it) routers can count to infinity, forwarding
+//! packets round in circles meanwhile. Every change is reported as a [`ConvergenceEpisode`].
+//! The `[[route]]` prefixes and static prefix routes are still computed as before.
+
+use super::link_state::{record_loop_drop, ConvergenceEpisode};
+use super::{cost_across, usable_edges, Destination, RouteEntry, RoutingTable};
+use crate::config::DistanceVectorConfig;
+use crate::drops::DropReason;
+use crate::topology::{Fabric, RouterId};
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tokio::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// A router's route towards one edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DvRoute {
+    metric: u32,
+    next_hop: RouterId,
+}
+
+/// Distance‑vector state of every router, and their update timers.
+#[derive(Debug)]
+pub struct DistanceVector {
+    interval: Duration,
+    infinity: u32,
+    split_horizon: bool,
+    started: Instant,
+    edges: [(Destination, RouterId); 2],
+    /// Neighbours of every router over the links that are up, with the cost of reaching them.
+    neighbours: BTreeMap<RouterId, Vec<(RouterId, u32)>>,
+    routes: HashMap<Destination, BTreeMap<RouterId, DvRoute>>,
+    next_update: BTreeMap<RouterId, Instant>,
+    /// Updates in a row that changed nothing; a round of them means the routes settled.
+    quiet: usize,
+    /// Routers whose routes changed in the episode in progress, and when the last one did.
+    changed: BTreeSet<RouterId>,
+    last_change: Instant,
+    pub episodes: Vec<ConvergenceEpisode>,
+}
+
+impl DistanceVector {
+    /// Start from the topology of `fabric`, with the updates exchanged until the routes
+    /// settled, as they have by the time traffic starts.
+    pub fn new(
+        cfg: &DistanceVectorConfig,
+        fabric: &Fabric,
+        tun_a: &RouterId,
+        tun_b: &RouterId,
+    ) -> Self {
+        let interval = Duration::from_secs_f64(cfg.update_interval_ms / 1000.0);
+        let now = Instant::now();
+        let neighbours = neighbours(fabric);
+        let count = neighbours.len().max(1) as u32;
+        let next_update = neighbours
+            .keys()
+            .enumerate()
+            .map(|(i, r)| (r.clone(), now + interval * (i as u32 + 1) / count))
+            .collect();
+        let mut dv = Self {
+            interval,
+            infinity: cfg.infinity,
+            split_horizon: cfg.split_horizon,
+            started: now,
+            edges: [
+                (Destination::TunA, tun_a.clone()),
+                (Destination::TunB, tun_b.clone()),
+            ],
+            neighbours,
+            routes: HashMap::new(),
+            next_update,
+            quiet: 0,
+            changed: BTreeSet::new(),
+            last_change: now,
+            episodes: Vec::new(),
+        };
+        for (destination, edge) in dv.edges.clone() {
+            let routes = dv
+                .neighbours
+                .keys()
+                .map(|r| {
+                    let route = if *r == edge {
+                        DvRoute {
+                            metric: 0,
+                            next_hop: r.clone(),
+                        }
+                    } else {
+                        dv.unreachable(r)
+                    };
+                    (r.clone(), route)
+                })
+                .collect();
+            dv.routes.insert(destination, routes);
+        }
+        let routers: Vec<RouterId> = dv.neighbours.keys().cloned().collect();
+        let mut rounds = 0;
+        loop {
+            let mut changed = false;
+            for router in &routers {
+                changed |= !dv.advertise(router).is_empty();
+            }
+            if !changed {
+                break;
+            }
+            rounds += 1;
+        }
+        debug!("Distance-vector routes settled after {} rounds", rounds);
+        dv
+    }
+
+    fn unreachable(&self, router: &RouterId) -> DvRoute {
+        DvRoute {
+            metric: self.infinity,
+            next_hop: router.clone(),
+        }
+    }
+
+    /// `router` sends its update to its neighbours. Returns the neighbours whose routes
+    /// changed.
+    fn advertise(&mut self, router: &RouterId) -> BTreeSet<RouterId> {
+        let mut changed = BTreeSet::new();
+        for (destination, edge) in &self.edges {
+            let routes = self.routes.get_mut(destination).unwrap();
+            let advertised = routes[router].clone();
+            for (neighbour, _) in &self.neighbours[router] {
+                if neighbour == edge {
+                    continue;
+                }
+                let Some(cost) = self.neighbours[neighbour]
+                    .iter()
+                    .find(|(n, _)| n == router)
+                    .map(|(_, cost)| *cost)
+                else {
+                    continue;
+                };
+                let metric = if self.split_horizon && advertised.next_hop == *neighbour {
+                    self.infinity
+                } else {
+                    advertised.metric.saturating_add(cost).min(self.infinity)
+                };
+                let current = &routes[neighbour];
+                let route = if current.next_hop == *router {
+                    if metric >= self.infinity {
+                        DvRoute {
+                            metric: self.infinity,
+                            next_hop: neighbour.clone(),
+                        }
+                    } else {
+                        DvRoute {
+                            metric,
+                            next_hop: router.clone(),
+                        }
+                    }
+                } else if metric < current.metric {
+                    DvRoute {
+                        metric,
+                        next_hop: router.clone(),
+                    }
+                } else {
+                    continue;
+                };
+                if route != *current {
+                    routes.insert(neighbour.clone(), route);
+                    changed.insert(neighbour.clone());
+                }
+            }
+        }
+        changed
+    }
+
+    /// Take in a change of `fabric`'s topology: the routers at the ends of failed links drop
+    /// the routes over them, and everybody learns the rest from the following updates.
+    pub fn topology_changed(&mut self, fabric: &Fabric) {
+        let neighbours = neighbours(fabric);
+        if neighbours == self.neighbours {
+            return;
+        }
+        self.neighbours = neighbours;
+        let now = Instant::now();
+        if self.converged() {
+            self.episodes.push(ConvergenceEpisode {
+                at_ms: now.duration_since(self.started).as_secs_f64() * 1000.0,
+                ..Default::default()
+            });
+            self.changed.clear();
+            self.last_change = now;
+            // The timers kept running while nothing changed.
+            for at in self.next_update.values_mut() {
+                while *at <= now {
+                    *at += self.interval;
+                }
+            }
+        }
+        self.quiet = 0;
+        for (destination, _) in self.edges.clone() {
+            let routes = self.routes.get_mut(&destination).unwrap();
+            for (router, neighbours) in &self.neighbours {
+                let route = &routes[router];
+                if route.next_hop != *router
+                    && !neighbours.iter().any(|(n, _)| *n == route.next_hop)
+                {
+                    let unreachable = DvRoute {
+                        metric: self.infinity,
+                        next_hop: router.clone(),
+                    };
+                    routes.insert(router.clone(), unreachable);
+                    self.changed.insert(router.clone());
+                }
+            }
+        }
+    }
+
+    fn converged(&self) -> bool {
+        self.episodes
+            .last()
+            .is_none_or(|e| e.convergence_ms.is_some())
+    }
+
+    /// When the next update is due, while routes are still changing.
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.converged() {
+            return None;
+        }
+        self.next_update.values().min().copied()
+    }
+
+    /// Send the updates that are due. Returns whether any route changed.
+    pub fn update_due(&mut self) -> bool {
+        let now = Instant::now();
+        let mut changed = false;
+        while let Some(at) = self.deadline().filter(|at| *at <= now) {
+            let router = self
+                .next_update
+                .iter()
+                .find(|(_, t)| **t == at)
+                .map(|(r, _)| r.clone())
+                .expect("router with the deadline");
+            self.next_update.insert(router.clone(), at + self.interval);
+            let routers = self.advertise(&router);
+            let episode = self.episodes.last_mut().expect("episode in progress");
+            if routers.is_empty() {
+                self.quiet += 1;
+                if self.quiet >= self.next_update.len() {
+                    episode.routers = self.changed.len();
+                    episode.converged_routers = self.changed.len();
+                    episode.convergence_ms = Some(
+                        self.last_change.duration_since(self.started).as_secs_f64() * 1000.0
+                            - episode.at_ms,
+                    );
+                    info!("Distance-vector routing converged: {}", episode.summary());
+                }
+            } else {
+                debug!(
+                    "Distance-vector update of {} changed {} router(s)",
+                    router.0,
+                    routers.len()
+                );
+                self.quiet = 0;
+                self.changed.extend(routers);
+                episode.routers = self.changed.len();
+                self.last_change = at;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Write the routes towards the edges into `tables`.
+    pub fn install(&self, tables: &mut HashMap<RouterId, RoutingTable>) {
+        for (destination, routes) in &self.routes {
+            for (router, route) in routes {
+                let table = tables.entry(router.clone()).or_default();
+                let entry = RouteEntry {
+                    next_hop: route.next_hop.clone(),
+                    total_cost: if route.metric >= self.infinity {
+                        u32::MAX
+                    } else {
+                        route.metric
+                    },
+                };
+                match destination {
+                    Destination::TunA => table.tun_a = entry,
+                    Destination::TunB => table.tun_b = entry,
+                }
+            }
+        }
+    }
+
+    /// Count a drop that happened while routes are still changing.
+    pub fn record_drop(&mut self, reason: DropReason) {
+        record_loop_drop(&mut self.episodes, reason);
+    }
+}
+
+/// Neighbours of every router of `fabric` over the links that are up, by RouterId, with the
+/// cost of crossing to them (the cheapest, over parallel links).
+fn neighbours(fabric: &Fabric) -> BTreeMap<RouterId, Vec<(RouterId, u32)>> {
+    fabric
+        .router_index
+        .iter()
+        .map(|(id, &node)| {
+            let mut neighbours: Vec<(RouterId, u32)> = usable_edges(fabric, node)
+                .map(|e| {
+                    (
+                        fabric.graph[e.target()].id.clone(),
+                        cost_across(fabric, e.weight(), node, e.target()),
+                    )
+                })
+                .collect();
+            neighbours.sort();
+            neighbours.dedup_by(|b, a| a.0 == b.0);
+            (id.clone(), neighbours)
+        })
+        .collect()
+}
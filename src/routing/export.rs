@@ -0,0 +1,337 @@
+// src/routing/export.rs
+
+//! Dumps of the routing tables, and their comparison.
+//!
+//! [`RoutingExport::new`] lists every route of every router: the single‑path routes towards
+//! tun_a and tun_b and the `[[route]]` prefixes (table `single`), the static prefix routes
+//! overriding them (`static`) and the equal‑cost next hops of the multipath tables
+//! (`multipath`), each with its next hop, cost and, for multipath, weight; static routes have
+//! their administrative distance instead of a cost. Prefix routes of a tenant have
+//! `<prefix>@<tenant>` as their destination. An export is written as JSON or CSV (quoted as in
+//! RFC 4180) and read back from either by [`RoutingExport::load`]; [`diff`] lists the routes
+//! that differ between two exports, e.g. before and after a link failure, to find out why a
+//! packet took its path. `routes export` and `routes diff` do the same from the command
+//! line, from the tables the run starts with.
+
+use super::{MultiPathTable, RoutingTable};
+use crate::topology::RouterId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Header of the CSV form.
+const CSV_HEADER: &str = "router,table,destination,next_hop,cost,weight,distance";
+
+/// One next hop of one route.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ExportedRoute {
+    pub router: String,
+    /// `single`, `static` or `multipath`.
+    pub table: String,
+    /// `tun_a`, `tun_b` or a prefix.
+    pub destination: String,
+    pub next_hop: String,
+    /// `None` if the destination is unreachable, or for static routes.
+    #[serde(default)]
+    pub cost: Option<u32>,
+    /// Share of the flows the next hop gets, for multipath routes.
+    #[serde(default)]
+    pub weight: Option<u32>,
+    /// Administrative distance, for static routes.
+    #[serde(default)]
+    pub distance: Option<u8>,
+}
+
+/// Router, table and destination of a route.
+type RouteKey<'a> = (&'a str, &'a str, &'a str);
+
+/// Next hops of a route, with their costs, weights and distances.
+type Hops<'a> = BTreeSet<(&'a str, Option<u32>, Option<u32>, Option<u8>)>;
+
+/// The routes of every router, sorted by router, table, destination and next hop.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutingExport {
+    pub routes: Vec<ExportedRoute>,
+}
+
+fn cost(total_cost: u32) -> Option<u32> {
+    (total_cost != u32::MAX).then_some(total_cost)
+}
+
+impl RoutingExport {
+    pub fn new(
+        tables: &HashMap<RouterId, RoutingTable>,
+        multipath: &HashMap<RouterId, MultiPathTable>,
+    ) -> Self {
+        let route = |router: &RouterId, table: &str, destination: String, next_hop: &RouterId| {
+            ExportedRoute {
+                router: router.0.clone(),
+                table: table.to_string(),
+                destination,
+                next_hop: next_hop.0.clone(),
+                cost: None,
+                weight: None,
+                distance: None,
+            }
+        };
+        let mut routes = Vec::new();
+        for (router, table) in tables {
+            for (destination, entry) in [("tun_a", &table.tun_a), ("tun_b", &table.tun_b)] {
+                routes.push(ExportedRoute {
+                    cost: cost(entry.total_cost),
+                    ..route(router, "single", destination.to_string(), &entry.next_hop)
+                });
+            }
+            for entry in table.fib.entries() {
+                let destination = match &entry.tenant {
+                    Some(tenant) => format!("{}@{}", entry.prefix, tenant),
+                    None => entry.prefix.to_string(),
+                };
+                routes.push(ExportedRoute {
+                    cost: cost(entry.total_cost),
+                    ..route(router, "single", destination, &entry.next_hop)
+                });
+            }
+            for pinned in &table.pinned {
+                routes.push(ExportedRoute {
+                    distance: Some(pinned.distance),
+                    ..route(
+                        router,
+                        "static",
+                        pinned.prefix.to_string(),
+                        &pinned.next_hop,
+                    )
+                });
+            }
+        }
+        for (router, table) in multipath {
+            for (destination, entries) in [("tun_a", &table.tun_a), ("tun_b", &table.tun_b)] {
+                for entry in entries {
+                    routes.push(ExportedRoute {
+                        cost: cost(entry.total_cost),
+                        weight: Some(entry.weight),
+                        ..route(
+                            router,
+                            "multipath",
+                            destination.to_string(),
+                            &entry.next_hop,
+                        )
+                    });
+                }
+            }
+        }
+        routes.sort();
+        Self { routes }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default() + "\n"
+    }
+
+    /// One line per route under [`CSV_HEADER`]; missing costs, weights and distances are
+    /// empty. Fields are quoted as in RFC 4180.
+    pub fn to_csv(&self) -> String {
+        fn optional(v: Option<impl ToString>) -> String {
+            v.map(|v| v.to_string()).unwrap_or_default()
+        }
+        let mut out = format!("{}\n", CSV_HEADER);
+        for r in &self.routes {
+            let fields = [
+                csv_field(&r.router),
+                csv_field(&r.table),
+                csv_field(&r.destination),
+                csv_field(&r.next_hop),
+                optional(r.cost),
+                optional(r.weight),
+                optional(r.distance),
+            ];
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Parse the JSON or the CSV form, whichever `text` is.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if text.trim_start().starts_with('{') {
+            return serde_json::from_str(text).map_err(|e| e.to_string());
+        }
+        fn optional<T: std::str::FromStr>(field: &str) -> Result<Option<T>, String> {
+            if field.is_empty() {
+                return Ok(None);
+            }
+            field
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("invalid number '{}'", field))
+        }
+        let mut routes = Vec::new();
+        for (line, fields) in csv_records(text)? {
+            if fields.iter().all(|f| f.trim().is_empty()) || fields.join(",") == CSV_HEADER {
+                continue;
+            }
+            let [router, table, destination, next_hop, cost, weight, distance] = &fields[..] else {
+                return Err(format!("line {}: expected {}", line, CSV_HEADER));
+            };
+            routes.push(ExportedRoute {
+                router: router.clone(),
+                table: table.clone(),
+                destination: destination.clone(),
+                next_hop: next_hop.clone(),
+                cost: optional(cost.trim()).map_err(|e| format!("line {}: {}", line, e))?,
+                weight: optional(weight.trim()).map_err(|e| format!("line {}: {}", line, e))?,
+                distance: optional(distance.trim()).map_err(|e| format!("line {}: {}", line, e))?,
+            });
+        }
+        routes.sort();
+        Ok(Self { routes })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read routing export {}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("Invalid routing export {}: {}", path, e))
+    }
+
+    /// Next hops (with cost, weight and distance) of every route, by router, table and destination.
+    fn by_route(&self) -> BTreeMap<RouteKey<'_>, Hops<'_>> {
+        let mut routes: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for r in &self.routes {
+            routes
+                .entry((r.router.as_str(), r.table.as_str(), r.destination.as_str()))
+                .or_default()
+                .insert((r.next_hop.as_str(), r.cost, r.weight, r.distance));
+        }
+        routes
+    }
+}
+
+/// `field` for a CSV line, in double quotes (doubled inside) if it holds a comma, a quote or a
+/// line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The records of the CSV `text`, each with the line it starts on. A field in double quotes
+/// may hold commas, line breaks and doubled quotes.
+fn csv_records(text: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut records = Vec::new();
+    let (mut fields, mut field) = (Vec::new(), String::new());
+    let (mut line, mut start) = (1, 1);
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                records.push((start, std::mem::take(&mut fields)));
+                line += 1;
+                start = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if quoted {
+        return Err(format!("line {}: unterminated quoted field", start));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((start, fields));
+    }
+    Ok(records)
+}
+
+/// A route whose next hops differ between two exports; `before` or `after` is empty if the
+/// route is in one export only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteChange {
+    pub router: String,
+    pub table: String,
+    pub destination: String,
+    pub before: Vec<ExportedRoute>,
+    pub after: Vec<ExportedRoute>,
+}
+
+/// The routes that differ from `a` to `b`, by router, table and destination.
+pub fn diff(a: &RoutingExport, b: &RoutingExport) -> Vec<RouteChange> {
+    let (before, after) = (a.by_route(), b.by_route());
+    let keys: BTreeSet<_> = before.keys().chain(after.keys()).copied().collect();
+    let routes = |export: &RoutingExport, (router, table, destination): RouteKey| {
+        export
+            .routes
+            .iter()
+            .filter(|r| r.router == router && r.table == table && r.destination == destination)
+            .cloned()
+            .collect()
+    };
+    keys.into_iter()
+        .filter(|key| before.get(key) != after.get(key))
+        .map(|key| RouteChange {
+            router: key.0.to_string(),
+            table: key.1.to_string(),
+            destination: key.2.to_string(),
+            before: routes(a, key),
+            after: routes(b, key),
+        })
+        .collect()
+}
+
+/// One line per changed route: `<router> <table> <destination>: <hops before> -> <hops after>`,
+/// each hop as `<next hop>(<cost>)`, `*<weight>` appended for multipath, and static hops as
+/// `<next hop>[<distance>]`.
+pub fn render_diff(changes: &[RouteChange]) -> String {
+    let hops = |routes: &[ExportedRoute]| {
+        if routes.is_empty() {
+            return "-".to_string();
+        }
+        routes
+            .iter()
+            .map(|r| {
+                let cost = r.cost.map_or("unreachable".to_string(), |c| c.to_string());
+                let mut hop = match r.table.as_str() {
+                    "static" => match r.distance {
+                        Some(distance) => format!("{}[{}]", r.next_hop, distance),
+                        None => r.next_hop.clone(),
+                    },
+                    _ => format!("{}({})", r.next_hop, cost),
+                };
+                if let Some(weight) = r.weight {
+                    hop.push_str(&format!("*{}", weight));
+                }
+                hop
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    changes
+        .iter()
+        .map(|c| {
+            format!(
+                "{} {} {}: {} -> {}\n",
+                c.router,
+                c.table,
+                c.destination,
+                hops(&c.before),
+                hops(&c.after)
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,170 @@
+// src/routing/fib.rs
+
+//! Longest‑prefix‑match FIB of prefix routes.
+//!
+//! Without `[[route]]` entries a router only knows the way to the two edges (the `tun_a` and
+//! `tun_b` entries of its [`RoutingTable`](super::RoutingTable)), and a packet heads for the
+//! edge chosen when it entered. A `[[route]]` attaches a destination prefix to a router where
+//! packets to it leave the fabric towards an edge, so several customer prefixes can sit behind
+//! different routers, and the same prefix behind several (the nearest one wins). Every router
+//! gets a FIB with the next hop and cost towards each prefix; the most specific prefix matching
+//! a packet's destination overrides the edge routes, and packets matching none are routed by
+//! the edge routes as before. A route of a tenant (see [`crate::tenant`]) is only looked up
+//! for that tenant's packets, so tenants may attach the same prefix to different routers.
+
+use super::{distances_to, link_cost, usable_edges, Destination};
+use crate::config::RouteConfig;
+use crate::topology::{Fabric, RouterId};
+use ipnet::IpNet;
+use petgraph::visit::EdgeRef;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// A prefix attached to the router it leaves the fabric at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixRoute {
+    pub prefix: IpNet,
+    /// Router the prefix is attached to.
+    pub router: RouterId,
+    /// Edge packets to the prefix leave the fabric towards.
+    pub edge: Destination,
+    /// Tenant the route belongs to; every packet sees it if `None`.
+    pub tenant: Option<String>,
+}
+
+impl PrefixRoute {
+    pub fn new(cfg: &RouteConfig) -> Result<Self, String> {
+        let prefix = cfg
+            .prefix
+            .parse::<IpNet>()
+            .map_err(|_| format!("Invalid route prefix '{}'", cfg.prefix))?;
+        let edge = match cfg.edge.as_str() {
+            "tun_a" => Destination::TunA,
+            "tun_b" => Destination::TunB,
+            other => {
+                return Err(format!(
+                    "Invalid route edge '{}' for {}: expected tun_a or tun_b",
+                    other, cfg.prefix
+                ))
+            }
+        };
+        Ok(Self {
+            prefix: prefix.trunc(),
+            router: RouterId(cfg.router.clone()),
+            edge,
+            tenant: cfg.tenant.clone(),
+        })
+    }
+}
+
+/// Route of one router towards a prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FibEntry {
+    pub prefix: IpNet,
+    /// The router itself at the router the prefix is attached to.
+    pub next_hop: RouterId,
+    pub total_cost: u32,
+    /// Router the packet leaves the fabric at.
+    pub exit: RouterId,
+    pub edge: Destination,
+    pub tenant: Option<String>,
+}
+
+/// Prefix routes of one router, most specific first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fib {
+    entries: Vec<FibEntry>,
+}
+
+impl Fib {
+    /// The most specific entry without a tenant whose prefix contains `dst`.
+    pub fn lookup(&self, dst: &IpAddr) -> Option<&FibEntry> {
+        self.lookup_for(dst, None)
+    }
+
+    /// The most specific entry whose prefix contains `dst` among those of `tenant` and those
+    /// without one; the tenant's entry wins over one without on the same prefix.
+    pub fn lookup_for(&self, dst: &IpAddr, tenant: Option<&str>) -> Option<&FibEntry> {
+        self.entries.iter().find(|e| {
+            e.prefix.contains(dst) && (e.tenant.is_none() || e.tenant.as_deref() == tenant)
+        })
+    }
+
+    pub fn entries(&self) -> &[FibEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The FIB of every router for `routes` over the links that are up. Each router reaches a
+/// prefix through its nearest attachment (the lowest router id on equal cost) and the lowest
+/// next hop among equal‑cost ones; prefixes a router cannot reach are left out. Each tenant's
+/// routes are chosen among its own.
+pub fn compute(fabric: &Fabric, routes: &[PrefixRoute]) -> HashMap<RouterId, Fib> {
+    let mut distances = HashMap::new();
+    for route in routes {
+        if let Some(&idx) = fabric.router_index.get(&route.router) {
+            distances
+                .entry(route.router.clone())
+                .or_insert_with(|| distances_to(fabric, idx));
+        }
+    }
+    let mut fibs = HashMap::new();
+    for (router_id, &node_idx) in &fabric.router_index {
+        let mut best: HashMap<(IpNet, Option<&str>), (u32, &PrefixRoute)> = HashMap::new();
+        for route in routes {
+            let Some(&cost) = distances.get(&route.router).and_then(|d| d.get(&node_idx)) else {
+                continue;
+            };
+            let key = (route.prefix, route.tenant.as_deref());
+            let better = best
+                .get(&key)
+                .is_none_or(|&(c, r)| (cost, &route.router) < (c, &r.router));
+            if better {
+                best.insert(key, (cost, route));
+            }
+        }
+        let mut entries: Vec<FibEntry> = best
+            .into_values()
+            .filter_map(|(cost, route)| {
+                let dist = &distances[&route.router];
+                let next_hop = if router_id == &route.router {
+                    router_id.clone()
+                } else {
+                    usable_edges(fabric, node_idx)
+                        .filter(|edge| {
+                            dist.get(&edge.target())
+                                .is_some_and(|d| d.saturating_add(link_cost(fabric, *edge)) == cost)
+                        })
+                        .map(|edge| fabric.graph[edge.target()].id.clone())
+                        .min()?
+                };
+                Some(FibEntry {
+                    prefix: route.prefix,
+                    next_hop,
+                    total_cost: cost,
+                    exit: route.router.clone(),
+                    edge: route.edge,
+                    tenant: route.tenant.clone(),
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.prefix
+                .prefix_len()
+                .cmp(&a.prefix.prefix_len())
+                .then(a.prefix.cmp(&b.prefix))
+                .then(b.tenant.is_some().cmp(&a.tenant.is_some()))
+                .then(a.tenant.cmp(&b.tenant))
+        });
+        fibs.insert(router_id.clone(), Fib { entries });
+    }
+    fibs
+}
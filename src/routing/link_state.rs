@@ -0,0 +1,266 @@
+// src/routing/link_state.rs
+
+//! Link‑state convergence after topology changes.
+//!
+//! Without `[link_state]` every router's tables are recomputed the moment an event changes the
+//! topology. With it, the routers that detect a change (both ends of a failed or recovered
+//! link, a drained or undrained router) flood it as an LSA: it reaches every other router over
+//! the links that are up, after each link's delay plus `flood_delay_ms` per hop, and each router
+//! runs its SPF `spf_delay_ms` later and installs its new tables. Until the last one has, routers
+//! forward by different views of the topology, so packets can loop between a router that
+//! already moved and one that has not; such a transient loop shows as packets dropped for
+//! their TTL or the hop limit. Every change is reported as a [`ConvergenceEpisode`].
+
+use super::{MultiPathTable, RoutingTable};
+use crate::config::LinkStateConfig;
+use crate::drops::DropReason;
+use crate::topology::{Fabric, LinkId, RouterId};
+use petgraph::algo::dijkstra;
+use petgraph::visit::{EdgeFiltered, EdgeRef};
+use std::collections::{BTreeSet, HashMap};
+use tokio::time::{Duration, Instant};
+use tracing::info;
+
+/// The topology as a link‑state database holds it: which links are up, which routers drained.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Lsdb {
+    links: HashMap<LinkId, bool>,
+    drained: BTreeSet<RouterId>,
+}
+
+impl Lsdb {
+    fn of(fabric: &Fabric) -> Self {
+        Self {
+            links: fabric
+                .graph
+                .edge_weights()
+                .map(|l| (l.id.clone(), l.is_up()))
+                .collect(),
+            drained: fabric
+                .graph
+                .node_weights()
+                .filter(|r| r.maintenance)
+                .map(|r| r.id.clone())
+                .collect(),
+        }
+    }
+
+    /// Routers that detect the differences from `old`.
+    fn origins(&self, old: &Lsdb) -> BTreeSet<RouterId> {
+        let mut origins: BTreeSet<RouterId> = self
+            .drained
+            .symmetric_difference(&old.drained)
+            .cloned()
+            .collect();
+        for (id, up) in &self.links {
+            if old.links.get(id) != Some(up) {
+                origins.insert(id.a.clone());
+                origins.insert(id.b.clone());
+            }
+        }
+        origins
+    }
+}
+
+/// How the routers converged after one topology change.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConvergenceEpisode {
+    /// Time of the change since the start of the run, in milliseconds.
+    pub at_ms: f64,
+    /// Routers the change reached (those cut off from its origins keep their tables).
+    pub routers: usize,
+    /// Routers that ran their SPF for this change so far.
+    pub converged_routers: usize,
+    /// Time from the change until the last router installed its tables, once it has.
+    pub convergence_ms: Option<f64>,
+    /// Packets dropped for their TTL or the hop limit meanwhile: most likely transient loops.
+    pub loop_drops: u64,
+}
+
+impl ConvergenceEpisode {
+    pub fn summary(&self) -> String {
+        let converged = match self.convergence_ms {
+            Some(ms) => format!("converged in {:.1} ms", ms),
+            None => format!(
+                "not converged ({} of {} routers)",
+                self.converged_routers, self.routers
+            ),
+        };
+        format!(
+            "change at {:.1} ms, {} routers, {}, {} loop drops",
+            self.at_ms, self.routers, converged, self.loop_drops
+        )
+    }
+}
+
+/// Tables computed for one state of the topology.
+#[derive(Debug)]
+struct Generation {
+    tables: HashMap<RouterId, RoutingTable>,
+    multipath: HashMap<RouterId, MultiPathTable>,
+}
+
+/// An SPF run due at a router.
+#[derive(Debug)]
+struct SpfRun {
+    at: Instant,
+    router: RouterId,
+    generation: u64,
+    episode: usize,
+}
+
+/// Flooding of topology changes and the routers' SPF runs.
+#[derive(Debug)]
+pub struct Convergence {
+    flood_delay_ms: f64,
+    spf_delay: Duration,
+    started: Instant,
+    /// The topology as of the last change, and its generation number.
+    lsdb: Lsdb,
+    generation: u64,
+    /// Generation of the tables each router installed; routers missing have the initial ones.
+    installed: HashMap<RouterId, u64>,
+    generations: HashMap<u64, Generation>,
+    pending: Vec<SpfRun>,
+    pub episodes: Vec<ConvergenceEpisode>,
+}
+
+impl Convergence {
+    /// Start from the topology of `fabric`, whose tables every router has installed.
+    pub fn new(cfg: &LinkStateConfig, fabric: &Fabric) -> Self {
+        Self {
+            flood_delay_ms: cfg.flood_delay_ms,
+            spf_delay: Duration::from_secs_f64(cfg.spf_delay_ms / 1000.0),
+            started: Instant::now(),
+            lsdb: Lsdb::of(fabric),
+            generation: 0,
+            installed: HashMap::new(),
+            generations: HashMap::new(),
+            pending: Vec::new(),
+            episodes: Vec::new(),
+        }
+    }
+
+    /// Flood the change of `fabric`'s topology since the last call; `tables` and `multipath`
+    /// are the tables of its new state, which each router installs when its SPF run is due.
+    pub fn flood(
+        &mut self,
+        fabric: &Fabric,
+        tables: HashMap<RouterId, RoutingTable>,
+        multipath: HashMap<RouterId, MultiPathTable>,
+    ) {
+        let lsdb = Lsdb::of(fabric);
+        let origins = lsdb.origins(&self.lsdb);
+        if origins.is_empty() {
+            return;
+        }
+        self.lsdb = lsdb;
+        self.generation += 1;
+        self.generations
+            .insert(self.generation, Generation { tables, multipath });
+        let now = Instant::now();
+        let arrivals = self.arrivals(fabric, &origins);
+        let episode = self.episodes.len();
+        self.episodes.push(ConvergenceEpisode {
+            at_ms: now.duration_since(self.started).as_secs_f64() * 1000.0,
+            routers: arrivals.len(),
+            ..Default::default()
+        });
+        info!(
+            "Topology change detected by {} router(s), flooding to {} router(s)",
+            origins.len(),
+            arrivals.len()
+        );
+        for (router, ms) in arrivals {
+            self.pending.push(SpfRun {
+                at: now + Duration::from_secs_f64(ms / 1000.0) + self.spf_delay,
+                router,
+                generation: self.generation,
+                episode,
+            });
+        }
+        self.pending.sort_by_key(|run| run.at);
+    }
+
+    /// Milliseconds until the LSA from the nearest of `origins` reaches each router.
+    fn arrivals(&self, fabric: &Fabric, origins: &BTreeSet<RouterId>) -> HashMap<RouterId, f64> {
+        let usable = EdgeFiltered::from_fn(&fabric.graph, |e| e.weight().is_up());
+        let mut arrivals: HashMap<RouterId, f64> = HashMap::new();
+        for origin in origins {
+            let Some(&idx) = fabric.router_index.get(origin) else {
+                continue;
+            };
+            let reached = dijkstra(&usable, idx, None, |e| {
+                e.weight().delay_ms(&fabric.graph[e.source()].id) as f64 + self.flood_delay_ms
+            });
+            for (node, ms) in reached {
+                let best = arrivals.entry(fabric.graph[node].id.clone()).or_insert(ms);
+                *best = best.min(ms);
+            }
+        }
+        arrivals
+    }
+
+    /// When the next SPF run is due.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.first().map(|run| run.at)
+    }
+
+    /// Install the tables of the SPF runs that are due into `tables` and `multipath`. Returns
+    /// whether any router's tables changed.
+    pub fn install_due(
+        &mut self,
+        tables: &mut HashMap<RouterId, RoutingTable>,
+        multipath: &mut HashMap<RouterId, MultiPathTable>,
+    ) -> bool {
+        let now = Instant::now();
+        let due = self.pending.partition_point(|run| run.at <= now);
+        let mut changed = false;
+        for run in self.pending.drain(..due).collect::<Vec<_>>() {
+            let installed = self.installed.get(&run.router).copied().unwrap_or(0);
+            // A router that already has a newer view keeps it.
+            if run.generation > installed {
+                let generation = &self.generations[&run.generation];
+                if let Some(table) = generation.tables.get(&run.router) {
+                    tables.insert(run.router.clone(), table.clone());
+                }
+                if let Some(table) = generation.multipath.get(&run.router) {
+                    multipath.insert(run.router.clone(), table.clone());
+                }
+                self.installed.insert(run.router.clone(), run.generation);
+                changed = true;
+            }
+            let episode = &mut self.episodes[run.episode];
+            episode.converged_routers += 1;
+            if episode.converged_routers == episode.routers {
+                // Runs installed late (between packets of a file) count from when they were due.
+                let ms = run.at.duration_since(self.started).as_secs_f64() * 1000.0 - episode.at_ms;
+                episode.convergence_ms = Some(ms);
+                info!("Routing converged: {}", episode.summary());
+            }
+        }
+        let pending = &self.pending;
+        self.generations
+            .retain(|g, _| pending.iter().any(|run| run.generation == *g));
+        changed
+    }
+
+    /// Count a drop that happened while routers still disagree.
+    pub fn record_drop(&mut self, reason: DropReason) {
+        record_loop_drop(&mut self.episodes, reason);
+    }
+}
+
+/// Count a drop for its TTL or the hop limit into the latest of `episodes` still converging.
+pub(crate) fn record_loop_drop(episodes: &mut [ConvergenceEpisode], reason: DropReason) {
+    if !matches!(reason, DropReason::TtlExpired | DropReason::HopLimit) {
+        return;
+    }
+    let converging = episodes
+        .iter_mut()
+        .rev()
+        .find(|e| e.convergence_ms.is_none());
+    if let Some(episode) = converging {
+        episode.loop_drops += 1;
+    }
+}
@@ -1,13 +1,35 @@
 // src/routing/mod.rs
 
-use crate::topology::{Fabric, RouterId};
+use crate::topology::{Fabric, Link, RouterId};
 use petgraph::algo::dijkstra;
-use petgraph::visit::EdgeRef;
+use petgraph::graph::{EdgeReference, NodeIndex};
+use petgraph::visit::{EdgeFiltered, EdgeRef};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub mod bgp;
+pub mod destination_map;
+pub mod distance_vector;
+pub mod export;
+pub mod fib;
+pub mod link_state;
 pub mod multipath;
-pub use multipath::{compute_multi_path_routing, MultiPathTable};
+pub mod static_routes;
+pub mod widest;
+pub use multipath::{compute_multi_path_routing, MultiPathEntry, MultiPathTable};
+
+/// What the routing tables optimise (`[routing] algorithm`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RoutingAlgorithm {
+    /// Lowest sum of link costs, which default to the links' delays.
+    #[default]
+    Spf,
+    /// Highest bottleneck bandwidth; see [`widest`].
+    WidestPath,
+    /// Fewest hops: every link costs 1.
+    MinHop,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Destination {
@@ -34,40 +56,180 @@ impl Default for RouteEntry {
 pub struct RoutingTable {
     pub tun_a: RouteEntry,
     pub tun_b: RouteEntry,
+    /// Routes towards the `[[route]]` prefixes, taking precedence over the edge routes.
+    #[serde(skip)]
+    pub fib: fib::Fib,
+    /// Static prefix routes, most specific first, competing with the FIB and taking precedence
+    /// over the edge routes.
+    #[serde(skip)]
+    pub pinned: Vec<static_routes::PinnedRoute>,
 }
 
 // Removed manual Default implementation for RoutingTable – now derived.
 
+impl RoutingTable {
+    /// Cost from this router to `destination`'s edge router (`u32::MAX` if unreachable).
+    pub fn distance(&self, destination: Destination) -> u32 {
+        match destination {
+            Destination::TunA => self.tun_a.total_cost,
+            Destination::TunB => self.tun_b.total_cost,
+        }
+    }
+
+    /// Next hop of the most specific static prefix route containing `dst`.
+    pub fn pinned_hop(&self, dst: &std::net::IpAddr) -> Option<&RouterId> {
+        self.pinned
+            .iter()
+            .find(|route| route.prefix.contains(dst))
+            .map(|route| &route.next_hop)
+    }
+
+    /// Next hop of the static prefix route `dst` resolves to, if it wins over the FIB route
+    /// for `tenant`: the more specific prefix wins, then the lower administrative distance,
+    /// where FIB routes are connected (0) at their attachment and `computed_distance`
+    /// elsewhere.
+    pub fn static_hop(
+        &self,
+        dst: &std::net::IpAddr,
+        tenant: Option<&str>,
+        computed_distance: u8,
+    ) -> Option<&RouterId> {
+        let pinned = self
+            .pinned
+            .iter()
+            .find(|route| route.prefix.contains(dst))?;
+        let Some(fib) = self.fib.lookup_for(dst, tenant) else {
+            return Some(&pinned.next_hop);
+        };
+        let fib_distance = if fib.total_cost == 0 {
+            0
+        } else {
+            computed_distance
+        };
+        let key = |len: u8, distance: u8| (std::cmp::Reverse(len), distance);
+        (key(pinned.prefix.prefix_len(), pinned.distance)
+            <= key(fib.prefix.prefix_len(), fib_distance))
+        .then_some(&pinned.next_hop)
+    }
+}
+
+/// Cost of the links of a router in maintenance (OSPF's MaxLinkMetric), so that paths cross it
+/// only when there is no other way.
+pub const MAINTENANCE_COST: u32 = 0xFFFF;
+
+/// Routing cost of crossing a link from `edge.source()` to `edge.target()`: the direction's
+/// configured or bandwidth‑derived cost, else its delay (at least 1), or [`MAINTENANCE_COST`]
+/// if a router at either end is in maintenance. With [`RoutingAlgorithm::MinHop`] it is 1.
+pub fn link_cost(fabric: &Fabric, edge: EdgeReference<'_, Link>) -> u32 {
+    cost_across(fabric, edge.weight(), edge.source(), edge.target())
+}
+
+/// [`link_cost`] of crossing `link` from `from` to `to`.
+pub(crate) fn cost_across(fabric: &Fabric, link: &Link, from: NodeIndex, to: NodeIndex) -> u32 {
+    if fabric.graph[from].maintenance || fabric.graph[to].maintenance {
+        return MAINTENANCE_COST;
+    }
+    if fabric.routing_algorithm == RoutingAlgorithm::MinHop {
+        return 1;
+    }
+    let from = &fabric.graph[from].id;
+    link.cost(from)
+        .unwrap_or_else(|| link.delay_ms(from))
+        .max(1)
+}
+
+/// Links of `node` routing may use: those that are up.
+pub(crate) fn usable_edges(
+    fabric: &Fabric,
+    node: NodeIndex,
+) -> impl Iterator<Item = EdgeReference<'_, Link>> {
+    fabric.graph.edges(node).filter(|e| e.weight().is_up())
+}
+
+/// Shortest distances to `dst` over the links that are up. Links are directed: a path towards
+/// `dst` pays each link's cost in the direction it is crossed.
+pub(crate) fn distances_to(fabric: &Fabric, dst: NodeIndex) -> HashMap<NodeIndex, u32> {
+    let usable = EdgeFiltered::from_fn(&fabric.graph, |e| e.weight().is_up());
+    // Searching outwards from `dst` walks every link against the direction packets cross it.
+    dijkstra(&usable, dst, None, |e| {
+        cost_across(fabric, e.weight(), e.target(), e.source())
+    })
+}
+
+/// Stable FNV‑1a hash used for seeded tie‑breaking (identical on every platform and run).
+fn tie_break_hash(seed: u64, router: &RouterId, destination: Destination) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325 ^ seed;
+    let tag: &[u8] = match destination {
+        Destination::TunA => b"tun_a",
+        Destination::TunB => b"tun_b",
+    };
+    for byte in router.0.as_bytes().iter().chain(tag) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Choose one of several equal‑cost next hops. Candidates are ordered by RouterId; without a
+/// seed the lowest one wins, with a seed the choice is a stable hash of (seed, router,
+/// destination), so different seeds spread routers over different equal‑cost paths.
+fn break_tie(
+    mut candidates: Vec<RouterId>,
+    router: &RouterId,
+    destination: Destination,
+    seed: Option<u64>,
+) -> Option<RouterId> {
+    if candidates.is_empty() {
+        return None;
+    }
+    candidates.sort();
+    candidates.dedup();
+    let idx = match seed {
+        Some(seed) => {
+            (tie_break_hash(seed, router, destination) % candidates.len() as u64) as usize
+        }
+        None => 0,
+    };
+    Some(candidates.swap_remove(idx))
+}
+
 /// Compute routing tables for all routers in the fabric.
 /// Returns a map from RouterId to its RoutingTable.
+/// Equal‑cost next hops are resolved towards the lowest RouterId.
 pub fn compute_routing(
     fabric: &Fabric,
     ingress_a: RouterId,
     ingress_b: RouterId,
 ) -> HashMap<RouterId, RoutingTable> {
-    // Helper to compute distances from a source router using Dijkstra.
-    fn distances_from(
-        fabric: &Fabric,
-        src: &RouterId,
-    ) -> HashMap<petgraph::prelude::NodeIndex, u32> {
-        let src_idx = fabric
+    compute_routing_seeded(fabric, ingress_a, ingress_b, None)
+}
+
+/// Like [`compute_routing`], with an optional seed selecting among equal‑cost next hops.
+pub fn compute_routing_seeded(
+    fabric: &Fabric,
+    ingress_a: RouterId,
+    ingress_b: RouterId,
+    tie_break_seed: Option<u64>,
+) -> HashMap<RouterId, RoutingTable> {
+    // Helper to compute distances to an edge router using Dijkstra.
+    fn distances_to(fabric: &Fabric, dst: &RouterId) -> HashMap<petgraph::prelude::NodeIndex, u32> {
+        let dst_idx = fabric
             .router_index
-            .get(src)
+            .get(dst)
             .expect("ingress router missing in fabric");
-        dijkstra(&fabric.graph, *src_idx, None, |e| {
-            let w = e.weight().cfg.delay_ms;
-            if w == 0 {
-                1
-            } else {
-                w
-            }
-        })
+        self::distances_to(fabric, *dst_idx)
     }
 
-    let dist_a = distances_from(fabric, &ingress_a);
-    let dist_b = distances_from(fabric, &ingress_b);
+    let dist_a = distances_to(fabric, &ingress_a);
+    let dist_b = distances_to(fabric, &ingress_b);
 
+    let mut fibs = fib::compute(fabric, &fabric.routes);
     let mut tables = HashMap::new();
+    let edge_distances = HashMap::from([(Destination::TunA, dist_a), (Destination::TunB, dist_b)]);
+    let (dist_a, dist_b) = (
+        &edge_distances[&Destination::TunA],
+        &edge_distances[&Destination::TunB],
+    );
 
     for (router_id, &node_idx) in &fabric.router_index {
         // ----- TUN A -----
@@ -75,20 +237,19 @@ pub fn compute_routing(
         let next_hop_a = if router_id == &ingress_a {
             router_id.clone()
         } else {
-            let mut chosen: Option<RouterId> = None;
-            for edge in fabric.graph.edges(node_idx) {
+            let mut candidates = Vec::new();
+            for edge in usable_edges(fabric, node_idx) {
                 let neighbor_idx = edge.target();
-                let w = edge.weight().cfg.delay_ms;
                 let neighbor_dist = *dist_a.get(&neighbor_idx).unwrap_or(&u32::MAX);
                 if neighbor_dist != u32::MAX
                     && total_cost_a != u32::MAX
-                    && neighbor_dist + if w == 0 { 1 } else { w } == total_cost_a
+                    && neighbor_dist + link_cost(fabric, edge) == total_cost_a
                 {
-                    chosen = Some(fabric.graph[neighbor_idx].id.clone());
-                    break;
+                    candidates.push(fabric.graph[neighbor_idx].id.clone());
                 }
             }
-            chosen.unwrap_or_else(|| router_id.clone())
+            break_tie(candidates, router_id, Destination::TunA, tie_break_seed)
+                .unwrap_or_else(|| router_id.clone())
         };
 
         // ----- TUN B -----
@@ -96,36 +257,80 @@ pub fn compute_routing(
         let next_hop_b = if router_id == &ingress_b {
             router_id.clone()
         } else {
-            let mut chosen: Option<RouterId> = None;
-            for edge in fabric.graph.edges(node_idx) {
+            let mut candidates = Vec::new();
+            for edge in usable_edges(fabric, node_idx) {
                 let neighbor_idx = edge.target();
-                let w = edge.weight().cfg.delay_ms;
                 let neighbor_dist = *dist_b.get(&neighbor_idx).unwrap_or(&u32::MAX);
                 if neighbor_dist != u32::MAX
                     && total_cost_b != u32::MAX
-                    && neighbor_dist + if w == 0 { 1 } else { w } == total_cost_b
+                    && neighbor_dist + link_cost(fabric, edge) == total_cost_b
                 {
-                    chosen = Some(fabric.graph[neighbor_idx].id.clone());
-                    break;
+                    candidates.push(fabric.graph[neighbor_idx].id.clone());
                 }
             }
-            chosen.unwrap_or_else(|| router_id.clone())
+            break_tie(candidates, router_id, Destination::TunB, tie_break_seed)
+                .unwrap_or_else(|| router_id.clone())
         };
 
-        tables.insert(
-            router_id.clone(),
-            RoutingTable {
-                tun_a: RouteEntry {
-                    next_hop: next_hop_a,
-                    total_cost: total_cost_a,
-                },
-                tun_b: RouteEntry {
-                    next_hop: next_hop_b,
-                    total_cost: total_cost_b,
-                },
+        let table = RoutingTable {
+            tun_a: RouteEntry {
+                next_hop: next_hop_a,
+                total_cost: total_cost_a,
+            },
+            tun_b: RouteEntry {
+                next_hop: next_hop_b,
+                total_cost: total_cost_b,
             },
-        );
+            fib: fibs.remove(router_id).unwrap_or_default(),
+            pinned: Vec::new(),
+        };
+        tables.insert(router_id.clone(), table);
+    }
+    let edges = [
+        (Destination::TunA, fabric.router_index[&ingress_a]),
+        (Destination::TunB, fabric.router_index[&ingress_b]),
+    ];
+    if let Some(bgp) = &fabric.bgp {
+        bgp.apply(fabric, edges, &mut tables);
+    }
+    if fabric.routing_algorithm == RoutingAlgorithm::WidestPath {
+        widest::apply(fabric, edges, tie_break_seed, &mut tables);
+    }
+    for (router_id, &node_idx) in &fabric.router_index {
+        if let Some(table) = tables.get_mut(router_id) {
+            static_routes::apply(fabric, node_idx, table, &edge_distances);
+        }
     }
 
     tables
 }
+
+/// Next hop from every router towards `target` along the same shortest paths as
+/// [`compute_routing`] (lowest RouterId on ties). `target` itself and routers that cannot reach
+/// it are left out.
+pub fn next_hops_towards(fabric: &Fabric, target: &RouterId) -> HashMap<RouterId, RouterId> {
+    let Some(&target_idx) = fabric.router_index.get(target) else {
+        return HashMap::new();
+    };
+    let dist = distances_to(fabric, target_idx);
+    let mut hops = HashMap::new();
+    for (router_id, &node_idx) in &fabric.router_index {
+        let Some(&total) = dist.get(&node_idx) else {
+            continue;
+        };
+        if node_idx == target_idx {
+            continue;
+        }
+        let best = usable_edges(fabric, node_idx)
+            .filter(|edge| {
+                dist.get(&edge.target())
+                    .is_some_and(|d| d + link_cost(fabric, *edge) == total)
+            })
+            .map(|edge| fabric.graph[edge.target()].id.clone())
+            .min();
+        if let Some(hop) = best {
+            hops.insert(router_id.clone(), hop);
+        }
+    }
+    hops
+}
@@ -1,17 +1,62 @@
 // src/routing/multipath.rs
 
-use crate::routing::RouteEntry;
+use crate::routing::{cost_across, usable_edges, Destination};
 use crate::topology::{Fabric, RouterId};
-use petgraph::algo::dijkstra;
 use petgraph::visit::EdgeRef;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// One of the equal‑cost next hops towards a destination.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiPathEntry {
+    pub next_hop: RouterId,
+    pub total_cost: u32,
+    /// Share of the load‑balanced flows the next hop gets, relative to the others (WCMP); see
+    /// [`crate::forwarding::EcmpHash::weight`].
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
 /// Multi‑path routing table containing all equal‑cost next hops for each destination.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct MultiPathTable {
-    pub tun_a: Vec<RouteEntry>,
-    pub tun_b: Vec<RouteEntry>,
+    pub tun_a: Vec<MultiPathEntry>,
+    pub tun_b: Vec<MultiPathEntry>,
+}
+
+impl MultiPathTable {
+    /// Weight of `next_hop` among the next hops towards `destination`, 1 if it is not one.
+    pub fn weight(&self, destination: Destination, next_hop: &RouterId) -> u32 {
+        let entries = match destination {
+            Destination::TunA => &self.tun_a,
+            Destination::TunB => &self.tun_b,
+        };
+        entries
+            .iter()
+            .find(|e| e.next_hop == *next_hop)
+            .map_or(1, |e| e.weight)
+    }
+
+    /// Cost from `router`, whose table this is, to `destination`'s edge router: zero at the edge
+    /// router itself, otherwise the cheapest entry (`u32::MAX` without one).
+    pub fn distance(&self, router: &RouterId, destination: Destination) -> u32 {
+        let entries = match destination {
+            Destination::TunA => &self.tun_a,
+            Destination::TunB => &self.tun_b,
+        };
+        if entries.iter().any(|e| e.next_hop == *router) {
+            return 0;
+        }
+        entries
+            .iter()
+            .map(|e| e.total_cost)
+            .min()
+            .unwrap_or(u32::MAX)
+    }
 }
 
 /// Compute multi‑path routing tables for all routers.
@@ -20,54 +65,46 @@ pub fn compute_multi_path_routing(
     ingress_a: RouterId,
     ingress_b: RouterId,
 ) -> HashMap<RouterId, MultiPathTable> {
-    // Helper to compute distances from a source router using Dijkstra.
-    fn distances_from(
-        fabric: &Fabric,
-        src: &RouterId,
-    ) -> HashMap<petgraph::prelude::NodeIndex, u32> {
-        let src_idx = fabric
+    // Helper to compute distances to an edge router using Dijkstra.
+    fn distances_to(fabric: &Fabric, dst: &RouterId) -> HashMap<petgraph::prelude::NodeIndex, u32> {
+        let dst_idx = fabric
             .router_index
-            .get(src)
+            .get(dst)
             .expect("ingress router missing in fabric");
-        dijkstra(&fabric.graph, *src_idx, None, |e| {
-            let w = e.weight().cfg.delay_ms;
-            if w == 0 {
-                1
-            } else {
-                w
-            }
-        })
+        crate::routing::distances_to(fabric, *dst_idx)
     }
 
-    let dist_a = distances_from(fabric, &ingress_a);
-    let dist_b = distances_from(fabric, &ingress_b);
+    let dist_a = distances_to(fabric, &ingress_a);
+    let dist_b = distances_to(fabric, &ingress_b);
     let mut tables: HashMap<RouterId, MultiPathTable> = HashMap::new();
 
     for (router_id, &node_idx) in &fabric.router_index {
         // Tun A entries (traffic from ingress A towards B) use distances from ingress B.
         let mut entries_a = Vec::new();
         let mut min_cost_a = u32::MAX;
-        for edge in fabric.graph.edges(node_idx) {
+        for edge in usable_edges(fabric, node_idx) {
             let neighbor_idx = if edge.source() == node_idx {
                 edge.target()
             } else {
                 edge.source()
             };
-            let w = edge.weight().cfg.delay_ms;
             if let Some(&neighbor_dist) = dist_b.get(&neighbor_idx) {
                 if neighbor_dist != u32::MAX {
-                    let cost = neighbor_dist + if w == 0 { 1 } else { w };
+                    let cost =
+                        neighbor_dist + cost_across(fabric, edge.weight(), node_idx, neighbor_idx);
                     if cost < min_cost_a {
                         min_cost_a = cost;
                         entries_a.clear();
-                        entries_a.push(RouteEntry {
+                        entries_a.push(MultiPathEntry {
                             next_hop: fabric.graph[neighbor_idx].id.clone(),
                             total_cost: cost,
+                            weight: fabric.ecmp_hash.weight(edge.weight()),
                         });
                     } else if cost == min_cost_a {
-                        entries_a.push(RouteEntry {
+                        entries_a.push(MultiPathEntry {
                             next_hop: fabric.graph[neighbor_idx].id.clone(),
                             total_cost: cost,
+                            weight: fabric.ecmp_hash.weight(edge.weight()),
                         });
                     }
                 }
@@ -76,32 +113,37 @@ pub fn compute_multi_path_routing(
         // Tun B entries (traffic from ingress B towards A) use distances from ingress A.
         let mut entries_b = Vec::new();
         let mut min_cost_b = u32::MAX;
-        for edge in fabric.graph.edges(node_idx) {
+        for edge in usable_edges(fabric, node_idx) {
             let neighbor_idx = if edge.source() == node_idx {
                 edge.target()
             } else {
                 edge.source()
             };
-            let w = edge.weight().cfg.delay_ms;
             if let Some(&neighbor_dist) = dist_a.get(&neighbor_idx) {
                 if neighbor_dist != u32::MAX {
-                    let cost = neighbor_dist + if w == 0 { 1 } else { w };
+                    let cost =
+                        neighbor_dist + cost_across(fabric, edge.weight(), node_idx, neighbor_idx);
                     if cost < min_cost_b {
                         min_cost_b = cost;
                         entries_b.clear();
-                        entries_b.push(RouteEntry {
+                        entries_b.push(MultiPathEntry {
                             next_hop: fabric.graph[neighbor_idx].id.clone(),
                             total_cost: cost,
+                            weight: fabric.ecmp_hash.weight(edge.weight()),
                         });
                     } else if cost == min_cost_b {
-                        entries_b.push(RouteEntry {
+                        entries_b.push(MultiPathEntry {
                             next_hop: fabric.graph[neighbor_idx].id.clone(),
                             total_cost: cost,
+                            weight: fabric.ecmp_hash.weight(edge.weight()),
                         });
                     }
                 }
             }
         }
+        // Keep next hops in RouterId order so that hashing over them is stable across runs.
+        entries_a.sort_by(|x, y| x.next_hop.cmp(&y.next_hop));
+        entries_b.sort_by(|x, y| x.next_hop.cmp(&y.next_hop));
         tables.insert(
             router_id.clone(),
             MultiPathTable {
@@ -0,0 +1,192 @@
+// src/routing/static_routes.rs
+
+//! Static routes pinned per router.
+//!
+//! `[topology.routers.<id>.static_routes]` maps a destination to the adjacent router packets
+//! for it are handed to, either as the router's name or as `{ via = "...", distance = N }`. A
+//! destination is an edge (`tun_a`, `tun_b`), competing with the router's computed route
+//! towards it, or a prefix, competing with the `[[route]]` FIB for the packets it contains.
+//! Policy‑based routing and SRv6 segments still come first. A static route whose link is down
+//! is ignored and the computed route is used, as a router withdraws a static route when its
+//! interface goes down.
+//!
+//! Routes from the different sources are resolved as real routers do: the most specific
+//! prefix wins, and among routes for the same destination the lowest administrative distance.
+//! Connected routes (the router is the edge or the prefix's attachment) have distance 0,
+//! static routes [`STATIC_DISTANCE`] unless they set their own, and computed routes
+//! `[routing] distance` ([`COMPUTED_DISTANCE`] by default); a static route wins a tie with a
+//! computed one. A static route with a higher distance than the computed routes is a floating
+//! static: it is only used once the computed route is gone.
+
+use super::{cost_across, Destination, RoutingTable};
+use crate::topology::{Fabric, RouterId};
+use ipnet::IpNet;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Administrative distance of static routes that do not set one.
+pub const STATIC_DISTANCE: u8 = 1;
+
+/// Administrative distance of the computed routes unless `[routing] distance` sets it.
+pub const COMPUTED_DISTANCE: u8 = 110;
+
+/// One entry of a router's `static_routes`: the next hop router, or a table with its
+/// administrative distance too.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum StaticRouteConfig {
+    Via(String),
+    Route {
+        via: String,
+        #[serde(default)]
+        distance: Option<u8>, // administrative distance (default 1)
+    },
+}
+
+impl StaticRouteConfig {
+    /// The next hop router.
+    pub fn via(&self) -> &str {
+        match self {
+            Self::Via(via) | Self::Route { via, .. } => via,
+        }
+    }
+
+    /// The administrative distance.
+    pub fn distance(&self) -> u8 {
+        match self {
+            Self::Route {
+                distance: Some(distance),
+                ..
+            } => *distance,
+            _ => STATIC_DISTANCE,
+        }
+    }
+}
+
+/// What a static route is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaticTarget {
+    Edge(Destination),
+    Prefix(IpNet),
+}
+
+/// A next hop pinned for a destination at one router.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticRoute {
+    pub target: StaticTarget,
+    pub next_hop: RouterId,
+    /// Administrative distance; the lowest wins among routes for the same destination.
+    pub distance: u8,
+}
+
+/// A static prefix route installed in a router's table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedRoute {
+    pub prefix: IpNet,
+    pub next_hop: RouterId,
+    pub distance: u8,
+}
+
+impl StaticRoute {
+    /// Parse one `destination = ...` entry of a router's `static_routes`.
+    pub fn new(destination: &str, cfg: &StaticRouteConfig) -> Result<Self, String> {
+        if cfg.distance() == 0 {
+            return Err(format!(
+                "Static route distance for {} must be between 1 and 255",
+                destination
+            ));
+        }
+        let target = match destination {
+            "tun_a" => StaticTarget::Edge(Destination::TunA),
+            "tun_b" => StaticTarget::Edge(Destination::TunB),
+            prefix => StaticTarget::Prefix(
+                prefix
+                    .parse::<IpNet>()
+                    .map_err(|_| {
+                        format!(
+                            "Invalid static route destination '{}': expected tun_a, tun_b or a prefix",
+                            prefix
+                        )
+                    })?
+                    .trunc(),
+            ),
+        };
+        Ok(Self {
+            target,
+            next_hop: RouterId(cfg.via().to_string()),
+            distance: cfg.distance(),
+        })
+    }
+}
+
+/// Apply the static routes of the router at `node` to its computed `table`. `edge_distances`
+/// are the distances to each edge router, pricing the pinned edge routes; routes whose next
+/// hop is not reachable over a link that is up are left out, and so are edge routes losing to
+/// the computed or connected route on administrative distance.
+pub(crate) fn apply(
+    fabric: &Fabric,
+    node: NodeIndex,
+    table: &mut RoutingTable,
+    edge_distances: &HashMap<Destination, HashMap<NodeIndex, u32>>,
+) {
+    let router = &fabric.graph[node];
+    for route in &router.static_routes {
+        let link = fabric
+            .graph
+            .edges(node)
+            .find(|e| e.weight().is_up() && fabric.graph[e.target()].id == route.next_hop);
+        let Some(link) = link else {
+            debug!(
+                "Static route of {} via {} has no usable link, using the computed route",
+                router.id.0, route.next_hop.0
+            );
+            continue;
+        };
+        match route.target {
+            StaticTarget::Edge(edge) => {
+                let entry = match edge {
+                    Destination::TunA => &mut table.tun_a,
+                    Destination::TunB => &mut table.tun_b,
+                };
+                let computed = if entry.next_hop == router.id && entry.total_cost == 0 {
+                    Some(0)
+                } else if entry.total_cost != u32::MAX {
+                    Some(fabric.routing_distance)
+                } else {
+                    None
+                };
+                if computed.is_some_and(|distance| distance < route.distance) {
+                    debug!(
+                        "Static route of {} via {} loses to the computed route on distance",
+                        router.id.0, route.next_hop.0
+                    );
+                    continue;
+                }
+                let beyond = edge_distances
+                    .get(&edge)
+                    .and_then(|d| d.get(&link.target()))
+                    .copied()
+                    .unwrap_or(u32::MAX);
+                entry.next_hop = route.next_hop.clone();
+                entry.total_cost =
+                    beyond.saturating_add(cost_across(fabric, link.weight(), node, link.target()));
+            }
+            StaticTarget::Prefix(prefix) => table.pinned.push(PinnedRoute {
+                prefix,
+                next_hop: route.next_hop.clone(),
+                distance: route.distance,
+            }),
+        }
+    }
+    // Most specific first, then the lowest distance.
+    table.pinned.sort_by(|a, b| {
+        b.prefix
+            .prefix_len()
+            .cmp(&a.prefix.prefix_len())
+            .then(a.prefix.cmp(&b.prefix))
+            .then(a.distance.cmp(&b.distance))
+    });
+}
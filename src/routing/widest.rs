@@ -0,0 +1,118 @@
+// src/routing/widest.rs
+
+//! Widest‑path routing (`[routing] algorithm = "widest-path"`): the edge routes maximise the
+//! bottleneck bandwidth, the smallest `bandwidth_mbps` along the path (links without one are
+//! unlimited), and among equally wide paths take the one with the fewest hops. Links of
+//! routers in maintenance count as having no bandwidth, so paths cross them only when there is
+//! no other way. A route's cost is its number of hops.
+
+use super::{break_tie, usable_edges, Destination, RouteEntry, RoutingTable};
+use crate::topology::{Fabric, Link, RouterId};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::HashMap;
+
+/// Bottleneck bandwidth and hop count of a path; see [`Width::better`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Width {
+    pub bandwidth: f64,
+    pub hops: u32,
+}
+
+impl Width {
+    /// Wider, or as wide and shorter.
+    fn better(&self, other: &Width) -> bool {
+        self.bandwidth > other.bandwidth
+            || (self.bandwidth == other.bandwidth && self.hops < other.hops)
+    }
+
+    /// The path through `link` to a router whose path is `self`.
+    pub fn through(&self, fabric: &Fabric, link: &Link, a: NodeIndex, b: NodeIndex) -> Width {
+        let bandwidth = if fabric.graph[a].maintenance || fabric.graph[b].maintenance {
+            0.0
+        } else {
+            link.cfg.bandwidth_mbps.unwrap_or(f64::INFINITY)
+        };
+        Width {
+            bandwidth: self.bandwidth.min(bandwidth),
+            hops: self.hops + 1,
+        }
+    }
+}
+
+/// Widest paths to `dst` over the links that are up, from every router that can reach it.
+pub(crate) fn widths_to(fabric: &Fabric, dst: NodeIndex) -> HashMap<NodeIndex, Width> {
+    let mut settled: HashMap<NodeIndex, Width> = HashMap::new();
+    let mut frontier: HashMap<NodeIndex, Width> = HashMap::from([(
+        dst,
+        Width {
+            bandwidth: f64::INFINITY,
+            hops: 0,
+        },
+    )]);
+    // Label setting as in Dijkstra: the best path not yet settled cannot get better, since
+    // extending a path never widens or shortens it.
+    while let Some((&node, &width)) = frontier
+        .iter()
+        .reduce(|a, b| if b.1.better(a.1) { b } else { a })
+    {
+        frontier.remove(&node);
+        settled.insert(node, width);
+        for edge in usable_edges(fabric, node) {
+            let next = edge.target();
+            if settled.contains_key(&next) {
+                continue;
+            }
+            let candidate = width.through(fabric, edge.weight(), node, next);
+            if frontier.get(&next).is_none_or(|w| candidate.better(w)) {
+                frontier.insert(next, candidate);
+            }
+        }
+    }
+    settled
+}
+
+/// Replace the edge routes of `tables` with the widest paths towards `edges`, the edge
+/// routers; equally good next hops are chosen as with the shortest paths.
+pub(crate) fn apply(
+    fabric: &Fabric,
+    edges: [(Destination, NodeIndex); 2],
+    tie_break_seed: Option<u64>,
+    tables: &mut HashMap<RouterId, RoutingTable>,
+) {
+    for (destination, edge) in edges {
+        let widths = widths_to(fabric, edge);
+        for (router_id, &node) in &fabric.router_index {
+            let entry = match widths.get(&node) {
+                Some(width) if node != edge => {
+                    let candidates = usable_edges(fabric, node)
+                        .filter(|e| {
+                            widths.get(&e.target()).is_some_and(|w| {
+                                w.through(fabric, e.weight(), e.target(), node) == *width
+                            })
+                        })
+                        .map(|e| fabric.graph[e.target()].id.clone())
+                        .collect();
+                    RouteEntry {
+                        next_hop: break_tie(candidates, router_id, destination, tie_break_seed)
+                            .unwrap_or_else(|| router_id.clone()),
+                        total_cost: width.hops,
+                    }
+                }
+                Some(_) => RouteEntry {
+                    next_hop: router_id.clone(),
+                    total_cost: 0,
+                },
+                None => RouteEntry {
+                    next_hop: router_id.clone(),
+                    total_cost: u32::MAX,
+                },
+            };
+            let table = tables.entry(router_id.clone()).or_default();
+            match destination {
+                Destination::TunA => table.tun_a = entry,
+                Destination::TunB => table.tun_b = entry,
+            }
+        }
+    }
+}
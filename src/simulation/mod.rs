@@ -1,9 +1,14 @@
 // src/simulation/mod.rs
 
-use crate::topology::Link;
+use crate::sojourn::Sojourn;
+use crate::topology::link::FragmentKey;
+use crate::topology::{Link, RouterId};
 use once_cell::sync::Lazy;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::sync::Mutex;
 use thiserror::Error;
 use tokio::time::{sleep, Duration};
@@ -18,44 +23,220 @@ pub fn init_rng(seed: u64) {
     *rng = StdRng::seed_from_u64(seed);
 }
 
+tokio::task_local! {
+    // Generator of one experiment run; takes precedence over the global RNG inside its scope.
+    static RUN_RNG: RefCell<StdRng>;
+}
+
+/// Run `f` with the current run's RNG, or the global one outside a run, so that generators
+/// share the seeded random sequence.
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    let mut f = Some(f);
+    if let Ok(value) = RUN_RNG.try_with(|rng| (f.take().unwrap())(&mut rng.borrow_mut())) {
+        return value;
+    }
+    let mut rng = GLOBAL_RNG.lock().unwrap();
+    (f.take().unwrap())(&mut rng)
+}
+
+/// Drive `fut` with an RNG of its own (seeded, or from entropy), so that concurrent runs neither
+/// share nor reseed a random sequence.
+pub async fn with_run_rng<F: Future>(seed: Option<u64>, fut: F) -> F::Output {
+    let rng = seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+    RUN_RNG.scope(RefCell::new(rng), fut).await
+}
+
+tokio::task_local! {
+    // Set while delays are only accounted for, the caller releasing the packet later itself.
+    static PACED: ();
+}
+
+/// Drive `fut` with paced delays if `enabled`: links, egress queues and router CPUs report the
+/// time a packet spends with them without sleeping through it, so the caller can release the
+/// packet at its simulated egress time while the next one is already being processed.
+pub async fn with_paced_delays<F: Future>(enabled: bool, fut: F) -> F::Output {
+    if enabled {
+        PACED.scope((), fut).await
+    } else {
+        fut.await
+    }
+}
+
+/// Whether the current task is inside [`with_paced_delays`].
+pub fn delays_paced() -> bool {
+    PACED.try_with(|_| ()).is_ok()
+}
+
 /// Errors that can arise during link simulation.
 #[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum SimulationError {
     #[error("Packet lost due to link loss simulation")]
     PacketLost,
+    #[error("Packet dropped because its egress queue was full")]
+    QueueFull,
     #[error("Packet size {packet_size} exceeds MTU {mtu}")]
     MtuExceeded { packet_size: usize, mtu: u32 },
+    #[error("Packet dropped because the link is not up")]
+    LinkDown,
     #[error("Other simulation error: {0}")]
     Other(String),
 }
 
+/// Fragment trains remembered per link before the table is flushed; bounds the state kept for
+/// trains whose last fragment never shows up.
+const MAX_FRAGMENT_TRAINS: usize = 4096;
+
+/// If `packet` is an IPv4 fragment or carries an IPv6 Fragment header, return the key of its
+/// datagram and whether it is the last fragment (More Fragments clear).
+pub fn fragment_key(packet: &[u8]) -> Option<(FragmentKey, bool)> {
+    match packet.first()? >> 4 {
+        4 if packet.len() >= 20 => {
+            let flags_offset = u16::from_be_bytes([packet[6], packet[7]]);
+            let more = flags_offset & 0x2000 != 0;
+            if !more && flags_offset & 0x1FFF == 0 {
+                return None;
+            }
+            let src = Ipv4Addr::new(packet[12], packet[13], packet[14], packet[15]);
+            let dst = Ipv4Addr::new(packet[16], packet[17], packet[18], packet[19]);
+            let id = u16::from_be_bytes([packet[4], packet[5]]) as u32;
+            Some(((IpAddr::V4(src), IpAddr::V4(dst), id, packet[9]), !more))
+        }
+        6 if packet.len() >= 40 => {
+            let mut next = packet[6];
+            let mut offset = 40;
+            // Skip Hop-by-Hop, Routing and Destination Options headers up to the Fragment header.
+            while matches!(next, 0 | 43 | 60) {
+                let len = (*packet.get(offset + 1)? as usize + 1) * 8;
+                next = *packet.get(offset)?;
+                offset += len;
+            }
+            if next != 44 || packet.len() < offset + 8 {
+                return None;
+            }
+            let frag = &packet[offset..offset + 8];
+            let more = frag[3] & 0x01 != 0;
+            let id = u32::from_be_bytes([frag[4], frag[5], frag[6], frag[7]]);
+            let addr = |at: usize| {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(&packet[at..at + 16]);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            };
+            Some(((addr(8), addr(24), id, frag[0]), !more))
+        }
+        _ => None,
+    }
+}
+
 /// Apply link characteristics (delay, jitter, loss) to a packet.
 /// Returns `Ok(())` if the packet survives the link, or `Err` if it is dropped due to loss or other issues.
 pub async fn simulate_link(link: &Link, packet: &[u8]) -> Result<(), SimulationError> {
+    simulate_link_from(link, &link.id.a, packet).await
+}
+
+/// Like [`simulate_link`], for a packet sent by router `from`; the direction selects the
+/// egress queues of links with a bandwidth.
+pub async fn simulate_link_from(
+    link: &Link,
+    from: &RouterId,
+    packet: &[u8],
+) -> Result<(), SimulationError> {
+    simulate_link_timed(link, from, packet).await.map(|_| ())
+}
+
+/// Like [`simulate_link_from`], returning how the time on the link was made up.
+pub async fn simulate_link_timed(
+    link: &Link,
+    from: &RouterId,
+    packet: &[u8],
+) -> Result<Sojourn, SimulationError> {
     // Increment packet counter for load‑balancing statistics
     use std::sync::atomic::Ordering;
     link.counter.fetch_add(1, Ordering::Relaxed);
 
+    // A failed link, or one still in its bring‑up delay, carries nothing.
+    if !link.is_up() {
+        debug!("Link {:?} is {:?}", link.id, link.state());
+        link.lost.fetch_add(1, Ordering::Relaxed);
+        return Err(SimulationError::LinkDown);
+    }
+
+    // On tunnel links the MTU, queues and byte counters see the encapsulated packet.
+    let wire = crate::gre::on_wire(link, from, packet);
     // MTU enforcement
     if let Some(mtu) = link.cfg.mtu {
-        if packet.len() > mtu as usize {
+        if wire.len() > mtu as usize {
             debug!(
                 "Packet size {} exceeds MTU {} on link {:?}",
-                packet.len(),
+                wire.len(),
                 mtu,
                 link.id
             );
+            link.too_big.fetch_add(1, Ordering::Relaxed);
+            // The inner packet has to fit in what the tunnel leaves of the MTU.
             return Err(SimulationError::MtuExceeded {
                 packet_size: packet.len(),
-                mtu,
+                mtu: mtu.saturating_sub(crate::gre::overhead(link, packet) as u32),
             });
         }
     }
 
+    // Egress queueing on links with a bandwidth: tail drop or wait for the traffic ahead and
+    // the packet's own serialisation.
+    let queue_wait = match &link.queues {
+        Some(queues) => {
+            let mut queues = queues.lock().unwrap();
+            let set = if *from == link.id.a {
+                &mut queues.a_to_b
+            } else {
+                &mut queues.b_to_a
+            };
+            match set.admit(crate::qos::dscp_of(&wire), wire.len()) {
+                Some(wait) => wait,
+                None => {
+                    debug!("Egress queue full on link {:?}", link.id);
+                    link.lost.fetch_add(1, Ordering::Relaxed);
+                    return Err(SimulationError::QueueFull);
+                }
+            }
+        }
+        None => Duration::ZERO,
+    };
+    // Then the shared pool, if any: the aggregate of its links queues here.
+    let queue_wait = match &link.pool {
+        Some(pool) => match pool
+            .queues
+            .lock()
+            .unwrap()
+            .admit(crate::qos::dscp_of(&wire), wire.len())
+        {
+            Some(wait) => queue_wait + wait,
+            None => {
+                debug!("Pool {} full on link {:?}", pool.name, link.id);
+                link.lost.fetch_add(1, Ordering::Relaxed);
+                return Err(SimulationError::QueueFull);
+            }
+        },
+        None => queue_wait,
+    };
+
     // Simulate packet loss and compute jitter without holding the global RNG lock across await points.
-    let (loss_occurred, jitter_val) = {
-        let mut rng = GLOBAL_RNG.lock().unwrap();
-        let loss = rng.gen_range(0.0..100.0) < link.cfg.loss_percent as f64;
+    let (loss_occurred, jitter_val) = with_rng(|rng| {
+        let mut loss = rng.gen_range(0.0..100.0) < link.cfg.loss_percent as f64;
+        // Correlated fragment loss: the first fragment seen decides for the whole train.
+        if let (Some(train_loss), Some((key, last))) =
+            (link.cfg.fragment_train_loss_percent, fragment_key(packet))
+        {
+            let mut fates = link.fragment_fates.lock().unwrap();
+            if !fates.contains_key(&key) && fates.len() >= MAX_FRAGMENT_TRAINS {
+                fates.clear();
+            }
+            loss = *fates
+                .entry(key)
+                .or_insert_with(|| rng.gen_range(0.0..100.0) < train_loss as f64);
+            if last {
+                fates.remove(&key);
+            }
+        }
         let jitter = if link.cfg.jitter_ms > 0 {
             // Generate jitter in the range [-jitter_ms, +jitter_ms]
             let range = -(link.cfg.jitter_ms as i32)..=link.cfg.jitter_ms as i32;
@@ -64,31 +245,38 @@ pub async fn simulate_link(link: &Link, packet: &[u8]) -> Result<(), SimulationE
             0
         };
         (loss, jitter)
-    };
+    });
     if loss_occurred {
-        debug!(
-            "Packet dropped on link {:?} due to loss ({}%)",
-            link.id, link.cfg.loss_percent
-        );
+        debug!("Packet dropped on link {:?} due to loss", link.id);
+        link.lost.fetch_add(1, Ordering::Relaxed);
         return Err(SimulationError::PacketLost);
     }
 
     // Compute total delay = base delay + jitter (can be negative).
     let jitter = jitter_val;
+    let delay_ms = link.delay_ms(from);
     // Ensure total delay is non‑negative
-    let total_delay_i32 = link.cfg.delay_ms as i32 + jitter;
+    let total_delay_i32 = delay_ms as i32 + jitter;
     let total_delay = if total_delay_i32 < 0 {
         0
     } else {
         total_delay_i32 as u32
     };
-    if total_delay > 0 {
+    if (total_delay > 0 || !queue_wait.is_zero()) && !delays_paced() {
         debug!(
-            "Delaying packet on link {:?} by {} ms (jitter {} ms)",
-            link.id, link.cfg.delay_ms, jitter
+            "Delaying packet on link {:?} by {} ms (jitter {} ms, queueing {:?})",
+            link.id, delay_ms, jitter, queue_wait
         );
-        sleep(Duration::from_millis(total_delay as u64)).await;
+        sleep(Duration::from_millis(total_delay as u64) + queue_wait).await;
     }
     debug!("Packet passed through link {:?}", link.id);
-    Ok(())
+    link.bytes.fetch_add(wire.len() as u64, Ordering::Relaxed);
+    link.sizes.record(wire.len());
+    Ok(Sojourn {
+        propagation_ms: delay_ms as f64,
+        queueing_ms: queue_wait.as_secs_f64() * 1000.0,
+        // What the delay actually became, so that a clamped negative jitter is not overstated.
+        jitter_ms: total_delay as f64 - delay_ms as f64,
+        processing_ms: 0.0,
+    })
 }
@@ -0,0 +1,148 @@
+// src/sla/mod.rs
+
+//! Per‑customer flow metrics and SLA evaluation.
+//!
+//! Every packet generated for a virtual customer is recorded in a [`FlowMetrics`] entry on the
+//! fabric. At the end of the run the metrics are checked against the customer's `[sla]` targets
+//! and a pass/fail [`SlaResult`] is produced per customer.
+
+use crate::config::SlaConfig;
+use tokio::time::{Duration, Instant};
+
+/// Traffic statistics collected for one customer flow.
+#[derive(Debug, Clone, Default)]
+pub struct FlowMetrics {
+    pub sent: u64,
+    pub delivered: u64,
+    pub bytes_delivered: u64,
+    /// One‑way latency of each delivered packet, in milliseconds.
+    pub latencies_ms: Vec<f64>,
+    pub first_sent: Option<Instant>,
+    pub last_delivered: Option<Instant>,
+    /// Delivered packets whose payload no longer matched its pattern.
+    pub payload_errors: u64,
+}
+
+impl FlowMetrics {
+    /// Record one packet sent at `sent_at`; `latency` is `Some` if it reached the far edge.
+    pub fn record(&mut self, sent_at: Instant, bytes: usize, latency: Option<Duration>) {
+        self.sent += 1;
+        self.first_sent.get_or_insert(sent_at);
+        if let Some(latency) = latency {
+            self.delivered += 1;
+            self.bytes_delivered += bytes as u64;
+            self.latencies_ms.push(latency.as_secs_f64() * 1000.0);
+            self.last_delivered = Some(sent_at + latency);
+        }
+    }
+
+    pub fn loss_percent(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        (self.sent - self.delivered) as f64 * 100.0 / self.sent as f64
+    }
+
+    /// Latency percentile (nearest rank), `p` in 0..=100.
+    pub fn latency_percentile_ms(&self, p: f64) -> Option<f64> {
+        if self.latencies_ms.is_empty() {
+            return None;
+        }
+        let mut sorted = self.latencies_ms.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+
+    /// Delivered throughput between the first packet sent and the last one delivered.
+    pub fn throughput_mbps(&self) -> f64 {
+        match (self.first_sent, self.last_delivered) {
+            (Some(first), Some(last)) if last > first => {
+                self.bytes_delivered as f64 * 8.0 / (last - first).as_secs_f64() / 1_000_000.0
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Outcome of a single SLA target.
+#[derive(Debug, Clone)]
+pub struct SlaCheck {
+    pub metric: &'static str,
+    pub target: f64,
+    /// Measured value, `None` if nothing could be measured (counts as a failure).
+    pub actual: Option<f64>,
+    pub pass: bool,
+}
+
+/// SLA verdict for one customer.
+#[derive(Debug, Clone)]
+pub struct SlaResult {
+    pub customer: String,
+    pub checks: Vec<SlaCheck>,
+}
+
+impl SlaResult {
+    pub fn pass(&self) -> bool {
+        self.checks.iter().all(|c| c.pass)
+    }
+
+    /// Multi‑line report, one line per checked target.
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "SLA {}: {}\n",
+            self.customer,
+            if self.pass() { "PASS" } else { "FAIL" }
+        );
+        for check in &self.checks {
+            let actual = check
+                .actual
+                .map_or_else(|| "n/a".to_string(), |v| format!("{:.3}", v));
+            out.push_str(&format!(
+                "  {:<20} target={:<10} actual={:<10} {}\n",
+                check.metric,
+                check.target,
+                actual,
+                if check.pass { "ok" } else { "VIOLATED" }
+            ));
+        }
+        out
+    }
+}
+
+/// Check `metrics` against the targets declared in `sla`.
+pub fn evaluate(customer: &str, sla: &SlaConfig, metrics: &FlowMetrics) -> SlaResult {
+    let mut checks = Vec::new();
+    let measured = metrics.sent > 0;
+    if let Some(target) = sla.max_loss_percent {
+        let actual = measured.then(|| metrics.loss_percent());
+        checks.push(SlaCheck {
+            metric: "loss_percent",
+            target,
+            actual,
+            pass: actual.is_some_and(|a| a <= target),
+        });
+    }
+    if let Some(target) = sla.p95_latency_ms {
+        let actual = metrics.latency_percentile_ms(95.0);
+        checks.push(SlaCheck {
+            metric: "p95_latency_ms",
+            target,
+            actual,
+            pass: actual.is_some_and(|a| a <= target),
+        });
+    }
+    if let Some(target) = sla.min_throughput_mbps {
+        let actual = measured.then(|| metrics.throughput_mbps());
+        checks.push(SlaCheck {
+            metric: "throughput_mbps",
+            target,
+            actual,
+            pass: actual.is_some_and(|a| a >= target),
+        });
+    }
+    SlaResult {
+        customer: customer.to_string(),
+        checks,
+    }
+}
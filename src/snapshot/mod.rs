@@ -0,0 +1,230 @@
+// src/snapshot/mod.rs
+
+//! Scheduled snapshots of the fabric's mutable state.
+//!
+//! Events and injected faults change the links and routers of a running fabric, so after a
+//! while its state is no longer the one its configuration describes. With `[snapshot]` the run
+//! loop writes that state every `interval_secs`, and once more at the end of the run, to
+//! `<dir>/snapshot-<ms since the start>.toml`: the effective parameters of every link and
+//! whether it is up, and which routers are in maintenance, with the time the snapshot was taken
+//! and the run it belongs to. `--restore-snapshot FILE` lays a snapshot over the configuration
+//! ([`Snapshot::apply`]) so a new run starts from exactly that state.
+
+use crate::config::{EventConfig, SimulatorConfig, SnapshotConfig};
+use crate::provenance::{self, RunMetadata};
+use crate::topology::link::{LinkConfig, LinkState};
+use crate::topology::Fabric;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time::{Duration, Instant};
+use tracing::{error, info};
+
+/// A link's state in a snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkSnapshot {
+    /// Whether the link was administratively up (it may still have been in its bring‑up delay).
+    pub up: bool,
+    #[serde(flatten)]
+    pub cfg: LinkConfig,
+}
+
+/// A router's state in a snapshot.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouterSnapshot {
+    pub maintenance: bool,
+}
+
+/// The mutable state of a fabric at one point of a run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// When the snapshot was taken, in UTC, RFC 3339.
+    pub taken_at: String,
+    /// Time since the start of the run.
+    pub elapsed_secs: f64,
+    #[serde(default)]
+    pub run: Option<RunMetadata>,
+    #[serde(default)]
+    pub routers: BTreeMap<String, RouterSnapshot>,
+    /// Links by name, `<a>_<b>` with `a` the lower RouterId.
+    #[serde(default)]
+    pub links: BTreeMap<String, LinkSnapshot>,
+}
+
+impl Snapshot {
+    /// The state of `fabric`, `elapsed` into the run.
+    pub fn of(fabric: &Fabric, elapsed: Duration) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let routers = fabric
+            .graph
+            .node_weights()
+            .map(|r| {
+                let state = RouterSnapshot {
+                    maintenance: r.maintenance,
+                };
+                (r.id.0.clone(), state)
+            })
+            .collect();
+        let links = fabric
+            .graph
+            .edge_weights()
+            .map(|link| {
+                let state = LinkSnapshot {
+                    up: link.admin_state != LinkState::Down,
+                    cfg: link.cfg.clone(),
+                };
+                (format!("{}_{}", link.id.a.0, link.id.b.0), state)
+            })
+            .collect();
+        Self {
+            taken_at: provenance::rfc3339(now),
+            elapsed_secs: elapsed.as_secs_f64(),
+            run: fabric.run.clone(),
+            routers,
+            links,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read snapshot {}: {}", path.display(), e))?;
+        toml::from_str(&text)
+            .map_err(|e| format!("Failed to parse snapshot {}: {}", path.display(), e))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let text =
+            toml::to_string(self).map_err(|e| format!("Failed to encode snapshot: {}", e))?;
+        std::fs::write(path, text)
+            .map_err(|e| format!("Failed to write snapshot {}: {}", path.display(), e))
+    }
+
+    /// Lay the snapshot over `cfg`: its links take the snapshot's parameters, links that were
+    /// down go down at the start, and its routers take their maintenance states. Timed events
+    /// that had fired by `elapsed_secs` are dropped and the others move that much earlier, so
+    /// the run carries on from where the snapshot was taken. Links and routers the
+    /// configuration does not have are an error.
+    pub fn apply(&self, cfg: &mut SimulatorConfig) -> Result<(), String> {
+        cfg.events.retain_mut(|event| match event.at_secs {
+            Some(at) if at < self.elapsed_secs => false,
+            Some(ref mut at) => {
+                *at -= self.elapsed_secs;
+                true
+            }
+            None => true,
+        });
+        for (name, router) in &self.routers {
+            let value = cfg.topology.routers.get_mut(name).ok_or_else(|| {
+                format!(
+                    "Snapshot router '{}' is not defined in topology.routers",
+                    name
+                )
+            })?;
+            if !value.is_table() {
+                *value = toml::Value::Table(toml::Table::new());
+            }
+            if let Some(table) = value.as_table_mut() {
+                table.insert(
+                    "maintenance".to_string(),
+                    toml::Value::Boolean(router.maintenance),
+                );
+            }
+        }
+        for (name, link) in &self.links {
+            // The configuration may name the link the other way round.
+            let reversed = name.split_once('_').map(|(a, b)| format!("{}_{}", b, a));
+            let (key, link_cfg) = if cfg.topology.links.contains_key(name) {
+                (name.clone(), link.cfg.clone())
+            } else if let Some(r) = reversed.filter(|r| cfg.topology.links.contains_key(r)) {
+                (r, link.cfg.clone().reversed())
+            } else {
+                return Err(format!(
+                    "Snapshot link '{}' is not defined in topology.links",
+                    name
+                ));
+            };
+            if !link.up {
+                cfg.events.push(EventConfig {
+                    at_secs: Some(0.0),
+                    action: "link_down".to_string(),
+                    link: key.clone(),
+                    ..Default::default()
+                });
+            }
+            cfg.topology.links.insert(key, link_cfg);
+        }
+        Ok(())
+    }
+}
+
+/// Writer of the snapshots of a run.
+#[derive(Debug)]
+pub struct Snapshots {
+    dir: PathBuf,
+    interval: Duration,
+    started: Instant,
+    next: Instant,
+    /// Files written so far.
+    pub written: Vec<PathBuf>,
+}
+
+impl Snapshots {
+    /// Snapshots into `cfg.dir`, which is created if needed.
+    pub fn new(cfg: &SnapshotConfig) -> Result<Self, String> {
+        std::fs::create_dir_all(&cfg.dir)
+            .map_err(|e| format!("Failed to create snapshot directory {}: {}", cfg.dir, e))?;
+        let interval = Duration::from_secs_f64(cfg.interval_secs);
+        let now = Instant::now();
+        Ok(Self {
+            dir: PathBuf::from(&cfg.dir),
+            interval,
+            started: now,
+            next: now + interval,
+            written: Vec::new(),
+        })
+    }
+
+    pub fn next_snapshot(&self) -> Instant {
+        self.next
+    }
+}
+
+/// Write a snapshot of `fabric` if the interval is over.
+pub fn tick(fabric: &mut Fabric) {
+    if fabric
+        .snapshots
+        .as_ref()
+        .is_some_and(|s| Instant::now() >= s.next_snapshot())
+    {
+        take(fabric);
+    }
+}
+
+/// Write a snapshot of `fabric` now.
+pub fn take(fabric: &mut Fabric) {
+    let Some(elapsed) = fabric.snapshots.as_ref().map(|s| s.started.elapsed()) else {
+        return;
+    };
+    let snapshot = Snapshot::of(fabric, elapsed);
+    let Some(snapshots) = fabric.snapshots.as_mut() else {
+        return;
+    };
+    snapshots.next = Instant::now() + snapshots.interval;
+    let path = snapshots
+        .dir
+        .join(format!("snapshot-{:010}.toml", elapsed.as_millis()));
+    match snapshot.write(&path) {
+        Ok(()) => {
+            info!("Wrote snapshot {}", path.display());
+            // A later snapshot of the same millisecond replaces the earlier one.
+            if snapshots.written.last() != Some(&path) {
+                snapshots.written.push(path);
+            }
+        }
+        Err(e) => error!("{}", e),
+    }
+}
@@ -0,0 +1,223 @@
+// src/sojourn/mod.rs
+
+//! Per‑packet sojourn‑time accounting.
+//!
+//! The processor adds up where each packet's time in the fabric went: link propagation delay,
+//! jitter (the signed deviation from it), egress queueing (including the packet's own
+//! serialisation) and waiting for router CPUs. With `packet_trace` set, every packet the
+//! processor finishes with is written as one CSV record with that breakdown next to the
+//! measured time, so a config can be tuned from the component that dominates. The record also
+//! has when the packet was sent and received by the clocks of the edges it went between (see
+//! [`clock`](crate::clock)), the way a measurement pipeline at the edges would see it. With
+//! `packet_trace_format = "json"` the records are JSON objects instead, one per line, carrying
+//! the whole packet (its `raw` bytes hex‑encoded) with the path it took.
+//!
+//! The same accounting is attached to the packet the processor returns as an [`Annotation`],
+//! together with when it entered and the routers and links it went through.
+
+use crate::clock::EdgeClocks;
+use crate::packet::PacketMeta;
+use crate::provenance::RunMetadata;
+use crate::routing::Destination;
+use crate::topology::{LinkId, RouterId};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tokio::time::Instant;
+use tracing::{error, info};
+
+/// Time spent in the fabric by component, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Sojourn {
+    pub propagation_ms: f64,
+    pub queueing_ms: f64,
+    /// Negative when jitter shortened a link's delay.
+    pub jitter_ms: f64,
+    pub processing_ms: f64,
+}
+
+impl Sojourn {
+    pub fn total_ms(&self) -> f64 {
+        self.propagation_ms + self.queueing_ms + self.jitter_ms + self.processing_ms
+    }
+
+    pub fn add(&mut self, other: &Sojourn) {
+        self.propagation_ms += other.propagation_ms;
+        self.queueing_ms += other.queueing_ms;
+        self.jitter_ms += other.jitter_ms;
+        self.processing_ms += other.processing_ms;
+    }
+}
+
+/// The path one packet took through the fabric, as set on it by the processor.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Annotation {
+    /// When the packet entered the fabric.
+    #[serde(skip)]
+    pub ingress_at: Instant,
+    /// Routers visited in order, from the ingress to where it was delivered or dropped.
+    pub routers: Vec<RouterId>,
+    /// Links crossed in order; one fewer than `routers`.
+    pub links: Vec<LinkId>,
+    /// Simulated delay accumulated on the way.
+    pub delay: Sojourn,
+}
+
+/// What happened to one packet, as handed to [`PacketTrace::record`].
+#[derive(Debug)]
+pub struct TraceRecord<'a> {
+    /// Router the packet entered at.
+    pub ingress: &'a RouterId,
+    /// Router it was delivered or dropped at.
+    pub last: &'a RouterId,
+    pub delivered: bool,
+    /// Links crossed.
+    pub hops: u32,
+    pub sojourn: Sojourn,
+    /// Time from entering to leaving the processor, as measured.
+    pub elapsed_ms: f64,
+    /// When the packet entered, and the edge it was sent from.
+    pub sent: (Instant, Destination),
+    /// Edge the packet was delivered to; the time is now.
+    pub received: Option<Destination>,
+    /// The packet as it left (an ICMP error if one replaced it).
+    pub packet: &'a PacketMeta,
+}
+
+pub const TRACE_HEADER: &str = "seq,ingress,last,outcome,hops,elapsed_ms,total_ms,propagation_ms,queueing_ms,jitter_ms,processing_ms,src,dst,protocol,bytes,sent_ms,received_ms";
+
+/// Format of the packet trace.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceFormat {
+    /// One CSV line per packet after the [`TRACE_HEADER`] line.
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    Json,
+}
+
+/// File of per‑packet records.
+#[derive(Debug)]
+pub struct PacketTrace {
+    pub file: String,
+    pub format: TraceFormat,
+    /// Records written since the file was (re)opened.
+    pub records: u64,
+    /// Metadata stamped on the first line, if set.
+    pub run: Option<RunMetadata>,
+    writer: Option<BufWriter<File>>,
+}
+
+impl PacketTrace {
+    pub fn new(file: &str, format: TraceFormat) -> Self {
+        Self {
+            file: file.to_string(),
+            format,
+            records: 0,
+            run: None,
+            writer: None,
+        }
+    }
+
+    /// (Re)create the file with its stamp and header lines.
+    pub fn open(&mut self) -> Result<(), String> {
+        let file = File::create(&self.file)
+            .map_err(|e| format!("Failed to create packet trace {}: {}", self.file, e))?;
+        let mut writer = BufWriter::new(file);
+        let failed =
+            |e: std::io::Error| format!("Failed to write packet trace {}: {}", self.file, e);
+        match (&self.run, self.format) {
+            (Some(run), TraceFormat::Csv) => {
+                writeln!(writer, "# {}", run.comment()).map_err(failed)?
+            }
+            (Some(run), TraceFormat::Json) => {
+                let stamp = serde_json::json!({ "run": run });
+                writeln!(writer, "{}", stamp).map_err(failed)?
+            }
+            (None, _) => {}
+        }
+        if self.format == TraceFormat::Csv {
+            writeln!(writer, "{}", TRACE_HEADER).map_err(failed)?;
+        }
+        self.writer = Some(writer);
+        self.records = 0;
+        Ok(())
+    }
+
+    /// Write `record`, with the send and receive times read from `clocks`.
+    pub fn record(&mut self, record: &TraceRecord, clocks: &EdgeClocks) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        let (at, from) = record.sent;
+        let sent_ms = clocks.read_ms(from, at);
+        let received_ms = record.received.map(|to| clocks.read_ms(to, Instant::now()));
+        let outcome = if record.delivered {
+            "delivered"
+        } else {
+            "dropped"
+        };
+        let result = match self.format {
+            TraceFormat::Csv => {
+                let s = &record.sojourn;
+                writeln!(
+                    writer,
+                    "{},{},{},{},{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{},{},{},{},{:.3},{}",
+                    self.records + 1,
+                    record.ingress.0,
+                    record.last.0,
+                    outcome,
+                    record.hops,
+                    record.elapsed_ms,
+                    s.total_ms(),
+                    s.propagation_ms,
+                    s.queueing_ms,
+                    s.jitter_ms,
+                    s.processing_ms,
+                    record.packet.src_ip,
+                    record.packet.dst_ip,
+                    record.packet.protocol,
+                    record.packet.raw.len(),
+                    sent_ms,
+                    received_ms
+                        .map(|ms| format!("{:.3}", ms))
+                        .unwrap_or_default()
+                )
+            }
+            // The path and the sojourn breakdown are in the packet's annotation.
+            TraceFormat::Json => {
+                let object = serde_json::json!({
+                    "seq": self.records + 1,
+                    "outcome": outcome,
+                    "elapsed_ms": record.elapsed_ms,
+                    "sent_ms": sent_ms,
+                    "received_ms": received_ms,
+                    "packet": record.packet,
+                });
+                writeln!(writer, "{}", object)
+            }
+        };
+        match result {
+            Ok(()) => self.records += 1,
+            Err(e) => {
+                error!("Failed to write packet trace {}: {}", self.file, e);
+                self.writer = None;
+            }
+        }
+    }
+
+    /// Flush the file.
+    pub fn finish(&mut self) {
+        let Some(mut writer) = self.writer.take() else {
+            return;
+        };
+        match writer.flush() {
+            Ok(()) => info!(
+                "Wrote {} packet trace records to {}",
+                self.records, self.file
+            ),
+            Err(e) => error!("Failed to write packet trace {}: {}", self.file, e),
+        }
+    }
+}
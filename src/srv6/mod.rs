@@ -0,0 +1,208 @@
+// src/srv6/mod.rs
+
+//! SRv6 traffic engineering with inserted Segment Routing Headers (RFC 8754).
+//!
+//! With an `[srv6]` section, IPv6 edge packets get an SRH inserted at the ingress edge holding
+//! the configured segment list (router IPv6 addresses) followed by the packet's own destination,
+//! and the destination address is rewritten to the first segment. Routers steer the packet along
+//! shortest paths towards the active segment; the router owning it decrements Segments Left and
+//! moves the next segment into the destination address. The last segment endpoint pops the SRH,
+//! so the packet leaves the fabric as it entered.
+
+use crate::config::Srv6Config;
+use crate::packet::PacketMeta;
+use crate::routing::{next_hops_towards, Destination};
+use crate::topology::{Fabric, RouterId};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr};
+use tracing::debug;
+
+/// Routing header type of the SRH.
+const ROUTING_TYPE_SRH: u8 = 4;
+const NEXT_HEADER_ROUTING: u8 = 43;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Srv6Stats {
+    /// Packets that got an SRH at the ingress edge.
+    pub inserted: u64,
+    /// Segments consumed at their endpoint routers.
+    pub endpoints: u64,
+    /// SRHs removed at the last segment endpoint.
+    pub popped: u64,
+}
+
+impl Srv6Stats {
+    pub fn summary(&self) -> String {
+        format!(
+            "inserted={}, endpoints={}, popped={}",
+            self.inserted, self.endpoints, self.popped
+        )
+    }
+}
+
+/// Parse a segment list, in travel order.
+pub fn parse_segments(segments: &[String]) -> Result<Vec<Ipv6Addr>, String> {
+    segments
+        .iter()
+        .map(|s| {
+            s.parse()
+                .map_err(|_| format!("Invalid SRv6 segment '{}': not an IPv6 address", s))
+        })
+        .collect()
+}
+
+/// Offset of the SRH in `raw` and of the Next Header byte pointing at it, if the packet carries
+/// one among its leading extension headers.
+fn find_srh(raw: &[u8]) -> Option<(usize, usize)> {
+    if raw.first()? >> 4 != 6 {
+        return None;
+    }
+    let (mut next_at, mut offset) = (6usize, 40usize);
+    loop {
+        let next = *raw.get(next_at)?;
+        match next {
+            NEXT_HEADER_ROUTING if *raw.get(offset + 2)? == ROUTING_TYPE_SRH => {
+                return Some((offset, next_at));
+            }
+            0 | 60 => {
+                next_at = offset;
+                offset += (*raw.get(offset + 1)? as usize + 1) * 8;
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn set_payload_length(raw: &mut [u8], delta: isize) {
+    let len = u16::from_be_bytes([raw[4], raw[5]]) as isize + delta;
+    raw[4..6].copy_from_slice(&(len as u16).to_be_bytes());
+}
+
+/// The upper‑layer checksum covers the final destination (RFC 8200 section 8.1), not the
+/// active segment, so it stays valid across these rewrites.
+fn set_destination(packet: &mut PacketMeta, dst: Ipv6Addr) {
+    packet.raw_mut()[24..40].copy_from_slice(&dst.octets());
+    packet.dst_ip = IpAddr::V6(dst);
+}
+
+/// Segment lists of both directions and the routes towards every segment endpoint.
+#[derive(Debug)]
+pub struct Srv6 {
+    a_to_b: Vec<Ipv6Addr>,
+    b_to_a: Vec<Ipv6Addr>,
+    /// Router owning each segment address.
+    endpoints: HashMap<Ipv6Addr, RouterId>,
+    /// Next hop from each router towards each endpoint router.
+    routes: HashMap<RouterId, HashMap<RouterId, RouterId>>,
+    pub stats: Srv6Stats,
+}
+
+impl Srv6 {
+    /// Resolve the configured segments to routers of `fabric`.
+    pub fn new(cfg: &Srv6Config, fabric: &Fabric) -> Result<Self, String> {
+        let a_to_b = parse_segments(&cfg.a_to_b)?;
+        let b_to_a = parse_segments(&cfg.b_to_a)?;
+        let mut endpoints = HashMap::new();
+        let mut routes = HashMap::new();
+        for segment in a_to_b.iter().chain(&b_to_a) {
+            let router = fabric
+                .graph
+                .node_weights()
+                .find(|r| r.ipv6_addr == *segment)
+                .ok_or_else(|| format!("SRv6 segment {} is not a router address", segment))?;
+            routes
+                .entry(router.id.clone())
+                .or_insert_with(|| next_hops_towards(fabric, &router.id));
+            endpoints.insert(*segment, router.id.clone());
+        }
+        Ok(Self {
+            a_to_b,
+            b_to_a,
+            endpoints,
+            routes,
+            stats: Srv6Stats::default(),
+        })
+    }
+
+    /// Insert the SRH for packets heading towards `destination`. IPv4 packets, and IPv6 packets
+    /// when that direction has no segments, are left alone.
+    pub fn insert(&mut self, destination: Destination, packet: &mut PacketMeta) {
+        let segments = match destination {
+            Destination::TunB => &self.a_to_b,
+            Destination::TunA => &self.b_to_a,
+        };
+        let (Some(&first), IpAddr::V6(final_dst)) = (segments.first(), packet.dst_ip) else {
+            return;
+        };
+        // The SRH goes after a Hop‑by‑Hop Options header, if there is one.
+        let (next_at, offset) = if packet.raw[6] == 0 {
+            (40, 40 + (packet.raw[41] as usize + 1) * 8)
+        } else {
+            (6, 40)
+        };
+        let entries = segments.len() + 1;
+        let mut srh = vec![
+            packet.raw[next_at],
+            (2 * entries) as u8,
+            ROUTING_TYPE_SRH,
+            segments.len() as u8,
+            segments.len() as u8,
+            0,
+            0,
+            0,
+        ];
+        // Segment List[0] is the last segment to visit.
+        srh.extend_from_slice(&final_dst.octets());
+        for segment in segments.iter().rev() {
+            srh.extend_from_slice(&segment.octets());
+        }
+        let mut raw = packet.raw_mut();
+        raw[next_at] = NEXT_HEADER_ROUTING;
+        set_payload_length(&mut raw, srh.len() as isize);
+        raw.splice(offset..offset, srh);
+        drop(raw);
+        set_destination(packet, first);
+        self.stats.inserted += 1;
+    }
+
+    /// Segment endpoint processing at `router` (owning `address`), then the next hop towards the
+    /// active segment, or `None` once no segment is left and normal routing applies.
+    pub fn steer(
+        &mut self,
+        router: &RouterId,
+        address: Ipv6Addr,
+        packet: &mut PacketMeta,
+    ) -> Option<RouterId> {
+        let (offset, next_at) = find_srh(&packet.raw)?;
+        if packet.dst_ip == IpAddr::V6(address) {
+            // The same router may be listed several times in a row.
+            while packet.dst_ip == IpAddr::V6(address) && packet.raw[offset + 3] > 0 {
+                let left = packet.raw[offset + 3] - 1;
+                packet.raw_mut()[offset + 3] = left;
+                let at = offset + 8 + left as usize * 16;
+                let next: [u8; 16] = packet.raw[at..at + 16].try_into().unwrap();
+                set_destination(packet, next.into());
+                self.stats.endpoints += 1;
+            }
+            if packet.raw[offset + 3] == 0 {
+                let len = (packet.raw[offset + 1] as usize + 1) * 8;
+                let mut raw = packet.raw_mut();
+                raw[next_at] = raw[offset];
+                raw.drain(offset..offset + len);
+                set_payload_length(&mut raw, -(len as isize));
+                self.stats.popped += 1;
+                debug!("SRv6: last segment at {}, SRH popped", router.0);
+                return None;
+            }
+        }
+        let IpAddr::V6(active) = packet.dst_ip else {
+            return None;
+        };
+        let endpoint = self.endpoints.get(&active)?;
+        self.routes.get(endpoint)?.get(router).cloned()
+    }
+
+    pub fn reset(&mut self) {
+        self.stats = Srv6Stats::default();
+    }
+}
@@ -0,0 +1,239 @@
+// src/stats/mod.rs
+
+//! JSON dumps of the run's counters and their comparison.
+//!
+//! `--stats-json <FILE>` writes every router counter and every link and per‑class queue counter
+//! (with the link state as 0 down, 1 init, 2 up, and the packets that crossed it per size bin as
+//! `sizes/<bin>`) after the simulation ends, and the counters of
+//! the real edge interfaces if the run opened them; `stats diff a.json b.json` prints the
+//! per‑router, per‑link and per‑interface deltas between two such dumps, so the effect of a
+//! config change is one command away. Dumps of CLI runs carry the run's [`RunMetadata`] as
+//! `run`. With `counter_state` every run adds its counters to a [`CounterState`] file, read at
+//! startup unless `--fresh`.
+
+use crate::provenance::RunMetadata;
+use crate::topology::Fabric;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Counters by name.
+pub type Counters = BTreeMap<String, f64>;
+
+/// Counters of one run, keyed by router and by link name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatsDump {
+    #[serde(default)]
+    pub routers: BTreeMap<String, Counters>,
+    #[serde(default)]
+    pub links: BTreeMap<String, Counters>,
+    /// Counters of the real edge interfaces, if the run opened them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub interfaces: BTreeMap<String, Counters>,
+    /// The run the counters come from, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<RunMetadata>,
+}
+
+impl StatsDump {
+    pub fn from_fabric(fabric: &Fabric) -> Self {
+        let mut dump = Self {
+            run: fabric.run.clone(),
+            ..Self::default()
+        };
+        for (id, s) in fabric.get_statistics() {
+            let counters = [
+                ("packets_injected", s.packets_injected),
+                ("packets_received", s.packets_received),
+                ("packets_forwarded", s.packets_forwarded),
+                ("packets_lost", s.packets_lost),
+                ("packets_delivered", s.packets_delivered),
+                ("icmp_generated", s.icmp_generated),
+                ("icmp_suppressed", s.icmp_suppressed),
+                ("cpu_drops", s.cpu_drops),
+                ("acl_drops", s.acl_drops),
+                ("policer_drops", s.policer_drops),
+                ("bad_checksums", s.bad_checksums),
+                ("bytes_received", s.bytes_received),
+                ("bytes_forwarded", s.bytes_forwarded),
+                ("tcp_received", s.tcp_received),
+                ("udp_received", s.udp_received),
+                ("icmp_received", s.icmp_received),
+                ("other_received", s.other_received),
+            ];
+            dump.routers.insert(
+                id.0,
+                counters
+                    .into_iter()
+                    .map(|(name, v)| (name.to_string(), v as f64))
+                    .collect(),
+            );
+        }
+        for link in fabric.graph.edge_weights() {
+            let mut counters = Counters::new();
+            counters.insert("packets".to_string(), link.counter() as f64);
+            counters.insert("too_big".to_string(), link.too_big() as f64);
+            counters.insert("lost".to_string(), link.lost() as f64);
+            counters.insert("bytes".to_string(), link.bytes() as f64);
+            counters.insert("state".to_string(), link.state().as_number() as f64);
+            counters.insert("flaps".to_string(), link.flaps as f64);
+            for (bin, count) in link.sizes.bins() {
+                counters.insert(format!("sizes/{}", bin), count as f64);
+            }
+            for (direction, classes) in link.queue_stats() {
+                for q in classes {
+                    let prefix = format!("{}/{}", direction, q.name);
+                    let values = [
+                        ("enqueued", q.enqueued as f64),
+                        ("dropped", q.dropped as f64),
+                        ("wred_dropped", q.wred_dropped as f64),
+                        ("bytes", q.bytes as f64),
+                        ("max_depth", q.max_depth as f64),
+                        ("total_wait_ms", q.total_wait_ms),
+                    ];
+                    for (name, v) in values {
+                        counters.insert(format!("{}/{}", prefix, name), v);
+                    }
+                }
+            }
+            dump.links
+                .insert(format!("{}_{}", link.id.a.0, link.id.b.0), counters);
+        }
+        for (name, iface) in fabric.tun_counters.iter().flat_map(|c| c.all()) {
+            dump.interfaces.insert(
+                name.to_string(),
+                iface
+                    .values()
+                    .into_iter()
+                    .map(|(counter, v)| (counter.to_string(), v as f64))
+                    .collect(),
+            );
+        }
+        dump
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to encode statistics: {}", e))?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| format!("Failed to write statistics to {}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read statistics {}: {}", path, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("Invalid statistics dump {}: {}", path, e))
+    }
+}
+
+/// Counters accumulated over the runs sharing a `counter_state` file, so that repeated short
+/// runs against one topology add up to a soak test. The file is a [`StatsDump`] with the number
+/// of runs, which `stats diff` reads like any other dump.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CounterState {
+    /// Runs added so far.
+    #[serde(default)]
+    pub runs: u64,
+    #[serde(flatten)]
+    pub totals: StatsDump,
+}
+
+impl CounterState {
+    /// The state in `path`, or an empty one if the file does not exist yet or `fresh`.
+    pub fn open(path: &str, fresh: bool) -> Result<Self, String> {
+        if fresh || !std::path::Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read counter state {}: {}", path, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("Invalid counter state {}: {}", path, e))
+    }
+
+    /// Add the counters of one run. Counts are summed; a link's `state` takes the run's value
+    /// and queue `max_depth`s keep the highest.
+    pub fn add(&mut self, dump: &StatsDump) {
+        fn merge(totals: &mut BTreeMap<String, Counters>, run: &BTreeMap<String, Counters>) {
+            for (name, counters) in run {
+                let total = totals.entry(name.clone()).or_default();
+                for (counter, &v) in counters {
+                    let value = total.entry(counter.clone()).or_insert(0.0);
+                    if counter == "state" {
+                        *value = v;
+                    } else if counter.ends_with("/max_depth") {
+                        *value = value.max(v);
+                    } else {
+                        *value += v;
+                    }
+                }
+            }
+        }
+        merge(&mut self.totals.routers, &dump.routers);
+        merge(&mut self.totals.links, &dump.links);
+        merge(&mut self.totals.interfaces, &dump.interfaces);
+        self.totals.run = dump.run.clone();
+        self.runs += 1;
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to encode counter state: {}", e))?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| format!("Failed to write counter state to {}: {}", path, e))
+    }
+}
+
+/// Write the counters of `fabric` as JSON to `path`.
+pub fn write(fabric: &Fabric, path: &str) -> Result<(), String> {
+    StatsDump::from_fabric(fabric).write(path)
+}
+
+/// Counters print as integers; only fractional values (queue wait times) get decimals.
+fn format_value(v: Option<f64>, sign: bool) -> String {
+    match v {
+        Some(v) if v.fract() == 0.0 && sign => format!("{:+}", v),
+        Some(v) if v.fract() == 0.0 => format!("{}", v),
+        Some(v) if sign => format!("{:+.3}", v),
+        Some(v) => format!("{:.3}", v),
+        None => "-".to_string(),
+    }
+}
+
+/// Table of every counter present in either dump with its values, delta (B − A) and relative
+/// change. Routers or links missing from one side show `-`.
+pub fn diff(a: &StatsDump, b: &StatsDump) -> String {
+    let mut out = format!(
+        "{:<6} {:<14} {:<40} {:>12} {:>12} {:>12} {:>9}\n",
+        "kind", "name", "counter", "a", "b", "delta", "delta%"
+    );
+    for (kind, side_a, side_b) in [
+        ("router", &a.routers, &b.routers),
+        ("link", &a.links, &b.links),
+        ("iface", &a.interfaces, &b.interfaces),
+    ] {
+        let names: BTreeSet<&String> = side_a.keys().chain(side_b.keys()).collect();
+        for name in names {
+            let (ca, cb) = (side_a.get(name), side_b.get(name));
+            let counters: BTreeSet<&String> =
+                ca.into_iter().chain(cb).flat_map(|c| c.keys()).collect();
+            for counter in counters {
+                let va = ca.and_then(|c| c.get(counter)).copied();
+                let vb = cb.and_then(|c| c.get(counter)).copied();
+                let delta = va.zip(vb).map(|(x, y)| y - x);
+                let pct = match (va, delta) {
+                    (Some(x), Some(d)) if x != 0.0 => format!("{:+.1}%", d * 100.0 / x.abs()),
+                    _ => "-".to_string(),
+                };
+                out.push_str(&format!(
+                    "{:<6} {:<14} {:<40} {:>12} {:>12} {:>12} {:>9}\n",
+                    kind,
+                    name,
+                    counter,
+                    format_value(va, false),
+                    format_value(vb, false),
+                    format_value(delta, true),
+                    pct
+                ));
+            }
+        }
+    }
+    out
+}
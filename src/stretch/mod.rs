@@ -0,0 +1,216 @@
+// src/stretch/mod.rs
+
+//! Path stretch report.
+//!
+//! Static routes, BGP policies and the choice of routing algorithm can send packets along paths
+//! that are longer than they need to be. For every router and each edge this walks the path
+//! the computed routing tables give, and compares its cost with the cheapest path (by the
+//! routing costs of the links, as SPF computes it) and its hops with the fewest hops over the
+//! links that are up. The stretch is the ratio of the two: 1.0 for a path as good as it gets.
+
+use crate::config::SimulatorConfig;
+use crate::routing::{cost_across, distances_to, usable_edges, Destination, RoutingTable};
+use crate::topology::{Fabric, RouterId};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+
+/// Hops walked before a path is declared looping, as in the processor.
+const MAX_HOPS: usize = 100;
+
+/// The path of one router towards one edge, and the best ones it could have taken.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathStretch {
+    pub router: RouterId,
+    pub destination: Destination,
+    /// Routers in the order the packet crosses them, `router` first.
+    pub routers: Vec<RouterId>,
+    /// Sum of the routing costs of the links crossed.
+    pub cost: u32,
+    /// Whether the walk reached the edge router.
+    pub reached: bool,
+    /// Cost of the cheapest path, `None` if the edge cannot be reached.
+    pub optimal_cost: Option<u32>,
+    /// Hops of the shortest path, `None` if the edge cannot be reached.
+    pub min_hops: Option<usize>,
+}
+
+impl PathStretch {
+    pub fn hops(&self) -> usize {
+        self.routers.len() - 1
+    }
+
+    /// Cost of the path over the cheapest one's; `None` if either is missing.
+    pub fn stretch(&self) -> Option<f64> {
+        match self.optimal_cost {
+            Some(optimal) if self.reached => Some(ratio(self.cost as f64, optimal as f64)),
+            _ => None,
+        }
+    }
+
+    /// Hops of the path over the shortest one's; `None` if either is missing.
+    pub fn hop_stretch(&self) -> Option<f64> {
+        match self.min_hops {
+            Some(min) if self.reached => Some(ratio(self.hops() as f64, min as f64)),
+            _ => None,
+        }
+    }
+
+    /// Whether the path is worse than the best one by cost or by hops.
+    pub fn stretched(&self) -> bool {
+        self.stretch().is_some_and(|s| s > 1.0) || self.hop_stretch().is_some_and(|s| s > 1.0)
+    }
+}
+
+/// `value / best`, 1.0 for the empty paths of the edge routers themselves.
+fn ratio(value: f64, best: f64) -> f64 {
+    if best == 0.0 {
+        1.0
+    } else {
+        value / best
+    }
+}
+
+/// Fewest hops to `dst` over the links that are up.
+fn hops_to(fabric: &Fabric, dst: NodeIndex) -> HashMap<NodeIndex, usize> {
+    let mut hops = HashMap::from([(dst, 0)]);
+    let mut queue = VecDeque::from([dst]);
+    while let Some(node) = queue.pop_front() {
+        let next = hops[&node] + 1;
+        for edge in usable_edges(fabric, node) {
+            hops.entry(edge.target()).or_insert_with(|| {
+                queue.push_back(edge.target());
+                next
+            });
+        }
+    }
+    hops
+}
+
+/// Walk the path the routing tables give from `start` towards `destination`, whose edge router
+/// is `edge`. Returns the routers crossed, their cost and whether the walk got to `edge`.
+fn walk(
+    fabric: &Fabric,
+    tables: &HashMap<RouterId, RoutingTable>,
+    start: &RouterId,
+    destination: Destination,
+    edge: &RouterId,
+) -> (Vec<RouterId>, u32, bool) {
+    let mut routers = vec![start.clone()];
+    let mut cost: u32 = 0;
+    let mut at = start.clone();
+    for _ in 0..MAX_HOPS {
+        let Some(table) = tables.get(&at) else {
+            break;
+        };
+        let next = match destination {
+            Destination::TunA => &table.tun_a.next_hop,
+            Destination::TunB => &table.tun_b.next_hop,
+        };
+        if *next == at {
+            break;
+        }
+        let (Some(&from), Some(&to)) =
+            (fabric.router_index.get(&at), fabric.router_index.get(next))
+        else {
+            break;
+        };
+        let crossing = usable_edges(fabric, from)
+            .filter(|e| e.target() == to)
+            .map(|e| cost_across(fabric, e.weight(), from, to))
+            .min();
+        let Some(crossing) = crossing else {
+            break;
+        };
+        cost = cost.saturating_add(crossing);
+        routers.push(next.clone());
+        at = next.clone();
+    }
+    let reached = at == *edge;
+    (routers, cost, reached)
+}
+
+/// Compare the path of every router towards tun_a and tun_b with the best ones.
+pub fn analyze(cfg: &SimulatorConfig) -> Vec<PathStretch> {
+    let fabric = crate::build_fabric(cfg);
+    let tables = crate::compute_routing_tables(cfg);
+    let mut ids: Vec<&RouterId> = fabric.router_index.keys().collect();
+    ids.sort();
+    let mut paths = Vec::new();
+    for (destination, edge_id) in [
+        (
+            Destination::TunA,
+            RouterId(cfg.tun_ingress.tun_a_ingress.clone()),
+        ),
+        (
+            Destination::TunB,
+            RouterId(cfg.tun_ingress.tun_b_ingress.clone()),
+        ),
+    ] {
+        let Some(&edge) = fabric.router_index.get(&edge_id) else {
+            continue;
+        };
+        let costs = distances_to(&fabric, edge);
+        let hops = hops_to(&fabric, edge);
+        for id in &ids {
+            let node = fabric.router_index[*id];
+            let (routers, cost, reached) = walk(&fabric, &tables, id, destination, &edge_id);
+            paths.push(PathStretch {
+                router: (*id).clone(),
+                destination,
+                routers,
+                cost,
+                reached,
+                optimal_cost: costs.get(&node).copied(),
+                min_hops: hops.get(&node).copied(),
+            });
+        }
+    }
+    paths
+}
+
+/// Human‑readable report of [`analyze`].
+pub fn render(paths: &[PathStretch]) -> String {
+    let mut out = String::new();
+    for path in paths {
+        let edge = match path.destination {
+            Destination::TunA => "tun_a",
+            Destination::TunB => "tun_b",
+        };
+        let verdict = match (path.stretch(), path.hop_stretch()) {
+            (Some(stretch), Some(hop_stretch)) => format!(
+                "{}cost {} (optimum {}, stretch {:.2}), {} hops (minimum {}, stretch {:.2})",
+                if path.stretched() { "STRETCHED: " } else { "" },
+                path.cost,
+                path.optimal_cost.unwrap_or_default(),
+                stretch,
+                path.hops(),
+                path.min_hops.unwrap_or_default(),
+                hop_stretch
+            ),
+            _ if path.optimal_cost.is_none() => "unreachable".to_string(),
+            _ => "NOT REACHED: the routes do not get there".to_string(),
+        };
+        let _ = writeln!(out, "{} -> {}: {}", path.router.0, edge, verdict);
+    }
+    let measured: Vec<(f64, f64)> = paths
+        .iter()
+        .filter_map(|p| Some((p.stretch()?, p.hop_stretch()?)))
+        .collect();
+    let stretched = paths.iter().filter(|p| p.stretched()).count();
+    let _ = write!(out, "{} of {} paths stretched", stretched, paths.len());
+    if !measured.is_empty() {
+        let n = measured.len() as f64;
+        let _ = write!(
+            out,
+            "; cost stretch mean {:.2} max {:.2}, hop stretch mean {:.2} max {:.2}",
+            measured.iter().map(|m| m.0).sum::<f64>() / n,
+            measured.iter().map(|m| m.0).fold(1.0, f64::max),
+            measured.iter().map(|m| m.1).sum::<f64>() / n,
+            measured.iter().map(|m| m.1).fold(1.0, f64::max),
+        );
+    }
+    out.push('\n');
+    out
+}
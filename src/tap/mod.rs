@@ -0,0 +1,170 @@
+// src/tap/mod.rs
+
+//! Ethernet side of an edge opened as a TAP device.
+//!
+//! With `tap = true` on a real edge interface the simulator owns an L2 segment instead of a
+//! point‑to‑point L3 link. The edge `address` then belongs to the simulated router: it answers
+//! ARP for it, strips the Ethernet header of frames before they enter the fabric and adds one to
+//! packets leaving the fabric. Destination MACs are learned from the source addresses of
+//! received frames; packets for hosts not seen yet are sent to the broadcast MAC. MPLS frames
+//! are taken too: their label stack is parsed and kept with the packet, which leaves the fabric
+//! unlabeled. 802.1Q tags are taken off before a frame is classified (see
+//! [`crate::packet::vlan`]) and put back on the frame a packet leaves the fabric in.
+
+use crate::packet::mpls::{ETHERTYPE_MPLS, ETHERTYPE_MPLS_MULTICAST};
+use crate::packet::vlan::{self, VlanTag};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tracing::debug;
+
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+pub const ETHERTYPE_ARP: u16 = 0x0806;
+pub const ETHERTYPE_IPV6: u16 = 0x86DD;
+pub const BROADCAST_MAC: [u8; 6] = [0xff; 6];
+
+const ETHERNET_HEADER_LEN: usize = 14;
+
+/// What to do with a frame read from the TAP device.
+#[derive(Debug, PartialEq)]
+pub enum TapInput<'a> {
+    /// Answer on the same device (an ARP reply).
+    Reply(Vec<u8>),
+    /// IP packet to hand to the fabric.
+    Packet(&'a [u8]),
+    /// MPLS label stack and the IP packet under it.
+    Labeled(&'a [u8]),
+    /// Not for us, or not understood.
+    Ignore,
+}
+
+/// The simulated router's interface on one TAP edge.
+#[derive(Debug)]
+pub struct TapEdge {
+    pub mac: [u8; 6],
+    pub address: Ipv4Addr,
+    neighbours: HashMap<IpAddr, [u8; 6]>,
+}
+
+impl TapEdge {
+    pub fn new(mac: [u8; 6], address: Ipv4Addr) -> Self {
+        Self {
+            mac,
+            address,
+            neighbours: HashMap::new(),
+        }
+    }
+
+    /// MAC address learned for `ip`, if a frame from it has been seen.
+    pub fn neighbour(&self, ip: &IpAddr) -> Option<[u8; 6]> {
+        self.neighbours.get(ip).copied()
+    }
+
+    /// Classify a received Ethernet frame, learning the sender's MAC along the way.
+    pub fn receive<'a>(&mut self, frame: &'a [u8]) -> TapInput<'a> {
+        if frame.len() < ETHERNET_HEADER_LEN {
+            return TapInput::Ignore;
+        }
+        let dst: [u8; 6] = frame[0..6].try_into().unwrap();
+        let src: [u8; 6] = frame[6..12].try_into().unwrap();
+        if dst != self.mac && dst[0] & 0x01 == 0 {
+            // Unicast to another station on the segment.
+            return TapInput::Ignore;
+        }
+        let payload = &frame[ETHERNET_HEADER_LEN..];
+        match u16::from_be_bytes([frame[12], frame[13]]) {
+            ETHERTYPE_ARP => self.arp(src, payload),
+            ETHERTYPE_IPV4 if payload.len() >= 20 => {
+                let sender = Ipv4Addr::new(payload[12], payload[13], payload[14], payload[15]);
+                if !sender.is_unspecified() {
+                    self.neighbours.insert(IpAddr::V4(sender), src);
+                }
+                TapInput::Packet(payload)
+            }
+            ETHERTYPE_IPV6 if payload.len() >= 40 => {
+                let octets: [u8; 16] = payload[8..24].try_into().unwrap();
+                let sender = Ipv6Addr::from(octets);
+                if !sender.is_unspecified() {
+                    self.neighbours.insert(IpAddr::V6(sender), src);
+                }
+                TapInput::Packet(payload)
+            }
+            ETHERTYPE_MPLS | ETHERTYPE_MPLS_MULTICAST => TapInput::Labeled(payload),
+            _ => TapInput::Ignore,
+        }
+    }
+
+    /// Answer ARP requests for the router address.
+    fn arp<'a>(&mut self, src: [u8; 6], arp: &[u8]) -> TapInput<'a> {
+        // Ethernet/IPv4 ARP only: htype 1, ptype 0x0800, hlen 6, plen 4.
+        if arp.len() < 28 || arp[0..6] != [0, 1, 8, 0, 6, 4] {
+            return TapInput::Ignore;
+        }
+        let sender_ip = Ipv4Addr::new(arp[14], arp[15], arp[16], arp[17]);
+        let target_ip = Ipv4Addr::new(arp[24], arp[25], arp[26], arp[27]);
+        if !sender_ip.is_unspecified() {
+            self.neighbours.insert(IpAddr::V4(sender_ip), src);
+        }
+        if u16::from_be_bytes([arp[6], arp[7]]) != 1 || target_ip != self.address {
+            return TapInput::Ignore;
+        }
+        debug!("ARP request for {} from {}", target_ip, sender_ip);
+        let mut reply = Vec::with_capacity(28);
+        reply.extend_from_slice(&[0, 1, 8, 0, 6, 4, 0, 2]);
+        reply.extend_from_slice(&self.mac);
+        reply.extend_from_slice(&self.address.octets());
+        reply.extend_from_slice(&arp[8..14]);
+        reply.extend_from_slice(&sender_ip.octets());
+        TapInput::Reply(self.frame(&arp[8..14].try_into().unwrap(), ETHERTYPE_ARP, &reply))
+    }
+
+    /// Ethernet frame from the router to `dst` carrying `payload`.
+    pub fn frame(&self, dst: &[u8; 6], ethertype: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(ETHERNET_HEADER_LEN + payload.len());
+        frame.extend_from_slice(dst);
+        frame.extend_from_slice(&self.mac);
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    /// Like [`frame`](Self::frame), with an 802.1Q tag when `vlan` is set.
+    pub fn frame_tagged(
+        &self,
+        dst: &[u8; 6],
+        ethertype: u16,
+        payload: &[u8],
+        vlan: Option<VlanTag>,
+    ) -> Vec<u8> {
+        let frame = self.frame(dst, ethertype, payload);
+        match vlan {
+            Some(tag) => vlan::tag(&frame, tag),
+            None => frame,
+        }
+    }
+
+    /// Frame an IP packet leaving the fabric towards the host it is addressed to.
+    pub fn encapsulate(&self, packet: &[u8]) -> Vec<u8> {
+        self.encapsulate_tagged(packet, None)
+    }
+
+    /// Like [`encapsulate`](Self::encapsulate), with an 802.1Q tag when `vlan` is set.
+    pub fn encapsulate_tagged(&self, packet: &[u8], vlan: Option<VlanTag>) -> Vec<u8> {
+        let (ethertype, dst) = match packet.first().map(|b| b >> 4) {
+            Some(6) if packet.len() >= 40 => {
+                let octets: [u8; 16] = packet[24..40].try_into().unwrap();
+                (ETHERTYPE_IPV6, Some(IpAddr::V6(Ipv6Addr::from(octets))))
+            }
+            Some(4) if packet.len() >= 20 => (
+                ETHERTYPE_IPV4,
+                Some(IpAddr::V4(Ipv4Addr::new(
+                    packet[16], packet[17], packet[18], packet[19],
+                ))),
+            ),
+            _ => (ETHERTYPE_IPV4, None),
+        };
+        let mac = dst
+            .and_then(|ip| self.neighbour(&ip))
+            .unwrap_or(BROADCAST_MAC);
+        self.frame_tagged(&mac, ethertype, packet, vlan)
+    }
+}
@@ -0,0 +1,227 @@
+// src/telemetry/dashboard.rs
+
+//! Built‑in topology dashboard.
+//!
+//! `dashboard_listen` serves a self‑contained HTML page drawing the routers on their `RxNyM`
+//! grid and the links between them, recolored with every published telemetry sample: green to
+//! red by utilization of the link bandwidth (both directions together), dashed red while the
+//! link loses packets. Nothing is loaded from outside, so it works for demos without network
+//! access or external tooling.
+//!
+//! - `GET /` returns the page;
+//! - `GET /topology` the routers and links as JSON;
+//! - `GET /events` a Server‑Sent Events stream with the link counters of each sample, ending
+//!   with the run.
+
+use super::Sample;
+use crate::topology::Fabric;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// Most bytes read of a request line and its headers together; longer requests are refused.
+const MAX_REQUEST_HEAD: u64 = 8 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardLink {
+    pub name: String,
+    pub a: String,
+    pub b: String,
+    pub bandwidth_mbps: Option<f64>,
+}
+
+/// Routers and links of the fabric, as drawn by the page.
+#[derive(Debug, Clone, Serialize)]
+pub struct Topology {
+    pub routers: Vec<String>,
+    pub links: Vec<DashboardLink>,
+}
+
+impl Topology {
+    pub fn of(fabric: &Fabric) -> Self {
+        let mut routers: Vec<String> = fabric
+            .graph
+            .node_weights()
+            .map(|r| r.id.0.clone())
+            .collect();
+        routers.sort();
+        let mut links: Vec<DashboardLink> = fabric
+            .graph
+            .edge_weights()
+            .map(|link| DashboardLink {
+                name: format!("{}_{}", link.id.a.0, link.id.b.0),
+                a: link.id.a.0.clone(),
+                b: link.id.b.0.clone(),
+                bandwidth_mbps: link.cfg.bandwidth_mbps,
+            })
+            .collect();
+        links.sort_by(|x, y| x.name.cmp(&y.name));
+        Self { routers, links }
+    }
+}
+
+/// Counters of one link in a sample.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LinkCounters {
+    pub pkts: u64,
+    pub octets: u64,
+    pub discards: u64,
+}
+
+/// Link counters of `sample` as the JSON of one event: `{"at_ms": …, "links": {name: …}}`.
+pub fn event(sample: &Sample) -> String {
+    let mut links: BTreeMap<&str, LinkCounters> = BTreeMap::new();
+    for (path, value) in &sample.counters {
+        let (Some(list), Some(entry), Some(counter)) =
+            (path.0.first(), path.0.get(1), path.0.last())
+        else {
+            continue;
+        };
+        let Some(name) = entry.key.as_deref() else {
+            continue;
+        };
+        if list.name != "interfaces" {
+            continue;
+        }
+        let link = links.entry(name).or_default();
+        match counter.name.as_str() {
+            "out-pkts" => link.pkts = *value,
+            "out-octets" => link.octets = *value,
+            "out-discards" => link.discards = *value,
+            _ => {}
+        }
+    }
+    let at_ms = sample
+        .at
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    serde_json::json!({ "at_ms": at_ms, "links": links }).to_string()
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(body.as_bytes()).await;
+}
+
+/// Answer one request; the connection is closed afterwards.
+async fn handle(
+    mut stream: TcpStream,
+    topology: Arc<String>,
+    mut samples: watch::Receiver<Arc<Sample>>,
+) {
+    let mut reader = BufReader::new(&mut stream).take(MAX_REQUEST_HEAD);
+    let mut request = String::new();
+    // Read up to the empty line ending the headers; the limit ends the stream early.
+    let mut complete = false;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) if !line.ends_with('\n') => break,
+            Ok(_) if request.is_empty() => request = line.clone(),
+            Ok(_) if line.trim_end().is_empty() => {
+                complete = true;
+                break;
+            }
+            Ok(_) => {}
+        }
+    }
+    let too_large = reader.limit() == 0;
+    drop(reader);
+    if !complete {
+        if too_large {
+            respond(
+                &mut stream,
+                "431 Request Header Fields Too Large",
+                "text/plain",
+                "request too large\n",
+            )
+            .await;
+        }
+        return;
+    }
+    let mut parts = request.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    debug!("dashboard: {} {}", method, target);
+    if method != "GET" {
+        respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            "GET only\n",
+        )
+        .await;
+        return;
+    }
+    match target.split('?').next().unwrap_or("") {
+        "/" | "/index.html" => {
+            respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE).await
+        }
+        "/topology" => respond(&mut stream, "200 OK", "application/json", &topology).await,
+        "/events" => {
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n";
+            if stream.write_all(head.as_bytes()).await.is_err() {
+                return;
+            }
+            let mut current = samples.borrow_and_update().clone();
+            loop {
+                let data = format!("data: {}\n\n", event(&current));
+                if stream.write_all(data.as_bytes()).await.is_err() {
+                    return;
+                }
+                if samples.changed().await.is_err() {
+                    // The run is over.
+                    let _ = stream.write_all(b"event: end\ndata: {}\n\n").await;
+                    return;
+                }
+                current = samples.borrow_and_update().clone();
+            }
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "not found\n").await,
+    }
+}
+
+/// Serve the dashboard of `topology` on `listener` until the run ends, i.e. `samples` is closed.
+pub async fn serve(
+    listener: TcpListener,
+    topology: Topology,
+    samples: watch::Receiver<Arc<Sample>>,
+) -> Result<(), String> {
+    let addr = listener.local_addr().map_err(|e| e.to_string())?;
+    let topology = Arc::new(serde_json::to_string(&topology).map_err(|e| e.to_string())?);
+    let mut closed = samples.clone();
+    info!("Topology dashboard on http://{}/", addr);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle(stream, topology.clone(), samples.clone()));
+                    }
+                    // E.g. out of file descriptors or a connection reset before it was
+                    // accepted: the dashboard keeps serving the others.
+                    Err(e) => warn!("dashboard {}: accept failed: {}", addr, e),
+                }
+            }
+            res = closed.changed() => {
+                if res.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,451 @@
+// src/telemetry/gnmi.rs
+
+//! gNMI Subscribe server (feature `gnmi`).
+//!
+//! Serves `gnmi.gNMI/Subscribe` from the published telemetry samples. The messages below are
+//! the subset of `gnmi.proto` the service uses, with the upstream field numbers, so standard
+//! collectors (gnmic, Telegraf's gnmi input) can subscribe. Counters are sent as `uint_val`
+//! updates with full paths and the sample time as the notification timestamp.
+//!
+//! - `ONCE`: the current sample, then `sync_response`, then the stream ends.
+//! - `POLL`: the current sample and `sync_response`, then the same for every `Poll` request.
+//! - `STREAM`: the current sample (unless `updates_only`) and `sync_response`, then every
+//!   `sample_interval` (the shortest one requested), or, without one, every published sample,
+//!   sending only the counters that changed for `ON_CHANGE` subscriptions.
+//!
+//! Streams end with the run.
+
+use super::{Path as CounterPath, PathElem as CounterElem, Sample};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use tokio::sync::{mpsc, watch};
+use tonic::codegen::{
+    empty_body, http, Body, BoxFuture, Context, Poll as TaskPoll, Service, StdError,
+};
+use tonic::{Status, Streaming};
+use tracing::{debug, info};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PathElem {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(map = "string, string", tag = "2")]
+    pub key: HashMap<String, String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Path {
+    #[prost(string, tag = "2")]
+    pub origin: String,
+    #[prost(message, repeated, tag = "3")]
+    pub elem: Vec<PathElem>,
+    #[prost(string, tag = "4")]
+    pub target: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TypedValue {
+    #[prost(oneof = "typed_value::Value", tags = "3")]
+    pub value: Option<typed_value::Value>,
+}
+
+pub mod typed_value {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Value {
+        #[prost(uint64, tag = "3")]
+        UintVal(u64),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Update {
+    #[prost(message, optional, tag = "1")]
+    pub path: Option<Path>,
+    #[prost(message, optional, tag = "3")]
+    pub val: Option<TypedValue>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Notification {
+    /// Nanoseconds since the Unix epoch.
+    #[prost(int64, tag = "1")]
+    pub timestamp: i64,
+    #[prost(message, optional, tag = "2")]
+    pub prefix: Option<Path>,
+    #[prost(message, repeated, tag = "4")]
+    pub update: Vec<Update>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeResponse {
+    #[prost(oneof = "subscribe_response::Response", tags = "1, 3")]
+    pub response: Option<subscribe_response::Response>,
+}
+
+pub mod subscribe_response {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Response {
+        #[prost(message, tag = "1")]
+        Update(super::Notification),
+        #[prost(bool, tag = "3")]
+        SyncResponse(bool),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Subscription {
+    #[prost(message, optional, tag = "1")]
+    pub path: Option<Path>,
+    #[prost(enumeration = "SubscriptionMode", tag = "2")]
+    pub mode: i32,
+    /// Nanoseconds between samples.
+    #[prost(uint64, tag = "3")]
+    pub sample_interval: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum SubscriptionMode {
+    TargetDefined = 0,
+    OnChange = 1,
+    Sample = 2,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscriptionList {
+    #[prost(message, optional, tag = "1")]
+    pub prefix: Option<Path>,
+    #[prost(message, repeated, tag = "2")]
+    pub subscription: Vec<Subscription>,
+    #[prost(enumeration = "subscription_list::Mode", tag = "5")]
+    pub mode: i32,
+    #[prost(bool, tag = "9")]
+    pub updates_only: bool,
+}
+
+pub mod subscription_list {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Mode {
+        Stream = 0,
+        Once = 1,
+        Poll = 2,
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Poll {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeRequest {
+    #[prost(oneof = "subscribe_request::Request", tags = "1, 3")]
+    pub request: Option<subscribe_request::Request>,
+}
+
+pub mod subscribe_request {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Request {
+        #[prost(message, tag = "1")]
+        Subscribe(super::SubscriptionList),
+        #[prost(message, tag = "3")]
+        Poll(super::Poll),
+    }
+}
+
+/// gNMI path of a counter path.
+fn to_gnmi(path: &CounterPath) -> Path {
+    Path {
+        elem: path
+            .0
+            .iter()
+            .map(|e| PathElem {
+                name: e.name.clone(),
+                key: e
+                    .key
+                    .iter()
+                    .map(|k| ("name".to_string(), k.clone()))
+                    .collect(),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Counter path of a gNMI path; of the keys only `name` is looked at.
+fn from_gnmi(path: &Path) -> CounterPath {
+    CounterPath(
+        path.elem
+            .iter()
+            .map(|e| CounterElem {
+                name: e.name.clone(),
+                key: e.key.get("name").cloned(),
+            })
+            .collect(),
+    )
+}
+
+/// Notification of the counters of `sample` that `wanted` accepts.
+fn notification(sample: &Sample, wanted: impl Fn(&CounterPath, u64) -> bool) -> Notification {
+    let timestamp = sample
+        .at
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or(0);
+    Notification {
+        timestamp,
+        prefix: None,
+        update: sample
+            .counters
+            .iter()
+            .filter(|(path, value)| wanted(path, *value))
+            .map(|(path, value)| Update {
+                path: Some(to_gnmi(path)),
+                val: Some(TypedValue {
+                    value: Some(typed_value::Value::UintVal(*value)),
+                }),
+            })
+            .collect(),
+    }
+}
+
+/// One client's subscription.
+struct Subscriber {
+    /// Subscription paths, already joined with the prefix.
+    paths: Vec<CounterPath>,
+    on_change: bool,
+    tx: mpsc::Sender<Result<SubscribeResponse, Status>>,
+}
+
+impl Subscriber {
+    fn covers(&self, path: &CounterPath) -> bool {
+        self.paths.iter().any(|p| p.covers(path))
+    }
+
+    /// Send the covered counters of `sample` that `changed` accepts; `false` once the client
+    /// is gone.
+    async fn send(&self, sample: &Sample, changed: impl Fn(&CounterPath, u64) -> bool) -> bool {
+        let update = notification(sample, |path, value| {
+            self.covers(path) && changed(path, value)
+        });
+        if update.update.is_empty() {
+            return true;
+        }
+        let response = SubscribeResponse {
+            response: Some(subscribe_response::Response::Update(update)),
+        };
+        self.tx.send(Ok(response)).await.is_ok()
+    }
+
+    async fn sync(&self) -> bool {
+        let response = SubscribeResponse {
+            response: Some(subscribe_response::Response::SyncResponse(true)),
+        };
+        self.tx.send(Ok(response)).await.is_ok()
+    }
+}
+
+/// Serve one Subscribe call until the client leaves or the run ends.
+async fn subscribe(
+    mut requests: Streaming<SubscribeRequest>,
+    mut samples: watch::Receiver<Arc<Sample>>,
+    tx: mpsc::Sender<Result<SubscribeResponse, Status>>,
+) {
+    let list = match requests.message().await {
+        Ok(Some(SubscribeRequest {
+            request: Some(subscribe_request::Request::Subscribe(list)),
+        })) => list,
+        _ => {
+            let status = Status::invalid_argument("the first request must be a SubscriptionList");
+            let _ = tx.send(Err(status)).await;
+            return;
+        }
+    };
+    let prefix = list.prefix.as_ref().map(from_gnmi).unwrap_or_default();
+    let mut paths: Vec<CounterPath> = list
+        .subscription
+        .iter()
+        .map(|s| {
+            let mut path = prefix.clone();
+            path.0
+                .extend(s.path.as_ref().map(from_gnmi).unwrap_or_default().0);
+            path
+        })
+        .collect();
+    if paths.is_empty() {
+        paths.push(prefix);
+    }
+    let on_change = !list.subscription.is_empty()
+        && list
+            .subscription
+            .iter()
+            .all(|s| s.mode == SubscriptionMode::OnChange as i32);
+    let interval = list
+        .subscription
+        .iter()
+        .filter(|s| s.mode != SubscriptionMode::OnChange as i32 && s.sample_interval > 0)
+        .map(|s| std::time::Duration::from_nanos(s.sample_interval))
+        .min();
+    let subscriber = Subscriber {
+        paths,
+        on_change,
+        tx,
+    };
+    let mode =
+        subscription_list::Mode::try_from(list.mode).unwrap_or(subscription_list::Mode::Stream);
+    debug!(
+        "gNMI subscription {:?} to {} paths",
+        mode,
+        subscriber.paths.len()
+    );
+
+    let mut last = samples.borrow_and_update().clone();
+    let initial = !(mode == subscription_list::Mode::Stream && list.updates_only);
+    if initial && !subscriber.send(&last, |_, _| true).await {
+        return;
+    }
+    if !subscriber.sync().await {
+        return;
+    }
+    match mode {
+        subscription_list::Mode::Once => {}
+        subscription_list::Mode::Poll => {
+            while let Ok(Some(request)) = requests.message().await {
+                if !matches!(request.request, Some(subscribe_request::Request::Poll(_))) {
+                    continue;
+                }
+                let current = samples.borrow().clone();
+                if !subscriber.send(&current, |_, _| true).await || !subscriber.sync().await {
+                    return;
+                }
+            }
+        }
+        subscription_list::Mode::Stream => loop {
+            match interval {
+                Some(interval) => {
+                    tokio::time::sleep(interval).await;
+                    if samples.has_changed().is_err() {
+                        return;
+                    }
+                }
+                None => {
+                    if samples.changed().await.is_err() {
+                        return;
+                    }
+                }
+            }
+            let current = samples.borrow_and_update().clone();
+            let sent = if subscriber.on_change {
+                let previous = &last;
+                subscriber
+                    .send(&current, |path, value| {
+                        previous
+                            .counters
+                            .binary_search_by(|(p, _)| p.cmp(path))
+                            .map_or(true, |i| previous.counters[i].1 != value)
+                    })
+                    .await
+            } else {
+                subscriber.send(&current, |_, _| true).await
+            };
+            if !sent {
+                return;
+            }
+            last = current;
+        },
+    }
+}
+
+/// The gNMI service, serving the samples published to `samples`.
+#[derive(Clone)]
+pub struct GnmiServer {
+    samples: watch::Receiver<Arc<Sample>>,
+}
+
+impl GnmiServer {
+    pub fn new(samples: watch::Receiver<Arc<Sample>>) -> Self {
+        Self { samples }
+    }
+}
+
+type ResponseStream =
+    std::pin::Pin<Box<dyn futures::Stream<Item = Result<SubscribeResponse, Status>> + Send>>;
+
+struct SubscribeSvc(watch::Receiver<Arc<Sample>>);
+
+impl tonic::server::StreamingService<SubscribeRequest> for SubscribeSvc {
+    type Response = SubscribeResponse;
+    type ResponseStream = ResponseStream;
+    type Future = BoxFuture<tonic::Response<ResponseStream>, Status>;
+
+    fn call(&mut self, request: tonic::Request<Streaming<SubscribeRequest>>) -> Self::Future {
+        let samples = self.0.clone();
+        Box::pin(async move {
+            let (tx, rx) = mpsc::channel(16);
+            tokio::spawn(subscribe(request.into_inner(), samples, tx));
+            let stream = futures::stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|item| (item, rx))
+            });
+            Ok(tonic::Response::new(Box::pin(stream) as ResponseStream))
+        })
+    }
+}
+
+impl<B> Service<http::Request<B>> for GnmiServer
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> TaskPoll<Result<(), Self::Error>> {
+        TaskPoll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if req.uri().path() == "/gnmi.gNMI/Subscribe" {
+            let svc = SubscribeSvc(self.samples.clone());
+            return Box::pin(async move {
+                let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::default());
+                Ok(grpc.streaming(svc, req).await)
+            });
+        }
+        Box::pin(async move {
+            let mut response = http::Response::new(empty_body());
+            let headers = response.headers_mut();
+            headers.insert(
+                tonic::Status::GRPC_STATUS,
+                (tonic::Code::Unimplemented as i32).into(),
+            );
+            headers.insert(
+                http::header::CONTENT_TYPE,
+                tonic::metadata::GRPC_CONTENT_TYPE,
+            );
+            Ok(response)
+        })
+    }
+}
+
+impl tonic::server::NamedService for GnmiServer {
+    const NAME: &'static str = "gnmi.gNMI";
+}
+
+/// Serve gNMI on `listener` until the run ends, i.e. `samples` is closed.
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    samples: watch::Receiver<Arc<Sample>>,
+) -> Result<(), String> {
+    let addr = listener.local_addr().map_err(|e| e.to_string())?;
+    let incoming = tonic::transport::server::TcpIncoming::from_listener(listener, true, None)
+        .map_err(|e| format!("gNMI listener {}: {}", addr, e))?;
+    let mut closed = samples.clone();
+    let shutdown = async move { while closed.changed().await.is_ok() {} };
+    info!("gNMI server listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(GnmiServer::new(samples))
+        .serve_with_incoming_shutdown(incoming, shutdown)
+        .await
+        .map_err(|e| format!("gNMI server {}: {}", addr, e))
+}
@@ -0,0 +1,247 @@
+// src/telemetry/mod.rs
+
+//! Streaming telemetry of the router and link counters.
+//!
+//! With `[telemetry]` the run loop samples the fabric every `interval_ms` and publishes the
+//! sample to its subscribers — the topology page of [`dashboard`] and the gNMI Subscribe server
+//! of the `gnmi` feature — so people and collectors that watch real devices can follow a running
+//! simulation. Counters are named by OpenConfig‑style paths:
+//!
+//! - `/network-instances/network-instance[name=<router>]/state/counters/<counter>` with
+//!   `packets-received`, `packets-forwarded`, `packets-lost`, `packets-delivered`,
+//!   `icmp-generated`, `cpu-drops` and `acl-drops`;
+//! - `/interfaces/interface[name=<link>]/state/counters/<counter>` with `out-pkts`,
+//!   `out-octets`, `out-discards` and `out-too-big`, for every link named `<a>_<b>`.
+
+use crate::config::TelemetryConfig;
+use crate::topology::Fabric;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::watch;
+use tokio::time::{Duration, Instant};
+
+pub mod dashboard;
+#[cfg(feature = "gnmi")]
+pub mod gnmi;
+
+/// One element of a path, with its `name` key if it has one.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PathElem {
+    pub name: String,
+    pub key: Option<String>,
+}
+
+/// OpenConfig‑style path of a counter, e.g. `/interfaces/interface[name=A_B]/state/counters`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Path(pub Vec<PathElem>);
+
+impl Path {
+    /// Parse `/a/b[name=x]/c`; keys other than `name` are not supported.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut elems = Vec::new();
+        for part in s.split('/').filter(|p| !p.is_empty()) {
+            let elem = match part.split_once('[') {
+                Some((name, key)) => {
+                    let value = key
+                        .strip_suffix(']')
+                        .and_then(|k| k.strip_prefix("name="))
+                        .ok_or_else(|| format!("invalid path element '{}' in '{}'", part, s))?;
+                    PathElem {
+                        name: name.to_string(),
+                        key: Some(value.to_string()),
+                    }
+                }
+                None => PathElem {
+                    name: part.to_string(),
+                    key: None,
+                },
+            };
+            elems.push(elem);
+        }
+        Ok(Self(elems))
+    }
+
+    /// Whether `self`, a subscription path, covers `path`: its elements are a prefix of those
+    /// of `path`, an element without a key (or with key `*`) matching any key.
+    pub fn covers(&self, path: &Path) -> bool {
+        self.0.len() <= path.0.len()
+            && self.0.iter().zip(&path.0).all(|(want, have)| {
+                want.name == have.name
+                    && match want.key.as_deref() {
+                        None | Some("*") => true,
+                        key => key == have.key.as_deref(),
+                    }
+            })
+    }
+
+    fn counter(list: &str, entry: &str, name: &str, counter: &str) -> Self {
+        let elem = |name: &str, key: Option<&str>| PathElem {
+            name: name.to_string(),
+            key: key.map(str::to_string),
+        };
+        Self(vec![
+            elem(list, None),
+            elem(entry, Some(name)),
+            elem("state", None),
+            elem("counters", None),
+            elem(counter, None),
+        ])
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "/");
+        }
+        for elem in &self.0 {
+            write!(f, "/{}", elem.name)?;
+            if let Some(key) = &elem.key {
+                write!(f, "[name={}]", key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counter values of the fabric at one point in time, sorted by path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub at: SystemTime,
+    pub counters: Vec<(Path, u64)>,
+}
+
+impl Sample {
+    pub fn get(&self, path: &str) -> Option<u64> {
+        let path = Path::parse(path).ok()?;
+        self.counters
+            .iter()
+            .find(|(p, _)| *p == path)
+            .map(|(_, v)| *v)
+    }
+}
+
+/// The counters of `fabric` under their OpenConfig‑style paths.
+pub fn sample(fabric: &Fabric) -> Sample {
+    let mut counters = Vec::new();
+    for (id, stats) in fabric.get_statistics() {
+        let router = |counter: &str, value: u64| {
+            (
+                Path::counter("network-instances", "network-instance", &id.0, counter),
+                value,
+            )
+        };
+        counters.extend([
+            router("packets-received", stats.packets_received),
+            router("packets-forwarded", stats.packets_forwarded),
+            router("packets-lost", stats.packets_lost),
+            router("packets-delivered", stats.packets_delivered),
+            router("icmp-generated", stats.icmp_generated),
+            router("icmp-suppressed", stats.icmp_suppressed),
+            router("cpu-drops", stats.cpu_drops),
+            router("acl-drops", stats.acl_drops),
+            router("policer-drops", stats.policer_drops),
+            router("bad-checksums", stats.bad_checksums),
+            router("bytes-received", stats.bytes_received),
+            router("bytes-forwarded", stats.bytes_forwarded),
+            router("tcp-received", stats.tcp_received),
+            router("udp-received", stats.udp_received),
+            router("icmp-received", stats.icmp_received),
+            router("other-received", stats.other_received),
+        ]);
+    }
+    for link in fabric.graph.edge_weights() {
+        let name = format!("{}_{}", link.id.a.0, link.id.b.0);
+        let interface = |counter: &str, value: u64| {
+            (
+                Path::counter("interfaces", "interface", &name, counter),
+                value,
+            )
+        };
+        counters.extend([
+            interface("out-pkts", link.counter()),
+            interface("out-octets", link.bytes()),
+            interface("out-discards", link.lost()),
+            interface("out-too-big", link.too_big()),
+        ]);
+    }
+    counters.sort();
+    Sample {
+        at: SystemTime::now(),
+        counters,
+    }
+}
+
+/// Publisher of the samples taken during the run.
+#[derive(Debug)]
+pub struct Telemetry {
+    tx: watch::Sender<Arc<Sample>>,
+    interval: Duration,
+    next: Instant,
+    /// Samples published so far.
+    pub published: u64,
+}
+
+impl Telemetry {
+    pub fn new(cfg: &TelemetryConfig) -> Self {
+        let (tx, _) = watch::channel(Arc::new(Sample {
+            at: SystemTime::now(),
+            counters: Vec::new(),
+        }));
+        let interval = Duration::from_millis(cfg.interval_ms);
+        Self {
+            tx,
+            interval,
+            next: Instant::now() + interval,
+            published: 0,
+        }
+    }
+
+    /// Receiver of the latest sample; it sees the end of the run as a closed channel.
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Sample>> {
+        self.tx.subscribe()
+    }
+
+    pub fn next_sample(&self) -> Instant {
+        self.next
+    }
+
+    /// End the subscriptions: receivers see the channel closed, the last sample is kept.
+    pub fn close(&mut self) {
+        let last = self.tx.borrow().clone();
+        self.tx = watch::channel(last).0;
+    }
+
+    /// The last published sample.
+    pub fn last(&self) -> Arc<Sample> {
+        self.tx.borrow().clone()
+    }
+
+    fn publish(&mut self, sample: Sample) {
+        self.tx.send_replace(Arc::new(sample));
+        self.published += 1;
+        self.next = Instant::now() + self.interval;
+    }
+}
+
+/// Publish a sample of `fabric` if the interval is over.
+pub fn tick(fabric: &mut Fabric) {
+    if fabric
+        .telemetry
+        .as_ref()
+        .is_some_and(|t| Instant::now() >= t.next_sample())
+    {
+        publish(fabric);
+    }
+}
+
+/// Publish a sample of `fabric` now.
+pub fn publish(fabric: &mut Fabric) {
+    if fabric.telemetry.is_some() {
+        let sample = sample(fabric);
+        if let Some(telemetry) = fabric.telemetry.as_mut() {
+            telemetry.publish(sample);
+        }
+    }
+}
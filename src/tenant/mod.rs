@@ -0,0 +1,152 @@
+// src/tenant/mod.rs
+
+//! Tenants (VRFs) sharing the fabric.
+//!
+//! Each `[[tenant]]` owns an address space (`prefixes`) and may own 802.1Q VLANs. A packet
+//! entering the fabric belongs to the first tenant carrying its VLAN, else to the tenant with
+//! the most specific prefix containing its source address; packets matching neither belong to
+//! no tenant. A tenant's packets are routed by the `[[route]]` prefixes of the tenant and those
+//! without one, so tenants may reuse the same prefixes behind different routers. A packet
+//! addressed into another tenant's address space, and not into its own, leaks out of its tenant:
+//! it is counted as a leak and dropped where it entered.
+
+use crate::config::TenantConfig;
+use crate::packet::PacketMeta;
+use ipnet::IpNet;
+use serde::Serialize;
+use std::net::IpAddr;
+
+/// Counters of the packets one tenant sent into the fabric.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TenantStats {
+    pub packets: u64,
+    pub bytes: u64,
+    pub delivered: u64,
+    pub dropped: u64,
+    /// Packets addressed into another tenant's address space (also counted as dropped).
+    pub leaks: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tenant {
+    pub name: String,
+    pub prefixes: Vec<IpNet>,
+    pub vlans: Vec<u16>,
+    pub stats: TenantStats,
+}
+
+impl Tenant {
+    pub fn from_config(cfg: &TenantConfig) -> Result<Self, String> {
+        let prefixes = cfg
+            .prefixes
+            .iter()
+            .map(|p| {
+                p.parse::<IpNet>()
+                    .map(|p| p.trunc())
+                    .map_err(|_| format!("Tenant '{}': invalid prefix '{}'", cfg.name, p))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            name: cfg.name.clone(),
+            prefixes,
+            vlans: cfg.vlans.clone(),
+            stats: TenantStats::default(),
+        })
+    }
+
+    /// Length of the most specific of the tenant's prefixes containing `addr`.
+    fn owns(&self, addr: &IpAddr) -> Option<u8> {
+        self.prefixes
+            .iter()
+            .filter(|p| p.contains(addr))
+            .map(|p| p.prefix_len())
+            .max()
+    }
+
+    pub fn summary(&self) -> String {
+        let s = &self.stats;
+        format!(
+            "packets={} ({} bytes), delivered={}, dropped={}, leaks={}",
+            s.packets, s.bytes, s.delivered, s.dropped, s.leaks
+        )
+    }
+}
+
+/// The configured tenants, in configuration order.
+#[derive(Debug, Clone, Default)]
+pub struct Tenants {
+    pub tenants: Vec<Tenant>,
+}
+
+impl Tenants {
+    pub fn from_config(cfg: &[TenantConfig]) -> Result<Self, String> {
+        Ok(Self {
+            tenants: cfg
+                .iter()
+                .map(Tenant::from_config)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tenant> {
+        self.tenants.iter().find(|t| t.name == name)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Tenant> {
+        self.tenants.iter_mut().find(|t| t.name == name)
+    }
+
+    /// Name of the tenant `packet` belongs to: the first carrying its VLAN, else the one with
+    /// the most specific prefix containing its source address (the first on a tie).
+    pub fn classify(&self, packet: &PacketMeta) -> Option<&str> {
+        if let Some(tag) = packet.vlan {
+            if let Some(tenant) = self.tenants.iter().find(|t| t.vlans.contains(&tag.vid)) {
+                return Some(&tenant.name);
+            }
+        }
+        let mut best: Option<(u8, &Tenant)> = None;
+        for tenant in &self.tenants {
+            if let Some(len) = tenant.owns(&packet.src_ip) {
+                if best.is_none_or(|(l, _)| len > l) {
+                    best = Some((len, tenant));
+                }
+            }
+        }
+        best.map(|(_, t)| t.name.as_str())
+    }
+
+    /// Whether `packet` of `tenant` is addressed into another tenant's address space and not
+    /// into its own; a leak is counted if so.
+    pub fn leaked(&mut self, tenant: &str, packet: &PacketMeta) -> bool {
+        let Some(own) = self.get(tenant) else {
+            return false;
+        };
+        if own.owns(&packet.dst_ip).is_some() {
+            return false;
+        }
+        let leaked = self
+            .tenants
+            .iter()
+            .any(|t| t.name != tenant && t.owns(&packet.dst_ip).is_some());
+        if leaked {
+            if let Some(own) = self.get_mut(tenant) {
+                own.stats.leaks += 1;
+            }
+        }
+        leaked
+    }
+
+    /// Count a packet of `bytes` that `tenant` sent into the fabric, and whether it was
+    /// delivered.
+    pub fn account(&mut self, tenant: &str, bytes: usize, delivered: bool) {
+        if let Some(t) = self.get_mut(tenant) {
+            t.stats.packets += 1;
+            t.stats.bytes += bytes as u64;
+            if delivered {
+                t.stats.delivered += 1;
+            } else {
+                t.stats.dropped += 1;
+            }
+        }
+    }
+}
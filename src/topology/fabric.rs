@@ -1,9 +1,50 @@
 // src/topology/fabric.rs
 
+use crate::alarms::AlarmMonitor;
+use crate::capture::mirror::MirrorPort;
+use crate::capture::CapturePoint;
+use crate::clock::EdgeClocks;
+use crate::config::ChecksumConfig;
+use crate::ddos::DdosReport;
+use crate::dedupe::Deduplicator;
+use crate::drops::{DropCapture, DropReason};
+use crate::events::Faults;
+use crate::explicit_path::ExplicitPath;
+use crate::forwarding::flow_table::FlowTable;
+use crate::forwarding::EcmpHash;
+#[cfg(feature = "http-test")]
+use crate::http::HttpLoadReport;
+use crate::marking::Marking;
+use crate::nat::nat64::Nat64;
+use crate::nat::Nat44;
+use crate::packet::PacketMeta;
+use crate::provenance::RunMetadata;
+use crate::pseudowire::Pseudowire;
+use crate::qos::{Pool, PoolConfig};
+use crate::reassembly::Reassembler;
+use crate::report::PacketTotals;
+use crate::routing::bgp::Bgp;
+use crate::routing::destination_map::DestinationMap;
+use crate::routing::distance_vector::DistanceVector;
+use crate::routing::fib::PrefixRoute;
+use crate::routing::link_state::Convergence;
+use crate::routing::{Destination, RoutingAlgorithm};
+use crate::sla::{FlowMetrics, SlaResult};
+use crate::snapshot::Snapshots;
+use crate::sojourn::{PacketTrace, Sojourn};
+use crate::srv6::Srv6;
+use crate::stats::CounterState;
+use crate::telemetry::Telemetry;
+use crate::tenant::Tenants;
 use crate::topology::{Link, LinkConfig, LinkId, Router, RouterId, RouterStats};
+use crate::ttl::TtlPolicy;
+use crate::tun::counters::{InterfaceCounters, TunCounters};
+use crate::twamp::TwampReport;
+use crate::wireguard::Wireguard;
 use petgraph::graph::EdgeIndex;
 use petgraph::graph::{NodeIndex, UnGraph};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use tracing::info;
 
 #[derive(Debug)]
@@ -11,6 +52,102 @@ pub struct Fabric {
     pub graph: UnGraph<Router, Link>,
     pub router_index: HashMap<RouterId, NodeIndex>,
     pub link_index: HashMap<LinkId, EdgeIndex>,
+    /// Result of the last TWAMP measurement session, if one was configured.
+    pub twamp_report: Option<TwampReport>,
+    /// Outcome of the `[ddos]` flood, if one was configured.
+    pub ddos_report: Option<DdosReport>,
+    /// Result of the built‑in HTTP load client, if it ran.
+    #[cfg(feature = "http-test")]
+    pub http_report: Option<HttpLoadReport>,
+    /// Flow metrics of virtual‑customer traffic, keyed by customer name.
+    pub customer_flows: HashMap<String, FlowMetrics>,
+    /// SLA verdicts produced at the end of the run.
+    pub sla_results: Vec<SlaResult>,
+    /// Sequence stamps of edge traffic, if packet marking is enabled.
+    pub marking: Option<Marking>,
+    /// Per‑link capture points.
+    pub captures: Vec<CapturePoint>,
+    /// Per‑router mirror ports.
+    pub mirrors: Vec<MirrorPort>,
+    /// L2TPv3 pseudowire carrying edge traffic, if configured.
+    pub pseudowire: Option<Pseudowire>,
+    /// Encrypted overlay carrying edge traffic, if configured.
+    pub wireguard: Option<Wireguard>,
+    /// SRv6 segment lists and endpoint routes, if configured.
+    pub srv6: Option<Srv6>,
+    /// Per‑edge TTL rewrite and model, if configured.
+    pub ttl: Option<TtlPolicy>,
+    /// Per‑packet sojourn records, if `packet_trace` is set.
+    pub packet_trace: Option<PacketTrace>,
+    /// Provenance stamped on the run's outputs, if the run has it (CLI runs do).
+    pub run: Option<RunMetadata>,
+    /// Sojourn of the packet the processor finished last; paced edges release it after this.
+    pub last_sojourn: Option<Sojourn>,
+    /// Further fragments of the packet the processor finished last that left the fabric, with
+    /// the edge they left towards; they follow that packet out.
+    pub fragments_out: Vec<(PacketMeta, Destination)>,
+    /// Link monitoring alarms and their log, if configured.
+    pub alarms: Option<AlarmMonitor>,
+    /// Reassembly of fragments before they leave the fabric, if configured.
+    pub reassembly: Option<Reassembler>,
+    /// Checksum verification at ingress, if `[checksum]` is configured.
+    pub checksum: Option<ChecksumConfig>,
+    /// Egress duplicate removal, if configured.
+    pub dedupe: Option<Deduplicator>,
+    /// NAT44 at one edge (`[nat44]`).
+    pub nat44: Option<Nat44>,
+    /// NAT64 at one router (`[nat64]`).
+    pub nat64: Option<Nat64>,
+    /// Counters of the real edge interfaces, once they are open.
+    pub tun_counters: Option<TunCounters>,
+    /// The last dropped packets of every router, if `[drop_capture]` is configured.
+    pub drops: Option<DropCapture>,
+    /// Packets dropped, by reason, whether or not `[drop_capture]` is configured.
+    pub drop_counts: BTreeMap<DropReason, u64>,
+    /// What the start‑up demonstration packet added to the router counters; the exit report
+    /// leaves it out.
+    pub demonstration: PacketTotals,
+    /// Publisher of counter samples to telemetry subscribers, if `[telemetry]` is configured.
+    pub telemetry: Option<Telemetry>,
+    /// Writer of the state snapshots, if `[snapshot]` is configured.
+    pub snapshots: Option<Snapshots>,
+    /// Counters accumulated in the `counter_state` file: those of the earlier runs, with this
+    /// run's added at its end.
+    pub counter_state: Option<CounterState>,
+    /// Egress edge overrides per ingress edge and destination prefix.
+    pub destination_map: DestinationMap,
+    /// Prefixes attached to routers (`[[route]]`), the routing tables' FIBs are computed from.
+    pub routes: Vec<PrefixRoute>,
+    /// Next hop each load-balanced flow is pinned to, kept across routing table updates.
+    pub flows: FlowTable,
+    /// Flooding of topology changes to the routers, if `[link_state]` is configured.
+    pub convergence: Option<Convergence>,
+    /// Distance-vector state of the routers, if `[distance_vector]` is configured.
+    pub distance_vector: Option<DistanceVector>,
+    /// Routing policies between the routers' ASes, if `[bgp]` is configured.
+    pub bgp: Option<Bgp>,
+    /// What the routing tables optimise (`[routing] algorithm`).
+    pub routing_algorithm: RoutingAlgorithm,
+    /// Administrative distance of the computed routes (`[routing] distance`), against which
+    /// static routes are weighed.
+    pub routing_distance: u8,
+    /// Router paths matching packets follow instead of the routing tables (`[[explicit_path]]`).
+    pub explicit_paths: Vec<ExplicitPath>,
+    /// Tenants (VRFs) the entering packets are classified to, with their counters, if
+    /// `[[tenant]]` tables are configured.
+    pub tenants: Option<Tenants>,
+    /// Faults injected while the simulation runs, until the TUN handling takes them.
+    pub faults: Option<Faults>,
+    /// Packet fields the ECMP hash covers.
+    pub ecmp_hash: EcmpHash,
+    /// Check on every hop that the next hop is closer to the destination (`--paranoid`).
+    pub paranoid: bool,
+    /// Reports of the hops `--paranoid` found violating the routing tables.
+    pub paranoid_violations: Vec<String>,
+    /// Clocks of the edges that timestamps are read from (`[clock_skew]`).
+    pub clocks: EdgeClocks,
+    /// Shared capacity pools links can reference (`[topology.pools]`), by name.
+    pub pools: BTreeMap<String, Arc<Pool>>,
 }
 
 impl Fabric {
@@ -26,6 +163,13 @@ impl Fabric {
         result
     }
 
+    /// Retrieve a link between two routers for modification, if it exists.
+    pub fn get_link_mut(&mut self, a: &RouterId, b: &RouterId) -> Option<&mut Link> {
+        let id = LinkId::new(a.clone(), b.clone());
+        let edge_idx = *self.link_index.get(&id)?;
+        self.graph.edge_weight_mut(edge_idx)
+    }
+
     /// Retrieve a link between two routers, if it exists.
     pub fn get_link(&self, a: &RouterId, b: &RouterId) -> Option<&Link> {
         let id = LinkId::new(a.clone(), b.clone());
@@ -40,16 +184,188 @@ impl Fabric {
             if let Some(router) = self.graph.node_weight(*node_idx) {
                 let stats = &router.stats;
                 info!(
-                    "Router {}: recv={}, fwd={}, icmp={}",
+                    "Router {}: recv={} ({} bytes), fwd={} ({} bytes), icmp={}, icmp_suppressed={}, delivered={}, cpu_drops={}, acl_drops={}, policer_drops={}, bad_checksums={}",
                     router_id.0,
                     stats.packets_received,
+                    stats.bytes_received,
                     stats.packets_forwarded,
-                    stats.icmp_generated
+                    stats.bytes_forwarded,
+                    stats.icmp_generated,
+                    stats.icmp_suppressed,
+                    stats.packets_delivered,
+                    stats.cpu_drops,
+                    stats.acl_drops,
+                    stats.policer_drops,
+                    stats.bad_checksums
                 );
+                for rule in &router.acl {
+                    info!(
+                        "Router {}: ACL {:?} \"{}\" hits={}",
+                        router_id.0,
+                        rule.action,
+                        rule.filter.source(),
+                        rule.hits
+                    );
+                }
+                for policer in &router.policers {
+                    info!("Router {}: policer {}", router_id.0, policer.summary());
+                }
+            }
+        }
+        for link in self.graph.edge_weights() {
+            for (direction, queues) in link.queue_stats() {
+                for queue in queues {
+                    info!("Link {} queue {}", direction, queue.summary());
+                }
+            }
+        }
+        for tenant in self.tenants.iter().flat_map(|t| &t.tenants) {
+            info!("Tenant {}: {}", tenant.name, tenant.summary());
+        }
+        if let Some(ref report) = self.twamp_report {
+            info!("TWAMP: {}", report.summary());
+        }
+        if let Some(ref report) = self.ddos_report {
+            info!("DDoS flood: {}", report.summary());
+        }
+        if let Some(ref marking) = self.marking {
+            info!("Marking: {}", marking.summary());
+        }
+        if let Some(ref pw) = self.pseudowire {
+            info!("Pseudowire: {}", pw.summary());
+        }
+        if let Some(ref wg) = self.wireguard {
+            info!("WireGuard: {}", wg.summary());
+        }
+        if let Some(ref srv6) = self.srv6 {
+            info!("SRv6: {}", srv6.stats.summary());
+        }
+        #[cfg(feature = "http-test")]
+        if let Some(ref report) = self.http_report {
+            info!("HTTP load: {}", report.summary());
+        }
+    }
+
+    /// Reset all router counters and customer flow metrics (end of the warm‑up phase).
+    pub fn reset_statistics(&mut self) {
+        for router in self.graph.node_weights_mut() {
+            router.stats = RouterStats::default();
+            for rule in &mut router.acl {
+                rule.hits = 0;
+            }
+            for rule in &mut router.pbr {
+                rule.hits = 0;
+            }
+            for policer in &mut router.policers {
+                policer.reset_stats();
+            }
+        }
+        for link in self.graph.edge_weights() {
+            if let Some(queues) = &link.queues {
+                let mut queues = queues.lock().unwrap();
+                queues.a_to_b.reset_stats();
+                queues.b_to_a.reset_stats();
+            }
+        }
+        for pool in self.pools.values() {
+            pool.queues.lock().unwrap().reset_stats();
+        }
+        self.customer_flows.clear();
+        if let Some(marking) = &mut self.marking {
+            marking.a_to_b.reset();
+            marking.b_to_a.reset();
+        }
+        if let Some(pw) = &mut self.pseudowire {
+            pw.a_to_b.reset();
+            pw.b_to_a.reset();
+        }
+        if let Some(wg) = &mut self.wireguard {
+            wg.a_to_b.reset();
+            wg.b_to_a.reset();
+        }
+        if let Some(srv6) = &mut self.srv6 {
+            srv6.reset();
+        }
+        if let Some(reassembly) = &mut self.reassembly {
+            reassembly.reset();
+        }
+        if let Some(dedupe) = &mut self.dedupe {
+            dedupe.reset();
+        }
+        if let Some(nat) = &mut self.nat44 {
+            nat.reset();
+        }
+        if let Some(nat) = &mut self.nat64 {
+            nat.reset();
+        }
+        if let Some(counters) = &mut self.tun_counters {
+            *counters = TunCounters::default();
+        }
+        if let Some(alarms) = &mut self.alarms {
+            alarms.reset();
+        }
+        if let Some(drops) = &mut self.drops {
+            drops.reset();
+        }
+        if let Some(trace) = &mut self.packet_trace {
+            // Like the capture files, keep only the records after the warm‑up.
+            if let Err(e) = trace.open() {
+                tracing::error!("{}", e);
+            }
+        }
+        for point in &mut self.captures {
+            // Start the file over, so that it only holds packets seen after the warm‑up.
+            if let Err(e) = point.open() {
+                tracing::error!("{}", e);
+            }
+        }
+        for port in &mut self.mirrors {
+            if let Err(e) = port.open() {
+                tracing::error!("{}", e);
             }
         }
     }
 
+    /// Hand a packet that was carried over `link` to the capture points.
+    pub fn capture(&mut self, link: &LinkId, packet: &PacketMeta) {
+        for point in &mut self.captures {
+            point.offer(link, packet);
+        }
+    }
+
+    /// Hand a packet that arrived at `router` to the mirror ports.
+    pub fn mirror(&mut self, router: &RouterId, packet: &PacketMeta) {
+        for port in &mut self.mirrors {
+            port.offer(router, packet);
+        }
+    }
+
+    /// Count `packet`, dropped at `router` for `reason`, and keep it in the drop capture, if
+    /// one is configured.
+    pub fn record_drop(&mut self, router: &RouterId, reason: DropReason, packet: &PacketMeta) {
+        // The start‑up demonstration packet has no bytes to keep.
+        if packet.raw.is_empty() {
+            return;
+        }
+        *self.drop_counts.entry(reason).or_default() += 1;
+        if let Some(drops) = &mut self.drops {
+            drops.record(router, reason, packet);
+        }
+        if let Some(convergence) = &mut self.convergence {
+            convergence.record_drop(reason);
+        }
+        if let Some(dv) = &mut self.distance_vector {
+            dv.record_drop(reason);
+        }
+    }
+
+    /// Counters of the real interface at `edge`.
+    pub fn interface(&mut self, edge: Destination) -> &mut InterfaceCounters {
+        self.tun_counters
+            .get_or_insert_with(TunCounters::default)
+            .edge_mut(edge)
+    }
+
     /// Return a map of router IDs to their statistics.
     pub fn get_statistics(&self) -> std::collections::HashMap<RouterId, RouterStats> {
         let mut map = std::collections::HashMap::new();
@@ -74,6 +390,43 @@ impl Fabric {
         let node_idx = *self.router_index.get(router_id)?;
         self.graph.node_weight_mut(node_idx)
     }
+
+    /// Put a router into maintenance or take it out again. Routing tables computed afterwards
+    /// avoid it as a transit node. Returns whether the state changed.
+    pub fn set_maintenance(&mut self, router_id: &RouterId, on: bool) -> Result<bool, String> {
+        let router = self
+            .get_router_mut(router_id)
+            .ok_or_else(|| format!("Router '{}' not found", router_id.0))?;
+        let changed = router.maintenance != on;
+        router.maintenance = on;
+        if changed {
+            info!(
+                "Router {} {} maintenance",
+                router_id.0,
+                if on { "entered" } else { "left" }
+            );
+        }
+        Ok(changed)
+    }
+
+    /// Fail the link between `a` and `b`, or let it recover: it comes back in
+    /// [`LinkState::Init`] for its `bringup_delay_ms` before routing may use it again. Routing
+    /// tables computed afterwards follow the link's state. Returns whether the state changed.
+    pub fn set_link_up(&mut self, a: &RouterId, b: &RouterId, up: bool) -> Result<bool, String> {
+        let link = self
+            .get_link_mut(a, b)
+            .ok_or_else(|| format!("Link {}_{} not found", a.0, b.0))?;
+        let changed = link.set_up(up);
+        if changed {
+            info!(
+                "Link {}_{} {}",
+                link.id.a.0,
+                link.id.b.0,
+                link.admin_state.as_str()
+            );
+        }
+        Ok(changed)
+    }
 }
 
 impl Fabric {
@@ -82,6 +435,52 @@ impl Fabric {
             graph: UnGraph::new_undirected(),
             router_index: HashMap::new(),
             link_index: HashMap::new(),
+            twamp_report: None,
+            ddos_report: None,
+            #[cfg(feature = "http-test")]
+            http_report: None,
+            customer_flows: HashMap::new(),
+            sla_results: Vec::new(),
+            marking: None,
+            pseudowire: None,
+            wireguard: None,
+            srv6: None,
+            ttl: None,
+            packet_trace: None,
+            run: None,
+            last_sojourn: None,
+            fragments_out: Vec::new(),
+            alarms: None,
+            reassembly: None,
+            checksum: None,
+            dedupe: None,
+            nat44: None,
+            nat64: None,
+            tun_counters: None,
+            drops: None,
+            drop_counts: BTreeMap::new(),
+            demonstration: PacketTotals::default(),
+            telemetry: None,
+            snapshots: None,
+            counter_state: None,
+            destination_map: DestinationMap::default(),
+            routes: Vec::new(),
+            flows: FlowTable::default(),
+            convergence: None,
+            distance_vector: None,
+            bgp: None,
+            routing_algorithm: RoutingAlgorithm::default(),
+            routing_distance: crate::routing::static_routes::COMPUTED_DISTANCE,
+            explicit_paths: Vec::new(),
+            tenants: None,
+            faults: None,
+            ecmp_hash: EcmpHash::default(),
+            paranoid: false,
+            paranoid_violations: Vec::new(),
+            clocks: EdgeClocks::default(),
+            pools: BTreeMap::new(),
+            captures: Vec::new(),
+            mirrors: Vec::new(),
         }
     }
 
@@ -92,6 +491,12 @@ impl Fabric {
         self.router_index.insert(router.id.clone(), idx);
     }
 
+    /// Declare a shared capacity pool; add it before the links naming it.
+    pub fn add_pool(&mut self, name: &str, cfg: &PoolConfig) {
+        self.pools
+            .insert(name.to_string(), Arc::new(Pool::new(name, cfg)));
+    }
+
     pub fn add_link(&mut self, a: &RouterId, b: &RouterId, cfg: LinkConfig) {
         // Ensure both routers exist
         let a_idx = self.router_index.get(a).expect("Router A missing");
@@ -101,11 +506,12 @@ impl Fabric {
         if self.link_index.contains_key(&id) {
             panic!("Link between {} and {} already exists", a.0, b.0);
         }
-        let link = Link {
-            id: id.clone(),
-            cfg,
-            counter: std::sync::atomic::AtomicU64::new(0),
-        };
+        // Per‑direction settings are given from `a` to `b`; the link keeps them in id order.
+        let cfg = if id.a == *a { cfg } else { cfg.reversed() };
+        let mut link = Link::new(id.clone(), cfg);
+        if let Some(name) = &link.cfg.pool {
+            link.pool = Some(self.pools.get(name).expect("Pool missing").clone());
+        }
         let edge_idx = self.graph.add_edge(*a_idx, *b_idx, link);
         self.link_index.insert(id, edge_idx);
     }
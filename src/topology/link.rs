@@ -1,8 +1,14 @@
 // src/topology/link.rs
 
+use crate::packet::vlan::{VlanPolicy, VlanRewrite};
+use crate::qos::{LinkQueues, Pool, QueueConfig, QueueStats, SchedulerKind};
 use crate::topology::router::RouterId;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, Instant};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct LinkId {
@@ -26,12 +32,124 @@ pub struct LinkConfig {
     pub mtu: Option<u32>,
     #[serde(default = "default_delay")]
     pub delay_ms: u32,
+    /// Delay from the router named first in the link's key to the second (`ab`) and back
+    /// (`ba`), when the directions differ; unset directions use `delay_ms`.
+    #[serde(default)]
+    pub delay_ms_ab: Option<u32>,
+    #[serde(default)]
+    pub delay_ms_ba: Option<u32>,
     #[serde(default = "default_jitter")]
     pub jitter_ms: u32,
     #[serde(default = "default_loss")]
     pub loss_percent: f32,
     #[serde(default)]
     pub load_balance: bool,
+    /// Loss probability (percent) applied once per fragmented datagram: all of its fragments
+    /// are lost or none is. Unset = fragments are lost independently like other packets.
+    #[serde(default)]
+    pub fragment_train_loss_percent: Option<f32>,
+    /// Link rate; when set, packets are serialised and queued per traffic class.
+    #[serde(default)]
+    pub bandwidth_mbps: Option<f64>,
+    /// Traffic classes in priority order (a single FIFO when empty).
+    #[serde(default)]
+    pub queues: Vec<QueueConfig>,
+    #[serde(default)]
+    pub scheduler: SchedulerKind,
+    /// Shared capacity pool (`[topology.pools]`) the link's traffic also goes through.
+    #[serde(default)]
+    pub pool: Option<String>,
+    /// Routing cost. Unset: derived from `bandwidth_mbps` when `[topology]` sets
+    /// `reference_bandwidth_mbps`, otherwise `delay_ms`.
+    #[serde(default)]
+    pub cost: Option<u32>,
+    /// Routing cost per direction, as for `delay_ms_ab` / `delay_ms_ba`; unset directions use
+    /// `cost`, else their own delay.
+    #[serde(default)]
+    pub cost_ab: Option<u32>,
+    #[serde(default)]
+    pub cost_ba: Option<u32>,
+    /// Tunnel the link's packets are carried in; see [`crate::gre`] and [`crate::ipip`].
+    #[serde(default)]
+    pub encap: Option<LinkEncap>,
+    /// Carrier detect and negotiation time after the link recovers, during which it stays in
+    /// [`LinkState::Init`] and routing does not use it.
+    #[serde(default)]
+    pub bringup_delay_ms: u32,
+    /// 802.1Q VLAN IDs the link carries; tagged packets of other VLANs are dropped. Empty
+    /// carries all of them.
+    #[serde(default)]
+    pub vlans: Vec<u16>,
+    /// VLAN ID translations applied to tagged packets crossing the link.
+    #[serde(default)]
+    pub vlan_rewrite: Vec<VlanRewrite>,
+    /// Share of the load‑balanced flows the link gets among the equal‑cost next hops (WCMP).
+    /// Unset: its bandwidth in Mbit/s with `[ecmp_hash] bandwidth_weights`, otherwise 1.
+    #[serde(default)]
+    pub weight: Option<u32>,
+}
+
+/// Encapsulation of the packets crossing a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkEncap {
+    /// Outer IPv4 + GRE header between the link's two routers.
+    Gre,
+    /// IPv6 packets in an outer IPv4 header; IPv4 crosses natively. See [`crate::ipip`].
+    #[serde(rename = "6in4")]
+    SixInFour,
+    /// IPv4 packets in an outer IPv6 header; IPv6 crosses natively.
+    #[serde(rename = "4in6")]
+    FourInSix,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self {
+            mtu: None,
+            delay_ms: default_delay(),
+            delay_ms_ab: None,
+            delay_ms_ba: None,
+            jitter_ms: default_jitter(),
+            loss_percent: default_loss(),
+            load_balance: false,
+            fragment_train_loss_percent: None,
+            bandwidth_mbps: None,
+            queues: Vec::new(),
+            scheduler: SchedulerKind::default(),
+            pool: None,
+            cost: None,
+            cost_ab: None,
+            cost_ba: None,
+            encap: None,
+            bringup_delay_ms: 0,
+            vlans: Vec::new(),
+            vlan_rewrite: Vec::new(),
+            weight: None,
+        }
+    }
+}
+
+impl LinkConfig {
+    /// The same link seen from its other end: the per‑direction settings swap.
+    pub fn reversed(mut self) -> Self {
+        std::mem::swap(&mut self.delay_ms_ab, &mut self.delay_ms_ba);
+        std::mem::swap(&mut self.cost_ab, &mut self.cost_ba);
+        self
+    }
+
+    /// Whether the two directions differ in delay or cost.
+    pub fn is_asymmetric(&self) -> bool {
+        self.delay_ms_ab.unwrap_or(self.delay_ms) != self.delay_ms_ba.unwrap_or(self.delay_ms)
+            || self.cost_ab.or(self.cost) != self.cost_ba.or(self.cost)
+    }
+
+    pub fn vlan_policy(&self) -> VlanPolicy<'_> {
+        VlanPolicy {
+            allowed: &self.vlans,
+            rewrite: &self.vlan_rewrite,
+        }
+    }
 }
 
 fn default_delay() -> u32 {
@@ -44,19 +162,238 @@ fn default_loss() -> f32 {
     0.0
 }
 
+/// Operational state of a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkState {
+    Down,
+    /// Recovered, but still in its bring‑up delay.
+    Init,
+    Up,
+}
+
+impl LinkState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkState::Down => "down",
+            LinkState::Init => "init",
+            LinkState::Up => "up",
+        }
+    }
+
+    /// Numeric form for counter dumps: 0 down, 1 init, 2 up.
+    pub fn as_number(&self) -> u8 {
+        match self {
+            LinkState::Down => 0,
+            LinkState::Init => 1,
+            LinkState::Up => 2,
+        }
+    }
+}
+
+/// Identifies the fragments of one datagram: source, destination, identification, protocol.
+pub type FragmentKey = (IpAddr, IpAddr, u32, u8);
+
+/// Largest size, in bytes, of each bin of [`SizeHistogram`] but the last, which takes the
+/// rest: RMON's bins below 1024, then up to the IPv6 minimum MTU and up to Ethernet's.
+pub const SIZE_BINS: [usize; 7] = [64, 127, 255, 511, 1023, 1279, 1500];
+
+/// Packets that crossed a link, by size on the wire.
+#[derive(Debug, Default)]
+pub struct SizeHistogram {
+    bins: [AtomicU64; SIZE_BINS.len() + 1],
+}
+
+impl SizeHistogram {
+    pub fn record(&self, len: usize) {
+        use std::sync::atomic::Ordering;
+        let bin = SIZE_BINS.partition_point(|&max| max < len);
+        self.bins[bin].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Bin names (`0-64`, `65-127`, …, `1501+`) with their packet counts, smallest first.
+    pub fn bins(&self) -> Vec<(String, u64)> {
+        use std::sync::atomic::Ordering;
+        let mut low = 0;
+        let mut names = Vec::with_capacity(self.bins.len());
+        for max in SIZE_BINS {
+            names.push(format!("{}-{}", low, max));
+            low = max + 1;
+        }
+        names.push(format!("{}+", low));
+        names
+            .into_iter()
+            .zip(&self.bins)
+            .map(|(name, count)| (name, count.load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+impl Clone for SizeHistogram {
+    fn clone(&self) -> Self {
+        use std::sync::atomic::Ordering;
+        Self {
+            bins: std::array::from_fn(|i| AtomicU64::new(self.bins[i].load(Ordering::Relaxed))),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Link {
     pub id: LinkId,
     pub cfg: LinkConfig,
     pub counter: AtomicU64,
+    /// Packets refused because they exceeded `cfg.mtu`.
+    pub too_big: AtomicU64,
+    /// Packets dropped on the link: random or fragment‑train loss and full egress queues.
+    pub lost: AtomicU64,
+    /// Bytes that made it across the link.
+    pub bytes: AtomicU64,
+    /// Sizes of the packets that made it across the link.
+    pub sizes: SizeHistogram,
+    /// Loss decision taken for each fragment train currently crossing the link.
+    pub fragment_fates: Mutex<HashMap<FragmentKey, bool>>,
+    /// Egress queues of both directions, present when the link has a bandwidth.
+    pub queues: Option<Mutex<LinkQueues>>,
+    /// Pool named by `cfg.pool`, shared with the other links naming it.
+    pub pool: Option<Arc<Pool>>,
+    /// State last set with [`Link::set_up`]; see [`Link::state`] for the current one.
+    pub admin_state: LinkState,
+    /// End of the bring‑up delay while in [`LinkState::Init`].
+    pub up_at: Option<Instant>,
+    /// Times the link went down.
+    pub flaps: u64,
 }
 
 impl Link {
+    /// Propagation delay of packets sent by `from`.
+    pub fn delay_ms(&self, from: &RouterId) -> u32 {
+        let directed = if *from == self.id.a {
+            self.cfg.delay_ms_ab
+        } else {
+            self.cfg.delay_ms_ba
+        };
+        directed.unwrap_or(self.cfg.delay_ms)
+    }
+
+    /// Configured routing cost of the direction away from `from`, if any.
+    pub fn cost(&self, from: &RouterId) -> Option<u32> {
+        let directed = if *from == self.id.a {
+            self.cfg.cost_ab
+        } else {
+            self.cfg.cost_ba
+        };
+        directed.or(self.cfg.cost)
+    }
+
     /// Return the current packet counter value.
     pub fn counter(&self) -> u64 {
         use std::sync::atomic::Ordering;
         self.counter.load(Ordering::Relaxed)
     }
+
+    /// Return how many packets were refused for exceeding the link MTU.
+    pub fn too_big(&self) -> u64 {
+        use std::sync::atomic::Ordering;
+        self.too_big.load(Ordering::Relaxed)
+    }
+
+    /// Return how many packets were dropped on the link.
+    pub fn lost(&self) -> u64 {
+        use std::sync::atomic::Ordering;
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    /// Return how many bytes made it across the link.
+    pub fn bytes(&self) -> u64 {
+        use std::sync::atomic::Ordering;
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Packets waiting in the fuller direction's egress queues when the last packet was
+    /// admitted (zero without a bandwidth).
+    pub fn queue_depth(&self) -> usize {
+        self.queues.as_ref().map_or(0, |queues| {
+            let queues = queues.lock().unwrap();
+            queues.a_to_b.depth().max(queues.b_to_a.depth())
+        })
+    }
+
+    pub fn new(id: LinkId, cfg: LinkConfig) -> Self {
+        let queues = cfg
+            .bandwidth_mbps
+            .map(|mbps| Mutex::new(LinkQueues::new(mbps, cfg.scheduler, &cfg.queues)));
+        Self {
+            id,
+            cfg,
+            counter: AtomicU64::new(0),
+            too_big: AtomicU64::new(0),
+            lost: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            sizes: SizeHistogram::default(),
+            fragment_fates: Mutex::new(HashMap::new()),
+            queues,
+            pool: None,
+            admin_state: LinkState::Up,
+            up_at: None,
+            flaps: 0,
+        }
+    }
+
+    /// Current state: a link in [`LinkState::Init`] is up once its bring‑up delay has passed.
+    pub fn state(&self) -> LinkState {
+        match (self.admin_state, self.up_at) {
+            (LinkState::Init, Some(at)) if Instant::now() >= at => LinkState::Up,
+            (state, _) => state,
+        }
+    }
+
+    /// Whether routing and forwarding may use the link.
+    pub fn is_up(&self) -> bool {
+        self.state() == LinkState::Up
+    }
+
+    /// Fail the link, or let it recover through its bring‑up delay. Returns whether the state
+    /// changed.
+    pub fn set_up(&mut self, up: bool) -> bool {
+        match (up, self.admin_state) {
+            (false, LinkState::Down) | (true, LinkState::Init | LinkState::Up) => false,
+            (false, _) => {
+                self.admin_state = LinkState::Down;
+                self.up_at = None;
+                self.flaps += 1;
+                true
+            }
+            (true, LinkState::Down) => {
+                let delay = Duration::from_millis(self.cfg.bringup_delay_ms as u64);
+                if delay.is_zero() {
+                    self.admin_state = LinkState::Up;
+                } else {
+                    self.admin_state = LinkState::Init;
+                    self.up_at = Some(Instant::now() + delay);
+                }
+                true
+            }
+        }
+    }
+
+    /// Per‑class queue counters as `(direction, stats)`, e.g. `("Rx0y0->Rx0y1", ...)`.
+    pub fn queue_stats(&self) -> Vec<(String, Vec<QueueStats>)> {
+        let Some(queues) = &self.queues else {
+            return Vec::new();
+        };
+        let queues = queues.lock().unwrap();
+        vec![
+            (
+                format!("{}->{}", self.id.a.0, self.id.b.0),
+                queues.a_to_b.stats(),
+            ),
+            (
+                format!("{}->{}", self.id.b.0, self.id.a.0),
+                queues.b_to_a.stats(),
+            ),
+        ]
+    }
 }
 
 impl Clone for Link {
@@ -66,6 +403,19 @@ impl Clone for Link {
             id: self.id.clone(),
             cfg: self.cfg.clone(),
             counter: AtomicU64::new(self.counter.load(Ordering::Relaxed)),
+            too_big: AtomicU64::new(self.too_big.load(Ordering::Relaxed)),
+            lost: AtomicU64::new(self.lost.load(Ordering::Relaxed)),
+            bytes: AtomicU64::new(self.bytes.load(Ordering::Relaxed)),
+            sizes: self.sizes.clone(),
+            fragment_fates: Mutex::new(self.fragment_fates.lock().unwrap().clone()),
+            queues: self
+                .queues
+                .as_ref()
+                .map(|q| Mutex::new(q.lock().unwrap().clone())),
+            pool: self.pool.clone(),
+            admin_state: self.admin_state,
+            up_at: self.up_at,
+            flaps: self.flaps,
         }
     }
 }
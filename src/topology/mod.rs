@@ -5,5 +5,5 @@ pub mod link;
 pub mod router;
 
 pub use fabric::Fabric;
-pub use link::{Link, LinkConfig, LinkId};
-pub use router::{Router, RouterId, RouterStats};
+pub use link::{Link, LinkConfig, LinkEncap, LinkId, LinkState, SizeHistogram};
+pub use router::{CpuModel, Router, RouterConfig, RouterId, RouterStats};
@@ -3,8 +3,9 @@
 use serde::{Deserialize, Serialize};
 use std::hash::Hash;
 use std::net::{Ipv4Addr, Ipv6Addr};
+use tokio::time::{Duration, Instant};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct RouterId(pub String);
 
 impl RouterId {
@@ -42,6 +43,101 @@ pub struct Router {
     pub ipv6_addr: Ipv6Addr,
     pub routing: crate::routing::RoutingTable,
     pub stats: RouterStats,
+    /// Packet processing capacity; `None` means unlimited.
+    pub cpu: Option<CpuModel>,
+    /// Ordered ACL rules; empty permits everything.
+    pub acl: Vec<crate::acl::AclRule>,
+    /// Ordered PBR rules; empty leaves every decision to the routing table.
+    pub pbr: Vec<crate::pbr::PbrRule>,
+    /// Next hops pinned for some destinations, competing with the computed routes.
+    pub static_routes: Vec<crate::routing::static_routes::StaticRoute>,
+    /// Per‑class policers metering the traffic entering the fabric here.
+    pub policers: Vec<crate::policer::Policer>,
+    /// Drained for maintenance: routing avoids it as a transit node, but it still delivers to
+    /// its attached edge.
+    pub maintenance: bool,
+    /// Autonomous system the router belongs to, with `[bgp]`.
+    pub asn: Option<u32>,
+}
+
+/// Per‑router config options, read from the router's table in `[topology.routers]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RouterConfig {
+    /// Packets per second the router can process.
+    #[serde(default)]
+    pub max_pps: Option<f64>,
+    /// Packets that may wait for the CPU before further arrivals are dropped.
+    #[serde(default)]
+    pub cpu_queue: u32,
+    /// Access control list applied to every packet the router handles.
+    #[serde(default)]
+    pub acl: Vec<crate::acl::AclRuleConfig>,
+    /// Policy‑based routing rules, consulted before the routing table.
+    #[serde(default)]
+    pub pbr: Vec<crate::pbr::PbrRuleConfig>,
+    /// Static routes: destination (`tun_a`, `tun_b` or a prefix) to adjacent next hop router,
+    /// optionally with an administrative distance.
+    #[serde(default)]
+    pub static_routes:
+        std::collections::BTreeMap<String, crate::routing::static_routes::StaticRouteConfig>,
+    /// Ingress policers, one per traffic class; the first whose class matches meters a packet.
+    #[serde(default)]
+    pub policers: Vec<crate::policer::PolicerConfig>,
+    /// Seed of the router's ECMP hash, in place of the one derived from its ID.
+    #[serde(default)]
+    pub ecmp_seed: Option<u64>,
+    /// Start the router in maintenance (see [`Router::maintenance`]).
+    #[serde(default)]
+    pub maintenance: bool,
+    /// Autonomous system of the router (see [`Router::asn`]).
+    #[serde(default)]
+    pub asn: Option<u32>,
+}
+
+/// Single‑server queue in front of the router's forwarding CPU. Every packet occupies the CPU
+/// for `1 / max_pps` seconds; packets arriving while it is busy wait, and once `queue_limit`
+/// packets are already waiting the arrival is dropped.
+///
+/// The simulator forwards one packet at a time, so time spent waiting here would also hold
+/// back the packets behind it. Arrival times are therefore taken on a clock that excludes the
+/// waits this CPU imposed, so the offered load seen by the queue is the one the sender produced.
+#[derive(Debug, Clone)]
+pub struct CpuModel {
+    pub max_pps: f64,
+    pub queue_limit: u32,
+    busy_until: Option<Instant>,
+    stalled: Duration,
+}
+
+impl CpuModel {
+    pub fn new(max_pps: f64, queue_limit: u32) -> Self {
+        Self {
+            max_pps,
+            queue_limit,
+            busy_until: None,
+            stalled: Duration::ZERO,
+        }
+    }
+
+    /// Admit a packet arriving now. Returns how long it has to wait for the CPU (the caller
+    /// sleeps for it), or `None` if the queue is full and the packet is dropped.
+    pub fn admit(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let arrival = now.checked_sub(self.stalled).unwrap_or(now);
+        let service = Duration::from_secs_f64(1.0 / self.max_pps);
+        let start = self.busy_until.map_or(arrival, |busy| busy.max(arrival));
+        let wait = start - arrival;
+        // Packets still ahead of this one, including the one being processed.
+        let backlog = (wait.as_secs_f64() * self.max_pps - 1e-9).ceil() as u64;
+        if backlog > self.queue_limit as u64 {
+            return None;
+        }
+        self.busy_until = Some(start + service);
+        if !crate::simulation::delays_paced() {
+            self.stalled += wait;
+        }
+        Some(wait)
+    }
 }
 
 impl Router {
@@ -56,6 +152,13 @@ impl Router {
             ipv6_addr,
             routing: crate::routing::RoutingTable::default(),
             stats: RouterStats::default(),
+            cpu: None,
+            acl: Vec::new(),
+            pbr: Vec::new(),
+            static_routes: Vec::new(),
+            policers: Vec::new(),
+            maintenance: false,
+            asn: None,
         }
     }
 
@@ -81,12 +184,82 @@ impl Router {
     pub fn increment_forwarded(&mut self) {
         self.stats.packets_forwarded += 1;
     }
+    /// Count `packet` as received, with its bytes and protocol.
+    pub fn account_received(&mut self, packet: &crate::packet::PacketMeta) {
+        self.increment_received();
+        self.stats.bytes_received += packet.raw.len() as u64;
+        match packet.protocol {
+            6 => self.stats.tcp_received += 1,
+            17 => self.stats.udp_received += 1,
+            1 | 58 => self.stats.icmp_received += 1,
+            _ => self.stats.other_received += 1,
+        }
+    }
+    /// Count a packet of `bytes` as forwarded.
+    pub fn account_forwarded(&mut self, bytes: usize) {
+        self.increment_forwarded();
+        self.stats.bytes_forwarded += bytes as u64;
+    }
     pub fn increment_icmp(&mut self) {
         self.stats.icmp_generated += 1;
     }
     pub fn increment_lost(&mut self) {
         self.stats.packets_lost += 1;
     }
+    pub fn increment_delivered(&mut self) {
+        self.stats.packets_delivered += 1;
+    }
+
+    pub fn increment_injected(&mut self) {
+        self.stats.packets_injected += 1;
+    }
+
+    /// Run a packet through the router's ACL. Returns `false` (counted in `acl_drops`) if a
+    /// deny rule matched.
+    pub fn permit(&mut self, packet: &crate::packet::PacketMeta) -> bool {
+        if self.acl.is_empty() {
+            return true;
+        }
+        let permitted =
+            crate::acl::evaluate(&mut self.acl, packet) == crate::acl::AclAction::Permit;
+        if !permitted {
+            self.stats.acl_drops += 1;
+        }
+        permitted
+    }
+
+    /// Policy routing decision for a packet, if one of the router's PBR rules matches.
+    pub fn policy_route(
+        &mut self,
+        packet: &crate::packet::PacketMeta,
+    ) -> Option<crate::pbr::PbrAction> {
+        crate::pbr::lookup(&mut self.pbr, packet)
+    }
+
+    /// Run a packet entering the fabric through the router's policers, remarking it as
+    /// configured. Returns `false` (counted in `policer_drops`) if it is to be dropped.
+    pub fn police(&mut self, packet: &mut crate::packet::PacketMeta) -> bool {
+        let passed = crate::policer::police(&mut self.policers, packet);
+        if !passed {
+            self.stats.policer_drops += 1;
+        }
+        passed
+    }
+
+    /// Pass a packet through the CPU model. Returns the queueing delay, or `None` (counted in
+    /// `cpu_drops`) if the router is overloaded.
+    pub fn admit_cpu(&mut self) -> Option<Duration> {
+        match self.cpu.as_mut() {
+            None => Some(Duration::ZERO),
+            Some(cpu) => {
+                let admitted = cpu.admit();
+                if admitted.is_none() {
+                    self.stats.cpu_drops += 1;
+                }
+                admitted
+            }
+        }
+    }
 
     /// Get the router's IPv4 address
     pub fn ipv4_addr(&self) -> Ipv4Addr {
@@ -101,8 +274,32 @@ impl Router {
 
 #[derive(Debug, Default, Clone)]
 pub struct RouterStats {
+    /// Packets that entered the fabric at this router.
+    pub packets_injected: u64,
     pub packets_received: u64,
     pub packets_forwarded: u64,
     pub packets_lost: u64,
     pub icmp_generated: u64,
+    /// ICMP errors not generated because the packet was an ICMP error itself or the packet
+    /// had already drawn [`MAX_ICMP_ERRORS`](crate::processor::MAX_ICMP_ERRORS).
+    pub icmp_suppressed: u64,
+    /// Packets that reached this router as their destination edge.
+    pub packets_delivered: u64,
+    /// Packets dropped because the router's CPU queue was full.
+    pub cpu_drops: u64,
+    /// Packets dropped by a deny rule of the router's ACL.
+    pub acl_drops: u64,
+    /// Packets dropped by an ingress policer.
+    pub policer_drops: u64,
+    /// Packets entering the fabric here with a bad checksum (dropped unless `[checksum]` only
+    /// counts them).
+    pub bad_checksums: u64,
+    /// Bytes of the packets received and forwarded, as they crossed the links.
+    pub bytes_received: u64,
+    pub bytes_forwarded: u64,
+    /// Packets received by IP protocol (ICMP counts ICMPv6 too).
+    pub tcp_received: u64,
+    pub udp_received: u64,
+    pub icmp_received: u64,
+    pub other_received: u64,
 }
@@ -0,0 +1,304 @@
+// src/traceroute/mod.rs
+
+//! Router‑originated traceroute.
+//!
+//! Any router in the fabric can act as the vantage point: it originates UDP (or ICMP echo)
+//! probes with increasing TTL towards a destination behind one of the edges. The ICMP errors
+//! generated along the way are collected from the processor directly instead of being
+//! delivered to a host, so the forward path can be verified from interior routers too.
+
+use crate::config::SimulatorConfig;
+use crate::icmp::{calculate_icmp_checksum, icmpv6_checksum};
+use crate::packet::{transport_detail, update_ipv4_checksum, PacketMeta};
+use crate::processor::{process_packet, process_packet_multi};
+use crate::routing::{Destination, MultiPathTable, RoutingTable};
+use crate::topology::{Fabric, RouterId};
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use tokio::time::Instant;
+
+/// First UDP destination port used by classic traceroute.
+pub const BASE_PORT: u16 = 33434;
+
+/// Probe settings.
+#[derive(Debug, Clone)]
+pub struct TraceOptions {
+    pub max_ttl: u8,
+    /// Send ICMP echo requests instead of UDP datagrams.
+    pub icmp: bool,
+}
+
+impl Default for TraceOptions {
+    fn default() -> Self {
+        Self {
+            max_ttl: 16,
+            icmp: false,
+        }
+    }
+}
+
+/// Result of one TTL step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TracerouteHop {
+    pub ttl: u8,
+    /// Router that answered (or the egress router, when the destination was reached).
+    pub router: Option<RouterId>,
+    pub address: Option<IpAddr>,
+    pub rtt_ms: Option<f64>,
+    pub reached: bool,
+}
+
+/// Edge the destination address lives behind, based on the configured edge prefixes.
+/// Addresses matching neither tun_a prefix are assumed to be behind tun_b; the IPv6 prefix
+/// of tun_a only counts when it is narrower than the catch‑all `::/0` default.
+pub fn destination_for(cfg: &SimulatorConfig, dst: IpAddr) -> Destination {
+    let matches = |prefix: &str| {
+        prefix
+            .parse::<IpNet>()
+            .map(|net| net.contains(&dst))
+            .unwrap_or(false)
+    };
+    let t = &cfg.tun_ingress;
+    let v6_a = dst.is_ipv6() && t.tun_a_ipv6_prefix != "::/0" && matches(&t.tun_a_ipv6_prefix);
+    if matches(&t.tun_a_prefix) || v6_a {
+        Destination::TunA
+    } else {
+        Destination::TunB
+    }
+}
+
+/// Build one probe from `src` to `dst` with the given TTL.
+pub fn build_probe(src: IpAddr, dst: IpAddr, ttl: u8, icmp: bool) -> PacketMeta {
+    let seq = ttl as u16;
+    let dst_port = BASE_PORT + seq;
+    let src_port = BASE_PORT;
+    let (protocol, mut l4) = match (icmp, dst) {
+        (true, IpAddr::V4(_)) => (1u8, vec![8, 0, 0, 0, 0x4e, 0x53, 0, seq as u8]),
+        (true, IpAddr::V6(_)) => (58u8, vec![128, 0, 0, 0, 0x4e, 0x53, 0, seq as u8]),
+        (false, _) => {
+            let mut udp = Vec::with_capacity(12);
+            udp.extend_from_slice(&src_port.to_be_bytes());
+            udp.extend_from_slice(&dst_port.to_be_bytes());
+            udp.extend_from_slice(&12u16.to_be_bytes());
+            udp.extend_from_slice(&[0, 0]);
+            udp.extend_from_slice(b"NSTR");
+            (17u8, udp)
+        }
+    };
+    let raw = match (src, dst) {
+        (IpAddr::V6(s), IpAddr::V6(d)) => {
+            if icmp {
+                let c = icmpv6_checksum(s, d, &l4);
+                l4[2..4].copy_from_slice(&c.to_be_bytes());
+            }
+            let mut raw = vec![0x60, 0, 0, 0];
+            raw.extend_from_slice(&(l4.len() as u16).to_be_bytes());
+            raw.push(protocol);
+            raw.push(ttl);
+            raw.extend_from_slice(&s.octets());
+            raw.extend_from_slice(&d.octets());
+            raw.extend_from_slice(&l4);
+            raw
+        }
+        (IpAddr::V4(s), IpAddr::V4(d)) => {
+            if icmp {
+                let c = calculate_icmp_checksum(&l4);
+                l4[2..4].copy_from_slice(&c.to_be_bytes());
+            }
+            let total = 20 + l4.len();
+            let mut raw = vec![0x45, 0];
+            raw.extend_from_slice(&(total as u16).to_be_bytes());
+            raw.extend_from_slice(&[0, seq as u8, 0, 0, ttl, protocol, 0, 0]);
+            raw.extend_from_slice(&s.octets());
+            raw.extend_from_slice(&d.octets());
+            update_ipv4_checksum(&mut raw);
+            raw.extend_from_slice(&l4);
+            raw
+        }
+        _ => Vec::new(),
+    };
+    PacketMeta {
+        src_ip: src,
+        dst_ip: dst,
+        src_port: if icmp { 0 } else { src_port },
+        dst_port: if icmp { 0 } else { dst_port },
+        protocol,
+        ttl,
+        dscp: 0,
+        ecn: 0,
+        transport: transport_detail(&raw),
+        annotation: None,
+        mpls: Vec::new(),
+        vlan: None,
+        raw: raw.into(),
+    }
+}
+
+pub(crate) fn router_by_address(fabric: &Fabric, addr: IpAddr) -> Option<RouterId> {
+    fabric
+        .graph
+        .node_weights()
+        .find(|r| IpAddr::V4(r.ipv4_addr) == addr || IpAddr::V6(r.ipv6_addr) == addr)
+        .map(|r| r.id.clone())
+}
+
+pub(crate) fn total_lost(fabric: &Fabric) -> u64 {
+    fabric
+        .graph
+        .node_weights()
+        .map(|r| r.stats.packets_lost)
+        .sum()
+}
+
+/// ICMP(v6) message type of a packet, if it is one.
+fn icmp_type(packet: &PacketMeta) -> Option<u8> {
+    match packet.src_ip {
+        IpAddr::V4(_) if packet.protocol == 1 => {
+            let ihl = (packet.raw.first()? & 0x0F) as usize * 4;
+            packet.raw.get(ihl).copied()
+        }
+        IpAddr::V6(_) if packet.protocol == 58 => packet.raw.get(40).copied(),
+        _ => None,
+    }
+}
+
+/// Trace the path from `origin` to `dst` (behind `destination`), one probe per TTL.
+#[allow(clippy::too_many_arguments)]
+pub async fn trace(
+    fabric: &mut Fabric,
+    routing_tables: &HashMap<RouterId, RoutingTable>,
+    multipath_tables: &HashMap<RouterId, MultiPathTable>,
+    enable_multipath: bool,
+    origin: &RouterId,
+    egress: &RouterId,
+    dst: IpAddr,
+    destination: Destination,
+    opts: &TraceOptions,
+) -> Result<Vec<TracerouteHop>, String> {
+    let router = fabric
+        .get_router(origin)
+        .ok_or_else(|| format!("Unknown router {}", origin.0))?;
+    let src = match dst {
+        IpAddr::V4(_) => IpAddr::V4(router.ipv4_addr),
+        IpAddr::V6(_) => IpAddr::V6(router.ipv6_addr),
+    };
+    let mut hops = Vec::new();
+    for hop in 1..=opts.max_ttl {
+        // The processor decrements the TTL at the origin as well, so the probe carries one
+        // extra hop to expire exactly `hop` routers further down the path.
+        let probe = build_probe(src, dst, hop.saturating_add(1), opts.icmp);
+        let delivered_before = fabric
+            .get_router(egress)
+            .map(|r| r.stats.packets_delivered)
+            .unwrap_or(0);
+        let lost_before = total_lost(fabric);
+        let start = Instant::now();
+        let result = if enable_multipath {
+            process_packet_multi(fabric, multipath_tables, origin.clone(), probe, destination).await
+        } else {
+            process_packet(fabric, routing_tables, origin.clone(), probe, destination).await
+        };
+        let rtt_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let delivered = fabric
+            .get_router(egress)
+            .map(|r| r.stats.packets_delivered)
+            .unwrap_or(0)
+            > delivered_before;
+        // A probe or reply dropped on a link leaves the last packet in flight behind; it
+        // must not be mistaken for an answer.
+        let lost = total_lost(fabric) > lost_before;
+        let is_error =
+            !lost && result.src_ip != src && matches!(icmp_type(&result), Some(3 | 11 | 1));
+        let entry = if is_error {
+            // The processor checks the TTL before delivery, so the egress router itself expires
+            // the probe one hop before it would be delivered. It is the last hop of the path
+            // either way, so its answer completes the trace instead of being listed twice.
+            let router = router_by_address(fabric, result.src_ip);
+            let reached = router.as_ref() == Some(egress);
+            TracerouteHop {
+                ttl: hop,
+                router,
+                address: Some(if reached { dst } else { result.src_ip }),
+                rtt_ms: Some(rtt_ms),
+                reached,
+            }
+        } else if !lost && delivered && result.src_ip == src {
+            TracerouteHop {
+                ttl: hop,
+                router: Some(egress.clone()),
+                address: Some(dst),
+                rtt_ms: Some(rtt_ms),
+                reached: true,
+            }
+        } else {
+            TracerouteHop {
+                ttl: hop,
+                router: None,
+                address: None,
+                rtt_ms: None,
+                reached: false,
+            }
+        };
+        let reached = entry.reached;
+        hops.push(entry);
+        if reached {
+            break;
+        }
+    }
+    Ok(hops)
+}
+
+/// Render hops in the usual traceroute layout.
+pub fn render(origin: &RouterId, dst: IpAddr, max_ttl: u8, hops: &[TracerouteHop]) -> String {
+    let mut out = format!(
+        "traceroute from {} to {}, {} hops max\n",
+        origin.0, dst, max_ttl
+    );
+    for hop in hops {
+        match (&hop.router, hop.address, hop.rtt_ms) {
+            (Some(router), Some(addr), Some(rtt)) if hop.reached => out.push_str(&format!(
+                "{:>2}  {}  {:.3} ms  (delivered by {})\n",
+                hop.ttl, addr, rtt, router.0
+            )),
+            (router, Some(addr), Some(rtt)) => out.push_str(&format!(
+                "{:>2}  {} ({})  {:.3} ms\n",
+                hop.ttl,
+                router.as_ref().map_or("?", |r| r.0.as_str()),
+                addr,
+                rtt
+            )),
+            _ => out.push_str(&format!("{:>2}  *\n", hop.ttl)),
+        }
+    }
+    out
+}
+
+/// Build the fabric and routing tables described by `cfg` and trace from `origin` to `dst`.
+pub async fn run(
+    cfg: &SimulatorConfig,
+    origin: &RouterId,
+    dst: IpAddr,
+    opts: &TraceOptions,
+) -> Result<Vec<TracerouteHop>, String> {
+    let mut fabric = crate::build_fabric(cfg);
+    let routing_tables = crate::compute_routing_tables(cfg);
+    let multipath_tables = crate::compute_multipath_tables(cfg);
+    let destination = destination_for(cfg, dst);
+    let egress = RouterId(match destination {
+        Destination::TunA => cfg.tun_ingress.tun_a_ingress.clone(),
+        Destination::TunB => cfg.tun_ingress.tun_b_ingress.clone(),
+    });
+    trace(
+        &mut fabric,
+        &routing_tables,
+        &multipath_tables,
+        cfg.enable_multipath,
+        origin,
+        &egress,
+        dst,
+        destination,
+        opts,
+    )
+    .await
+}
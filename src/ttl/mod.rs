@@ -0,0 +1,88 @@
+// src/ttl/mod.rs
+
+//! TTL handling at the edges.
+//!
+//! Each edge may rewrite the TTL (IPv6 Hop Limit) of the packets entering there to a fixed
+//! value, and choose how the fabric treats it, following the MPLS TTL models (RFC 3443):
+//! `uniform`, the default, decrements it at every router; `pipe` carries the packet across the
+//! fabric with a TTL of its own and restores the customer's on exit, so the fabric is invisible
+//! to traceroute and never expires the packet.
+
+use crate::config::{EdgeTtlConfig, TtlConfig};
+use crate::packet::PacketMeta;
+use crate::routing::Destination;
+
+/// TTL a packet carries across the fabric in pipe mode.
+pub const PIPE_TTL: u8 = 255;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TtlMode {
+    #[default]
+    Uniform,
+    Pipe,
+}
+
+impl TtlMode {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "uniform" => Ok(Self::Uniform),
+            "pipe" => Ok(Self::Pipe),
+            other => Err(format!(
+                "Unknown TTL mode '{}', expected 'uniform' or 'pipe'",
+                other
+            )),
+        }
+    }
+}
+
+/// TTL treatment of the packets entering at one edge.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeTtl {
+    pub set: Option<u8>,
+    pub mode: TtlMode,
+}
+
+impl EdgeTtl {
+    fn from_config(cfg: &EdgeTtlConfig) -> Result<Self, String> {
+        Ok(Self {
+            set: cfg.set,
+            mode: TtlMode::parse(&cfg.mode)?,
+        })
+    }
+}
+
+/// TTL treatment of both edges.
+#[derive(Debug, Clone, Default)]
+pub struct TtlPolicy {
+    pub tun_a: EdgeTtl,
+    pub tun_b: EdgeTtl,
+}
+
+impl TtlPolicy {
+    pub fn from_config(cfg: &TtlConfig) -> Result<Self, String> {
+        Ok(Self {
+            tun_a: EdgeTtl::from_config(&cfg.tun_a)?,
+            tun_b: EdgeTtl::from_config(&cfg.tun_b)?,
+        })
+    }
+
+    /// Apply the ingress edge's treatment to a packet heading towards `destination`. Returns
+    /// the TTL to restore on exit in pipe mode.
+    pub fn ingress(&self, destination: Destination, packet: &mut PacketMeta) -> Option<u8> {
+        let edge = match destination {
+            Destination::TunB => &self.tun_a,
+            Destination::TunA => &self.tun_b,
+        };
+        if let Some(ttl) = edge.set {
+            packet.set_ttl(ttl);
+        }
+        match edge.mode {
+            TtlMode::Uniform => None,
+            TtlMode::Pipe => {
+                let customer = packet.ttl;
+                packet.set_ttl(PIPE_TTL);
+                Some(customer)
+            }
+        }
+    }
+}
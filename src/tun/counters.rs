@@ -0,0 +1,98 @@
+// src/tun/counters.rs
+
+//! Counters of the real edge interfaces themselves, kept apart from the fabric's counters.
+//!
+//! Every read from and write to a real TUN/TAP device is counted here before the packet enters
+//! or after it leaves the simulated fabric, together with what happened to it at the boundary:
+//! frames whose Ethernet header was stripped, frames answered locally (ARP, DHCP) or ignored,
+//! and packets that did not parse. Comparing these with the router counters tells loss at the
+//! kernel boundary from loss inside the fabric.
+
+use crate::routing::Destination;
+
+/// Counters of one real edge interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceCounters {
+    /// Packets (or frames, on a TAP edge) read from the device.
+    pub rx_packets: u64,
+    pub rx_bytes: u64,
+    /// Failed reads.
+    pub rx_errors: u64,
+    /// Packets read that did not parse and never entered the fabric.
+    pub parse_errors: u64,
+    /// Frames whose Ethernet header (and VLAN tag) was stripped before they entered the fabric.
+    pub stripped: u64,
+    /// Frames answered at the edge (ARP, DHCP) instead of entering the fabric.
+    pub answered: u64,
+    /// Frames neither answered nor handed to the fabric.
+    pub ignored: u64,
+    /// Packets (or frames) written to the device.
+    pub tx_packets: u64,
+    pub tx_bytes: u64,
+    /// Failed writes.
+    pub tx_errors: u64,
+}
+
+impl InterfaceCounters {
+    /// Count a read of `len` bytes.
+    pub fn received(&mut self, len: usize) {
+        self.rx_packets += 1;
+        self.rx_bytes += len as u64;
+    }
+
+    /// Count a write of `len` bytes, or its failure.
+    pub fn sent<T, E>(&mut self, len: usize, result: &Result<T, E>) {
+        if result.is_ok() {
+            self.tx_packets += 1;
+            self.tx_bytes += len as u64;
+        } else {
+            self.tx_errors += 1;
+        }
+    }
+
+    /// Counters by name, in the order of the struct.
+    pub fn values(&self) -> [(&'static str, u64); 10] {
+        [
+            ("rx_packets", self.rx_packets),
+            ("rx_bytes", self.rx_bytes),
+            ("rx_errors", self.rx_errors),
+            ("parse_errors", self.parse_errors),
+            ("stripped", self.stripped),
+            ("answered", self.answered),
+            ("ignored", self.ignored),
+            ("tx_packets", self.tx_packets),
+            ("tx_bytes", self.tx_bytes),
+            ("tx_errors", self.tx_errors),
+        ]
+    }
+
+    pub fn summary(&self) -> String {
+        self.values()
+            .iter()
+            .map(|(name, v)| format!("{}={}", name, v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Counters of both real edge interfaces.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TunCounters {
+    pub tun_a: InterfaceCounters,
+    pub tun_b: InterfaceCounters,
+}
+
+impl TunCounters {
+    /// Counters of the interface at `edge`.
+    pub fn edge_mut(&mut self, edge: Destination) -> &mut InterfaceCounters {
+        match edge {
+            Destination::TunA => &mut self.tun_a,
+            Destination::TunB => &mut self.tun_b,
+        }
+    }
+
+    /// Both interfaces with their names.
+    pub fn all(&self) -> [(&'static str, &InterfaceCounters); 2] {
+        [("tun_a", &self.tun_a), ("tun_b", &self.tun_b)]
+    }
+}
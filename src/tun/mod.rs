@@ -1,19 +1,27 @@
 #![allow(clippy::collapsible_else_if)]
 // src/tun/mod.rs
 
+pub mod counters;
+
 use crate::config::SimulatorConfig;
 use crate::config::VirtualCustomerConfig;
-use crate::packet::{calculate_ipv4_checksum, parse, PacketMeta};
+use crate::dhcp::DhcpServer;
+use crate::dns::{DnsInterceptor, PendingReplies};
+use crate::events::EventSchedule;
+use crate::pacing::{egress_time, EgressPacer};
+use crate::packet::{parse, parse_labeled, vlan, PacketMeta};
 use crate::processor::{process_packet, process_packet_multi};
+use crate::replay::sink::EgressSink;
+use crate::replay::ReverseTraffic;
 use crate::routing::multipath::MultiPathTable;
 use crate::routing::RoutingTable;
-use crate::routing::{compute_multi_path_routing, compute_routing, Destination};
+use crate::routing::{compute_multi_path_routing, compute_routing_seeded, Destination};
+use crate::simulation::with_paced_delays;
+use crate::tap::{TapEdge, TapInput, BROADCAST_MAC, ETHERTYPE_IPV4};
 use crate::topology::router::RouterId;
 use crate::topology::Fabric;
 
-use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
-
+use bytes::Bytes;
 use futures::future::pending; // used for idle handling when no virtual‑customer interval is configured
 use ipnet::IpNet;
 use tokio::select;
@@ -32,6 +40,34 @@ fn ip_in_prefix(ip: &std::net::IpAddr, prefix: &str) -> bool {
     }
 }
 
+/// Egress edge of a packet to `dst` that the injection rules sent towards `destination` (so it
+/// entered at the opposite edge), after the `[[destination_map]]` overrides.
+fn mapped_destination(
+    fabric: &Fabric,
+    destination: Destination,
+    dst: &std::net::IpAddr,
+) -> Destination {
+    let entered = match destination {
+        Destination::TunA => Destination::TunB,
+        Destination::TunB => Destination::TunA,
+    };
+    fabric.destination_map.egress(entered, dst)
+}
+
+/// Prefix length of an edge netmask, given either as a length or (IPv4) in dotted notation.
+fn edge_prefix_len(ip: std::net::IpAddr, netmask: &str) -> u8 {
+    let default = if ip.is_ipv4() { 24 } else { 64 };
+    if netmask.is_empty() {
+        default
+    } else if let Ok(p) = netmask.parse::<u8>() {
+        p
+    } else if let (true, Ok(mask)) = (ip.is_ipv4(), netmask.parse::<std::net::Ipv4Addr>()) {
+        mask.octets().iter().map(|b| b.count_ones() as u8).sum()
+    } else {
+        default
+    }
+}
+
 /// Mock TUN handling.
 /// If `packet_file` is specified in the config, each line of the file should contain a hex-encoded
 /// packet (e.g., "45000014..." without spaces). The function reads the file, parses each packet,
@@ -39,7 +75,7 @@ fn ip_in_prefix(ip: &std::net::IpAddr, prefix: &str) -> bool {
 /// In a full implementation this would interact with real TUN devices.
 // Helper function to generate a virtual‑customer packet
 async fn generate_virtual_packet(
-    vc: &VirtualCustomerConfig,
+    (vc, mirror): &(VirtualCustomerConfig, bool),
     cfg: &SimulatorConfig,
     fabric: &mut Fabric,
     routing_tables: &std::collections::HashMap<RouterId, RoutingTable>,
@@ -47,128 +83,496 @@ async fn generate_virtual_packet(
     ingress_a: &RouterId,
     ingress_b: &RouterId,
 ) {
-    // Determine ingress based on CIDR prefixes using the module‑level ip_in_prefix
-    if let (Some(src_str), Some(dst_str)) = (&vc.src_ip, &vc.dst_ip) {
-        // IPv4 handling
-        if let (Ok(src_ip), Ok(dst_ip)) = (
-            src_str.parse::<std::net::Ipv4Addr>(),
-            dst_str.parse::<std::net::Ipv4Addr>(),
-        ) {
-            let mut raw = vec![0u8; 20];
-            raw[0] = 0x45;
-            raw[1] = 0;
-            raw[2] = 0;
-            raw[3] = 20;
-            raw[4] = 0;
-            raw[5] = 0;
-            raw[6] = 0;
-            raw[7] = 0;
-            raw[8] = 64;
-            raw[9] = vc.protocol.unwrap_or(6);
-            raw[10] = 0;
-            raw[11] = 0;
-            raw[12..16].copy_from_slice(&src_ip.octets());
-            raw[16..20].copy_from_slice(&dst_ip.octets());
-            if let Some(sz) = vc.size {
-                raw.extend(vec![0u8; sz]);
-            }
-            let checksum = calculate_ipv4_checksum(&raw);
-            raw[10] = (checksum >> 8) as u8;
-            raw[11] = (checksum & 0xFF) as u8;
-            let packet = PacketMeta {
-                src_ip: std::net::IpAddr::V4(src_ip),
-                dst_ip: std::net::IpAddr::V4(dst_ip),
-                src_port: 0,
-                dst_port: 0,
-                protocol: raw[9],
-                ttl: 64,
-                raw,
-            };
-            let (ingress, destination) = if let Some(ref inject) = cfg.packet_inject_tun {
-                match inject.as_str() {
-                    "tun_a" => (ingress_a.clone(), Destination::TunB),
-                    "tun_b" => (ingress_b.clone(), Destination::TunA),
-                    _ => (ingress_a.clone(), Destination::TunB),
-                }
-            } else {
-                if ip_in_prefix(&packet.src_ip, &cfg.tun_ingress.tun_a_prefix) {
-                    (ingress_a.clone(), Destination::TunB)
-                } else if ip_in_prefix(&packet.src_ip, &cfg.tun_ingress.tun_b_prefix) {
-                    (ingress_b.clone(), Destination::TunA)
-                } else {
-                    (ingress_a.clone(), Destination::TunB)
-                }
-            };
-            debug!(
-                "Processing virtual customer IPv4 packet at ingress {}",
-                ingress.0
-            );
-            if cfg.enable_multipath {
-                process_packet_multi(fabric, multipath_tables, ingress, packet, destination).await;
-            } else {
-                process_packet(fabric, routing_tables, ingress, packet, destination).await;
+    let (Some(src_str), Some(dst_str)) = (&vc.src_ip, &vc.dst_ip) else {
+        warn!("virtual_customer missing src_ip or dst_ip");
+        return;
+    };
+    // Packets are numbered per customer, so the far edge can check the payload pattern.
+    let seq = fabric
+        .customer_flows
+        .get(vc.customer_name())
+        .map_or(0, |flow| flow.sent);
+    let Some(packet) = crate::customer::build_packet(vc, seq) else {
+        warn!(
+            "Invalid IPs in virtual_customer: src='{}', dst='{}'",
+            src_str, dst_str
+        );
+        return;
+    };
+    // Determine ingress based on CIDR prefixes using the module‑level ip_in_prefix. A mirror
+    // flow goes the opposite way of the flow it mirrors, whose source is its destination.
+    let origin = if *mirror {
+        &packet.dst_ip
+    } else {
+        &packet.src_ip
+    };
+    let towards_b = if let Some(ref inject) = cfg.packet_inject_tun {
+        inject != "tun_b"
+    } else {
+        ip_in_prefix(origin, &cfg.tun_ingress.tun_a_prefix)
+            || !ip_in_prefix(origin, &cfg.tun_ingress.tun_b_prefix)
+    };
+    let (ingress, destination) = if towards_b != *mirror {
+        (ingress_a.clone(), Destination::TunB)
+    } else {
+        (ingress_b.clone(), Destination::TunA)
+    };
+    let destination = mapped_destination(fabric, destination, &packet.dst_ip);
+    debug!(
+        "Processing virtual customer {} packet {} at ingress {}",
+        if packet.src_ip.is_ipv4() {
+            "IPv4"
+        } else {
+            "IPv6"
+        },
+        seq,
+        ingress.0
+    );
+    let egress = match destination {
+        Destination::TunA => ingress_a,
+        Destination::TunB => ingress_b,
+    };
+    forward_customer_packet(
+        vc,
+        cfg,
+        fabric,
+        routing_tables,
+        multipath_tables,
+        (ingress, destination, egress),
+        (seq, packet),
+    )
+    .await;
+}
+
+/// Forward one virtual‑customer packet and record it in the customer's flow metrics.
+/// The packet counts as delivered if the egress router's delivered counter advanced; a delivered
+/// packet whose payload no longer matches packet number `seq` counts as a payload error.
+async fn forward_customer_packet(
+    vc: &VirtualCustomerConfig,
+    cfg: &SimulatorConfig,
+    fabric: &mut Fabric,
+    routing_tables: &std::collections::HashMap<RouterId, RoutingTable>,
+    multipath_tables: &std::collections::HashMap<RouterId, MultiPathTable>,
+    (ingress, destination, egress): (RouterId, Destination, &RouterId),
+    (seq, packet): (u64, PacketMeta),
+) {
+    let delivered_at = |fabric: &Fabric| {
+        fabric
+            .get_router(egress)
+            .map(|r| r.stats.packets_delivered)
+            .unwrap_or(0)
+    };
+    let before = delivered_at(fabric);
+    let bytes = packet.raw.len();
+    let sent_at = tokio::time::Instant::now();
+    let processed = if cfg.enable_multipath {
+        process_packet_multi(fabric, multipath_tables, ingress, packet, destination).await
+    } else {
+        process_packet(fabric, routing_tables, ingress, packet, destination).await
+    };
+    let latency = (delivered_at(fabric) > before).then(|| sent_at.elapsed());
+    let flow = fabric
+        .customer_flows
+        .entry(vc.customer_name().to_string())
+        .or_default();
+    flow.record(sent_at, bytes, latency);
+    if latency.is_some() && !crate::customer::payload_intact(vc, seq, &processed) {
+        warn!(
+            "Virtual customer {} packet {} arrived with an altered payload",
+            vc.customer_name(),
+            seq
+        );
+        flow.payload_errors += 1;
+    }
+}
+
+/// Forward a packet that entered the fabric at an edge. With NAT44, a packet from the inside
+/// edge is translated on the way in and one towards the external address on the way out
+/// (`None` if the NAT drops it). With packet marking enabled the packet
+/// is stamped on the way in and, if the far edge received it, verified on the way out. A pipe
+/// TTL model swaps the customer's TTL for the fabric's on the way in and back out. With a
+/// pseudowire and/or encrypted overlay it crosses the fabric encapsulated (the pseudowire
+/// innermost), and only a packet the far end accepted leaves the fabric (`None` otherwise).
+async fn forward_edge_packet(
+    cfg: &SimulatorConfig,
+    fabric: &mut Fabric,
+    routing_tables: &std::collections::HashMap<RouterId, RoutingTable>,
+    multipath_tables: &std::collections::HashMap<RouterId, MultiPathTable>,
+    ingress: RouterId,
+    destination: Destination,
+    mut packet: PacketMeta,
+) -> Option<PacketMeta> {
+    let egress = RouterId(match destination {
+        Destination::TunA => cfg.tun_ingress.tun_a_ingress.clone(),
+        Destination::TunB => cfg.tun_ingress.tun_b_ingress.clone(),
+    });
+    let delivered_at = |fabric: &Fabric| {
+        fabric
+            .get_router(&egress)
+            .map(|r| r.stats.packets_delivered)
+            .unwrap_or(0)
+    };
+    if let Some(nat) = fabric.nat44.as_mut() {
+        if destination != nat.inside && !nat.outbound(&mut packet) {
+            return None;
+        }
+    }
+    let customer_ttl = fabric
+        .ttl
+        .as_ref()
+        .and_then(|ttl| ttl.ingress(destination, &mut packet));
+    let stamped = fabric
+        .marking
+        .as_mut()
+        .is_some_and(|m| m.marker_mut(destination).stamp(&mut packet));
+    if let Some(pw) = fabric.pseudowire.as_mut() {
+        packet = pw.session_mut(destination).encapsulate(&packet);
+    }
+    if let Some(wg) = fabric.wireguard.as_mut() {
+        packet = wg.tunnel_mut(destination).encrypt(&packet);
+    }
+    if let Some(srv6) = fabric.srv6.as_mut() {
+        srv6.insert(destination, &mut packet);
+    }
+    let before = delivered_at(fabric);
+    let mut processed = if cfg.enable_multipath {
+        process_packet_multi(fabric, multipath_tables, ingress, packet, destination).await
+    } else {
+        process_packet(fabric, routing_tables, ingress, packet, destination).await
+    };
+    let delivered = delivered_at(fabric) > before;
+    if fabric.pseudowire.is_some() || fabric.wireguard.is_some() {
+        // Only the far tunnel end may hand the inner packet on, and it does not reassemble a
+        // fragmented outer packet.
+        fabric.fragments_out.clear();
+        if !delivered || crate::packet::is_fragment(&processed.raw) {
+            return None;
+        }
+    }
+    if let Some(wg) = fabric.wireguard.as_mut() {
+        processed = wg.tunnel_mut(destination).decrypt(&processed).ok()?;
+    }
+    if let Some(pw) = fabric.pseudowire.as_mut() {
+        processed = pw.session_mut(destination).decapsulate(&processed).ok()?;
+    }
+    if stamped && delivered {
+        if let Some(marking) = fabric.marking.as_mut() {
+            let strip = marking.strip;
+            marking
+                .marker_mut(destination)
+                .verify(&mut processed, strip);
+        }
+    }
+    if let Some(ttl) = customer_ttl.filter(|_| delivered) {
+        processed.set_ttl(ttl);
+    }
+    if let Some(nat) = fabric.nat44.as_mut() {
+        if !nat.inbound(&mut processed) {
+            return None;
+        }
+    }
+    Some(processed)
+}
+
+/// Forward an edge packet like [`forward_edge_packet`]. With DNS interception enabled, a query
+/// that reaches the far edge is answered there: its reply is queued in `pending` for the
+/// resolution delay and `None` is returned, since nothing leaves the fabric.
+#[allow(clippy::too_many_arguments)]
+async fn forward_or_resolve(
+    cfg: &SimulatorConfig,
+    fabric: &mut Fabric,
+    routing_tables: &std::collections::HashMap<RouterId, RoutingTable>,
+    multipath_tables: &std::collections::HashMap<RouterId, MultiPathTable>,
+    dns: Option<&DnsInterceptor>,
+    pending: &mut PendingReplies,
+    ingress: RouterId,
+    destination: Destination,
+    packet: PacketMeta,
+) -> Option<PacketMeta> {
+    let egress = RouterId(match destination {
+        Destination::TunA => cfg.tun_ingress.tun_a_ingress.clone(),
+        Destination::TunB => cfg.tun_ingress.tun_b_ingress.clone(),
+    });
+    let delivered_at = |fabric: &Fabric| {
+        fabric
+            .get_router(&egress)
+            .map(|r| r.stats.packets_delivered)
+            .unwrap_or(0)
+    };
+    let before = delivered_at(fabric);
+    let processed = forward_edge_packet(
+        cfg,
+        fabric,
+        routing_tables,
+        multipath_tables,
+        ingress,
+        destination,
+        packet,
+    )
+    .await?;
+    if let Some(dns) = dns.filter(|_| DnsInterceptor::is_query(&processed)) {
+        if delivered_at(fabric) > before {
+            if let Some(reply) = dns.respond(&processed) {
+                let back = match destination {
+                    Destination::TunA => Destination::TunB,
+                    Destination::TunB => Destination::TunA,
+                };
+                pending.push(reply, back);
+                return None;
             }
-        } else if let (Ok(src_ip), Ok(dst_ip)) = (
-            src_str.parse::<std::net::Ipv6Addr>(),
-            dst_str.parse::<std::net::Ipv6Addr>(),
-        ) {
-            // IPv6 handling
-            let mut raw = vec![0u8; 40];
-            raw[0] = 0x60;
-            let payload_len_pos = 4;
-            raw[payload_len_pos] = 0;
-            raw[payload_len_pos + 1] = 0;
-            raw[6] = vc.protocol.unwrap_or(6);
-            raw[7] = 64;
-            raw[8..24].copy_from_slice(&src_ip.octets());
-            raw[24..40].copy_from_slice(&dst_ip.octets());
-            if let Some(sz) = vc.size {
-                raw.extend(vec![0u8; sz]);
+        }
+    }
+    Some(processed)
+}
+
+/// Send the DNS replies whose resolution delay has passed back across the fabric. Returns each
+/// reply as it leaves the fabric, with the edge it leaves on.
+async fn release_dns_replies(
+    cfg: &SimulatorConfig,
+    fabric: &mut Fabric,
+    routing_tables: &std::collections::HashMap<RouterId, RoutingTable>,
+    multipath_tables: &std::collections::HashMap<RouterId, MultiPathTable>,
+    pending: &mut PendingReplies,
+) -> Vec<(PacketMeta, Destination)> {
+    let mut released = Vec::new();
+    while let Some((reply, destination)) = pending.pop_due(tokio::time::Instant::now()) {
+        // The resolver sits behind the far edge, so replies enter there.
+        let ingress = RouterId(match destination {
+            Destination::TunA => cfg.tun_ingress.tun_b_ingress.clone(),
+            Destination::TunB => cfg.tun_ingress.tun_a_ingress.clone(),
+        });
+        if let Some(processed) = forward_edge_packet(
+            cfg,
+            fabric,
+            routing_tables,
+            multipath_tables,
+            ingress,
+            destination,
+            reply,
+        )
+        .await
+        {
+            released.push((processed, destination));
+        }
+    }
+    released
+}
+
+/// Pass a packet leaving the fabric through the egress reassembly and duplicate removal, if
+/// configured: a fragment is held until its datagram is complete, and a duplicate of a recent
+/// packet is dropped. Returns what leaves now.
+fn reassemble(fabric: &mut Fabric, raw: Bytes) -> Option<Bytes> {
+    let now = tokio::time::Instant::now();
+    let raw = match fabric.reassembly.as_mut() {
+        Some(reassembly) => Bytes::from(reassembly.offer(raw.into(), now)?),
+        None => raw,
+    };
+    match fabric.dedupe.as_mut() {
+        Some(dedupe) => dedupe.offer(&raw, now).then_some(raw),
+        None => Some(raw),
+    }
+}
+
+/// Write packets leaving the fabric to a mock output file: processed packets, released DNS
+/// replies or synthesized reverse traffic, each towards its edge, followed by the further
+/// fragments forwarding left behind. All of them pass through [`reassemble`].
+async fn write_egress(
+    out_file: &mut EgressSink,
+    fabric: &mut Fabric,
+    packets: Vec<(PacketMeta, Destination)>,
+) {
+    let fragments = std::mem::take(&mut fabric.fragments_out);
+    for (packet, destination) in packets.into_iter().chain(fragments) {
+        let Some(raw) = reassemble(fabric, packet.raw) else {
+            continue;
+        };
+        out_file.send(raw, destination).await;
+    }
+}
+
+/// Packets delivered so far by the edge router towards `destination`.
+fn edge_delivered(cfg: &SimulatorConfig, fabric: &Fabric, destination: Destination) -> u64 {
+    let egress = RouterId(match destination {
+        Destination::TunA => cfg.tun_ingress.tun_a_ingress.clone(),
+        Destination::TunB => cfg.tun_ingress.tun_b_ingress.clone(),
+    });
+    fabric
+        .get_router(&egress)
+        .map(|r| r.stats.packets_delivered)
+        .unwrap_or(0)
+}
+
+/// Send the synthesized replies to `packet`, which left the fabric towards `destination`, back
+/// from the far edge. Returns each reply that left the fabric at the near edge.
+async fn send_reverse_traffic(
+    cfg: &SimulatorConfig,
+    fabric: &mut Fabric,
+    routing_tables: &std::collections::HashMap<RouterId, RoutingTable>,
+    multipath_tables: &std::collections::HashMap<RouterId, MultiPathTable>,
+    reverse: &mut ReverseTraffic,
+    packet: &PacketMeta,
+    destination: Destination,
+) -> Vec<(PacketMeta, Destination)> {
+    let (ingress, back) = match destination {
+        Destination::TunA => (cfg.tun_ingress.tun_a_ingress.clone(), Destination::TunB),
+        Destination::TunB => (cfg.tun_ingress.tun_b_ingress.clone(), Destination::TunA),
+    };
+    let mut released = Vec::new();
+    for reply in reverse.respond(packet) {
+        let before = edge_delivered(cfg, fabric, back);
+        if let Some(processed) = forward_edge_packet(
+            cfg,
+            fabric,
+            routing_tables,
+            multipath_tables,
+            RouterId(ingress.clone()),
+            back,
+            reply,
+        )
+        .await
+        {
+            if edge_delivered(cfg, fabric, back) > before {
+                reverse.delivered += 1;
+                released.push((processed, back));
             }
-            let payload_len = (raw.len() - 40) as u16;
-            raw[payload_len_pos] = (payload_len >> 8) as u8;
-            raw[payload_len_pos + 1] = (payload_len & 0xFF) as u8;
-            let packet = PacketMeta {
-                src_ip: std::net::IpAddr::V6(src_ip),
-                dst_ip: std::net::IpAddr::V6(dst_ip),
-                src_port: 0,
-                dst_port: 0,
-                protocol: raw[6],
-                ttl: raw[7],
-                raw,
-            };
-            let (ingress, destination) = if let Some(ref inject) = cfg.packet_inject_tun {
-                match inject.as_str() {
-                    "tun_a" => (ingress_a.clone(), Destination::TunB),
-                    "tun_b" => (ingress_b.clone(), Destination::TunA),
-                    _ => (ingress_a.clone(), Destination::TunB),
-                }
-            } else {
-                if ip_in_prefix(&packet.src_ip, &cfg.tun_ingress.tun_a_prefix) {
-                    (ingress_a.clone(), Destination::TunB)
-                } else if ip_in_prefix(&packet.src_ip, &cfg.tun_ingress.tun_b_prefix) {
-                    (ingress_b.clone(), Destination::TunA)
-                } else {
-                    (ingress_a.clone(), Destination::TunB)
-                }
-            };
-            debug!(
-                "Processing virtual customer IPv6 packet at ingress {}",
-                ingress.0
-            );
-            if cfg.enable_multipath {
-                process_packet_multi(fabric, multipath_tables, ingress, packet, destination).await;
-            } else {
-                process_packet(fabric, routing_tables, ingress, packet, destination).await;
+        }
+    }
+    released
+}
+
+/// Warm‑up phase: statistics gathered before the deadline are discarded.
+struct Warmup {
+    deadline: Option<tokio::time::Instant>,
+}
+
+impl Warmup {
+    fn new(secs: f64) -> Self {
+        let deadline = (secs > 0.0)
+            .then(|| tokio::time::Instant::now() + std::time::Duration::from_secs_f64(secs));
+        Self { deadline }
+    }
+
+    /// Reset the fabric counters once the warm‑up deadline has passed.
+    fn check(&mut self, fabric: &mut Fabric) {
+        if let Some(deadline) = self.deadline {
+            if tokio::time::Instant::now() >= deadline {
+                info!("Warm-up phase over, resetting statistics");
+                fabric.reset_statistics();
+                self.deadline = None;
             }
-        } else {
-            warn!(
-                "Invalid IPs in virtual_customer: src='{}', dst='{}'",
-                src_str, dst_str
-            );
         }
+    }
+}
+
+/// Compute the routing tables (and multipath tables, if enabled) from the current state of
+/// the fabric's links and routers; with `[distance_vector]`, the edge routes are those the
+/// routers have learned. Both are computed in full before they replace the old ones, so no
+/// packet sees a mix of the two. Pinned ECMP flows whose next hop is no longer an equal-cost
+/// choice are released, so that they are hashed again over the new next hops.
+fn recompute_routing(
+    cfg: &SimulatorConfig,
+    fabric: &Fabric,
+    ingress_a: &RouterId,
+    ingress_b: &RouterId,
+    routing_tables: &mut std::collections::HashMap<RouterId, RoutingTable>,
+    multipath_tables: &mut std::collections::HashMap<RouterId, MultiPathTable>,
+) {
+    let mut tables = compute_routing_seeded(
+        fabric,
+        ingress_a.clone(),
+        ingress_b.clone(),
+        cfg.simulation.tie_break_seed,
+    );
+    if let Some(dv) = &fabric.distance_vector {
+        dv.install(&mut tables);
+    }
+    let multipath = if cfg.enable_multipath {
+        compute_multi_path_routing(fabric, ingress_a.clone(), ingress_b.clone())
     } else {
-        warn!("virtual_customer missing src_ip or dst_ip");
+        std::collections::HashMap::new()
+    };
+    *routing_tables = tables;
+    *multipath_tables = multipath;
+    if cfg.enable_multipath {
+        let released = fabric.flows.revalidate(multipath_tables);
+        if released > 0 {
+            info!("Routing changed, {} pinned flows released", released);
+        }
+    }
+}
+
+/// Routing after an event changed the topology: recomputed at once, or with `[link_state]`
+/// flooded to the routers, which install their new tables as their SPF runs come due. With
+/// `[distance_vector]` the routers at the ends of failed links drop the routes over them and
+/// the others follow with the periodic updates.
+fn topology_changed(
+    cfg: &SimulatorConfig,
+    fabric: &mut Fabric,
+    ingress_a: &RouterId,
+    ingress_b: &RouterId,
+    routing_tables: &mut std::collections::HashMap<RouterId, RoutingTable>,
+    multipath_tables: &mut std::collections::HashMap<RouterId, MultiPathTable>,
+) {
+    if let Some(mut dv) = fabric.distance_vector.take() {
+        dv.topology_changed(fabric);
+        fabric.distance_vector = Some(dv);
+    }
+    if fabric.convergence.is_none() {
+        recompute_routing(
+            cfg,
+            fabric,
+            ingress_a,
+            ingress_b,
+            routing_tables,
+            multipath_tables,
+        );
+        return;
+    }
+    let tables = compute_routing_seeded(
+        fabric,
+        ingress_a.clone(),
+        ingress_b.clone(),
+        cfg.simulation.tie_break_seed,
+    );
+    let multipath = if cfg.enable_multipath {
+        compute_multi_path_routing(fabric, ingress_a.clone(), ingress_b.clone())
+    } else {
+        std::collections::HashMap::new()
+    };
+    if let Some(mut convergence) = fabric.convergence.take() {
+        convergence.flood(fabric, tables, multipath);
+        fabric.convergence = Some(convergence);
+    }
+    converge(fabric, routing_tables, multipath_tables);
+}
+
+/// With `[link_state]`, install the tables of the routers whose SPF run is due; with
+/// `[distance_vector]`, exchange the updates that are due.
+fn converge(
+    fabric: &mut Fabric,
+    routing_tables: &mut std::collections::HashMap<RouterId, RoutingTable>,
+    multipath_tables: &mut std::collections::HashMap<RouterId, MultiPathTable>,
+) {
+    if let Some(dv) = fabric.distance_vector.as_mut() {
+        if dv.update_due() {
+            dv.install(routing_tables);
+        }
+    }
+    let Some(convergence) = fabric.convergence.as_mut() else {
+        return;
+    };
+    if convergence.install_due(routing_tables, multipath_tables) && !multipath_tables.is_empty() {
+        let released = fabric.flows.revalidate(multipath_tables);
+        if released > 0 {
+            info!("Routing changed, {} pinned flows released", released);
+        }
+    }
+}
+
+async fn sleep_until_opt(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(d) => tokio::time::sleep_until(d).await,
+        None => pending::<()>().await,
     }
 }
 
@@ -185,37 +589,101 @@ pub async fn start(
         && cfg.packet_file.is_none()
         && cfg.packet_files.is_none()
         && cfg.virtual_customer.is_none()
+        && cfg.twamp.is_none()
+        && cfg.ddos.is_none()
     {
         // No real TUN to handle and nothing to mock; nothing to do.
         return Ok(());
     }
-    // Compute routing tables once.
-    // Compute routing tables once.
+    let mut warmup = Warmup::new(cfg.simulation.warmup_secs);
+    let mut events = EventSchedule::new(&cfg.events).with_faults(fabric.faults.take());
+    // Compute routing tables; they are computed again whenever an event changes the topology.
     let ingress_a = RouterId(cfg.tun_ingress.tun_a_ingress.clone());
     let ingress_b = RouterId(cfg.tun_ingress.tun_b_ingress.clone());
-    let routing_tables = compute_routing(fabric, ingress_a.clone(), ingress_b.clone());
-    let multipath_tables = if cfg.enable_multipath {
-        compute_multi_path_routing(fabric, ingress_a.clone(), ingress_b.clone())
-    } else {
-        std::collections::HashMap::new()
-    };
+    let mut routing_tables = std::collections::HashMap::new();
+    let mut multipath_tables = std::collections::HashMap::new();
+    recompute_routing(
+        cfg,
+        fabric,
+        &ingress_a,
+        &ingress_b,
+        &mut routing_tables,
+        &mut multipath_tables,
+    );
     // ip_in_prefix function defined above; vc_interval already declared above
 
-    // Virtual customer packet generation (burst)
+    // DNS interception (answers queries at the far edge instead of letting them leave).
+    let dns_interceptor = match &cfg.dns {
+        Some(dns_cfg) => {
+            let interceptor = DnsInterceptor::from_config(dns_cfg)?;
+            info!(
+                "DNS interception enabled ({} names, {} ms delay)",
+                interceptor.zone.len(),
+                interceptor.delay_ms
+            );
+            Some(interceptor)
+        }
+        None => None,
+    };
+    let mut dns_pending = PendingReplies::new(dns_interceptor.as_ref().map_or(0, |d| d.delay_ms));
+    // Synthesized replies to one‑sided packet files.
+    let mut reverse = cfg.reverse_traffic.as_ref().map(ReverseTraffic::new);
+
+    // TWAMP‑light measurement session between the two ingress routers.
+    if let Some(twamp_cfg) = &cfg.twamp {
+        info!(
+            "Starting TWAMP session ({} packets) from {} to {}",
+            twamp_cfg.count, ingress_a.0, ingress_b.0
+        );
+        let report = crate::twamp::run_session(
+            twamp_cfg,
+            fabric,
+            &routing_tables,
+            &multipath_tables,
+            cfg.enable_multipath,
+            &ingress_a,
+            &ingress_b,
+        )
+        .await;
+        info!("TWAMP: {}", report.summary());
+        fabric.twamp_report = Some(report);
+    }
+
+    // Spoofed‑source flood towards the configured target prefix.
+    if let Some(ddos_cfg) = &cfg.ddos {
+        info!(
+            "Starting flood towards {} at {} pps for {} s",
+            ddos_cfg.target_prefix, ddos_cfg.rate_pps, ddos_cfg.duration_secs
+        );
+        let report =
+            crate::ddos::run_flood(cfg, ddos_cfg, fabric, &routing_tables, &multipath_tables)
+                .await?;
+        info!("DDoS flood: {}", report.summary());
+        fabric.ddos_report = Some(report);
+    }
+
+    // Virtual customer packet generation (burst), with its mirror flow if `reverse` is set.
+    let customer_flows: Vec<(VirtualCustomerConfig, bool)> = cfg
+        .virtual_customer
+        .iter()
+        .flat_map(|vc| std::iter::once((vc.clone(), false)).chain(vc.mirrored().map(|m| (m, true))))
+        .collect();
     if let Some(vc) = &cfg.virtual_customer {
         // Initial burst based on rate (default 1)
         let packet_count = vc.rate.unwrap_or(1) as usize;
         for _ in 0..packet_count {
-            generate_virtual_packet(
-                vc,
-                cfg,
-                fabric,
-                &routing_tables,
-                &multipath_tables,
-                &ingress_a,
-                &ingress_b,
-            )
-            .await;
+            for flow in &customer_flows {
+                generate_virtual_packet(
+                    flow,
+                    cfg,
+                    fabric,
+                    &routing_tables,
+                    &multipath_tables,
+                    &ingress_a,
+                    &ingress_b,
+                )
+                .await;
+            }
         }
         // Setup periodic interval if rate > 0
         if let Some(rate) = vc.rate {
@@ -226,34 +694,41 @@ pub async fn start(
             }
         }
     }
+    let output = cfg.replay_output.clone().unwrap_or_default();
     if let Some(ref path) = cfg.packet_file {
         info!("Reading mock packets from {}", path);
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
+        let packets = crate::replay::open(path)?;
         // Prepare output file to capture packets exiting the mock TUN.
-        let out_path = format!("{}_out.txt", path);
-        let mut out_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&out_path)
-            .map_err(|e| format!("Failed to open output file {}: {}", out_path, e))?;
-        for (idx, line_res) in reader.lines().enumerate() {
-            let raw_line = line_res?;
-            let line = raw_line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
+        let out_path = crate::replay::sink::output_path(path, &output);
+        let mut out_file = EgressSink::open(&out_path, &output, fabric.run.as_ref())?;
+        for (num, bytes) in packets {
+            warmup.check(fabric);
+            if events.apply_due(fabric) {
+                topology_changed(
+                    cfg,
+                    fabric,
+                    &ingress_a,
+                    &ingress_b,
+                    &mut routing_tables,
+                    &mut multipath_tables,
+                );
             }
-            let bytes = match hex::decode(line) {
+            converge(fabric, &mut routing_tables, &mut multipath_tables);
+            events.count_packet();
+            crate::alarms::tick(fabric);
+            crate::telemetry::tick(fabric);
+            crate::snapshot::tick(fabric);
+            let bytes = match bytes {
                 Ok(b) => b,
                 Err(e) => {
-                    warn!("Failed to decode hex on line {}: {}", idx + 1, e);
+                    warn!("Failed to decode packet {}: {}", num, e);
                     continue;
                 }
             };
             let packet = match parse(&bytes) {
                 Ok(p) => p,
                 Err(e) => {
-                    error!("Failed to parse packet on line {}: {}", idx + 1, e);
+                    error!("Failed to parse packet {}: {}", num, e);
                     continue;
                 }
             };
@@ -301,53 +776,106 @@ pub async fn start(
                     (ingress_a.clone(), Destination::TunB)
                 }
             };
-            debug!(
-                "Processing mock packet {} at ingress {}",
-                idx + 1,
-                ingress.0
-            );
-            let processed = if cfg.enable_multipath {
-                process_packet_multi(fabric, &multipath_tables, ingress, packet, destination).await
-            } else {
-                process_packet(fabric, &routing_tables, ingress, packet, destination).await
-            };
-            // Write processed packet raw bytes as hex to output file.
-            let hex_str = hex::encode(&processed.raw);
-            if let Err(e) = writeln!(out_file, "{}", hex_str) {
-                error!("Failed to write processed packet to output file: {}", e);
+            let destination = mapped_destination(fabric, destination, &packet.dst_ip);
+            debug!("Processing mock packet {} at ingress {}", num, ingress.0);
+            let delivered_before = edge_delivered(cfg, fabric, destination);
+            let processed = forward_or_resolve(
+                cfg,
+                fabric,
+                &routing_tables,
+                &multipath_tables,
+                dns_interceptor.as_ref(),
+                &mut dns_pending,
+                ingress,
+                destination,
+                packet,
+            )
+            .await;
+            // Write the processed packet to the output file.
+            if let Some(processed) = processed {
+                write_egress(
+                    &mut out_file,
+                    fabric,
+                    vec![(processed.clone(), destination)],
+                )
+                .await;
+                if let Some(reverse) = reverse.as_mut() {
+                    if edge_delivered(cfg, fabric, destination) > delivered_before {
+                        let replies = send_reverse_traffic(
+                            cfg,
+                            fabric,
+                            &routing_tables,
+                            &multipath_tables,
+                            reverse,
+                            &processed,
+                            destination,
+                        )
+                        .await;
+                        write_egress(&mut out_file, fabric, replies).await;
+                    }
+                }
             }
+            let replies = release_dns_replies(
+                cfg,
+                fabric,
+                &routing_tables,
+                &multipath_tables,
+                &mut dns_pending,
+            )
+            .await;
+            write_egress(&mut out_file, fabric, replies).await;
+        }
+        // Replies still waiting out their resolution delay are written once due.
+        while let Some(deadline) = dns_pending.next_deadline() {
+            tokio::time::sleep_until(deadline).await;
+            let replies = release_dns_replies(
+                cfg,
+                fabric,
+                &routing_tables,
+                &multipath_tables,
+                &mut dns_pending,
+            )
+            .await;
+            write_egress(&mut out_file, fabric, replies).await;
         }
+        out_file.finish().await?;
     } else if let Some(ref files) = cfg.packet_files {
         // Multiple packet files handling.
         let injects = cfg.packet_inject_tuns.clone().unwrap_or_default();
         for (i, path) in files.iter().enumerate() {
             info!("Reading mock packets from {}", path);
-            let file = File::open(path)?;
-            let reader = BufReader::new(file);
-            let out_path = format!("{}_out.txt", path);
-            let mut out_file = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&out_path)
-                .map_err(|e| format!("Failed to open output file {}: {}", out_path, e))?;
+            let packets = crate::replay::open(path)?;
+            let out_path = crate::replay::sink::output_path(path, &output);
+            let mut out_file = EgressSink::open(&out_path, &output, fabric.run.as_ref())?;
             let inject_opt = injects.get(i).cloned();
-            for (idx, line_res) in reader.lines().enumerate() {
-                let raw_line = line_res?;
-                let line = raw_line.trim();
-                if line.is_empty() || line.starts_with('#') {
-                    continue;
+            for (num, bytes) in packets {
+                warmup.check(fabric);
+                if events.apply_due(fabric) {
+                    topology_changed(
+                        cfg,
+                        fabric,
+                        &ingress_a,
+                        &ingress_b,
+                        &mut routing_tables,
+                        &mut multipath_tables,
+                    );
                 }
-                let bytes = match hex::decode(line) {
+                converge(fabric, &mut routing_tables, &mut multipath_tables);
+                events.count_packet();
+                crate::alarms::tick(fabric);
+                crate::telemetry::tick(fabric);
+                crate::snapshot::tick(fabric);
+                let bytes = match bytes {
                     Ok(b) => b,
                     Err(e) => {
-                        warn!("Failed to decode hex on line {}: {}", idx + 1, e);
+                        warn!("Failed to decode packet {} of {}: {}", num, path, e);
                         continue;
                     }
                 };
                 let packet = match parse(&bytes) {
                     Ok(p) => p,
                     Err(e) => {
-                        error!("Failed to parse packet on line {}: {}", idx + 1, e);
+                        error!("Failed to parse packet {} of {}: {}", num, path, e);
                         continue;
                     }
                 };
@@ -396,20 +924,74 @@ pub async fn start(
                         (ingress_a.clone(), Destination::TunB)
                     }
                 };
-                let processed = if cfg.enable_multipath {
-                    process_packet_multi(fabric, &multipath_tables, ingress, packet, destination)
-                        .await
-                } else {
-                    process_packet(fabric, &routing_tables, ingress, packet, destination).await
-                };
-                let hex_str = hex::encode(&processed.raw);
-                if let Err(e) = writeln!(out_file, "{}", hex_str) {
-                    error!("Failed to write processed packet to output file: {}", e);
+                let destination = mapped_destination(fabric, destination, &packet.dst_ip);
+                let delivered_before = edge_delivered(cfg, fabric, destination);
+                let processed = forward_or_resolve(
+                    cfg,
+                    fabric,
+                    &routing_tables,
+                    &multipath_tables,
+                    dns_interceptor.as_ref(),
+                    &mut dns_pending,
+                    ingress,
+                    destination,
+                    packet,
+                )
+                .await;
+                if let Some(processed) = processed {
+                    write_egress(
+                        &mut out_file,
+                        fabric,
+                        vec![(processed.clone(), destination)],
+                    )
+                    .await;
+                    if let Some(reverse) = reverse.as_mut() {
+                        if edge_delivered(cfg, fabric, destination) > delivered_before {
+                            let replies = send_reverse_traffic(
+                                cfg,
+                                fabric,
+                                &routing_tables,
+                                &multipath_tables,
+                                reverse,
+                                &processed,
+                                destination,
+                            )
+                            .await;
+                            write_egress(&mut out_file, fabric, replies).await;
+                        }
+                    }
                 }
+                let replies = release_dns_replies(
+                    cfg,
+                    fabric,
+                    &routing_tables,
+                    &multipath_tables,
+                    &mut dns_pending,
+                )
+                .await;
+                write_egress(&mut out_file, fabric, replies).await;
+            }
+            // Replies to queries from this file are written to its output once due.
+            while let Some(deadline) = dns_pending.next_deadline() {
+                tokio::time::sleep_until(deadline).await;
+                let replies = release_dns_replies(
+                    cfg,
+                    fabric,
+                    &routing_tables,
+                    &multipath_tables,
+                    &mut dns_pending,
+                )
+                .await;
+                write_egress(&mut out_file, fabric, replies).await;
             }
+            out_file.finish().await?;
         }
     }
 
+    if let Some(reverse) = &reverse {
+        info!("Reverse traffic: {}", reverse.summary());
+    }
+
     // If mock packet handling was performed, skip real TUN handling.
     if cfg.packet_file.is_some() || cfg.packet_files.is_some() {
         return Ok(());
@@ -422,8 +1004,10 @@ pub async fn start(
         name: &str,
         addr_str: &str,
         netmask_str: &str,
+        tap: bool,
+        mtu: u16,
     ) -> Result<AsyncDevice, String> {
-        use tun_rs::DeviceBuilder;
+        use tun_rs::{DeviceBuilder, Layer};
 
         // Parse address, supporting both IPv4 and IPv6.
         let ip_addr = addr_str
@@ -431,40 +1015,34 @@ pub async fn start(
             .map_err(|_| format!("Invalid IP address for TUN {}: '{}'", name, addr_str))?;
 
         let mut builder = DeviceBuilder::new().name(name);
+        if tap {
+            // The edge address belongs to the simulated router on the segment, not the host.
+            return builder
+                .layer(Layer::L2)
+                .mtu(mtu)
+                .build_async()
+                .map_err(|e| e.to_string());
+        }
 
         match ip_addr {
             std::net::IpAddr::V4(v4) => {
-                // Parse netmask as prefix length or dotted notation
-                let prefix: u8 = if netmask_str.is_empty() {
-                    24
-                } else if let Ok(p) = netmask_str.parse::<u8>() {
-                    p
-                } else if let Ok(mask) = netmask_str.parse::<std::net::Ipv4Addr>() {
-                    // Convert netmask to prefix length
-                    mask.octets().iter().map(|b| b.count_ones() as u8).sum()
-                } else {
-                    24
-                };
-                builder = builder.ipv4(v4, prefix, None);
+                builder = builder.ipv4(v4, edge_prefix_len(ip_addr, netmask_str), None);
             }
             std::net::IpAddr::V6(v6) => {
-                let prefix: u8 = if netmask_str.is_empty() {
-                    64
-                } else {
-                    netmask_str.parse::<u8>().unwrap_or(64)
-                };
-                builder = builder.ipv6(v6, prefix);
+                builder = builder.ipv6(v6, edge_prefix_len(ip_addr, netmask_str));
             }
         }
 
         // Build the async TUN device
-        builder.mtu(1500).build_async().map_err(|e| e.to_string())
+        builder.mtu(mtu).build_async().map_err(|e| e.to_string())
     }
 
     let async_dev_a = match create_async_tun(
         &cfg.interfaces.real_tun_a.name,
         &cfg.interfaces.real_tun_a.address,
         &cfg.interfaces.real_tun_a.netmask,
+        cfg.interfaces.real_tun_a.tap,
+        cfg.simulation.mtu as u16,
     ) {
         Ok(dev) => dev,
         Err(e) => {
@@ -483,6 +1061,8 @@ pub async fn start(
         &cfg.interfaces.real_tun_b.name,
         &cfg.interfaces.real_tun_b.address,
         &cfg.interfaces.real_tun_b.netmask,
+        cfg.interfaces.real_tun_b.tap,
+        cfg.simulation.mtu as u16,
     ) {
         Ok(dev) => dev,
         Err(e) => {
@@ -498,13 +1078,127 @@ pub async fn start(
         }
     };
 
+    fabric.tun_counters = Some(counters::TunCounters::default());
+
+    // Edges with a namespace are moved there, so their hosts only reach each other via the fabric.
+    for iface in [&cfg.interfaces.real_tun_a, &cfg.interfaces.real_tun_b] {
+        if let Some(ref ns) = iface.netns {
+            let address = match iface.address.parse::<std::net::IpAddr>() {
+                Ok(ip) if !iface.tap => Some((ip, edge_prefix_len(ip, &iface.netmask))),
+                _ => None,
+            };
+            crate::netns::attach(ns, &iface.name, address)?;
+        }
+    }
+
+    // Monitor interfaces of the mirror ports that copy to a TUN instead of a file.
+    for port in &mut fabric.mirrors {
+        let Some(name) = port.tun.clone() else {
+            continue;
+        };
+        let dev = tun_rs::DeviceBuilder::new()
+            .name(&name)
+            .mtu(cfg.simulation.mtu as u16)
+            .build_async()
+            .map_err(|e| format!("Mirror TUN {}: {}", name, e))?;
+        let (tx, mut rx) = tokio::sync::mpsc::channel(crate::capture::mirror::TUN_BACKLOG);
+        port.attach(tx);
+        tokio::spawn(async move {
+            while let Some(copy) = rx.recv().await {
+                if let Err(e) = dev.send(&copy).await {
+                    warn!("Failed to write to mirror TUN {}: {}", name, e);
+                }
+            }
+        });
+    }
+
+    // TAP edges carry Ethernet frames; the simulated router answers ARP for the edge address.
+    let tap_for = |iface: &crate::config::RealTunConfig, last_octet: u8| -> Option<TapEdge> {
+        let address = iface.address.parse::<std::net::Ipv4Addr>().ok()?;
+        iface
+            .tap
+            .then(|| TapEdge::new([0x02, 0, 0, 0, 0, last_octet], address))
+    };
+    let mut tap_a = tap_for(&cfg.interfaces.real_tun_a, 0x0a);
+    let mut tap_b = tap_for(&cfg.interfaces.real_tun_b, 0x0b);
+
+    // Optional DHCP server answering clients on a TAP edge.
+    let dhcp_for = |edge: &str| -> Option<DhcpServer> {
+        let dhcp = cfg.dhcp.as_ref().filter(|d| d.edge == edge)?;
+        let (prefix, addr) = if edge == "tun_a" {
+            (
+                &cfg.tun_ingress.tun_a_prefix,
+                &cfg.interfaces.real_tun_a.address,
+            )
+        } else {
+            (
+                &cfg.tun_ingress.tun_b_prefix,
+                &cfg.interfaces.real_tun_b.address,
+            )
+        };
+        let gateway = addr.parse::<std::net::Ipv4Addr>().ok()?;
+        match DhcpServer::new(prefix, gateway, dhcp.lease_secs) {
+            Ok(server) => {
+                info!("DHCP server enabled on {} for {}", edge, server.network);
+                Some(server)
+            }
+            Err(e) => {
+                warn!("Not starting DHCP server on {}: {}", edge, e);
+                None
+            }
+        }
+    };
+    let mut dhcp_a = dhcp_for("tun_a");
+    let mut dhcp_b = dhcp_for("tun_b");
+
+    // Optional HTTP test origin and load client; they only produce traffic once the TUN devices
+    // exist, so they are started here rather than in mock mode. The origin lives behind edge B
+    // and the client behind edge A.
+    #[cfg(feature = "http-test")]
+    let (_http_origin, mut http_client) = match cfg.http_test.clone() {
+        Some(http) => {
+            let origin = match http.server_bind {
+                Some(ref bind) => Some(
+                    crate::http::start_server(bind, cfg.interfaces.real_tun_b.netns.as_deref())
+                        .await?
+                        .1,
+                ),
+                None => None,
+            };
+            let client_ns = cfg.interfaces.real_tun_a.netns.clone();
+            let client = http.target.is_some().then(|| {
+                tokio::spawn(
+                    async move { crate::http::run_load(&http, client_ns.as_deref()).await },
+                )
+            });
+            (origin, client)
+        }
+        None => (None, None),
+    };
+    #[cfg(not(feature = "http-test"))]
+    let mut http_client: Option<tokio::task::JoinHandle<()>> = None;
+
+    // Periodic TWAMP sessions: a new session starts every `period_secs`, and its test packets go
+    // out one per `interval_ms` tick so that edge traffic keeps flowing in between.
+    let mut twamp_period = cfg.twamp.as_ref().filter(|t| t.period_secs > 0).map(|t| {
+        let period = std::time::Duration::from_secs(t.period_secs);
+        tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+    });
+    let mut twamp_session: Option<(crate::twamp::TwampSession, tokio::time::Interval)> = None;
+
+    // Egress pacing: packets wait for their simulated egress time before they are written.
+    let pacing = cfg.simulation.egress_pacing;
+    let mut pacer_a = EgressPacer::new();
+    let mut pacer_b = EgressPacer::new();
+
+    // Room for the MTU plus an Ethernet header and VLAN tag on TAP edges.
     let mut buf_a = vec![0u8; cfg.simulation.mtu as usize + 100];
     let mut buf_b = vec![0u8; cfg.simulation.mtu as usize + 100];
     // Graceful shutdown signal future.
     let shutdown_signal = signal::ctrl_c();
     // Pin the shutdown future for select! macro.
     tokio::pin!(shutdown_signal);
-    loop {
+    'io: loop {
         debug!("Entering dual‑TUN processing loop");
         select! {
             // Periodic virtual‑customer generation tick
@@ -515,8 +1209,39 @@ pub async fn start(
                     pending::<()>().await;
                 }
             } => {
-                if let Some(vc) = &cfg.virtual_customer {
-                    generate_virtual_packet(vc, cfg, fabric, &routing_tables, &multipath_tables, &ingress_a, &ingress_b).await;
+                for flow in &customer_flows {
+                    generate_virtual_packet(flow, cfg, fabric, &routing_tables, &multipath_tables, &ingress_a, &ingress_b).await;
+                }
+            },
+
+            // Start of a periodic TWAMP session.
+            _ = async {
+                match twamp_period.as_mut() {
+                    Some(int) => { int.tick().await; }
+                    None => pending::<()>().await,
+                }
+            } => {
+                if let (Some(twamp_cfg), None) = (&cfg.twamp, &twamp_session) {
+                    debug!("Starting periodic TWAMP session");
+                    let session = crate::twamp::TwampSession::new(twamp_cfg, fabric, &ingress_a, &ingress_b);
+                    let spacing = std::time::Duration::from_millis(twamp_cfg.interval_ms.max(1));
+                    twamp_session = Some((session, tokio::time::interval(spacing)));
+                }
+            },
+            // Next test packet of the running TWAMP session.
+            _ = async {
+                match twamp_session.as_mut() {
+                    Some((_, int)) => { int.tick().await; }
+                    None => pending::<()>().await,
+                }
+            } => {
+                if let Some((session, _)) = twamp_session.as_mut() {
+                    session.send_next(fabric, &routing_tables, &multipath_tables, cfg.enable_multipath).await;
+                    if session.is_complete() {
+                        let report = twamp_session.take().unwrap().0.into_report();
+                        info!("TWAMP: {}", report.summary());
+                        fabric.twamp_report = Some(report);
+                    }
                 }
             },
 
@@ -527,34 +1252,98 @@ pub async fn start(
                     Ok(0) => { debug!("Read zero bytes from TUN device, continuing"); continue; },
                     Ok(n) => n,
                     Err(e) => {
+                        fabric.interface(Destination::TunA).rx_errors += 1;
                         error!("Error reading from TUN A: {}", e);
                         break;
                     }
                 };
-                // tun-rs provides consistent IP packets across platforms (no 4-byte header)
-                let packet_slice = &buf_a[..n];
-                let packet = match parse(packet_slice) {
+                // tun-rs provides consistent IP packets across platforms (no 4-byte header);
+                // a TAP edge delivers Ethernet frames, which are unwrapped first.
+                fabric.interface(Destination::TunA).received(n);
+                let (vlan, start) = match tap_a {
+                    Some(_) => vlan::untag(&mut buf_a[..n]),
+                    None => (None, 0),
+                };
+                let counters = fabric.interface(Destination::TunA);
+                let (packet_slice, labeled) = match tap_a.as_mut().map(|tap| tap.receive(&buf_a[start..n])) {
+                    None => (&buf_a[..n], false),
+                    Some(TapInput::Packet(payload)) => { counters.stripped += 1; (payload, false) }
+                    Some(TapInput::Labeled(payload)) => { counters.stripped += 1; (payload, true) }
+                    Some(TapInput::Reply(frame)) => {
+                        counters.answered += 1;
+                        let result = async_dev_a.send(&frame).await;
+                        fabric.interface(Destination::TunA).sent(frame.len(), &result);
+                        if let Err(e) = result {
+                            error!("Failed to write ARP reply to TUN A: {}", e);
+                        }
+                        continue;
+                    }
+                    Some(TapInput::Ignore) => { counters.ignored += 1; continue }
+                };
+                let parsed = if labeled { parse_labeled(packet_slice) } else { parse(packet_slice) };
+                let mut packet = match parsed {
                     Ok(p) => p,
                     Err(e) => {
+                        fabric.interface(Destination::TunA).parse_errors += 1;
                         error!("Failed to parse packet from TUN A: {}", e);
                         continue;
                     }
                 };
-                let (ingress, destination) = (ingress_a.clone(), Destination::TunB);
+                packet.vlan = vlan;
+                // DHCP requests from hosts behind TUN A are answered locally.
+                if let Some(reply) = dhcp_a.as_mut().and_then(|srv| srv.handle(&packet)) {
+                    // Clients have no address yet, so replies go to the broadcast MAC.
+                    let reply = match tap_a {
+                        Some(ref tap) => tap.frame_tagged(&BROADCAST_MAC, ETHERTYPE_IPV4, &reply, packet.vlan),
+                        None => reply,
+                    };
+                    let counters = fabric.interface(Destination::TunA);
+                    counters.answered += 1;
+                    let result = async_dev_a.send(&reply).await;
+                    counters.sent(reply.len(), &result);
+                    if let Err(e) = result {
+                        error!("Failed to write DHCP reply to TUN A: {}", e);
+                    }
+                    continue;
+                }
+                let (ingress, destination) = (ingress_a.clone(), fabric.destination_map.egress(Destination::TunA, &packet.dst_ip));
                 debug!("Processing packet from TUN A on ingress {}", ingress.0);
-                let processed = if cfg.enable_multipath {
-                    process_packet_multi(fabric, &multipath_tables, ingress.clone(), packet, destination).await
-                } else {
-                    process_packet(fabric, &routing_tables, ingress.clone(), packet, destination).await
+                let arrival = tokio::time::Instant::now();
+                fabric.last_sojourn = None;
+                let Some(processed) = with_paced_delays(pacing, forward_or_resolve(cfg, fabric, &routing_tables, &multipath_tables, dns_interceptor.as_ref(), &mut dns_pending, ingress.clone(), destination, packet)).await else {
+                    continue;
                 };
-                // tun-rs handles the packet format consistently, so we just send the raw IP packet
-                if let Err(e) = async_dev_b.send(&processed.raw).await {
-                    let err_msg = e.to_string();
-                    if err_msg.contains("seek on unseekable file") {
-                        warn!("Write to TUN B failed (unseekable), likely due to mock mode; ignoring.");
-                    } else {
-                        error!("Failed to write packet to TUN B: {}", e);
-                        break;
+                // The packet leaves at its egress edge, further fragments of it follow.
+                let fragments = std::mem::take(&mut fabric.fragments_out);
+                let frames = std::iter::once((processed.raw, processed.vlan, destination)).chain(fragments.into_iter().map(|(f, edge)| (f.raw, f.vlan, edge)));
+                for (raw, vlan, edge) in frames {
+                    let Some(raw) = reassemble(fabric, raw) else {
+                        continue;
+                    };
+                    let (tap, dev, pacer, name) = match edge {
+                        Destination::TunA => (&tap_a, &async_dev_a, &mut pacer_a, "A"),
+                        Destination::TunB => (&tap_b, &async_dev_b, &mut pacer_b, "B"),
+                    };
+                    // tun-rs handles the packet format consistently, so we just send the raw IP packet
+                    let out = match tap {
+                        Some(tap) => Bytes::from(tap.encapsulate_tagged(&raw, vlan)),
+                        None => raw,
+                    };
+                    if pacing {
+                        let at = fabric.last_sojourn.map_or(arrival, |sojourn| egress_time(arrival, &sojourn));
+                        pacer.schedule(at, out);
+                        continue;
+                    }
+                    let result = dev.send(&out).await;
+                    fabric.interface(edge).sent(out.len(), &result);
+                    if let Err(e) = result {
+                        let err_msg = e.to_string();
+                        if err_msg.contains("seek on unseekable file") {
+                            warn!("Write to TUN {} failed (unseekable), likely due to mock mode; ignoring.", name);
+                        } else {
+                            error!("Failed to write packet to TUN {}: {}", name, e);
+                            break 'io;
+                        }
                     }
                 }
             }
@@ -565,37 +1354,204 @@ pub async fn start(
                     Ok(0) => { debug!("Read zero bytes from TUN device, continuing"); continue; },
                     Ok(n) => { debug!("Read {} bytes from B", n); n }
                     Err(e) => {
+                        fabric.interface(Destination::TunB).rx_errors += 1;
                         error!("Error reading from TUN B: {}", e);
                         break;
                     }
                 };
-                // tun-rs provides consistent IP packets across platforms (no 4-byte header)
-                let packet_slice = &buf_b[..n];
-                let packet = match parse(packet_slice) {
+                // tun-rs provides consistent IP packets across platforms (no 4-byte header);
+                // a TAP edge delivers Ethernet frames, which are unwrapped first.
+                fabric.interface(Destination::TunB).received(n);
+                let (vlan, start) = match tap_b {
+                    Some(_) => vlan::untag(&mut buf_b[..n]),
+                    None => (None, 0),
+                };
+                let counters = fabric.interface(Destination::TunB);
+                let (packet_slice, labeled) = match tap_b.as_mut().map(|tap| tap.receive(&buf_b[start..n])) {
+                    None => (&buf_b[..n], false),
+                    Some(TapInput::Packet(payload)) => { counters.stripped += 1; (payload, false) }
+                    Some(TapInput::Labeled(payload)) => { counters.stripped += 1; (payload, true) }
+                    Some(TapInput::Reply(frame)) => {
+                        counters.answered += 1;
+                        let result = async_dev_b.send(&frame).await;
+                        fabric.interface(Destination::TunB).sent(frame.len(), &result);
+                        if let Err(e) = result {
+                            error!("Failed to write ARP reply to TUN B: {}", e);
+                        }
+                        continue;
+                    }
+                    Some(TapInput::Ignore) => { counters.ignored += 1; continue }
+                };
+                let parsed = if labeled { parse_labeled(packet_slice) } else { parse(packet_slice) };
+                let mut packet = match parsed {
                     Ok(p) => p,
                     Err(e) => {
+                        fabric.interface(Destination::TunB).parse_errors += 1;
                         error!("Failed to parse packet from TUN B: {}", e);
                         continue;
                     }
                 };
-                let (ingress, destination) = (ingress_b.clone(), Destination::TunA);
+                packet.vlan = vlan;
+                // DHCP requests from hosts behind TUN B are answered locally.
+                if let Some(reply) = dhcp_b.as_mut().and_then(|srv| srv.handle(&packet)) {
+                    // Clients have no address yet, so replies go to the broadcast MAC.
+                    let reply = match tap_b {
+                        Some(ref tap) => tap.frame_tagged(&BROADCAST_MAC, ETHERTYPE_IPV4, &reply, packet.vlan),
+                        None => reply,
+                    };
+                    let counters = fabric.interface(Destination::TunB);
+                    counters.answered += 1;
+                    let result = async_dev_b.send(&reply).await;
+                    counters.sent(reply.len(), &result);
+                    if let Err(e) = result {
+                        error!("Failed to write DHCP reply to TUN B: {}", e);
+                    }
+                    continue;
+                }
+                let (ingress, destination) = (ingress_b.clone(), fabric.destination_map.egress(Destination::TunB, &packet.dst_ip));
                 debug!("Processing packet from TUN B on ingress {}", ingress.0);
-                let processed = if cfg.enable_multipath {
-                    process_packet_multi(fabric, &multipath_tables, ingress.clone(), packet, destination).await
-                } else {
-                    process_packet(fabric, &routing_tables, ingress.clone(), packet, destination).await
+                let arrival = tokio::time::Instant::now();
+                fabric.last_sojourn = None;
+                let Some(processed) = with_paced_delays(pacing, forward_or_resolve(cfg, fabric, &routing_tables, &multipath_tables, dns_interceptor.as_ref(), &mut dns_pending, ingress.clone(), destination, packet)).await else {
+                    continue;
                 };
-                // tun-rs handles the packet format consistently
-                if let Err(e) = async_dev_a.send(&processed.raw).await {
-                    let err_msg = e.to_string();
-                    if err_msg.contains("seek on unseekable file") {
-                        warn!("Write to TUN A failed (unseekable), likely due to mock mode; ignoring.");
-                    } else {
-                        error!("Failed to write packet to TUN A: {}", e);
-                        break;
+                // The packet leaves at its egress edge, further fragments of it follow.
+                let fragments = std::mem::take(&mut fabric.fragments_out);
+                let frames = std::iter::once((processed.raw, processed.vlan, destination)).chain(fragments.into_iter().map(|(f, edge)| (f.raw, f.vlan, edge)));
+                for (raw, vlan, edge) in frames {
+                    let Some(raw) = reassemble(fabric, raw) else {
+                        continue;
+                    };
+                    let (tap, dev, pacer, name) = match edge {
+                        Destination::TunA => (&tap_a, &async_dev_a, &mut pacer_a, "A"),
+                        Destination::TunB => (&tap_b, &async_dev_b, &mut pacer_b, "B"),
+                    };
+                    // tun-rs handles the packet format consistently, so we just send the raw IP packet
+                    let out = match tap {
+                        Some(tap) => Bytes::from(tap.encapsulate_tagged(&raw, vlan)),
+                        None => raw,
+                    };
+                    if pacing {
+                        let at = fabric.last_sojourn.map_or(arrival, |sojourn| egress_time(arrival, &sojourn));
+                        pacer.schedule(at, out);
+                        continue;
+                    }
+                    let result = dev.send(&out).await;
+                    fabric.interface(edge).sent(out.len(), &result);
+                    if let Err(e) = result {
+                        let err_msg = e.to_string();
+                        if err_msg.contains("seek on unseekable file") {
+                            warn!("Write to TUN {} failed (unseekable), likely due to mock mode; ignoring.", name);
+                        } else {
+                            error!("Failed to write packet to TUN {}: {}", name, e);
+                            break 'io;
+                        }
+                    }
+                }
+            }
+            // Paced packets whose simulated egress time has come.
+            _ = sleep_until_opt(pacer_a.next_deadline()) => {
+                for frame in pacer_a.due(tokio::time::Instant::now()) {
+                    let result = async_dev_a.send(&frame).await;
+                    fabric.interface(Destination::TunA).sent(frame.len(), &result);
+                    if let Err(e) = result {
+                        error!("Failed to write paced packet to TUN A: {}", e);
                     }
                 }
             }
+            _ = sleep_until_opt(pacer_b.next_deadline()) => {
+                for frame in pacer_b.due(tokio::time::Instant::now()) {
+                    let result = async_dev_b.send(&frame).await;
+                    fabric.interface(Destination::TunB).sent(frame.len(), &result);
+                    if let Err(e) = result {
+                        error!("Failed to write paced packet to TUN B: {}", e);
+                    }
+                }
+            }
+            // DNS replies whose resolution delay has passed.
+            _ = sleep_until_opt(dns_pending.next_deadline()) => {
+                let replies = release_dns_replies(cfg, fabric, &routing_tables, &multipath_tables, &mut dns_pending).await;
+                // Replies and their fragments leave through reassembly and duplicate removal.
+                let fragments = std::mem::take(&mut fabric.fragments_out);
+                let frames = replies.into_iter().chain(fragments).map(|(r, edge)| (r.raw, r.vlan, edge)).collect::<Vec<_>>();
+                for (raw, vlan, destination) in frames {
+                    let Some(raw) = reassemble(fabric, raw) else {
+                        continue;
+                    };
+                    let (dev, tap) = match destination {
+                        Destination::TunA => (&async_dev_a, &tap_a),
+                        Destination::TunB => (&async_dev_b, &tap_b),
+                    };
+                    let out = match tap {
+                        Some(tap) => Bytes::from(tap.encapsulate_tagged(&raw, vlan)),
+                        None => raw,
+                    };
+                    let result = dev.send(&out).await;
+                    fabric.interface(destination).sent(out.len(), &result);
+                    if let Err(e) = result {
+                        error!("Failed to write DNS reply: {}", e);
+                    }
+                }
+            }
+            // End of the warm-up phase.
+            _ = sleep_until_opt(warmup.deadline) => {
+                warmup.check(fabric);
+            }
+            // Scheduled topology event or end of a link's bring-up delay.
+            _ = sleep_until_opt(events.deadline()) => {
+                if events.apply_due(fabric) {
+                    topology_changed(cfg, fabric, &ingress_a, &ingress_b, &mut routing_tables, &mut multipath_tables);
+                }
+            }
+            // Fault injected at runtime.
+            action = events.next_fault() => {
+                if events.inject(fabric, action) {
+                    topology_changed(cfg, fabric, &ingress_a, &ingress_b, &mut routing_tables, &mut multipath_tables);
+                }
+            }
+            // SPF run of a router due after a topology change (`[link_state]`), or the next
+            // update while distance-vector routes are changing (`[distance_vector]`).
+            _ = sleep_until_opt(
+                fabric
+                    .convergence
+                    .as_ref()
+                    .and_then(|c| c.deadline())
+                    .or_else(|| fabric.distance_vector.as_ref().and_then(|d| d.deadline())),
+            ) => {
+                converge(fabric, &mut routing_tables, &mut multipath_tables);
+            }
+            // End of an alarm monitoring window.
+            _ = sleep_until_opt(fabric.alarms.as_ref().map(|a| a.next_evaluation())) => {
+                crate::alarms::tick(fabric);
+            }
+            // Telemetry sample due.
+            _ = sleep_until_opt(fabric.telemetry.as_ref().map(|t| t.next_sample())) => {
+                crate::telemetry::tick(fabric);
+            }
+            // State snapshot due.
+            _ = sleep_until_opt(fabric.snapshots.as_ref().map(|s| s.next_snapshot())) => {
+                crate::snapshot::tick(fabric);
+            }
+            // HTTP load client finished.
+            res = async {
+                match http_client.as_mut() {
+                    Some(handle) => handle.await,
+                    None => pending().await,
+                }
+            } => {
+                http_client = None;
+                #[cfg(feature = "http-test")]
+                match res {
+                    Ok(Ok(report)) => {
+                        info!("HTTP load: {}", report.summary());
+                        fabric.http_report = Some(report);
+                    }
+                    Ok(Err(e)) => error!("HTTP load client failed: {}", e),
+                    Err(e) => error!("HTTP load client task failed: {}", e),
+                }
+                #[cfg(not(feature = "http-test"))]
+                let _ = res;
+            }
             _ = &mut shutdown_signal => {
                 info!("Shutdown signal received, exiting dual‑TUN loop");
                 // TUN interfaces will be cleaned up when async_dev_a and async_dev_b are dropped
@@ -603,5 +1559,18 @@ pub async fn start(
             }
         }
     }
+    for (name, iface) in fabric.tun_counters.iter().flat_map(|c| c.all()) {
+        info!("Interface {}: {}", name, iface.summary());
+    }
+    if pacing {
+        info!("Egress pacing to TUN A: {}", pacer_a.summary());
+        info!("Egress pacing to TUN B: {}", pacer_b.summary());
+    }
+    if let Some(ref reassembly) = fabric.reassembly {
+        info!("Reassembly: {}", reassembly.stats.summary());
+    }
+    if let Some(ref dedupe) = fabric.dedupe {
+        info!("Dedupe: {}", dedupe.stats.summary());
+    }
     Ok(())
 }
@@ -0,0 +1,388 @@
+// src/twamp/mod.rs
+
+//! TWAMP‑light style active measurement.
+//!
+//! A sender attached to the tun_a ingress router emits UDP test packets towards a reflector on the
+//! tun_b ingress router. Each packet that reaches the reflector is turned around and sent back, so
+//! both one‑way delays and the round trip can be measured over the simulated fabric.
+//!
+//! Probes are measurement traffic: they show up in the report only, not in the router counters,
+//! and a probe counts as received when a packet with its sequence number leaves the fabric at
+//! the far edge.
+//!
+//! Like real TWAMP, one‑way delays come from timestamps: each test packet carries its send time
+//! by the clock of the edge that sent it (the sender is on the tun_a edge, the reflector on the
+//! tun_b edge), and the receiving edge subtracts it from its own clock. With `[clock_skew]` the
+//! two clocks disagree and the one‑way delays are off by their difference, as they would be in
+//! an unsynchronized deployment; the round trip is not.
+
+use crate::clock::EdgeClocks;
+use crate::config::TwampConfig;
+use crate::packet::{transport_detail, transport_offset, update_ipv4_checksum, PacketMeta};
+use crate::processor::{process_packet_multi_to_edge, process_packet_to_edge};
+use crate::routing::{Destination, MultiPathTable, RoutingTable};
+use crate::topology::{Fabric, RouterId};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::time::{sleep, Duration, Instant};
+use tracing::debug;
+
+/// Result of a single test packet exchange.
+#[derive(Debug, Clone, Default)]
+pub struct TwampSample {
+    pub seq: u32,
+    /// Sender → reflector delay, `None` if the test packet was lost.
+    pub forward_ms: Option<f64>,
+    /// Reflector → sender delay, `None` if the reflected packet was lost (or never sent).
+    pub reverse_ms: Option<f64>,
+}
+
+impl TwampSample {
+    /// Round‑trip time, available only if both directions succeeded.
+    pub fn rtt_ms(&self) -> Option<f64> {
+        Some(self.forward_ms? + self.reverse_ms?)
+    }
+}
+
+/// Collected samples of one measurement session.
+#[derive(Debug, Clone, Default)]
+pub struct TwampReport {
+    pub samples: Vec<TwampSample>,
+}
+
+impl TwampReport {
+    pub fn sent(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Number of test packets that never reached the reflector.
+    pub fn forward_lost(&self) -> usize {
+        self.samples
+            .iter()
+            .filter(|s| s.forward_ms.is_none())
+            .count()
+    }
+
+    /// Number of reflected packets that never made it back to the sender.
+    pub fn reverse_lost(&self) -> usize {
+        self.samples
+            .iter()
+            .filter(|s| s.forward_ms.is_some() && s.reverse_ms.is_none())
+            .count()
+    }
+
+    /// Round‑trip loss in percent.
+    pub fn loss_percent(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let lost = self.forward_lost() + self.reverse_lost();
+        lost as f64 * 100.0 / self.samples.len() as f64
+    }
+
+    fn rtts(&self) -> Vec<f64> {
+        self.samples.iter().filter_map(|s| s.rtt_ms()).collect()
+    }
+
+    pub fn rtt_min_ms(&self) -> Option<f64> {
+        self.rtts().into_iter().reduce(f64::min)
+    }
+
+    pub fn rtt_max_ms(&self) -> Option<f64> {
+        self.rtts().into_iter().reduce(f64::max)
+    }
+
+    pub fn rtt_avg_ms(&self) -> Option<f64> {
+        let rtts = self.rtts();
+        if rtts.is_empty() {
+            None
+        } else {
+            Some(rtts.iter().sum::<f64>() / rtts.len() as f64)
+        }
+    }
+
+    /// Average one‑way delay from sender to reflector.
+    pub fn forward_avg_ms(&self) -> Option<f64> {
+        let v: Vec<f64> = self.samples.iter().filter_map(|s| s.forward_ms).collect();
+        if v.is_empty() {
+            None
+        } else {
+            Some(v.iter().sum::<f64>() / v.len() as f64)
+        }
+    }
+
+    /// Average one‑way delay from reflector back to sender.
+    pub fn reverse_avg_ms(&self) -> Option<f64> {
+        let v: Vec<f64> = self.samples.iter().filter_map(|s| s.reverse_ms).collect();
+        if v.is_empty() {
+            None
+        } else {
+            Some(v.iter().sum::<f64>() / v.len() as f64)
+        }
+    }
+
+    /// Jitter as the mean absolute difference between consecutive round‑trip times.
+    pub fn jitter_ms(&self) -> f64 {
+        let rtts = self.rtts();
+        if rtts.len() < 2 {
+            return 0.0;
+        }
+        let total: f64 = rtts.windows(2).map(|w| (w[1] - w[0]).abs()).sum();
+        total / (rtts.len() - 1) as f64
+    }
+
+    /// One‑line human readable summary used by the statistics output.
+    pub fn summary(&self) -> String {
+        let fmt = |v: Option<f64>| v.map_or_else(|| "-".to_string(), |x| format!("{:.3}", x));
+        format!(
+            "sent={}, fwd_lost={}, rev_lost={}, loss={:.1}%, rtt min/avg/max={}/{}/{} ms, owd fwd/rev={}/{} ms, jitter={:.3} ms",
+            self.sent(),
+            self.forward_lost(),
+            self.reverse_lost(),
+            self.loss_percent(),
+            fmt(self.rtt_min_ms()),
+            fmt(self.rtt_avg_ms()),
+            fmt(self.rtt_max_ms()),
+            fmt(self.forward_avg_ms()),
+            fmt(self.reverse_avg_ms()),
+            self.jitter_ms()
+        )
+    }
+}
+
+/// Build an IPv4/UDP test packet carrying the sequence number and a send timestamp
+/// (nanoseconds by the sender's clock), laid out like a TWAMP‑light test packet.
+pub fn build_test_packet(
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    src_port: u16,
+    dst_port: u16,
+    seq: u32,
+    timestamp_ns: u64,
+) -> PacketMeta {
+    // Sequence number (4) + timestamp (8) + error estimate (2)
+    let mut payload = Vec::with_capacity(14);
+    payload.extend_from_slice(&seq.to_be_bytes());
+    payload.extend_from_slice(&timestamp_ns.to_be_bytes());
+    payload.extend_from_slice(&[0x80, 0x01]); // S bit set, multiplier 1
+    let udp_len = 8 + payload.len();
+    let total_len = 20 + udp_len;
+    let mut raw = Vec::with_capacity(total_len);
+    raw.extend_from_slice(&[0x45, 0x00]);
+    raw.extend_from_slice(&(total_len as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0, 0, 0]); // Identification, Flags/Fragment Offset
+    raw.push(64); // TTL
+    raw.push(17); // Protocol = UDP
+    raw.extend_from_slice(&[0, 0]); // Header checksum placeholder
+    raw.extend_from_slice(&src.octets());
+    raw.extend_from_slice(&dst.octets());
+    update_ipv4_checksum(&mut raw);
+    raw.extend_from_slice(&src_port.to_be_bytes());
+    raw.extend_from_slice(&dst_port.to_be_bytes());
+    raw.extend_from_slice(&(udp_len as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0]); // UDP checksum (optional for IPv4)
+    raw.extend_from_slice(&payload);
+    PacketMeta {
+        src_ip: IpAddr::V4(src),
+        dst_ip: IpAddr::V4(dst),
+        src_port,
+        dst_port,
+        protocol: 17,
+        ttl: 64,
+        dscp: 0,
+        ecn: 0,
+        transport: transport_detail(&raw),
+        annotation: None,
+        mpls: Vec::new(),
+        vlan: None,
+        raw: raw.into(),
+    }
+}
+
+/// Sequence number carried by a test packet.
+fn probe_seq(packet: &PacketMeta) -> Option<u32> {
+    let at = transport_offset(&packet.raw)? + 8;
+    let seq = packet.raw.get(at..at + 4)?;
+    Some(u32::from_be_bytes([seq[0], seq[1], seq[2], seq[3]]))
+}
+
+/// Send timestamp carried by a test packet.
+fn probe_timestamp(packet: &PacketMeta) -> Option<u64> {
+    let at = transport_offset(&packet.raw)? + 12;
+    let stamp = packet.raw.get(at..at + 8)?;
+    Some(u64::from_be_bytes(stamp.try_into().ok()?))
+}
+
+/// Delay in milliseconds of a test packet that arrived at `arrival_ns` by the receiver's clock.
+fn one_way_ms(packet: &PacketMeta, arrival_ns: u64) -> Option<f64> {
+    let sent = probe_timestamp(packet)?;
+    Some(arrival_ns.wrapping_sub(sent) as i64 as f64 / 1e6)
+}
+
+/// Forward a test packet and return it as it left the fabric, if it reached `destination`.
+/// The router counters are restored afterwards, so probes do not mix with user traffic.
+async fn forward(
+    fabric: &mut Fabric,
+    routing_tables: &HashMap<RouterId, RoutingTable>,
+    multipath_tables: &HashMap<RouterId, MultiPathTable>,
+    enable_multipath: bool,
+    ingress: RouterId,
+    packet: PacketMeta,
+    destination: Destination,
+) -> Option<PacketMeta> {
+    let saved: Vec<_> = fabric
+        .graph
+        .node_weights()
+        .map(|r| r.stats.clone())
+        .collect();
+    let (out, edge) = if enable_multipath {
+        process_packet_multi_to_edge(fabric, multipath_tables, ingress, packet, destination).await
+    } else {
+        process_packet_to_edge(fabric, routing_tables, ingress, packet, destination).await
+    };
+    for (router, stats) in fabric.graph.node_weights_mut().zip(saved) {
+        router.stats = stats;
+    }
+    (edge == Some(destination)).then_some(out)
+}
+
+/// One measurement session in progress. Test packets are sent one at a time with
+/// [`TwampSession::send_next`], so a caller can interleave them with other forwarding work.
+#[derive(Debug, Clone)]
+pub struct TwampSession {
+    count: u32,
+    port: u16,
+    sender: RouterId,
+    reflector: RouterId,
+    sender_addr: Ipv4Addr,
+    reflector_addr: Ipv4Addr,
+    clocks: EdgeClocks,
+    report: TwampReport,
+}
+
+impl TwampSession {
+    pub fn new(
+        cfg: &TwampConfig,
+        fabric: &Fabric,
+        sender: &RouterId,
+        reflector: &RouterId,
+    ) -> Self {
+        let addr = |id: &RouterId| {
+            fabric
+                .get_router(id)
+                .map(|r| r.ipv4_addr)
+                .unwrap_or(Ipv4Addr::UNSPECIFIED)
+        };
+        Self {
+            count: cfg.count,
+            port: cfg.port,
+            sender: sender.clone(),
+            reflector: reflector.clone(),
+            sender_addr: addr(sender),
+            reflector_addr: addr(reflector),
+            clocks: fabric.clocks,
+            report: TwampReport::default(),
+        }
+    }
+
+    /// All `count` test packets have been sent.
+    pub fn is_complete(&self) -> bool {
+        self.report.samples.len() as u32 >= self.count
+    }
+
+    pub fn into_report(self) -> TwampReport {
+        self.report
+    }
+
+    /// Send the next test packet and, if it reaches the reflector, its reflection.
+    /// A packet is considered received when it leaves the fabric at the far edge still carrying
+    /// its sequence number.
+    pub async fn send_next(
+        &mut self,
+        fabric: &mut Fabric,
+        routing_tables: &HashMap<RouterId, RoutingTable>,
+        multipath_tables: &HashMap<RouterId, MultiPathTable>,
+        enable_multipath: bool,
+    ) {
+        let seq = self.report.samples.len() as u32;
+        let sender_port = self.port.wrapping_add(1);
+        let mut sample = TwampSample {
+            seq,
+            ..Default::default()
+        };
+        let probe = build_test_packet(
+            self.sender_addr,
+            self.reflector_addr,
+            sender_port,
+            self.port,
+            seq,
+            self.clocks.stamp_ns(Destination::TunA, Instant::now()),
+        );
+        let received = forward(
+            fabric,
+            routing_tables,
+            multipath_tables,
+            enable_multipath,
+            self.sender.clone(),
+            probe,
+            Destination::TunB,
+        )
+        .await
+        .filter(|p| probe_seq(p) == Some(seq));
+        let arrival = self.clocks.stamp_ns(Destination::TunB, Instant::now());
+        let Some(received) = received else {
+            debug!("TWAMP test packet {} lost towards reflector", seq);
+            self.report.samples.push(sample);
+            return;
+        };
+        sample.forward_ms = one_way_ms(&received, arrival);
+        let reply = build_test_packet(
+            self.reflector_addr,
+            self.sender_addr,
+            self.port,
+            sender_port,
+            seq,
+            arrival,
+        );
+        let reflected = forward(
+            fabric,
+            routing_tables,
+            multipath_tables,
+            enable_multipath,
+            self.reflector.clone(),
+            reply,
+            Destination::TunA,
+        )
+        .await
+        .filter(|p| probe_seq(p) == Some(seq));
+        let arrival = self.clocks.stamp_ns(Destination::TunA, Instant::now());
+        match reflected {
+            Some(reflected) => sample.reverse_ms = one_way_ms(&reflected, arrival),
+            None => debug!("TWAMP reflected packet {} lost towards sender", seq),
+        }
+        self.report.samples.push(sample);
+    }
+}
+
+/// Run one complete measurement session of `cfg.count` test packets spaced `cfg.interval_ms`
+/// apart.
+pub async fn run_session(
+    cfg: &TwampConfig,
+    fabric: &mut Fabric,
+    routing_tables: &HashMap<RouterId, RoutingTable>,
+    multipath_tables: &HashMap<RouterId, MultiPathTable>,
+    enable_multipath: bool,
+    sender: &RouterId,
+    reflector: &RouterId,
+) -> TwampReport {
+    let mut session = TwampSession::new(cfg, fabric, sender, reflector);
+    while !session.is_complete() {
+        if !session.report.samples.is_empty() && cfg.interval_ms > 0 {
+            sleep(Duration::from_millis(cfg.interval_ms)).await;
+        }
+        session
+            .send_next(fabric, routing_tables, multipath_tables, enable_multipath)
+            .await;
+    }
+    session.into_report()
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use common::rid;
+use network_simulator::alarms::{AlarmMonitor, LinkSample, Metric, Severity};
+use network_simulator::config::{AlarmsConfig, SimulatorConfig};
+use network_simulator::topology::LinkId;
+use std::io::Write;
+use tempfile::NamedTempFile;
+use tokio::time::{Duration, Instant};
+
+fn monitor(rules: &str) -> AlarmMonitor {
+    let cfg: AlarmsConfig = toml::from_str(rules).expect("alarms parse");
+    AlarmMonitor::new(&cfg).expect("valid alarms")
+}
+
+fn sample(b: &str, offered: u64, lost: u64, bytes: u64, depth: usize) -> LinkSample {
+    LinkSample {
+        id: LinkId::new(rid("Rx0y0"), rid(b)),
+        offered,
+        lost,
+        bytes,
+        queue_depth: depth,
+        bandwidth_mbps: Some(1.0),
+    }
+}
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+#[test]
+fn test_alarms_raised_and_cleared_per_window() {
+    let mut alarms = monitor(
+        r#"
+window_secs = 1.0
+
+[[rule]]
+metric = "loss_percent"
+threshold = 5
+severity = "critical"
+
+[[rule]]
+metric = "utilization_percent"
+threshold = 50
+severity = "major"
+link = "Rx0y0_Rx0y1"
+
+[[rule]]
+metric = "queue_depth"
+threshold = 10
+"#,
+    );
+    let start = alarms.next_evaluation() - Duration::from_secs(1);
+    // Window 1: 10% loss on both links, 100 kB (80%) across the first, a short queue.
+    alarms.evaluate(
+        &[
+            sample("Rx0y1", 100, 10, 100_000, 3),
+            sample("Rx0y2", 100, 10, 100_000, 3),
+        ],
+        start + Duration::from_secs(1),
+    );
+    assert_eq!(alarms.raised(Severity::Critical), 2);
+    assert_eq!(alarms.raised(Severity::Major), 1);
+    assert_eq!(alarms.active(), 3);
+    // Window 2: no loss, little traffic, a deep queue on the second link.
+    alarms.evaluate(
+        &[
+            sample("Rx0y1", 200, 10, 110_000, 0),
+            sample("Rx0y2", 200, 10, 110_000, 20),
+        ],
+        start + Duration::from_secs(2),
+    );
+    assert_eq!(alarms.active(), 1);
+    assert_eq!(alarms.raised(Severity::Warning), 1);
+    let cleared: Vec<_> = alarms.events.iter().filter(|e| !e.raised).collect();
+    assert_eq!(cleared.len(), 3);
+    let last = alarms.events.last().unwrap();
+    assert_eq!(
+        (last.metric, last.link.as_str(), last.value),
+        (Metric::QueueDepth, "Rx0y0_Rx0y2", 20.0)
+    );
+    assert!(
+        alarms.summary().contains("critical=2"),
+        "{}",
+        alarms.summary()
+    );
+}
+
+#[test]
+fn test_invalid_alarm_rules_rejected() {
+    for (rule, expected) in [
+        ("metric = \"jitter\"\nthreshold = 1", "metric"),
+        (
+            "metric = \"loss_percent\"\nthreshold = 1\nseverity = \"fatal\"",
+            "severity",
+        ),
+        ("metric = \"loss_percent\"\nthreshold = -1", "threshold"),
+        (
+            "metric = \"loss_percent\"\nthreshold = 1\nlink = \"Rx0y0_Rx0y9\"",
+            "not defined",
+        ),
+    ] {
+        let cfg = common::line("", &["", ""], &[""], &format!("[[alarms.rule]]\n{}", rule));
+        let err = addressed(cfg).validate().unwrap_err();
+        assert!(err.contains(expected), "{}: {}", expected, err);
+    }
+}
+
+#[tokio::test]
+async fn test_lossy_link_raises_critical_alarm() {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..4 {
+        writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", "", ""],
+        &["", "loss_percent = 100"],
+        r#"
+[alarms]
+window_secs = 10
+
+[[alarms.rule]]
+metric = "loss_percent"
+threshold = 50
+severity = "critical"
+"#,
+    );
+    let started = Instant::now();
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    let alarms = fabric.alarms.as_ref().expect("alarms");
+    assert_eq!(alarms.raised(Severity::Critical), 1);
+    assert_eq!(alarms.events[0].link, "Rx0y1_Rx0y2");
+    assert_eq!(alarms.events[0].value, 100.0);
+    let metrics = network_simulator::metrics::render(&fabric);
+    assert!(
+        metrics.contains("nsim_alarms_raised_total{severity=\"critical\"} 1"),
+        "{}",
+        metrics
+    );
+    assert!(metrics.contains("nsim_alarms_raised_total{severity=\"major\"} 0"));
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::rid;
+use network_simulator::asymmetry::{analyze, render};
+
+/// Square Rx0y0 - Rx0y1 - Rx1y1 - Rx1y0 - Rx0y0 with tun_a at Rx0y0 and tun_b at Rx1y1, so both
+/// directions tie between the two sides and resolve towards Rx0y1.
+fn square(b_opts: &str, rest: &str) -> network_simulator::config::SimulatorConfig {
+    common::scenario(
+        "",
+        &[
+            ("Rx0y0", ""),
+            ("Rx0y1", ""),
+            ("Rx1y0", ""),
+            ("Rx1y1", b_opts),
+        ],
+        &[
+            ("Rx0y0_Rx0y1", ""),
+            ("Rx0y1_Rx1y1", ""),
+            ("Rx0y0_Rx1y0", ""),
+            ("Rx1y0_Rx1y1", ""),
+        ],
+        rest,
+    )
+}
+
+#[test]
+fn test_tied_paths_resolve_symmetrically() {
+    let pairs = analyze(&square("", "")).unwrap();
+    assert_eq!(pairs.len(), 1);
+    let pair = &pairs[0];
+    assert_eq!(
+        pair.forward.routers,
+        vec![rid("Rx0y0"), rid("Rx0y1"), rid("Rx1y1")]
+    );
+    assert_eq!(
+        pair.reverse.routers,
+        vec![rid("Rx1y1"), rid("Rx0y1"), rid("Rx0y0")]
+    );
+    assert_eq!(pair.asymmetry(), None);
+    let report = render(&pairs);
+    assert!(
+        report.contains("10.0.0.0/24 <-> 10.0.1.0/24: symmetric"),
+        "{}",
+        report
+    );
+    assert!(report.ends_with("0 of 1 prefix pairs asymmetric\n"));
+}
+
+#[test]
+fn test_policy_route_on_return_path_flagged() {
+    let pbr = r#"pbr = [ { match = "dst net 10.0.0.0/24", next_hop = "Rx1y0" } ]"#;
+    let pairs = analyze(&square(pbr, "")).unwrap();
+    assert_eq!(pairs[0].reverse.routers[1], rid("Rx1y0"));
+    assert_eq!(pairs[0].asymmetry().as_deref(), Some("different routers"));
+    assert!(render(&pairs).contains("ASYMMETRIC: different routers"));
+}
+
+#[test]
+fn test_directed_link_costs_flagged() {
+    let mut cfg = square("", "");
+    let link = cfg.topology.links.get_mut("Rx0y1_Rx1y1").unwrap();
+    link.cost_ba = Some(5);
+    let pairs = analyze(&cfg).unwrap();
+    assert_eq!(pairs[0].forward.routers[1], rid("Rx0y1"));
+    assert_eq!(pairs[0].reverse.routers[1], rid("Rx1y0"));
+    assert_eq!(pairs[0].asymmetry().as_deref(), Some("different routers"));
+}
+
+#[test]
+fn test_destination_map_prefixes_paired() {
+    let rest = r#"
+[[destination_map]]
+ingress = "tun_a"
+prefix = "10.0.2.0/24"
+egress = "tun_b"
+"#;
+    let pairs = analyze(&square("", rest)).unwrap();
+    let b: Vec<String> = pairs.iter().map(|p| p.b_prefix.to_string()).collect();
+    assert_eq!(b, vec!["10.0.1.0/24", "10.0.2.0/24"]);
+    assert!(pairs.iter().all(|p| p.asymmetry().is_none()));
+}
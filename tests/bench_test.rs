@@ -0,0 +1,76 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use network_simulator::bench::{run, BenchInterval, BenchOptions, BenchReport};
+use predicates::str::contains;
+use std::net::{SocketAddr, TcpListener};
+
+/// Reserve a free loopback port.
+fn free_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap()
+}
+
+fn options(addr: SocketAddr, udp: bool) -> BenchOptions {
+    BenchOptions {
+        duration_secs: 1,
+        parallel: 2,
+        udp,
+        bitrate_mbps: 1.0,
+        interval_secs: 0.5,
+        length: None,
+        listen: addr,
+        target: addr,
+        bind: Some("127.0.0.1".parse().unwrap()),
+        sink_netns: None,
+        sender_netns: None,
+    }
+}
+
+#[tokio::test]
+async fn test_bench_tcp_over_loopback() {
+    let report = run(&options(free_addr(), false)).await.expect("bench");
+    assert!(!report.intervals.is_empty());
+    assert_eq!(report.intervals[0].stream_bytes.len(), 2);
+    assert!(report.sent_bytes > 0);
+    assert!(report.received_bytes > 0);
+    let text = report.render();
+    assert!(text.contains("[SUM]"));
+    assert!(text.contains("sender"));
+}
+
+#[tokio::test]
+async fn test_bench_udp_counts_datagrams() {
+    let report = run(&options(free_addr(), true)).await.expect("bench");
+    assert!(report.datagrams_sent > 0);
+    assert!(report.datagrams_received <= report.datagrams_sent);
+    assert!(report.render().contains("receiver"));
+}
+
+#[test]
+fn test_bench_report_loss_and_interval_totals() {
+    let report = BenchReport {
+        udp: true,
+        intervals: vec![BenchInterval {
+            start_secs: 0.0,
+            end_secs: 1.0,
+            stream_bytes: vec![1000, 3000],
+            retransmits: 0,
+        }],
+        duration_secs: 1.0,
+        datagrams_sent: 200,
+        datagrams_received: 150,
+        ..Default::default()
+    };
+    assert_eq!(report.intervals[0].bytes(), 4000);
+    assert_eq!(report.loss_percent(), 25.0);
+    assert!(report.render().contains("50/200 (25.00%)"));
+}
+
+#[test]
+fn test_bench_subcommand_help() {
+    let mut cmd = cargo_bin_cmd!("network-simulator");
+    cmd.args(["bench", "--help"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("--duration"))
+        .stdout(contains("--parallel"));
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::rid;
+use network_simulator::compute_routing_tables;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::routing::RoutingTable;
+use network_simulator::topology::RouterId;
+use std::collections::HashMap;
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// AS 100 (Rx0y0 with tun_a, Rx0y1) reaches AS 400 (Rx0y2 with tun_b) through AS 200 (Rx1y0)
+/// from Rx0y0 or through AS 300 (Rx1y1 - Rx1y2) from Rx0y1; `policies` go in `[bgp]`.
+fn ases(policies: &str) -> SimulatorConfig {
+    addressed(common::scenario(
+        "",
+        &[
+            ("Rx0y0", "asn = 100"),
+            ("Rx0y1", "asn = 100"),
+            ("Rx1y0", "asn = 200"),
+            ("Rx1y1", "asn = 300"),
+            ("Rx1y2", "asn = 300"),
+            ("Rx0y2", "asn = 400"),
+        ],
+        &[
+            ("Rx0y0_Rx0y1", ""),
+            ("Rx0y0_Rx1y0", ""),
+            ("Rx1y0_Rx0y2", ""),
+            ("Rx0y1_Rx1y1", ""),
+            ("Rx1y1_Rx1y2", ""),
+            ("Rx1y2_Rx0y2", ""),
+        ],
+        &format!("[bgp]\n{}", policies),
+    ))
+}
+
+/// Routers a packet from `from` passes on its way to tun_b, `from` included.
+fn path(tables: &HashMap<RouterId, RoutingTable>, from: &str) -> Vec<String> {
+    let mut path = vec![from.to_string()];
+    let mut at = rid(from);
+    while at != rid("Rx0y2") && path.len() < 10 {
+        at = tables[&at].tun_b.next_hop.clone();
+        path.push(at.0.clone());
+    }
+    path
+}
+
+#[test]
+fn test_shortest_as_path_and_hot_potato() {
+    let cfg = ases("");
+    cfg.validate().expect("valid");
+    let tables = compute_routing_tables(&cfg);
+    // Both AS paths are two long, so each router of AS 100 leaves by its nearest session.
+    assert_eq!(path(&tables, "Rx0y0"), ["Rx0y0", "Rx1y0", "Rx0y2"]);
+    assert_eq!(path(&tables, "Rx0y1"), ["Rx0y1", "Rx1y1", "Rx1y2", "Rx0y2"]);
+    assert_eq!(tables[&rid("Rx0y1")].tun_b.total_cost, 3);
+    // The way back is chosen the same way from AS 400.
+    assert_eq!(tables[&rid("Rx0y2")].tun_a.next_hop, rid("Rx1y0"));
+}
+
+#[test]
+fn test_prepend_and_local_pref() {
+    // AS 200 makes its path look longer to AS 100, so all of AS 100 goes through AS 300.
+    let prepend = "[[bgp.policy]]\nasn = 200\nneighbor_as = 100\nprepend = 2\n";
+    let tables = compute_routing_tables(&ases(prepend));
+    assert_eq!(
+        path(&tables, "Rx0y0"),
+        ["Rx0y0", "Rx0y1", "Rx1y1", "Rx1y2", "Rx0y2"]
+    );
+
+    // A higher local preference wins over the shorter path.
+    let local_pref = format!(
+        "{}[[bgp.policy]]\nasn = 100\nneighbor_as = 200\nlocal_pref = 200\n",
+        prepend
+    );
+    let tables = compute_routing_tables(&ases(&local_pref));
+    assert_eq!(path(&tables, "Rx0y1"), ["Rx0y1", "Rx0y0", "Rx1y0", "Rx0y2"]);
+}
+
+#[test]
+fn test_denied_routes() {
+    let deny = "[[bgp.policy]]\nasn = 100\nneighbor_as = 300\ndeny = true\n";
+    let tables = compute_routing_tables(&ases(deny));
+    assert_eq!(path(&tables, "Rx0y1"), ["Rx0y1", "Rx0y0", "Rx1y0", "Rx0y2"]);
+
+    // AS 200 ignoring AS 400 leaves AS 100 only the way through AS 300, and AS 200 gets there
+    // through AS 100.
+    let mut cfg = ases("[[bgp.policy]]\nasn = 200\nneighbor_as = 400\ndeny = true\n");
+    cfg.validate().expect("valid");
+    let tables = compute_routing_tables(&cfg);
+    assert_eq!(
+        path(&tables, "Rx0y0"),
+        ["Rx0y0", "Rx0y1", "Rx1y1", "Rx1y2", "Rx0y2"]
+    );
+    assert_eq!(
+        path(&tables, "Rx1y0"),
+        ["Rx1y0", "Rx0y0", "Rx0y1", "Rx1y1", "Rx1y2", "Rx0y2"]
+    );
+    cfg.bgp = None;
+    assert!(cfg
+        .validate()
+        .unwrap_err()
+        .contains("asn requires a [bgp] section"));
+}
+
+#[test]
+fn test_bgp_validation() {
+    let err = ases("[[bgp.policy]]\nasn = 100\nneighbor_as = 999\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("AS 999"), "{}", err);
+    let cfg = addressed(common::line("", &["asn = 1", ""], &[""], "[bgp]\n"));
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("every router needs an asn"), "{}", err);
+}
@@ -0,0 +1,155 @@
+use network_simulator::capture::CaptureFilter;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::packet::{self, PacketMeta};
+use std::io::Write;
+use tempfile::{NamedTempFile, TempDir};
+
+const UDP_PACKET: &str = "4500001e000000004011000a0a0000020a0001021f903039000a00006869";
+const TCP_PACKET: &str = "4500001400000000400600000a0000020a000102";
+
+fn hex_packet(hex: &str) -> PacketMeta {
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect();
+    packet::parse(&bytes).expect("packet parses")
+}
+
+fn matches(filter: &str, hex: &str) -> bool {
+    CaptureFilter::parse(filter)
+        .expect("filter parses")
+        .matches(&hex_packet(hex))
+}
+
+#[test]
+fn test_filter_primitives() {
+    assert!(matches("", UDP_PACKET));
+    assert!(matches("udp", UDP_PACKET));
+    assert!(!matches("tcp", UDP_PACKET));
+    assert!(matches("ip and proto 17", UDP_PACKET));
+    assert!(!matches("ip6", UDP_PACKET));
+    assert!(matches("dst port 12345", UDP_PACKET));
+    assert!(matches("port 12345", UDP_PACKET));
+    assert!(!matches("src port 53", UDP_PACKET));
+    assert!(matches("src net 10.0.0.0/24", UDP_PACKET));
+    assert!(!matches("dst net 10.0.0.0/24", UDP_PACKET));
+    assert!(matches("host 10.0.1.2", UDP_PACKET));
+    assert!(!matches("src host 10.0.1.2", UDP_PACKET));
+}
+
+#[test]
+fn test_filter_combinators() {
+    assert!(matches("TCP or udp", UDP_PACKET));
+    assert!(!matches("udp && !port 12345", UDP_PACKET));
+    assert!(matches(
+        "not (tcp or icmp) and dst net 10.0.1.0/24",
+        UDP_PACKET
+    ));
+    assert!(matches("(udp and port 53) || tcp", TCP_PACKET));
+    // `and` binds tighter than `or`.
+    assert!(matches("tcp or udp and port 53", TCP_PACKET));
+    assert!(!matches("(tcp or udp) and port 53", TCP_PACKET));
+}
+
+#[test]
+fn test_filter_errors() {
+    for bad in [
+        "udpp",
+        "port",
+        "port http",
+        "src tcp",
+        "net 10.0.0.0/33",
+        "(udp",
+        "udp)",
+        "udp and",
+    ] {
+        assert!(
+            CaptureFilter::parse(bad).is_err(),
+            "{} should not parse",
+            bad
+        );
+    }
+}
+
+fn scenario(packets: &NamedTempFile, dir: &TempDir) -> SimulatorConfig {
+    toml::from_str(&format!(
+        r#"
+packet_file = "{packets}"
+
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y2"
+
+[topology.routers]
+Rx0y0 = {{}}
+Rx0y1 = {{}}
+Rx0y2 = {{}}
+
+[topology.links]
+Rx0y0_Rx0y1 = {{}}
+Rx0y1_Rx0y2 = {{}}
+
+[[capture]]
+link = "Rx0y1_Rx0y2"
+filter = "udp and dst port 12345"
+file = "{dir}/udp.txt"
+
+[[capture]]
+link = "Rx0y0_Rx0y1"
+file = "{dir}/all.txt"
+"#,
+        packets = packets.path().display(),
+        dir = dir.path().display()
+    ))
+    .expect("config parses")
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_capture_points_write_matching_packets() {
+    let mut packets = NamedTempFile::new().unwrap();
+    for hex in [UDP_PACKET, TCP_PACKET, UDP_PACKET] {
+        writeln!(packets, "{}", hex).unwrap();
+    }
+    let dir = TempDir::new().unwrap();
+    let fabric = network_simulator::run(scenario(&packets, &dir))
+        .await
+        .expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+
+    assert_eq!(fabric.captures[0].captured, 2);
+    assert_eq!(fabric.captures[0].seen, 3);
+    let udp = std::fs::read_to_string(dir.path().join("udp.txt")).unwrap();
+    let lines: Vec<&str> = udp.lines().collect();
+    assert!(lines[0].starts_with("# capture on Rx0y1_Rx0y2"));
+    // Captured as carried over the link: two hops later, TTL and checksum are rewritten.
+    let on_wire = "4500001e000000003e1167cc0a0000020a0001021f903039000a00006869";
+    assert_eq!(&lines[1..], &[on_wire, on_wire, "# 2 of 3 packets"]);
+
+    let all = std::fs::read_to_string(dir.path().join("all.txt")).unwrap();
+    assert_eq!(all.lines().filter(|l| !l.starts_with('#')).count(), 3);
+}
+
+#[test]
+fn test_capture_validation() {
+    let packets = NamedTempFile::new().unwrap();
+    let dir = TempDir::new().unwrap();
+    let base = || {
+        let mut cfg = scenario(&packets, &dir);
+        cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+        cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+        cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+        cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+        cfg
+    };
+    base().validate().expect("valid");
+
+    let mut cfg = base();
+    cfg.captures[0].link = "Rx0y0_Rx0y2".to_string();
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("Rx0y0_Rx0y2"), "{}", err);
+
+    let mut cfg = base();
+    cfg.captures[1].filter = "udp or".to_string();
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("Capture on Rx0y0_Rx0y1"), "{}", err);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use network_simulator::config::DropCaptureConfig;
+use network_simulator::drops::{DropCapture, DropReason};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{parse, verify_checksums, PacketMeta};
+use network_simulator::processor::process_packet;
+use network_simulator::report::ExitReport;
+use network_simulator::routing::Destination;
+use network_simulator::topology::{Fabric, RouterId};
+
+fn udp(src: &str, dst: &str) -> PacketMeta {
+    PacketBuilder::new(src.parse().unwrap(), dst.parse().unwrap())
+        .udp(1000, 2000)
+        .payload(vec![1, 2, 3, 4])
+        .build()
+        .unwrap()
+}
+
+/// `packet` with the byte at `at` flipped.
+fn corrupt(packet: &PacketMeta, at: usize) -> PacketMeta {
+    let mut raw = packet.raw.to_vec();
+    raw[at] ^= 0xFF;
+    parse(&raw).unwrap()
+}
+
+#[test]
+fn test_verify_checksums() {
+    let v4 = udp("10.0.0.2", "10.0.1.2");
+    assert_eq!(verify_checksums(&v4.raw, true), Ok(()));
+    // TTL changed without fixing the header checksum.
+    assert_eq!(
+        verify_checksums(&corrupt(&v4, 8).raw, false),
+        Err("bad IPv4 header checksum")
+    );
+    // Payload corruption only shows with transport checksums.
+    let payload = corrupt(&v4, v4.raw.len() - 1);
+    assert_eq!(verify_checksums(&payload.raw, false), Ok(()));
+    assert_eq!(
+        verify_checksums(&payload.raw, true),
+        Err("bad transport checksum")
+    );
+    // A zero UDP checksum over IPv4 means none.
+    let mut raw = v4.raw.to_vec();
+    raw[26..28].copy_from_slice(&[0, 0]);
+    raw[31] ^= 0xFF;
+    assert_eq!(verify_checksums(&raw, true), Ok(()));
+
+    let v6 = udp("2001:db8::2", "2001:db8:1::2");
+    assert_eq!(verify_checksums(&v6.raw, true), Ok(()));
+    assert_eq!(
+        verify_checksums(&corrupt(&v6, 45).raw, true),
+        Err("bad transport checksum")
+    );
+}
+
+fn bad_checksums(fabric: &Fabric) -> u64 {
+    fabric
+        .get_router(&RouterId("Rx0y0".into()))
+        .unwrap()
+        .stats
+        .bad_checksums
+}
+
+fn delivered(fabric: &Fabric) -> u64 {
+    fabric
+        .get_router(&RouterId("Rx0y1".into()))
+        .unwrap()
+        .stats
+        .packets_delivered
+}
+
+#[tokio::test]
+async fn test_bad_checksum_dropped_or_counted() {
+    let good = udp("10.0.0.2", "10.0.1.2");
+    let bad = corrupt(&good, 8);
+    for (section, passed) in [("[checksum]", 1), ("[checksum]\ndrop = false", 2)] {
+        let cfg = common::line("", &["", ""], &[""], section);
+        let mut fabric = network_simulator::build_fabric(&cfg);
+        fabric.drops = Some(DropCapture::new(&DropCaptureConfig::default()));
+        let tables = network_simulator::compute_routing_tables(&cfg);
+        for packet in [good.clone(), bad.clone()] {
+            process_packet(
+                &mut fabric,
+                &tables,
+                RouterId("Rx0y0".into()),
+                packet,
+                Destination::TunB,
+            )
+            .await;
+        }
+        assert_eq!(bad_checksums(&fabric), 1);
+        assert_eq!(delivered(&fabric), passed);
+        // Only a packet dropped counts as dropped in the exit report.
+        let report = ExitReport::of(&fabric, None);
+        assert_eq!(report.packets.dropped, 2 - passed);
+        let drops = fabric.drops.as_ref().unwrap();
+        let reasons: Vec<_> = drops
+            .recent(&RouterId("Rx0y0".into()))
+            .map(|d| d.reason)
+            .collect();
+        let expected: &[DropReason] = if passed == 1 {
+            &[DropReason::BadChecksum]
+        } else {
+            &[]
+        };
+        assert_eq!(reasons, expected);
+    }
+}
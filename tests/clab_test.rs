@@ -0,0 +1,119 @@
+mod common;
+
+use network_simulator::clab::{self, Lab, EDGE_LABEL};
+use network_simulator::config::SimulatorConfig;
+
+fn triangle() -> SimulatorConfig {
+    common::scenario(
+        "",
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx1y1", "")],
+        &[
+            (
+                "Rx0y0_Rx0y1",
+                "delay_ms = 10, jitter_ms = 2, loss_percent = 0.5",
+            ),
+            ("Rx0y1_Rx1y1", "bandwidth_mbps = 2.5, mtu = 1400"),
+            ("Rx0y0_Rx1y1", "delay_ms = 30, cost = 5"),
+        ],
+        "",
+    )
+}
+
+#[test]
+fn test_export_writes_links_and_netem() {
+    let exported = clab::export(&triangle(), "lab", "frrouting/frr:latest");
+    let lab = &exported.output;
+    assert_eq!(lab.topology.nodes.len(), 3);
+    let endpoints: Vec<_> = lab.topology.links.iter().map(|l| &l.endpoints).collect();
+    assert_eq!(
+        endpoints,
+        [
+            &["Rx0y0:eth1", "Rx0y1:eth1"],
+            &["Rx0y0:eth2", "Rx1y1:eth1"],
+            &["Rx0y1:eth2", "Rx1y1:eth2"],
+        ]
+    );
+    let node = |name: &str| &lab.topology.nodes[name];
+    assert_eq!(
+        node("Rx0y0").exec,
+        [
+            "tc qdisc replace dev eth1 root netem delay 10ms 2ms loss 0.5%",
+            "tc qdisc replace dev eth2 root netem delay 30ms",
+        ]
+    );
+    assert_eq!(
+        node("Rx1y1").exec[1..],
+        [
+            "tc qdisc replace dev eth2 root netem rate 2500kbit",
+            "ip link set dev eth2 mtu 1400",
+        ]
+    );
+    assert_eq!(node("Rx0y0").labels[EDGE_LABEL], "tun_a");
+    assert_eq!(node("Rx1y1").labels[EDGE_LABEL], "tun_b");
+    assert!(node("Rx0y1").labels.is_empty());
+    assert_eq!(exported.notes, ["Link Rx0y0_Rx1y1: cost not exported"]);
+}
+
+#[test]
+fn test_import_round_trips_through_yaml() {
+    let yaml = serde_yaml::to_string(&clab::export(&triangle(), "lab", "alpine").output).unwrap();
+    let lab: Lab = serde_yaml::from_str(&yaml).unwrap();
+    let imported = clab::import(&lab).unwrap();
+    assert!(imported.notes.is_empty(), "{:?}", imported.notes);
+    let cfg: SimulatorConfig = toml::from_str(&imported.output).unwrap();
+    assert_eq!(cfg.tun_ingress.tun_a_ingress, "Rx0y0");
+    assert_eq!(cfg.tun_ingress.tun_b_ingress, "Rx1y1");
+    assert_eq!(cfg.topology.routers.len(), 3);
+    let link = |name: &str| &cfg.topology.links[name];
+    let first = link("Rx0y0_Rx0y1");
+    assert_eq!(
+        (first.delay_ms, first.jitter_ms, first.loss_percent),
+        (10, 2, 0.5)
+    );
+    assert_eq!(link("Rx0y1_Rx1y1").bandwidth_mbps, Some(2.5));
+    assert_eq!(link("Rx0y1_Rx1y1").mtu, Some(1400));
+    assert_eq!(link("Rx0y0_Rx1y1").delay_ms, 30);
+    // What netem cannot express does not come back.
+    assert_eq!(link("Rx0y0_Rx1y1").cost, None);
+}
+
+#[test]
+fn test_import_reports_what_it_skips() {
+    let lab: Lab = serde_yaml::from_str(
+        r#"
+name: hand-written
+topology:
+  nodes:
+    Rx0y0:
+      kind: linux
+      exec:
+        - tc qdisc add dev eth1 root netem delay 1.5ms loss 2%
+        - sysctl -w net.ipv4.ip_forward=1
+    Rx0y1:
+      kind: linux
+      exec:
+        - tc qdisc add dev eth1 root netem delay 4ms
+  links:
+    - endpoints: ["Rx0y0:eth1", "Rx0y1:eth1"]
+"#,
+    )
+    .unwrap();
+    let imported = clab::import(&lab).unwrap();
+    assert_eq!(
+        imported.notes,
+        [
+            "Node Rx0y0: ignored command: sysctl -w net.ipv4.ip_forward=1",
+            "Link Rx0y0_Rx0y1: the ends are impaired differently, kept Rx0y0:eth1",
+        ]
+    );
+    let cfg: SimulatorConfig = toml::from_str(&imported.output).unwrap();
+    let link = &cfg.topology.links["Rx0y0_Rx0y1"];
+    assert_eq!((link.delay_ms, link.loss_percent), (2, 2.0));
+
+    let mut lab = lab;
+    let node = lab.topology.nodes.remove("Rx0y1").unwrap();
+    lab.topology.nodes.insert("spine1".to_string(), node);
+    assert!(clab::import(&lab)
+        .unwrap_err()
+        .contains("Invalid router id 'spine1'"));
+}
@@ -0,0 +1,75 @@
+//! Scenario helpers shared by the integration tests.
+
+// Each test binary uses only some of these.
+#![allow(dead_code)]
+
+use network_simulator::config::SimulatorConfig;
+use network_simulator::topology::RouterId;
+
+pub fn rid(s: &str) -> RouterId {
+    RouterId(s.to_string())
+}
+
+/// Config with tun_a (10.0.0.0/24) behind the first router and tun_b (10.0.1.0/24) behind the
+/// last one. Routers and links are `(name, inline table body)` pairs; `top` goes before the first
+/// section (e.g. `packet_file`) and `rest` after the topology (e.g. a `[ddos]` section).
+pub fn scenario(
+    top: &str,
+    routers: &[(&str, &str)],
+    links: &[(&str, &str)],
+    rest: &str,
+) -> SimulatorConfig {
+    let table = |entries: &[(&str, &str)]| -> String {
+        entries
+            .iter()
+            .map(|(name, opts)| format!("{} = {{ {} }}\n", name, opts))
+            .collect()
+    };
+    let first = routers.first().expect("at least one router").0;
+    let last = routers.last().expect("at least one router").0;
+    toml::from_str(&format!(
+        r#"{}
+
+[tun_ingress]
+tun_a_ingress = "{}"
+tun_b_ingress = "{}"
+tun_a_prefix = "10.0.0.0/24"
+tun_b_prefix = "10.0.1.0/24"
+
+[topology.routers]
+{}
+[topology.links]
+{}
+{}
+"#,
+        top,
+        first,
+        last,
+        table(routers),
+        table(links),
+        rest
+    ))
+    .expect("config parses")
+}
+
+/// Line Rx0y0 - Rx0y1 - … with one router per entry of `routers` and the links between
+/// neighbours in order; see [`scenario`].
+pub fn line(top: &str, routers: &[&str], links: &[&str], rest: &str) -> SimulatorConfig {
+    assert_eq!(links.len() + 1, routers.len(), "one link between each pair");
+    let names: Vec<String> = (0..routers.len()).map(|i| format!("Rx0y{}", i)).collect();
+    let link_names: Vec<String> = names
+        .windows(2)
+        .map(|w| format!("{}_{}", w[0], w[1]))
+        .collect();
+    let routers: Vec<(&str, &str)> = names
+        .iter()
+        .map(String::as_str)
+        .zip(routers.iter().copied())
+        .collect();
+    let links: Vec<(&str, &str)> = link_names
+        .iter()
+        .map(String::as_str)
+        .zip(links.iter().copied())
+        .collect();
+    scenario(top, &routers, &links, rest)
+}
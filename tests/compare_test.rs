@@ -0,0 +1,115 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use network_simulator::experiment::{apply_override, compare_report, run_scenario, RunSummary};
+use predicates::str::contains;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const SCENARIO: &str = r#"
+[interfaces.real_tun_a]
+address = "10.0.0.1"
+
+[interfaces.real_tun_b]
+address = "10.0.1.1"
+
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+
+[topology.routers]
+Rx0y0 = {}
+Rx0y1 = {}
+
+[topology.links]
+Rx0y0_Rx0y1 = { delay_ms = 1 }
+
+[virtual_customer]
+src_ip = "10.0.0.2"
+dst_ip = "10.0.1.2"
+size = 100
+rate = 4
+"#;
+
+/// Scenario bounded by a mock packet file (one IPv4 packet), so no real TUN is opened.
+fn scenario() -> (toml::Value, NamedTempFile) {
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    let mut value: toml::Value = toml::from_str(SCENARIO).unwrap();
+    value.as_table_mut().unwrap().insert(
+        "packet_file".to_string(),
+        toml::Value::String(packets.path().to_string_lossy().into_owned()),
+    );
+    (value, packets)
+}
+
+/// Remove the `<packet_file>_out.txt` written by the mock TUN.
+fn remove_output(packets: &NamedTempFile) {
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+}
+
+#[test]
+fn test_apply_override_sets_nested_values() {
+    let mut value: toml::Value = toml::from_str(SCENARIO).unwrap();
+    apply_override(&mut value, "topology.links.Rx0y0_Rx0y1.delay_ms=20").unwrap();
+    apply_override(&mut value, "simulation.seed = 7").unwrap();
+    apply_override(&mut value, "virtual_customer.name=acme").unwrap();
+    assert_eq!(
+        value["topology"]["links"]["Rx0y0_Rx0y1"]["delay_ms"].as_integer(),
+        Some(20)
+    );
+    assert_eq!(value["simulation"]["seed"].as_integer(), Some(7));
+    assert_eq!(value["virtual_customer"]["name"].as_str(), Some("acme"));
+    assert!(apply_override(&mut value, "no_equals_sign").is_err());
+    assert!(apply_override(&mut value, "virtual_customer.rate.x=1").is_err());
+}
+
+#[tokio::test]
+async fn test_compare_runs_reflect_override() {
+    let (value_a, packets) = scenario();
+    let mut value_b = value_a.clone();
+    apply_override(&mut value_b, "topology.links.Rx0y0_Rx0y1.delay_ms=30").unwrap();
+    let a = run_scenario(value_a, false).await.expect("run A");
+    let b = run_scenario(value_b, false).await.expect("run B");
+    remove_output(&packets);
+    // Four virtual-customer packets, the mock packet and the start-up demonstration packet.
+    assert_eq!(a.delivered, 6);
+    assert!(b.latency_avg_ms.unwrap() >= 30.0);
+    assert!(b.latency_avg_ms.unwrap() > a.latency_avg_ms.unwrap());
+    let report = compare_report("A", &a, "B", &b);
+    assert!(report.contains("latency_avg_ms"));
+    assert!(report
+        .lines()
+        .any(|l| l.starts_with("delivered") && l.contains("+0.000")));
+}
+
+#[test]
+fn test_compare_report_percent_delta() {
+    let a = RunSummary {
+        delivered: 100,
+        ..Default::default()
+    };
+    let b = RunSummary {
+        delivered: 90,
+        ..Default::default()
+    };
+    let report = compare_report("A", &a, "B", &b);
+    assert!(report.contains("-10.0%"));
+}
+
+#[test]
+fn test_compare_subcommand() {
+    let (value, packets) = scenario();
+    let mut cfg = NamedTempFile::new().unwrap();
+    cfg.write_all(toml::to_string(&value).unwrap().as_bytes())
+        .unwrap();
+    let mut cmd = cargo_bin_cmd!("network-simulator");
+    cmd.arg("--config").arg(cfg.path()).args([
+        "compare",
+        "--set",
+        "topology.links.Rx0y0_Rx0y1.loss_percent=100",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("loss_percent"))
+        .stdout(contains("100.000"));
+    remove_output(&packets);
+}
@@ -2,9 +2,11 @@ use network_simulator::config::SimulatorConfig;
 
 #[test]
 fn test_invalid_packet_inject_tun_value() {
-    let mut cfg = SimulatorConfig::default();
-    cfg.packet_file = Some("dummy.txt".to_string());
-    cfg.packet_inject_tun = Some("invalid_tun".to_string());
+    let cfg = SimulatorConfig {
+        packet_file: Some("dummy.txt".to_string()),
+        packet_inject_tun: Some("invalid_tun".to_string()),
+        ..Default::default()
+    };
     let result = cfg.validate();
     assert!(result.is_err());
     assert_eq!(
@@ -15,9 +17,11 @@ fn test_invalid_packet_inject_tun_value() {
 
 #[test]
 fn test_invalid_packet_inject_tuns_values() {
-    let mut cfg = SimulatorConfig::default();
-    cfg.packet_files = Some(vec!["file1.txt".to_string()]);
-    cfg.packet_inject_tuns = Some(vec!["tun_a".to_string(), "bad".to_string()]);
+    let cfg = SimulatorConfig {
+        packet_files: Some(vec!["file1.txt".to_string()]),
+        packet_inject_tuns: Some(vec!["tun_a".to_string(), "bad".to_string()]),
+        ..Default::default()
+    };
     let result = cfg.validate();
     assert!(result.is_err());
     assert_eq!(
@@ -19,6 +19,7 @@ fn test_invalid_link_name_format() {
             jitter_ms: 0,
             loss_percent: 0.0,
             load_balance: false,
+            ..Default::default()
         },
     );
     let result = cfg.validate();
@@ -33,6 +33,7 @@ fn test_duplicate_bidirectional_link() {
             jitter_ms: 0,
             loss_percent: 0.0,
             load_balance: false,
+            ..Default::default()
         },
     );
     cfg.topology.links.insert(
@@ -43,6 +44,7 @@ fn test_duplicate_bidirectional_link() {
             jitter_ms: 0,
             loss_percent: 0.0,
             load_balance: false,
+            ..Default::default()
         },
     );
     let result = cfg.validate();
@@ -2,9 +2,11 @@ use network_simulator::config::SimulatorConfig;
 
 #[test]
 fn test_mutually_exclusive_packet_files() {
-    let mut cfg = SimulatorConfig::default();
-    cfg.packet_file = Some("single.txt".to_string());
-    cfg.packet_files = Some(vec!["multi1.txt".to_string(), "multi2.txt".to_string()]);
+    let cfg = SimulatorConfig {
+        packet_file: Some("single.txt".to_string()),
+        packet_files: Some(vec!["multi1.txt".to_string(), "multi2.txt".to_string()]),
+        ..Default::default()
+    };
     let result = cfg.validate();
     assert!(result.is_err());
     assert_eq!(
@@ -15,9 +17,11 @@ fn test_mutually_exclusive_packet_files() {
 
 #[test]
 fn test_mismatched_injection_counts() {
-    let mut cfg = SimulatorConfig::default();
-    cfg.packet_files = Some(vec!["file1.txt".to_string(), "file2.txt".to_string()]);
-    cfg.packet_inject_tuns = Some(vec!["tun_a".to_string()]); // mismatched length
+    let cfg = SimulatorConfig {
+        packet_files: Some(vec!["file1.txt".to_string(), "file2.txt".to_string()]),
+        packet_inject_tuns: Some(vec!["tun_a".to_string()]), // mismatched length
+        ..Default::default()
+    };
     let result = cfg.validate();
     assert!(result.is_err());
     assert!(result
@@ -27,8 +31,10 @@ fn test_mismatched_injection_counts() {
 
 #[test]
 fn test_inject_without_file() {
-    let mut cfg = SimulatorConfig::default();
-    cfg.packet_inject_tun = Some("tun_a".to_string());
+    let cfg = SimulatorConfig {
+        packet_inject_tun: Some("tun_a".to_string()),
+        ..Default::default()
+    };
     // No packet_file set
     let result = cfg.validate();
     assert!(result.is_err());
@@ -40,8 +46,10 @@ fn test_inject_without_file() {
 
 #[test]
 fn test_injects_without_files() {
-    let mut cfg = SimulatorConfig::default();
-    cfg.packet_inject_tuns = Some(vec!["tun_a".to_string()]);
+    let cfg = SimulatorConfig {
+        packet_inject_tuns: Some(vec!["tun_a".to_string()]),
+        ..Default::default()
+    };
     // No packet_files set
     let result = cfg.validate();
     assert!(result.is_err());
@@ -0,0 +1,224 @@
+use network_simulator::config::{SimulatorConfig, VirtualCustomerConfig};
+use network_simulator::customer::{build_packet, payload_intact, validate, Pattern};
+use network_simulator::packet::explain;
+
+fn customer(src: &str, dst: &str, extra: &str) -> VirtualCustomerConfig {
+    toml::from_str(&format!(
+        "src_ip = \"{}\"\ndst_ip = \"{}\"\n{}",
+        src, dst, extra
+    ))
+    .expect("customer parses")
+}
+
+#[test]
+fn test_udp_packet_with_sequence_payload() {
+    let vc = customer(
+        "10.0.0.2",
+        "10.0.1.2",
+        "protocol = 17\nsize = 12\nsrc_port = 5000\ndst_port = 6000\npayload_pattern = \"sequence\"",
+    );
+    let packet = build_packet(&vc, 7).unwrap();
+    assert_eq!(packet.raw.len(), 20 + 8 + 12);
+    assert_eq!((packet.src_port, packet.dst_port), (5000, 6000));
+    assert_eq!(&packet.raw[28..], &[0, 0, 0, 0, 0, 0, 0, 7, 7, 8, 9, 10]);
+    let text = explain(&packet.raw);
+    assert!(text.contains("UDP 5000 -> 6000, length 20"), "{}", text);
+    assert!(!text.contains("INVALID"), "{}", text);
+    assert!(payload_intact(&vc, 7, &packet));
+    assert!(!payload_intact(&vc, 8, &packet));
+}
+
+#[test]
+fn test_mirrored_customer_swaps_addresses_and_ports() {
+    let vc = customer(
+        "10.0.0.2",
+        "10.0.1.2",
+        "name = \"acme\"\nprotocol = 17\nsrc_port = 5000\nsize = 12",
+    );
+    assert!(vc.mirrored().is_none());
+    assert_eq!(vc.flows().len(), 1);
+    let vc = VirtualCustomerConfig {
+        reverse: true,
+        ..vc
+    };
+    let mirror = vc.mirrored().unwrap();
+    assert_eq!(mirror.customer_name(), "acme-reverse");
+    assert!(!mirror.reverse);
+    let packet = build_packet(&mirror, 0).unwrap();
+    assert_eq!(packet.src_ip.to_string(), "10.0.1.2");
+    assert_eq!(packet.dst_ip.to_string(), "10.0.0.2");
+    // The destination port defaults to discard, so that is where the mirror comes from.
+    assert_eq!((packet.src_port, packet.dst_port), (9, 5000));
+    assert_eq!(packet.raw.len(), 20 + 8 + 12);
+}
+
+#[test]
+fn test_tcp_over_ipv6_with_extension_headers() {
+    let vc = customer(
+        "2001:db8::2",
+        "2001:db8:1::2",
+        "protocol = 6\nsize = 5\npayload_pattern = \"hex:abcd\"\n\
+         ipv6_extension_headers = [\"hop_by_hop\", \"destination_options\"]",
+    );
+    validate(&vc).unwrap();
+    let packet = build_packet(&vc, 0).unwrap();
+    assert_eq!(packet.raw.len(), 40 + 16 + 20 + 5);
+    assert_eq!(packet.raw[6], 0, "Hop-by-Hop first");
+    assert_eq!(packet.raw[40], 60, "then Destination Options");
+    assert_eq!(packet.raw[48], 6, "then TCP");
+    assert_eq!(packet.protocol, 6);
+    assert_eq!(&packet.raw[76..], &[0xab, 0xcd, 0xab, 0xcd, 0xab]);
+    let text = explain(&packet.raw);
+    assert!(!text.contains("INVALID"), "{}", text);
+    let mut altered = packet.clone();
+    *altered.raw_mut().last_mut().unwrap() ^= 1;
+    assert!(payload_intact(&vc, 0, &packet));
+    assert!(!payload_intact(&vc, 0, &altered));
+}
+
+#[test]
+fn test_customer_options_validated() {
+    let err = validate(&customer(
+        "10.0.0.2",
+        "10.0.1.2",
+        "payload_pattern = \"hex:zz\"",
+    ))
+    .unwrap_err();
+    assert!(err.contains("payload pattern"), "{}", err);
+    assert!(Pattern::parse("random").is_err());
+    let err = validate(&customer(
+        "10.0.0.2",
+        "10.0.1.2",
+        "ipv6_extension_headers = [\"hop_by_hop\"]",
+    ))
+    .unwrap_err();
+    assert!(err.contains("IPv6 addresses"), "{}", err);
+    let err = validate(&customer(
+        "2001:db8::2",
+        "2001:db8:1::2",
+        "ipv6_extension_headers = [\"destination_options\", \"hop_by_hop\"]",
+    ))
+    .unwrap_err();
+    assert!(err.contains("first"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_customer_payload_verified_at_egress() {
+    let cfg: SimulatorConfig = toml::from_str(
+        r#"
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+
+[topology.routers]
+Rx0y0 = {}
+Rx0y1 = {}
+
+[topology.links]
+Rx0y0_Rx0y1 = { delay_ms = 1 }
+
+[virtual_customer]
+name = "acme"
+src_ip = "10.0.0.2"
+dst_ip = "10.0.1.2"
+protocol = 17
+size = 64
+rate = 4
+payload_pattern = "sequence"
+"#,
+    )
+    .expect("config parses");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let flow = &fabric.customer_flows["acme"];
+    assert_eq!(flow.sent, 4);
+    assert_eq!(flow.delivered, 4);
+    assert_eq!(flow.bytes_delivered, 4 * (20 + 8 + 64));
+    assert_eq!(flow.payload_errors, 0);
+}
+
+#[test]
+fn test_template_restamped_per_packet() {
+    let source = customer(
+        "192.0.2.1",
+        "192.0.2.9",
+        "protocol = 6\nsize = 10\nsrc_port = 1234\ndst_port = 80\npayload_pattern = \"hex:474554\"",
+    );
+    let captured = build_packet(&source, 3).unwrap();
+    let vc = customer(
+        "10.0.0.2",
+        "10.0.1.2",
+        &format!(
+            "template = \"hex:{}\"\nsrc_port = 5000",
+            hex::encode(&captured.raw)
+        ),
+    );
+    validate(&vc).unwrap();
+    let first = build_packet(&vc, 0).unwrap();
+    let second = build_packet(&vc, 1).unwrap();
+    assert_eq!(first.raw.len(), captured.raw.len());
+    assert_eq!((second.src_port, second.dst_port), (5000, 80));
+    assert_eq!(second.src_ip.to_string(), "10.0.0.2");
+    assert_eq!(
+        &second.raw[4..6],
+        &[0, 1],
+        "identification is the sequence number"
+    );
+    let tcp_seq = |p: &network_simulator::packet::PacketMeta| {
+        u32::from_be_bytes(p.raw[24..28].try_into().unwrap())
+    };
+    assert_eq!(tcp_seq(&second), tcp_seq(&first) + 10);
+    assert_eq!(&second.raw[40..], &captured.raw[40..]);
+    let text = explain(&second.raw);
+    assert!(!text.contains("INVALID"), "{}", text);
+    assert!(payload_intact(&vc, 1, &second));
+    let mut altered = second.clone();
+    *altered.raw_mut().last_mut().unwrap() ^= 1;
+    assert!(!payload_intact(&vc, 1, &altered));
+}
+
+#[test]
+fn test_template_from_packet_file() {
+    let udp = |n: u8| {
+        let source = customer(
+            "2001:db8::1",
+            "2001:db8::9",
+            &format!(
+                "protocol = 17\nsize = 4\npayload_pattern = \"hex:{:02x}\"",
+                n
+            ),
+        );
+        hex::encode(&build_packet(&source, 0).unwrap().raw)
+    };
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), format!("{}\n{}\n", udp(1), udp(2))).unwrap();
+    let path = file.path().display();
+    let vc = customer(
+        "2001:db8::2",
+        "2001:db8:1::2",
+        &format!("template = \"pcap:{}#2\"", path),
+    );
+    validate(&vc).unwrap();
+    let packet = build_packet(&vc, 5).unwrap();
+    assert_eq!(&packet.raw[48..], &[2, 2, 2, 2]);
+    assert_eq!(packet.dst_ip.to_string(), "2001:db8:1::2");
+    assert!(!explain(&packet.raw).contains("INVALID"));
+
+    let missing = customer(
+        "2001:db8::2",
+        "2001:db8:1::2",
+        &format!("template = \"pcap:{}#3\"", path),
+    );
+    assert!(validate(&missing).unwrap_err().contains("no packet 3"));
+    let family = customer(
+        "10.0.0.2",
+        "10.0.1.2",
+        &format!("template = \"pcap:{}\"", path),
+    );
+    assert!(validate(&family).unwrap_err().contains("address families"));
+    let sized = customer(
+        "2001:db8::2",
+        "2001:db8:1::2",
+        &format!("template = \"pcap:{}\"\nsize = 5", path),
+    );
+    assert!(validate(&sized).unwrap_err().contains("cannot be combined"));
+}
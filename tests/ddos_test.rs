@@ -0,0 +1,160 @@
+mod common;
+
+use network_simulator::config::SimulatorConfig;
+use network_simulator::ddos::{build_flood_packet, FloodKind};
+use network_simulator::topology::RouterId;
+use std::net::IpAddr;
+
+fn scenario(ddos: &str, rx0y1_opts: &str) -> SimulatorConfig {
+    common::line(
+        "[simulation]\nseed = 7",
+        &["", rx0y1_opts, ""],
+        &["", ""],
+        &format!("[ddos]\ntarget_prefix = \"10.0.1.0/24\"\n{}", ddos),
+    )
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_unmitigated_flood_reaches_target() {
+    let fabric = network_simulator::run(scenario("rate_pps = 100\nduration_secs = 0.5", ""))
+        .await
+        .expect("run");
+    let report = fabric.ddos_report.expect("flood ran");
+    assert_eq!(report.sent, 50);
+    assert_eq!(report.delivered, 50);
+    assert_eq!(report.acl_drops + report.cpu_drops + report.link_lost, 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_acl_filters_flood() {
+    let acl = r#"acl = [ { action = "permit", match = "src net 10.0.0.0/24" }, { action = "deny", match = "udp and dst port 53" } ]"#;
+    let fabric = network_simulator::run(scenario("rate_pps = 100\nduration_secs = 0.2", acl))
+        .await
+        .expect("run");
+    let report = fabric.ddos_report.as_ref().expect("flood ran");
+    assert_eq!(report.sent, 20);
+    assert_eq!(report.acl_drops, 20);
+    assert_eq!(report.delivered, 0);
+    let router = fabric.get_router(&RouterId("Rx0y1".into())).unwrap();
+    assert_eq!(router.stats.acl_drops, 20);
+    // Flood sources are spoofed from 198.18.0.0/15; only the start-up demonstration packet
+    // matches the permit rule.
+    assert_eq!(router.acl[0].hits, 1);
+    assert_eq!(router.acl[1].hits, 20);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_flood_overloads_router_cpu() {
+    let fabric = network_simulator::run(scenario(
+        "rate_pps = 1000\nduration_secs = 0.1",
+        "max_pps = 200, cpu_queue = 5",
+    ))
+    .await
+    .expect("run");
+    let report = fabric.ddos_report.expect("flood ran");
+    assert_eq!(report.sent, 100);
+    assert!(report.cpu_drops > 50, "{}", report.summary());
+    assert_eq!(report.delivered + report.cpu_drops, report.sent);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_report_shows_achieved_rate() {
+    let fabric = network_simulator::run(scenario("rate_pps = 100\nduration_secs = 0.5", ""))
+        .await
+        .expect("run");
+    let report = fabric.ddos_report.expect("flood ran");
+    assert!(
+        (report.achieved_pps() - 100.0).abs() < 1.0,
+        "{}",
+        report.summary()
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_slow_path_holds_back_achieved_rate() {
+    let mut cfg = scenario("rate_pps = 100\nduration_secs = 0.5", "");
+    for link in cfg.topology.links.values_mut() {
+        link.delay_ms = 20;
+    }
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let report = fabric.ddos_report.expect("flood ran");
+    assert_eq!(report.sent, 50);
+    // Each packet spends 40 ms on the two links before the next one is sent.
+    assert!(report.achieved_pps() < 30.0, "{}", report.summary());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_flood_round_robins_ingresses() {
+    let fabric = network_simulator::run(scenario(
+        "rate_pps = 100\nduration_secs = 0.1\ningresses = [\"Rx0y0\", \"Rx0y1\"]",
+        "",
+    ))
+    .await
+    .expect("run");
+    let stats = fabric.get_statistics();
+    // Ten flood packets enter at Rx0y0 or Rx0y1 in turn and all of them cross Rx0y1.
+    // Rx0y0 additionally sees the start-up demonstration packet.
+    assert_eq!(stats[&RouterId("Rx0y0".into())].packets_received, 6);
+    assert_eq!(stats[&RouterId("Rx0y1".into())].packets_received, 11);
+    assert_eq!(fabric.ddos_report.unwrap().delivered, 10);
+}
+
+#[test]
+fn test_flood_packet_layout() {
+    let src: IpAddr = "198.18.0.1".parse().unwrap();
+    let dst: IpAddr = "10.0.1.9".parse().unwrap();
+    let syn = build_flood_packet(FloodKind::TcpSyn, src, dst, 40000, 80, 100);
+    assert_eq!(syn.raw.len(), 100);
+    assert_eq!(syn.protocol, 6);
+    assert_eq!(syn.dst_port, 80);
+    assert_eq!(syn.raw[33], 0x02); // SYN flag
+    let parsed = network_simulator::packet::parse(&syn.raw).expect("parses");
+    assert_eq!(parsed.src_ip, src);
+    assert_eq!(parsed.dst_port, 80);
+
+    // The size never drops below the headers.
+    let icmp = build_flood_packet(FloodKind::Icmp, src, dst, 0, 0, 10);
+    assert_eq!(icmp.raw.len(), 28);
+    assert_eq!(icmp.raw[20], 8);
+
+    let v6 = build_flood_packet(
+        FloodKind::Udp,
+        "2001:db8::1".parse().unwrap(),
+        "fd00::5".parse().unwrap(),
+        5000,
+        53,
+        80,
+    );
+    assert_eq!(v6.raw.len(), 80);
+    assert_eq!(v6.raw[6], 17);
+    assert_eq!(u16::from_be_bytes([v6.raw[44], v6.raw[45]]), 40);
+}
+
+#[test]
+fn test_ddos_config_validated() {
+    let with_interfaces = |ddos: &str, opts: &str| {
+        let mut cfg = scenario(ddos, opts);
+        cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+        cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+        cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+        cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+        cfg
+    };
+    with_interfaces("", "").validate().expect("valid");
+    let err = with_interfaces("spoof_prefix = \"2001:db8::/32\"", "")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("address family"), "{}", err);
+    let err = with_interfaces("ingresses = [\"Rx9y9\"]", "")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("Rx9y9"), "{}", err);
+    let err = with_interfaces("protocol = \"gre\"", "")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("protocol"), "{}", err);
+    let err = with_interfaces("", r#"acl = [ { action = "deny", match = "port http" } ]"#)
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("ACL"), "{}", err);
+}
@@ -23,6 +23,7 @@ async fn test_destination_detection_stops_forwarding() {
         jitter_ms: 0,
         loss_percent: 0.0,
         load_balance: false,
+        ..Default::default()
     };
     cfg.topology
         .links
@@ -45,11 +46,9 @@ async fn test_destination_detection_stops_forwarding() {
     let packet = packet::parse(&raw).unwrap_or(PacketMeta {
         src_ip: "10.0.0.1".parse().unwrap(),
         dst_ip: "10.0.1.1".parse().unwrap(),
-        src_port: 0,
-        dst_port: 0,
         protocol: 6,
-        ttl: 64,
-        raw,
+        raw: raw.into(),
+        ..Default::default()
     });
     // Process packet from ingress Rx0y0 towards TunB (destination router is Rx0y1).
     let result = process_packet(
@@ -0,0 +1,96 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::{DestinationMapConfig, SimulatorConfig};
+use network_simulator::routing::destination_map::DestinationMap;
+use network_simulator::Destination;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn entry(ingress: &str, prefix: &str, egress: &str) -> DestinationMapConfig {
+    DestinationMapConfig {
+        ingress: ingress.to_string(),
+        prefix: prefix.to_string(),
+        egress: egress.to_string(),
+    }
+}
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+#[test]
+fn test_longest_prefix_of_the_ingress_edge_wins() {
+    let map = DestinationMap::new(&[
+        entry("tun_a", "10.0.0.0/8", "tun_a"),
+        entry("tun_a", "10.0.1.0/24", "tun_b"),
+        entry("tun_b", "2001:db8::/32", "tun_b"),
+    ])
+    .unwrap();
+    let egress = |ingress, dst: &str| map.egress(ingress, &dst.parse().unwrap());
+    assert_eq!(egress(Destination::TunA, "10.0.5.2"), Destination::TunA);
+    assert_eq!(egress(Destination::TunA, "10.0.1.2"), Destination::TunB);
+    assert_eq!(egress(Destination::TunA, "192.0.2.1"), Destination::TunB);
+    // Entries only apply to packets from their own ingress edge.
+    assert_eq!(egress(Destination::TunB, "10.0.5.2"), Destination::TunA);
+    assert_eq!(egress(Destination::TunB, "2001:db8::1"), Destination::TunB);
+    assert!(DestinationMap::new(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_invalid_entries_rejected() {
+    let cfg = common::line(
+        "",
+        &["", ""],
+        &[""],
+        "[[destination_map]]\ningress = \"tun_c\"\nprefix = \"10.0.5.0/24\"\negress = \"tun_a\"",
+    );
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("tun_c"), "{}", err);
+    let cfg = common::line(
+        "",
+        &["", ""],
+        &[""],
+        "[[destination_map]]\ningress = \"tun_a\"\nprefix = \"10.0.5.0/33\"\negress = \"tun_a\"",
+    );
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("prefix"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_mapped_prefix_leaves_at_its_edge() {
+    let mut packets = NamedTempFile::new().unwrap();
+    // tun_a -> a second site behind tun_a, tun_b -> one behind tun_b, then plain tun_a -> tun_b.
+    writeln!(packets, "4500001400000000401100000a0000020a000502").unwrap();
+    writeln!(packets, "4500001400000000401100000a0001020a000702").unwrap();
+    writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", "", ""],
+        &["", ""],
+        r#"
+[[destination_map]]
+ingress = "tun_a"
+prefix = "10.0.5.0/24"
+egress = "tun_a"
+
+[[destination_map]]
+ingress = "tun_b"
+prefix = "10.0.7.0/24"
+egress = "tun_b"
+"#,
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    let stats = |r: &str| fabric.get_router(&rid(r)).unwrap().stats.clone();
+    assert_eq!(stats("Rx0y0").packets_delivered, 1);
+    // The startup demonstration packet is delivered at Rx0y2 too.
+    assert_eq!(stats("Rx0y2").packets_delivered, 3);
+    assert_eq!(stats("Rx0y1").packets_received, 2);
+}
@@ -0,0 +1,211 @@
+use network_simulator::config::{DhcpConfig, SimulatorConfig};
+use network_simulator::dhcp::{parse_request, DhcpServer, DHCP_CLIENT_PORT, DHCP_SERVER_PORT};
+use network_simulator::packet::parse;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+/// Build a client DHCP message (DISCOVER=1, REQUEST=3) as a broadcast IPv4/UDP packet.
+fn client_message(mac: u8, message_type: u8, requested: Option<Ipv4Addr>) -> Vec<u8> {
+    let mut bootp = vec![0u8; 236];
+    bootp[0] = 1; // BOOTREQUEST
+    bootp[1] = 1;
+    bootp[2] = 6;
+    bootp[4..8].copy_from_slice(&0x1234_5678u32.to_be_bytes());
+    bootp[28..34].copy_from_slice(&[0x02, 0, 0, 0, 0, mac]);
+    bootp.extend_from_slice(&[99, 130, 83, 99, 53, 1, message_type]);
+    if let Some(addr) = requested {
+        bootp.extend_from_slice(&[50, 4]);
+        bootp.extend_from_slice(&addr.octets());
+    }
+    bootp.push(255);
+    let udp_len = 8 + bootp.len();
+    let total_len = 20 + udp_len;
+    let mut raw = vec![
+        0x45,
+        0,
+        (total_len >> 8) as u8,
+        total_len as u8,
+        0,
+        0,
+        0,
+        0,
+        64,
+        17,
+        0,
+        0,
+    ];
+    raw.extend_from_slice(&[0, 0, 0, 0]);
+    raw.extend_from_slice(&[255, 255, 255, 255]);
+    raw.extend_from_slice(&DHCP_CLIENT_PORT.to_be_bytes());
+    raw.extend_from_slice(&DHCP_SERVER_PORT.to_be_bytes());
+    raw.extend_from_slice(&(udp_len as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0]);
+    raw.extend_from_slice(&bootp);
+    raw
+}
+
+/// Extract (message type, yiaddr, router option) from a server reply.
+fn reply_fields(reply: &[u8]) -> (u8, Ipv4Addr, Option<Ipv4Addr>) {
+    let bootp = &reply[28..];
+    assert_eq!(bootp[0], 2, "reply must be BOOTREPLY");
+    let yiaddr = Ipv4Addr::new(bootp[16], bootp[17], bootp[18], bootp[19]);
+    let mut i = 240;
+    let (mut msg_type, mut router) = (0, None);
+    while bootp[i] != 255 {
+        let (code, len) = (bootp[i], bootp[i + 1] as usize);
+        let v = &bootp[i + 2..i + 2 + len];
+        match code {
+            53 => msg_type = v[0],
+            3 => router = Some(Ipv4Addr::new(v[0], v[1], v[2], v[3])),
+            _ => {}
+        }
+        i += 2 + len;
+    }
+    (msg_type, yiaddr, router)
+}
+
+#[test]
+fn test_dhcp_discover_request_flow() {
+    let gateway = Ipv4Addr::new(10, 0, 0, 1);
+    let mut server = DhcpServer::new("10.0.0.0/29", gateway, 600).expect("server");
+
+    let discover = parse(&client_message(1, 1, None)).expect("parse discover");
+    let offer = server.handle(&discover).expect("offer");
+    let offer_meta = parse(&offer).expect("offer is a valid IPv4 packet");
+    assert_eq!(offer_meta.src_port, DHCP_SERVER_PORT);
+    assert_eq!(offer_meta.dst_port, DHCP_CLIENT_PORT);
+    let (msg_type, offered, router) = reply_fields(&offer);
+    assert_eq!(msg_type, 2); // OFFER
+    assert_eq!(router, Some(gateway));
+    assert_ne!(offered, gateway, "gateway address must not be leased");
+
+    let request = parse(&client_message(1, 3, Some(offered))).expect("parse request");
+    let (msg_type, acked, _) = reply_fields(&server.handle(&request).expect("ack"));
+    assert_eq!(msg_type, 5); // ACK
+    assert_eq!(acked, offered);
+
+    // A second client gets a different address.
+    let discover2 = parse(&client_message(2, 1, None)).expect("parse discover");
+    let (_, offered2, _) = reply_fields(&server.handle(&discover2).expect("offer"));
+    assert_ne!(offered2, offered);
+}
+
+#[test]
+fn test_dhcp_request_for_foreign_address_is_nakked() {
+    let mut server = DhcpServer::new("10.0.0.0/29", Ipv4Addr::new(10, 0, 0, 1), 600).unwrap();
+    let request = parse(&client_message(3, 3, Some(Ipv4Addr::new(192, 168, 1, 9)))).unwrap();
+    let (msg_type, _, _) = reply_fields(&server.handle(&request).expect("nak"));
+    assert_eq!(msg_type, 6); // NAK
+}
+
+#[test]
+fn test_dhcp_foreign_request_does_not_take_a_pool_address() {
+    let mut server = DhcpServer::new("10.0.0.0/30", Ipv4Addr::new(10, 0, 0, 1), 600).unwrap();
+    let foreign = parse(&client_message(3, 3, Some(Ipv4Addr::new(192, 168, 1, 9)))).unwrap();
+    server.handle(&foreign).expect("nak");
+    // The /30 has a single leasable host, which must still be free for the next client.
+    let discover = parse(&client_message(4, 1, None)).unwrap();
+    let (msg_type, offered, _) = reply_fields(&server.handle(&discover).expect("offer"));
+    assert_eq!(msg_type, 2);
+    assert_eq!(offered, Ipv4Addr::new(10, 0, 0, 2));
+}
+
+#[test]
+fn test_dhcp_offers_expire_and_retries_reuse_the_binding() {
+    let mut server = DhcpServer::new("10.0.0.0/30", Ipv4Addr::new(10, 0, 0, 1), 600).unwrap();
+    let now = Instant::now();
+    let discover = parse(&client_message(1, 1, None)).unwrap();
+    let (_, first, _) = reply_fields(&server.handle_at(&discover, now).unwrap());
+    let (_, again, _) = reply_fields(&server.handle_at(&discover, now).unwrap());
+    assert_eq!(first, again);
+    assert_eq!(server.active_bindings(now), 1);
+    // The pool is exhausted while the offer is held ...
+    let other = parse(&client_message(2, 1, None)).unwrap();
+    assert!(server.handle_at(&other, now).is_none());
+    // ... but an offer that was never requested is released after a while.
+    let later = now + Duration::from_secs(120);
+    let (_, offered, _) = reply_fields(&server.handle_at(&other, later).unwrap());
+    assert_eq!(offered, first);
+}
+
+#[test]
+fn test_dhcp_leases_expire_after_lease_secs() {
+    let mut server = DhcpServer::new("10.0.0.0/30", Ipv4Addr::new(10, 0, 0, 1), 30).unwrap();
+    let now = Instant::now();
+    let addr = Ipv4Addr::new(10, 0, 0, 2);
+    let request = parse(&client_message(1, 3, Some(addr))).unwrap();
+    let (msg_type, acked, _) = reply_fields(&server.handle_at(&request, now).unwrap());
+    assert_eq!((msg_type, acked), (5, addr));
+    let chaddr = {
+        let mut c = [0u8; 16];
+        c[..6].copy_from_slice(&[0x02, 0, 0, 0, 0, 1]);
+        c
+    };
+    assert_eq!(server.lease_at(&chaddr, now), Some(addr));
+    let later = now + Duration::from_secs(31);
+    assert_eq!(server.lease_at(&chaddr, later), None);
+    // Another client may now take the address.
+    let request2 = parse(&client_message(2, 3, Some(addr))).unwrap();
+    let (msg_type, _, _) = reply_fields(&server.handle_at(&request2, later).unwrap());
+    assert_eq!(msg_type, 5);
+}
+
+#[test]
+fn test_dhcp_gateway_must_be_inside_pool() {
+    assert!(DhcpServer::new("10.0.0.0/29", Ipv4Addr::new(10, 0, 1, 1), 600).is_err());
+}
+
+#[test]
+fn test_dhcp_ignores_non_dhcp_packets() {
+    let mut server = DhcpServer::new("10.0.0.0/29", Ipv4Addr::new(10, 0, 0, 1), 600).unwrap();
+    let data = vec![
+        0x45, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00, 10, 0, 0, 2, 10, 0,
+        1, 2, 0x00, 0x35, 0x00, 0x35,
+    ];
+    let packet = parse(&data).unwrap();
+    assert!(server.handle(&packet).is_none());
+    assert!(parse_request(&[0u8; 10]).is_none());
+}
+
+#[test]
+fn test_dhcp_config_requires_ipv4_prefix() {
+    let mut cfg: SimulatorConfig = toml::from_str(
+        r#"
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y0"
+tun_a_prefix = "10.0.0.0/24"
+
+[interfaces.real_tun_a]
+address = "10.0.0.1"
+tap = true
+
+[interfaces.real_tun_b]
+address = "10.0.1.1"
+
+[topology.routers]
+Rx0y0 = {}
+
+[dhcp]
+edge = "tun_a"
+"#,
+    )
+    .expect("config parses");
+    assert!(cfg.validate().is_ok());
+    cfg.interfaces.real_tun_a.tap = false;
+    assert!(cfg.validate().unwrap_err().contains("requires a TAP edge"));
+    cfg.interfaces.real_tun_a.tap = true;
+    cfg.interfaces.real_tun_a.address = "10.0.5.1".to_string();
+    assert!(cfg.validate().unwrap_err().contains("outside the pool"));
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.tap = true;
+    cfg.dhcp = Some(DhcpConfig {
+        edge: "tun_b".to_string(),
+        lease_secs: 60,
+    });
+    // Default tun_b prefix is empty and cannot be served.
+    assert!(cfg
+        .validate()
+        .unwrap_err()
+        .contains("IPv4 CIDR edge prefix"));
+}
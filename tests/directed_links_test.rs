@@ -0,0 +1,132 @@
+mod common;
+
+use common::rid;
+use network_simulator::build_fabric;
+use network_simulator::clab;
+use network_simulator::compute_routing_tables;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::routing::compute_multi_path_routing;
+use network_simulator::simulation::simulate_link_timed;
+use network_simulator::topology::{Fabric, LinkConfig, Router};
+
+/// Square Rx0y0 - Rx0y1 - Rx1y1 - Rx1y0 - Rx0y0 with tun_a at Rx0y0 and tun_b at Rx1y1; the
+/// Rx1y0 side costs 5 per link both ways and the Rx0y1 side is given by `near` and `far`.
+fn square(near: (&str, &str), far: (&str, &str)) -> SimulatorConfig {
+    common::scenario(
+        "",
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx1y0", ""), ("Rx1y1", "")],
+        &[
+            near,
+            far,
+            ("Rx0y0_Rx1y0", "cost = 5"),
+            ("Rx1y0_Rx1y1", "cost = 5"),
+        ],
+        "",
+    )
+}
+
+#[test]
+fn test_routes_follow_the_cost_of_their_direction() {
+    // Rx0y0 -> Rx0y1 costs 1, the way back 20: tun_a to tun_b takes the Rx0y1 side (2 against
+    // 10), tun_b to tun_a the Rx1y0 side (10 against 21).
+    let cfg = square(
+        ("Rx0y0_Rx0y1", "cost_ab = 1, cost_ba = 20"),
+        ("Rx0y1_Rx1y1", "cost = 1"),
+    );
+    let tables = compute_routing_tables(&cfg);
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.next_hop, rid("Rx0y1"));
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.total_cost, 2);
+    assert_eq!(tables[&rid("Rx1y1")].tun_a.next_hop, rid("Rx1y0"));
+    assert_eq!(tables[&rid("Rx1y1")].tun_a.total_cost, 10);
+    // Rx0y1 goes round the square rather than back over its expensive direction.
+    assert_eq!(tables[&rid("Rx0y1")].tun_a.next_hop, rid("Rx1y1"));
+    assert_eq!(tables[&rid("Rx0y1")].tun_a.total_cost, 11);
+
+    // The same link keyed the other way round: `ab` is always first name to second.
+    let reversed = square(
+        ("Rx0y1_Rx0y0", "cost_ab = 20, cost_ba = 1"),
+        ("Rx0y1_Rx1y1", "cost = 1"),
+    );
+    let again = compute_routing_tables(&reversed);
+    for router in ["Rx0y0", "Rx0y1", "Rx1y0", "Rx1y1"] {
+        let (x, y) = (&tables[&rid(router)], &again[&rid(router)]);
+        assert_eq!(
+            (&x.tun_a.next_hop, x.tun_a.total_cost, &x.tun_b.next_hop),
+            (&y.tun_a.next_hop, y.tun_a.total_cost, &y.tun_b.next_hop),
+            "{}",
+            router
+        );
+    }
+
+    // Multipath tables see the same directed costs.
+    let fabric = build_fabric(&cfg);
+    let multi = compute_multi_path_routing(&fabric, rid("Rx0y0"), rid("Rx1y1"));
+    let next_hops = |entries: &[network_simulator::routing::MultiPathEntry]| {
+        entries
+            .iter()
+            .map(|e| e.next_hop.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(next_hops(&multi[&rid("Rx0y0")].tun_a), [rid("Rx0y1")]);
+    assert_eq!(next_hops(&multi[&rid("Rx1y1")].tun_b), [rid("Rx1y0")]);
+}
+
+#[test]
+fn test_delay_defaults_the_cost_of_each_direction() {
+    let cfg = square(
+        ("Rx0y0_Rx0y1", "delay_ms = 1, delay_ms_ba = 30"),
+        ("Rx0y1_Rx1y1", "delay_ms = 1"),
+    );
+    let tables = compute_routing_tables(&cfg);
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.next_hop, rid("Rx0y1"));
+    assert_eq!(tables[&rid("Rx1y1")].tun_a.next_hop, rid("Rx1y0"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_link_delay_per_direction() {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx0y1"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    let cfg = LinkConfig {
+        delay_ms: 7,
+        delay_ms_ab: Some(40),
+        ..Default::default()
+    };
+    assert!(cfg.is_asymmetric());
+    // Given from Rx0y1 to Rx0y0, the reverse of the link's id order.
+    fabric.add_link(&rid("Rx0y1"), &rid("Rx0y0"), cfg);
+    let link = fabric.get_link(&rid("Rx0y0"), &rid("Rx0y1")).unwrap();
+    let packet = [0x45u8; 20];
+    let sent = tokio::time::Instant::now();
+    let there = simulate_link_timed(link, &rid("Rx0y1"), &packet)
+        .await
+        .unwrap();
+    assert_eq!(there.propagation_ms, 40.0);
+    assert_eq!(sent.elapsed(), std::time::Duration::from_millis(40));
+    let back = simulate_link_timed(link, &rid("Rx0y0"), &packet)
+        .await
+        .unwrap();
+    assert_eq!(back.propagation_ms, 7.0);
+}
+
+#[test]
+fn test_containerlab_export_delays_each_end() {
+    let cfg = common::line("", &["", ""], &["delay_ms = 10, delay_ms_ba = 25"], "");
+    let lab = clab::export(&cfg, "lab", "frrouting/frr:latest").output;
+    assert_eq!(
+        lab.topology.nodes["Rx0y0"].exec,
+        ["tc qdisc replace dev eth1 root netem delay 10ms"]
+    );
+    assert_eq!(
+        lab.topology.nodes["Rx0y1"].exec,
+        ["tc qdisc replace dev eth1 root netem delay 25ms"]
+    );
+}
+
+#[test]
+fn test_zero_directed_cost_rejected() {
+    let cfg = common::line("", &["", ""], &["cost_ba = 0"], "");
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("cost must be at least 1"), "{}", err);
+}
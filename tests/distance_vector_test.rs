@@ -0,0 +1,204 @@
+mod common;
+
+use common::rid;
+use network_simulator::build_fabric;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::routing::distance_vector::DistanceVector;
+use network_simulator::routing::{compute_routing, RoutingTable};
+use network_simulator::topology::{Fabric, RouterId};
+use std::collections::HashMap;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Line Rx0y0 - Rx0y1 - Rx0y2 with links of 20 ms (and cost 1) and `[distance_vector]` set to
+/// `dv`.
+fn line(top: &str, dv: &str, rest: &str) -> SimulatorConfig {
+    addressed(common::line(
+        top,
+        &["", "", ""],
+        &["delay_ms = 20, cost = 1", "delay_ms = 20, cost = 1"],
+        &format!("[distance_vector]\n{}\n{}", dv, rest),
+    ))
+}
+
+fn start(cfg: &SimulatorConfig) -> (Fabric, DistanceVector, HashMap<RouterId, RoutingTable>) {
+    cfg.validate().expect("valid");
+    let fabric = build_fabric(cfg);
+    let dv = DistanceVector::new(
+        cfg.distance_vector.as_ref().unwrap(),
+        &fabric,
+        &rid(&cfg.tun_ingress.tun_a_ingress),
+        &rid(&cfg.tun_ingress.tun_b_ingress),
+    );
+    let mut tables = HashMap::new();
+    dv.install(&mut tables);
+    (fabric, dv, tables)
+}
+
+/// Edge routes of every router as (router, next hop towards tun_a, cost, towards tun_b, cost).
+fn edge_routes(
+    tables: &HashMap<RouterId, RoutingTable>,
+) -> Vec<(String, String, u32, String, u32)> {
+    let mut routes: Vec<_> = tables
+        .iter()
+        .map(|(id, t)| {
+            (
+                id.0.clone(),
+                t.tun_a.next_hop.0.clone(),
+                t.tun_a.total_cost,
+                t.tun_b.next_hop.0.clone(),
+                t.tun_b.total_cost,
+            )
+        })
+        .collect();
+    routes.sort();
+    routes
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_routes_converge_to_shortest_paths() {
+    // Square with the way round through Rx1y0 costlier.
+    let cfg = addressed(common::scenario(
+        "",
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx1y0", ""), ("Rx1y1", "")],
+        &[
+            ("Rx0y0_Rx0y1", ""),
+            ("Rx0y1_Rx1y1", ""),
+            ("Rx0y0_Rx1y0", "cost = 5"),
+            ("Rx1y0_Rx1y1", ""),
+        ],
+        "[distance_vector]\nupdate_interval_ms = 30\n",
+    ));
+    let (mut fabric, mut dv, mut tables) = start(&cfg);
+    let spf = compute_routing(&fabric, rid("Rx0y0"), rid("Rx1y1"));
+    assert_eq!(edge_routes(&tables), edge_routes(&spf));
+    assert_eq!(dv.deadline(), None, "settled before traffic starts");
+
+    fabric
+        .set_link_up(&rid("Rx0y0"), &rid("Rx0y1"), false)
+        .unwrap();
+    dv.topology_changed(&fabric);
+    dv.install(&mut tables);
+    // Only the ends of the link know at once.
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.total_cost, u32::MAX);
+    assert_eq!(tables[&rid("Rx1y0")].tun_b.next_hop, rid("Rx1y1"));
+    while let Some(at) = dv.deadline() {
+        tokio::time::sleep_until(at).await;
+        dv.update_due();
+    }
+    dv.install(&mut tables);
+    let spf = compute_routing(&fabric, rid("Rx0y0"), rid("Rx1y1"));
+    assert_eq!(edge_routes(&tables), edge_routes(&spf));
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.total_cost, 6);
+    let episode = &dv.episodes[0];
+    assert_eq!(dv.episodes.len(), 1);
+    assert!(episode.convergence_ms.unwrap() <= 60.0, "{:?}", episode);
+}
+
+/// Fail Rx0y1_Rx0y2 and run the updates until the routes settle. Returns whether Rx0y0 and
+/// Rx0y1 ever pointed at each other, and the highest finite metric of Rx0y0 towards tun_b.
+async fn fail_far_link(split_horizon: bool) -> (bool, u32, DistanceVector) {
+    let cfg = line(
+        "",
+        &format!("update_interval_ms = 10\nsplit_horizon = {}", split_horizon),
+        "",
+    );
+    let (mut fabric, mut dv, mut tables) = start(&cfg);
+    fabric
+        .set_link_up(&rid("Rx0y1"), &rid("Rx0y2"), false)
+        .unwrap();
+    dv.topology_changed(&fabric);
+    let (mut looped, mut highest) = (false, 0);
+    while let Some(at) = dv.deadline() {
+        tokio::time::sleep_until(at).await;
+        dv.update_due();
+        dv.install(&mut tables);
+        let (a, b) = (&tables[&rid("Rx0y0")].tun_b, &tables[&rid("Rx0y1")].tun_b);
+        looped |= a.next_hop == rid("Rx0y1") && b.next_hop == rid("Rx0y0");
+        if a.total_cost != u32::MAX {
+            highest = highest.max(a.total_cost);
+        }
+    }
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.total_cost, u32::MAX);
+    assert_eq!(tables[&rid("Rx0y1")].tun_b.total_cost, u32::MAX);
+    (looped, highest, dv)
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_count_to_infinity() {
+    let (looped, highest, dv) = fail_far_link(false).await;
+    assert!(looped);
+    assert!(highest >= 14, "counted up to {}", highest);
+    let slow = dv.episodes[0].convergence_ms.unwrap();
+    assert!(slow >= 50.0, "{}", slow);
+
+    // Poisoned reverse tells Rx0y0 at once that Rx0y1 has no route of its own.
+    let (looped, _, dv) = fail_far_link(true).await;
+    assert!(!looped);
+    assert!(dv.episodes[0].convergence_ms.unwrap() <= 10.0);
+}
+
+/// Run 10 packets from tun_a to tun_b, Rx0y1_Rx0y2 failing after the first one. Each one
+/// takes at least 20 ms, so the updates go on in between.
+async fn run(split_horizon: bool) -> Fabric {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..10 {
+        writeln!(
+            packets,
+            "4500001c000000004011000a0a0000020a0001020001000200080000"
+        )
+        .unwrap();
+    }
+    let path = packets.path().display().to_string();
+    let cfg = line(
+        &format!("packet_file = \"{}\"", path),
+        &format!("update_interval_ms = 10\nsplit_horizon = {}", split_horizon),
+        "[[event]]\nafter_packets = 1\naction = \"link_down\"\nlink = \"Rx0y1_Rx0y2\"\n",
+    );
+    cfg.validate().expect("valid");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    fabric
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_transient_loops_in_run() {
+    let fabric = run(false).await;
+    let dv = fabric.distance_vector.as_ref().expect("distance vector");
+    assert_eq!(dv.episodes.len(), 1);
+    assert!(dv.episodes[0].loop_drops >= 1, "{:?}", dv.episodes[0]);
+
+    let fabric = run(true).await;
+    let dv = fabric.distance_vector.as_ref().unwrap();
+    assert_eq!(dv.episodes[0].loop_drops, 0);
+}
+
+#[test]
+fn test_distance_vector_config_validation() {
+    let err = line("", "update_interval_ms = 0", "")
+        .validate()
+        .unwrap_err();
+    assert!(
+        err.contains("distance_vector.update_interval_ms"),
+        "{}",
+        err
+    );
+    let err = line("", "infinity = 1", "").validate().unwrap_err();
+    assert!(err.contains("distance_vector.infinity"), "{}", err);
+    let err = line("", "", "[link_state]\n").validate().unwrap_err();
+    assert!(err.contains("cannot be combined"), "{}", err);
+    let cfg = line("", "", "");
+    let dv = cfg.distance_vector.as_ref().unwrap();
+    assert_eq!(
+        (dv.update_interval_ms, dv.infinity, dv.split_horizon),
+        (1000.0, 16, true)
+    );
+}
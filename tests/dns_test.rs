@@ -0,0 +1,236 @@
+use network_simulator::config::{DnsConfig, SimulatorConfig};
+use network_simulator::dns::{load_zone, parse_query, DnsInterceptor, PendingReplies, DNS_PORT};
+use network_simulator::packet::parse;
+use network_simulator::routing::Destination;
+use network_simulator::topology::RouterId;
+use std::io::Write;
+use std::net::IpAddr;
+use tempfile::NamedTempFile;
+
+const ZONE: &str = "\
+# test zone
+www.example.com A 192.0.2.10
+www.example.com. 300 IN AAAA 2001:db8::10 ; comment
+api.example.com A 192.0.2.20
+";
+
+/// Encode a DNS query message for `name` with the given record type.
+fn query_message(name: &str, qtype: u16) -> Vec<u8> {
+    let mut msg = vec![0xab, 0xcd, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in name.split('.') {
+        msg.push(label.len() as u8);
+        msg.extend_from_slice(label.as_bytes());
+    }
+    msg.push(0);
+    msg.extend_from_slice(&qtype.to_be_bytes());
+    msg.extend_from_slice(&1u16.to_be_bytes());
+    msg
+}
+
+fn udp4_query(name: &str, qtype: u16) -> Vec<u8> {
+    let dns = query_message(name, qtype);
+    let udp_len = 8 + dns.len();
+    let total_len = 20 + udp_len;
+    let mut raw = vec![0x45, 0, (total_len >> 8) as u8, total_len as u8];
+    raw.extend_from_slice(&[0, 0, 0, 0, 64, 17, 0, 0]);
+    raw.extend_from_slice(&[10, 0, 0, 2, 10, 0, 0, 53]);
+    raw.extend_from_slice(&40000u16.to_be_bytes());
+    raw.extend_from_slice(&DNS_PORT.to_be_bytes());
+    raw.extend_from_slice(&(udp_len as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0]);
+    raw.extend_from_slice(&dns);
+    raw
+}
+
+fn udp6_query(name: &str, qtype: u16) -> Vec<u8> {
+    let dns = query_message(name, qtype);
+    let udp_len = 8 + dns.len();
+    let mut raw = vec![0x60, 0, 0, 0];
+    raw.extend_from_slice(&(udp_len as u16).to_be_bytes());
+    raw.extend_from_slice(&[17, 64]);
+    let src: std::net::Ipv6Addr = "2001:db8:a::2".parse().unwrap();
+    let dst: std::net::Ipv6Addr = "2001:db8:a::53".parse().unwrap();
+    raw.extend_from_slice(&src.octets());
+    raw.extend_from_slice(&dst.octets());
+    raw.extend_from_slice(&40001u16.to_be_bytes());
+    raw.extend_from_slice(&DNS_PORT.to_be_bytes());
+    raw.extend_from_slice(&(udp_len as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0]);
+    raw.extend_from_slice(&dns);
+    raw
+}
+
+fn interceptor(delay_ms: u64) -> (DnsInterceptor, NamedTempFile) {
+    let mut zone_file = NamedTempFile::new().expect("temp zone file");
+    zone_file.write_all(ZONE.as_bytes()).unwrap();
+    let cfg = DnsConfig {
+        zone_file: zone_file.path().to_string_lossy().into_owned(),
+        delay_ms,
+        ttl: 60,
+    };
+    (
+        DnsInterceptor::from_config(&cfg).expect("interceptor"),
+        zone_file,
+    )
+}
+
+#[test]
+fn test_dns_a_query_answered() {
+    let (dns, _zone) = interceptor(20);
+    let query = parse(&udp4_query("WWW.example.com", 1)).unwrap();
+    let reply = dns.respond(&query).expect("reply");
+    assert_eq!(reply.src_ip, query.dst_ip);
+    assert_eq!(reply.dst_ip, query.src_ip);
+    assert_eq!(reply.src_port, DNS_PORT);
+    assert_eq!(reply.dst_port, 40000);
+    // The reply is a valid packet that parses back to the same addressing.
+    let reparsed = parse(&reply.raw).expect("reply parses");
+    assert_eq!(reparsed.dst_port, 40000);
+    let msg = &reply.raw[28..];
+    assert_eq!(&msg[0..2], &[0xab, 0xcd]);
+    assert_eq!(msg[3] & 0x0F, 0, "NOERROR");
+    assert_eq!(u16::from_be_bytes([msg[6], msg[7]]), 1, "one answer");
+    assert_eq!(&msg[msg.len() - 4..], &[192, 0, 2, 10]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_dns_pending_replies_wait_for_delay() {
+    let (dns, _zone) = interceptor(20);
+    let mut pending = PendingReplies::new(dns.delay_ms);
+    let reply = dns
+        .respond(&parse(&udp4_query("www.example.com", 1)).unwrap())
+        .unwrap();
+    pending.push(reply, Destination::TunA);
+    let now = tokio::time::Instant::now();
+    assert!(pending.pop_due(now).is_none());
+    assert_eq!(
+        pending.next_deadline(),
+        Some(now + std::time::Duration::from_millis(20))
+    );
+    tokio::time::advance(std::time::Duration::from_millis(20)).await;
+    let (_, destination) = pending.pop_due(tokio::time::Instant::now()).expect("due");
+    assert_eq!(destination, Destination::TunA);
+    assert!(pending.is_empty());
+}
+
+#[test]
+fn test_dns_query_behind_ipv6_extension_header() {
+    let (dns, _zone) = interceptor(0);
+    let mut raw = udp6_query("www.example.com", 28);
+    // Insert an 8-byte destination options header between IPv6 and UDP.
+    raw[6] = 60;
+    let payload_len = u16::from_be_bytes([raw[4], raw[5]]) + 8;
+    raw[4..6].copy_from_slice(&payload_len.to_be_bytes());
+    raw.splice(40..40, [17, 0, 1, 4, 0, 0, 0, 0]);
+    let query = parse(&raw).unwrap();
+    let mut query_meta = query.clone();
+    // The minimal parser does not skip destination options, so fill in the ports here.
+    query_meta.protocol = 17;
+    query_meta.dst_port = DNS_PORT;
+    query_meta.src_port = 40001;
+    let reply = dns.respond(&query_meta).expect("reply");
+    let msg = &reply.raw[48..];
+    assert_eq!(u16::from_be_bytes([msg[6], msg[7]]), 1);
+}
+
+#[test]
+fn test_dns_aaaa_query_over_ipv6_and_nxdomain() {
+    let (dns, _zone) = interceptor(0);
+    let reply = dns
+        .respond(&parse(&udp6_query("www.example.com", 28)).unwrap())
+        .expect("reply");
+    let msg = &reply.raw[48..];
+    assert_eq!(u16::from_be_bytes([msg[6], msg[7]]), 1);
+    let expected: std::net::Ipv6Addr = "2001:db8::10".parse().unwrap();
+    assert_eq!(&msg[msg.len() - 16..], &expected.octets());
+    assert_ne!(
+        &reply.raw[46..48],
+        &[0, 0],
+        "IPv6 UDP checksum is mandatory"
+    );
+
+    let reply = dns
+        .respond(&parse(&udp4_query("missing.example.com", 1)).unwrap())
+        .expect("reply");
+    let msg = &reply.raw[28..];
+    assert_eq!(msg[3] & 0x0F, 3, "NXDOMAIN");
+    assert_eq!(u16::from_be_bytes([msg[6], msg[7]]), 0);
+}
+
+#[test]
+fn test_dns_ignores_other_traffic() {
+    let (dns, _zone) = interceptor(0);
+    let mut raw = udp4_query("www.example.com", 1);
+    raw[22..24].copy_from_slice(&5353u16.to_be_bytes());
+    assert!(dns.respond(&parse(&raw).unwrap()).is_none());
+    assert!(parse_query(&[0u8; 4]).is_none());
+}
+
+#[test]
+fn test_dns_zone_parsing() {
+    let zone = load_zone(ZONE).expect("zone");
+    assert_eq!(zone["www.example.com"].len(), 2);
+    assert_eq!(
+        zone["api.example.com"],
+        vec!["192.0.2.20".parse::<IpAddr>().unwrap()]
+    );
+    assert!(load_zone("bad.example.com AAAA 192.0.2.1").is_err());
+    assert!(load_zone("bad.example.com A").is_err());
+    // Fields are taken by position, so owner names that look like types are fine.
+    let zone =
+        load_zone("a A 192.0.2.1\naaaa 60 IN AAAA 2001:db8::1\nx IN 60 A 192.0.2.2").unwrap();
+    assert_eq!(zone["a"], vec!["192.0.2.1".parse::<IpAddr>().unwrap()]);
+    assert_eq!(zone["aaaa"], vec!["2001:db8::1".parse::<IpAddr>().unwrap()]);
+    assert_eq!(zone["x"].len(), 1);
+    assert!(load_zone("x 60 IN TXT hello").is_err());
+}
+
+#[tokio::test]
+async fn test_dns_query_crosses_fabric_and_reply_comes_back() {
+    let mut zone_file = NamedTempFile::new().unwrap();
+    zone_file.write_all(ZONE.as_bytes()).unwrap();
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", hex::encode(udp4_query("www.example.com", 1))).unwrap();
+    let cfg: SimulatorConfig = toml::from_str(&format!(
+        r#"
+packet_file = "{}"
+
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+tun_a_prefix = "10.0.0.0/28"
+tun_b_prefix = "10.0.0.48/28"
+
+[topology.routers]
+Rx0y0 = {{}}
+Rx0y1 = {{}}
+
+[topology.links]
+Rx0y0_Rx0y1 = {{ delay_ms = 5 }}
+
+[dns]
+zone_file = "{}"
+delay_ms = 10
+"#,
+        packets.path().display(),
+        zone_file.path().display()
+    ))
+    .unwrap();
+    let start = std::time::Instant::now();
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    // Query and reply each cross the 5 ms link; the resolver adds 10 ms.
+    assert!(start.elapsed().as_millis() >= 20);
+    let out_path = format!("{}_out.txt", packets.path().display());
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 1, "only the reply leaves the fabric");
+    let reply = parse(&hex::decode(lines[0]).unwrap()).unwrap();
+    assert_eq!(reply.src_port, DNS_PORT);
+    assert_eq!(reply.dst_ip, "10.0.0.2".parse::<IpAddr>().unwrap());
+    // The query was delivered at the far edge (next to the start-up demonstration packet) and
+    // the reply back at the near one.
+    let stats = fabric.get_statistics();
+    assert_eq!(stats[&RouterId("Rx0y1".into())].packets_delivered, 2);
+    assert_eq!(stats[&RouterId("Rx0y0".into())].packets_delivered, 1);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use network_simulator::config::SimulatorConfig;
+use network_simulator::drops::DropReason;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::replay::read_pcap;
+use network_simulator::topology::RouterId;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// IPv4/UDP packet 10.0.0.2 -> 10.0.1.2:53 with `ttl` and identification `id`.
+fn dns_query(id: u16, ttl: u8) -> Vec<u8> {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .ttl(ttl)
+        .identification(id)
+        .udp(5000, 53)
+        .build()
+        .unwrap()
+        .raw
+        .to_vec()
+}
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+#[tokio::test]
+async fn test_last_drops_kept_per_router_with_reasons() {
+    let mut packets = NamedTempFile::new().unwrap();
+    for id in 1..=3 {
+        writeln!(packets, "{}", hex::encode(dns_query(id, 64))).unwrap();
+    }
+    writeln!(packets, "{}", hex::encode(dns_query(4, 1))).unwrap();
+    let path = packets.path().display().to_string();
+    let pcap = NamedTempFile::new().unwrap();
+    let pcap_path = pcap.path().display().to_string();
+    let acl = r#"acl = [ { action = "deny", match = "udp and dst port 53" } ]"#;
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", acl, ""],
+        &["", ""],
+        &format!("[drop_capture]\nper_router = 2\npcap = \"{}\"", pcap_path),
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    let drops = fabric.drops.as_ref().expect("drop capture");
+    assert_eq!(drops.counts[&DropReason::AclDenied], 3);
+    assert_eq!(drops.counts[&DropReason::TtlExpired], 1);
+    // The ring of Rx0y1 only holds its last two drops.
+    let acl_drops: Vec<_> = drops.recent(&RouterId("Rx0y1".into())).collect();
+    assert_eq!(acl_drops.len(), 2);
+    assert!(acl_drops.iter().all(|d| d.reason == DropReason::AclDenied));
+    assert_eq!(acl_drops[0].raw, dns_query(2, 63));
+    assert_eq!(acl_drops[1].raw, dns_query(3, 63));
+    let expired: Vec<_> = drops.recent(&RouterId("Rx0y0".into())).collect();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].raw, dns_query(4, 1));
+    // The pcap file holds the three drops kept, oldest first.
+    let written = read_pcap(&std::fs::read(&pcap_path).unwrap()).unwrap();
+    let written: Vec<Vec<u8>> = written.into_iter().map(Result::unwrap).collect();
+    assert_eq!(
+        written,
+        vec![dns_query(2, 63), dns_query(3, 63), dns_query(4, 1)]
+    );
+}
+
+#[test]
+fn test_zero_ring_size_rejected() {
+    let cfg = common::line("", &["", ""], &[""], "[drop_capture]\nper_router = 0");
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("per_router"), "{}", err);
+}
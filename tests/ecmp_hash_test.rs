@@ -0,0 +1,266 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::{EcmpHashConfig, SimulatorConfig};
+use network_simulator::forwarding::EcmpHash;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{parse, PacketMeta};
+use network_simulator::processor::process_packet_multi;
+use network_simulator::routing::{compute_multi_path_routing, Destination};
+use network_simulator::topology::{Fabric, LinkConfig, Router};
+use std::collections::HashSet;
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+fn hash(toml: &str) -> EcmpHash {
+    let cfg: EcmpHashConfig = toml::from_str(toml).expect("ecmp_hash parses");
+    EcmpHash::from_config(&cfg)
+}
+
+fn udp(src_port: u16, tos: u8, flow_label: u32) -> PacketMeta {
+    PacketBuilder::new(
+        "2001:db8::1".parse().unwrap(),
+        "2001:db8:1::1".parse().unwrap(),
+    )
+    .udp(src_port, 53)
+    .tos(tos)
+    .flow_label(flow_label)
+    .build()
+    .unwrap()
+}
+
+#[test]
+fn test_hashed_fields_follow_config() {
+    let five = EcmpHash::default();
+    assert_eq!(five, hash(""));
+    let three = hash("fields = \"3-tuple\"");
+    let dscp = hash("include_dscp = true");
+    let label = hash("include_flow_label = true");
+
+    // Ports only count for the 5-tuple.
+    assert_ne!(five.flow(&udp(1000, 0, 0)), five.flow(&udp(1001, 0, 0)));
+    assert_eq!(three.flow(&udp(1000, 0, 0)), three.flow(&udp(1001, 0, 0)));
+    assert_eq!(three.hash(&udp(1000, 0, 0)), three.hash(&udp(1001, 0, 0)));
+    // DSCP and flow label only when included.
+    assert_eq!(five.flow(&udp(1000, 0xb8, 0)), five.flow(&udp(1000, 0, 0)));
+    assert_ne!(dscp.flow(&udp(1000, 0xb8, 0)), dscp.flow(&udp(1000, 0, 0)));
+    assert_eq!(five.flow(&udp(1000, 0, 7)), five.flow(&udp(1000, 0, 8)));
+    assert_ne!(label.flow(&udp(1000, 0, 7)), label.flow(&udp(1000, 0, 8)));
+    assert_eq!(label.flow(&udp(1000, 0, 7)).flow_label, Some(7));
+}
+
+/// A fragment past the first of an IPv6 UDP datagram with `flow_label`: no UDP header, so
+/// none of the ports.
+fn later_fragment(flow_label: u32) -> PacketMeta {
+    let first = udp(1000, 0, flow_label);
+    let mut raw = first.raw[..40].to_vec();
+    raw[4..6].copy_from_slice(&24u16.to_be_bytes());
+    raw[6] = 44;
+    // Next header UDP, offset 1448 bytes, last fragment, identification 7.
+    raw.extend_from_slice(&[17, 0, 0x05, 0xa8, 0, 0, 0, 7]);
+    raw.extend_from_slice(&[0xaa; 16]);
+    parse(&raw).unwrap()
+}
+
+#[test]
+fn test_flow_label_replaces_ports() {
+    let label = hash("flow_label_replaces_ports = true");
+    assert_eq!(udp(1000, 0, 0x12345).flow_label(), Some(0x12345));
+    // With a label, the ports do not count: a flow's fragments and ESP packets hash alike.
+    assert_eq!(
+        label.hash(&udp(1000, 0, 0x12345)),
+        label.hash(&udp(1001, 0, 0x12345))
+    );
+    assert_eq!(
+        label.hash(&udp(1000, 0, 0x12345)),
+        label.hash(&later_fragment(0x12345))
+    );
+    assert_ne!(
+        label.hash(&udp(1000, 0, 0x12345)),
+        label.hash(&udp(1000, 0, 0x12346))
+    );
+    let esp = |label: u32| {
+        PacketBuilder::new(
+            "2001:db8::1".parse().unwrap(),
+            "2001:db8:1::1".parse().unwrap(),
+        )
+        .protocol(50)
+        .flow_label(label)
+        .payload(vec![0; 16])
+        .build()
+        .unwrap()
+    };
+    assert_ne!(label.hash(&esp(1)), label.hash(&esp(2)));
+    // Without a label the ports are hashed as usual; the 5-tuple splits the fragments.
+    assert_ne!(label.hash(&udp(1000, 0, 0)), label.hash(&udp(1001, 0, 0)));
+    let five = EcmpHash::default();
+    assert_ne!(
+        five.hash(&udp(1000, 0, 0x12345)),
+        five.hash(&later_fragment(0x12345))
+    );
+}
+
+#[test]
+fn test_echo_identifier_hashed_as_ports() {
+    let ping = |id: u16, seq: u16| {
+        PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+            .echo_request(id, seq)
+            .build()
+            .unwrap()
+    };
+    let five = EcmpHash::default();
+    // The pings of one session are one flow; another session may take another path.
+    assert_eq!(five.flow(&ping(7, 1)), five.flow(&ping(7, 2)));
+    assert_eq!(five.flow(&ping(7, 1)).ports, Some((7, 0)));
+    assert_ne!(five.flow(&ping(7, 1)), five.flow(&ping(8, 1)));
+    let three = hash("fields = \"3-tuple\"");
+    assert_eq!(three.flow(&ping(7, 1)), three.flow(&ping(8, 1)));
+}
+
+/// Rx0y0 reaches Rx2y0 over three equal-cost paths, through Rx1y0, Rx1y1 and Rx1y2.
+fn diamond(hash: EcmpHash) -> Fabric {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx1y0", "Rx1y1", "Rx1y2", "Rx2y0"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    let cfg = LinkConfig {
+        delay_ms: 0,
+        load_balance: true,
+        ..Default::default()
+    };
+    for middle in ["Rx1y0", "Rx1y1", "Rx1y2"] {
+        fabric.add_link(&rid("Rx0y0"), &rid(middle), cfg.clone());
+        fabric.add_link(&rid(middle), &rid("Rx2y0"), cfg.clone());
+    }
+    fabric.ecmp_hash = hash;
+    fabric
+}
+
+/// Next hops at Rx0y0 taken by UDP flows from 40 source ports.
+async fn spread(hash: EcmpHash) -> HashSet<String> {
+    let mut fabric = diamond(hash);
+    let (a, b) = (rid("Rx0y0"), rid("Rx2y0"));
+    let tables = compute_multi_path_routing(&fabric, a.clone(), b.clone());
+    let mut hops = HashSet::new();
+    for port in 2000..2040 {
+        let packet = udp(port, 0, 0);
+        process_packet_multi(
+            &mut fabric,
+            &tables,
+            a.clone(),
+            packet.clone(),
+            Destination::TunB,
+        )
+        .await;
+        let flow = fabric.ecmp_hash.flow(&packet);
+        let hop = fabric.flows.next_hop(&a, Destination::TunB, &flow);
+        hops.insert(hop.expect("flow pinned").0);
+    }
+    hops
+}
+
+#[tokio::test]
+async fn test_three_tuple_polarizes_flows_of_one_host_pair() {
+    assert_eq!(spread(EcmpHash::default()).await.len(), 3);
+    assert_eq!(spread(hash("fields = \"3-tuple\"")).await.len(), 1);
+}
+
+/// (first, second) ECMP choices of 40 UDP flows across two consecutive two-way ECMP stages:
+/// Rx0y0 to Rx1y0 or Rx1y1, each of which reaches Rx3y0 through Rx2y0 or Rx2y1.
+async fn stages(hash: EcmpHash) -> HashSet<(String, String)> {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx1y0", "Rx1y1", "Rx2y0", "Rx2y1", "Rx3y0"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    let cfg = LinkConfig {
+        delay_ms: 0,
+        load_balance: true,
+        ..Default::default()
+    };
+    for first in ["Rx1y0", "Rx1y1"] {
+        fabric.add_link(&rid("Rx0y0"), &rid(first), cfg.clone());
+        for second in ["Rx2y0", "Rx2y1"] {
+            fabric.add_link(&rid(first), &rid(second), cfg.clone());
+        }
+    }
+    for second in ["Rx2y0", "Rx2y1"] {
+        fabric.add_link(&rid(second), &rid("Rx3y0"), cfg.clone());
+    }
+    fabric.ecmp_hash = hash;
+    let (a, b) = (rid("Rx0y0"), rid("Rx3y0"));
+    let tables = compute_multi_path_routing(&fabric, a.clone(), b.clone());
+    let mut paths = HashSet::new();
+    for port in 2000..2040 {
+        let packet = udp(port, 0, 0);
+        process_packet_multi(
+            &mut fabric,
+            &tables,
+            a.clone(),
+            packet.clone(),
+            Destination::TunA,
+        )
+        .await;
+        let flow = fabric.ecmp_hash.flow(&packet);
+        let first = fabric.flows.next_hop(&a, Destination::TunA, &flow).unwrap();
+        let second = fabric
+            .flows
+            .next_hop(&first, Destination::TunA, &flow)
+            .unwrap();
+        paths.insert((first.0, second.0));
+    }
+    paths
+}
+
+#[tokio::test]
+async fn test_shared_seed_polarizes_consecutive_stages() {
+    // Seeds derived from the router IDs: every first-stage router uses both of its links.
+    assert_eq!(stages(EcmpHash::default()).await.len(), 4);
+    // One seed everywhere: the flows a router receives all hashed to the same index upstream,
+    // so they all take the same link again.
+    assert_eq!(stages(hash("seed = 7")).await.len(), 2);
+    // Seeding the first-stage routers differently spreads their flows again.
+    let mut reseeded = hash("seed = 7");
+    reseeded.router_seeds.insert(rid("Rx1y0"), 8);
+    reseeded.router_seeds.insert(rid("Rx1y1"), 8);
+    assert_eq!(stages(reseeded).await.len(), 4);
+}
+
+#[test]
+fn test_router_seed_config() {
+    let cfg = addressed(common::line(
+        "",
+        &["ecmp_seed = 42", ""],
+        &[""],
+        "[ecmp_hash]\nseed = 7\nconsistent = true\n",
+    ));
+    assert!(cfg.validate().is_ok());
+    let fabric = network_simulator::build_fabric(&cfg);
+    assert!(fabric.ecmp_hash.consistent);
+    assert_eq!(fabric.ecmp_hash.seed(&rid("Rx0y0")), 42);
+    assert_eq!(fabric.ecmp_hash.seed(&rid("Rx0y1")), 7);
+    let hash = EcmpHash::default();
+    assert_ne!(hash.seed(&rid("Rx0y0")), hash.seed(&rid("Rx0y1")));
+    assert_eq!(hash.seed(&rid("Rx0y0")), hash.seed(&rid("Rx0y0")));
+}
+
+#[test]
+fn test_unknown_hash_fields_rejected() {
+    let cfg = common::line("", &["", ""], &[""], "[ecmp_hash]\nfields = \"4-tuple\"\n");
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("ecmp_hash.fields"), "{}", err);
+    let cfg = common::line(
+        "",
+        &["", ""],
+        &[""],
+        "[ecmp_hash]\nfields = \"3-tuple\"\ninclude_dscp = true\n",
+    );
+    let fabric = network_simulator::build_fabric(&addressed(cfg));
+    assert!(!fabric.ecmp_hash.ports && fabric.ecmp_hash.dscp);
+}
@@ -0,0 +1,198 @@
+use network_simulator::forwarding::flow_table::FlowTable;
+use network_simulator::forwarding::rendezvous_index;
+use network_simulator::packet::PacketMeta;
+use network_simulator::processor::process_packet_multi;
+use network_simulator::routing::{compute_multi_path_routing, Destination};
+use network_simulator::topology::{Fabric, LinkConfig, Router, RouterId};
+
+fn rid(name: &str) -> RouterId {
+    RouterId(name.to_string())
+}
+
+/// Rx0y0 reaches Rx2y0 over three equal-cost paths, through Rx1y0, Rx1y1 and Rx1y2.
+fn diamond() -> Fabric {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx1y0", "Rx1y1", "Rx1y2", "Rx2y0"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    let cfg = LinkConfig {
+        delay_ms: 0,
+        load_balance: true,
+        ..Default::default()
+    };
+    for middle in ["Rx1y0", "Rx1y1", "Rx1y2"] {
+        fabric.add_link(&rid("Rx0y0"), &rid(middle), cfg.clone());
+        fabric.add_link(&rid(middle), &rid("Rx2y0"), cfg.clone());
+    }
+    fabric
+}
+
+fn flow(port: u16) -> PacketMeta {
+    PacketMeta {
+        src_ip: "10.0.0.2".parse().unwrap(),
+        dst_ip: "10.0.1.2".parse().unwrap(),
+        src_port: port,
+        dst_port: 80,
+        protocol: 6,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_only_flows_of_removed_path_are_remapped() {
+    let mut fabric = diamond();
+    let (a, b) = (rid("Rx0y0"), rid("Rx2y0"));
+    let ports: Vec<u16> = (1000..1060).collect();
+    let tables = compute_multi_path_routing(&fabric, a.clone(), b.clone());
+    let mut before = Vec::new();
+    for &port in &ports {
+        process_packet_multi(
+            &mut fabric,
+            &tables,
+            a.clone(),
+            flow(port),
+            Destination::TunB,
+        )
+        .await;
+        before.push(
+            fabric
+                .flows
+                .next_hop(&a, Destination::TunB, &fabric.ecmp_hash.flow(&flow(port)))
+                .expect("flow pinned"),
+        );
+    }
+    let drained = before.iter().filter(|h| **h == rid("Rx1y2")).count();
+    assert!(drained > 0 && drained < ports.len());
+
+    // Draining Rx1y2 takes it out of the equal-cost set at Rx0y0.
+    fabric.set_maintenance(&rid("Rx1y2"), true).unwrap();
+    let tables = compute_multi_path_routing(&fabric, a.clone(), b.clone());
+    assert_eq!(fabric.flows.revalidate(&tables), drained);
+    for (&port, old) in ports.iter().zip(&before) {
+        process_packet_multi(
+            &mut fabric,
+            &tables,
+            a.clone(),
+            flow(port),
+            Destination::TunB,
+        )
+        .await;
+        let new = fabric
+            .flows
+            .next_hop(&a, Destination::TunB, &fabric.ecmp_hash.flow(&flow(port)))
+            .unwrap();
+        if *old == rid("Rx1y2") {
+            assert_ne!(new, rid("Rx1y2"));
+        } else {
+            assert_eq!(new, *old, "flow {} moved off a valid path", port);
+        }
+    }
+    assert_eq!(fabric.flows.remapped(), drained as u64);
+}
+
+#[test]
+fn test_rendezvous_index_moves_only_flows_of_changed_hops() {
+    let hops: Vec<RouterId> = ["Rx1y0", "Rx1y1", "Rx1y2", "Rx1y3"]
+        .iter()
+        .map(|h| rid(h))
+        .collect();
+    let pick = |hash: u64, hops: &[RouterId]| {
+        hops[rendezvous_index(hash, hops, &vec![1; hops.len()])].clone()
+    };
+    let hashes: Vec<u64> = (0..4000u64)
+        .map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+        .collect();
+    let before: Vec<RouterId> = hashes.iter().map(|&h| pick(h, &hops)).collect();
+    for hop in &hops {
+        let share = before.iter().filter(|h| *h == hop).count();
+        assert!((800..1200).contains(&share), "{}: {}", hop.0, share);
+    }
+    // The order of the candidates does not matter.
+    let reversed: Vec<RouterId> = hops.iter().rev().cloned().collect();
+    assert!(hashes
+        .iter()
+        .zip(&before)
+        .all(|(&h, hop)| pick(h, &reversed) == *hop));
+    // Without Rx1y1 only its flows move, and they spread over the others.
+    let without: Vec<RouterId> = hops.iter().filter(|h| h.0 != "Rx1y1").cloned().collect();
+    for (&h, old) in hashes.iter().zip(&before) {
+        let new = pick(h, &without);
+        if old.0 == "Rx1y1" {
+            assert_ne!(new.0, "Rx1y1");
+        } else {
+            assert_eq!(new, *old);
+        }
+    }
+    // A new hop takes its share from all the others, and nothing else moves.
+    let mut with = hops.clone();
+    with.push(rid("Rx1y4"));
+    let moved = hashes
+        .iter()
+        .zip(&before)
+        .filter(|(&h, old)| {
+            let new = pick(h, &with);
+            assert!(new == **old || new.0 == "Rx1y4");
+            new != **old
+        })
+        .count();
+    assert!((600..1000).contains(&moved), "{}", moved);
+    // Weights set the shares.
+    let heavy = hashes
+        .iter()
+        .filter(|&&h| rendezvous_index(h, &hops[..2], &[3, 1]) == 0)
+        .count();
+    assert!((2800..3200).contains(&heavy), "{}", heavy);
+}
+
+#[tokio::test]
+async fn test_consistent_hashing_keeps_unpinned_flows() {
+    let mut fabric = diamond();
+    fabric.ecmp_hash.consistent = true;
+    let (a, b) = (rid("Rx0y0"), rid("Rx2y0"));
+    let ports: Vec<u16> = (1000..1060).collect();
+    let tables = compute_multi_path_routing(&fabric, a.clone(), b.clone());
+    let mut before = Vec::new();
+    for &port in &ports {
+        process_packet_multi(
+            &mut fabric,
+            &tables,
+            a.clone(),
+            flow(port),
+            Destination::TunB,
+        )
+        .await;
+        before.push(
+            fabric
+                .flows
+                .next_hop(&a, Destination::TunB, &fabric.ecmp_hash.flow(&flow(port)))
+                .expect("flow pinned"),
+        );
+    }
+
+    // Even with the pins forgotten, draining Rx1y2 moves only its flows, and undraining it
+    // brings back exactly those.
+    for drained in [true, false] {
+        fabric.set_maintenance(&rid("Rx1y2"), drained).unwrap();
+        let tables = compute_multi_path_routing(&fabric, a.clone(), b.clone());
+        fabric.flows = FlowTable::default();
+        for (&port, old) in ports.iter().zip(&before) {
+            process_packet_multi(
+                &mut fabric,
+                &tables,
+                a.clone(),
+                flow(port),
+                Destination::TunB,
+            )
+            .await;
+            let new = fabric
+                .flows
+                .next_hop(&a, Destination::TunB, &fabric.ecmp_hash.flow(&flow(port)))
+                .unwrap();
+            if drained && *old == rid("Rx1y2") {
+                assert_ne!(new, rid("Rx1y2"));
+            } else {
+                assert_eq!(new, *old, "flow {} moved off a valid path", port);
+            }
+        }
+    }
+}
@@ -26,6 +26,7 @@ async fn test_end_to_end_packet_processing() {
             jitter_ms: 0,
             loss_percent: 0.0,
             load_balance: false,
+            ..Default::default()
         },
     );
     // Build fabric
@@ -51,7 +52,13 @@ async fn test_end_to_end_packet_processing() {
         dst_port: 80,
         protocol: 6,
         ttl: 64,
-        raw: raw_clone,
+        dscp: 0,
+        ecn: 0,
+        transport: None,
+        annotation: None,
+        mpls: Vec::new(),
+        vlan: None,
+        raw: raw_clone.into(),
     });
     // Process packet from tun A (ingress Rx0y0) towards TunB
     let processed = process_packet(
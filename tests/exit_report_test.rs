@@ -0,0 +1,288 @@
+mod common;
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use network_simulator::alarms::Severity;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::report::{ExitReport, EXIT_CHECKS_FAILED, EXIT_ERROR};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+const ALARMS: &str = r#"
+[alarms]
+window_secs = 10
+
+[[alarms.rule]]
+metric = "loss_percent"
+threshold = 50
+severity = "major"
+"#;
+
+/// A packet file of `count` UDP packets from tun_a to tun_b.
+fn packets(count: usize) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    for _ in 0..count {
+        writeln!(file, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    file
+}
+
+#[tokio::test]
+async fn test_report_counts_packets_and_alarms() {
+    let file = packets(4);
+    let path = file.path().display().to_string();
+    let run = |loss: &str| {
+        network_simulator::run(common::line(
+            &format!("packet_file = \"{}\"", path),
+            &["", "", ""],
+            &["", loss],
+            ALARMS,
+        ))
+    };
+
+    let fabric = run("").await.expect("run");
+    let report = ExitReport::of(&fabric, Some(Severity::Warning));
+    assert!(report.passed, "{:?}", report.failures);
+    assert_eq!(report.exit_code, 0);
+    // The packets of the file only, not the one `run` sends through the fabric at the start.
+    assert_eq!((report.packets.injected, report.packets.delivered), (4, 4));
+    assert_eq!(report.alarms.as_ref().expect("alarms").active, 0);
+
+    // The alarm of the lossy link fails the run only at or above the severity asked for.
+    let fabric = run("loss_percent = 100").await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    let report = ExitReport::of(&fabric, Some(Severity::Major));
+    assert!(!report.passed);
+    assert_eq!(report.exit_code, EXIT_CHECKS_FAILED);
+    assert_eq!(report.failures.len(), 1);
+    assert!(report.failures[0].contains("major raised loss_percent on Rx0y1_Rx0y2"));
+    assert_eq!(report.packets.injected, 4);
+    assert_eq!(report.packets.delivered, 0);
+    assert_eq!(report.packets.lost, 4);
+    assert_eq!(report.packets.dropped, 0);
+    let alarms = report.alarms.as_ref().expect("alarms");
+    assert_eq!(alarms.raised["major"], 1);
+    assert_eq!(alarms.events.len(), 1);
+    assert!(ExitReport::of(&fabric, Some(Severity::Critical)).passed);
+    assert!(ExitReport::of(&fabric, None).passed);
+
+    let report = ExitReport::error("no route", None);
+    assert_eq!((report.passed, report.exit_code), (false, EXIT_ERROR));
+}
+
+#[test]
+fn test_exit_report_flag_sets_exit_code() {
+    let file = packets(2);
+    let dir = tempfile::tempdir().unwrap();
+    let report_path = dir.path().join("report.json");
+    let config = |fail_on: &str| {
+        format!(
+            r#"
+packet_file = "{}"
+
+[interfaces.real_tun_a]
+address = "10.0.0.1"
+netmask = "255.255.255.0"
+
+[interfaces.real_tun_b]
+address = "10.0.1.1"
+netmask = "255.255.255.0"
+
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+tun_a_prefix = "10.0.0.0/24"
+tun_b_prefix = "10.0.1.0/24"
+
+[topology.routers]
+Rx0y0 = {{}}
+Rx0y1 = {{}}
+
+[topology.links]
+Rx0y0_Rx0y1 = {{ loss_percent = 100 }}
+
+[exit_report]
+path = "unused.json"
+{}
+{}"#,
+            file.path().display(),
+            fail_on,
+            ALARMS
+        )
+    };
+    let mut cfg = NamedTempFile::new().unwrap();
+    cfg.write_all(config("fail_on_alarm = \"major\"").as_bytes())
+        .unwrap();
+    cargo_bin_cmd!("network-simulator")
+        .arg("--config")
+        .arg(cfg.path())
+        .arg("--exit-report")
+        .arg(&report_path)
+        .assert()
+        .code(EXIT_CHECKS_FAILED);
+    let _ = std::fs::remove_file(format!("{}_out.txt", file.path().display()));
+    let report: ExitReport =
+        serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+    assert!(!report.passed);
+    assert_eq!(report.exit_code, EXIT_CHECKS_FAILED);
+    assert_eq!((report.packets.injected, report.packets.lost), (2, 2));
+    assert!(report.run.is_some());
+
+    // Without fail_on_alarm the same run passes, and says so.
+    let mut cfg = NamedTempFile::new().unwrap();
+    cfg.write_all(config("").as_bytes()).unwrap();
+    cargo_bin_cmd!("network-simulator")
+        .arg("--config")
+        .arg(cfg.path())
+        .arg("--exit-report")
+        .arg(&report_path)
+        .assert()
+        .success();
+    let _ = std::fs::remove_file(format!("{}_out.txt", file.path().display()));
+    let report: ExitReport =
+        serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+    assert!(report.passed);
+    assert_eq!(report.alarms.expect("alarms").raised["major"], 1);
+}
+
+#[test]
+fn test_invalid_exit_report_config_rejected() {
+    for (section, expected) in [
+        ("path = \"\"", "exit_report.path"),
+        (
+            "path = \"report.json\"\nfail_on_alarm = \"fatal\"",
+            "alarm severity",
+        ),
+    ] {
+        let cfg = addressed(common::line(
+            "",
+            &["", ""],
+            &[""],
+            &format!("[exit_report]\n{}", section),
+        ));
+        let err = cfg.validate().unwrap_err();
+        assert!(err.contains(expected), "{}: {}", expected, err);
+    }
+}
+
+#[test]
+fn test_multi_writes_exit_reports() {
+    let (one, two) = (packets(2), packets(3));
+    let dir = tempfile::tempdir().unwrap();
+    let path = |name: &str| dir.path().join(name).display().to_string();
+    let section = |name: &str, file: &NamedTempFile, link: &str| {
+        format!(
+            r#"
+[fabrics.{name}]
+packet_file = "{file}"
+
+[fabrics.{name}.interfaces.real_tun_a]
+address = "10.0.0.1"
+
+[fabrics.{name}.interfaces.real_tun_b]
+address = "10.0.1.1"
+
+[fabrics.{name}.tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+
+[fabrics.{name}.topology.routers]
+Rx0y0 = {{}}
+Rx0y1 = {{}}
+
+[fabrics.{name}.topology.links]
+Rx0y0_Rx0y1 = {{ {link} }}
+
+[fabrics.{name}.exit_report]
+path = "{report}"
+fail_on_alarm = "major"
+
+[fabrics.{name}.alarms]
+window_secs = 10
+
+[[fabrics.{name}.alarms.rule]]
+metric = "loss_percent"
+threshold = 50
+severity = "major"
+"#,
+            file = file.path().display(),
+            report = path(&format!("{}.json", name)),
+        )
+    };
+    let mut scenario = NamedTempFile::new().unwrap();
+    write!(
+        scenario,
+        "{}{}",
+        section("one", &one, ""),
+        section("two", &two, "loss_percent = 100")
+    )
+    .unwrap();
+    cargo_bin_cmd!("network-simulator")
+        .arg("--config")
+        .arg(scenario.path())
+        .arg("--exit-report")
+        .arg(path("all.json"))
+        .arg("multi")
+        .assert()
+        .code(EXIT_CHECKS_FAILED);
+    for file in [&one, &two] {
+        let _ = std::fs::remove_file(format!("{}_out.txt", file.path().display()));
+    }
+    let load = |name: &str| -> ExitReport {
+        serde_json::from_str(&std::fs::read_to_string(path(name)).unwrap()).unwrap()
+    };
+    let (first, second) = (load("one.json"), load("two.json"));
+    assert!(first.passed);
+    assert_eq!((first.packets.injected, first.packets.delivered), (2, 2));
+    assert!(!second.passed);
+    assert_eq!((second.packets.injected, second.packets.lost), (3, 3));
+
+    let all = load("all.json");
+    assert_eq!((all.passed, all.exit_code), (false, EXIT_CHECKS_FAILED));
+    assert_eq!(all.failures.len(), 1);
+    assert!(
+        all.failures[0].starts_with("Fabric 'two': Alarm "),
+        "{:?}",
+        all.failures
+    );
+    assert_eq!((all.packets.injected, all.packets.delivered), (5, 2));
+    assert_eq!(all.fabrics["one"], first);
+    assert_eq!(all.fabrics["two"], second);
+}
+
+#[test]
+fn test_config_errors_write_exit_report() {
+    let dir = tempfile::tempdir().unwrap();
+    let report_path = dir.path().join("report.json");
+    let run = |config: &str| -> ExitReport {
+        let mut cfg = NamedTempFile::new().unwrap();
+        cfg.write_all(config.as_bytes()).unwrap();
+        cargo_bin_cmd!("network-simulator")
+            .arg("--config")
+            .arg(cfg.path())
+            .arg("--exit-report")
+            .arg(&report_path)
+            .assert()
+            .code(EXIT_ERROR);
+        serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap()
+    };
+
+    let report = run("packet_file = [");
+    assert_eq!((report.passed, report.exit_code), (false, EXIT_ERROR));
+    assert_eq!(report.failures.len(), 1);
+
+    let report = run("[tun_ingress]\ntun_a_ingress = \"Rx0y0\"\n");
+    assert_eq!((report.passed, report.exit_code), (false, EXIT_ERROR));
+    assert_eq!(
+        report.failures,
+        ["Topology must define at least one router"]
+    );
+}
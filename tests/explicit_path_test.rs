@@ -0,0 +1,151 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::PacketMeta;
+use network_simulator::processor::{process_packet_along, process_packet_to_edge};
+use network_simulator::routing::Destination;
+use network_simulator::{build_fabric, compute_routing_tables};
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Square with tun_a at Rx0y0 and tun_b at Rx1y1, routed through Rx0y1; `rest` follows the
+/// tables.
+fn square(rest: &str) -> SimulatorConfig {
+    addressed(common::scenario(
+        "",
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx1y0", ""), ("Rx1y1", "")],
+        &[
+            ("Rx0y0_Rx0y1", ""),
+            ("Rx0y1_Rx1y1", ""),
+            ("Rx0y0_Rx1y0", ""),
+            ("Rx1y0_Rx1y1", ""),
+        ],
+        rest,
+    ))
+}
+
+fn udp(dst_port: u16) -> PacketMeta {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, dst_port)
+        .build()
+        .unwrap()
+}
+
+/// Routers `out` crossed, the first included.
+fn path(out: &PacketMeta) -> Vec<String> {
+    let annotation = out.annotation.as_ref().expect("annotation");
+    annotation.routers.iter().map(|r| r.0.clone()).collect()
+}
+
+#[tokio::test]
+async fn test_matching_packets_follow_the_path() {
+    let cfg = square(
+        "[[explicit_path]]\nmatch = \"udp and dst port 4000\"\nhops = [\"Rx0y0\", \"Rx1y0\", \"Rx1y1\"]\n",
+    );
+    cfg.validate().expect("valid");
+    let mut fabric = build_fabric(&cfg);
+    let tables = compute_routing_tables(&cfg);
+    let (out, edge) = process_packet_to_edge(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp(4000),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(path(&out), ["Rx0y0", "Rx1y0", "Rx1y1"]);
+    assert_eq!(edge, Some(Destination::TunB));
+    // Other packets, and packets entering elsewhere, follow the routing tables.
+    let (out, _) = process_packet_to_edge(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp(5000),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(path(&out), ["Rx0y0", "Rx0y1", "Rx1y1"]);
+    let (out, _) = process_packet_to_edge(
+        &mut fabric,
+        &tables,
+        rid("Rx0y1"),
+        udp(4000),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(path(&out), ["Rx0y1", "Rx1y1"]);
+    assert_eq!(fabric.explicit_paths[0].hits, 1);
+}
+
+#[tokio::test]
+async fn test_packet_along_given_hops() {
+    let cfg = square("");
+    let mut fabric = build_fabric(&cfg);
+    let tables = compute_routing_tables(&cfg);
+    let hops = |names: &[&str]| names.iter().map(|n| rid(n)).collect::<Vec<_>>();
+
+    // A detour back through the ingress router.
+    let detour = hops(&["Rx0y0", "Rx0y1", "Rx0y0", "Rx1y0", "Rx1y1"]);
+    let (out, edge) =
+        process_packet_along(&mut fabric, &tables, &detour, udp(5000), Destination::TunB)
+            .await
+            .expect("path");
+    assert_eq!(path(&out), ["Rx0y0", "Rx0y1", "Rx0y0", "Rx1y0", "Rx1y1"]);
+    assert_eq!((edge, out.ttl), (Some(Destination::TunB), 60));
+    assert!(fabric.explicit_paths.is_empty());
+
+    // The packet leaves the fabric at the last hop, wherever that is.
+    let short = hops(&["Rx0y0", "Rx1y0"]);
+    let (out, edge) =
+        process_packet_along(&mut fabric, &tables, &short, udp(5000), Destination::TunB)
+            .await
+            .unwrap();
+    assert_eq!((path(&out).len(), edge), (2, Some(Destination::TunB)));
+    let delivered = fabric
+        .get_router(&rid("Rx1y0"))
+        .unwrap()
+        .stats
+        .packets_delivered;
+    assert_eq!(delivered, 1);
+
+    // Hops without a link between them drop the packet.
+    let broken = hops(&["Rx0y0", "Rx1y1"]);
+    let (_, edge) =
+        process_packet_along(&mut fabric, &tables, &broken, udp(5000), Destination::TunB)
+            .await
+            .unwrap();
+    assert_eq!(edge, None);
+    assert!(
+        process_packet_along(&mut fabric, &tables, &[], udp(5000), Destination::TunB)
+            .await
+            .is_err()
+    );
+}
+
+#[test]
+fn test_explicit_path_validation() {
+    let err = square("[[explicit_path]]\nhops = [\"Rx0y0\", \"Rx9y9\"]\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("router 'Rx9y9'"), "{}", err);
+    let err = square("[[explicit_path]]\nhops = [\"Rx0y0\", \"Rx1y1\"]\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("no link between Rx0y0 and Rx1y1"), "{}", err);
+    let err = square("[[explicit_path]]\nhops = []\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("no hops"), "{}", err);
+    let err = square("[[explicit_path]]\nmatch = \"bogus\"\nhops = [\"Rx0y0\"]\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("capture filter"), "{}", err);
+}
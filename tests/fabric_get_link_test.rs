@@ -17,6 +17,7 @@ fn test_fabric_get_link() {
         jitter_ms: 0,
         loss_percent: 0.0,
         load_balance: false,
+        ..Default::default()
     };
     fabric.add_link(&a_id, &b_id, cfg);
     let link_opt = fabric.get_link(&a_id, &b_id);
@@ -17,6 +17,7 @@ fn test_fabric_incident_links_with_links() {
         jitter_ms: 0,
         loss_percent: 0.0,
         load_balance: false,
+        ..Default::default()
     };
     fabric.add_link(&router_a_id, &router_b_id, link_cfg);
     // Verify incident_links returns the link for each router
@@ -0,0 +1,153 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::PacketMeta;
+use network_simulator::processor::process_packet_to_edge;
+use network_simulator::routing::{compute_routing, Destination};
+use network_simulator::{build_fabric, compute_routing_tables};
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Line Rx0y0 - Rx0y1 - Rx0y2 - Rx0y3 with the `[[route]]` tables `routes`.
+fn line(routes: &[(&str, &str, &str)]) -> SimulatorConfig {
+    let rest: String = routes
+        .iter()
+        .map(|(prefix, router, edge)| {
+            format!(
+                "[[route]]\nprefix = \"{}\"\nrouter = \"{}\"\nedge = \"{}\"\n",
+                prefix, router, edge
+            )
+        })
+        .collect();
+    addressed(common::line("", &["", "", "", ""], &["", "", ""], &rest))
+}
+
+#[test]
+fn test_longest_prefix_wins() {
+    let cfg = line(&[
+        ("198.51.0.0/16", "Rx0y3", "tun_b"),
+        ("198.51.100.0/24", "Rx0y1", "tun_a"),
+        ("2001:db8:5::/48", "Rx0y2", "tun_b"),
+    ]);
+    cfg.validate().expect("valid");
+    let tables = compute_routing_tables(&cfg);
+    let fib = &tables[&rid("Rx0y0")].fib;
+    assert_eq!(fib.len(), 3);
+    let route = fib.lookup(&"198.51.100.7".parse().unwrap()).unwrap();
+    assert_eq!(route.prefix.to_string(), "198.51.100.0/24");
+    assert_eq!(
+        (&route.next_hop, &route.exit, route.edge),
+        (&rid("Rx0y1"), &rid("Rx0y1"), Destination::TunA)
+    );
+    let route = fib.lookup(&"198.51.7.1".parse().unwrap()).unwrap();
+    assert_eq!((&route.exit, route.total_cost), (&rid("Rx0y3"), 3));
+    let route = fib.lookup(&"2001:db8:5::1".parse().unwrap()).unwrap();
+    assert_eq!(route.exit, rid("Rx0y2"));
+    assert_eq!(fib.lookup(&"10.0.1.2".parse().unwrap()), None);
+    // At the attachment the router is its own next hop.
+    let own = tables[&rid("Rx0y1")]
+        .fib
+        .lookup(&"198.51.100.7".parse().unwrap());
+    assert_eq!(own.map(|r| &r.next_hop), Some(&rid("Rx0y1")));
+    // Without routes the tables only have the edge routes.
+    let plain = compute_routing_tables(&line(&[]));
+    assert!(plain.values().all(|t| t.fib.is_empty()));
+}
+
+#[test]
+fn test_nearest_attachment_wins() {
+    let cfg = line(&[
+        ("192.0.2.0/24", "Rx0y0", "tun_a"),
+        ("192.0.2.0/24", "Rx0y3", "tun_b"),
+    ]);
+    let tables = compute_routing_tables(&cfg);
+    let exit = |router: &str| {
+        let route = tables[&rid(router)]
+            .fib
+            .lookup(&"192.0.2.1".parse().unwrap());
+        route.map(|r| r.exit.0.clone()).unwrap()
+    };
+    assert_eq!(exit("Rx0y1"), "Rx0y0");
+    assert_eq!(exit("Rx0y2"), "Rx0y3");
+
+    // A prefix behind a failed link is only reached through the other attachment.
+    let mut fabric = build_fabric(&cfg);
+    fabric
+        .set_link_up(&rid("Rx0y2"), &rid("Rx0y3"), false)
+        .unwrap();
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx0y3"));
+    let route = tables[&rid("Rx0y2")]
+        .fib
+        .lookup(&"192.0.2.1".parse().unwrap());
+    assert_eq!(
+        route.map(|r| (&r.exit, &r.next_hop)),
+        Some((&rid("Rx0y0"), &rid("Rx0y1")))
+    );
+}
+
+fn udp(dst: &str) -> PacketMeta {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), dst.parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_packets_leave_at_their_prefix() {
+    let cfg = line(&[("198.51.100.0/24", "Rx0y1", "tun_a")]);
+    let mut fabric = build_fabric(&cfg);
+    let tables = compute_routing_tables(&cfg);
+    let (out, edge) = process_packet_to_edge(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp("198.51.100.7"),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(edge, Some(Destination::TunA));
+    assert_eq!(out.ttl, 63, "one hop to the attachment");
+    let delivered = |fabric: &network_simulator::topology::Fabric, r: &str| {
+        fabric.get_router(&rid(r)).unwrap().stats.packets_delivered
+    };
+    assert_eq!(delivered(&fabric, "Rx0y1"), 1);
+
+    // Other destinations still follow the edge routes.
+    let (out, edge) = process_packet_to_edge(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp("10.0.1.2"),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!((edge, out.ttl), (Some(Destination::TunB), 61));
+    assert_eq!(delivered(&fabric, "Rx0y3"), 1);
+}
+
+#[test]
+fn test_route_config_validation() {
+    let err = line(&[("198.51.100.0/24", "Rx9y9", "tun_a")])
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("Route router 'Rx9y9'"), "{}", err);
+    let err = line(&[("198.51.100.0/33", "Rx0y1", "tun_a")])
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("Invalid route prefix"), "{}", err);
+    let err = line(&[("198.51.100.0/24", "Rx0y1", "tun_c")])
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("Invalid route edge 'tun_c'"), "{}", err);
+    let mut cfg = line(&[("198.51.100.0/24", "Rx0y1", "tun_a")]);
+    cfg.enable_multipath = true;
+    assert!(cfg.validate().unwrap_err().contains("enable_multipath"));
+}
@@ -0,0 +1,94 @@
+use network_simulator::simulation::{fragment_key, simulate_link};
+use network_simulator::topology::{Link, LinkConfig, LinkId, RouterId};
+
+/// IPv4 fragment `index` of `count` of datagram `id` (8 payload bytes each).
+fn ipv4_fragment(id: u16, index: u16, count: u16) -> Vec<u8> {
+    let mut p = vec![
+        0x45, 0, 0, 28, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 2, 10, 0, 1, 2,
+    ];
+    p[4..6].copy_from_slice(&id.to_be_bytes());
+    let more = if index + 1 < count { 0x2000 } else { 0 };
+    p[6..8].copy_from_slice(&(more | index).to_be_bytes());
+    p.extend_from_slice(&[0u8; 8]);
+    p
+}
+
+fn ipv6_fragment(id: u32, more: bool) -> Vec<u8> {
+    let mut p = vec![0x60, 0, 0, 0, 0, 16, 44, 64];
+    p.extend_from_slice(&[0xfd, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    p.extend_from_slice(&[0xfd, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2]);
+    p.extend_from_slice(&[17, 0, 0, more as u8]);
+    p.extend_from_slice(&id.to_be_bytes());
+    p.extend_from_slice(&[0u8; 8]);
+    p
+}
+
+fn link(loss_percent: f32, fragment_train_loss_percent: Option<f32>) -> Link {
+    Link::new(
+        LinkId::new(RouterId("Rx0y0".into()), RouterId("Rx0y1".into())),
+        LinkConfig {
+            loss_percent,
+            fragment_train_loss_percent,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn test_fragment_key_detection() {
+    let (first_key, first_last) = fragment_key(&ipv4_fragment(7, 0, 3)).unwrap();
+    let (last_key, last_last) = fragment_key(&ipv4_fragment(7, 2, 3)).unwrap();
+    assert_eq!(first_key, last_key);
+    assert!(!first_last && last_last);
+    assert_ne!(fragment_key(&ipv4_fragment(8, 1, 3)).unwrap().0, first_key);
+    // A whole datagram (offset 0, no More Fragments) is not a fragment.
+    assert!(fragment_key(&ipv4_fragment(7, 0, 1)).is_none());
+
+    let (v6_key, v6_last) = fragment_key(&ipv6_fragment(99, true)).unwrap();
+    assert_eq!(v6_key.2, 99);
+    assert_eq!(v6_key.3, 17);
+    assert!(!v6_last);
+    assert!(fragment_key(&ipv6_fragment(99, false)).unwrap().1);
+    let mut not_fragmented = ipv6_fragment(99, true);
+    not_fragmented[6] = 17;
+    assert!(fragment_key(&not_fragmented).is_none());
+}
+
+/// Send `trains` datagrams of 4 fragments and return how many fragments of each were lost.
+async fn lost_per_train(link: &Link, trains: u16) -> Vec<usize> {
+    let mut lost = Vec::new();
+    for id in 0..trains {
+        let mut n = 0;
+        for index in 0..4 {
+            if simulate_link(link, &ipv4_fragment(id, index, 4))
+                .await
+                .is_err()
+            {
+                n += 1;
+            }
+        }
+        lost.push(n);
+    }
+    lost
+}
+
+#[tokio::test]
+async fn test_fragment_trains_lost_all_or_none() {
+    let link = link(0.0, Some(50.0));
+    let lost = lost_per_train(&link, 200).await;
+    assert!(lost.iter().all(|&n| n == 0 || n == 4), "{:?}", lost);
+    assert!(lost.contains(&0) && lost.contains(&4));
+    // Each train is forgotten once its last fragment has crossed the link.
+    assert!(link.fragment_fates.lock().unwrap().is_empty());
+    // Unfragmented packets still use the ordinary loss rate.
+    let whole = ipv4_fragment(1, 0, 1);
+    for _ in 0..50 {
+        assert!(simulate_link(&link, &whole).await.is_ok());
+    }
+}
+
+#[tokio::test]
+async fn test_fragments_lost_independently_by_default() {
+    let lost = lost_per_train(&link(50.0, None), 200).await;
+    assert!(lost.iter().any(|&n| n != 0 && n != 4), "{:?}", lost);
+}
@@ -0,0 +1,213 @@
+#![cfg(feature = "gnmi")]
+
+use network_simulator::config::TelemetryConfig;
+use network_simulator::telemetry::gnmi::{
+    subscribe_request, subscribe_response, subscription_list, typed_value, Path, PathElem, Poll,
+    SubscribeRequest, SubscribeResponse, Subscription, SubscriptionList, SubscriptionMode,
+};
+use network_simulator::telemetry::{self, Telemetry};
+use network_simulator::topology::{Fabric, LinkConfig, Router, RouterId};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::Streaming;
+
+fn rid(name: &str) -> RouterId {
+    RouterId(name.to_string())
+}
+
+fn fabric() -> Fabric {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx0y1"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    fabric.add_link(&rid("Rx0y0"), &rid("Rx0y1"), LinkConfig::default());
+    let cfg = TelemetryConfig {
+        interval_ms: 10,
+        ..Default::default()
+    };
+    fabric.telemetry = Some(Telemetry::new(&cfg));
+    fabric
+}
+
+/// gNMI path of `/network-instances/network-instance[name=<router>]/state/counters`.
+fn router_counters(router: &str) -> Path {
+    let elem = |name: &str, key: Option<&str>| PathElem {
+        name: name.to_string(),
+        key: key
+            .map(|k| HashMap::from([("name".to_string(), k.to_string())]))
+            .unwrap_or_default(),
+    };
+    Path {
+        elem: vec![
+            elem("network-instances", None),
+            elem("network-instance", Some(router)),
+            elem("state", None),
+            elem("counters", None),
+        ],
+        ..Default::default()
+    }
+}
+
+/// Serve `fabric`'s telemetry on a local port and open a Subscribe call sending `requests`.
+async fn subscribe(
+    fabric: &Fabric,
+    requests: mpsc::Receiver<SubscribeRequest>,
+) -> Streaming<SubscribeResponse> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let samples = fabric.telemetry.as_ref().unwrap().subscribe();
+    tokio::spawn(telemetry::gnmi::serve(listener, samples));
+    let channel = tonic::transport::Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .expect("connect");
+    let mut client = tonic::client::Grpc::new(channel);
+    client.ready().await.unwrap();
+    let requests = futures::stream::unfold(requests, |mut rx| async move {
+        rx.recv().await.map(|r| (r, rx))
+    });
+    client
+        .streaming(
+            tonic::Request::new(requests),
+            PathAndQuery::from_static("/gnmi.gNMI/Subscribe"),
+            tonic::codec::ProstCodec::default(),
+        )
+        .await
+        .expect("subscribe")
+        .into_inner()
+}
+
+fn list(mode: subscription_list::Mode, sub_mode: SubscriptionMode) -> SubscribeRequest {
+    SubscribeRequest {
+        request: Some(subscribe_request::Request::Subscribe(SubscriptionList {
+            subscription: vec![Subscription {
+                path: Some(router_counters("Rx0y1")),
+                mode: sub_mode as i32,
+                sample_interval: 0,
+            }],
+            mode: mode as i32,
+            ..Default::default()
+        })),
+    }
+}
+
+/// Next response as counter name and value pairs, or `None` for a sync response.
+async fn next(stream: &mut Streaming<SubscribeResponse>) -> Option<Vec<(String, u64)>> {
+    let response = timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("response in time")
+        .expect("stream ok")
+        .expect("stream open");
+    match response.response.unwrap() {
+        subscribe_response::Response::SyncResponse(_) => None,
+        subscribe_response::Response::Update(notification) => {
+            assert!(notification.timestamp > 0);
+            Some(
+                notification
+                    .update
+                    .iter()
+                    .map(|u| {
+                        let path = u.path.as_ref().unwrap();
+                        assert_eq!(path.elem[1].key["name"], "Rx0y1");
+                        let Some(typed_value::Value::UintVal(v)) = u.val.as_ref().unwrap().value
+                        else {
+                            panic!("not a counter");
+                        };
+                        (path.elem.last().unwrap().name.clone(), v)
+                    })
+                    .collect(),
+            )
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_once_subscription_gets_sample_and_sync() {
+    let mut fabric = fabric();
+    fabric
+        .get_router_mut(&rid("Rx0y1"))
+        .unwrap()
+        .stats
+        .packets_received = 7;
+    telemetry::publish(&mut fabric);
+    let (tx, rx) = mpsc::channel(4);
+    tx.send(list(
+        subscription_list::Mode::Once,
+        SubscriptionMode::TargetDefined,
+    ))
+    .await
+    .unwrap();
+    let mut stream = subscribe(&fabric, rx).await;
+    let updates = next(&mut stream).await.expect("update");
+    assert_eq!(updates.len(), 9);
+    assert!(updates.contains(&("packets-received".to_string(), 7)));
+    assert_eq!(next(&mut stream).await, None);
+    let end = timeout(Duration::from_secs(5), stream.message())
+        .await
+        .unwrap();
+    assert!(matches!(end, Ok(None)), "{:?}", end);
+}
+
+#[tokio::test]
+async fn test_stream_on_change_sends_changed_counters_until_run_ends() {
+    let mut fabric = fabric();
+    telemetry::publish(&mut fabric);
+    let (tx, rx) = mpsc::channel(4);
+    tx.send(list(
+        subscription_list::Mode::Stream,
+        SubscriptionMode::OnChange,
+    ))
+    .await
+    .unwrap();
+    let mut stream = subscribe(&fabric, rx).await;
+    assert_eq!(next(&mut stream).await.expect("initial").len(), 9);
+    assert_eq!(next(&mut stream).await, None);
+    fabric
+        .get_router_mut(&rid("Rx0y1"))
+        .unwrap()
+        .stats
+        .packets_forwarded = 3;
+    telemetry::publish(&mut fabric);
+    assert_eq!(
+        next(&mut stream).await,
+        Some(vec![("packets-forwarded".to_string(), 3)])
+    );
+    fabric.telemetry.as_mut().unwrap().close();
+    let end = timeout(Duration::from_secs(5), stream.message())
+        .await
+        .unwrap();
+    assert!(!matches!(end, Ok(Some(_))), "{:?}", end);
+}
+
+#[tokio::test]
+async fn test_poll_subscription_answers_each_poll() {
+    let mut fabric = fabric();
+    telemetry::publish(&mut fabric);
+    let (tx, rx) = mpsc::channel(4);
+    tx.send(list(
+        subscription_list::Mode::Poll,
+        SubscriptionMode::TargetDefined,
+    ))
+    .await
+    .unwrap();
+    let mut stream = subscribe(&fabric, rx).await;
+    assert!(next(&mut stream).await.is_some());
+    assert_eq!(next(&mut stream).await, None);
+    fabric
+        .get_router_mut(&rid("Rx0y1"))
+        .unwrap()
+        .stats
+        .packets_lost = 2;
+    telemetry::publish(&mut fabric);
+    tx.send(SubscribeRequest {
+        request: Some(subscribe_request::Request::Poll(Poll {})),
+    })
+    .await
+    .unwrap();
+    let updates = next(&mut stream).await.expect("poll update");
+    assert!(updates.contains(&("packets-lost".to_string(), 2)));
+    assert_eq!(next(&mut stream).await, None);
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::gre::{decapsulate, encapsulate, IPPROTO_GRE, OVERHEAD};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{calculate_ipv4_checksum, PacketMeta};
+use network_simulator::processor::process_packet;
+use network_simulator::routing::{compute_routing, Destination};
+use network_simulator::topology::{Fabric, LinkConfig, LinkEncap, Router};
+use std::net::Ipv4Addr;
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// UDP packet of `len` bytes with Don't Fragment set.
+fn udp(len: usize) -> PacketMeta {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .tos(0xb8)
+        .dont_fragment(true)
+        .payload(vec![0; len - 28])
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_encapsulate_round_trip() {
+    let (src, dst) = (Ipv4Addr::new(10, 100, 0, 1), Ipv4Addr::new(10, 100, 1, 1));
+    let inner = udp(100);
+    let outer = encapsulate(&inner.raw, src, dst);
+    assert_eq!(outer.len(), 100 + OVERHEAD);
+    assert_eq!(outer[1], 0xb8, "outer TOS copies the inner one");
+    assert_eq!(outer[9], IPPROTO_GRE);
+    assert_eq!(&outer[12..20], &[10, 100, 0, 1, 10, 100, 1, 1]);
+    assert_eq!(
+        calculate_ipv4_checksum(&outer),
+        u16::from_be_bytes([outer[10], outer[11]])
+    );
+    assert_eq!(&outer[20..24], &[0, 0, 0x08, 0x00]);
+    assert_eq!(decapsulate(&outer), Some(&inner.raw[..]));
+
+    let v6 = PacketBuilder::new(
+        "2001:db8::1".parse().unwrap(),
+        "2001:db8::2".parse().unwrap(),
+    )
+    .udp(1, 2)
+    .build()
+    .unwrap();
+    let outer = encapsulate(&v6.raw, src, dst);
+    assert_eq!(&outer[22..24], &[0x86, 0xdd]);
+    assert_eq!(decapsulate(&outer), Some(&v6.raw[..]));
+    assert_eq!(decapsulate(&v6.raw), None);
+}
+
+/// Rx0y0 — Rx0y1 over a GRE link with MTU 1500.
+fn tunnel() -> Fabric {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx0y1"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    let cfg = LinkConfig {
+        mtu: Some(1500),
+        encap: Some(LinkEncap::Gre),
+        ..Default::default()
+    };
+    fabric.add_link(&rid("Rx0y0"), &rid("Rx0y1"), cfg);
+    fabric
+}
+
+#[tokio::test]
+async fn test_tunnel_overhead_reduces_mtu() {
+    let mut fabric = tunnel();
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx0y1"));
+    // Fits with the 24 bytes of outer headers: delivered unchanged.
+    let packet = udp(1476);
+    let out = process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        packet.clone(),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(&out.raw[12..], &packet.raw[12..]);
+    let link = fabric.get_link(&rid("Rx0y0"), &rid("Rx0y1")).unwrap();
+    assert_eq!(link.bytes(), 1500, "the link carries the encapsulated size");
+    // 1480 bytes fit the link but not the tunnel: Fragmentation Needed with the tunnel MTU.
+    let out = process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp(1480),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(out.protocol, 1);
+    assert_eq!(&out.raw[20..22], &[3, 4]);
+    assert_eq!(u16::from_be_bytes([out.raw[26], out.raw[27]]), 1476);
+    let link = fabric.get_link(&rid("Rx0y0"), &rid("Rx0y1")).unwrap();
+    assert_eq!(link.too_big(), 1);
+}
+
+#[test]
+fn test_encap_config() {
+    let cfg = common::line("", &["", ""], &["encap = \"gre\", mtu = 40"], "");
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("room inside the tunnel"), "{}", err);
+    let cfg = addressed(common::line("", &["", ""], &["encap = \"gre\""], ""));
+    let link = cfg.topology.links.values().next().unwrap();
+    assert_eq!(link.encap, Some(LinkEncap::Gre));
+    assert!(cfg.validate().is_ok());
+}
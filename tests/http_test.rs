@@ -0,0 +1,88 @@
+#![cfg(feature = "http-test")]
+
+use network_simulator::config::{HttpTestConfig, SimulatorConfig};
+use network_simulator::http::{run_load, start_server, HttpLoadReport};
+
+#[tokio::test]
+async fn test_http_load_against_echo_origin() {
+    let (addr, server) = start_server("127.0.0.1:0", None).await.expect("origin");
+    let cfg = HttpTestConfig {
+        target: Some(addr.to_string()),
+        client_bind: Some("127.0.0.1".to_string()),
+        requests: 20,
+        concurrency: 3,
+        body_bytes: 512,
+        ..Default::default()
+    };
+    let report = run_load(&cfg, None).await.expect("load run");
+    assert_eq!(report.completed(), 20);
+    assert_eq!(report.errors, 0);
+    assert_eq!(report.bytes, 20 * 512);
+    assert!(report.throughput_mbps() > 0.0);
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_http_load_counts_connection_errors() {
+    // Grab a free port and release it so nothing is listening there.
+    let (addr, server) = start_server("127.0.0.1:0", None).await.unwrap();
+    server.abort();
+    let _ = server.await;
+    let cfg = HttpTestConfig {
+        target: Some(addr.to_string()),
+        requests: 4,
+        concurrency: 2,
+        ..Default::default()
+    };
+    let report = run_load(&cfg, None).await.expect("load run");
+    assert_eq!(report.completed(), 0);
+    assert_eq!(report.errors, 4);
+}
+
+#[test]
+fn test_http_report_percentiles() {
+    let report = HttpLoadReport {
+        latencies_ms: vec![4.0, 1.0, 3.0, 2.0],
+        bytes: 1_000_000,
+        elapsed_secs: 2.0,
+        ..Default::default()
+    };
+    assert_eq!(report.latency_percentile_ms(50.0), Some(2.0));
+    assert_eq!(report.latency_percentile_ms(99.0), Some(4.0));
+    assert_eq!(report.latency_avg_ms(), Some(2.5));
+    assert!((report.throughput_mbps() - 4.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_http_config_validation() {
+    let mut cfg: SimulatorConfig = toml::from_str(
+        r#"
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y0"
+
+[interfaces.real_tun_a]
+address = "10.0.0.1"
+netns = "client"
+
+[interfaces.real_tun_b]
+address = "10.0.1.1"
+netns = "origin"
+
+[topology.routers]
+Rx0y0 = {}
+
+[http_test]
+server_bind = "10.0.1.1:8080"
+target = "10.0.1.1:8080"
+client_bind = "10.0.0.1"
+"#,
+    )
+    .expect("config parses");
+    assert_eq!(cfg.validate(), Ok(()));
+    cfg.interfaces.real_tun_b.netns = None;
+    assert!(cfg.validate().unwrap_err().contains("netns"));
+    cfg.interfaces.real_tun_b.netns = Some("origin".to_string());
+    cfg.http_test = Some(HttpTestConfig::default());
+    assert!(cfg.validate().unwrap_err().contains("server_bind"));
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::rid;
+use network_simulator::icmp::{generate_icmp_error, is_error};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{self, PacketMeta};
+use network_simulator::processor::{process_packet, MAX_ICMP_ERRORS};
+use network_simulator::routing::Destination;
+use network_simulator::topology::{Fabric, RouterStats};
+use network_simulator::{build_fabric, compute_routing_tables};
+use std::net::Ipv4Addr;
+
+fn udp(ttl: u8, len: usize) -> PacketMeta {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .ttl(ttl)
+        .payload(vec![0; len - 28])
+        .build()
+        .unwrap()
+}
+
+/// A Time Exceeded about a UDP packet, from a host behind tun_b to one behind tun_a.
+fn time_exceeded(ttl: u8) -> PacketMeta {
+    let quoted = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.9".parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap();
+    let mut raw = generate_icmp_error(&quoted, 11, 0, Ipv4Addr::new(10, 0, 1, 2));
+    raw[8] = ttl;
+    packet::update_ipv4_checksum(&mut raw);
+    packet::parse(&raw).unwrap()
+}
+
+fn stats(fabric: &Fabric, router: &str) -> RouterStats {
+    fabric.get_router(&rid(router)).unwrap().stats.clone()
+}
+
+#[test]
+fn test_error_classification() {
+    assert!(is_error(&time_exceeded(64)));
+    let echo = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .echo_request(1, 1)
+        .build()
+        .unwrap();
+    assert!(!is_error(&echo));
+    assert!(!is_error(&udp(64, 100)));
+}
+
+#[tokio::test]
+async fn test_no_error_about_an_error() {
+    let cfg = common::line("", &["", "", ""], &["", ""], "");
+    let mut fabric = build_fabric(&cfg);
+    let tables = compute_routing_tables(&cfg);
+    // The error expires at Rx0y2 on its way to tun_a and is dropped silently.
+    process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        time_exceeded(3),
+        Destination::TunB,
+    )
+    .await;
+    let rx0y2 = stats(&fabric, "Rx0y2");
+    assert_eq!((rx0y2.icmp_generated, rx0y2.icmp_suppressed), (0, 1));
+    assert_eq!(stats(&fabric, "Rx0y0").packets_delivered, 0);
+
+    // A UDP packet expiring there draws one, which is delivered.
+    let out = process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp(3, 100),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!((out.protocol, out.raw[20]), (1, 11));
+    let rx0y2 = stats(&fabric, "Rx0y2");
+    assert_eq!((rx0y2.icmp_generated, rx0y2.icmp_suppressed), (1, 1));
+    assert_eq!(stats(&fabric, "Rx0y0").packets_delivered, 1);
+}
+
+#[tokio::test]
+async fn test_errors_per_packet_capped() {
+    let cfg = common::line("", &["", "", ""], &["mtu = 68", ""], "");
+    let mut fabric = build_fabric(&cfg);
+    let tables = compute_routing_tables(&cfg);
+    // 980 bytes of payload leave Rx0y0 as 21 fragments of up to 48, and every one expires at
+    // Rx0y1.
+    process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp(2, 1000),
+        Destination::TunB,
+    )
+    .await;
+    let rx0y1 = stats(&fabric, "Rx0y1");
+    assert_eq!(rx0y1.icmp_generated, MAX_ICMP_ERRORS as u64);
+    assert_eq!(rx0y1.icmp_suppressed, 21 - MAX_ICMP_ERRORS as u64);
+
+    // The budget is per call.
+    process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp(2, 60),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(
+        stats(&fabric, "Rx0y1").icmp_generated,
+        MAX_ICMP_ERRORS as u64 + 1
+    );
+}
@@ -17,11 +17,9 @@ fn test_generate_fragmentation_needed() {
     let packet = PacketMeta {
         src_ip: std::net::IpAddr::V4(src_ip),
         dst_ip: std::net::IpAddr::V4(dst_ip),
-        src_port: 0,
-        dst_port: 0,
         protocol: 6,
-        ttl: 64,
-        raw,
+        raw: raw.into(),
+        ..Default::default()
     };
     let mtu = 1500u32;
     // Router address for the ICMP error
@@ -23,6 +23,7 @@ fn test_icmp_destination_unreachable_generated() {
         packet_inject_tun: None,
         packet_inject_tuns: None,
         virtual_customer: None,
+        ..Default::default()
     };
 
     // Build minimal fabric with one router having a valid ID.
@@ -37,11 +38,9 @@ fn test_icmp_destination_unreachable_generated() {
     let packet = PacketMeta {
         src_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
         dst_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
-        src_port: 0,
-        dst_port: 0,
         protocol: 6, // TCP
-        ttl: 64,
-        raw: vec![0u8; 20],
+        raw: vec![0u8; 20].into(),
+        ..Default::default()
     };
 
     let rt = Runtime::new().unwrap();
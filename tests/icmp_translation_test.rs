@@ -0,0 +1,111 @@
+// tests/icmp_translation_test.rs
+
+use network_simulator::icmp::{self, QuotedEnd};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{calculate_ipv4_checksum, PacketMeta};
+use std::net::Ipv4Addr;
+
+const INSIDE: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 10);
+const OUTSIDE: Ipv4Addr = Ipv4Addr::new(203, 0, 113, 5);
+const SERVER: Ipv4Addr = Ipv4Addr::new(198, 51, 100, 7);
+const ROUTER: Ipv4Addr = Ipv4Addr::new(10, 100, 0, 1);
+
+fn udp(src: Ipv4Addr, sport: u16, dst: Ipv4Addr, dport: u16) -> PacketMeta {
+    PacketBuilder::new(src.into(), dst.into())
+        .udp(sport, dport)
+        .payload(b"query".to_vec())
+        .build()
+        .unwrap()
+}
+
+fn tcp(src: Ipv4Addr, sport: u16, dst: Ipv4Addr, dport: u16) -> PacketMeta {
+    PacketBuilder::new(src.into(), dst.into())
+        .tcp(sport, dport)
+        .payload(vec![7; 100])
+        .build()
+        .unwrap()
+}
+
+fn assert_checksums_valid(raw: &[u8]) {
+    assert_eq!(
+        calculate_ipv4_checksum(raw),
+        u16::from_be_bytes([raw[10], raw[11]])
+    );
+    let quoted = &raw[28..48];
+    assert_eq!(
+        calculate_ipv4_checksum(quoted),
+        u16::from_be_bytes([quoted[10], quoted[11]])
+    );
+    let mut icmp = raw[20..].to_vec();
+    icmp[2..4].copy_from_slice(&[0, 0]);
+    let mut sum: u32 = icmp
+        .chunks(2)
+        .map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]) as u32)
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    assert_eq!(!(sum as u16), u16::from_be_bytes([raw[22], raw[23]]));
+}
+
+#[test]
+fn test_translate_error_for_translated_source() {
+    // The host sent 192.168.1.10:5000, the NAT put 203.0.113.5:40000 on the wire, and a
+    // router beyond it answered with Fragmentation Needed.
+    let original = udp(INSIDE, 5000, SERVER, 53);
+    let translated = udp(OUTSIDE, 40000, SERVER, 53);
+    let mut error = icmp::generate_fragmentation_needed(&translated, 1400, ROUTER);
+    icmp::translate_error(&mut error, QuotedEnd::Source, INSIDE, 5000).unwrap();
+
+    assert_eq!(&error[16..20], &INSIDE.octets(), "outer destination");
+    assert_eq!(&error[12..16], &ROUTER.octets(), "outer source unchanged");
+    // The quote is now the host's own packet, UDP checksum included.
+    assert_eq!(&error[28..56], &original.raw[..28]);
+    assert_eq!(&error[26..28], &1400u16.to_be_bytes(), "MTU kept");
+    assert_checksums_valid(&error);
+}
+
+#[test]
+fn test_translate_error_for_translated_destination() {
+    // A port‑forwarded TCP connection to 203.0.113.5:8080 reached 192.168.1.10:80, whose
+    // answer is an error going back out through the NAT.
+    let arrived = tcp(SERVER, 33000, INSIDE, 80);
+    let sent = tcp(SERVER, 33000, OUTSIDE, 8080);
+    let mut error = icmp::generate_icmp_error(&arrived, 3, 3, INSIDE);
+    icmp::translate_error(&mut error, QuotedEnd::Destination, OUTSIDE, 8080).unwrap();
+
+    assert_eq!(&error[12..16], &OUTSIDE.octets(), "outer source");
+    assert_eq!(&error[16..20], &SERVER.octets());
+    assert_eq!(&error[28..56], &sent.raw[..28]);
+    assert_checksums_valid(&error);
+}
+
+#[test]
+fn test_translate_error_without_ports_rewrites_addresses() {
+    // An error quoting GRE (protocol 47) has only the addresses to translate.
+    let gre = PacketBuilder::new(OUTSIDE.into(), SERVER.into())
+        .protocol(47)
+        .payload(vec![0; 24])
+        .build()
+        .unwrap();
+    let mut error = icmp::generate_fragmentation_needed(&gre, 1400, ROUTER);
+    let rest = error[48..].to_vec();
+    icmp::translate_error(&mut error, QuotedEnd::Source, INSIDE, 5000).unwrap();
+    assert_eq!(&error[16..20], &INSIDE.octets(), "outer destination");
+    assert_eq!(&error[40..44], &INSIDE.octets(), "quoted source");
+    assert_eq!(&error[48..], &rest[..], "GRE header untouched");
+    assert_checksums_valid(&error);
+}
+
+#[test]
+fn test_translate_error_rejects_non_errors() {
+    let mut packet = udp(INSIDE, 5000, SERVER, 53).raw.to_vec();
+    assert!(icmp::translate_error(&mut packet, QuotedEnd::Source, OUTSIDE, 1).is_err());
+    let mut echo = PacketBuilder::new(INSIDE.into(), SERVER.into())
+        .echo_request(1, 1)
+        .build()
+        .unwrap()
+        .raw
+        .to_vec();
+    assert!(icmp::translate_error(&mut echo, QuotedEnd::Source, OUTSIDE, 1).is_err());
+}
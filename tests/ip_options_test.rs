@@ -0,0 +1,109 @@
+mod common;
+
+use common::rid;
+use network_simulator::packet::options::{record_hop, recorded_route, IPOPT_RR, IPOPT_TS};
+use network_simulator::packet::{calculate_ipv4_checksum, parse, update_ipv4_checksum};
+use network_simulator::topology::Router;
+use std::io::Write;
+use std::net::Ipv4Addr;
+use tempfile::NamedTempFile;
+
+/// IPv4/UDP packet 10.0.0.2 -> 10.0.1.2 carrying `options`, padded with End of Options.
+fn with_options(options: &[u8]) -> Vec<u8> {
+    let mut header_options = options.to_vec();
+    header_options.resize(options.len().div_ceil(4) * 4, 0);
+    let ihl = 20 + header_options.len();
+    let total = ihl + 8;
+    let mut raw = vec![0x40 | (ihl / 4) as u8, 0];
+    raw.extend_from_slice(&(total as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 2, 10, 0, 1, 2]);
+    raw.extend_from_slice(&header_options);
+    raw.extend_from_slice(&[0x03, 0xe8, 0x07, 0xd0, 0, 8, 0, 0]);
+    update_ipv4_checksum(&mut raw);
+    raw
+}
+
+/// Record Route option with room for `slots` addresses.
+fn record_route(slots: usize) -> Vec<u8> {
+    let mut option = vec![IPOPT_RR, (3 + 4 * slots) as u8, 4];
+    option.resize(3 + 4 * slots, 0);
+    option
+}
+
+fn addr(s: &str) -> Ipv4Addr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_record_route_fills_until_full() {
+    let mut raw = with_options(&record_route(2));
+    assert_eq!(recorded_route(&raw), Some(vec![]));
+    for hop in ["192.0.2.1", "192.0.2.2", "192.0.2.3"] {
+        record_hop(&mut raw, addr(hop), 0);
+    }
+    assert_eq!(
+        recorded_route(&raw),
+        Some(vec![addr("192.0.2.1"), addr("192.0.2.2")])
+    );
+    assert_eq!(raw[22], 12);
+    assert_eq!(recorded_route(&with_options(&[])), None);
+}
+
+#[test]
+fn test_timestamp_flags_and_overflow() {
+    // Timestamps only, room for one.
+    let mut raw = with_options(&[IPOPT_TS, 8, 5, 0, 0, 0, 0, 0]);
+    assert!(record_hop(&mut raw, addr("192.0.2.1"), 1234));
+    assert_eq!(&raw[20..28], &[IPOPT_TS, 8, 9, 0, 0, 0, 0x04, 0xd2]);
+    // The next router finds no room and counts itself in the overflow field.
+    assert!(record_hop(&mut raw, addr("192.0.2.2"), 1235));
+    assert_eq!(raw[23], 0x10);
+
+    // Address and timestamp.
+    let mut raw = with_options(&[IPOPT_TS, 12, 5, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    record_hop(&mut raw, addr("192.0.2.1"), 7);
+    assert_eq!(&raw[24..32], &[192, 0, 2, 1, 0, 0, 0, 7]);
+    assert_eq!(raw[22], 13);
+
+    // Prespecified: only the named router stamps.
+    let mut option = vec![IPOPT_TS, 12, 5, 3];
+    option.extend_from_slice(&[192, 0, 2, 9, 0, 0, 0, 0]);
+    let mut raw = with_options(&option);
+    assert!(!record_hop(&mut raw, addr("192.0.2.1"), 7));
+    assert!(record_hop(&mut raw, addr("192.0.2.9"), 8));
+    assert_eq!(&raw[28..32], &[0, 0, 0, 8]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_routers_record_route_across_fabric() {
+    let mut option = record_route(4);
+    option.extend_from_slice(&[IPOPT_TS, 12, 5, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", hex::encode(with_options(&option))).unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"\npacket_inject_tun = \"tun_a\"", path),
+        &["", "", ""],
+        &["", ""],
+        "",
+    );
+    network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    let raw = hex::decode(out.trim()).unwrap();
+    // Every forwarding router is recorded; the last one delivers the packet to tun_b.
+    let expected: Vec<Ipv4Addr> = ["Rx0y0", "Rx0y1"]
+        .iter()
+        .map(|r| Router::new(rid(r)).ipv4_addr())
+        .collect();
+    assert_eq!(recorded_route(&raw), Some(expected.clone()));
+    // The Timestamp option after it carries the first router's address.
+    assert_eq!(&raw[43..47], &expected[0].octets());
+    assert_eq!(raw[41], 13);
+    assert_eq!(
+        calculate_ipv4_checksum(&raw),
+        u16::from_be_bytes([raw[10], raw[11]])
+    );
+    assert_eq!(parse(&raw).unwrap().ttl, 62);
+}
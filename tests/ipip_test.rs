@@ -0,0 +1,151 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::ipip::{
+    decapsulate, encapsulate_4in6, encapsulate_6in4, IPPROTO_IPIP, IPPROTO_IPV6,
+};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{calculate_ipv4_checksum, PacketMeta};
+use network_simulator::processor::process_packet;
+use network_simulator::routing::{compute_routing, Destination};
+use network_simulator::topology::{Fabric, LinkConfig, LinkEncap, Router};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// UDP packet of `len` bytes with Don't Fragment set (IPv4) from `src` to `dst`.
+fn udp(src: &str, dst: &str, len: usize) -> PacketMeta {
+    let (src, dst): (IpAddr, IpAddr) = (src.parse().unwrap(), dst.parse().unwrap());
+    let header = if src.is_ipv6() { 48 } else { 28 };
+    PacketBuilder::new(src, dst)
+        .udp(1000, 2000)
+        .tos(0xb8)
+        .dont_fragment(true)
+        .payload(vec![0; len - header])
+        .build()
+        .unwrap()
+}
+
+fn v4(len: usize) -> PacketMeta {
+    udp("10.0.0.2", "10.0.1.2", len)
+}
+
+fn v6(len: usize) -> PacketMeta {
+    udp("2001:db8::2", "2001:db8:1::2", len)
+}
+
+#[test]
+fn test_encapsulate_round_trip() {
+    let inner = v6(100);
+    let outer = encapsulate_6in4(
+        &inner.raw,
+        Ipv4Addr::new(10, 100, 0, 1),
+        Ipv4Addr::new(10, 100, 1, 1),
+    );
+    assert_eq!(outer.len(), 120);
+    assert_eq!(outer[1], 0xb8, "outer TOS copies the inner Traffic Class");
+    assert_eq!(outer[9], IPPROTO_IPV6);
+    assert_eq!(
+        calculate_ipv4_checksum(&outer),
+        u16::from_be_bytes([outer[10], outer[11]])
+    );
+    assert_eq!(decapsulate(&outer), Some(&inner.raw[..]));
+
+    let inner = v4(100);
+    let (src, dst) = (
+        Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 0),
+        Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1),
+    );
+    let outer = encapsulate_4in6(&inner.raw, src, dst);
+    assert_eq!(outer.len(), 140);
+    assert_eq!((outer[0], outer[1]), (0x6b, 0x80));
+    assert_eq!((outer[4], outer[5], outer[6]), (0, 100, IPPROTO_IPIP));
+    assert_eq!(decapsulate(&outer), Some(&inner.raw[..]));
+    assert_eq!(decapsulate(&inner.raw), None);
+}
+
+/// Rx0y0 — Rx0y1 over a link with MTU 1500 and `encap`.
+fn tunnel(encap: LinkEncap) -> Fabric {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx0y1"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    let cfg = LinkConfig {
+        mtu: Some(1500),
+        encap: Some(encap),
+        ..Default::default()
+    };
+    fabric.add_link(&rid("Rx0y0"), &rid("Rx0y1"), cfg);
+    fabric
+}
+
+async fn send(fabric: &mut Fabric, packet: PacketMeta) -> PacketMeta {
+    let tables = compute_routing(fabric, rid("Rx0y0"), rid("Rx0y1"));
+    process_packet(fabric, &tables, rid("Rx0y0"), packet, Destination::TunB).await
+}
+
+fn link_bytes(fabric: &Fabric) -> u64 {
+    fabric
+        .get_link(&rid("Rx0y0"), &rid("Rx0y1"))
+        .unwrap()
+        .bytes()
+}
+
+#[tokio::test]
+async fn test_6in4_link_reduces_ipv6_mtu_only() {
+    let mut fabric = tunnel(LinkEncap::SixInFour);
+    let out = send(&mut fabric, v6(1480)).await;
+    assert_eq!(out.protocol, 17);
+    assert_eq!(
+        link_bytes(&fabric),
+        1500,
+        "IPv6 crosses with an outer IPv4 header"
+    );
+    // IPv4 crosses natively and keeps the whole MTU.
+    let out = send(&mut fabric, v4(1500)).await;
+    assert_eq!(out.protocol, 17);
+    assert_eq!(link_bytes(&fabric), 3000);
+    // 1481 bytes of IPv6 fit the link but not the tunnel: Packet Too Big with the tunnel MTU.
+    let out = send(&mut fabric, v6(1481)).await;
+    assert_eq!(out.protocol, 58);
+    assert_eq!(out.raw[40], 2);
+    assert_eq!(&out.raw[44..48], &1480u32.to_be_bytes());
+}
+
+#[tokio::test]
+async fn test_4in6_link_reduces_ipv4_mtu_only() {
+    let mut fabric = tunnel(LinkEncap::FourInSix);
+    let out = send(&mut fabric, v4(1460)).await;
+    assert_eq!(out.protocol, 17);
+    assert_eq!(
+        link_bytes(&fabric),
+        1500,
+        "IPv4 crosses with an outer IPv6 header"
+    );
+    let out = send(&mut fabric, v6(1500)).await;
+    assert_eq!(out.protocol, 17);
+    assert_eq!(link_bytes(&fabric), 3000);
+    let out = send(&mut fabric, v4(1461)).await;
+    assert_eq!(out.protocol, 1);
+    assert_eq!(&out.raw[20..22], &[3, 4]);
+    assert_eq!(u16::from_be_bytes([out.raw[26], out.raw[27]]), 1460);
+}
+
+#[test]
+fn test_ipip_encap_config() {
+    let cfg = addressed(common::line("", &["", ""], &["encap = \"6in4\""], ""));
+    let link = cfg.topology.links.values().next().unwrap();
+    assert_eq!(link.encap, Some(LinkEncap::SixInFour));
+    assert!(cfg.validate().is_ok());
+    let cfg = common::line("", &["", ""], &["encap = \"4in6\", mtu = 60"], "");
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("room inside the tunnel"), "{}", err);
+}
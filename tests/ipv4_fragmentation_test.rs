@@ -0,0 +1,113 @@
+mod common;
+
+use common::rid;
+use network_simulator::packet::{
+    calculate_ipv4_checksum, fragment_ipv4, is_fragment, parse, update_ipv4_checksum,
+};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// IPv4/UDP packet 10.0.0.2 -> 10.0.1.2 with `options` in the header, `data` payload bytes
+/// counting up and the given flags/offset word.
+fn udp(options: &[u8], data: usize, flags: u16) -> Vec<u8> {
+    let ihl = 20 + options.len();
+    let total = ihl + 8 + data;
+    let mut raw = vec![0x40 | (ihl / 4) as u8, 0];
+    raw.extend_from_slice(&(total as u16).to_be_bytes());
+    raw.extend_from_slice(&[0x12, 0x34]);
+    raw.extend_from_slice(&flags.to_be_bytes());
+    raw.extend_from_slice(&[64, 17, 0, 0, 10, 0, 0, 2, 10, 0, 1, 2]);
+    raw.extend_from_slice(options);
+    raw.extend_from_slice(&[0x13, 0x88, 0x17, 0x70]);
+    raw.extend_from_slice(&((8 + data) as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0]);
+    raw.extend((0..data).map(|i| i as u8));
+    update_ipv4_checksum(&mut raw);
+    raw
+}
+
+/// Flags/offset word of a fragment.
+fn flags(fragment: &[u8]) -> u16 {
+    u16::from_be_bytes([fragment[6], fragment[7]])
+}
+
+#[test]
+fn test_fragments_have_offsets_flags_and_checksums() {
+    // Router Alert (copied) and Record Route (not copied).
+    let options = [0x94, 4, 0, 0, 0x07, 4, 4, 0];
+    let packet = udp(&options, 92, 0);
+    let fragments = fragment_ipv4(&packet, 68).unwrap();
+    // 40 data bytes fit after the 28-byte first header, 40 after the 24-byte later ones.
+    let lengths: Vec<usize> = fragments.iter().map(Vec::len).collect();
+    assert_eq!(lengths, vec![68, 64, 44]);
+    let offsets: Vec<u16> = fragments.iter().map(|f| flags(f) & 0x1FFF).collect();
+    assert_eq!(offsets, vec![0, 5, 10]);
+    let more: Vec<bool> = fragments.iter().map(|f| flags(f) & 0x2000 != 0).collect();
+    assert_eq!(more, vec![true, true, false]);
+    assert_eq!(&fragments[1][20..24], &[0x94, 4, 0, 0]);
+    let mut data = Vec::new();
+    for fragment in &fragments {
+        let ihl = (fragment[0] & 0x0F) as usize * 4;
+        let checksum = calculate_ipv4_checksum(&fragment[..ihl]);
+        assert_eq!(u16::from_be_bytes([fragment[10], fragment[11]]), checksum);
+        assert_eq!(&fragment[4..6], &[0x12, 0x34], "identification is kept");
+        assert!(is_fragment(fragment));
+        data.extend_from_slice(&fragment[ihl..]);
+    }
+    assert_eq!(data, packet[28..]);
+    // Only the first fragment carries the ports.
+    assert_eq!(parse(&fragments[0]).unwrap().dst_port, 6000);
+    assert_eq!(parse(&fragments[1]).unwrap().dst_port, 0);
+}
+
+#[test]
+fn test_fragment_of_fragment_keeps_offset_and_more_flag() {
+    let packet = udp(&[], 52, 0x2000 | 4);
+    let fragments = fragment_ipv4(&packet, 52).unwrap();
+    assert_eq!(fragments.len(), 2);
+    assert_eq!(flags(&fragments[0]), 0x2000 | 4);
+    assert_eq!(flags(&fragments[1]), 0x2000 | 8);
+}
+
+#[test]
+fn test_fragmentation_refused() {
+    let packet = udp(&[], 100, 0x4000);
+    assert_eq!(
+        fragment_ipv4(&packet, 68).unwrap_err(),
+        "Don't Fragment is set"
+    );
+    assert!(fragment_ipv4(&udp(&[], 100, 0), 24).is_err());
+    let mut ipv6 = vec![0x60, 0, 0, 0, 0, 0, 59, 64];
+    ipv6.resize(140, 0);
+    assert!(fragment_ipv4(&ipv6, 68).is_err());
+    let small = udp(&[], 10, 0);
+    assert_eq!(fragment_ipv4(&small, 68).unwrap(), vec![small.clone()]);
+    assert!(!is_fragment(&small));
+}
+
+#[tokio::test]
+async fn test_oversized_packet_leaves_as_fragments() {
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", hex::encode(udp(&[], 92, 0))).unwrap();
+    writeln!(packets, "{}", hex::encode(udp(&[], 92, 0x4000))).unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", "", ""],
+        &["mtu = 68", ""],
+        "",
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    let lines: Vec<Vec<u8>> = out.lines().map(|l| hex::decode(l).unwrap()).collect();
+    // Three fragments for the first packet, an ICMP Fragmentation Needed for the DF one.
+    assert_eq!(lines.len(), 4);
+    assert!(lines[..3].iter().all(|f| f[9] == 17 && is_fragment(f)));
+    assert_eq!(lines[3][9], 1);
+    assert_eq!((lines[3][20], lines[3][21]), (3, 4));
+    let stats = &fabric.get_router(&rid("Rx0y2")).unwrap().stats;
+    // The startup demonstration packet is delivered there too.
+    assert_eq!(stats.packets_delivered, 4);
+}
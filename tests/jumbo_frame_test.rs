@@ -0,0 +1,96 @@
+mod common;
+
+use network_simulator::config::{SimulatorConfig, MAX_MTU};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{parse, PacketMeta};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn jumbo(v6: bool) -> PacketMeta {
+    let (src, dst) = if v6 {
+        ("2001:db8::2", "2001:db8:1::2")
+    } else {
+        ("10.0.0.2", "10.0.1.2")
+    };
+    PacketBuilder::new(src.parse().unwrap(), dst.parse().unwrap())
+        .udp(5000, 5001)
+        .payload(vec![0xab; 9000 - if v6 { 48 } else { 28 }])
+        .dont_fragment(true)
+        .build()
+        .unwrap()
+}
+
+/// Run both jumbo packets from tun_a over links of `mtus`; returns what left the fabric.
+async fn run(mtus: [u32; 2]) -> Vec<PacketMeta> {
+    let mut packets = NamedTempFile::new().unwrap();
+    for v6 in [false, true] {
+        writeln!(packets, "{}", hex::encode(&jumbo(v6).raw)).unwrap();
+    }
+    let path = packets.path().display().to_string();
+    let mut cfg = common::line(
+        &format!("packet_file = \"{}\"\n[simulation]\nmtu = 9216", path),
+        &["", "", ""],
+        &[&format!("mtu = {}", mtus[0]), &format!("mtu = {}", mtus[1])],
+        "",
+    );
+    cfg.tun_ingress.tun_a_ipv6_prefix = "2001:db8::/64".to_string();
+    cfg.tun_ingress.tun_b_ipv6_prefix = "2001:db8:1::/64".to_string();
+    network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    out.lines()
+        .map(|line| parse(&hex::decode(line).unwrap()).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_jumbo_packets_cross_jumbo_links() {
+    let out = run([MAX_MTU, 9000]).await;
+    assert_eq!(out.len(), 2);
+    assert_eq!(out[0].raw.len(), 9000);
+    assert_eq!(out[0].raw[28..], jumbo(false).raw[28..]);
+    assert_eq!(out[1].raw.len(), 9000);
+}
+
+#[tokio::test]
+async fn test_too_big_reports_the_narrower_link() {
+    let out = run([MAX_MTU, 1500]).await;
+    // Fragmentation Needed and Packet Too Big, each advertising the 1500-byte link.
+    assert_eq!(out.len(), 2);
+    assert_eq!((out[0].raw[20], out[0].raw[21]), (3, 4));
+    assert_eq!(u16::from_be_bytes([out[0].raw[26], out[0].raw[27]]), 1500);
+    assert_eq!(out[1].raw[40], 2);
+    assert_eq!(
+        u32::from_be_bytes(out[1].raw[44..48].try_into().unwrap()),
+        1500
+    );
+}
+
+#[test]
+fn test_mtu_limits_validated() {
+    let cfg = |mtu: u32, link_mtu: u32| {
+        let mut cfg: SimulatorConfig = common::line(
+            &format!("[simulation]\nmtu = {}", mtu),
+            &["", ""],
+            &[&format!("mtu = {}", link_mtu)],
+            "",
+        );
+        cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+        cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+        cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+        cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+        cfg.validate()
+    };
+    assert!(cfg(MAX_MTU, MAX_MTU).is_ok());
+    let err = cfg(MAX_MTU + 1, 1500).unwrap_err();
+    assert!(err.contains("simulation.mtu"), "{}", err);
+    let err = cfg(1500, 10000).unwrap_err();
+    assert!(
+        err.contains("exceeds the largest supported MTU 9216"),
+        "{}",
+        err
+    );
+    // A config without `[simulation]` gets the standard Ethernet MTU.
+    assert_eq!(SimulatorConfig::default().simulation.mtu, 1500);
+}
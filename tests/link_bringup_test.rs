@@ -0,0 +1,110 @@
+mod common;
+
+use common::rid;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::processor::process_packet;
+use network_simulator::routing::{compute_routing, Destination};
+use network_simulator::stats::StatsDump;
+use network_simulator::topology::{Fabric, LinkConfig, LinkState, Router};
+use std::time::Duration;
+
+/// Rx0y0 reaches Rx1y1 through Rx0y1 (cost 2) or Rx1y0 (cost 20); Rx0y0_Rx0y1 takes 500 ms
+/// to come back up.
+fn square() -> Fabric {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx0y1", "Rx1y0", "Rx1y1"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    let cost = |cost| LinkConfig {
+        cost: Some(cost),
+        ..Default::default()
+    };
+    fabric.add_link(
+        &rid("Rx0y0"),
+        &rid("Rx0y1"),
+        LinkConfig {
+            bringup_delay_ms: 500,
+            ..cost(1)
+        },
+    );
+    fabric.add_link(&rid("Rx0y1"), &rid("Rx1y1"), cost(1));
+    fabric.add_link(&rid("Rx0y0"), &rid("Rx1y0"), cost(10));
+    fabric.add_link(&rid("Rx1y0"), &rid("Rx1y1"), cost(10));
+    fabric
+}
+
+fn next_hop(fabric: &Fabric) -> String {
+    let tables = compute_routing(fabric, rid("Rx0y0"), rid("Rx1y1"));
+    tables[&rid("Rx0y0")].tun_b.next_hop.0.clone()
+}
+
+fn state(fabric: &Fabric) -> LinkState {
+    fabric
+        .get_link(&rid("Rx0y0"), &rid("Rx0y1"))
+        .unwrap()
+        .state()
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_recovered_link_waits_for_bringup() {
+    let mut fabric = square();
+    let (a, b) = (rid("Rx0y0"), rid("Rx0y1"));
+    assert_eq!(next_hop(&fabric), "Rx0y1");
+
+    assert_eq!(fabric.set_link_up(&a, &b, false), Ok(true));
+    assert_eq!(fabric.set_link_up(&a, &b, false), Ok(false));
+    assert_eq!(state(&fabric), LinkState::Down);
+    assert_eq!(next_hop(&fabric), "Rx1y0");
+
+    // Recovered, but still negotiating: routing keeps away from it.
+    assert_eq!(fabric.set_link_up(&b, &a, true), Ok(true));
+    assert_eq!(state(&fabric), LinkState::Init);
+    assert_eq!(next_hop(&fabric), "Rx1y0");
+    tokio::time::advance(Duration::from_millis(499)).await;
+    assert_eq!(state(&fabric), LinkState::Init);
+    tokio::time::advance(Duration::from_millis(1)).await;
+    assert_eq!(state(&fabric), LinkState::Up);
+    assert_eq!(next_hop(&fabric), "Rx0y1");
+
+    assert!(fabric.set_link_up(&a, &rid("Rx1y1"), true).is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_packets_on_a_link_coming_up_are_dropped() {
+    let mut fabric = square();
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx1y1"));
+    fabric
+        .set_link_up(&rid("Rx0y0"), &rid("Rx0y1"), false)
+        .unwrap();
+    fabric
+        .set_link_up(&rid("Rx0y0"), &rid("Rx0y1"), true)
+        .unwrap();
+    // Stale tables still point at the link.
+    let packet = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap();
+    let out = process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        packet,
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(out.annotation.unwrap().routers, vec![rid("Rx0y0")]);
+    let link = fabric.get_link(&rid("Rx0y0"), &rid("Rx0y1")).unwrap();
+    assert_eq!(link.lost(), 1);
+
+    let dump = StatsDump::from_fabric(&fabric);
+    assert_eq!(dump.links["Rx0y0_Rx0y1"]["state"], 1.0);
+    assert_eq!(dump.links["Rx0y0_Rx0y1"]["flaps"], 1.0);
+    assert_eq!(dump.links["Rx0y1_Rx1y1"]["state"], 2.0);
+}
+
+#[test]
+fn test_bringup_delay_config() {
+    let cfg = common::line("", &["", ""], &["bringup_delay_ms = 250"], "");
+    let link = cfg.topology.links.values().next().unwrap();
+    assert_eq!(link.bringup_delay_ms, 250);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+
+// Square: Rx0y0 reaches Rx1y1 via Rx0y1 over 10 Mbps links or via Rx1y0 over 1 Gbps links.
+fn square(top: &str, slow: &str) -> SimulatorConfig {
+    let mut cfg = common::scenario(
+        top,
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx1y0", ""), ("Rx1y1", "")],
+        &[
+            ("Rx0y0_Rx0y1", slow),
+            ("Rx0y1_Rx1y1", "bandwidth_mbps = 10"),
+            ("Rx0y0_Rx1y0", "bandwidth_mbps = 1000"),
+            ("Rx1y0_Rx1y1", "bandwidth_mbps = 1000"),
+        ],
+        "",
+    );
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+fn route(cfg: &SimulatorConfig) -> (String, u32) {
+    let tables = network_simulator::compute_routing_tables(cfg);
+    let entry = &tables[&rid("Rx0y0")].tun_b;
+    (entry.next_hop.0.clone(), entry.total_cost)
+}
+
+#[test]
+fn test_cost_derived_from_reference_bandwidth() {
+    // Without a reference bandwidth both paths cost one per hop and the tie picks Rx0y1.
+    let cfg = square("", "bandwidth_mbps = 10");
+    assert_eq!(route(&cfg), ("Rx0y1".to_string(), 2));
+
+    let cfg = square(
+        "[topology]\nreference_bandwidth_mbps = 1000",
+        "bandwidth_mbps = 10",
+    );
+    cfg.validate().expect("valid");
+    assert_eq!(route(&cfg), ("Rx1y0".to_string(), 2));
+    let fabric = network_simulator::build_fabric(&cfg);
+    let slow = fabric.get_link(&rid("Rx0y0"), &rid("Rx0y1")).unwrap();
+    assert_eq!(slow.cfg.cost, Some(100));
+    // Links faster than the reference still cost 1.
+    let cfg = square(
+        "[topology]\nreference_bandwidth_mbps = 100",
+        "bandwidth_mbps = 10",
+    );
+    let fabric = network_simulator::build_fabric(&cfg);
+    let fast = fabric.get_link(&rid("Rx0y0"), &rid("Rx1y0")).unwrap();
+    assert_eq!(fast.cfg.cost, Some(1));
+}
+
+#[test]
+fn test_manual_cost_takes_precedence() {
+    let cfg = square(
+        "[topology]\nreference_bandwidth_mbps = 1000",
+        "bandwidth_mbps = 10, cost = 1",
+    );
+    let fabric = network_simulator::build_fabric(&cfg);
+    let cost = |a: &str, b: &str| fabric.get_link(&rid(a), &rid(b)).unwrap().cfg.cost;
+    assert_eq!(cost("Rx0y0", "Rx0y1"), Some(1));
+    assert_eq!(cost("Rx0y1", "Rx1y1"), Some(100));
+    // A manual cost also replaces the delay without a reference bandwidth.
+    let cfg = square("", "cost = 5");
+    assert_eq!(route(&cfg), ("Rx1y0".to_string(), 2));
+}
+
+#[test]
+fn test_cost_config_validated() {
+    let err = square("", "cost = 0").validate().unwrap_err();
+    assert!(err.contains("cost must be at least 1"), "{}", err);
+    let err = square("[topology]\nreference_bandwidth_mbps = 0", "")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("reference_bandwidth_mbps"), "{}", err);
+}
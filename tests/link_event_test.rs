@@ -0,0 +1,175 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::topology::{Fabric, LinkState};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// Square: Rx0y0 reaches Rx1y1 through Rx0y1 (links with options `via_y1`) or Rx1y0 (options
+/// `via_x1`), with `events` after the topology.
+fn square(top: &str, via_y1: &str, via_x1: &str, events: &str) -> SimulatorConfig {
+    let mut cfg = common::scenario(
+        top,
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx1y0", ""), ("Rx1y1", "")],
+        &[
+            ("Rx0y0_Rx0y1", via_y1),
+            ("Rx0y1_Rx1y1", via_y1),
+            ("Rx0y0_Rx1y0", via_x1),
+            ("Rx1y0_Rx1y1", via_x1),
+        ],
+        events,
+    );
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+fn forwarded(fabric: &Fabric, router: &str) -> u64 {
+    fabric
+        .get_router(&rid(router))
+        .unwrap()
+        .stats
+        .packets_forwarded
+}
+
+/// Packet file with one UDP packet from tun_a to tun_b per source port.
+fn packet_file(ports: impl IntoIterator<Item = u16>) -> NamedTempFile {
+    let mut packets = NamedTempFile::new().unwrap();
+    for port in ports {
+        let packet = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+            .udp(port, 2000)
+            .build()
+            .unwrap();
+        writeln!(packets, "{}", hex::encode(&packet.raw)).unwrap();
+    }
+    packets
+}
+
+#[tokio::test]
+async fn test_scheduled_flap_reroutes() {
+    let packets = packet_file(1000..1003);
+    let path = packets.path().display().to_string();
+    let cfg = square(
+        &format!("packet_file = \"{}\"", path),
+        "cost = 1",
+        "cost = 10",
+        r#"
+[[event]]
+after_packets = 1
+action = "link_down"
+link = "Rx0y0_Rx0y1"
+
+[[event]]
+after_packets = 2
+action = "link_up"
+link = "Rx0y0_Rx0y1"
+"#,
+    );
+    cfg.validate().expect("valid");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    // The packet sent while the link was down took the detour instead of being dropped.
+    assert_eq!(out.lines().count(), 3);
+    // Besides the startup demonstration packet, Rx0y1 carried the packets before and after.
+    assert_eq!(forwarded(&fabric, "Rx0y1"), 3);
+    assert_eq!(forwarded(&fabric, "Rx1y0"), 1);
+    let link = fabric.get_link(&rid("Rx0y0"), &rid("Rx0y1")).unwrap();
+    assert_eq!((link.state(), link.flaps), (LinkState::Up, 1));
+}
+
+#[tokio::test]
+async fn test_injected_fault_reroutes() {
+    let packets = packet_file(1000..1003);
+    let path = packets.path().display().to_string();
+    let cfg = square(
+        &format!("packet_file = \"{}\"", path),
+        "cost = 1",
+        "cost = 10",
+        "",
+    );
+    let (injector, faults) = network_simulator::events::fault_injector();
+    // Queued before the run, applied before its first packet.
+    assert!(injector.link_down("Rx0y0", "Rx0y1"));
+    let fabric = network_simulator::run_with_faults(cfg, Some(faults))
+        .await
+        .expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    assert_eq!(out.lines().count(), 3);
+    // Only the startup demonstration packet went through Rx0y1.
+    assert_eq!(forwarded(&fabric, "Rx0y1"), 1);
+    assert_eq!(forwarded(&fabric, "Rx1y0"), 3);
+    let link = fabric.get_link(&rid("Rx0y0"), &rid("Rx0y1")).unwrap();
+    assert_eq!(link.state(), LinkState::Down);
+    // The run is over, nothing takes faults any more.
+    assert!(!injector.link_up("Rx0y0", "Rx0y1"));
+}
+
+#[tokio::test]
+async fn test_scheduled_drain_moves_transit() {
+    let packets = packet_file(1000..1003);
+    let path = packets.path().display().to_string();
+    let cfg = square(
+        &format!("packet_file = \"{}\"", path),
+        "cost = 1",
+        "cost = 10",
+        r#"
+[[event]]
+after_packets = 1
+action = "drain"
+router = "Rx0y1"
+
+[[event]]
+after_packets = 2
+action = "undrain"
+router = "Rx0y1"
+"#,
+    );
+    cfg.validate().expect("valid");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    assert_eq!(forwarded(&fabric, "Rx0y1"), 3);
+    assert_eq!(forwarded(&fabric, "Rx1y0"), 1);
+    assert!(!fabric.get_router(&rid("Rx0y1")).unwrap().maintenance);
+}
+
+#[test]
+fn test_event_config_validation() {
+    let event = |body: &str| square("", "", "", &format!("[[event]]\n{}", body)).validate();
+    assert!(event("at_secs = 1.5\naction = \"link_down\"\nlink = \"Rx0y0_Rx0y1\"").is_ok());
+    let err = event("action = \"link_down\"\nlink = \"Rx0y0_Rx0y1\"").unwrap_err();
+    assert!(err.contains("exactly one of"), "{}", err);
+    let err = event("at_secs = 1\naction = \"reboot\"\nlink = \"Rx0y0_Rx0y1\"").unwrap_err();
+    assert!(err.contains("Unknown event action"), "{}", err);
+    let err = event("at_secs = 1\naction = \"link_up\"\nlink = \"Rx0y0_Rx1y1\"").unwrap_err();
+    assert!(err.contains("not defined in topology.links"), "{}", err);
+    let err = event("at_secs = 1\naction = \"drain\"\nrouter = \"Rx9y9\"").unwrap_err();
+    assert!(err.contains("not defined in topology.routers"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_recompute_releases_pinned_flows() {
+    // Twenty flows are pinned, then one more packet follows the drain.
+    let packets = packet_file((1000..1020).chain([2000]));
+    let path = packets.path().display().to_string();
+    let cfg = square(
+        &format!("packet_file = \"{}\"\nenable_multipath = true", path),
+        "load_balance = true",
+        "load_balance = true",
+        "[[event]]\nafter_packets = 20\naction = \"drain\"\nrouter = \"Rx0y1\"",
+    );
+    cfg.validate().expect("valid");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    // The flows pinned through the drained router were released when routing was recomputed,
+    // without waiting for their next packet.
+    let released = fabric.flows.remapped() as usize;
+    assert!(released > 0 && released < 20, "{}", released);
+}
@@ -1,5 +1,4 @@
 use network_simulator::config::SimulatorConfig;
-use toml;
 
 #[test]
 fn test_link_reference_validation_fails_on_unknown_router() {
@@ -0,0 +1,109 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::topology::Fabric;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Line Rx0y0 - Rx0y1 - Rx0y2 - Rx0y3 with a costlier way round through Rx1y0, links of 1 ms,
+/// and Rx0y2_Rx0y3 failing after the first packet; `rest` goes after the topology.
+fn scenario(top: &str, rest: &str) -> SimulatorConfig {
+    addressed(common::scenario(
+        top,
+        &[
+            ("Rx0y0", ""),
+            ("Rx0y1", ""),
+            ("Rx0y2", ""),
+            ("Rx1y0", ""),
+            ("Rx0y3", ""),
+        ],
+        &[
+            ("Rx0y0_Rx0y1", "delay_ms = 1"),
+            ("Rx0y1_Rx0y2", "delay_ms = 1"),
+            ("Rx0y2_Rx0y3", "delay_ms = 1"),
+            ("Rx0y0_Rx1y0", "delay_ms = 1, cost = 10"),
+            ("Rx1y0_Rx0y3", "delay_ms = 1, cost = 10"),
+        ],
+        &format!(
+            "[[event]]\nafter_packets = 1\naction = \"link_down\"\nlink = \"Rx0y2_Rx0y3\"\n{}",
+            rest
+        ),
+    ))
+}
+
+/// Run 8 packets from tun_a to tun_b through `scenario(.., rest)`.
+async fn run(rest: &str) -> Fabric {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..8 {
+        writeln!(
+            packets,
+            "4500001c000000004011000a0a0000020a0001020001000200080000"
+        )
+        .unwrap();
+    }
+    let path = packets.path().display().to_string();
+    let cfg = scenario(&format!("packet_file = \"{}\"", path), rest);
+    cfg.validate().expect("valid");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    fabric
+}
+
+fn delivered(fabric: &Fabric, router: &str) -> u64 {
+    fabric
+        .get_router(&rid(router))
+        .unwrap()
+        .stats
+        .packets_delivered
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_immediate_recomputation_has_no_transient() {
+    let fabric = run("").await;
+    assert!(fabric.convergence.is_none());
+    // The startup demonstration packet is delivered too.
+    assert_eq!(delivered(&fabric, "Rx0y3"), 9);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_flooding_delay_causes_microloop() {
+    let fabric = run("[link_state]\nflood_delay_ms = 100\nspf_delay_ms = 0\n").await;
+    let convergence = fabric.convergence.as_ref().expect("link state");
+    assert_eq!(convergence.episodes.len(), 1);
+    let episode = &convergence.episodes[0];
+    assert_eq!((episode.routers, episode.converged_routers), (5, 5));
+    // Rx0y0 is two hops of 1 + 100 ms from either end of the failed link.
+    let ms = episode.convergence_ms.expect("converged");
+    assert!((ms - 202.0).abs() < 1.0, "{}", ms);
+    // Rx0y2 turned back towards Rx0y1 while Rx0y1 still sent it the packets.
+    assert!(episode.loop_drops >= 1, "{:?}", episode);
+    let late = delivered(&fabric, "Rx0y3");
+    assert!(late > 2 && late < 9, "{}", late);
+    let stats = &fabric.get_router(&rid("Rx1y0")).unwrap().stats;
+    assert_eq!(stats.packets_forwarded, late - 2, "after it, the way round");
+    assert!(
+        episode.summary().contains("converged in"),
+        "{}",
+        episode.summary()
+    );
+}
+
+#[test]
+fn test_link_state_config_validation() {
+    let cfg = scenario("", "[link_state]\nflood_delay_ms = -1\n");
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("link_state.flood_delay_ms"), "{}", err);
+    let cfg = scenario("", "[link_state]\n");
+    let ls = cfg.link_state.as_ref().unwrap();
+    assert_eq!((ls.flood_delay_ms, ls.spf_delay_ms), (10.0, 50.0));
+    assert!(cfg.validate().is_ok());
+}
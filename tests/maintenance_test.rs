@@ -0,0 +1,108 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::ddos::{build_flood_packet, FloodKind};
+use network_simulator::processor::process_packet;
+use network_simulator::routing::{compute_routing, Destination, MAINTENANCE_COST};
+use network_simulator::topology::Fabric;
+
+// Square: Rx0y0 reaches Rx1y1 via Rx0y1 (preferred on the tie) or via Rx1y0.
+fn square(rx0y1: &str) -> SimulatorConfig {
+    common::scenario(
+        "",
+        &[
+            ("Rx0y0", ""),
+            ("Rx0y1", rx0y1),
+            ("Rx1y0", ""),
+            ("Rx1y1", ""),
+        ],
+        &[
+            ("Rx0y0_Rx0y1", ""),
+            ("Rx0y1_Rx1y1", ""),
+            ("Rx0y0_Rx1y0", ""),
+            ("Rx1y0_Rx1y1", ""),
+        ],
+        "",
+    )
+}
+
+async fn send(fabric: &mut Fabric) {
+    let tables = compute_routing(fabric, rid("Rx0y0"), rid("Rx1y1"));
+    let packet = build_flood_packet(
+        FloodKind::Udp,
+        "10.0.0.2".parse().unwrap(),
+        "10.0.1.2".parse().unwrap(),
+        40000,
+        53,
+        60,
+    );
+    process_packet(fabric, &tables, rid("Rx0y0"), packet, Destination::TunB).await;
+}
+
+fn stats(fabric: &Fabric, id: &str) -> (u64, u64) {
+    let s = &fabric.get_router(&rid(id)).unwrap().stats;
+    (s.packets_forwarded, s.packets_delivered)
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_drained_router_is_not_used_for_transit() {
+    let mut fabric = network_simulator::build_fabric(&square(""));
+    send(&mut fabric).await;
+    assert_eq!(stats(&fabric, "Rx0y1").0, 1);
+
+    assert_eq!(fabric.set_maintenance(&rid("Rx0y1"), true), Ok(true));
+    assert_eq!(fabric.set_maintenance(&rid("Rx0y1"), true), Ok(false));
+    send(&mut fabric).await;
+    assert_eq!(stats(&fabric, "Rx0y1").0, 1);
+    assert_eq!(stats(&fabric, "Rx1y0").0, 1);
+    assert_eq!(stats(&fabric, "Rx1y1").1, 2);
+
+    // Back in service, the original path returns.
+    fabric.set_maintenance(&rid("Rx0y1"), false).unwrap();
+    send(&mut fabric).await;
+    assert_eq!(stats(&fabric, "Rx0y1").0, 2);
+    assert!(fabric.set_maintenance(&rid("Rx9y9"), true).is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_drained_router_still_delivers_and_carries_the_only_path() {
+    // The egress itself in maintenance keeps delivering to its edge.
+    let mut fabric = network_simulator::build_fabric(&square(""));
+    fabric.set_maintenance(&rid("Rx1y1"), true).unwrap();
+    send(&mut fabric).await;
+    assert_eq!(stats(&fabric, "Rx1y1").1, 1);
+
+    // With no way around it, a drained router still carries the traffic.
+    let cfg = common::line("", &["", "maintenance = true", ""], &["", ""], "");
+    let mut fabric = network_simulator::build_fabric(&cfg);
+    assert!(fabric.get_router(&rid("Rx0y1")).unwrap().maintenance);
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx0y2"));
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.total_cost, 2 * MAINTENANCE_COST);
+    let packet = build_flood_packet(
+        FloodKind::Udp,
+        "10.0.0.2".parse().unwrap(),
+        "10.0.1.2".parse().unwrap(),
+        40000,
+        53,
+        60,
+    );
+    process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        packet,
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(stats(&fabric, "Rx0y2").1, 1);
+}
+
+#[test]
+fn test_maintenance_from_router_config() {
+    let fabric = network_simulator::build_fabric(&square("maintenance = true"));
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx1y1"));
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.next_hop, rid("Rx1y0"));
+    assert_eq!(tables[&rid("Rx1y1")].tun_a.next_hop, rid("Rx1y0"));
+    assert_eq!(tables[&rid("Rx0y1")].tun_b.total_cost, MAINTENANCE_COST);
+}
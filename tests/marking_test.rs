@@ -0,0 +1,162 @@
+use network_simulator::config::SimulatorConfig;
+use network_simulator::marking::{Marker, MarkingStats, WINDOW};
+use network_simulator::packet::{self, PacketMeta};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const IPV4_UDP: &str = "4500001e12340000401100000a0000020a0001021f903039000a00006869";
+const IPV6_UDP: &str = "6000000000081140fd000000000000000000000000000002fd0000000000000100000000000000021f903039000800ff";
+
+fn packet(hex: &str) -> PacketMeta {
+    let bytes = hex::decode(hex).unwrap();
+    packet::parse(&bytes).expect("packet parses")
+}
+
+#[test]
+fn test_ipv4_stamp_uses_id_and_strip_restores_it() {
+    let mut marker = Marker::default();
+    let original = packet(IPV4_UDP);
+    let mut p = original.clone();
+    marker.stamp(&mut p);
+    marker.stamp(&mut packet(IPV4_UDP));
+    assert_eq!(&p.raw[4..6], &[0, 0]);
+    assert_eq!(p.raw.len(), original.raw.len());
+    assert_eq!(marker.verify(&mut p, true), Some(0));
+    assert_eq!(&p.raw[4..6], &[0x12, 0x34]);
+    let checksum = network_simulator::packet::calculate_ipv4_checksum(&p.raw);
+    assert_eq!(u16::from_be_bytes([p.raw[10], p.raw[11]]), checksum);
+    assert_eq!(
+        marker.stats(),
+        MarkingStats {
+            stamped: 2,
+            delivered: 1,
+            lost: 1,
+        }
+    );
+}
+
+#[test]
+fn test_ipv6_stamp_inserts_destination_option() {
+    let mut marker = Marker::default();
+    let original = packet(IPV6_UDP);
+    let mut p = original.clone();
+    marker.stamp(&mut p);
+    marker.stamp(&mut p.clone());
+    let mut second = original.clone();
+    marker.stamp(&mut second);
+    assert_eq!(p.raw.len(), original.raw.len() + 8);
+    assert_eq!(p.raw[6], 60);
+    assert_eq!(u16::from_be_bytes([p.raw[4], p.raw[5]]), 16);
+    assert_eq!(&p.raw[40..44], &[17, 0, 0x1e, 4]);
+    assert_eq!(marker.verify(&mut second, false), Some(2));
+    assert_eq!(second.raw[6], 60);
+    assert_eq!(marker.verify(&mut p, true), Some(0));
+    assert_eq!(p.raw, original.raw);
+}
+
+#[test]
+fn test_wrapped_stamps_count_earlier_packets_as_lost() {
+    let mut marker = Marker::default();
+    let mut first = packet(IPV4_UDP);
+    marker.stamp(&mut first);
+    // The IPv4 stamp space wraps after WINDOW packets; the first stamp is then written off
+    // instead of being silently replaced.
+    for _ in 0..WINDOW {
+        let mut p = packet(IPV4_UDP);
+        marker.stamp(&mut p);
+        assert!(marker.verify(&mut p, false).is_some());
+    }
+    assert_eq!(marker.verify(&mut first, false), None);
+    let stats = marker.stats();
+    assert_eq!((stats.delivered, stats.lost), (WINDOW, 1));
+    // Packets without a stamp handed out by this marker are ignored.
+    assert_eq!(Marker::default().verify(&mut packet(IPV4_UDP), true), None);
+}
+
+#[test]
+fn test_ipv6_outstanding_stamps_are_bounded() {
+    let mut marker = Marker::default();
+    for _ in 0..WINDOW + 10 {
+        marker.stamp(&mut packet(IPV6_UDP));
+    }
+    let stats = marker.stats();
+    assert_eq!((stats.stamped, stats.lost), (WINDOW + 10, WINDOW + 10));
+    assert_eq!(marker.outstanding(), WINDOW as usize);
+}
+
+#[test]
+fn test_ipv4_fragments_are_not_stamped() {
+    let mut marker = Marker::default();
+    // Same packet with More Fragments set.
+    let mut fragment = packet(&IPV4_UDP.replace("123400004011", "123420004011"));
+    let before = fragment.raw.clone();
+    assert!(!marker.stamp(&mut fragment));
+    assert_eq!(fragment.raw, before);
+    assert_eq!(marker.stats().stamped, 0);
+}
+
+fn scenario(packets: &NamedTempFile, strip: bool, loss_percent: f64) -> SimulatorConfig {
+    toml::from_str(&format!(
+        r#"
+packet_file = "{}"
+
+[marking]
+strip = {}
+
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+
+[topology.routers]
+Rx0y0 = {{}}
+Rx0y1 = {{}}
+
+[topology.links]
+Rx0y0_Rx0y1 = {{ loss_percent = {} }}
+"#,
+        packets.path().display(),
+        strip,
+        loss_percent
+    ))
+    .expect("config parses")
+}
+
+async fn run(strip: bool, loss_percent: f64) -> (network_simulator::topology::Fabric, Vec<String>) {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..3 {
+        writeln!(packets, "{}", IPV4_UDP).unwrap();
+    }
+    let fabric = network_simulator::run(scenario(&packets, strip, loss_percent))
+        .await
+        .expect("run");
+    let out_path = format!("{}_out.txt", packets.path().display());
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    (fabric, out.lines().map(str::to_string).collect())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_mock_packets_are_stamped_and_restored() {
+    let (fabric, out) = run(true, 0.0).await;
+    let marking = fabric.marking.as_ref().expect("marking enabled");
+    let stats = marking.a_to_b.stats();
+    assert_eq!((stats.stamped, stats.delivered, stats.lost), (3, 3, 0));
+    assert_eq!(marking.b_to_a.stats().stamped, 0);
+    assert_eq!(out.len(), 3);
+    for line in &out {
+        // Original ID restored; only the TTL (and checksum) changed on the way.
+        assert_eq!(&line[8..12], "1234");
+    }
+
+    let (fabric, out) = run(false, 0.0).await;
+    assert_eq!(fabric.marking.unwrap().a_to_b.stats().delivered, 3);
+    let ids: Vec<&str> = out.iter().map(|l| &l[8..12]).collect();
+    assert_eq!(ids, vec!["0000", "0001", "0002"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_lost_packets_are_counted() {
+    let (fabric, _) = run(true, 100.0).await;
+    let stats = fabric.marking.unwrap().a_to_b.stats();
+    assert_eq!((stats.stamped, stats.delivered, stats.lost), (3, 0, 3));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use network_simulator::metrics;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+#[tokio::test(start_paused = true)]
+async fn test_metrics_export_router_and_queue_counters() {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..4 {
+        writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", packets.path().display()),
+        &["", ""],
+        &[
+            r#"bandwidth_mbps = 0.16, queues = [ { name = "voice", dscp = [46] }, { name = "default", limit = 3 } ]"#,
+        ],
+        "",
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+    let text = metrics::render(&fabric);
+    assert!(text.contains("# TYPE nsim_router_packets_received_total counter\n"));
+    assert!(text.contains("nsim_router_packets_lost_total{router=\"Rx0y0\"} 1\n"));
+    // The start-up demonstration packet and three of the four file packets fit the queue.
+    assert!(text.contains("nsim_queue_enqueued_total{link=\"Rx0y0->Rx0y1\",class=\"default\"} 4\n"));
+    assert!(text.contains("nsim_queue_dropped_total{link=\"Rx0y0->Rx0y1\",class=\"default\"} 1\n"));
+    assert!(
+        text.contains("nsim_queue_wred_dropped_total{link=\"Rx0y0->Rx0y1\",class=\"voice\"} 0\n")
+    );
+
+    let out = NamedTempFile::new().unwrap();
+    metrics::write(&fabric, out.path().to_str().unwrap()).expect("written");
+    assert_eq!(std::fs::read_to_string(out.path()).unwrap(), text);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_metrics_without_queues_has_only_router_families() {
+    let packets = NamedTempFile::new().unwrap();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", packets.path().display()),
+        &["", ""],
+        &[""],
+        "",
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+    let text = metrics::render(&fabric);
+    assert!(text.contains("nsim_router_packets_delivered_total{router=\"Rx0y1\"}"));
+    assert!(!text.contains("nsim_queue_"));
+}
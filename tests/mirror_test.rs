@@ -0,0 +1,78 @@
+mod common;
+
+use network_simulator::config::SimulatorConfig;
+use std::io::Write;
+use tempfile::{NamedTempFile, TempDir};
+
+const UDP_PACKET: &str = "4500001e000000004011000a0a0000020a0001021f903039000a00006869";
+const TCP_PACKET: &str = "4500001400000000400600000a0000020a000102";
+
+/// Line Rx0y0 - Rx0y1 - Rx0y2 where Rx0y1 denies TCP and mirrors IPv4 by `mirror`.
+fn scenario(packets: &NamedTempFile, mirror: &str) -> SimulatorConfig {
+    common::line(
+        &format!("packet_file = \"{}\"", packets.path().display()),
+        &[
+            "",
+            r#"acl = [ { action = "deny", match = "tcp" }, { action = "permit", match = "" } ]"#,
+            "",
+        ],
+        &["", ""],
+        &format!(
+            "[[mirror]]\nrouter = \"Rx0y1\"\nfilter = \"ip\"\n{}",
+            mirror
+        ),
+    )
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_mirror_copies_arrivals_to_file() {
+    let mut packets = NamedTempFile::new().unwrap();
+    for hex in [UDP_PACKET, TCP_PACKET, UDP_PACKET] {
+        writeln!(packets, "{}", hex).unwrap();
+    }
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("span.txt");
+    let cfg = scenario(
+        &packets,
+        &format!("file = \"{}\"\ntruncate = 24", file.display()),
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+
+    let port = &fabric.mirrors[0];
+    // The TCP packet is mirrored although the router's ACL then drops it.
+    assert_eq!((port.mirrored, port.seen, port.dropped), (3, 3, 0));
+    // Denied: the TCP packet and the empty start-up packet, which has no bytes to mirror.
+    assert_eq!(fabric.get_statistics()[&common::rid("Rx0y1")].acl_drops, 2);
+    let span = std::fs::read_to_string(&file).unwrap();
+    let lines: Vec<&str> = span.lines().collect();
+    assert_eq!(lines[0], "# mirror of Rx0y1 filter \"ip\"");
+    assert_eq!(lines[4], "# 3 of 3 packets");
+    // Cut to 24 bytes (the 20-byte TCP packet is shorter), as they arrived one hop in.
+    assert_eq!((lines[1].len(), lines[2].len()), (48, 40));
+    assert_eq!(&lines[1][16..20], "3f11");
+    assert_eq!(&lines[2][16..20], "3f06");
+}
+
+#[test]
+fn test_mirror_validation() {
+    let packets = NamedTempFile::new().unwrap();
+    let config = |mirror: &str| {
+        let mut cfg = scenario(&packets, mirror);
+        cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+        cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+        cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+        cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+        cfg
+    };
+    let cfg = |mirror: &str| config(mirror).validate();
+    assert!(cfg("tun = \"span0\"").is_ok());
+    let err = cfg("").unwrap_err();
+    assert!(err.contains("exactly one of file and tun"), "{}", err);
+    let err = cfg("tun = \"span0\"\ntruncate = 0").unwrap_err();
+    assert!(err.contains("truncate"), "{}", err);
+    let mut elsewhere = config("tun = \"span0\"");
+    elsewhere.mirrors[0].router = "Rx9y9".to_string();
+    let err = elsewhere.validate().unwrap_err();
+    assert!(err.contains("Rx9y9"), "{}", err);
+}
@@ -0,0 +1,90 @@
+use network_simulator::gre::{encapsulate, parse_inner};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::mpls::{encode_stack, parse_stack, MplsLabel, ETHERTYPE_MPLS};
+use network_simulator::packet::{parse_ethertype, parse_labeled, PacketMeta};
+use network_simulator::tap::{TapEdge, TapInput, ETHERTYPE_IPV4};
+use std::net::Ipv4Addr;
+
+fn udp() -> PacketMeta {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap()
+}
+
+fn stack() -> Vec<MplsLabel> {
+    vec![
+        MplsLabel {
+            label: 16,
+            tc: 5,
+            ttl: 64,
+        },
+        MplsLabel::new(0xF_FFFF, 255),
+    ]
+}
+
+#[test]
+fn test_label_stack_round_trip() {
+    let wire = encode_stack(&stack());
+    // Label 16, TC 5, not bottom, TTL 64; then label 0xFFFFF, bottom, TTL 255.
+    assert_eq!(wire, vec![0x00, 0x01, 0x0a, 64, 0xff, 0xff, 0xf1, 255]);
+    assert_eq!(parse_stack(&wire), Ok((stack(), 8)));
+    assert!(parse_stack(&wire[..4]).is_err(), "no bottom of stack");
+
+    let mut labeled = wire.clone();
+    labeled.extend_from_slice(&udp().raw);
+    let packet = parse_labeled(&labeled).unwrap();
+    assert_eq!(packet.mpls, stack());
+    assert_eq!(packet.raw, udp().raw);
+    assert_eq!(packet.dst_port, 2000);
+    assert_eq!(packet.labeled(), labeled);
+    assert_eq!(
+        parse_ethertype(ETHERTYPE_MPLS, &labeled).unwrap().mpls,
+        stack()
+    );
+    assert!(parse_ethertype(ETHERTYPE_IPV4, &udp().raw)
+        .unwrap()
+        .mpls
+        .is_empty());
+}
+
+#[test]
+fn test_push_swap_pop() {
+    let mut packet = udp();
+    assert!(packet.mpls.is_empty());
+    assert!(packet.swap_label(17).is_err());
+    packet.push_label(MplsLabel::new(100, 64)).unwrap();
+    packet.push_label(MplsLabel::new(200, 64)).unwrap();
+    assert!(packet.push_label(MplsLabel::new(1 << 20, 64)).is_err());
+    packet.swap_label(300).unwrap();
+    assert_eq!(packet.mpls[0], MplsLabel::new(300, 63));
+    assert_eq!(packet.pop_label(), Some(MplsLabel::new(300, 63)));
+    assert_eq!(packet.pop_label(), Some(MplsLabel::new(100, 64)));
+    assert_eq!(packet.pop_label(), None);
+    assert_eq!(packet.labeled(), udp().raw.to_vec());
+
+    packet.push_label(MplsLabel::new(5, 1)).unwrap();
+    assert!(packet.swap_label(6).is_err(), "TTL expires");
+}
+
+#[test]
+fn test_labeled_payloads_from_tap_and_gre() {
+    let mut labeled = encode_stack(&stack());
+    labeled.extend_from_slice(&udp().raw);
+
+    let mac = [0x02, 0, 0, 0, 0, 1];
+    let mut tap = TapEdge::new(mac, Ipv4Addr::new(10, 0, 0, 1));
+    let mut frame = mac.to_vec();
+    frame.extend_from_slice(&[0x02, 0, 0, 0, 0, 2]);
+    frame.extend_from_slice(&ETHERTYPE_MPLS.to_be_bytes());
+    frame.extend_from_slice(&labeled);
+    assert_eq!(tap.receive(&frame), TapInput::Labeled(&labeled[..]));
+
+    let src = Ipv4Addr::new(10, 100, 0, 1);
+    let mut outer = encapsulate(&labeled, src, Ipv4Addr::new(10, 100, 1, 1));
+    outer[22..24].copy_from_slice(&ETHERTYPE_MPLS.to_be_bytes());
+    let inner = parse_inner(&outer).unwrap();
+    assert_eq!(inner.mpls, stack());
+    assert_eq!(inner.raw, udp().raw);
+    assert!(parse_inner(&udp().raw).is_err());
+}
@@ -0,0 +1,141 @@
+use network_simulator::multi::{parse_scenario, report, run, validate};
+use network_simulator::topology::Fabric;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const PACKET: &str = "4500001400000000401100000a0000020a000102";
+
+fn packets(count: usize) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    for _ in 0..count {
+        writeln!(file, "{}", PACKET).unwrap();
+    }
+    file
+}
+
+/// `[fabrics.<name>]` section: Rx0y0 - Rx0y1 with the given link options, fed from `file`.
+fn section(name: &str, file: &str, link: &str) -> String {
+    format!(
+        r#"
+[fabrics.{name}]
+packet_file = "{file}"
+
+[fabrics.{name}.simulation]
+seed = 11
+
+[fabrics.{name}.interfaces.real_tun_a]
+address = "10.0.0.1"
+
+[fabrics.{name}.interfaces.real_tun_b]
+address = "10.0.1.1"
+
+[fabrics.{name}.tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+
+[fabrics.{name}.topology.routers]
+Rx0y0 = {{}}
+Rx0y1 = {{}}
+
+[fabrics.{name}.topology.links]
+Rx0y0_Rx0y1 = {{ {link} }}
+"#
+    )
+}
+
+fn cleanup(files: &[&NamedTempFile]) {
+    for file in files {
+        let _ = std::fs::remove_file(format!("{}_out.txt", file.path().display()));
+    }
+}
+
+fn lost(fabric: &Fabric) -> u64 {
+    fabric
+        .get_statistics()
+        .values()
+        .map(|s| s.packets_lost)
+        .sum()
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_instances_keep_separate_statistics() {
+    let (one, two) = (packets(3), packets(5));
+    let text = section("one", &one.path().display().to_string(), "delay_ms = 1")
+        + &section(
+            "two",
+            &two.path().display().to_string(),
+            "loss_percent = 100",
+        );
+    let instances = parse_scenario(&text).expect("parses");
+    validate(&instances).expect("isolated");
+    let results = run(instances, false).await.expect("runs");
+    cleanup(&[&one, &two]);
+    assert_eq!(results[0].0, "one");
+    assert_eq!(results[1].0, "two");
+    assert_eq!(lost(&results[0].1), 0);
+    // Each run also sends the startup demonstration packet.
+    assert_eq!(lost(&results[1].1), 6);
+    let text = report(&results);
+    assert!(
+        text.contains("Fabric one: routers=2, links=1, delivered=4, lost=0"),
+        "{}",
+        text
+    );
+    assert!(text.contains("Fabric two: routers=2, links=1, delivered=0, lost=6, loss=100.00%"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_random_sequence_does_not_depend_on_neighbours() {
+    let alone = packets(40);
+    let text = section(
+        "lossy",
+        &alone.path().display().to_string(),
+        "loss_percent = 50",
+    );
+    let results = run(parse_scenario(&text).unwrap(), false).await.unwrap();
+    let expected = lost(&results[0].1);
+    assert!(expected > 0 && expected < 40, "{}", expected);
+
+    let (lossy, noisy) = (packets(40), packets(40));
+    let text = section(
+        "lossy",
+        &lossy.path().display().to_string(),
+        "loss_percent = 50",
+    ) + &section(
+        "noisy",
+        &noisy.path().display().to_string(),
+        "loss_percent = 30, jitter_ms = 5",
+    );
+    let results = run(parse_scenario(&text).unwrap(), false).await.unwrap();
+    cleanup(&[&alone, &lossy, &noisy]);
+    assert_eq!(lost(&results[0].1), expected);
+}
+
+#[test]
+fn test_scenario_file_rejected_when_instances_overlap() {
+    let shared = packets(1);
+    let file = &shared.path().display().to_string();
+    let err =
+        validate(&parse_scenario(&(section("a", file, "") + &section("b", file, ""))).unwrap())
+            .unwrap_err();
+    assert_eq!(
+        err,
+        format!("Fabrics 'a' and 'b' both use packet file '{}'", file)
+    );
+
+    let err =
+        parse_scenario(&format!("packet_file = \"x\"\n{}", section("a", file, ""))).unwrap_err();
+    assert!(
+        err.contains("Unknown top-level key 'packet_file'"),
+        "{}",
+        err
+    );
+    assert!(parse_scenario("")
+        .unwrap_err()
+        .contains("no [fabrics.<name>]"));
+
+    let broken =
+        section("a", file, "").replace("tun_b_ingress = \"Rx0y1\"", "tun_b_ingress = \"Rx9y9\"");
+    let err = validate(&parse_scenario(&broken).unwrap()).unwrap_err();
+    assert!(err.starts_with("Fabric 'a': "), "{}", err);
+}
@@ -22,6 +22,7 @@ fn test_multipath_forwarding_load_balance() {
         jitter_ms: 0,
         loss_percent: 0.0,
         load_balance: true,
+        ..Default::default()
     };
     fabric.add_link(
         &RouterId("Rx0y0".to_string()),
@@ -46,8 +47,7 @@ fn test_multipath_forwarding_load_balance() {
         src_port: 1234,
         dst_port: 80,
         protocol: 6,
-        ttl: 64,
-        raw: vec![],
+        ..Default::default()
     };
     // Packet 2 with different src_ip
     let packet2 = PacketMeta {
@@ -56,8 +56,7 @@ fn test_multipath_forwarding_load_balance() {
         src_port: 1234,
         dst_port: 80,
         protocol: 6,
-        ttl: 64,
-        raw: vec![],
+        ..Default::default()
     };
     let link1 = select_egress_link_multi(
         &ingress_a,
@@ -65,6 +64,7 @@ fn test_multipath_forwarding_load_balance() {
         incident.as_slice(),
         &tables,
         Destination::TunB,
+        &fabric.ecmp_hash,
     )
     .expect("no link selected");
     let link2 = select_egress_link_multi(
@@ -73,19 +73,12 @@ fn test_multipath_forwarding_load_balance() {
         incident.as_slice(),
         &tables,
         Destination::TunB,
+        &fabric.ecmp_hash,
     )
     .expect("no link selected");
     // Both links should be among the two load‑balanced links.
-    assert!(
-        link1.id
-            == fabric
-                .link_index
-                .keys()
-                .find(|k| k.a == ingress_a && k.b == link1.id.b)
-                .unwrap()
-                .clone()
-            || true
-    );
+    assert!(fabric.link_index.contains_key(&link1.id));
+    assert!(fabric.link_index.contains_key(&link2.id));
     // Ensure that the two packets can select possibly different links (non‑deterministic, but should not panic).
     // The test passes as long as both selections are valid links.
     assert!(incident.iter().any(|l| l.id == link1.id));
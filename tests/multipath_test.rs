@@ -20,6 +20,7 @@ fn test_multipath_routing_two_equal_paths() {
         jitter_ms: 0,
         loss_percent: 0.0,
         load_balance: false,
+        ..Default::default()
     };
     let cfg_via = LinkConfig {
         mtu: None,
@@ -27,6 +28,7 @@ fn test_multipath_routing_two_equal_paths() {
         jitter_ms: 0,
         loss_percent: 0.0,
         load_balance: false,
+        ..Default::default()
     };
     fabric.add_link(
         &RouterId("Rx0y0".to_string()),
@@ -0,0 +1,171 @@
+mod common;
+
+use network_simulator::config::{Nat44Config, SimulatorConfig};
+use network_simulator::icmp;
+use network_simulator::nat::{Nat44, NatStats};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{parse, verify_checksums, PacketMeta};
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr};
+use tempfile::NamedTempFile;
+
+const HOST: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+const EXTERNAL: Ipv4Addr = Ipv4Addr::new(203, 0, 113, 1);
+const SERVER: Ipv4Addr = Ipv4Addr::new(10, 0, 1, 2);
+
+fn nat() -> Nat44 {
+    Nat44::new(&Nat44Config {
+        inside: "tun_a".to_string(),
+        external_address: EXTERNAL.to_string(),
+        first_port: 40000,
+    })
+    .unwrap()
+}
+
+fn udp(src: Ipv4Addr, sport: u16, dst: Ipv4Addr, dport: u16) -> PacketMeta {
+    PacketBuilder::new(src.into(), dst.into())
+        .udp(sport, dport)
+        .payload(b"query".to_vec())
+        .build()
+        .unwrap()
+}
+
+/// Address and source/destination port of the packet quoted by the ICMP error `raw`.
+fn quoted(raw: &[u8]) -> (Ipv4Addr, Ipv4Addr, u16, u16) {
+    let q = &raw[28..];
+    let port = |at: usize| u16::from_be_bytes([q[at], q[at + 1]]);
+    (
+        Ipv4Addr::new(q[12], q[13], q[14], q[15]),
+        Ipv4Addr::new(q[16], q[17], q[18], q[19]),
+        port(20),
+        port(22),
+    )
+}
+
+#[test]
+fn test_flow_translated_both_ways() {
+    let mut nat = nat();
+    let mut out = udp(HOST, 5000, SERVER, 53);
+    assert!(nat.outbound(&mut out));
+    assert_eq!((out.src_ip, out.src_port), (IpAddr::V4(EXTERNAL), 40000));
+    assert_eq!(verify_checksums(&out.raw, true), Ok(()));
+    // The same flow keeps its port; another one gets the next.
+    let mut again = udp(HOST, 5000, SERVER, 53);
+    assert!(nat.outbound(&mut again));
+    assert_eq!(again.src_port, 40000);
+    let mut other = udp(HOST, 5001, SERVER, 53);
+    assert!(nat.outbound(&mut other));
+    assert_eq!(other.src_port, 40001);
+    assert_eq!(nat.len(), 2);
+
+    let mut reply = udp(SERVER, 53, EXTERNAL, 40000);
+    assert!(nat.inbound(&mut reply));
+    assert_eq!((reply.dst_ip, reply.dst_port), (IpAddr::V4(HOST), 5000));
+    assert_eq!(verify_checksums(&reply.raw, true), Ok(()));
+    // Unsolicited packets towards the external address are dropped, others pass.
+    assert!(!nat.inbound(&mut udp(SERVER, 53, EXTERNAL, 41000)));
+    assert!(nat.inbound(&mut udp(SERVER, 53, HOST, 5000)));
+    assert_eq!(
+        nat.stats,
+        NatStats {
+            outbound: 3,
+            inbound: 1,
+            icmp_errors: 0,
+            dropped: 1,
+        }
+    );
+}
+
+#[test]
+fn test_icmp_errors_follow_the_mapping() {
+    let mut nat = nat();
+    let mut out = udp(HOST, 5000, SERVER, 53);
+    assert!(nat.outbound(&mut out));
+
+    // Fragmentation Needed from beyond the NAT reaches the host and quotes what it sent.
+    let error = icmp::generate_fragmentation_needed(&out, 1400, "10.100.0.1".parse().unwrap());
+    let mut error = parse(&error).unwrap();
+    assert!(nat.inbound(&mut error));
+    assert_eq!(error.dst_ip, IpAddr::V4(HOST));
+    assert_eq!(quoted(&error.raw), (HOST, SERVER, 5000, 53));
+
+    // Port Unreachable from the host quotes the external mapping.
+    let mut reply = udp(SERVER, 53, EXTERNAL, 40000);
+    assert!(nat.inbound(&mut reply));
+    let error = icmp::generate_icmp_error(&reply, 3, 3, HOST);
+    let mut error = parse(&error).unwrap();
+    assert!(nat.outbound(&mut error));
+    assert_eq!(error.src_ip, IpAddr::V4(EXTERNAL));
+    assert_eq!(quoted(&error.raw), (SERVER, EXTERNAL, 53, 40000));
+    assert_eq!(nat.stats.icmp_errors, 2);
+
+    // An error about a flow without a mapping is dropped.
+    let stray = udp(EXTERNAL, 40999, SERVER, 53);
+    let error = icmp::generate_fragmentation_needed(&stray, 1400, "10.100.0.1".parse().unwrap());
+    assert!(!nat.inbound(&mut parse(&error).unwrap()));
+}
+
+#[test]
+fn test_icmp_error_quoting_gre_is_dropped() {
+    // GRE (protocol 47) has no port, so there is no mapping to follow.
+    let gre = PacketBuilder::new(EXTERNAL.into(), SERVER.into())
+        .protocol(47)
+        .payload(vec![0; 24])
+        .build()
+        .unwrap();
+    let error = icmp::generate_fragmentation_needed(&gre, 1400, "10.100.0.1".parse().unwrap());
+    let mut nat = nat();
+    assert!(!nat.inbound(&mut parse(&error).unwrap()));
+    assert_eq!(nat.stats.dropped, 1);
+}
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+#[tokio::test]
+async fn test_fragmentation_needed_reaches_host_behind_nat() {
+    let big = PacketBuilder::new(HOST.into(), SERVER.into())
+        .udp(5000, 53)
+        .payload(vec![0; 1472])
+        .dont_fragment(true)
+        .build()
+        .unwrap();
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", hex::encode(&big.raw)).unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = addressed(common::line(
+        &format!("packet_file = \"{}\"\npacket_inject_tun = \"tun_a\"", path),
+        &["", "", ""],
+        &["", "mtu = 1400"],
+        "[nat44]\nexternal_address = \"203.0.113.1\"",
+    ));
+    cfg.validate().expect("valid");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    let error = parse(&hex::decode(out.lines().next().expect("ICMP error")).unwrap()).unwrap();
+    assert_eq!(error.protocol, 1);
+    assert_eq!(error.dst_ip, IpAddr::V4(HOST));
+    assert_eq!(quoted(&error.raw), (HOST, SERVER, 5000, 53));
+    let stats = &fabric.nat44.as_ref().expect("nat44").stats;
+    assert_eq!(
+        (stats.outbound, stats.inbound, stats.icmp_errors),
+        (1, 1, 1)
+    );
+}
+
+#[test]
+fn test_nat44_config_validation() {
+    let cfg = |section: &str| addressed(common::line("", &["", ""], &[""], section)).validate();
+    assert!(cfg("[nat44]\nexternal_address = \"203.0.113.1\"").is_ok());
+    let err = cfg("[nat44]\nexternal_address = \"2001:db8::1\"").unwrap_err();
+    assert!(err.contains("nat44.external_address"), "{}", err);
+    let err = cfg("[nat44]\nexternal_address = \"203.0.113.1\"\ninside = \"lan\"").unwrap_err();
+    assert!(err.contains("nat44.inside"), "{}", err);
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use network_simulator::config::{Nat64Config, SimulatorConfig};
+use network_simulator::nat::nat64::{Nat64, Nat64Stats};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{parse, verify_checksums, PacketMeta};
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tempfile::NamedTempFile;
+
+const HOST: Ipv6Addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2);
+const POOL: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+const SERVER: Ipv4Addr = Ipv4Addr::new(10, 0, 1, 2);
+/// `SERVER` under the well-known prefix.
+const SERVER6: Ipv6Addr = Ipv6Addr::new(0x64, 0xff9b, 0, 0, 0, 0, 0x0a00, 0x0102);
+
+fn nat() -> Nat64 {
+    Nat64::new(&Nat64Config {
+        router: "Rx0y1".to_string(),
+        prefix: "64:ff9b::/96".to_string(),
+        pool_address: POOL.to_string(),
+        first_port: 40000,
+    })
+    .unwrap()
+}
+
+fn udp(src: IpAddr, sport: u16, dst: IpAddr, dport: u16) -> PacketMeta {
+    PacketBuilder::new(src, dst)
+        .udp(sport, dport)
+        .ttl(60)
+        .tos(0xb8)
+        .payload(b"query".to_vec())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_udp_translated_both_ways() {
+    let mut nat = nat();
+    assert_eq!(nat.embed(SERVER), SERVER6);
+    let mut out = udp(HOST.into(), 5000, SERVER6.into(), 53);
+    assert!(nat.translate(&mut out));
+    assert_eq!(
+        (out.src_ip, out.src_port, out.dst_ip, out.dst_port),
+        (IpAddr::V4(POOL), 40000, IpAddr::V4(SERVER), 53)
+    );
+    assert_eq!((out.ttl, out.dscp), (60, 0xb8 >> 2));
+    assert_eq!(verify_checksums(&out.raw, true), Ok(()));
+    assert_eq!(&out.raw[out.raw.len() - 5..], b"query");
+
+    let mut reply = udp(SERVER.into(), 53, POOL.into(), 40000);
+    assert!(nat.translate(&mut reply));
+    assert_eq!(
+        (reply.src_ip, reply.src_port, reply.dst_ip, reply.dst_port),
+        (IpAddr::V6(SERVER6), 53, IpAddr::V6(HOST), 5000)
+    );
+    assert_eq!(verify_checksums(&reply.raw, true), Ok(()));
+
+    // Packets outside the prefix and the pool pass untouched; unsolicited ones are dropped.
+    let mut native = udp(HOST.into(), 5000, "2001:db8:1::2".parse().unwrap(), 53);
+    assert!(nat.translate(&mut native));
+    assert!(native.dst_ip.is_ipv6());
+    assert!(!nat.translate(&mut udp(SERVER.into(), 53, POOL.into(), 41000)));
+    assert_eq!(
+        nat.stats,
+        Nat64Stats {
+            to_v4: 1,
+            to_v6: 1,
+            dropped: 1,
+        }
+    );
+}
+
+#[test]
+fn test_echo_and_tcp_translated() {
+    let mut nat = nat();
+    let mut ping = PacketBuilder::new(HOST.into(), SERVER6.into())
+        .echo_request(7, 1)
+        .payload(vec![0xab; 32])
+        .build()
+        .unwrap();
+    assert!(nat.translate(&mut ping));
+    assert_eq!(ping.protocol, 1);
+    assert_eq!(ping.icmp_type_code(), Some((8, 0)));
+    assert_eq!(ping.icmp_echo().map(|e| e.id), Some(40000));
+    assert_eq!(verify_checksums(&ping.raw, true), Ok(()));
+
+    let mut pong = PacketBuilder::new(SERVER.into(), POOL.into())
+        .icmp(0, 0, [0x9c, 0x40, 0, 1])
+        .payload(vec![0xab; 32])
+        .build()
+        .unwrap();
+    assert!(nat.translate(&mut pong));
+    assert_eq!(pong.protocol, 58);
+    assert_eq!(pong.icmp_type_code(), Some((129, 0)));
+    assert_eq!(pong.icmp_echo().map(|e| (e.id, e.seq)), Some((7, 1)));
+    assert_eq!(verify_checksums(&pong.raw, true), Ok(()));
+
+    let mut syn = PacketBuilder::new(HOST.into(), SERVER6.into())
+        .tcp(6000, 80)
+        .build()
+        .unwrap();
+    assert!(nat.translate(&mut syn));
+    assert_eq!((syn.protocol, syn.src_port), (6, 40001));
+    assert_eq!(verify_checksums(&syn.raw, true), Ok(()));
+    assert_eq!(nat.len(), 2);
+
+    // ICMPv6 errors and packets with extension headers cannot be translated.
+    let mut error = PacketBuilder::new(HOST.into(), SERVER6.into())
+        .icmp(1, 4, [0; 4])
+        .build()
+        .unwrap();
+    assert!(!nat.translate(&mut error));
+    let mut extended = PacketBuilder::new(HOST.into(), SERVER6.into())
+        .extension_header(60)
+        .udp(5000, 53)
+        .build()
+        .unwrap();
+    assert!(!nat.translate(&mut extended));
+}
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+#[tokio::test]
+async fn test_ipv6_packet_leaves_the_fabric_as_ipv4() {
+    let packet = udp(HOST.into(), 5000, SERVER6.into(), 53);
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", hex::encode(&packet.raw)).unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = addressed(common::line(
+        &format!("packet_file = \"{}\"\npacket_inject_tun = \"tun_a\"", path),
+        &["", "", ""],
+        &["", ""],
+        "[nat64]\nrouter = \"Rx0y1\"\npool_address = \"192.0.2.1\"",
+    ));
+    cfg.validate().expect("valid");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    let delivered = parse(&hex::decode(out.lines().next().expect("packet")).unwrap()).unwrap();
+    assert_eq!(
+        (delivered.src_ip, delivered.dst_ip),
+        (IpAddr::V4(POOL), IpAddr::V4(SERVER))
+    );
+    // One hop after the translating router.
+    assert_eq!(delivered.ttl, 58);
+    assert_eq!(verify_checksums(&delivered.raw, true), Ok(()));
+    let stats = &fabric.nat64.as_ref().expect("nat64").stats;
+    assert_eq!((stats.to_v4, stats.to_v6), (1, 0));
+}
+
+#[test]
+fn test_nat64_config_validation() {
+    let cfg = |section: &str| addressed(common::line("", &["", ""], &[""], section)).validate();
+    assert!(cfg("[nat64]\nrouter = \"Rx0y0\"\npool_address = \"192.0.2.1\"").is_ok());
+    let err = cfg("[nat64]\nrouter = \"Rx9y9\"\npool_address = \"192.0.2.1\"").unwrap_err();
+    assert!(err.contains("NAT64 router 'Rx9y9'"), "{}", err);
+    let err = cfg("[nat64]\nrouter = \"Rx0y0\"\npool_address = \"2001:db8::1\"").unwrap_err();
+    assert!(err.contains("nat64.pool_address"), "{}", err);
+    let err =
+        cfg("[nat64]\nrouter = \"Rx0y0\"\npool_address = \"192.0.2.1\"\nprefix = \"64:ff9b::/64\"")
+            .unwrap_err();
+    assert!(err.contains("must be a /96"), "{}", err);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use bytes::Bytes;
+use common::rid;
+use network_simulator::pacing::{egress_time, EgressPacer};
+use network_simulator::packet::parse;
+use network_simulator::processor::process_packet;
+use network_simulator::simulation::with_paced_delays;
+use network_simulator::sojourn::Sojourn;
+use network_simulator::Destination;
+use tokio::time::{Duration, Instant};
+
+const PACKET: &str = "4500001400000000401100000a0000020a000102";
+
+#[test]
+fn test_pacer_releases_in_egress_order() {
+    let start = Instant::now();
+    let at = |ms: u64| start + Duration::from_millis(ms);
+    let mut pacer = EgressPacer::new();
+    pacer.schedule(at(10), Bytes::from(vec![1]));
+    pacer.schedule(at(20), Bytes::from(vec![2]));
+    pacer.schedule(at(10), Bytes::from(vec![3]));
+    // Jitter made this one faster: it overtakes the packets still held.
+    pacer.schedule(at(5), Bytes::from(vec![4]));
+    assert_eq!(pacer.len(), 4);
+    assert_eq!(pacer.next_deadline(), Some(at(5)));
+    assert!(pacer.due(at(4)).is_empty());
+    assert_eq!(pacer.due(at(10)), vec![vec![4], vec![1], vec![3]]);
+    assert_eq!(pacer.next_deadline(), Some(at(20)));
+    assert_eq!(pacer.due(at(30)), vec![vec![2]]);
+    assert!(pacer.is_empty());
+    assert_eq!(pacer.summary(), "released=4, held=0, max_backlog=4");
+}
+
+#[test]
+fn test_egress_time_adds_sojourn() {
+    let start = Instant::now();
+    let sojourn = Sojourn {
+        propagation_ms: 10.0,
+        queueing_ms: 2.5,
+        jitter_ms: -0.5,
+        processing_ms: 1.0,
+    };
+    assert_eq!(
+        egress_time(start, &sojourn),
+        start + Duration::from_millis(13)
+    );
+    // A jitter larger than the rest never schedules into the past.
+    let early = Sojourn {
+        jitter_ms: -20.0,
+        ..sojourn
+    };
+    assert_eq!(egress_time(start, &early), start);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_paced_processing_accounts_without_sleeping() {
+    // 0.16 Mbps serialises the 20-byte packet in 1 ms.
+    let cfg = common::line(
+        "",
+        &["", "", ""],
+        &["delay_ms = 10", "delay_ms = 5, bandwidth_mbps = 0.16"],
+        "",
+    );
+    let mut fabric = network_simulator::build_fabric(&cfg);
+    let tables = network_simulator::compute_routing_tables(&cfg);
+    let packet = parse(&hex::decode(PACKET).unwrap()).unwrap();
+
+    let start = Instant::now();
+    let mut totals = Vec::new();
+    for _ in 0..2 {
+        let processed = with_paced_delays(
+            true,
+            process_packet(
+                &mut fabric,
+                &tables,
+                rid("Rx0y0"),
+                packet.clone(),
+                Destination::TunB,
+            ),
+        )
+        .await;
+        assert_eq!(processed.raw[8], 62);
+        totals.push(fabric.last_sojourn.unwrap().total_ms());
+    }
+    assert_eq!(Instant::now(), start, "paced processing must not sleep");
+    // The second packet queues behind the first on the slow link, as it would unpaced.
+    assert_eq!(totals, [16.0, 17.0]);
+
+    // Without pacing the same packet takes its latency in real (paused) time.
+    let start = Instant::now();
+    process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        packet,
+        Destination::TunB,
+    )
+    .await;
+    assert!(Instant::now() - start >= Duration::from_millis(16));
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::rid;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::PacketMeta;
+use network_simulator::processor::process_packet;
+use network_simulator::routing::{compute_routing, Destination};
+use network_simulator::topology::{Fabric, LinkConfig, LinkId, Router};
+use tokio::time::Instant;
+
+/// Rx0y0 — Rx0y1 — Rx0y2, each link `delay_ms` long; the last one loses `loss_percent`.
+fn line(delay_ms: u32, loss_percent: f32) -> Fabric {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx0y1", "Rx0y2"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    let cfg = LinkConfig {
+        delay_ms,
+        ..Default::default()
+    };
+    fabric.add_link(&rid("Rx0y0"), &rid("Rx0y1"), cfg.clone());
+    fabric.add_link(
+        &rid("Rx0y1"),
+        &rid("Rx0y2"),
+        LinkConfig {
+            loss_percent,
+            ..cfg
+        },
+    );
+    fabric
+}
+
+fn udp() -> PacketMeta {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap()
+}
+
+fn link(a: &str, b: &str) -> LinkId {
+    LinkId {
+        a: rid(a),
+        b: rid(b),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_delivered_packet_carries_its_path() {
+    let mut fabric = line(5, 0.0);
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx0y2"));
+    let entered = Instant::now();
+    let out = process_packet(&mut fabric, &tables, rid("Rx0y0"), udp(), Destination::TunB).await;
+    let path = out.annotation.expect("annotated");
+    assert_eq!(path.ingress_at, entered);
+    assert_eq!(path.routers, vec![rid("Rx0y0"), rid("Rx0y1"), rid("Rx0y2")]);
+    assert_eq!(
+        path.links,
+        vec![link("Rx0y0", "Rx0y1"), link("Rx0y1", "Rx0y2")]
+    );
+    assert_eq!(path.delay.propagation_ms, 10.0);
+    assert_eq!(path.delay.total_ms(), 10.0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_dropped_packet_path_ends_where_it_was_lost() {
+    let mut fabric = line(0, 100.0);
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx0y2"));
+    let out = process_packet(&mut fabric, &tables, rid("Rx0y0"), udp(), Destination::TunB).await;
+    let path = out.annotation.expect("annotated");
+    assert_eq!(path.routers, vec![rid("Rx0y0"), rid("Rx0y1")]);
+    assert_eq!(path.links, vec![link("Rx0y0", "Rx0y1")]);
+    assert_eq!(path.delay.total_ms(), 0.0);
+}
@@ -0,0 +1,131 @@
+use network_simulator::packet::builder::{PacketBuilder, TCP_SYN};
+use network_simulator::packet::{
+    calculate_ipv4_checksum, parse, transport_offset, update_tcp_checksum, update_udp_checksum,
+};
+use std::net::IpAddr;
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+/// One's complement sum of `data`; a message with a valid Internet checksum sums to 0xFFFF.
+fn ones_sum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]) as u32)
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum as u16
+}
+
+#[test]
+fn test_ipv4_tcp_fields_and_checksums() {
+    let packet = PacketBuilder::new(ip("10.0.0.2"), ip("10.0.1.2"))
+        .ttl(7)
+        .tos(0xB8)
+        .identification(0x1234)
+        .dont_fragment(true)
+        .tcp(40000, 443)
+        .tcp_seq(100, 200)
+        .tcp_flags(TCP_SYN, 1024)
+        .payload(vec![1, 2, 3])
+        .build()
+        .unwrap();
+    let raw = &packet.raw;
+    assert_eq!(raw.len(), 20 + 20 + 3);
+    assert_eq!(u16::from_be_bytes([raw[2], raw[3]]), 43);
+    assert_eq!((raw[1], raw[8], raw[9]), (0xB8, 7, 6));
+    assert_eq!(&raw[4..8], &[0x12, 0x34, 0x40, 0]);
+    assert_eq!(
+        u16::from_be_bytes([raw[10], raw[11]]),
+        calculate_ipv4_checksum(raw)
+    );
+    assert_eq!(
+        u32::from_be_bytes([raw[24], raw[25], raw[26], raw[27]]),
+        100
+    );
+    assert_eq!(
+        (raw[33], u16::from_be_bytes([raw[34], raw[35]])),
+        (TCP_SYN, 1024)
+    );
+    let mut again = raw.to_vec();
+    update_tcp_checksum(&mut again);
+    assert_eq!(&again, raw);
+    let parsed = parse(raw).unwrap();
+    assert_eq!(
+        (
+            parsed.src_port,
+            parsed.dst_port,
+            parsed.protocol,
+            parsed.ttl
+        ),
+        (
+            packet.src_port,
+            packet.dst_port,
+            packet.protocol,
+            packet.ttl
+        )
+    );
+}
+
+#[test]
+fn test_ipv6_udp_with_extension_headers() {
+    let packet = PacketBuilder::new(ip("2001:db8::2"), ip("2001:db8:1::2"))
+        .flow_label(0xABCDE)
+        .extension_header(0)
+        .extension_header(60)
+        .udp(5000, 53)
+        .payload(vec![0xAA; 10])
+        .build()
+        .unwrap();
+    let raw = &packet.raw;
+    assert_eq!(&raw[..4], &[0x60, 0x0A, 0xBC, 0xDE]);
+    assert_eq!(u16::from_be_bytes([raw[4], raw[5]]), 16 + 8 + 10);
+    assert_eq!((raw[6], raw[40], raw[48]), (0, 60, 17));
+    assert_eq!(transport_offset(raw), Some(56));
+    assert_eq!(u16::from_be_bytes([raw[60], raw[61]]), 18);
+    let mut again = raw.to_vec();
+    update_udp_checksum(&mut again);
+    assert_eq!(&again, raw);
+    assert_eq!((packet.protocol, packet.dst_port), (17, 53));
+}
+
+#[test]
+fn test_echo_requests_have_valid_checksums() {
+    let v4 = PacketBuilder::new(ip("10.0.0.2"), ip("10.0.1.2"))
+        .echo_request(0x77, 3)
+        .payload(b"ping".to_vec())
+        .build()
+        .unwrap();
+    assert_eq!((v4.protocol, v4.raw[20]), (1, 8));
+    assert_eq!(&v4.raw[24..28], &[0, 0x77, 0, 3]);
+    assert_eq!(ones_sum(&v4.raw[20..]), 0xFFFF);
+
+    let v6 = PacketBuilder::new(ip("2001:db8::2"), ip("2001:db8:1::2"))
+        .echo_request(0x77, 3)
+        .build()
+        .unwrap();
+    assert_eq!((v6.protocol, v6.raw[6], v6.raw[40]), (58, 58, 128));
+    // ICMPv6 covers the pseudo-header: addresses, length and Next Header.
+    let mut pseudo = v6.raw[8..40].to_vec();
+    pseudo.extend_from_slice(&(v6.raw.len() as u32 - 40).to_be_bytes());
+    pseudo.extend_from_slice(&[0, 0, 0, 58]);
+    pseudo.extend_from_slice(&v6.raw[40..]);
+    assert_eq!(ones_sum(&pseudo), 0xFFFF);
+}
+
+#[test]
+fn test_invalid_packets_rejected() {
+    let mixed = PacketBuilder::new(ip("10.0.0.2"), ip("2001:db8::2")).build();
+    assert!(mixed.unwrap_err().contains("address families"));
+    let headers = PacketBuilder::new(ip("10.0.0.2"), ip("10.0.1.2"))
+        .extension_header(0)
+        .build();
+    assert!(headers.is_err());
+    let huge = PacketBuilder::new(ip("10.0.0.2"), ip("10.0.1.2"))
+        .payload(vec![0; 65535])
+        .build();
+    assert!(huge.unwrap_err().contains("too long"));
+}
@@ -0,0 +1,85 @@
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::{diff, FieldChange};
+
+fn udp(src: &str, ttl: u8, payload: &[u8]) -> Vec<u8> {
+    PacketBuilder::new(src.parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .ttl(ttl)
+        .udp(1000, 2000)
+        .payload(payload.to_vec())
+        .build()
+        .unwrap()
+        .raw
+        .to_vec()
+}
+
+#[test]
+fn test_identical_packets() {
+    let packet = udp("10.0.0.2", 64, b"abcd");
+    let d = diff(&packet, &packet);
+    assert!(d.is_empty());
+    assert_eq!(d.to_string(), "identical\n");
+}
+
+#[test]
+fn test_forwarded_packet_shows_ttl_and_checksum() {
+    let d = diff(&udp("10.0.0.2", 64, b"abcd"), &udp("10.0.0.2", 62, b"abcd"));
+    let fields: Vec<&str> = d.fields.iter().map(|c| c.field).collect();
+    assert_eq!(fields, ["ttl", "header_checksum"]);
+    assert_eq!(
+        d.field("ttl"),
+        Some(&FieldChange {
+            field: "ttl",
+            before: "64".into(),
+            after: "62".into(),
+        })
+    );
+    assert!(d.payload_bytes.is_empty());
+}
+
+#[test]
+fn test_addresses_and_payload_bytes() {
+    let d = diff(
+        &udp("10.0.0.2", 64, b"abcd"),
+        &udp("10.0.0.9", 64, b"abXdY"),
+    );
+    assert_eq!(d.field("src").unwrap().after, "10.0.0.9");
+    assert!(d.field("transport_checksum").is_some());
+    assert!(d.field("udp_length").is_some());
+    assert_eq!(d.payload_bytes, [2]);
+    assert_eq!(d.payload_len, Some((4, 5)));
+    let text = d.to_string();
+    assert!(text.contains("src: 10.0.0.2 -> 10.0.0.9\n"), "{}", text);
+    assert!(text.contains("payload length: 4 -> 5\n"), "{}", text);
+    assert!(
+        text.contains("payload bytes differ at offsets 2\n"),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn test_ipv6_hop_limit_and_flow_label() {
+    let build = |hops: u8, label: u32| {
+        PacketBuilder::new(
+            "2001:db8::2".parse().unwrap(),
+            "2001:db8:1::2".parse().unwrap(),
+        )
+        .ttl(hops)
+        .flow_label(label)
+        .udp(1000, 2000)
+        .build()
+        .unwrap()
+        .raw
+        .to_vec()
+    };
+    let d = diff(&build(64, 1), &build(63, 2));
+    let fields: Vec<&str> = d.fields.iter().map(|c| c.field).collect();
+    assert_eq!(fields, ["flow_label", "hop_limit"]);
+}
+
+#[test]
+fn test_non_ip_bytes_compared_as_payload() {
+    let d = diff(&[1, 2, 3], &[1, 9, 3]);
+    assert!(d.fields.is_empty());
+    assert_eq!(d.payload_bytes, [1]);
+}
@@ -0,0 +1,84 @@
+use network_simulator::ddos::{build_flood_packet, FloodKind};
+use network_simulator::packet::{explain, hex_dump};
+
+#[test]
+fn test_explain_ipv4_udp_with_checksums() {
+    let packet = build_flood_packet(
+        FloodKind::Udp,
+        "10.0.0.2".parse().unwrap(),
+        "10.0.1.2".parse().unwrap(),
+        40000,
+        53,
+        60,
+    );
+    let text = explain(&packet.raw);
+    assert!(text.starts_with("IPv4 10.0.0.2 -> 10.0.1.2\n"), "{}", text);
+    assert!(
+        text.contains("protocol 17 (UDP), header checksum 0x"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("(valid)\nUDP 40000 -> 53, length 40\n"),
+        "{}",
+        text
+    );
+    assert!(!text.contains("INVALID"), "{}", text);
+    assert!(text.contains("60 bytes:\n0000  45 "), "{}", text);
+}
+
+#[test]
+fn test_explain_flags_corrupted_checksums() {
+    let mut packet = build_flood_packet(
+        FloodKind::TcpSyn,
+        "10.0.0.2".parse().unwrap(),
+        "10.0.1.2".parse().unwrap(),
+        40000,
+        80,
+        40,
+    );
+    let text = explain(&packet.raw);
+    assert!(text.contains("TCP 40000 -> 80, flags [SYN]"), "{}", text);
+    assert!(text.contains("header checksum 0x65cd (valid)"), "{}", text);
+    // Flood segments leave the TCP checksum zero.
+    assert!(text.contains("  checksum 0x0000 (INVALID)"), "{}", text);
+    // Rewriting the TTL without fixing the header checksum is reported as well.
+    packet.raw_mut()[8] -= 1;
+    let text = explain(&packet.raw);
+    assert!(
+        text.contains("ttl 63, protocol 6 (TCP), header checksum 0x65cd (INVALID)"),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn test_explain_ipv6_icmp_echo() {
+    let packet = build_flood_packet(
+        FloodKind::Icmp,
+        "2001:db8::1".parse().unwrap(),
+        "2001:db8:1::2".parse().unwrap(),
+        7,
+        0,
+        64,
+    );
+    let text = explain(&packet.raw);
+    assert!(
+        text.starts_with("IPv6 2001:db8::1 -> 2001:db8:1::2\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("next header 58 (ICMPv6)"), "{}", text);
+    assert!(text.contains("ICMPv6 type 128, code 0"), "{}", text);
+    assert!(!text.contains("INVALID"), "{}", text);
+}
+
+#[test]
+fn test_explain_truncated_and_unknown() {
+    assert!(explain(&[0x45, 0, 0, 20]).starts_with("IPv4 truncated: 4 of 20"));
+    assert!(explain(&[0x20]).starts_with("unsupported IP version 2"));
+    assert_eq!(
+        hex_dump(b"AB\x00"),
+        format!("0000  41 42 00{}  AB.\n", " ".repeat(39))
+    );
+}
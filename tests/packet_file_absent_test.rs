@@ -21,7 +21,7 @@ Rx0y0 = {}
 Rx0y0_Rx0y0 = { delay_ms = 0 }
 "#;
     let cfg_path = "tests/tmp_config_no_packet.toml";
-    fs::write(&cfg_path, cfg_content).expect("write config");
+    fs::write(cfg_path, cfg_content).expect("write config");
 
     let cfg_str = fs::read_to_string(cfg_path).expect("read config");
     let cfg: SimulatorConfig = toml::from_str(&cfg_str).expect("parse config");
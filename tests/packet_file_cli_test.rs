@@ -39,11 +39,11 @@ netmask = "255.255.255.0"
 packet_file = "unused_path"
 "#;
     let cfg_path = "tests/tmp_config_packet.toml";
-    fs::write(&cfg_path, cfg_content).expect("write config");
+    fs::write(cfg_path, cfg_content).expect("write config");
 
     let mut cmd = cargo_bin_cmd!("network-simulator");
     cmd.arg("--config")
-        .arg(&cfg_path)
+        .arg(cfg_path)
         .arg("--packet-file")
         .arg(&packet_path)
         .arg("--tun-name")
@@ -53,5 +53,5 @@ packet_file = "unused_path"
         .arg("--tun-netmask")
         .arg("255.255.255.0");
     cmd.assert().success();
-    let _ = fs::remove_file(&cfg_path);
+    let _ = fs::remove_file(cfg_path);
 }
@@ -29,11 +29,11 @@ Rx5y5 = {}
 Rx0y0_Rx5y5 = { delay_ms = 0 }
 "#;
     let cfg_path = "tests/tmp_config_empty_packet.toml";
-    fs::write(&cfg_path, cfg_content).expect("write config");
+    fs::write(cfg_path, cfg_content).expect("write config");
 
     let mut cmd = cargo_bin_cmd!("network-simulator");
     cmd.arg("--config")
-        .arg(&cfg_path)
+        .arg(cfg_path)
         .arg("--packet-file")
         .arg(&empty_path);
     // Should succeed even if the packet file is empty
@@ -24,11 +24,11 @@ Rx0y0 = {}
 Rx0y0_Rx0y0 = { delay_ms = 0 }
 "#;
     let cfg_path = "tests/tmp_missing_config.toml";
-    fs::write(&cfg_path, cfg_content).expect("write config");
+    fs::write(cfg_path, cfg_content).expect("write config");
 
     let mut cmd = cargo_bin_cmd!("network-simulator");
     cmd.arg("--config")
-        .arg(&cfg_path)
+        .arg(cfg_path)
         .arg("--packet-file")
         .arg(missing_path);
     // Expect the command to fail because the packet file cannot be opened
@@ -34,11 +34,11 @@ Rx5y5 = {}
 Rx0y0_Rx5y5 = { delay_ms = 0 }
 "#;
     let cfg_path = "tests/tmp_config_multi_ingress.toml";
-    fs::write(&cfg_path, cfg_content).expect("write config");
+    fs::write(cfg_path, cfg_content).expect("write config");
 
     let mut cmd = cargo_bin_cmd!("network-simulator");
     cmd.arg("--config")
-        .arg(&cfg_path)
+        .arg(cfg_path)
         .arg("--packet-file")
         .arg(&packet_path);
     cmd.assert().success();
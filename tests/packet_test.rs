@@ -1,4 +1,7 @@
-use network_simulator::packet::parse;
+use network_simulator::packet::builder::{PacketBuilder, TCP_ACK, TCP_SYN};
+use network_simulator::packet::{
+    calculate_ipv4_checksum, fragment_ipv4, parse, transport_detail, IcmpEcho, TransportDetail,
+};
 
 #[test]
 fn test_ipv4_parse_minimal() {
@@ -77,3 +80,189 @@ fn test_ipv4_udp_ports() {
     assert_eq!(meta.src_port, 53);
     assert_eq!(meta.dst_port, 1234);
 }
+
+#[test]
+fn test_tcp_checksum_follows_port_rewrite() {
+    use network_simulator::packet::{explain, update_tcp_checksum, update_udp_checksum};
+    let mut data = vec![
+        0x45, 0x00, 0x00, 0x2c, // version/IHL, TOS, total length 44
+        0x00, 0x00, 0x00, 0x00, // ID, flags/frag
+        0x40, 0x06, 0x00, 0x00, // TTL=64, protocol=6 (TCP), checksum
+        10, 0, 0, 1, // src IP 10.0.0.1
+        10, 0, 0, 2, // dst IP 10.0.0.2
+        0x30, 0x39, 0x00, 0x50, // ports 12345 -> 80
+        0, 0, 0, 1, 0, 0, 0, 0, // seq, ack
+        0x50, 0x18, 0xff, 0xff, 0, 0, 0, 0, // offset, PSH|ACK, window, checksum, urgent
+        b'd', b'a', b't', b'a', // payload
+    ];
+    network_simulator::packet::update_ipv4_checksum(&mut data);
+    update_tcp_checksum(&mut data);
+    assert!(explain(&data).contains("checksum 0x") && !explain(&data).contains("INVALID"));
+    // Rewrite the source port: the old checksum no longer holds until it is recomputed.
+    data[20..22].copy_from_slice(&4242u16.to_be_bytes());
+    assert!(explain(&data).contains("INVALID"));
+    update_udp_checksum(&mut data);
+    assert!(explain(&data).contains("INVALID"), "not UDP, left alone");
+    update_tcp_checksum(&mut data);
+    assert!(!explain(&data).contains("INVALID"), "{}", explain(&data));
+    // Ethernet padding after the IP payload is not summed.
+    data.extend_from_slice(&[0xee; 6]);
+    update_tcp_checksum(&mut data);
+    data.truncate(44);
+    assert!(!explain(&data).contains("INVALID"));
+}
+
+#[test]
+fn test_udp_checksum_behind_ipv6_extension_header() {
+    use network_simulator::packet::{explain, update_udp_checksum};
+    let mut data = vec![
+        0x60, 0x00, 0x00, 0x00, // Version=6
+        0x00, 0x14, 0x00, 0x40, // Payload Length=20, Next Header=0 (Hop-by-Hop), Hop Limit=64
+    ];
+    data.extend_from_slice(
+        &"2001:db8::1"
+            .parse::<std::net::Ipv6Addr>()
+            .unwrap()
+            .octets(),
+    );
+    data.extend_from_slice(
+        &"2001:db8::2"
+            .parse::<std::net::Ipv6Addr>()
+            .unwrap()
+            .octets(),
+    );
+    data.extend_from_slice(&[17, 0, 1, 4, 0, 0, 0, 0]); // Hop-by-Hop: UDP next, PadN
+    data.extend_from_slice(&[0x13, 0x88, 0x00, 0x35, 0x00, 0x0c, 0, 0, 1, 2, 3, 4]);
+    update_udp_checksum(&mut data);
+    assert_ne!(&data[54..56], &[0, 0]);
+    let text = explain(&data);
+    assert!(
+        text.contains("UDP 5000 -> 53") && !text.contains("INVALID"),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn test_dscp_ecn_parsed_and_rewritten() {
+    // EF with ECT(0) over IPv4.
+    let v4 = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .tos(0xba)
+        .udp(1000, 2000)
+        .build()
+        .unwrap();
+    let mut meta = parse(&v4.raw).unwrap();
+    assert_eq!((meta.dscp, meta.ecn), (46, 2));
+    meta.set_ecn(3);
+    meta.set_dscp(10);
+    assert_eq!(meta.raw[1], (10 << 2) | 3);
+    let checksum = u16::from_be_bytes([meta.raw[10], meta.raw[11]]);
+    assert_eq!(checksum, calculate_ipv4_checksum(&meta.raw));
+    let reparsed = parse(&meta.raw).unwrap();
+    assert_eq!((reparsed.dscp, reparsed.ecn), (10, 3));
+
+    // The Traffic Class straddles the first two bytes of an IPv6 header.
+    let v6 = PacketBuilder::new(
+        "2001:db8::1".parse().unwrap(),
+        "2001:db8::2".parse().unwrap(),
+    )
+    .tos(0xb9)
+    .udp(1000, 2000)
+    .build()
+    .unwrap();
+    let mut meta = parse(&v6.raw).unwrap();
+    assert_eq!((meta.dscp, meta.ecn), (46, 1));
+    let flow_label = [meta.raw[1] & 0x0f, meta.raw[2], meta.raw[3]];
+    meta.set_dscp(0);
+    meta.set_ecn(3);
+    assert_eq!(meta.raw[0], 0x60);
+    assert_eq!(meta.raw[1] >> 4, 3);
+    assert_eq!([meta.raw[1] & 0x0f, meta.raw[2], meta.raw[3]], flow_label);
+    let reparsed = parse(&meta.raw).unwrap();
+    assert_eq!((reparsed.dscp, reparsed.ecn), (0, 3));
+}
+
+#[test]
+fn test_tcp_and_udp_detail_parsed() {
+    let syn = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .tcp(40000, 80)
+        .tcp_seq(1000, 0)
+        .tcp_flags(TCP_SYN, 1024)
+        .build()
+        .unwrap();
+    let meta = parse(&syn.raw).unwrap();
+    assert_eq!(
+        meta.transport,
+        Some(TransportDetail::Tcp {
+            flags: TCP_SYN,
+            seq: 1000,
+            ack: 0
+        })
+    );
+    assert!(meta.is_tcp_syn());
+    let syn_ack = PacketBuilder::new("10.0.1.2".parse().unwrap(), "10.0.0.2".parse().unwrap())
+        .tcp(80, 40000)
+        .tcp_seq(5000, 1001)
+        .tcp_flags(TCP_SYN | TCP_ACK, 1024)
+        .build()
+        .unwrap();
+    assert_eq!(syn_ack.tcp_flags(), Some(TCP_SYN | TCP_ACK));
+    assert!(!syn_ack.is_tcp_syn());
+
+    let udp = PacketBuilder::new(
+        "2001:db8::1".parse().unwrap(),
+        "2001:db8::2".parse().unwrap(),
+    )
+    .udp(1000, 53)
+    .payload(vec![0; 12])
+    .build()
+    .unwrap();
+    let meta = parse(&udp.raw).unwrap();
+    assert_eq!(meta.transport, Some(TransportDetail::Udp { length: 20 }));
+    assert_eq!(meta.tcp_flags(), None);
+
+    // Only the first fragment carries the transport header.
+    let big = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .payload(vec![0; 100])
+        .build()
+        .unwrap();
+    let fragments = fragment_ipv4(&big.raw, 60).unwrap();
+    assert_eq!(
+        transport_detail(&fragments[0]),
+        Some(TransportDetail::Udp { length: 108 })
+    );
+    assert_eq!(transport_detail(&fragments[1]), None);
+    assert_eq!(parse(&fragments[1]).unwrap().transport, None);
+}
+
+#[test]
+fn test_icmp_type_and_echo_parsed() {
+    let ping = PacketBuilder::new(
+        "2001:db8::1".parse().unwrap(),
+        "2001:db8::2".parse().unwrap(),
+    )
+    .echo_request(0x1234, 9)
+    .build()
+    .unwrap();
+    let meta = parse(&ping.raw).unwrap();
+    assert_eq!(meta.icmp_type_code(), Some((128, 0)));
+    assert_eq!(meta.icmp_echo(), Some(IcmpEcho { id: 0x1234, seq: 9 }));
+    assert_eq!((meta.src_port, meta.dst_port), (0, 0));
+
+    // Other messages have a type and code but no echo fields.
+    let unreachable = PacketBuilder::new("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap())
+        .icmp(3, 3, [0; 4])
+        .build()
+        .unwrap();
+    assert_eq!(
+        parse(&unreachable.raw).unwrap().transport,
+        Some(TransportDetail::Icmp {
+            icmp_type: 3,
+            code: 3,
+            echo: None
+        })
+    );
+    assert_eq!(unreachable.icmp_echo(), None);
+    assert_eq!(meta.tcp_flags(), None);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::DropCaptureConfig;
+use network_simulator::drops::{DropCapture, DropReason};
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::PacketMeta;
+use network_simulator::processor::process_packet;
+use network_simulator::routing::{compute_routing, Destination};
+use network_simulator::topology::{Fabric, LinkConfig, Router};
+
+/// Rx0y0 — Rx0y1 — Rx0y2 with paranoid checking on.
+fn line() -> Fabric {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx0y1", "Rx0y2"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    fabric.add_link(&rid("Rx0y0"), &rid("Rx0y1"), LinkConfig::default());
+    fabric.add_link(&rid("Rx0y1"), &rid("Rx0y2"), LinkConfig::default());
+    fabric.paranoid = true;
+    fabric
+}
+
+fn udp() -> PacketMeta {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_consistent_tables_pass() {
+    let mut fabric = line();
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx0y2"));
+    let out = process_packet(&mut fabric, &tables, rid("Rx0y0"), udp(), Destination::TunB).await;
+    assert_eq!(out.ttl, 62);
+}
+
+#[tokio::test]
+async fn test_stale_next_hop_dropped_and_reported() {
+    let mut fabric = line();
+    fabric.drops = Some(DropCapture::new(&DropCaptureConfig::default()));
+    let mut tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx0y2"));
+    // A stale entry at Rx0y1 sends the packet back where it came from.
+    tables.get_mut(&rid("Rx0y1")).unwrap().tun_b.next_hop = rid("Rx0y0");
+    process_packet(&mut fabric, &tables, rid("Rx0y0"), udp(), Destination::TunB).await;
+    assert_eq!(fabric.paranoid_violations.len(), 1);
+    let report = &fabric.paranoid_violations[0];
+    assert!(
+        report.starts_with(
+            "paranoid: Rx0y1 forwarded to Rx0y0 towards TunB, but the distance does not decrease (1 -> 2)"
+        ),
+        "{}",
+        report
+    );
+    assert!(report.contains("path: Rx0y0 -> Rx0y1"), "{}", report);
+    let drops = fabric.drops.as_ref().unwrap();
+    let reasons: Vec<_> = drops.recent(&rid("Rx0y1")).map(|d| d.reason).collect();
+    assert_eq!(reasons, [DropReason::RoutingMismatch]);
+    let delivered = fabric
+        .get_router(&rid("Rx0y2"))
+        .unwrap()
+        .stats
+        .packets_delivered;
+    assert_eq!(delivered, 0);
+}
+
+#[test]
+fn test_paranoid_from_config() {
+    let cfg = common::line("paranoid = true", &["", ""], &[""], "");
+    assert!(network_simulator::build_fabric(&cfg).paranoid);
+    let cfg = common::line("", &["", ""], &[""], "");
+    assert!(!network_simulator::build_fabric(&cfg).paranoid);
+}
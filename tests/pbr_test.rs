@@ -0,0 +1,127 @@
+mod common;
+
+use network_simulator::config::SimulatorConfig;
+use network_simulator::ddos::{build_flood_packet, FloodKind};
+use network_simulator::pbr::PbrAction;
+use network_simulator::processor::process_packet;
+use network_simulator::routing::Destination;
+use network_simulator::topology::RouterId;
+
+// Two paths from Rx0y0 to Rx1y1: the short one via Rx0y1 and a longer one via Rx1y0/Rx2y0.
+fn scenario(rx0y0_opts: &str, rx0y1_opts: &str) -> SimulatorConfig {
+    common::scenario(
+        "",
+        &[
+            ("Rx0y0", rx0y0_opts),
+            ("Rx0y1", rx0y1_opts),
+            ("Rx1y0", ""),
+            ("Rx2y0", ""),
+            ("Rx1y1", ""),
+        ],
+        &[
+            ("Rx0y0_Rx0y1", ""),
+            ("Rx0y1_Rx1y1", ""),
+            ("Rx0y0_Rx1y0", ""),
+            ("Rx1y0_Rx2y0", ""),
+            ("Rx2y0_Rx1y1", ""),
+        ],
+        "",
+    )
+}
+
+fn udp(dst_port: u16) -> network_simulator::packet::PacketMeta {
+    build_flood_packet(
+        FloodKind::Udp,
+        "10.0.0.2".parse().unwrap(),
+        "10.0.1.2".parse().unwrap(),
+        40000,
+        dst_port,
+        60,
+    )
+}
+
+fn forwarded(fabric: &network_simulator::topology::Fabric, id: &str) -> u64 {
+    fabric
+        .get_router(&RouterId(id.into()))
+        .unwrap()
+        .stats
+        .packets_forwarded
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pbr_next_hop_steers_matching_traffic() {
+    let cfg = scenario(
+        r#"pbr = [ { match = "udp and dst port 5060", next_hop = "Rx1y0" } ]"#,
+        "",
+    );
+    let mut fabric = network_simulator::build_fabric(&cfg);
+    let tables = network_simulator::compute_routing_tables(&cfg);
+    let a = RouterId("Rx0y0".into());
+    process_packet(
+        &mut fabric,
+        &tables,
+        a.clone(),
+        udp(5060),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(forwarded(&fabric, "Rx1y0"), 1);
+    assert_eq!(forwarded(&fabric, "Rx0y1"), 0);
+    process_packet(&mut fabric, &tables, a.clone(), udp(80), Destination::TunB).await;
+    assert_eq!(forwarded(&fabric, "Rx1y0"), 1);
+    assert_eq!(forwarded(&fabric, "Rx0y1"), 1);
+    let egress = fabric.get_router(&RouterId("Rx1y1".into())).unwrap();
+    assert_eq!(egress.stats.packets_delivered, 2);
+    assert_eq!(fabric.get_router(&a).unwrap().pbr[0].hits, 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pbr_destination_redirects_to_other_edge() {
+    let cfg = scenario(
+        "",
+        r#"pbr = [ { match = "udp and dst port 53", destination = "tun_a" } ]"#,
+    );
+    let mut fabric = network_simulator::build_fabric(&cfg);
+    let tables = network_simulator::compute_routing_tables(&cfg);
+    let a = RouterId("Rx0y0".into());
+    process_packet(&mut fabric, &tables, a.clone(), udp(53), Destination::TunB).await;
+    // The packet turns around at Rx0y1 and leaves at the tun_a edge.
+    assert_eq!(fabric.get_router(&a).unwrap().stats.packets_delivered, 1);
+    let b = fabric.get_router(&RouterId("Rx1y1".into())).unwrap();
+    assert_eq!(b.stats.packets_delivered, 0);
+    let rx0y1 = fabric.get_router(&RouterId("Rx0y1".into())).unwrap();
+    assert_eq!(
+        rx0y1.pbr[0].action,
+        PbrAction::Destination(Destination::TunA)
+    );
+    assert_eq!(rx0y1.pbr[0].hits, 1);
+}
+
+#[test]
+fn test_pbr_rules_validated() {
+    let with_interfaces = |opts: &str| {
+        let mut cfg = scenario(opts, "");
+        cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+        cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+        cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+        cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+        cfg
+    };
+    with_interfaces(r#"pbr = [ { match = "tcp", next_hop = "Rx0y1" } ]"#)
+        .validate()
+        .expect("valid");
+    let err = with_interfaces(r#"pbr = [ { match = "tcp", next_hop = "Rx1y1" } ]"#)
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("not a neighbour"), "{}", err);
+    let err = with_interfaces(
+        r#"pbr = [ { match = "tcp", next_hop = "Rx0y1", destination = "tun_a" } ]"#,
+    )
+    .validate()
+    .unwrap_err();
+    assert!(err.contains("exactly one"), "{}", err);
+    let err = with_interfaces(r#"pbr = [ { match = "tcp", destination = "tun_c" } ]"#)
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("tun_c"), "{}", err);
+}
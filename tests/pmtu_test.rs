@@ -0,0 +1,122 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::pmtu::{self, build_probe, PmtuOptions};
+use network_simulator::topology::LinkId;
+use std::net::IpAddr;
+
+/// Line Rx0y0 - Rx0y1 - Rx0y2 with a narrower second link.
+fn line(mtu_a: u32, mtu_b: u32) -> SimulatorConfig {
+    common::line(
+        "",
+        &["", "", ""],
+        &[&format!("mtu = {}", mtu_a), &format!("mtu = {}", mtu_b)],
+        "",
+    )
+}
+
+fn opts(max: u32) -> PmtuOptions {
+    PmtuOptions { min: None, max }
+}
+
+#[test]
+fn test_probe_has_df_and_requested_size() {
+    let probe = build_probe(
+        "10.0.0.1".parse().unwrap(),
+        "10.0.1.2".parse().unwrap(),
+        1400,
+    );
+    assert_eq!(probe.raw.len(), 1400);
+    assert_eq!(probe.raw[6] & 0x40, 0x40);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pmtu_finds_bottleneck_link() {
+    let cfg = line(1500, 1400);
+    let dst: IpAddr = "10.0.1.2".parse().unwrap();
+    let report = pmtu::run(&cfg, dst, &opts(9000)).await.expect("pmtu");
+    assert_eq!(report.path_mtu, 1400);
+    assert_eq!(report.reported_by, Some(rid("Rx0y1")));
+    assert_eq!(
+        report.bottleneck,
+        Some(LinkId::new(rid("Rx0y1"), rid("Rx0y2")))
+    );
+    assert!(report.probes.contains(&(1400, true)));
+    assert!(report.probes.contains(&(1401, false)));
+    assert!(report.render(dst).contains("path MTU to 10.0.1.2: 1400"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pmtu_reverse_direction_and_unlimited_path() {
+    let cfg = line(1500, 1400);
+    // Probes towards tun_a start at the tun_b ingress and hit the narrow link first.
+    let report = pmtu::run(&cfg, "10.0.0.9".parse().unwrap(), &opts(9000))
+        .await
+        .expect("pmtu");
+    assert_eq!(report.path_mtu, 1400);
+    assert_eq!(report.reported_by, Some(rid("Rx0y2")));
+
+    let report = pmtu::run(&cfg, "10.0.1.2".parse().unwrap(), &opts(1300))
+        .await
+        .expect("pmtu");
+    assert_eq!(report.path_mtu, 1300);
+    assert_eq!(report.probes, vec![(1300, true)]);
+    assert_eq!(report.bottleneck, None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pmtu_ipv6_and_bounds() {
+    let cfg = line(1500, 1400);
+    let report = pmtu::run(&cfg, "2001:db8::2".parse().unwrap(), &opts(9000))
+        .await
+        .expect("pmtu");
+    assert_eq!(report.path_mtu, 1400);
+    let err = pmtu::run(&cfg, "10.0.1.2".parse().unwrap(), &opts(50))
+        .await
+        .unwrap_err();
+    assert!(err.contains("below"), "{}", err);
+    let err = pmtu::run(
+        &line(1500, 100),
+        "2001:db8::2".parse().unwrap(),
+        &opts(9000),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.contains("1280"), "{}", err);
+    let err = pmtu::run(&cfg, "10.0.1.2".parse().unwrap(), &opts(65536))
+        .await
+        .unwrap_err();
+    assert!(err.contains("65535"), "{}", err);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pmtu_bottleneck_is_the_link_taken() {
+    // Rx0y1 has stub links with the same MTU as its egress link towards tun_b; only the one the
+    // probe was actually refused on is the bottleneck.
+    let cfg = common::scenario(
+        "",
+        &[
+            ("Rx0y0", ""),
+            ("Rx1y1", ""),
+            ("Rx2y1", ""),
+            ("Rx0y1", ""),
+            ("Rx0y2", ""),
+        ],
+        &[
+            ("Rx0y0_Rx0y1", "mtu = 1500"),
+            ("Rx0y1_Rx1y1", "mtu = 1400"),
+            ("Rx0y1_Rx2y1", "mtu = 1400"),
+            ("Rx0y1_Rx0y2", "mtu = 1400"),
+        ],
+        "",
+    );
+    let report = pmtu::run(&cfg, "10.0.1.2".parse().unwrap(), &opts(9000))
+        .await
+        .expect("pmtu");
+    assert_eq!(report.path_mtu, 1400);
+    assert_eq!(
+        report.bottleneck,
+        Some(LinkId::new(rid("Rx0y1"), rid("Rx0y2")))
+    );
+}
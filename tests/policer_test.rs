@@ -0,0 +1,175 @@
+mod common;
+
+use network_simulator::config::SimulatorConfig;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::PacketMeta;
+use network_simulator::policer::{Color, Policer, PolicerAction, PolicerConfig};
+use network_simulator::processor::process_packet;
+use network_simulator::routing::Destination;
+use network_simulator::topology::{Fabric, RouterId};
+use std::time::Duration;
+
+/// UDP packet of `len` bytes with the given DSCP.
+fn udp(len: usize, dscp: u8) -> PacketMeta {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .tos(dscp << 2)
+        .payload(vec![0; len - 28])
+        .build()
+        .unwrap()
+}
+
+fn config(table: &str) -> PolicerConfig {
+    toml::from_str(table).unwrap()
+}
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_two_rate_three_colors() {
+    // 8 Mbit/s = 1000 bytes per ms committed, 16 Mbit/s peak; 2000 / 4000 byte buckets.
+    let mut policer = Policer::from_config(&config(
+        "cir_mbps = 8.0\ncbs_bytes = 2000\npir_mbps = 16.0\npbs_bytes = 4000",
+    ))
+    .unwrap();
+    let colors: Vec<Color> = (0..5).map(|_| policer.meter(1000)).collect();
+    assert_eq!(
+        colors,
+        [
+            Color::Conform,
+            Color::Conform,
+            Color::Exceed,
+            Color::Exceed,
+            Color::Violate
+        ]
+    );
+    // 1 ms refills 1000 bytes committed and 2000 bytes peak.
+    tokio::time::advance(Duration::from_millis(1)).await;
+    assert_eq!(policer.meter(1000), Color::Conform);
+    assert_eq!(policer.meter(1000), Color::Exceed);
+    assert_eq!(policer.meter(1000), Color::Violate);
+    assert_eq!(policer.conformed.packets, 3);
+    assert_eq!(policer.exceeded.bytes, 3000);
+    assert_eq!(policer.violated.packets, 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_single_rate_never_violates() {
+    let mut policer = Policer::from_config(&config("cir_mbps = 8.0\ncbs_bytes = 1000")).unwrap();
+    assert_eq!(policer.meter(1000), Color::Conform);
+    for _ in 0..10 {
+        assert_eq!(policer.meter(1000), Color::Exceed);
+    }
+    assert_eq!(policer.violated.packets, 0);
+}
+
+fn delivered(fabric: &Fabric, id: &str) -> u64 {
+    fabric
+        .get_router(&RouterId(id.into()))
+        .unwrap()
+        .stats
+        .packets_delivered
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_ingress_policer_remarks_and_drops() {
+    let cfg = addressed(common::line(
+        "",
+        &[
+            r#"policers = [
+                { name = "voice", dscp = [46], cir_mbps = 8.0, cbs_bytes = 1000 },
+                { cir_mbps = 8.0, cbs_bytes = 1000, exceed = { remark = 8 }, violate = "drop" },
+            ]"#,
+            "",
+        ],
+        &[""],
+        "",
+    ));
+    assert!(cfg.validate().is_ok());
+    let mut fabric = network_simulator::build_fabric(&cfg);
+    let tables = network_simulator::compute_routing_tables(&cfg);
+    let a = RouterId("Rx0y0".into());
+
+    // Voice: one packet fits the bucket, the next one is dropped.
+    for _ in 0..2 {
+        process_packet(
+            &mut fabric,
+            &tables,
+            a.clone(),
+            udp(1000, 46),
+            Destination::TunB,
+        )
+        .await;
+    }
+    assert_eq!(delivered(&fabric, "Rx0y1"), 1);
+
+    // Best effort: the excess is remarked to DSCP 8 and still delivered.
+    let first = process_packet(
+        &mut fabric,
+        &tables,
+        a.clone(),
+        udp(1000, 0),
+        Destination::TunB,
+    )
+    .await;
+    let second = process_packet(
+        &mut fabric,
+        &tables,
+        a.clone(),
+        udp(1000, 0),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(first.dscp, 0);
+    assert_eq!(second.dscp, 8);
+    assert_eq!(second.raw[1], 8 << 2);
+    assert_eq!(delivered(&fabric, "Rx0y1"), 3);
+
+    let router = fabric.get_router(&a).unwrap();
+    assert_eq!(router.stats.policer_drops, 1);
+    assert_eq!(router.policers[0].name, "voice");
+    assert_eq!(router.policers[0].exceeded.packets, 1);
+    assert_eq!(router.policers[1].name, "default");
+    assert_eq!(router.policers[1].exceeded.packets, 1);
+    // Only traffic entering the fabric is policed.
+    let transit = fabric.get_router(&RouterId("Rx0y1".into())).unwrap();
+    assert_eq!(transit.stats.policer_drops, 0);
+}
+
+#[test]
+fn test_policer_config() {
+    let cfg = config(
+        r#"cir_mbps = 1.0
+conform = { remark = 10 }
+exceed = "transmit""#,
+    );
+    assert_eq!(cfg.conform, PolicerAction::Remark(10));
+    assert_eq!(cfg.exceed, PolicerAction::Transmit);
+    assert_eq!(cfg.violate, PolicerAction::Drop);
+    assert!(cfg.validate().is_ok());
+
+    let err = config("cir_mbps = 10.0\npir_mbps = 5.0")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("pir_mbps"), "{}", err);
+    let err = config("cir_mbps = 1.0\nexceed = { remark = 64 }")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("invalid DSCP value 64"), "{}", err);
+
+    let cfg = addressed(common::line(
+        "",
+        &["policers = [ { cir_mbps = 0.0 } ]", ""],
+        &[""],
+        "",
+    ));
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("Router 'Rx0y0': invalid policer"), "{}", err);
+}
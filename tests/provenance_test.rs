@@ -0,0 +1,171 @@
+mod common;
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::provenance::{index, read_stamp, RunMetadata};
+use network_simulator::stats;
+use predicates::str::contains;
+use std::io::Write;
+
+fn metadata(config_text: &str) -> RunMetadata {
+    RunMetadata::at(
+        1_700_000_000,
+        "lab.toml",
+        config_text,
+        Some(42),
+        vec!["network-simulator".to_string(), "--stats".to_string()],
+    )
+}
+
+#[test]
+fn test_metadata_fields_and_comment_round_trip() {
+    let run = metadata("");
+    assert_eq!(run.started, "2023-11-14T22:13:20Z");
+    assert_eq!(run.run_id, "20231114T221320Z-cbf29ce4");
+    assert_eq!(run.config_hash, "fnv1a64:cbf29ce484222325");
+    assert_eq!(run.crate_version, env!("CARGO_PKG_VERSION"));
+    assert_ne!(metadata("a = 1").config_hash, run.config_hash);
+    let line = format!("# {}", run.comment());
+    assert_eq!(RunMetadata::from_comment(&line), Some(run.clone()));
+    assert_eq!(RunMetadata::from_comment(&run.comment()), Some(run));
+    assert_eq!(RunMetadata::from_comment("# mirror of Rx0y0"), None);
+}
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Run a one-packet scenario in `dir` with `top` and `rest` added to the configuration and
+/// `run` stamped.
+async fn run_in(dir: &std::path::Path, top: &str, rest: &str, run: &RunMetadata) -> String {
+    let packets = dir.join("packets.txt").display().to_string();
+    let packet = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap();
+    let mut file = std::fs::File::create(&packets).unwrap();
+    writeln!(file, "{}", hex::encode(&packet.raw)).unwrap();
+    let mut cfg = addressed(common::line(
+        &format!(
+            "packet_file = \"{}\"\npacket_inject_tun = \"tun_a\"\npacket_trace = \"{}\"\n{}",
+            packets,
+            dir.join("trace.csv").display(),
+            top
+        ),
+        &["", ""],
+        &[""],
+        rest,
+    ));
+    cfg.run = Some(run.clone());
+    cfg.validate().expect("valid");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    stats::write(&fabric, &dir.join("stats.json").display().to_string()).unwrap();
+    packets
+}
+
+#[tokio::test]
+async fn test_outputs_stamped_and_indexed() {
+    let dir = tempfile::tempdir().unwrap();
+    let run = metadata("scenario");
+    let rest = format!(
+        "[drop_capture]\npcap = \"{}\"",
+        dir.path().join("drops.pcap").display()
+    );
+    let packets = run_in(dir.path(), "", &rest, &run).await;
+
+    let trace = std::fs::read_to_string(dir.path().join("trace.csv")).unwrap();
+    assert!(
+        trace.lines().nth(1).unwrap().starts_with("seq,"),
+        "{}",
+        trace
+    );
+    let out = format!("{}_out.txt", packets);
+    for output in [
+        out.as_str(),
+        &dir.path().join("trace.csv").display().to_string(),
+        &dir.path().join("stats.json").display().to_string(),
+        &dir.path().join("drops.pcap.run.json").display().to_string(),
+    ] {
+        assert_eq!(
+            read_stamp(output.as_ref()).as_ref(),
+            Some(&run),
+            "{}",
+            output
+        );
+    }
+    // The stats dump still reads back for `stats diff`.
+    let dump = stats::StatsDump::load(&dir.path().join("stats.json").display().to_string());
+    assert_eq!(dump.unwrap().run, Some(run.clone()));
+
+    let runs = index(dir.path()).unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].0, run);
+    assert_eq!(
+        runs[0].1,
+        [
+            "drops.pcap",
+            "packets.txt_out.txt",
+            "stats.json",
+            "trace.csv"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_pcapng_output_and_json_trace_stamped() {
+    let dir = tempfile::tempdir().unwrap();
+    let run = metadata("pcapng");
+    let packets = run_in(
+        dir.path(),
+        "packet_trace_format = \"json\"",
+        "[replay_output]\nformat = \"pcapng\"",
+        &run,
+    )
+    .await;
+    let pcapng = format!("{}_out.pcapng", packets);
+    assert_eq!(read_stamp(pcapng.as_ref()), Some(run.clone()));
+    // The output still reads back as a packet file.
+    let frames = network_simulator::replay::open(&pcapng).unwrap().count();
+    assert_eq!(frames, 1);
+    let trace = dir.path().join("trace.csv");
+    assert_eq!(read_stamp(&trace), Some(run));
+    let trace = std::fs::read_to_string(trace).unwrap();
+    assert!(trace.lines().nth(1).unwrap().contains("\"delivered\""));
+}
+
+#[test]
+fn test_runs_list_subcommand() {
+    let dir = tempfile::tempdir().unwrap();
+    let (older, newer) = (
+        metadata("a"),
+        RunMetadata::at(1_800_000_000, "b.toml", "b", None, vec![]),
+    );
+    older
+        .write_sidecar(&dir.path().join("a.pcap").display().to_string())
+        .unwrap();
+    let stamp = format!("# {}\n", newer.comment());
+    std::fs::write(dir.path().join("b_out.txt"), stamp).unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "unrelated\n").unwrap();
+    let out = cargo_bin_cmd!("network-simulator")
+        .args(["runs", "list"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(contains("seed 42"))
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert!(lines[0].starts_with(&older.run_id), "{}", out);
+    assert_eq!(lines[1], "  args: network-simulator --stats");
+    assert_eq!(lines[2], "  a.pcap");
+    assert!(lines[3].starts_with(&newer.run_id), "{}", out);
+    assert_eq!(lines[4], "  b_out.txt");
+    assert!(!out.contains("notes.txt"));
+}
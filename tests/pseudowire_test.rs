@@ -0,0 +1,162 @@
+mod common;
+
+use network_simulator::config::{PseudowireConfig, PseudowireEndConfig};
+use network_simulator::packet::parse;
+use network_simulator::pseudowire::{parse_cookie, Pseudowire, Reject};
+use network_simulator::routing::Destination;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const INNER: &str = "4500001400000000401100000a0000020a000102";
+
+fn end(local: u32, remote: u32, cookie: Option<&str>) -> PseudowireEndConfig {
+    PseudowireEndConfig {
+        local_session_id: local,
+        remote_session_id: remote,
+        local_cookie: cookie.map(str::to_string),
+        remote_cookie: cookie.map(str::to_string),
+    }
+}
+
+fn pseudowire(a: PseudowireEndConfig, b: PseudowireEndConfig) -> Pseudowire {
+    let cfg = PseudowireConfig {
+        a,
+        b,
+        ..Default::default()
+    };
+    Pseudowire::new(
+        &cfg,
+        "10.100.0.1".parse().unwrap(),
+        "10.100.0.2".parse().unwrap(),
+    )
+    .expect("pseudowire")
+}
+
+#[test]
+fn test_encapsulation_round_trip_and_overhead() {
+    let mut pw = pseudowire(
+        end(100, 200, Some("01020304")),
+        end(200, 100, Some("01020304")),
+    );
+    let inner = parse(&hex::decode(INNER).unwrap()).unwrap();
+    let session = pw.session_mut(Destination::TunB);
+    // IPv4 + UDP + session ID word pair + 4-byte cookie + sublayer.
+    assert_eq!(session.overhead(), 44);
+    let outer = session.encapsulate(&inner);
+    assert_eq!(outer.raw.len(), 64);
+    assert_eq!((outer.protocol, outer.dst_port), (17, 1701));
+    assert_eq!(outer.dst_ip.to_string(), "10.100.0.2");
+    assert_eq!(&outer.raw[32..36], &200u32.to_be_bytes());
+    let back = session.decapsulate(&outer).expect("accepted");
+    assert_eq!(back.raw, inner.raw);
+    assert_eq!(session.stats().encapsulated, 1);
+    assert_eq!(session.stats().decapsulated, 1);
+}
+
+#[test]
+fn test_session_and_cookie_mismatches_are_counted() {
+    // a sends session 300 but b expects 200.
+    let mut pw = pseudowire(end(100, 300, None), end(200, 100, None));
+    let inner = parse(&hex::decode(INNER).unwrap()).unwrap();
+    let session = pw.session_mut(Destination::TunB);
+    let outer = session.encapsulate(&inner);
+    assert_eq!(
+        session.decapsulate(&outer).unwrap_err(),
+        Reject::SessionMismatch(300)
+    );
+    assert_eq!(session.stats().session_mismatch, 1);
+
+    let mut b = end(200, 100, Some("0a0b0c0d"));
+    b.remote_cookie = Some("01020304".to_string());
+    let mut pw = pseudowire(end(100, 200, Some("01020304")), b);
+    let session = pw.session_mut(Destination::TunB);
+    let outer = session.encapsulate(&inner);
+    assert_eq!(
+        session.decapsulate(&outer).unwrap_err(),
+        Reject::CookieMismatch
+    );
+    assert_eq!(session.stats().cookie_mismatch, 1);
+    // The other direction sends the cookie a expects.
+    let session = pw.session_mut(Destination::TunA);
+    let outer = session.encapsulate(&inner);
+    assert_eq!(session.decapsulate(&outer).unwrap().raw, inner.raw);
+}
+
+#[test]
+fn test_late_packets_are_dropped() {
+    let mut pw = pseudowire(end(100, 200, None), end(200, 100, None));
+    let inner = parse(&hex::decode(INNER).unwrap()).unwrap();
+    let session = pw.session_mut(Destination::TunB);
+    let first = session.encapsulate(&inner);
+    let second = session.encapsulate(&inner);
+    assert!(session.decapsulate(&second).is_ok());
+    assert_eq!(
+        session.decapsulate(&first).unwrap_err(),
+        Reject::OutOfOrder {
+            seq: 0,
+            expected: 2
+        }
+    );
+    assert_eq!(session.stats().out_of_order, 1);
+}
+
+#[test]
+fn test_cookie_length_is_validated() {
+    assert!(parse_cookie(None).unwrap().is_empty());
+    assert_eq!(parse_cookie(Some("0102030405060708")).unwrap().len(), 8);
+    assert!(parse_cookie(Some("010203")).is_err());
+    assert!(parse_cookie(Some("xyz")).is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_outer_packet_is_subject_to_link_mtu() {
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", INNER).unwrap();
+    // 24 more bytes of inner packet push the outer one past the 70-byte link MTU.
+    writeln!(
+        packets,
+        "4500002c00000000401100000a0000020a000102{}",
+        "00".repeat(24)
+    )
+    .unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", ""],
+        &["mtu = 70"],
+        r#"
+[pseudowire]
+a = { local_session_id = 100, remote_session_id = 200 }
+b = { local_session_id = 200, remote_session_id = 100 }
+"#,
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    // Only the decapsulated small packet leaves the far edge.
+    assert_eq!(out, format!("{}\n", INNER));
+    let pw = fabric.pseudowire.as_ref().expect("pseudowire");
+    assert_eq!(pw.a_to_b.stats().encapsulated, 2);
+    assert_eq!(pw.a_to_b.stats().decapsulated, 1);
+}
+
+#[test]
+fn test_config_rejects_zero_session_id() {
+    let mut cfg = common::line(
+        "",
+        &["", ""],
+        &[""],
+        r#"
+[pseudowire]
+a = { local_session_id = 0, remote_session_id = 200 }
+b = { local_session_id = 200, remote_session_id = 100 }
+"#,
+    );
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("non-zero"), "{}", err);
+}
@@ -0,0 +1,264 @@
+mod common;
+
+use network_simulator::config::SimulatorConfig;
+use network_simulator::qos::{dscp_of, QueueConfig, QueueSet, SchedulerKind, WredConfig};
+use network_simulator::topology::RouterId;
+use std::io::Write;
+use tempfile::NamedTempFile;
+use tokio::time::{sleep, Duration};
+
+fn voice_and_default(default_limit: u32) -> Vec<QueueConfig> {
+    vec![
+        QueueConfig {
+            name: Some("voice".into()),
+            dscp: vec![46],
+            limit: 10,
+            ..Default::default()
+        },
+        QueueConfig {
+            name: Some("default".into()),
+            limit: default_limit,
+            ..Default::default()
+        },
+    ]
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_strict_priority_bypasses_lower_classes() {
+    // 125-byte packets take 1 ms at 1 Mbit/s.
+    let mut set = QueueSet::new(1.0, SchedulerKind::Strict, &voice_and_default(3));
+    // The processor sleeps each returned delay before the next packet arrives; every packet
+    // waits for those ahead of it and for its own serialisation.
+    for expected in [1, 2, 3] {
+        let wait = set.admit(0, 125).expect("admitted");
+        assert_eq!(wait.as_millis(), expected);
+        sleep(wait).await;
+    }
+    assert_eq!(set.admit(0, 125), None);
+    // Voice only waits for voice.
+    assert_eq!(set.admit(46, 125), Some(Duration::from_millis(1)));
+    let stats = set.stats();
+    assert_eq!((stats[0].name.as_str(), stats[0].enqueued), ("voice", 1));
+    assert_eq!(
+        (stats[1].enqueued, stats[1].dropped, stats[1].max_depth),
+        (3, 1, 3)
+    );
+    assert!((stats[1].avg_wait_ms() - 1.0).abs() < 1e-6);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_strict_priority_voice_waits_for_voice_only() {
+    let mut set = QueueSet::new(1.0, SchedulerKind::Strict, &voice_and_default(10));
+    for _ in 0..4 {
+        set.admit(0, 125).unwrap();
+    }
+    set.admit(46, 125).unwrap();
+    assert_eq!(set.admit(46, 125), Some(Duration::from_millis(2)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_wfq_favours_heavier_weight() {
+    let queues = vec![
+        QueueConfig {
+            dscp: vec![10],
+            weight: 3,
+            ..Default::default()
+        },
+        QueueConfig::default(),
+    ];
+    let mut set = QueueSet::new(1.0, SchedulerKind::Wfq, &queues);
+    for _ in 0..4 {
+        set.admit(0, 125).unwrap();
+        set.admit(10, 125).unwrap();
+    }
+    let heavy = set.admit(10, 125).unwrap();
+    let light = set.admit(0, 125).unwrap();
+    assert!(heavy < light, "{:?} vs {:?}", heavy, light);
+    // Backlog drains over time: after a long pause only serialisation remains.
+    tokio::time::advance(Duration::from_secs(1)).await;
+    assert_eq!(set.admit(0, 125), Some(Duration::from_millis(1)));
+}
+
+fn wred(min_threshold: f64, max_threshold: f64, ewma_weight: f64) -> WredConfig {
+    WredConfig {
+        min_threshold,
+        max_threshold,
+        max_drop_percent: 10.0,
+        ewma_weight,
+    }
+}
+
+#[test]
+fn test_wred_drop_probability() {
+    let w = wred(10.0, 30.0, 1.0);
+    assert_eq!(w.drop_probability(5.0), 0.0);
+    assert!((w.drop_probability(20.0) - 0.05).abs() < 1e-9);
+    assert_eq!(w.drop_probability(30.0), 1.0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_wred_drops_early_and_spares_other_classes() {
+    let mut queues = voice_and_default(64);
+    queues[1].wred = Some(wred(2.0, 4.0, 1.0));
+    let mut set = QueueSet::new(1.0, SchedulerKind::Strict, &queues);
+    for _ in 0..10 {
+        let _ = set.admit(0, 125);
+    }
+    assert_eq!(set.admit(46, 125), Some(Duration::from_millis(1)));
+    let stats = set.stats();
+    let default = &stats[1];
+    // Beyond an average depth of four packets every arrival is dropped early.
+    assert!(default.wred_dropped >= 6, "{}", default.summary());
+    assert_eq!(default.dropped, 0);
+    assert!(default.max_depth <= 4);
+    assert_eq!(default.enqueued + default.wred_dropped, 10);
+    assert_eq!((stats[0].enqueued, stats[0].wred_dropped), (1, 0));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_wred_average_decays_while_idle() {
+    let mut queues = voice_and_default(64);
+    queues[1].wred = Some(WredConfig {
+        max_drop_percent: 0.0,
+        ..wred(1.0, 3.0, 0.1)
+    });
+    let mut set = QueueSet::new(1.0, SchedulerKind::Strict, &queues);
+    for _ in 0..20 {
+        let _ = set.admit(0, 125);
+    }
+    assert_eq!(set.stats()[1].wred_dropped, 11);
+    // The average is far beyond max_threshold; one empty sample alone would not bring it
+    // below, but a second of idle link does.
+    tokio::time::advance(Duration::from_secs(1)).await;
+    assert!(set.admit(0, 125).is_some());
+    assert_eq!(set.stats()[1].wred_dropped, 11);
+}
+
+#[test]
+fn test_dscp_extraction() {
+    assert_eq!(dscp_of(&[0x45, 0xb8, 0, 20]), 46);
+    assert_eq!(dscp_of(&[0x45, 0x00]), 0);
+    // IPv6 traffic class 0xb8 spans the first two bytes.
+    assert_eq!(dscp_of(&[0x6b, 0x80, 0, 0]), 46);
+    assert_eq!(dscp_of(&[]), 0);
+}
+
+fn scenario(packets: &NamedTempFile, link_opts: &str) -> SimulatorConfig {
+    common::line(
+        &format!("packet_file = \"{}\"", packets.path().display()),
+        &["", ""],
+        &[link_opts],
+        "",
+    )
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_link_queues_in_simulation() {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..4 {
+        writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    writeln!(packets, "45b8001400000000401100000a0000020a000102").unwrap();
+    // 20-byte packets take 1 ms each at 0.16 Mbit/s.
+    let link = r#"bandwidth_mbps = 0.16, queues = [ { name = "voice", dscp = [46] }, { name = "default", limit = 3 } ]"#;
+    let fabric = network_simulator::run(scenario(&packets, link))
+        .await
+        .expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+    let link = fabric
+        .get_link(&RouterId("Rx0y0".into()), &RouterId("Rx0y1".into()))
+        .unwrap();
+    let stats = link.queue_stats();
+    assert_eq!(stats[0].0, "Rx0y0->Rx0y1");
+    let (voice, default) = (&stats[0].1[0], &stats[0].1[1]);
+    // The empty start-up demonstration packet is served at once; then three packets fit the
+    // default queue and the fourth is tail-dropped.
+    assert_eq!((default.enqueued, default.dropped), (4, 1));
+    assert_eq!((voice.enqueued, voice.total_wait_ms), (1, 0.0));
+    let rx0y0 = &fabric.get_statistics()[&RouterId("Rx0y0".into())];
+    assert_eq!(rx0y0.packets_lost, 1);
+}
+
+#[test]
+fn test_link_queue_options_validated() {
+    let packets = NamedTempFile::new().unwrap();
+    let with_interfaces = |opts: &str| {
+        let mut cfg = scenario(&packets, opts);
+        cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+        cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+        cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+        cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+        cfg
+    };
+    with_interfaces(r#"bandwidth_mbps = 10, scheduler = "wfq", queues = [ { dscp = [46] } ]"#)
+        .validate()
+        .expect("valid");
+    let err = with_interfaces("queues = [ { dscp = [46] } ]")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("bandwidth_mbps"), "{}", err);
+    let err = with_interfaces("bandwidth_mbps = 10, queues = [ { dscp = [64] } ]")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("DSCP"), "{}", err);
+    let err = with_interfaces(
+        "bandwidth_mbps = 10, queues = [ { wred = { min_threshold = 5, max_threshold = 5 } } ]",
+    )
+    .validate()
+    .unwrap_err();
+    assert!(err.contains("WRED"), "{}", err);
+    let err = with_interfaces("bandwidth_mbps = 0")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("positive"), "{}", err);
+    let err = with_interfaces(r#"pool = "uplink""#)
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("unknown pool 'uplink'"), "{}", err);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pool_limits_links_sharing_it() {
+    use network_simulator::simulation::{simulate_link_timed, SimulationError};
+    // Two 10 Mbit/s tails behind a 0.1 Mbit/s backhaul: a 1250-byte packet takes 1 ms on a tail
+    // and 100 ms in the backhaul.
+    let cfg = common::scenario(
+        "",
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx0y2", "")],
+        &[
+            ("Rx0y0_Rx0y1", r#"bandwidth_mbps = 10, pool = "backhaul""#),
+            ("Rx0y0_Rx0y2", r#"bandwidth_mbps = 10, pool = "backhaul""#),
+        ],
+        "[topology.pools]\nbackhaul = { bandwidth_mbps = 0.1, queues = [ { limit = 3 } ] }",
+    );
+    let fabric = network_simulator::build_fabric(&cfg);
+    let hub = common::rid("Rx0y0");
+    let tails = [
+        fabric.get_link(&hub, &common::rid("Rx0y1")).unwrap(),
+        fabric.get_link(&hub, &common::rid("Rx0y2")).unwrap(),
+    ];
+    let packet = [0u8; 1250];
+    for i in 0..3 {
+        let sojourn = simulate_link_timed(tails[i % 2], &hub, &packet)
+            .await
+            .expect("admitted");
+        // Each packet waits for the ones ahead of it in the pool, whichever link they took.
+        let expected = 1.0 + 100.0 * (i + 1) as f64;
+        assert!(
+            (sojourn.queueing_ms - expected).abs() < 3.0,
+            "packet {}: {} ms",
+            i,
+            sojourn.queueing_ms
+        );
+    }
+    assert_eq!(
+        simulate_link_timed(tails[1], &hub, &packet).await,
+        Err(SimulationError::QueueFull)
+    );
+    let pool = &fabric.pools["backhaul"].stats()[0];
+    assert_eq!((pool.enqueued, pool.dropped), (3, 1));
+    // Neither tail came close to its own limit.
+    for tail in tails {
+        assert_eq!(tail.queue_stats()[0].1[0].total_wait_ms, 0.0);
+    }
+}
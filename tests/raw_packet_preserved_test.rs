@@ -22,14 +22,28 @@ fn test_raw_packet_ttl_decrement_preserves_raw() {
     let actual_checksum = ((pkt.raw[10] as u16) << 8) | (pkt.raw[11] as u16);
     assert_eq!(actual_checksum, expected_checksum);
     // Ensure other bytes unchanged (except checksum bytes 10-11)
-    for i in 0..8 {
-        assert_eq!(pkt.raw[i], raw[i]);
-    }
-    for i in 9..10 {
-        // byte 9 (protocol) should stay the same
-        assert_eq!(pkt.raw[i], raw[i]);
-    }
-    for i in 12..raw.len() {
-        assert_eq!(pkt.raw[i], raw[i]);
-    }
+    assert_eq!(pkt.raw[..8], raw[..8]);
+    // byte 9 (protocol) should stay the same
+    assert_eq!(pkt.raw[9], raw[9]);
+    assert_eq!(pkt.raw[12..], raw[12..]);
+}
+
+#[test]
+fn test_clones_share_raw_until_written() {
+    let raw: Vec<u8> = vec![
+        0x45, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00, 0x40, 0x06, 0x00, 0x00, 10, 0, 0, 1, 10, 0,
+        0, 2,
+    ];
+    let packet = parse(&raw).expect("parse failed");
+    let mut copy = packet.clone();
+    assert_eq!(copy.raw.as_ptr(), packet.raw.as_ptr());
+    // Writing to a shared buffer copies it; the original keeps its bytes.
+    copy.decrement_ttl().expect("decrement failed");
+    assert_ne!(copy.raw.as_ptr(), packet.raw.as_ptr());
+    assert_eq!((packet.raw[8], copy.raw[8]), (64, 63));
+    // A buffer no other packet holds is written in place.
+    let before = copy.raw.as_ptr();
+    copy.set_ttl(10);
+    assert_eq!(copy.raw.as_ptr(), before);
+    assert_eq!(copy.raw[8], 10);
 }
@@ -0,0 +1,134 @@
+mod common;
+
+use network_simulator::config::ReassemblyConfig;
+use network_simulator::packet::{fragment_ipv4, update_ipv4_checksum};
+use network_simulator::reassembly::Reassembler;
+use std::io::Write;
+use tempfile::NamedTempFile;
+use tokio::time::{Duration, Instant};
+
+/// IPv4/UDP packet 10.0.0.2 -> 10.0.1.2 with identification `id` and `data` payload bytes.
+fn udp(id: u16, data: usize) -> Vec<u8> {
+    let total = 28 + data;
+    let mut raw = vec![0x45, 0];
+    raw.extend_from_slice(&(total as u16).to_be_bytes());
+    raw.extend_from_slice(&id.to_be_bytes());
+    raw.extend_from_slice(&[0, 0, 64, 17, 0, 0, 10, 0, 0, 2, 10, 0, 1, 2]);
+    raw.extend_from_slice(&[0x13, 0x88, 0x17, 0x70]);
+    raw.extend_from_slice(&((8 + data) as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 0]);
+    raw.extend((0..data).map(|i| i as u8));
+    update_ipv4_checksum(&mut raw);
+    raw
+}
+
+fn reassembler(timeout_ms: u64, max_buffer_bytes: usize) -> Reassembler {
+    Reassembler::new(&ReassemblyConfig {
+        timeout_ms,
+        max_buffer_bytes,
+    })
+}
+
+/// IPv6 fragment with a Hop-by-Hop header before the Fragment header.
+fn ipv6_fragment(offset: u16, more: bool, data: &[u8]) -> Vec<u8> {
+    let mut raw = vec![0x60, 0, 0, 0];
+    raw.extend_from_slice(&((16 + data.len()) as u16).to_be_bytes());
+    raw.extend_from_slice(&[0, 64]);
+    raw.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    raw.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    raw.extend_from_slice(&[44, 0, 1, 4, 0, 0, 0, 0]);
+    raw.extend_from_slice(&[17, 0]);
+    raw.extend_from_slice(&(offset | more as u16).to_be_bytes());
+    raw.extend_from_slice(&0xdead_beefu32.to_be_bytes());
+    raw.extend_from_slice(data);
+    raw
+}
+
+#[test]
+fn test_ipv4_fragments_reassembled_in_any_order() {
+    let packet = udp(7, 200);
+    let mut fragments = fragment_ipv4(&packet, 68).unwrap();
+    assert_eq!(fragments.len(), 5);
+    fragments.swap(0, 4);
+    fragments.swap(1, 3);
+    let now = Instant::now();
+    let mut r = reassembler(1000, 4096);
+    // Packets that are not fragments pass straight through.
+    let whole = udp(8, 10);
+    assert_eq!(r.offer(whole.clone(), now), Some(whole));
+    let last = fragments.pop().unwrap();
+    for fragment in fragments {
+        assert_eq!(r.offer(fragment, now), None);
+    }
+    assert_eq!(r.pending(), 1);
+    assert_eq!(r.offer(last, now), Some(packet));
+    assert_eq!(r.pending(), 0);
+    assert_eq!(r.stats.fragments, 5);
+    assert_eq!(r.stats.reassembled, 1);
+    assert_eq!(r.stats.failures(), 0);
+}
+
+#[test]
+fn test_ipv6_fragments_reassembled() {
+    let data: Vec<u8> = (0..40).collect();
+    let mut r = reassembler(1000, 4096);
+    let now = Instant::now();
+    assert_eq!(r.offer(ipv6_fragment(24, false, &data[24..]), now), None);
+    let datagram = r.offer(ipv6_fragment(0, true, &data[..24]), now).unwrap();
+    assert_eq!(datagram.len(), 40 + 8 + 40);
+    assert_eq!(u16::from_be_bytes([datagram[4], datagram[5]]), 48);
+    assert_eq!(datagram[40], 17, "Hop-by-Hop now chains to UDP");
+    assert_eq!(&datagram[48..], &data[..]);
+}
+
+#[test]
+fn test_reassembly_failures_counted() {
+    let now = Instant::now();
+    let fragments = fragment_ipv4(&udp(1, 100), 68).unwrap();
+    let mut r = reassembler(100, 4096);
+    assert_eq!(r.offer(fragments[0].clone(), now), None);
+    r.expire(now + Duration::from_millis(100));
+    assert_eq!(r.stats.timed_out, 1);
+    assert_eq!(r.pending(), 0);
+
+    let mut r = reassembler(100, 60);
+    assert_eq!(r.offer(fragments[0].clone(), now), None);
+    assert_eq!(r.offer(fragments[1].clone(), now), None);
+    assert_eq!(r.stats.buffer_overflow, 1);
+    assert_eq!(r.pending(), 0);
+
+    let mut r = reassembler(100, 4096);
+    let mut overlapping = fragments[1].clone();
+    overlapping[7] -= 1;
+    update_ipv4_checksum(&mut overlapping);
+    assert_eq!(r.offer(fragments[0].clone(), now), None);
+    assert_eq!(r.offer(overlapping, now), None);
+    assert_eq!(r.stats.invalid, 1);
+    // An exact duplicate is not an overlap.
+    assert_eq!(r.offer(fragments[0].clone(), now), None);
+    assert_eq!(r.offer(fragments[0].clone(), now), None);
+    assert_eq!(r.stats.invalid, 1);
+}
+
+#[tokio::test]
+async fn test_fragmented_packet_leaves_reassembled() {
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", hex::encode(udp(3, 200))).unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", "", ""],
+        &["mtu = 68", ""],
+        "[reassembly]\ntimeout_ms = 1000",
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    let lines: Vec<Vec<u8>> = out.lines().map(|l| hex::decode(l).unwrap()).collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].len(), 228);
+    assert_eq!(&lines[0][28..], &udp(3, 200)[28..]);
+    let stats = &fabric.reassembly.as_ref().expect("reassembly").stats;
+    assert_eq!((stats.fragments, stats.reassembled), (5, 1));
+}
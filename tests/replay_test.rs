@@ -0,0 +1,365 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::{ReplayOutputConfig, SimulatorConfig};
+use network_simulator::packet::{explain, parse, update_ipv4_checksum};
+use network_simulator::replay::sink::EgressSink;
+use network_simulator::replay::{read_pcap, reverse_packet};
+use network_simulator::routing::Destination;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// IPv4/TCP segment 10.0.0.2:40000 -> 10.0.1.2:80 with `flags` and `data` payload bytes.
+fn tcp(seq: u32, ack: u32, flags: u8, data: usize) -> Vec<u8> {
+    let total = 40 + data;
+    let mut raw = vec![
+        0x45,
+        0,
+        (total >> 8) as u8,
+        total as u8,
+        0,
+        0,
+        0,
+        0,
+        64,
+        6,
+        0,
+        0,
+    ];
+    raw.extend_from_slice(&[10, 0, 0, 2, 10, 0, 1, 2]);
+    update_ipv4_checksum(&mut raw);
+    raw.extend_from_slice(&40000u16.to_be_bytes());
+    raw.extend_from_slice(&80u16.to_be_bytes());
+    raw.extend_from_slice(&seq.to_be_bytes());
+    raw.extend_from_slice(&ack.to_be_bytes());
+    raw.extend_from_slice(&[5 << 4, flags, 0xff, 0xff, 0, 0, 0, 0]);
+    raw.resize(total, 0xab);
+    raw
+}
+
+/// Little‑endian pcap file of `linktype` holding `frames`.
+fn pcap(linktype: u32, frames: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    out.extend_from_slice(&[2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0]);
+    out.extend_from_slice(&linktype.to_le_bytes());
+    for frame in frames {
+        out.extend_from_slice(&[0; 8]);
+        out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        out.extend_from_slice(frame);
+    }
+    out
+}
+
+/// pcapng block of `kind` around `body`, in little or big endian.
+fn pcapng_block(little: bool, kind: u32, body: &[u8]) -> Vec<u8> {
+    let word = |v: u32| {
+        if little {
+            v.to_le_bytes()
+        } else {
+            v.to_be_bytes()
+        }
+    };
+    let padded = body.len().div_ceil(4) * 4;
+    let len = (12 + padded) as u32;
+    let mut out = Vec::new();
+    out.extend_from_slice(&word(kind));
+    out.extend_from_slice(&word(len));
+    out.extend_from_slice(body);
+    out.resize(8 + padded, 0);
+    out.extend_from_slice(&word(len));
+    out
+}
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+#[test]
+fn test_pcap_link_headers_are_stripped() {
+    let ip = tcp(1, 0, 0x02, 0);
+    let ethernet = |tags: &[u8], ethertype: u16| {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(tags);
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame.extend_from_slice(&ip);
+        frame
+    };
+    let file = pcap(
+        1,
+        &[
+            ethernet(&[], 0x0800),
+            ethernet(&[0x81, 0x00, 0, 10], 0x0800),
+            ethernet(&[], 0x0806),
+        ],
+    );
+    let packets = read_pcap(&file).unwrap();
+    assert_eq!(packets.len(), 3);
+    assert_eq!(packets[0].as_ref().unwrap(), &ip);
+    assert_eq!(packets[1].as_ref().unwrap(), &ip);
+    assert!(packets[2].as_ref().unwrap_err().contains("0x0806"));
+
+    let raw = read_pcap(&pcap(101, std::slice::from_ref(&ip))).unwrap();
+    assert_eq!(raw[0].as_ref().unwrap(), &ip);
+
+    let err = read_pcap(&pcap(105, &[])).unwrap_err();
+    assert!(err.contains("link type 105"), "{}", err);
+    let mut truncated = pcap(101, &[ip]);
+    truncated.pop();
+    assert!(read_pcap(&truncated).is_err());
+}
+
+#[test]
+fn test_pcapng_sections_and_interfaces() {
+    let ip = tcp(1, 0, 0x02, 0);
+    let mut ethernet = vec![0u8; 12];
+    ethernet.extend_from_slice(&[0x08, 0x00]);
+    ethernet.extend_from_slice(&ip);
+    let section = |little: bool| {
+        let mut body = if little {
+            0x1a2b_3c4du32.to_le_bytes().to_vec()
+        } else {
+            0x1a2b_3c4du32.to_be_bytes().to_vec()
+        };
+        body.extend_from_slice(&[0, 1, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        pcapng_block(little, 0x0a0d_0d0a, &body)
+    };
+    let interface = |little: bool, linktype: u16| {
+        let mut body = if little {
+            linktype.to_le_bytes().to_vec()
+        } else {
+            linktype.to_be_bytes().to_vec()
+        };
+        body.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        pcapng_block(little, 1, &body)
+    };
+    let enhanced = |little: bool, interface: u32, frame: &[u8]| {
+        let word = |v: u32| {
+            if little {
+                v.to_le_bytes()
+            } else {
+                v.to_be_bytes()
+            }
+        };
+        let mut body = word(interface).to_vec();
+        body.extend_from_slice(&[0; 8]);
+        body.extend_from_slice(&word(frame.len() as u32));
+        body.extend_from_slice(&word(frame.len() as u32));
+        body.extend_from_slice(frame);
+        pcapng_block(little, 6, &body)
+    };
+    let mut simple = (ethernet.len() as u32).to_le_bytes().to_vec();
+    simple.extend_from_slice(&ethernet);
+
+    let mut file = section(true);
+    file.extend(interface(true, 1));
+    file.extend(interface(true, 105));
+    file.extend(pcapng_block(true, 5, &[0; 8]));
+    file.extend(enhanced(true, 0, &ethernet));
+    file.extend(enhanced(true, 1, &ethernet));
+    file.extend(pcapng_block(true, 3, &simple));
+    // A second, big-endian section starts its own interface list.
+    file.extend(section(false));
+    file.extend(interface(false, 101));
+    file.extend(enhanced(false, 0, &ip));
+    file.extend(enhanced(false, 1, &ip));
+
+    let packets = read_pcap(&file).unwrap();
+    assert_eq!(packets.len(), 5);
+    assert_eq!(packets[0].as_ref().unwrap(), &ip);
+    assert!(packets[1].as_ref().unwrap_err().contains("link type 105"));
+    assert_eq!(packets[2].as_ref().unwrap(), &ip);
+    assert_eq!(packets[3].as_ref().unwrap(), &ip);
+    assert!(packets[4].as_ref().unwrap_err().contains("undeclared"));
+
+    file.truncate(file.len() - 2);
+    assert!(read_pcap(&file).is_err());
+}
+
+#[test]
+fn test_reverse_packet_acknowledges_segment() {
+    let syn = parse(&tcp(1000, 0, 0x02, 0)).unwrap();
+    let reply = reverse_packet(&syn, 0).unwrap();
+    assert_eq!(reply.src_ip.to_string(), "10.0.1.2");
+    assert_eq!(reply.dst_ip.to_string(), "10.0.0.2");
+    assert_eq!((reply.src_port, reply.dst_port), (80, 40000));
+    let text = explain(&reply.raw);
+    assert!(text.contains("seq 0, ack 1001"), "{}", text);
+    assert_eq!(reply.raw[33], 0x12, "SYN-ACK");
+
+    let data = parse(&tcp(5000, 777, 0x18, 100)).unwrap();
+    let reply = reverse_packet(&data, 10).unwrap();
+    assert_eq!(reply.raw.len(), 50);
+    assert_eq!(reply.raw[33], 0x10, "ACK");
+    let text = explain(&reply.raw);
+    assert!(text.contains("seq 777, ack 5100"), "{}", text);
+    assert!(!text.contains("INVALID"), "{}", text);
+
+    let rst = parse(&tcp(1, 1, 0x14, 0)).unwrap();
+    assert!(reverse_packet(&rst, 0).is_none());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pcap_replay_with_reverse_traffic() {
+    let frames: Vec<Vec<u8>> = (0..4).map(|i| tcp(1 + i * 100, 1, 0x10, 100)).collect();
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(&pcap(101, &frames)).unwrap();
+    let path = file.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", "", ""],
+        &["", ""],
+        "[reverse_traffic]\nratio = 0.5",
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let out = std::fs::read_to_string(format!("{}_out.txt", path)).unwrap();
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+
+    let delivered = |r: &str| fabric.get_router(&rid(r)).unwrap().stats.packets_delivered;
+    // One ACK per two segments; the startup demonstration packet is delivered at Rx0y2 too.
+    assert_eq!(delivered("Rx0y2"), 5);
+    assert_eq!(delivered("Rx0y0"), 2);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 6);
+    let acks: Vec<_> = lines
+        .iter()
+        .map(|l| parse(&hex::decode(l).unwrap()).unwrap())
+        .filter(|p| p.src_port == 80)
+        .collect();
+    assert_eq!(acks.len(), 2);
+    assert!(explain(&acks[1].raw).contains("ack 401"));
+}
+
+#[test]
+fn test_reverse_traffic_needs_packet_file() {
+    let cfg = common::line("", &["", ""], &[""], "[reverse_traffic]\nratio = 1");
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("packet_file"), "{}", err);
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", packets.path().display()),
+        &["", ""],
+        &[""],
+        "[reverse_traffic]\nratio = 0",
+    );
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("reverse_traffic.ratio"), "{}", err);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_slow_sink_holds_back_replay() {
+    // 140-byte segments written at 1120 bit/s take a second each.
+    let frames: Vec<Vec<u8>> = (0..10).map(|i| tcp(1 + i * 100, 1, 0x10, 100)).collect();
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(&pcap(101, &frames)).unwrap();
+    let path = file.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", ""],
+        &[""],
+        "[replay_output]\nqueue = 2\nrate_bps = 1120",
+    );
+    let started = tokio::time::Instant::now();
+    network_simulator::run(cfg).await.expect("run");
+    let out = std::fs::read_to_string(format!("{}_out.txt", path)).unwrap();
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    assert!(started.elapsed() >= std::time::Duration::from_secs(10));
+    let seqs: Vec<String> = out
+        .lines()
+        .map(|l| explain(&hex::decode(l).unwrap()))
+        .collect();
+    assert_eq!(seqs.len(), 10);
+    assert!(seqs[9].contains("seq 901"), "{}", seqs[9]);
+
+    // The sender waits once the queue is full.
+    let out = NamedTempFile::new().unwrap();
+    let output = ReplayOutputConfig {
+        queue: 1,
+        rate_bps: Some(1120),
+        ..Default::default()
+    };
+    let mut sink = EgressSink::open(&out.path().display().to_string(), &output, None).unwrap();
+    for frame in &frames {
+        sink.send(frame.clone().into(), Destination::TunB).await;
+    }
+    assert!(sink.stalls >= 8, "{}", sink.stalls);
+    assert_eq!(sink.finish().await.unwrap(), 10);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_sink_flushes_periodically() {
+    let out = NamedTempFile::new().unwrap();
+    let output = ReplayOutputConfig {
+        flush_ms: 100,
+        ..Default::default()
+    };
+    let mut sink = EgressSink::open(&out.path().display().to_string(), &output, None).unwrap();
+    let frame = tcp(1, 1, 0x10, 100);
+    sink.send(frame.clone().into(), Destination::TunB).await;
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    let written = || std::fs::read_to_string(out.path()).unwrap();
+    assert_eq!(written(), "", "buffered until the next flush");
+    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+    assert_eq!(written(), format!("{}\n", hex::encode(&frame)));
+    assert_eq!(sink.finish().await.unwrap(), 1);
+}
+
+#[test]
+fn test_bad_replay_output_rejected() {
+    let cfg = common::line("", &["", ""], &[""], "[replay_output]\nqueue = 0");
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("replay_output.queue"), "{}", err);
+    let cfg = common::line("", &["", ""], &[""], "[replay_output]\nflush_ms = 0");
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("replay_output.flush_ms"), "{}", err);
+    let cfg = common::line("", &["", ""], &[""], "[replay_output]\nformat = \"pcap\"");
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("replay_output.format"), "{}", err);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pcapng_output_per_direction() {
+    let frames: Vec<Vec<u8>> = (0..4).map(|i| tcp(1 + i * 100, 1, 0x10, 100)).collect();
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(&pcap(101, &frames)).unwrap();
+    let path = file.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", "", ""],
+        &["", ""],
+        "[reverse_traffic]\nratio = 0.5\n[replay_output]\nformat = \"pcapng\"",
+    );
+    network_simulator::run(cfg).await.expect("run");
+    let out = std::fs::read(format!("{}_out.pcapng", path)).unwrap();
+    let _ = std::fs::remove_file(format!("{}_out.pcapng", path));
+    assert!(!std::path::Path::new(&format!("{}_out.txt", path)).exists());
+
+    // Interface of every enhanced packet block, in file order.
+    let mut interfaces = Vec::new();
+    let mut offset = 0;
+    while offset < out.len() {
+        let word = |at: usize| u32::from_ne_bytes(out[at..at + 4].try_into().unwrap());
+        if word(offset) == 6 {
+            interfaces.push(word(offset + 8));
+            assert!(word(offset + 12) > 0 || word(offset + 16) > 0, "timestamp");
+        }
+        offset += word(offset + 4) as usize;
+    }
+    // Segments leave towards tun_b, every second one followed by an ACK towards tun_a.
+    assert_eq!(interfaces, vec![1, 1, 0, 1, 1, 0]);
+    let packets: Vec<Vec<u8>> = read_pcap(&out)
+        .unwrap()
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(packets.len(), 6);
+    assert_eq!(parse(&packets[0]).unwrap().dst_port, 80);
+    assert_eq!(parse(&packets[2]).unwrap().src_port, 80);
+}
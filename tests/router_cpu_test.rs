@@ -0,0 +1,77 @@
+mod common;
+
+use network_simulator::config::SimulatorConfig;
+use network_simulator::topology::{CpuModel, RouterId};
+use std::io::Write;
+use tempfile::NamedTempFile;
+use tokio::time::{advance, Duration};
+
+#[tokio::test(start_paused = true)]
+async fn test_cpu_model_queues_then_drops() {
+    let mut cpu = CpuModel::new(10.0, 2);
+    // The processor sleeps for each returned wait before the next packet arrives.
+    for expected in [0, 100, 200] {
+        let wait = cpu.admit();
+        assert_eq!(wait, Some(Duration::from_millis(expected)));
+        tokio::time::sleep(wait.unwrap()).await;
+    }
+    assert_eq!(cpu.admit(), None);
+    // Once the backlog has drained the CPU accepts packets without delay again.
+    advance(Duration::from_secs(1)).await;
+    assert_eq!(cpu.admit(), Some(Duration::ZERO));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_cpu_model_paced_arrivals_never_wait() {
+    let mut cpu = CpuModel::new(10.0, 0);
+    for _ in 0..20 {
+        assert_eq!(cpu.admit(), Some(Duration::ZERO));
+        advance(Duration::from_millis(100)).await;
+    }
+}
+
+fn scenario(packets: &NamedTempFile, router_opts: &str) -> SimulatorConfig {
+    common::line(
+        &format!("packet_file = \"{}\"", packets.path().display()),
+        &[router_opts, ""],
+        &[""],
+        "",
+    )
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_overloaded_router_counts_cpu_drops() {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..6 {
+        writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    let fabric = network_simulator::run(scenario(&packets, "max_pps = 10, cpu_queue = 2"))
+        .await
+        .expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+    let stats = fabric.get_statistics();
+    // Six mock packets plus the start-up demonstration packet all arrive at t=0: three fit
+    // (one in service, two queued), the rest are dropped.
+    assert_eq!(stats[&RouterId("Rx0y0".into())].cpu_drops, 4);
+    assert_eq!(stats[&RouterId("Rx0y1".into())].cpu_drops, 0);
+}
+
+#[test]
+fn test_router_options_validated() {
+    let packets = NamedTempFile::new().unwrap();
+    let with_interfaces = |opts: &str| {
+        let mut cfg = scenario(&packets, opts);
+        cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+        cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+        cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+        cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+        cfg
+    };
+    with_interfaces("max_pps = 1000").validate().expect("valid");
+    let err = with_interfaces("max_pps = 0").validate().unwrap_err();
+    assert!(err.contains("max_pps"), "{}", err);
+    let err = with_interfaces("max_pps = \"fast\"")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("Rx0y0"), "{}", err);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::rid;
+use network_simulator::compute_routing_tables;
+use network_simulator::config::{RoutingConfig, SimulatorConfig};
+use network_simulator::routing::{RoutingAlgorithm, RoutingTable};
+use network_simulator::topology::RouterId;
+use std::collections::HashMap;
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Three ways from Rx0y0 (tun_a) to Rx1y1 (tun_b): a slow narrow direct link, a fast narrow
+/// way through Rx1y0, and a wide way through Rx0y1 and Rx0y2. `rest` goes after the topology.
+fn ways(rest: &str) -> SimulatorConfig {
+    addressed(common::scenario(
+        "",
+        &[
+            ("Rx0y0", ""),
+            ("Rx0y1", ""),
+            ("Rx0y2", ""),
+            ("Rx1y0", ""),
+            ("Rx1y1", ""),
+        ],
+        &[
+            ("Rx0y0_Rx1y1", "delay_ms = 50, bandwidth_mbps = 10"),
+            ("Rx0y0_Rx1y0", "delay_ms = 5, bandwidth_mbps = 10"),
+            ("Rx1y0_Rx1y1", "delay_ms = 5, bandwidth_mbps = 100"),
+            ("Rx0y0_Rx0y1", "delay_ms = 10, bandwidth_mbps = 1000"),
+            ("Rx0y1_Rx0y2", "delay_ms = 10"),
+            ("Rx0y2_Rx1y1", "delay_ms = 10, bandwidth_mbps = 1000"),
+        ],
+        rest,
+    ))
+}
+
+/// Routers a packet from `from` passes on its way to tun_b, `from` included.
+fn path(tables: &HashMap<RouterId, RoutingTable>, from: &str) -> Vec<String> {
+    let mut path = vec![from.to_string()];
+    let mut at = rid(from);
+    while at != rid("Rx1y1") && path.len() < 10 {
+        at = tables[&at].tun_b.next_hop.clone();
+        path.push(at.0.clone());
+    }
+    path
+}
+
+#[test]
+fn test_each_algorithm_picks_its_path() {
+    let cfg = ways("");
+    cfg.validate().expect("valid");
+    assert_eq!(cfg.routing.algorithm, RoutingAlgorithm::Spf);
+    let tables = compute_routing_tables(&cfg);
+    assert_eq!(path(&tables, "Rx0y0"), ["Rx0y0", "Rx1y0", "Rx1y1"]);
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.total_cost, 10);
+
+    let cfg = ways("[routing]\nalgorithm = \"min-hop\"\n");
+    cfg.validate().expect("valid");
+    let tables = compute_routing_tables(&cfg);
+    assert_eq!(path(&tables, "Rx0y0"), ["Rx0y0", "Rx1y1"]);
+    assert_eq!(tables[&rid("Rx0y1")].tun_b.total_cost, 2);
+
+    let cfg = ways("[routing]\nalgorithm = \"widest-path\"\n");
+    cfg.validate().expect("valid");
+    let tables = compute_routing_tables(&cfg);
+    assert_eq!(path(&tables, "Rx0y0"), ["Rx0y0", "Rx0y1", "Rx0y2", "Rx1y1"]);
+    // The cost of a widest path is its hop count.
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.total_cost, 3);
+    // Rx1y0 has 100 Mbps to tun_b directly, and only 10 back through Rx0y0.
+    assert_eq!(path(&tables, "Rx1y0"), ["Rx1y0", "Rx1y1"]);
+    // Towards tun_a too it takes 100 Mbps the long way round over 10 directly.
+    assert_eq!(tables[&rid("Rx1y1")].tun_a.next_hop, rid("Rx0y2"));
+    assert_eq!(tables[&rid("Rx1y0")].tun_a.next_hop, rid("Rx1y1"));
+    assert_eq!(tables[&rid("Rx1y0")].tun_a.total_cost, 4);
+}
+
+#[test]
+fn test_widest_path_among_equals_takes_fewest_hops() {
+    // Without bandwidths every path is unlimited, so widest-path routes by hop count.
+    let cfg = addressed(common::line(
+        "",
+        &["", "", ""],
+        &["delay_ms = 1", "delay_ms = 1"],
+        "[routing]\nalgorithm = \"widest-path\"\n",
+    ));
+    let tables = compute_routing_tables(&cfg);
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.next_hop, rid("Rx0y1"));
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.total_cost, 2);
+    assert_eq!(tables[&rid("Rx0y2")].tun_a.total_cost, 2);
+}
+
+#[test]
+fn test_routing_algorithm_validation() {
+    let mut cfg = ways("[routing]\nalgorithm = \"widest-path\"\n");
+    cfg.enable_multipath = true;
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("widest-path"), "{}", err);
+    let mut cfg = ways("[routing]\nalgorithm = \"min-hop\"\n");
+    cfg.enable_multipath = true;
+    assert!(cfg.validate().is_ok());
+    let parsed: Result<RoutingConfig, _> = toml::from_str("algorithm = \"ospf\"\n");
+    assert!(parsed.is_err());
+}
@@ -37,6 +37,7 @@ fn test_compute_routing_tables_counts() {
                         jitter_ms: 0,
                         loss_percent: 0.0,
                         load_balance: true,
+                        ..Default::default()
                     },
                 );
                 map.insert(
@@ -47,10 +48,13 @@ fn test_compute_routing_tables_counts() {
                         jitter_ms: 0,
                         loss_percent: 0.0,
                         load_balance: true,
+                        ..Default::default()
                     },
                 );
                 map
             },
+            reference_bandwidth_mbps: None,
+            pools: HashMap::new(),
         },
         enable_multipath: false,
         packet_file: None,
@@ -58,6 +62,7 @@ fn test_compute_routing_tables_counts() {
         packet_inject_tun: None,
         packet_inject_tuns: None,
         virtual_customer: None,
+        ..Default::default()
     };
 
     let tables = network_simulator::compute_routing_tables(&cfg);
@@ -99,6 +104,7 @@ fn test_compute_multipath_tables_when_enabled() {
                         jitter_ms: 0,
                         loss_percent: 0.0,
                         load_balance: true,
+                        ..Default::default()
                     },
                 );
                 map.insert(
@@ -109,10 +115,13 @@ fn test_compute_multipath_tables_when_enabled() {
                         jitter_ms: 0,
                         loss_percent: 0.0,
                         load_balance: true,
+                        ..Default::default()
                     },
                 );
                 map
             },
+            reference_bandwidth_mbps: None,
+            pools: HashMap::new(),
         },
         enable_multipath: true,
         packet_file: None,
@@ -120,6 +129,7 @@ fn test_compute_multipath_tables_when_enabled() {
         packet_inject_tun: None,
         packet_inject_tuns: None,
         virtual_customer: None,
+        ..Default::default()
     };
     let tables = network_simulator::compute_multipath_tables(&cfg);
     // Should have entries for each router.
@@ -140,6 +150,8 @@ fn test_compute_multipath_tables_when_disabled() {
         topology: TopologyConfig {
             routers: HashMap::new(),
             links: HashMap::new(),
+            reference_bandwidth_mbps: None,
+            pools: HashMap::new(),
         },
         enable_multipath: false,
         packet_file: None,
@@ -147,6 +159,7 @@ fn test_compute_multipath_tables_when_disabled() {
         packet_inject_tun: None,
         packet_inject_tuns: None,
         virtual_customer: None,
+        ..Default::default()
     };
     let tables = network_simulator::compute_multipath_tables(&cfg);
     assert!(tables.is_empty());
@@ -0,0 +1,226 @@
+mod common;
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::routing::export::{diff, render_diff, RoutingExport};
+use network_simulator::routing::{compute_multi_path_routing, compute_routing};
+use network_simulator::{build_fabric, compute_routing_tables};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// Square with tun_a at Rx0y0 and tun_b at Rx1y1, two equal-cost ways between them, and a
+/// `[[route]]` prefix behind tun_b.
+fn square() -> SimulatorConfig {
+    common::scenario(
+        "enable_multipath = true",
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx1y0", ""), ("Rx1y1", "")],
+        &[
+            ("Rx0y0_Rx0y1", ""),
+            ("Rx0y1_Rx1y1", ""),
+            ("Rx0y0_Rx1y0", ""),
+            ("Rx1y0_Rx1y1", ""),
+        ],
+        "[[route]]\nprefix = \"192.0.2.0/24\"\nrouter = \"Rx1y1\"\nedge = \"tun_b\"\n",
+    )
+}
+
+fn export(cfg: &SimulatorConfig, down: Option<(&str, &str)>) -> RoutingExport {
+    let mut fabric = build_fabric(cfg);
+    if let Some((a, b)) = down {
+        fabric.set_link_up(&rid(a), &rid(b), false).unwrap();
+    }
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx1y1"));
+    let multipath = compute_multi_path_routing(&fabric, rid("Rx0y0"), rid("Rx1y1"));
+    RoutingExport::new(&tables, &multipath)
+}
+
+#[test]
+fn test_export_round_trips_as_json_and_csv() {
+    let export = export(&square(), None);
+    let csv = export.to_csv();
+    assert!(csv.starts_with("router,table,destination,next_hop,cost,weight,distance\n"));
+    let rows: Vec<&str> = csv
+        .lines()
+        .filter(|l| l.starts_with("Rx0y0,single,") || l.starts_with("Rx0y0,multipath,tun_b,"))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            "Rx0y0,multipath,tun_b,Rx0y1,2,1,",
+            "Rx0y0,multipath,tun_b,Rx1y0,2,1,",
+            "Rx0y0,single,192.0.2.0/24,Rx0y1,2,,",
+            "Rx0y0,single,tun_a,Rx0y0,0,,",
+            "Rx0y0,single,tun_b,Rx0y1,2,,",
+        ]
+    );
+    assert_eq!(RoutingExport::parse(&csv), Ok(export.clone()));
+    assert_eq!(RoutingExport::parse(&export.to_json()), Ok(export.clone()));
+    assert!(diff(&export, &export).is_empty());
+
+    let err = RoutingExport::parse(&export.to_csv().replace(",0,", ",x,")).unwrap_err();
+    assert!(
+        err.starts_with("line ") && err.contains("invalid number 'x'"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_csv_fields_are_quoted() {
+    let mut export = export(&square(), None);
+    export.routes[0].destination = "a,b".to_string();
+    export.routes[1].next_hop = "say \"hi\"\nthere".to_string();
+    let csv = export.to_csv();
+    assert!(csv.contains(",\"a,b\","), "{}", csv);
+    assert!(csv.contains(",\"say \"\"hi\"\"\nthere\","), "{}", csv);
+    assert_eq!(RoutingExport::parse(&csv), Ok(export.clone()));
+
+    let err = RoutingExport::parse(&format!("{}Rx0y0,\"single,tun_a", csv)).unwrap_err();
+    assert!(err.contains("unterminated quoted field"), "{}", err);
+}
+
+#[test]
+fn test_export_has_distance_vector_routes() {
+    // With an infinity of 2, the far end of the line cannot reach tun_a over two links.
+    let cfg = common::line(
+        "",
+        &["", "", ""],
+        &["cost = 1", "cost = 1"],
+        "[distance_vector]\ninfinity = 2\n",
+    );
+    let export = RoutingExport::new(&compute_routing_tables(&cfg), &Default::default());
+    let route = export
+        .routes
+        .iter()
+        .find(|r| r.router == "Rx0y2" && r.table == "single" && r.destination == "tun_a")
+        .expect("Rx0y2 towards tun_a");
+    assert_eq!(route.cost, None);
+}
+
+#[test]
+fn test_diff_shows_routes_that_moved() {
+    let cfg = square();
+    let before = export(&cfg, None);
+    let after = export(&cfg, Some(("Rx0y0", "Rx0y1")));
+    let changes = diff(&before, &after);
+    let rendered = render_diff(&changes);
+    assert!(
+        rendered.contains("Rx0y0 multipath tun_b: Rx0y1(2)*1 Rx1y0(2)*1 -> Rx1y0(2)*1\n"),
+        "{}",
+        rendered
+    );
+    assert!(
+        rendered.contains("Rx0y0 single tun_b: Rx0y1(2) -> Rx1y0(2)\n"),
+        "{}",
+        rendered
+    );
+    // Rx0y1 now goes round through tun_b's router.
+    let change = changes
+        .iter()
+        .find(|c| c.router == "Rx0y1" && c.table == "single" && c.destination == "tun_a")
+        .expect("Rx0y1 towards tun_a changed");
+    assert_eq!(change.after[0].next_hop, "Rx1y1");
+    assert_eq!(change.after[0].cost, Some(3));
+}
+
+#[test]
+fn test_diff_shows_static_route_distance() {
+    let export = |distance: u8| {
+        let statics = format!(
+            "static_routes = {{ \"198.51.100.0/24\" = {{ via = \"Rx1y0\", distance = {} }} }}",
+            distance
+        );
+        let cfg = common::scenario(
+            "",
+            &[
+                ("Rx0y0", &statics),
+                ("Rx0y1", ""),
+                ("Rx1y0", ""),
+                ("Rx1y1", ""),
+            ],
+            &[
+                ("Rx0y0_Rx0y1", ""),
+                ("Rx0y1_Rx1y1", ""),
+                ("Rx0y0_Rx1y0", ""),
+                ("Rx1y0_Rx1y1", ""),
+            ],
+            "",
+        );
+        RoutingExport::new(&compute_routing_tables(&cfg), &Default::default())
+    };
+    let (before, after) = (export(200), export(5));
+    let csv = before.to_csv();
+    assert!(
+        csv.contains("Rx0y0,static,198.51.100.0/24,Rx1y0,,,200\n"),
+        "{}",
+        csv
+    );
+    assert_eq!(RoutingExport::parse(&csv), Ok(before.clone()));
+    assert_eq!(
+        render_diff(&diff(&before, &after)),
+        "Rx0y0 static 198.51.100.0/24: Rx1y0[200] -> Rx1y0[5]\n"
+    );
+}
+
+#[test]
+fn test_routes_subcommands() {
+    let mut cfg = NamedTempFile::new().unwrap();
+    cfg.write_all(
+        br#"
+[interfaces.real_tun_a]
+address = "10.0.0.1"
+netmask = "255.255.255.0"
+
+[interfaces.real_tun_b]
+address = "10.0.1.1"
+netmask = "255.255.255.0"
+
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+
+[topology.routers]
+Rx0y0 = {}
+Rx0y1 = {}
+
+[topology.links]
+Rx0y0_Rx0y1 = {}
+"#,
+    )
+    .unwrap();
+    let exported = NamedTempFile::new().unwrap();
+    cargo_bin_cmd!("network-simulator")
+        .arg("--config")
+        .arg(cfg.path())
+        .args(["routes", "export", "--format", "csv", "--output"])
+        .arg(exported.path())
+        .assert()
+        .success();
+    let csv = std::fs::read_to_string(exported.path()).unwrap();
+    assert!(csv.contains("Rx0y1,single,tun_a,Rx0y0,1,,\n"), "{}", csv);
+
+    let mut other = NamedTempFile::new().unwrap();
+    write!(
+        other,
+        "{}",
+        csv.replace(
+            "Rx0y1,single,tun_a,Rx0y0,1,,",
+            "Rx0y1,single,tun_a,Rx0y0,5,,"
+        )
+    )
+    .unwrap();
+    let out = cargo_bin_cmd!("network-simulator")
+        .args(["routes", "diff"])
+        .arg(exported.path())
+        .arg(other.path())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Rx0y1 single tun_a: Rx0y0(1) -> Rx0y0(5)\n"
+    );
+}
@@ -0,0 +1,79 @@
+use network_simulator::routing::{
+    compute_multi_path_routing, compute_routing, compute_routing_seeded,
+};
+use network_simulator::topology::{Fabric, LinkConfig, Router, RouterId};
+
+fn rid(s: &str) -> RouterId {
+    RouterId(s.to_string())
+}
+
+fn link() -> LinkConfig {
+    LinkConfig {
+        mtu: None,
+        delay_ms: 5,
+        jitter_ms: 0,
+        loss_percent: 0.0,
+        load_balance: false,
+        ..Default::default()
+    }
+}
+
+/// Diamond Rx0y0 -> {Rx0y1, Rx1y0} -> Rx1y1 built with routers and links in the given order.
+fn diamond(reversed: bool) -> Fabric {
+    let mut routers = vec!["Rx0y0", "Rx0y1", "Rx1y0", "Rx1y1"];
+    let mut links = vec![
+        ("Rx0y0", "Rx0y1"),
+        ("Rx0y0", "Rx1y0"),
+        ("Rx0y1", "Rx1y1"),
+        ("Rx1y0", "Rx1y1"),
+    ];
+    if reversed {
+        routers.reverse();
+        links.reverse();
+    }
+    let mut fabric = Fabric::new();
+    for r in routers {
+        fabric.add_router(Router::new(rid(r)));
+    }
+    for (a, b) in links {
+        fabric.add_link(&rid(a), &rid(b), link());
+    }
+    fabric
+}
+
+#[test]
+fn test_tie_break_prefers_lowest_router_id() {
+    for reversed in [false, true] {
+        let tables = compute_routing(&diamond(reversed), rid("Rx0y0"), rid("Rx1y1"));
+        assert_eq!(tables[&rid("Rx0y0")].tun_b.next_hop, rid("Rx0y1"));
+        assert_eq!(tables[&rid("Rx1y1")].tun_a.next_hop, rid("Rx0y1"));
+    }
+}
+
+#[test]
+fn test_seeded_tie_break_is_stable_and_spreads() {
+    let mut seen = std::collections::HashSet::new();
+    for seed in 0..32 {
+        let forward =
+            compute_routing_seeded(&diamond(false), rid("Rx0y0"), rid("Rx1y1"), Some(seed));
+        let reverse =
+            compute_routing_seeded(&diamond(true), rid("Rx0y0"), rid("Rx1y1"), Some(seed));
+        let hop = forward[&rid("Rx0y0")].tun_b.next_hop.clone();
+        assert_eq!(hop, reverse[&rid("Rx0y0")].tun_b.next_hop);
+        seen.insert(hop);
+    }
+    assert_eq!(seen.len(), 2, "different seeds should select both paths");
+}
+
+#[test]
+fn test_multipath_members_sorted() {
+    for reversed in [false, true] {
+        let tables = compute_multi_path_routing(&diamond(reversed), rid("Rx0y0"), rid("Rx1y1"));
+        let hops: Vec<RouterId> = tables[&rid("Rx0y0")]
+            .tun_a
+            .iter()
+            .map(|e| e.next_hop.clone())
+            .collect();
+        assert_eq!(hops, vec![rid("Rx0y1"), rid("Rx1y0")]);
+    }
+}
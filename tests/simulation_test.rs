@@ -9,7 +9,7 @@ use std::net::{IpAddr, Ipv4Addr};
 async fn test_link_simulation_and_load_balancing() {
     // Setup fabric with two routers and a link with load_balance enabled
     let mut fabric = Fabric::new();
-    let dummy_route = network_simulator::routing::RoutingTable {
+    let _dummy_route = network_simulator::routing::RoutingTable {
         tun_a: network_simulator::routing::RouteEntry {
             next_hop: RouterId("".to_string()),
             total_cost: 0,
@@ -18,6 +18,8 @@ async fn test_link_simulation_and_load_balancing() {
             next_hop: RouterId("".to_string()),
             total_cost: 0,
         },
+        fib: Default::default(),
+        pinned: Default::default(),
     };
     let r1 = Router::new(RouterId("Rx0y0".to_string()));
     let r2 = Router::new(RouterId("Rx0y1".to_string()));
@@ -29,6 +31,7 @@ async fn test_link_simulation_and_load_balancing() {
         jitter_ms: 0,
         loss_percent: 0.0,
         load_balance: true,
+        ..Default::default()
     };
     fabric.add_link(&r1.id, &r2.id, link_cfg.clone());
 
@@ -46,6 +49,8 @@ async fn test_link_simulation_and_load_balancing() {
                 next_hop: r2.id.clone(),
                 total_cost: 0,
             },
+            fib: Default::default(),
+            pinned: Default::default(),
         },
     );
     tables.insert(
@@ -59,6 +64,8 @@ async fn test_link_simulation_and_load_balancing() {
                 next_hop: r1.id.clone(),
                 total_cost: 0,
             },
+            fib: Default::default(),
+            pinned: Default::default(),
         },
     );
 
@@ -69,14 +76,20 @@ async fn test_link_simulation_and_load_balancing() {
         src_port: 1234,
         dst_port: 80,
         protocol: 6,
-        ttl: 64,
-        raw: vec![],
+        ..Default::default()
     };
 
     // Select egress link from r1
     let links = fabric.incident_links(&r1.id);
-    let selected = select_egress_link(&r1.id, &packet, &links, &tables, Destination::TunB)
-        .expect("link selected");
+    let selected = select_egress_link(
+        &r1.id,
+        &packet,
+        &links,
+        &tables,
+        Destination::TunB,
+        &fabric.ecmp_hash,
+    )
+    .expect("link selected");
     assert!(
         selected.cfg.load_balance,
         "selected link should have load_balance enabled"
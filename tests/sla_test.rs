@@ -0,0 +1,109 @@
+use network_simulator::config::{SimulatorConfig, SlaConfig};
+use network_simulator::sla::{evaluate, FlowMetrics};
+use network_simulator::topology::RouterId;
+use tokio::time::{Duration, Instant};
+
+fn config(loss_percent: f32) -> SimulatorConfig {
+    toml::from_str(&format!(
+        r#"
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+
+[topology.routers]
+Rx0y0 = {{}}
+Rx0y1 = {{}}
+
+[topology.links]
+Rx0y0_Rx0y1 = {{ delay_ms = 2, loss_percent = {} }}
+
+[virtual_customer]
+name = "acme"
+src_ip = "10.0.0.2"
+dst_ip = "10.0.1.2"
+protocol = 17
+size = 100
+rate = 5
+
+[virtual_customer.sla]
+max_loss_percent = 1.0
+p95_latency_ms = 500.0
+"#,
+        loss_percent
+    ))
+    .expect("config parses")
+}
+
+#[tokio::test]
+async fn test_sla_pass_on_clean_link() {
+    let fabric = network_simulator::run(config(0.0)).await.expect("run");
+    let flow = &fabric.customer_flows["acme"];
+    assert_eq!(flow.sent, 5);
+    assert_eq!(flow.delivered, 5);
+    assert!(flow.latency_percentile_ms(95.0).unwrap() >= 2.0);
+    assert_eq!(fabric.sla_results.len(), 1);
+    assert!(
+        fabric.sla_results[0].pass(),
+        "{}",
+        fabric.sla_results[0].render()
+    );
+}
+
+#[tokio::test]
+async fn test_sla_fails_on_lossy_link() {
+    let fabric = network_simulator::run(config(100.0)).await.expect("run");
+    let result = &fabric.sla_results[0];
+    assert!(!result.pass());
+    assert_eq!(result.checks[0].actual, Some(100.0));
+    // Nothing delivered, so the latency target cannot be met either.
+    assert!(result.checks[1].actual.is_none());
+    assert!(result.render().contains("VIOLATED"));
+}
+
+#[tokio::test]
+async fn test_reverse_flow_mirrors_customer() {
+    let mut cfg = config(0.0);
+    cfg.virtual_customer.as_mut().unwrap().reverse = true;
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    for name in ["acme", "acme-reverse"] {
+        let flow = &fabric.customer_flows[name];
+        assert_eq!((flow.sent, flow.delivered), (5, 5), "{}", name);
+    }
+    // The mirror flow entered at tun_b and left at tun_a.
+    let a = fabric.get_router(&RouterId("Rx0y0".to_string())).unwrap();
+    assert_eq!(a.stats.packets_delivered, 5);
+    let customers: Vec<&str> = fabric
+        .sla_results
+        .iter()
+        .map(|r| r.customer.as_str())
+        .collect();
+    assert_eq!(customers, ["acme", "acme-reverse"]);
+    assert!(fabric.sla_results.iter().all(|r| r.pass()));
+}
+
+#[test]
+fn test_sla_throughput_target() {
+    let start = Instant::now();
+    let mut metrics = FlowMetrics::default();
+    // 125 000 bytes delivered over one second = 1 Mbit/s.
+    metrics.record(start, 62_500, Some(Duration::from_millis(10)));
+    metrics.record(
+        start + Duration::from_millis(990),
+        62_500,
+        Some(Duration::from_millis(10)),
+    );
+    metrics.record(start + Duration::from_millis(995), 500, None);
+    assert!((metrics.throughput_mbps() - 1.0).abs() < 1e-6);
+    let sla = SlaConfig {
+        min_throughput_mbps: Some(0.5),
+        max_loss_percent: Some(50.0),
+        ..Default::default()
+    };
+    let result = evaluate("c1", &sla, &metrics);
+    assert!(result.pass(), "{}", result.render());
+    let strict = SlaConfig {
+        min_throughput_mbps: Some(2.0),
+        ..Default::default()
+    };
+    assert!(!evaluate("c1", &strict, &metrics).pass());
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use network_simulator::build_fabric;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::snapshot::Snapshot;
+use std::io::Write;
+use tempfile::NamedTempFile;
+use tokio::time::Duration;
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Rx0y0 - Rx0y1 - Rx0y2 with 40 ms links; `rest` goes after the topology.
+fn line(top: &str, rest: &str) -> SimulatorConfig {
+    common::line(
+        top,
+        &["", "", ""],
+        &["delay_ms = 40", "delay_ms = 40, cost = 7"],
+        rest,
+    )
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_snapshots_follow_runtime_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..4 {
+        writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    let path = packets.path().display().to_string();
+    let cfg = line(
+        &format!("packet_file = \"{}\"", path),
+        &format!(
+            r#"
+[snapshot]
+dir = "{}"
+interval_secs = 0.05
+
+[[event]]
+after_packets = 2
+action = "link_down"
+link = "Rx0y1_Rx0y2"
+
+[[event]]
+after_packets = 2
+action = "drain"
+router = "Rx0y1"
+"#,
+            dir.path().display()
+        ),
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    let written = &fabric.snapshots.as_ref().expect("snapshots").written;
+    assert!(written.len() >= 2, "{:?}", written);
+    let mut names: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    names.sort();
+    assert_eq!(&names, written);
+
+    // The first snapshot has the configured state, the last one the changes of the events.
+    let first = Snapshot::load(&written[0]).expect("first snapshot");
+    assert!(first.links["Rx0y1_Rx0y2"].up);
+    assert!(!first.routers["Rx0y1"].maintenance);
+    let last = Snapshot::load(written.last().unwrap()).expect("last snapshot");
+    assert!(last.elapsed_secs > first.elapsed_secs);
+    assert!(!last.links["Rx0y1_Rx0y2"].up);
+    assert!(last.links["Rx0y0_Rx0y1"].up);
+    assert_eq!(last.links["Rx0y1_Rx0y2"].cfg.cost, Some(7));
+    assert_eq!(last.links["Rx0y0_Rx0y1"].cfg.delay_ms, 40);
+    assert!(last.routers["Rx0y1"].maintenance);
+    assert!(last.taken_at.ends_with('Z'), "{}", last.taken_at);
+
+    // Restoring it reproduces that state at the start of a new run.
+    let mut restored = addressed(line("", ""));
+    last.apply(&mut restored).expect("apply");
+    restored.validate().expect("valid");
+    let events: Vec<_> = restored
+        .events
+        .iter()
+        .map(|e| (e.at_secs, e.action.as_str(), e.link.as_str()))
+        .collect();
+    assert_eq!(events, [(Some(0.0), "link_down", "Rx0y1_Rx0y2")]);
+    assert_eq!(restored.topology.links["Rx0y1_Rx0y2"].cost, Some(7));
+    let fabric = build_fabric(&restored);
+    let router = &fabric.graph[fabric.router_index[&common::rid("Rx0y1")]];
+    assert!(router.maintenance);
+}
+
+#[test]
+fn test_restore_keeps_link_direction() {
+    let reversed = || {
+        common::scenario(
+            "",
+            &[("Rx0y0", ""), ("Rx0y1", "")],
+            &[("Rx0y1_Rx0y0", "delay_ms_ab = 5, delay_ms_ba = 9")],
+            "",
+        )
+    };
+    // The snapshot names links by RouterId order, with their settings in that direction.
+    let snapshot = Snapshot::of(&build_fabric(&reversed()), Duration::from_secs(3));
+    assert_eq!(snapshot.elapsed_secs, 3.0);
+    let link = &snapshot.links["Rx0y0_Rx0y1"];
+    assert_eq!(
+        (link.cfg.delay_ms_ab, link.cfg.delay_ms_ba),
+        (Some(9), Some(5))
+    );
+
+    let text = toml::to_string(&snapshot).expect("encode");
+    let snapshot: Snapshot = toml::from_str(&text).expect("decode");
+    let mut restored = reversed();
+    snapshot.apply(&mut restored).expect("apply");
+    let link = &restored.topology.links["Rx0y1_Rx0y0"];
+    assert_eq!((link.delay_ms_ab, link.delay_ms_ba), (Some(5), Some(9)));
+    assert!(!restored.topology.links.contains_key("Rx0y0_Rx0y1"));
+    assert!(restored.events.is_empty());
+
+    let mut other = common::scenario(
+        "",
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx0y2", "")],
+        &[("Rx0y0_Rx0y2", ""), ("Rx0y2_Rx0y1", "")],
+        "",
+    );
+    let err = snapshot.apply(&mut other).unwrap_err();
+    assert!(err.contains("Snapshot link 'Rx0y0_Rx0y1'"), "{}", err);
+}
+
+#[test]
+fn test_invalid_snapshot_config_rejected() {
+    for (section, expected) in [
+        ("dir = \"\"", "snapshot.dir"),
+        (
+            "dir = \"snaps\"\ninterval_secs = 0",
+            "snapshot.interval_secs",
+        ),
+    ] {
+        let cfg = addressed(line("", &format!("[snapshot]\n{}", section)));
+        let err = cfg.validate().unwrap_err();
+        assert!(err.contains(expected), "{}: {}", expected, err);
+    }
+}
+
+#[test]
+fn test_restore_skips_events_already_fired() {
+    let events = r#"
+[[event]]
+at_secs = 1.0
+action = "link_down"
+link = "Rx0y1_Rx0y2"
+
+[[event]]
+at_secs = 3.0
+action = "link_up"
+link = "Rx0y1_Rx0y2"
+
+[[event]]
+after_packets = 10
+action = "drain"
+router = "Rx0y1"
+"#;
+    // Taken 1.5 s in, after the first event took the link down.
+    let mut fabric = build_fabric(&line("", events));
+    fabric
+        .set_link_up(&common::rid("Rx0y1"), &common::rid("Rx0y2"), false)
+        .unwrap();
+    let snapshot = Snapshot::of(&fabric, Duration::from_millis(1500));
+
+    let mut restored = addressed(line("", events));
+    snapshot.apply(&mut restored).expect("apply");
+    restored.validate().expect("valid");
+    let events: Vec<_> = restored
+        .events
+        .iter()
+        .map(|e| (e.at_secs, e.after_packets, e.action.as_str()))
+        .collect();
+    // The link starts down, comes back up 1.5 s later as it would have, and the event
+    // counting packets is left alone.
+    assert_eq!(
+        events,
+        [
+            (Some(1.5), None, "link_up"),
+            (None, Some(10), "drain"),
+            (Some(0.0), None, "link_down"),
+        ]
+    );
+}
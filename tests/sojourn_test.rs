@@ -0,0 +1,127 @@
+mod common;
+
+use network_simulator::sojourn::TRACE_HEADER;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const PACKET: &str = "4500001400000000401100000a0000020a000102";
+
+/// Trace records (header skipped) for `count` packets across Rx0y0 - Rx0y1 - Rx0y2.
+async fn trace(count: usize, links: &[&str]) -> Vec<Vec<String>> {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..count {
+        writeln!(packets, "{}", PACKET).unwrap();
+    }
+    let trace = NamedTempFile::new().unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!(
+            "packet_file = \"{}\"\npacket_trace = \"{}\"\n[simulation]\nseed = 7",
+            path,
+            trace.path().display()
+        ),
+        &["", "", ""],
+        links,
+        "",
+    );
+    network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    let text = std::fs::read_to_string(trace.path()).unwrap();
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some(TRACE_HEADER));
+    lines
+        .map(|l| l.split(',').map(str::to_string).collect())
+        .collect()
+}
+
+fn ms(field: &str) -> f64 {
+    field.parse().unwrap()
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_trace_breaks_latency_into_components() {
+    // 0.16 Mbps serialises the 20-byte packet in 1 ms.
+    let records = trace(1, &["delay_ms = 10", "delay_ms = 5, bandwidth_mbps = 0.16"]).await;
+    assert_eq!(records.len(), 1);
+    let r = &records[0];
+    assert_eq!(&r[..5], &["1", "Rx0y0", "Rx0y2", "delivered", "2"]);
+    assert_eq!(ms(&r[5]), 16.0, "elapsed");
+    assert_eq!(ms(&r[6]), 16.0, "total");
+    assert_eq!(ms(&r[7]), 15.0, "propagation");
+    assert_eq!(ms(&r[8]), 1.0, "queueing");
+    assert_eq!(ms(&r[9]), 0.0, "jitter");
+    assert_eq!(ms(&r[10]), 0.0, "processing");
+    assert_eq!(&r[11..15], &["10.0.0.2", "10.0.1.2", "17", "20"]);
+    // Sent and received by the edge clocks, which agree by default.
+    assert_eq!(ms(&r[16]) - ms(&r[15]), 16.0, "received - sent");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_components_add_up_to_measured_time_with_jitter() {
+    let records = trace(5, &["delay_ms = 10, jitter_ms = 4", "delay_ms = 2"]).await;
+    assert_eq!(records.len(), 5);
+    let mut jittered = false;
+    for r in &records {
+        assert_eq!(r[3], "delivered");
+        assert_eq!(ms(&r[7]), 12.0);
+        assert!((ms(&r[5]) - ms(&r[6])).abs() < 1e-6, "{:?}", r);
+        assert!(ms(&r[9]).abs() <= 4.0);
+        jittered |= ms(&r[9]) != 0.0;
+    }
+    assert!(jittered, "{:?}", records);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_dropped_packet_is_recorded_where_it_was_lost() {
+    let records = trace(1, &["delay_ms = 3", "loss_percent = 100"]).await;
+    let r = &records[0];
+    assert_eq!(&r[1..5], &["Rx0y0", "Rx0y1", "dropped", "1"]);
+    assert_eq!(ms(&r[6]), 3.0);
+    assert_eq!(r[16], "", "never received");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_json_trace_carries_the_packet_and_its_path() {
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", PACKET).unwrap();
+    let trace = NamedTempFile::new().unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!(
+            "packet_file = \"{}\"\npacket_trace = \"{}\"\npacket_trace_format = \"json\"",
+            path,
+            trace.path().display()
+        ),
+        &["", "", ""],
+        &["delay_ms = 4", "delay_ms = 1"],
+        "",
+    );
+    network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    let text = std::fs::read_to_string(trace.path()).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 1, "no header: {}", text);
+    let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(record["seq"], 1);
+    assert_eq!(record["outcome"], "delivered");
+    assert_eq!(record["elapsed_ms"], 5.0);
+    let packet = &record["packet"];
+    assert_eq!(packet["src_ip"], "10.0.0.2");
+    assert_eq!(packet["dst_ip"], "10.0.1.2");
+    assert_eq!(packet["protocol"], 17);
+    // Two routers decremented the TTL of the 64 the packet was sent with.
+    assert_eq!(packet["ttl"], 62);
+    let raw = hex::decode(packet["raw"].as_str().unwrap()).unwrap();
+    assert_eq!(raw.len(), 20);
+    let annotation = &packet["annotation"];
+    assert_eq!(
+        annotation["routers"],
+        serde_json::json!(["Rx0y0", "Rx0y1", "Rx0y2"])
+    );
+    assert_eq!(
+        annotation["links"][0],
+        serde_json::json!({"a": "Rx0y0", "b": "Rx0y1"})
+    );
+    assert_eq!(annotation["delay"]["propagation_ms"], 5.0);
+    assert!(packet.get("mpls").is_none() && packet.get("vlan").is_none());
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::{SimulatorConfig, Srv6Config};
+use network_simulator::ddos::{build_flood_packet, FloodKind};
+use network_simulator::packet::{explain, PacketMeta};
+use network_simulator::processor::process_packet;
+use network_simulator::routing::Destination;
+use network_simulator::srv6::Srv6;
+use network_simulator::topology::Fabric;
+
+// Two paths from Rx0y0 to Rx1y1: the short one via Rx0y1 and a longer one via Rx1y0/Rx2y0.
+fn scenario() -> SimulatorConfig {
+    common::scenario(
+        "",
+        &[
+            ("Rx0y0", ""),
+            ("Rx0y1", ""),
+            ("Rx1y0", ""),
+            ("Rx2y0", ""),
+            ("Rx1y1", ""),
+        ],
+        &[
+            ("Rx0y0_Rx0y1", ""),
+            ("Rx0y1_Rx1y1", ""),
+            ("Rx0y0_Rx1y0", ""),
+            ("Rx1y0_Rx2y0", ""),
+            ("Rx2y0_Rx1y1", ""),
+        ],
+        "",
+    )
+}
+
+fn udp6() -> PacketMeta {
+    build_flood_packet(
+        FloodKind::Udp,
+        "2001:db8::2".parse().unwrap(),
+        "2001:db8:1::2".parse().unwrap(),
+        40000,
+        5060,
+        80,
+    )
+}
+
+fn forwarded(fabric: &Fabric, id: &str) -> u64 {
+    fabric.get_router(&rid(id)).unwrap().stats.packets_forwarded
+}
+
+fn srv6(fabric: &Fabric, a_to_b: &[&str]) -> Result<Srv6, String> {
+    let cfg = Srv6Config {
+        a_to_b: a_to_b.iter().map(|s| s.to_string()).collect(),
+        b_to_a: Vec::new(),
+    };
+    Srv6::new(&cfg, fabric)
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_segment_list_steers_packet_and_is_popped() {
+    let cfg = scenario();
+    let mut fabric = network_simulator::build_fabric(&cfg);
+    let tables = network_simulator::compute_routing_tables(&cfg);
+    let mut srv6 = srv6(&fabric, &["fd00::2:0"]).expect("segments resolve");
+    let original = udp6();
+    let mut packet = original.clone();
+    srv6.insert(Destination::TunB, &mut packet);
+    // SRH with the segment and the original destination: 8 + 2 * 16 bytes.
+    assert_eq!(packet.raw.len(), original.raw.len() + 40);
+    assert_eq!(packet.dst_ip.to_string(), "fd00::2:0");
+    let text = explain(&packet.raw);
+    assert!(
+        text.contains("extension Routing (40 bytes), next header 17"),
+        "{}",
+        text
+    );
+    fabric.srv6 = Some(srv6);
+
+    let out = process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        packet,
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(forwarded(&fabric, "Rx2y0"), 1);
+    assert_eq!(forwarded(&fabric, "Rx0y1"), 0);
+    assert_eq!(
+        fabric
+            .get_router(&rid("Rx1y1"))
+            .unwrap()
+            .stats
+            .packets_delivered,
+        1
+    );
+    // Three hops later the packet is the original one again.
+    let mut expected = original.raw.to_vec();
+    expected[7] -= 3;
+    assert_eq!(out.raw, expected);
+    assert_eq!(out.dst_ip, original.dst_ip);
+    let stats = &fabric.srv6.as_ref().unwrap().stats;
+    assert_eq!((stats.inserted, stats.endpoints, stats.popped), (1, 1, 1));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_segment_beyond_the_egress_is_visited_first() {
+    let cfg = scenario();
+    let mut fabric = network_simulator::build_fabric(&cfg);
+    let tables = network_simulator::compute_routing_tables(&cfg);
+    // Out to Rx2y0 and back through the egress, then in via Rx0y1.
+    let mut srv6 = srv6(&fabric, &["fd00::2:0", "fd00::0:1"]).unwrap();
+    let mut packet = udp6();
+    srv6.insert(Destination::TunB, &mut packet);
+    fabric.srv6 = Some(srv6);
+    process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        packet,
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(forwarded(&fabric, "Rx2y0"), 1);
+    assert_eq!(forwarded(&fabric, "Rx1y1"), 1);
+    assert_eq!(forwarded(&fabric, "Rx0y1"), 1);
+    assert_eq!(
+        fabric
+            .get_router(&rid("Rx1y1"))
+            .unwrap()
+            .stats
+            .packets_delivered,
+        1
+    );
+    assert_eq!(fabric.srv6.as_ref().unwrap().stats.endpoints, 2);
+}
+
+#[test]
+fn test_ipv4_untouched_and_unknown_segment_rejected() {
+    let fabric = network_simulator::build_fabric(&scenario());
+    let mut srv6 = srv6(&fabric, &["fd00::2:0"]).unwrap();
+    let mut packet = build_flood_packet(
+        FloodKind::Udp,
+        "10.0.0.2".parse().unwrap(),
+        "10.0.1.2".parse().unwrap(),
+        40000,
+        53,
+        60,
+    );
+    let before = packet.raw.clone();
+    srv6.insert(Destination::TunB, &mut packet);
+    assert_eq!(packet.raw, before);
+    assert_eq!(srv6.stats.inserted, 0);
+
+    let err = self::srv6(&fabric, &["fd00::5:5"]).unwrap_err();
+    assert!(err.contains("not a router address"), "{}", err);
+    assert!(self::srv6(&fabric, &["Rx2y0"]).is_err());
+}
@@ -0,0 +1,239 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::PacketMeta;
+use network_simulator::processor::process_packet_to_edge;
+use network_simulator::routing::{compute_routing, Destination};
+use network_simulator::topology::Fabric;
+use network_simulator::{build_fabric, compute_routing_tables};
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Square Rx0y0 - Rx0y1 - Rx1y1 - Rx1y0 - Rx0y0, tun_a at Rx0y0 and tun_b at Rx1y1, with
+/// the way round through Rx1y0 costlier and `statics` as the static routes of Rx0y0.
+fn square(statics: &str) -> SimulatorConfig {
+    square_with(statics, "", "")
+}
+
+/// [`square`] with `rx1y1` as the static routes of Rx1y1 and `rest` after the topology.
+fn square_with(statics: &str, rx1y1: &str, rest: &str) -> SimulatorConfig {
+    let rx0y0 = format!("static_routes = {{ {} }}", statics);
+    let rx1y1 = format!("static_routes = {{ {} }}", rx1y1);
+    addressed(common::scenario(
+        "",
+        &[
+            ("Rx0y0", &rx0y0),
+            ("Rx0y1", ""),
+            ("Rx1y0", ""),
+            ("Rx1y1", &rx1y1),
+        ],
+        &[
+            ("Rx0y0_Rx0y1", ""),
+            ("Rx0y1_Rx1y1", ""),
+            ("Rx0y0_Rx1y0", "cost = 5"),
+            ("Rx1y0_Rx1y1", ""),
+        ],
+        rest,
+    ))
+}
+
+#[test]
+fn test_static_edge_route_overrides_shortest_path() {
+    let tables = compute_routing_tables(&square(""));
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.next_hop, rid("Rx0y1"));
+
+    let cfg = square("tun_b = \"Rx1y0\"");
+    cfg.validate().expect("valid");
+    let tables = compute_routing_tables(&cfg);
+    let route = &tables[&rid("Rx0y0")].tun_b;
+    assert_eq!(route.next_hop, rid("Rx1y0"));
+    assert_eq!(
+        route.total_cost,
+        5 + tables[&rid("Rx1y0")].tun_b.total_cost,
+        "the cost is the pinned link's plus the next hop's"
+    );
+    // Other routers and the other edge keep their computed routes.
+    assert_eq!(tables[&rid("Rx0y0")].tun_a.next_hop, rid("Rx0y0"));
+    assert_eq!(tables[&rid("Rx1y0")].tun_b.next_hop, rid("Rx1y1"));
+
+    // A static route over a failed link is ignored.
+    let mut fabric = build_fabric(&cfg);
+    fabric
+        .set_link_up(&rid("Rx0y0"), &rid("Rx1y0"), false)
+        .unwrap();
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx1y1"));
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.next_hop, rid("Rx0y1"));
+}
+
+fn udp(dst: &str) -> PacketMeta {
+    PacketBuilder::new("10.0.0.2".parse().unwrap(), dst.parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap()
+}
+
+fn received(fabric: &Fabric, router: &str) -> u64 {
+    fabric
+        .get_router(&rid(router))
+        .unwrap()
+        .stats
+        .packets_received
+}
+
+#[tokio::test]
+async fn test_static_prefix_route_pins_next_hop() {
+    let cfg = square("\"198.51.100.0/24\" = \"Rx1y0\", \"198.51.100.128/25\" = \"Rx0y1\"");
+    cfg.validate().expect("valid");
+    let mut fabric = build_fabric(&cfg);
+    let tables = compute_routing_tables(&cfg);
+    let pinned = &tables[&rid("Rx0y0")];
+    assert_eq!(
+        pinned.pinned_hop(&"198.51.100.200".parse().unwrap()),
+        Some(&rid("Rx0y1")),
+        "the most specific static prefix wins"
+    );
+    assert_eq!(pinned.pinned_hop(&"10.0.1.2".parse().unwrap()), None);
+
+    // The packet still heads for its edge, just by the pinned way.
+    let (out, edge) = process_packet_to_edge(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp("198.51.100.7"),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!((edge, out.ttl), (Some(Destination::TunB), 62));
+    assert_eq!(received(&fabric, "Rx1y0"), 1);
+    assert_eq!(received(&fabric, "Rx0y1"), 0);
+
+    // Other destinations follow the computed routes.
+    process_packet_to_edge(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp("10.0.1.2"),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(received(&fabric, "Rx1y0"), 1);
+    assert_eq!(received(&fabric, "Rx0y1"), 1);
+}
+
+#[test]
+fn test_edge_routes_resolved_by_distance() {
+    // A floating static route stays out while the computed route is there.
+    let cfg = square("tun_b = { via = \"Rx1y0\", distance = 200 }");
+    cfg.validate().expect("valid");
+    let tables = compute_routing_tables(&cfg);
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.next_hop, rid("Rx0y1"));
+
+    // It wins once the computed routes are less trusted.
+    let cfg = square_with(
+        "tun_b = { via = \"Rx1y0\", distance = 200 }",
+        "",
+        "[routing]\ndistance = 250\n",
+    );
+    cfg.validate().expect("valid");
+    let tables = compute_routing_tables(&cfg);
+    assert_eq!(tables[&rid("Rx0y0")].tun_b.next_hop, rid("Rx1y0"));
+
+    // A router's connected edge beats any static route towards it.
+    let tables = compute_routing_tables(&square("tun_a = \"Rx0y1\""));
+    assert_eq!(tables[&rid("Rx0y0")].tun_a.next_hop, rid("Rx0y0"));
+    assert_eq!(tables[&rid("Rx0y0")].tun_a.total_cost, 0);
+}
+
+#[tokio::test]
+async fn test_prefix_routes_resolved_by_distance() {
+    let route = "[[route]]\nprefix = \"198.51.100.0/24\"\nrouter = \"Rx1y1\"\nedge = \"tun_b\"\n";
+    let dst: std::net::IpAddr = "198.51.100.7".parse().unwrap();
+    let resolve = |statics: &str, rx1y1: &str| {
+        let cfg = square_with(statics, rx1y1, route);
+        cfg.validate().expect("valid");
+        let fabric = build_fabric(&cfg);
+        let tables = compute_routing_tables(&cfg);
+        ["Rx0y0", "Rx1y1"].map(|router| {
+            tables[&rid(router)]
+                .static_hop(&dst, None, fabric.routing_distance)
+                .cloned()
+        })
+    };
+    // A static route beats the computed FIB route for the same prefix, unless it floats.
+    assert_eq!(
+        resolve("\"198.51.100.0/24\" = \"Rx1y0\"", ""),
+        [Some(rid("Rx1y0")), None]
+    );
+    assert_eq!(
+        resolve(
+            "\"198.51.100.0/24\" = { via = \"Rx1y0\", distance = 200 }",
+            ""
+        ),
+        [None, None]
+    );
+    // The more specific prefix wins whatever its distance.
+    assert_eq!(
+        resolve(
+            "\"198.51.100.0/25\" = { via = \"Rx1y0\", distance = 200 }",
+            ""
+        ),
+        [Some(rid("Rx1y0")), None]
+    );
+    // The prefix's attachment keeps it as a connected route.
+    assert_eq!(resolve("", "\"198.51.100.0/24\" = \"Rx0y1\""), [None, None]);
+
+    // A floating static leaves the packets on the computed path.
+    let cfg = square_with(
+        "\"198.51.100.0/24\" = { via = \"Rx1y0\", distance = 200 }",
+        "",
+        route,
+    );
+    let mut fabric = build_fabric(&cfg);
+    let tables = compute_routing_tables(&cfg);
+    let (_, edge) = process_packet_to_edge(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp("198.51.100.7"),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(edge, Some(Destination::TunB));
+    assert_eq!(received(&fabric, "Rx0y1"), 1);
+    assert_eq!(received(&fabric, "Rx1y0"), 0);
+}
+
+#[test]
+fn test_static_route_validation() {
+    let err = square("tun_b = \"Rx1y1\"").validate().unwrap_err();
+    assert!(
+        err.contains("static route next hop 'Rx1y1' for tun_b is not a neighbour"),
+        "{}",
+        err
+    );
+    let err = square("tun_c = \"Rx0y1\"").validate().unwrap_err();
+    assert!(
+        err.contains("Invalid static route destination 'tun_c'"),
+        "{}",
+        err
+    );
+    let err = square("tun_b = { via = \"Rx1y0\", distance = 0 }")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("distance for tun_b"), "{}", err);
+    let err = square_with("", "", "[routing]\ndistance = 0\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("routing.distance"), "{}", err);
+    let mut cfg = square("tun_b = \"Rx1y0\"");
+    cfg.enable_multipath = true;
+    assert!(cfg.validate().unwrap_err().contains("enable_multipath"));
+}
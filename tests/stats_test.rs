@@ -0,0 +1,286 @@
+mod common;
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use common::rid;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::processor::process_packet;
+use network_simulator::routing::{compute_routing, Destination};
+use network_simulator::stats::{diff, CounterState, StatsDump};
+use network_simulator::topology::{Fabric, LinkConfig, Router};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// Counters after 4 packets across Rx0y0 - Rx0y1 - Rx0y2 with the given second link.
+async fn dump(second_link: &str) -> StatsDump {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..4 {
+        writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", "", ""],
+        &["bandwidth_mbps = 100", second_link],
+        "",
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    StatsDump::from_fabric(&fabric)
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_dump_round_trips_and_diffs() {
+    let before = dump("").await;
+    // The startup demonstration packet is counted too.
+    assert_eq!(before.routers["Rx0y0"]["packets_injected"], 5.0);
+    assert_eq!(before.routers["Rx0y2"]["packets_delivered"], 5.0);
+    assert_eq!(before.links["Rx0y1_Rx0y2"]["packets"], 5.0);
+    assert_eq!(before.links["Rx0y0_Rx0y1"]["Rx0y0->Rx0y1/q0/enqueued"], 5.0);
+
+    let file = NamedTempFile::new().unwrap();
+    let path = file.path().display().to_string();
+    before.write(&path).unwrap();
+    assert_eq!(StatsDump::load(&path).unwrap(), before);
+
+    let after = dump("loss_percent = 100").await;
+    let table = diff(&before, &after);
+    let row = |kind: &str, name: &str, counter: &str| -> Vec<String> {
+        table
+            .lines()
+            .find(|l| {
+                let f: Vec<&str> = l.split_whitespace().collect();
+                f.len() > 3 && f[0] == kind && f[1] == name && f[2] == counter
+            })
+            .unwrap_or_else(|| panic!("no {} {} {} in\n{}", kind, name, counter, table))
+            .split_whitespace()
+            .skip(3)
+            .map(str::to_string)
+            .collect()
+    };
+    assert_eq!(
+        row("router", "Rx0y2", "packets_delivered"),
+        ["5", "0", "-5", "-100.0%"]
+    );
+    assert_eq!(
+        row("router", "Rx0y1", "packets_lost"),
+        ["0", "5", "+5", "-"]
+    );
+    assert_eq!(
+        row("link", "Rx0y0_Rx0y1", "packets"),
+        ["5", "5", "+0", "+0.0%"]
+    );
+}
+
+#[tokio::test]
+async fn test_link_size_histogram() {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx0y1"] {
+        fabric.add_router(Router::new(rid(name)));
+    }
+    let cfg = LinkConfig {
+        mtu: Some(9000),
+        ..Default::default()
+    };
+    fabric.add_link(&rid("Rx0y0"), &rid("Rx0y1"), cfg);
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx0y1"));
+    for len in [28, 64, 65, 1500, 1501, 9000] {
+        let packet = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+            .udp(1000, 2000)
+            .payload(vec![0; len - 28])
+            .build()
+            .unwrap();
+        process_packet(
+            &mut fabric,
+            &tables,
+            rid("Rx0y0"),
+            packet,
+            Destination::TunB,
+        )
+        .await;
+    }
+    let dump = StatsDump::from_fabric(&fabric);
+    let sizes: Vec<(&str, f64)> = dump.links["Rx0y0_Rx0y1"]
+        .iter()
+        .filter_map(|(k, &v)| Some((k.strip_prefix("sizes/")?, v)))
+        .collect();
+    let mut expected = vec![
+        ("0-64", 2.0),
+        ("65-127", 1.0),
+        ("128-255", 0.0),
+        ("256-511", 0.0),
+        ("512-1023", 0.0),
+        ("1024-1279", 0.0),
+        ("1280-1500", 1.0),
+        ("1501+", 2.0),
+    ];
+    expected.sort_by(|a, b| a.0.cmp(b.0));
+    assert_eq!(sizes, expected);
+}
+
+#[test]
+fn test_stats_diff_subcommand() {
+    let write = |json: &str| {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(json.as_bytes()).unwrap();
+        file
+    };
+    let a = write(r#"{"routers": {"Rx0y0": {"packets_received": 10}}}"#);
+    let b = write(
+        r#"{"routers": {"Rx0y0": {"packets_received": 15}, "Rx0y1": {"packets_received": 3}}}"#,
+    );
+    let out = cargo_bin_cmd!("network-simulator")
+        .args(["stats", "diff"])
+        .arg(a.path())
+        .arg(b.path())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("kind"), "{}", out);
+    let rows: Vec<Vec<&str>> = out
+        .lines()
+        .skip(1)
+        .map(|l| l.split_whitespace().collect())
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            vec![
+                "router",
+                "Rx0y0",
+                "packets_received",
+                "10",
+                "15",
+                "+5",
+                "+50.0%"
+            ],
+            vec!["router", "Rx0y1", "packets_received", "-", "3", "-", "-"],
+        ]
+    );
+
+    let broken = write("not json");
+    cargo_bin_cmd!("network-simulator")
+        .args(["stats", "diff"])
+        .arg(a.path())
+        .arg(broken.path())
+        .assert()
+        .failure();
+}
+
+#[tokio::test]
+async fn test_router_bytes_and_protocols() {
+    let cfg = common::line("", &["", "", ""], &["", ""], "");
+    let mut fabric = network_simulator::build_fabric(&cfg);
+    let tables = network_simulator::compute_routing_tables(&cfg);
+    let builder = || PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap());
+    let packets = [
+        builder().udp(1000, 2000).payload(vec![0; 72]).build(),
+        builder().tcp(1000, 80).build(),
+        builder().echo_request(1, 1).build(),
+        builder().protocol(47).build(),
+    ];
+    let mut bytes = 0;
+    for packet in packets {
+        let packet = packet.unwrap();
+        bytes += packet.raw.len() as u64;
+        process_packet(
+            &mut fabric,
+            &tables,
+            rid("Rx0y0"),
+            packet,
+            Destination::TunB,
+        )
+        .await;
+    }
+    let stats = &fabric.get_router(&rid("Rx0y1")).unwrap().stats;
+    assert_eq!(
+        (stats.bytes_received, stats.bytes_forwarded),
+        (bytes, bytes)
+    );
+    assert_eq!(
+        (
+            stats.tcp_received,
+            stats.udp_received,
+            stats.icmp_received,
+            stats.other_received
+        ),
+        (1, 1, 1, 1)
+    );
+    let dump = StatsDump::from_fabric(&fabric);
+    assert_eq!(dump.routers["Rx0y2"]["bytes_received"], bytes as f64);
+    assert_eq!(dump.routers["Rx0y2"]["bytes_forwarded"], 0.0, "delivered");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_counter_state_accumulates_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = dir.path().join("counters.json");
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..4 {
+        writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    let path = packets.path().display().to_string();
+    let run = |fresh: bool| {
+        let mut cfg = common::line(
+            &format!(
+                "packet_file = \"{}\"\ncounter_state = \"{}\"",
+                path,
+                state.display()
+            ),
+            &["", "", ""],
+            &["", ""],
+            "",
+        );
+        cfg.fresh = fresh;
+        network_simulator::run(cfg)
+    };
+    let once = StatsDump::from_fabric(&run(false).await.expect("first run"));
+    let fabric = run(false).await.expect("second run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    let received = |dump: &StatsDump| dump.routers["Rx0y0"]["packets_received"];
+    assert!(received(&once) >= 4.0);
+
+    // The run's own counters start from zero; the state file adds them up.
+    assert_eq!(received(&StatsDump::from_fabric(&fabric)), received(&once));
+    let saved = CounterState::open(&state.display().to_string(), false).expect("state");
+    assert_eq!(saved.runs, 2);
+    assert_eq!(received(&saved.totals), 2.0 * received(&once));
+    assert_eq!(saved.totals.links["Rx0y1_Rx0y2"]["state"], 2.0);
+    assert_eq!(fabric.counter_state.as_ref(), Some(&saved));
+    // `stats diff` reads the state file like a dump.
+    let totals = StatsDump::load(&state.display().to_string()).expect("dump");
+    assert_eq!(totals, saved.totals);
+
+    run(true).await.expect("fresh run");
+    let saved = CounterState::open(&state.display().to_string(), false).expect("state");
+    assert_eq!(saved.runs, 1);
+    assert_eq!(received(&saved.totals), received(&once));
+}
+
+#[test]
+fn test_counter_state_merges_gauges() {
+    let dump = |state: f64, depth: f64| -> StatsDump {
+        serde_json::from_str(&format!(
+            r#"{{"links": {{"A_B": {{"packets": 5, "state": {}, "ab/be/max_depth": {}}}}}}}"#,
+            state, depth
+        ))
+        .unwrap()
+    };
+    let mut state = CounterState::default();
+    state.add(&dump(2.0, 7.0));
+    state.add(&dump(0.0, 3.0));
+    let link = &state.totals.links["A_B"];
+    assert_eq!(
+        (link["packets"], link["state"], link["ab/be/max_depth"]),
+        (10.0, 0.0, 7.0)
+    );
+
+    let mut broken = NamedTempFile::new().unwrap();
+    write!(broken, "not json").unwrap();
+    let path = broken.path().display().to_string();
+    let err = CounterState::open(&path, false).unwrap_err();
+    assert!(err.contains("Invalid counter state"), "{}", err);
+    assert_eq!(CounterState::open(&path, true), Ok(CounterState::default()));
+}
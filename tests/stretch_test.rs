@@ -0,0 +1,85 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::routing::Destination;
+use network_simulator::stretch::{analyze, render, PathStretch};
+
+/// Square with tun_a at Rx0y0 and tun_b at Rx1y1: the way through Rx0y1 costs 2, the way
+/// through Rx1y0 costs 10. `a_opts` are Rx0y0's options; `links` are extra links.
+fn square(a_opts: &str, links: &[(&str, &str)]) -> SimulatorConfig {
+    let mut all = vec![
+        ("Rx0y0_Rx0y1", "cost = 1"),
+        ("Rx0y1_Rx1y1", "cost = 1"),
+        ("Rx0y0_Rx1y0", "cost = 5"),
+        ("Rx1y0_Rx1y1", "cost = 5"),
+    ];
+    all.extend_from_slice(links);
+    common::scenario(
+        "",
+        &[
+            ("Rx0y0", a_opts),
+            ("Rx0y1", ""),
+            ("Rx1y0", ""),
+            ("Rx1y1", ""),
+        ],
+        &all,
+        "",
+    )
+}
+
+fn towards_b<'a>(paths: &'a [PathStretch], router: &str) -> &'a PathStretch {
+    paths
+        .iter()
+        .find(|p| p.router == rid(router) && p.destination == Destination::TunB)
+        .unwrap()
+}
+
+#[test]
+fn test_shortest_paths_are_not_stretched() {
+    let paths = analyze(&square("", &[]));
+    assert_eq!(paths.len(), 8);
+    assert!(paths.iter().all(|p| p.stretch() == Some(1.0)));
+    let report = render(&paths);
+    assert!(
+        report.contains("Rx0y0 -> tun_b: cost 2 (optimum 2, stretch 1.00), 2 hops"),
+        "{}",
+        report
+    );
+    assert!(report.ends_with(
+        "0 of 8 paths stretched; cost stretch mean 1.00 max 1.00, hop stretch mean 1.00 max 1.00\n"
+    ));
+}
+
+#[test]
+fn test_static_route_stretches_cost() {
+    let paths = analyze(&square(r#"static_routes = { tun_b = "Rx1y0" }"#, &[]));
+    let path = towards_b(&paths, "Rx0y0");
+    assert_eq!(path.routers, [rid("Rx0y0"), rid("Rx1y0"), rid("Rx1y1")]);
+    assert_eq!((path.cost, path.optimal_cost), (10, Some(2)));
+    assert_eq!(path.stretch(), Some(5.0));
+    assert_eq!(path.hop_stretch(), Some(1.0));
+    let report = render(&paths);
+    assert!(
+        report.contains("Rx0y0 -> tun_b: STRETCHED: cost 10 (optimum 2, stretch 5.00)"),
+        "{}",
+        report
+    );
+    assert!(report.contains("1 of 8 paths stretched"), "{}", report);
+}
+
+#[test]
+fn test_cheapest_path_stretches_hops() {
+    // A direct but costly link: SPF keeps the two hops through Rx0y1.
+    let paths = analyze(&square("", &[("Rx0y0_Rx1y1", "cost = 10")]));
+    let path = towards_b(&paths, "Rx0y0");
+    assert_eq!((path.hops(), path.min_hops), (2, Some(1)));
+    assert_eq!((path.stretch(), path.hop_stretch()), (Some(1.0), Some(2.0)));
+    assert!(path.stretched());
+
+    // With min-hop routing every link costs 1: the direct link is the cheapest and shortest.
+    let mut cfg = square("", &[("Rx0y0_Rx1y1", "cost = 10")]);
+    cfg.routing.algorithm = network_simulator::routing::RoutingAlgorithm::MinHop;
+    let paths = analyze(&cfg);
+    assert!(paths.iter().all(|p| !p.stretched()));
+}
@@ -0,0 +1,135 @@
+use network_simulator::experiment::{combinations, parse_sweep_param, run_sweep};
+#[cfg(feature = "virtual-time")]
+use network_simulator::experiment::{run_sweep_virtual_time, sweep_csv};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const SCENARIO: &str = r#"
+[interfaces.real_tun_a]
+address = "10.0.0.1"
+
+[interfaces.real_tun_b]
+address = "10.0.1.1"
+
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+
+[topology.routers]
+Rx0y0 = {}
+Rx0y1 = {}
+
+[topology.links]
+Rx0y0_Rx0y1 = { delay_ms = 1 }
+
+[virtual_customer]
+src_ip = "10.0.0.2"
+dst_ip = "10.0.1.2"
+size = 100
+rate = 2
+"#;
+
+#[test]
+fn test_parse_sweep_param_forms() {
+    let p = parse_sweep_param("topology.links.Rx0y0_Rx0y1.delay_ms=0:50:10").unwrap();
+    assert_eq!(p.key, "topology.links.Rx0y0_Rx0y1.delay_ms");
+    assert_eq!(p.values.len(), 6);
+    assert_eq!(p.values[5].as_integer(), Some(50));
+    let p = parse_sweep_param("x.loss_percent=0:1:0.25").unwrap();
+    assert_eq!(p.values.len(), 5);
+    assert_eq!(p.values[4].as_float(), Some(1.0));
+    let p = parse_sweep_param("x.loss_percent=0,1.5,5").unwrap();
+    assert_eq!(p.values[1].as_float(), Some(1.5));
+    assert!(parse_sweep_param("x=5:1:1").is_err());
+    assert!(parse_sweep_param("x=0:5:0").is_err());
+    assert!(parse_sweep_param("no_values").is_err());
+}
+
+#[test]
+fn test_combinations_cartesian_product() {
+    let a = parse_sweep_param("a=1,2").unwrap();
+    let b = parse_sweep_param("b=10,20,30").unwrap();
+    let combos = combinations(&[a, b]);
+    assert_eq!(combos.len(), 6);
+    assert_eq!(combos[0][0].as_integer(), Some(1));
+    assert_eq!(combos[3][0].as_integer(), Some(2));
+    assert_eq!(combos[3][1].as_integer(), Some(10));
+}
+
+#[cfg(feature = "virtual-time")]
+#[test]
+fn test_sweep_in_virtual_time_produces_csv() {
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    let mut base: toml::Value = toml::from_str(SCENARIO).unwrap();
+    base.as_table_mut().unwrap().insert(
+        "packet_file".to_string(),
+        toml::Value::String(packets.path().to_string_lossy().into_owned()),
+    );
+    let params = vec![
+        parse_sweep_param("topology.links.Rx0y0_Rx0y1.delay_ms=100,2000").unwrap(),
+        parse_sweep_param("topology.links.Rx0y0_Rx0y1.loss_percent=0,100").unwrap(),
+    ];
+    let wall = std::time::Instant::now();
+    let results = run_sweep_virtual_time(&base, &params, 2, false).expect("sweep");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+    // Several seconds of simulated delay complete almost instantly on the paused clock.
+    assert!(wall.elapsed().as_secs_f64() < 2.0);
+    assert_eq!(results.len(), 4);
+    let latency = results[2].1.latency_avg_ms.unwrap();
+    assert!((2000.0..2100.0).contains(&latency), "latency {}", latency);
+    assert_eq!(results[3].1.delivered, 0);
+    let csv = sweep_csv(&params, &results);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with(
+        "topology.links.Rx0y0_Rx0y1.delay_ms,topology.links.Rx0y0_Rx0y1.loss_percent,delivered"
+    ));
+    assert!(lines[3].starts_with("2000,0,"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_parallel_sweep_runs_use_their_own_seeded_rng() {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..40 {
+        writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    let mut base: toml::Value = toml::from_str(SCENARIO).unwrap();
+    let table = base.as_table_mut().unwrap();
+    table.insert(
+        "packet_file".to_string(),
+        toml::Value::String(packets.path().to_string_lossy().into_owned()),
+    );
+    table.insert(
+        "simulation".to_string(),
+        toml::from_str("seed = 7").unwrap(),
+    );
+    // Four identical lossy runs: with per-run generators they all see the same loss pattern,
+    // however they interleave.
+    let params = vec![
+        parse_sweep_param("topology.links.Rx0y0_Rx0y1.loss_percent=50").unwrap(),
+        parse_sweep_param("topology.links.Rx0y0_Rx0y1.delay_ms=1,1,1,1").unwrap(),
+    ];
+    let serial = run_sweep(&base, &params, 1, false)
+        .await
+        .expect("serial sweep");
+    let parallel = run_sweep(&base, &params, 4, false)
+        .await
+        .expect("parallel sweep");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+    let delivered: Vec<u64> = serial
+        .iter()
+        .chain(&parallel)
+        .map(|(_, s)| s.delivered)
+        .collect();
+    assert!(
+        delivered[0] > 0 && delivered[0] < 41,
+        "delivered {:?}",
+        delivered
+    );
+    assert!(
+        delivered.iter().all(|d| *d == delivered[0]),
+        "{:?}",
+        delivered
+    );
+}
@@ -0,0 +1,81 @@
+use network_simulator::tap::{TapEdge, TapInput, BROADCAST_MAC, ETHERTYPE_ARP, ETHERTYPE_IPV4};
+use std::net::{IpAddr, Ipv4Addr};
+
+const ROUTER_MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x0a];
+const HOST_MAC: [u8; 6] = [0x52, 0x54, 0, 0x12, 0x34, 0x56];
+
+fn edge() -> TapEdge {
+    TapEdge::new(ROUTER_MAC, Ipv4Addr::new(10, 0, 0, 1))
+}
+
+fn arp_request(target: Ipv4Addr) -> Vec<u8> {
+    let mut frame = Vec::new();
+    frame.extend_from_slice(&BROADCAST_MAC);
+    frame.extend_from_slice(&HOST_MAC);
+    frame.extend_from_slice(&ETHERTYPE_ARP.to_be_bytes());
+    frame.extend_from_slice(&[0, 1, 8, 0, 6, 4, 0, 1]);
+    frame.extend_from_slice(&HOST_MAC);
+    frame.extend_from_slice(&[10, 0, 0, 2]);
+    frame.extend_from_slice(&[0; 6]);
+    frame.extend_from_slice(&target.octets());
+    frame
+}
+
+fn ipv4_packet(src: [u8; 4], dst: [u8; 4]) -> Vec<u8> {
+    let mut raw = vec![0x45, 0, 0, 20, 0, 0, 0, 0, 64, 17, 0, 0];
+    raw.extend_from_slice(&src);
+    raw.extend_from_slice(&dst);
+    raw
+}
+
+#[test]
+fn test_tap_answers_arp_for_router_address() {
+    let mut tap = edge();
+    let reply = match tap.receive(&arp_request(Ipv4Addr::new(10, 0, 0, 1))) {
+        TapInput::Reply(frame) => frame,
+        other => panic!("expected ARP reply, got {:?}", other),
+    };
+    assert_eq!(&reply[0..6], &HOST_MAC);
+    assert_eq!(&reply[6..12], &ROUTER_MAC);
+    assert_eq!(&reply[12..14], &ETHERTYPE_ARP.to_be_bytes());
+    // Operation 2 (reply), sender hardware address is the router.
+    assert_eq!(&reply[20..22], &[0, 2]);
+    assert_eq!(&reply[22..28], &ROUTER_MAC);
+    // Requests for other addresses are left alone.
+    assert_eq!(
+        tap.receive(&arp_request(Ipv4Addr::new(10, 0, 0, 7))),
+        TapInput::Ignore
+    );
+}
+
+#[test]
+fn test_tap_unwraps_ip_and_learns_sender() {
+    let mut tap = edge();
+    let packet = ipv4_packet([10, 0, 0, 2], [10, 0, 1, 5]);
+    let mut frame = Vec::new();
+    frame.extend_from_slice(&ROUTER_MAC);
+    frame.extend_from_slice(&HOST_MAC);
+    frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+    frame.extend_from_slice(&packet);
+    assert_eq!(tap.receive(&frame), TapInput::Packet(&packet[..]));
+    let host = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    assert_eq!(tap.neighbour(&host), Some(HOST_MAC));
+
+    // Replies towards the learned host are unicast, unknown hosts get the broadcast MAC.
+    let back = tap.encapsulate(&ipv4_packet([10, 0, 1, 5], [10, 0, 0, 2]));
+    assert_eq!(&back[0..6], &HOST_MAC);
+    assert_eq!(&back[14..], &ipv4_packet([10, 0, 1, 5], [10, 0, 0, 2])[..]);
+    let unknown = tap.encapsulate(&ipv4_packet([10, 0, 1, 5], [10, 0, 0, 9]));
+    assert_eq!(&unknown[0..6], &BROADCAST_MAC);
+}
+
+#[test]
+fn test_tap_ignores_frames_for_other_stations() {
+    let mut tap = edge();
+    let mut frame = Vec::new();
+    frame.extend_from_slice(&[0x52, 0x54, 0, 0, 0, 1]);
+    frame.extend_from_slice(&HOST_MAC);
+    frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+    frame.extend_from_slice(&ipv4_packet([10, 0, 0, 2], [10, 0, 0, 3]));
+    assert_eq!(tap.receive(&frame), TapInput::Ignore);
+}
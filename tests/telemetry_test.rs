@@ -0,0 +1,205 @@
+mod common;
+
+use network_simulator::config::{SimulatorConfig, TelemetryConfig};
+use network_simulator::telemetry::dashboard::{self, Topology};
+use network_simulator::telemetry::{self, Path, Telemetry};
+use network_simulator::topology::{Fabric, LinkConfig, Router};
+use std::io::Write;
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+#[test]
+fn test_paths_parse_print_and_cover() {
+    let counter =
+        Path::parse("/interfaces/interface[name=Rx0y0_Rx0y1]/state/counters/out-pkts").unwrap();
+    assert_eq!(
+        counter.to_string(),
+        "/interfaces/interface[name=Rx0y0_Rx0y1]/state/counters/out-pkts"
+    );
+    for (subscription, covered) in [
+        ("/", true),
+        ("/interfaces", true),
+        ("/interfaces/interface", true),
+        ("/interfaces/interface[name=*]/state", true),
+        ("/interfaces/interface[name=Rx0y0_Rx0y1]", true),
+        ("/interfaces/interface[name=Rx0y1_Rx0y2]", false),
+        ("/network-instances", false),
+        (
+            "/interfaces/interface[name=Rx0y0_Rx0y1]/state/counters/out-pkts/more",
+            false,
+        ),
+    ] {
+        let path = Path::parse(subscription).unwrap();
+        assert_eq!(path.covers(&counter), covered, "{}", subscription);
+    }
+    assert!(Path::parse("/interfaces/interface[id=1]").is_err());
+}
+
+#[tokio::test]
+async fn test_counters_published_at_end_of_run() {
+    let mut packets = NamedTempFile::new().unwrap();
+    for _ in 0..3 {
+        writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", "", ""],
+        &["", ""],
+        "[telemetry]\ninterval_ms = 50\n",
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    let telemetry = fabric.telemetry.as_ref().expect("telemetry");
+    assert!(telemetry.published >= 1);
+    let sample = telemetry.last();
+    // The three packets plus the empty startup packet.
+    assert_eq!(
+        sample.get("/interfaces/interface[name=Rx0y0_Rx0y1]/state/counters/out-pkts"),
+        Some(4)
+    );
+    assert_eq!(
+        sample.get("/interfaces/interface[name=Rx0y1_Rx0y2]/state/counters/out-octets"),
+        Some(60)
+    );
+    assert_eq!(
+        sample.get(
+            "/network-instances/network-instance[name=Rx0y2]/state/counters/packets-delivered"
+        ),
+        Some(4)
+    );
+    assert_eq!(sample.counters.len(), 3 * 16 + 2 * 4);
+}
+
+#[test]
+fn test_bad_telemetry_config_rejected() {
+    let cfg = common::line("", &["", ""], &[""], "[telemetry]\ninterval_ms = 0\n");
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("interval_ms"), "{}", err);
+    let cfg = common::line(
+        "",
+        &["", ""],
+        &[""],
+        "[telemetry]\ngnmi_listen = \"not an address\"\n",
+    );
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("gnmi_listen"), "{}", err);
+    let cfg = common::line(
+        "",
+        &["", ""],
+        &[""],
+        "[telemetry]\ndashboard_listen = \"localhost\"\n",
+    );
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("dashboard_listen"), "{}", err);
+}
+
+/// Response to `GET <target>` from the dashboard at `addr`, read to the end.
+async fn get(addr: std::net::SocketAddr, target: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!("GET {} HTTP/1.1\r\nHost: test\r\n\r\n", target);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+/// Data of the next Server-Sent Event.
+async fn next_data(events: &mut BufReader<TcpStream>) -> serde_json::Value {
+    loop {
+        let mut line = String::new();
+        timeout(Duration::from_secs(5), events.read_line(&mut line))
+            .await
+            .expect("event in time")
+            .unwrap();
+        if let Some(data) = line.strip_prefix("data: ") {
+            return serde_json::from_str(data).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_dashboard_serves_page_topology_and_events() {
+    let mut fabric = Fabric::new();
+    for name in ["Rx0y0", "Rx1y0"] {
+        fabric.add_router(Router::new(common::rid(name)));
+    }
+    let link = LinkConfig {
+        bandwidth_mbps: Some(10.0),
+        ..Default::default()
+    };
+    fabric.add_link(&common::rid("Rx0y0"), &common::rid("Rx1y0"), link);
+    fabric.telemetry = Some(Telemetry::new(&TelemetryConfig::default()));
+    telemetry::publish(&mut fabric);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let samples = fabric.telemetry.as_ref().unwrap().subscribe();
+    tokio::spawn(dashboard::serve(listener, Topology::of(&fabric), samples));
+
+    let page = get(addr, "/").await;
+    assert!(page.starts_with("HTTP/1.1 200 OK"), "{}", page);
+    assert!(page.contains("<svg") && page.contains("EventSource"));
+    let topology = get(addr, "/topology").await;
+    let body = topology.split("\r\n\r\n").nth(1).unwrap();
+    let json: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(json["routers"], serde_json::json!(["Rx0y0", "Rx1y0"]));
+    assert_eq!(json["links"][0]["name"], "Rx0y0_Rx1y0");
+    assert_eq!(json["links"][0]["bandwidth_mbps"], 10.0);
+    assert!(get(addr, "/nothing").await.starts_with("HTTP/1.1 404"));
+    // A header running past the 8 KiB limit is refused; the dashboard keeps serving. (Sent
+    // exactly up to the limit, so the refusal isn't lost to a reset over unread bytes.)
+    let mut huge = TcpStream::connect(addr).await.unwrap();
+    let mut header = "GET / HTTP/1.1\r\nX-Filler: ".to_string();
+    header.push_str(&"a".repeat(8 * 1024 - header.len()));
+    let _ = huge.write_all(header.as_bytes()).await;
+    let mut response = String::new();
+    let _ = huge.read_to_string(&mut response).await;
+    assert!(response.starts_with("HTTP/1.1 431"), "{}", response);
+    assert!(get(addr, "/").await.starts_with("HTTP/1.1 200 OK"));
+
+    let mut events = TcpStream::connect(addr).await.unwrap();
+    events
+        .write_all(b"GET /events HTTP/1.1\r\nHost: test\r\n\r\n")
+        .await
+        .unwrap();
+    let mut events = BufReader::new(events);
+    assert_eq!(
+        next_data(&mut events).await["links"]["Rx0y0_Rx1y0"]["pkts"],
+        0
+    );
+    let link = fabric
+        .get_link(&common::rid("Rx0y0"), &common::rid("Rx1y0"))
+        .unwrap();
+    link.counter.store(5, std::sync::atomic::Ordering::Relaxed);
+    telemetry::publish(&mut fabric);
+    assert_eq!(
+        next_data(&mut events).await["links"]["Rx0y0_Rx1y0"]["pkts"],
+        5
+    );
+    fabric.telemetry.as_mut().unwrap().close();
+    assert_eq!(next_data(&mut events).await, serde_json::json!({}));
+}
+
+#[cfg(not(feature = "gnmi"))]
+#[test]
+fn test_gnmi_listen_requires_feature() {
+    let cfg = common::line(
+        "",
+        &["", ""],
+        &[""],
+        "[telemetry]\ngnmi_listen = \"127.0.0.1:57400\"\n",
+    );
+    let err = addressed(cfg).validate().unwrap_err();
+    assert!(err.contains("'gnmi' feature"), "{}", err);
+}
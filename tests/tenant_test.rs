@@ -0,0 +1,174 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::vlan::VlanTag;
+use network_simulator::packet::PacketMeta;
+use network_simulator::processor::process_packet_to_edge;
+use network_simulator::routing::{Destination, RoutingTable};
+use network_simulator::topology::{Fabric, RouterId};
+use network_simulator::{build_fabric, compute_routing_tables};
+use std::collections::HashMap;
+
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Line Rx0y0 - Rx0y1 - Rx0y2 - Rx0y3 shared by tenants red (10.1.0.0/16, VLAN 100) and blue
+/// (10.2.0.0/16, VLAN 200), which both use 192.168.1.0/24: red's behind Rx0y1, blue's behind
+/// Rx0y3. `rest` follows the tables.
+fn tenants(rest: &str) -> SimulatorConfig {
+    addressed(common::line(
+        "",
+        &["", "", "", ""],
+        &["", "", ""],
+        &format!(
+            r#"[[tenant]]
+name = "red"
+prefixes = ["10.1.0.0/16", "192.168.1.0/24"]
+vlans = [100]
+
+[[tenant]]
+name = "blue"
+prefixes = ["10.2.0.0/16", "192.168.1.0/24"]
+vlans = [200]
+
+[[route]]
+prefix = "192.168.1.0/24"
+router = "Rx0y1"
+edge = "tun_a"
+tenant = "red"
+
+[[route]]
+prefix = "192.168.1.0/24"
+router = "Rx0y3"
+edge = "tun_b"
+tenant = "blue"
+
+{}"#,
+            rest
+        ),
+    ))
+}
+
+fn udp(src: &str, dst: &str) -> PacketMeta {
+    PacketBuilder::new(src.parse().unwrap(), dst.parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_tenant_routes_and_classification() {
+    let cfg = tenants("");
+    cfg.validate().expect("valid");
+    let tables = compute_routing_tables(&cfg);
+    let fib = &tables[&rid("Rx0y2")].fib;
+    let dst = "192.168.1.7".parse().unwrap();
+    let exit = |tenant| fib.lookup_for(&dst, tenant).map(|r| r.exit.0.clone());
+    assert_eq!(exit(Some("red")).as_deref(), Some("Rx0y1"));
+    assert_eq!(exit(Some("blue")).as_deref(), Some("Rx0y3"));
+    assert_eq!(
+        exit(None),
+        None,
+        "packets of no tenant do not see the tenants' routes"
+    );
+
+    let fabric = build_fabric(&cfg);
+    let tenants = fabric.tenants.as_ref().expect("tenants");
+    assert_eq!(tenants.classify(&udp("10.2.3.4", "10.0.1.2")), Some("blue"));
+    assert_eq!(tenants.classify(&udp("10.9.3.4", "10.0.1.2")), None);
+    // The shared prefix is the first tenant's, unless the VLAN tells otherwise.
+    let mut shared = udp("192.168.1.5", "10.0.1.2");
+    assert_eq!(tenants.classify(&shared), Some("red"));
+    shared.vlan = Some(VlanTag {
+        vid: 200,
+        pcp: 0,
+        dei: false,
+    });
+    assert_eq!(tenants.classify(&shared), Some("blue"));
+}
+
+/// Send a UDP packet from `src` to `dst` in at Rx0y0. Returns the edge it left towards and the
+/// last router it reached.
+async fn send(
+    fabric: &mut Fabric,
+    tables: &HashMap<RouterId, RoutingTable>,
+    src: &str,
+    dst: &str,
+) -> (Option<Destination>, Option<String>) {
+    let (out, edge) = process_packet_to_edge(
+        fabric,
+        tables,
+        rid("Rx0y0"),
+        udp(src, dst),
+        Destination::TunB,
+    )
+    .await;
+    let last = out.annotation.and_then(|a| a.routers.last().cloned());
+    (edge, last.map(|r| r.0))
+}
+
+#[tokio::test]
+async fn test_isolation_and_stats() {
+    let cfg = tenants("");
+    let mut fabric = build_fabric(&cfg);
+    let tables = compute_routing_tables(&cfg);
+    // Each tenant reaches its own 192.168.1.0/24.
+    let (edge, last) = send(&mut fabric, &tables, "10.1.0.5", "192.168.1.7").await;
+    assert_eq!(
+        (edge, last.as_deref()),
+        (Some(Destination::TunA), Some("Rx0y1"))
+    );
+    let (edge, last) = send(&mut fabric, &tables, "10.2.0.5", "192.168.1.7").await;
+    assert_eq!(
+        (edge, last.as_deref()),
+        (Some(Destination::TunB), Some("Rx0y3"))
+    );
+    // Red may not reach blue's addresses.
+    let (edge, last) = send(&mut fabric, &tables, "10.1.0.5", "10.2.0.9").await;
+    assert_eq!((edge, last.as_deref()), (None, Some("Rx0y0")));
+    // Packets of no tenant are not isolated.
+    let (edge, _) = send(&mut fabric, &tables, "10.9.0.5", "10.2.0.9").await;
+    assert_eq!(edge, Some(Destination::TunB));
+
+    let tenants = fabric.tenants.as_ref().unwrap();
+    let red = tenants.get("red").unwrap().stats;
+    assert_eq!(
+        (red.packets, red.delivered, red.dropped, red.leaks),
+        (2, 1, 1, 1)
+    );
+    assert_eq!(red.bytes, 2 * 28);
+    let blue = tenants.get("blue").unwrap().stats;
+    assert_eq!((blue.packets, blue.delivered, blue.leaks), (1, 1, 0));
+}
+
+#[test]
+fn test_tenant_validation() {
+    let err = tenants("[[tenant]]\nname = \"red\"\nvlans = [300]\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("unique"), "{}", err);
+    let err = tenants("[[tenant]]\nname = \"green\"\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("needs prefixes or vlans"), "{}", err);
+    let err = tenants("[[tenant]]\nname = \"green\"\nprefixes = [\"10.3.0.0/33\"]\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("invalid prefix"), "{}", err);
+    let err = tenants("[[tenant]]\nname = \"green\"\nvlans = [4095]\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("VLAN 4095"), "{}", err);
+    let route = "[[route]]\nprefix = \"10.3.0.0/16\"\nrouter = \"Rx0y2\"\nedge = \"tun_b\"\n";
+    let err = tenants(&format!("{}tenant = \"green\"\n", route))
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("tenant 'green'"), "{}", err);
+}
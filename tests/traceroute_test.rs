@@ -0,0 +1,100 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::routing::Destination;
+use network_simulator::traceroute::{self, TraceOptions};
+use std::net::IpAddr;
+
+/// Line Rx0y0 - Rx0y1 - Rx0y2 - Rx0y3 with tun_a behind Rx0y0 and tun_b behind Rx0y3.
+fn line(loss_percent: f64) -> SimulatorConfig {
+    common::line(
+        "",
+        &["", "", "", ""],
+        &[
+            "delay_ms = 1",
+            "delay_ms = 1",
+            &format!("delay_ms = 1, loss_percent = {}", loss_percent),
+        ],
+        "",
+    )
+}
+
+#[test]
+fn test_destination_for_uses_edge_prefixes() {
+    let cfg = line(0.0);
+    let dst = |s: &str| traceroute::destination_for(&cfg, s.parse::<IpAddr>().unwrap());
+    assert_eq!(dst("10.0.0.7"), Destination::TunA);
+    assert_eq!(dst("10.0.1.7"), Destination::TunB);
+    assert_eq!(dst("192.0.2.1"), Destination::TunB);
+    assert_eq!(dst("2001:db8::1"), Destination::TunB);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_traceroute_lists_every_hop() {
+    let cfg = line(0.0);
+    let dst: IpAddr = "10.0.1.2".parse().unwrap();
+    for icmp in [false, true] {
+        let opts = TraceOptions { max_ttl: 8, icmp };
+        let hops = traceroute::run(&cfg, &rid("Rx0y1"), dst, &opts)
+            .await
+            .unwrap();
+        let routers: Vec<_> = hops.iter().map(|h| h.router.clone()).collect();
+        assert_eq!(routers, vec![Some(rid("Rx0y2")), Some(rid("Rx0y3"))]);
+        assert!(!hops[0].reached);
+        let last = hops.last().unwrap();
+        assert!(last.reached);
+        assert_eq!(last.address, Some(dst));
+        // Two 1 ms links lie between the origin and the destination edge.
+        assert!(last.rtt_ms.unwrap() >= 2.0);
+        let text = traceroute::render(&rid("Rx0y1"), dst, 8, &hops);
+        assert!(text.contains(" 1  Rx0y2 ("));
+        assert!(text.contains("(delivered by Rx0y3)"));
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_traceroute_ipv6_towards_tun_a() {
+    let mut cfg = line(0.0);
+    cfg.tun_ingress.tun_a_ipv6_prefix = "fd00:a::/64".to_string();
+    let dst: IpAddr = "fd00:a::2".parse().unwrap();
+    let hops = traceroute::run(&cfg, &rid("Rx0y2"), dst, &TraceOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(hops[0].router, Some(rid("Rx0y1")));
+    assert!(matches!(hops[0].address, Some(IpAddr::V6(_))));
+    assert!(hops.last().unwrap().reached);
+    assert_eq!(hops.len(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_traceroute_lossy_link_shows_stars() {
+    let cfg = line(100.0);
+    let dst: IpAddr = "10.0.1.2".parse().unwrap();
+    let opts = TraceOptions {
+        max_ttl: 4,
+        icmp: false,
+    };
+    let hops = traceroute::run(&cfg, &rid("Rx0y0"), dst, &opts)
+        .await
+        .unwrap();
+    assert_eq!(hops.len(), 4);
+    assert_eq!(hops[0].router, Some(rid("Rx0y1")));
+    assert_eq!(hops[1].router, Some(rid("Rx0y2")));
+    assert!(hops[2..].iter().all(|h| h.address.is_none() && !h.reached));
+    let text = traceroute::render(&rid("Rx0y0"), dst, 4, &hops);
+    assert!(text.contains(" 3  *"));
+}
+
+#[tokio::test]
+async fn test_traceroute_unknown_origin() {
+    let err = traceroute::run(
+        &line(0.0),
+        &rid("Rx9y9"),
+        "10.0.1.2".parse().unwrap(),
+        &TraceOptions::default(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.contains("Rx9y9"));
+}
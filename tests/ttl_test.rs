@@ -0,0 +1,81 @@
+mod common;
+
+use network_simulator::packet::parse;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// Run one IPv4 packet with `ttl` from tun_a across Rx0y0 - Rx0y1 - Rx0y2 and return what
+/// leaves at tun_b (empty if nothing did).
+async fn run(ttl: u8, section: &str) -> String {
+    let mut inner =
+        parse(&hex::decode("4500001400000000401100000a0000020a000102").unwrap()).unwrap();
+    inner.set_ttl(ttl);
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", hex::encode(&inner.raw)).unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"\npacket_inject_tun = \"tun_a\"", path),
+        &["", "", ""],
+        &["", ""],
+        section,
+    );
+    network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    out.trim().to_string()
+}
+
+fn ttl_of(hex_packet: &str) -> u8 {
+    let packet = parse(&hex::decode(hex_packet).unwrap()).unwrap();
+    assert_eq!(
+        network_simulator::packet::calculate_ipv4_checksum(&packet.raw),
+        u16::from_be_bytes([packet.raw[10], packet.raw[11]])
+    );
+    packet.ttl
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_uniform_mode_decrements_per_router() {
+    assert_eq!(ttl_of(&run(64, "").await), 62);
+    let rewritten = run(200, "[ttl.tun_a]\nset = 64\n").await;
+    assert_eq!(ttl_of(&rewritten), 62);
+    // The rewrite applies only to packets entering at the configured edge.
+    let other_edge = run(200, "[ttl.tun_b]\nset = 64\n").await;
+    assert_eq!(ttl_of(&other_edge), 198);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_pipe_mode_is_ttl_transparent() {
+    assert_eq!(ttl_of(&run(40, "[ttl.tun_a]\nmode = \"pipe\"\n").await), 40);
+    // A TTL that would expire at the first router crosses the fabric untouched.
+    let out = run(1, "[ttl.tun_a]\nmode = \"pipe\"\n").await;
+    assert_eq!(ttl_of(&out), 1);
+    assert_eq!(
+        ttl_of(&run(200, "[ttl.tun_a]\nset = 64\nmode = \"pipe\"\n").await),
+        64
+    );
+}
+
+#[test]
+fn test_ttl_config_validated() {
+    let with_interfaces = |section: &str| {
+        let mut cfg = common::line("", &["", ""], &[""], section);
+        cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+        cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+        cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+        cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+        cfg
+    };
+    with_interfaces("[ttl.tun_b]\nmode = \"pipe\"\n")
+        .validate()
+        .expect("valid");
+    let err = with_interfaces("[ttl.tun_a]\nmode = \"short-pipe\"\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("short-pipe"), "{}", err);
+    let err = with_interfaces("[ttl.tun_a]\nset = 0\n")
+        .validate()
+        .unwrap_err();
+    assert!(err.contains("at least 1"), "{}", err);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use network_simulator::routing::Destination;
+use network_simulator::stats::{diff, StatsDump};
+use network_simulator::topology::Fabric;
+use network_simulator::tun::counters::{InterfaceCounters, TunCounters};
+use std::io::Write;
+
+#[test]
+fn test_interface_counters() {
+    let mut counters = InterfaceCounters::default();
+    counters.received(100);
+    counters.received(60);
+    counters.sent(100, &Ok::<_, ()>(100));
+    counters.sent(1500, &Err::<usize, _>("no buffer space"));
+    counters.parse_errors += 1;
+    assert_eq!(
+        (counters.rx_packets, counters.rx_bytes),
+        (2, 160),
+        "reads are counted with their size"
+    );
+    assert_eq!(
+        (counters.tx_packets, counters.tx_bytes, counters.tx_errors),
+        (1, 100, 1),
+        "failed writes count as errors only"
+    );
+    assert_eq!(
+        counters.summary(),
+        "rx_packets=2, rx_bytes=160, rx_errors=0, parse_errors=1, stripped=0, answered=0, \
+         ignored=0, tx_packets=1, tx_bytes=100, tx_errors=1"
+    );
+}
+
+#[test]
+fn test_interface_counters_in_stats() {
+    let mut fabric = Fabric::new();
+    assert!(StatsDump::from_fabric(&fabric).interfaces.is_empty());
+    let before = StatsDump::from_fabric(&fabric);
+
+    fabric.interface(Destination::TunA).received(80);
+    fabric.interface(Destination::TunB).stripped += 2;
+    let dump = StatsDump::from_fabric(&fabric);
+    assert_eq!(dump.interfaces["tun_a"]["rx_bytes"], 80.0);
+    assert_eq!(dump.interfaces["tun_b"]["stripped"], 2.0);
+    assert_eq!(dump.interfaces["tun_b"].len(), 10);
+    let table = diff(&before, &dump);
+    assert!(
+        table
+            .lines()
+            .any(|l| l.split_whitespace().collect::<Vec<_>>()
+                == ["iface", "tun_a", "rx_packets", "-", "1", "-", "-"]),
+        "{}",
+        table
+    );
+
+    fabric.reset_statistics();
+    assert_eq!(fabric.tun_counters, Some(TunCounters::default()));
+}
+
+#[tokio::test]
+async fn test_mock_run_has_no_interfaces() {
+    let mut packets = tempfile::NamedTempFile::new().unwrap();
+    writeln!(packets, "4500001400000000401100000a0000020a000102").unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(&format!("packet_file = \"{}\"", path), &["", ""], &[""], "");
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", path));
+    assert_eq!(fabric.tun_counters, None);
+    assert!(StatsDump::from_fabric(&fabric).interfaces.is_empty());
+}
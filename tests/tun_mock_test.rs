@@ -53,10 +53,13 @@ fn test_tun_mock_packet_processing() {
                         jitter_ms: 0,
                         loss_percent: 0.0,
                         load_balance: false,
+                        ..Default::default()
                     },
                 );
                 map
             },
+            reference_bandwidth_mbps: None,
+            pools: HashMap::new(),
         },
         enable_multipath: false,
         packet_file: Some(path.clone()),
@@ -64,6 +67,7 @@ fn test_tun_mock_packet_processing() {
         packet_inject_tun: None,
         packet_inject_tuns: None,
         virtual_customer: None,
+        ..Default::default()
     };
 
     // Build fabric as in lib::run.
@@ -83,6 +87,7 @@ fn test_tun_mock_packet_processing() {
             jitter_ms: 0,
             loss_percent: 0.0,
             load_balance: false,
+            ..Default::default()
         },
     );
 
@@ -59,10 +59,13 @@ fn test_multiple_mock_packet_files_injection() {
                         jitter_ms: 0,
                         loss_percent: 0.0,
                         load_balance: false,
+                        ..Default::default()
                     },
                 );
                 map
             },
+            reference_bandwidth_mbps: None,
+            pools: HashMap::new(),
         },
         enable_multipath: false,
         packet_file: None,
@@ -70,6 +73,7 @@ fn test_multiple_mock_packet_files_injection() {
         packet_inject_tun: None,
         packet_inject_tuns: Some(vec!["tun_a".to_string(), "tun_b".to_string()]),
         virtual_customer: None,
+        ..Default::default()
     };
 
     // Build fabric.
@@ -87,6 +91,7 @@ fn test_multiple_mock_packet_files_injection() {
             jitter_ms: 0,
             loss_percent: 0.0,
             load_balance: false,
+            ..Default::default()
         },
     );
 
@@ -0,0 +1,167 @@
+use network_simulator::clock::{EdgeClock, EdgeClocks};
+use network_simulator::config::{ClockSkewConfig, EdgeClockConfig, TwampConfig};
+use network_simulator::routing::compute_routing;
+use network_simulator::topology::{Fabric, LinkConfig, Router, RouterId};
+use network_simulator::twamp::{run_session, TwampReport, TwampSample, TwampSession};
+use std::collections::HashMap;
+
+fn two_router_fabric(delay_ms: u32, loss_percent: f32) -> (Fabric, RouterId, RouterId) {
+    let mut fabric = Fabric::new();
+    let a = RouterId("Rx0y0".to_string());
+    let b = RouterId("Rx0y1".to_string());
+    fabric.add_router(Router::new(a.clone()));
+    fabric.add_router(Router::new(b.clone()));
+    fabric.add_link(
+        &a,
+        &b,
+        LinkConfig {
+            mtu: None,
+            delay_ms,
+            jitter_ms: 0,
+            loss_percent,
+            load_balance: false,
+            ..Default::default()
+        },
+    );
+    (fabric, a, b)
+}
+
+#[tokio::test]
+async fn test_twamp_session_measures_delay() {
+    let (mut fabric, a, b) = two_router_fabric(5, 0.0);
+    let tables = compute_routing(&fabric, a.clone(), b.clone());
+    let cfg = TwampConfig {
+        count: 3,
+        interval_ms: 0,
+        port: 862,
+        ..Default::default()
+    };
+    let report = run_session(&cfg, &mut fabric, &tables, &HashMap::new(), false, &a, &b).await;
+    assert_eq!(report.sent(), 3);
+    assert_eq!(report.forward_lost(), 0);
+    assert_eq!(report.reverse_lost(), 0);
+    assert_eq!(report.loss_percent(), 0.0);
+    // Each direction crosses the 5 ms link once.
+    assert!(report.forward_avg_ms().unwrap() >= 5.0);
+    assert!(report.reverse_avg_ms().unwrap() >= 5.0);
+    assert!(report.rtt_min_ms().unwrap() >= 10.0);
+    // Probes stay out of the router counters.
+    for router in [&a, &b] {
+        let stats = &fabric.get_router(router).unwrap().stats;
+        assert_eq!((stats.packets_received, stats.packets_delivered), (0, 0));
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_twamp_one_way_delays_follow_clock_skew() {
+    let (mut fabric, a, b) = two_router_fabric(5, 0.0);
+    let tables = compute_routing(&fabric, a.clone(), b.clone());
+    // The reflector's clock is 3 ms ahead.
+    fabric.clocks.tun_b = EdgeClock {
+        offset_ms: 3.0,
+        drift_ppm: 0.0,
+    };
+    let cfg = TwampConfig {
+        count: 2,
+        interval_ms: 0,
+        port: 862,
+        ..Default::default()
+    };
+    let report = run_session(&cfg, &mut fabric, &tables, &HashMap::new(), false, &a, &b).await;
+    assert_eq!(report.forward_avg_ms(), Some(8.0));
+    assert_eq!(report.reverse_avg_ms(), Some(2.0));
+    assert_eq!(report.rtt_avg_ms(), Some(10.0));
+
+    // A sender clock gaining 10% from the start of the run stamps the probe on time (at 0) but
+    // reads 11 ms when the reply comes back 10 ms later: drift skews the round trip too.
+    let (mut fabric, a, b) = two_router_fabric(5, 0.0);
+    fabric.clocks.tun_a.drift_ppm = 100_000.0;
+    let cfg = TwampConfig { count: 1, ..cfg };
+    let report = run_session(&cfg, &mut fabric, &tables, &HashMap::new(), false, &a, &b).await;
+    assert_eq!(report.forward_avg_ms(), Some(5.0));
+    assert_eq!(report.reverse_avg_ms(), Some(6.0));
+}
+
+#[test]
+fn test_clock_skew_config_checked() {
+    let skew = |drift_ppm: f64| ClockSkewConfig {
+        tun_b: EdgeClockConfig {
+            offset_ms: -2.5,
+            drift_ppm,
+        },
+        ..Default::default()
+    };
+    let clocks = EdgeClocks::from_config(&skew(20.0)).unwrap();
+    assert_eq!(
+        clocks.tun_b.read_ms(std::time::Duration::from_secs(100)),
+        100_000.0 - 2.5 + 2.0
+    );
+    let err = EdgeClocks::from_config(&skew(-1e6)).unwrap_err();
+    assert!(err.contains("clock_skew.tun_b.drift_ppm"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_twamp_session_counts_forward_loss() {
+    let (mut fabric, a, b) = two_router_fabric(0, 100.0);
+    let tables = compute_routing(&fabric, a.clone(), b.clone());
+    let cfg = TwampConfig {
+        count: 4,
+        interval_ms: 0,
+        port: 862,
+        ..Default::default()
+    };
+    let report = run_session(&cfg, &mut fabric, &tables, &HashMap::new(), false, &a, &b).await;
+    assert_eq!(report.forward_lost(), 4);
+    assert_eq!(report.loss_percent(), 100.0);
+    assert!(report.rtt_avg_ms().is_none());
+}
+
+#[tokio::test]
+async fn test_twamp_session_steps_one_packet_at_a_time() {
+    let (mut fabric, a, b) = two_router_fabric(0, 0.0);
+    let tables = compute_routing(&fabric, a.clone(), b.clone());
+    let cfg = TwampConfig {
+        count: 2,
+        ..Default::default()
+    };
+    let mut session = TwampSession::new(&cfg, &fabric, &a, &b);
+    session
+        .send_next(&mut fabric, &tables, &HashMap::new(), false)
+        .await;
+    assert!(!session.is_complete());
+    assert_eq!(fabric.get_router(&b).unwrap().stats.packets_delivered, 0);
+    session
+        .send_next(&mut fabric, &tables, &HashMap::new(), false)
+        .await;
+    assert!(session.is_complete());
+    let report = session.into_report();
+    assert_eq!(report.sent(), 2);
+    assert_eq!(report.loss_percent(), 0.0);
+}
+
+#[test]
+fn test_twamp_config_defaults_to_periodic_sessions() {
+    let cfg: TwampConfig = toml::from_str("count = 5").unwrap();
+    assert_eq!(cfg.period_secs, 60);
+    assert_eq!(cfg.interval_ms, 100);
+}
+
+#[test]
+fn test_twamp_report_jitter() {
+    let sample = |seq, fwd, rev| TwampSample {
+        seq,
+        forward_ms: Some(fwd),
+        reverse_ms: Some(rev),
+    };
+    let report = TwampReport {
+        samples: vec![
+            sample(0, 5.0, 5.0),
+            sample(1, 7.0, 5.0),
+            sample(2, 5.0, 6.0),
+        ],
+    };
+    // RTTs are 10, 12, 11 -> differences 2 and 1.
+    assert!((report.jitter_ms() - 1.5).abs() < 1e-9);
+    assert_eq!(report.rtt_min_ms(), Some(10.0));
+    assert_eq!(report.rtt_max_ms(), Some(12.0));
+}
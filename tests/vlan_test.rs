@@ -0,0 +1,163 @@
+mod common;
+
+use common::rid;
+use network_simulator::build_fabric;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::packet::vlan::{tag, untag, VlanTag, ETHERTYPE_VLAN};
+use network_simulator::packet::PacketMeta;
+use network_simulator::processor::process_packet;
+use network_simulator::routing::{compute_routing, Destination};
+use network_simulator::tap::{TapEdge, TapInput, ETHERTYPE_IPV4};
+use std::net::Ipv4Addr;
+
+const ROUTER_MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x0a];
+const HOST_MAC: [u8; 6] = [0x52, 0x54, 0, 0x12, 0x34, 0x56];
+
+/// `cfg` with the edge addresses `validate` expects.
+fn addressed(mut cfg: SimulatorConfig) -> SimulatorConfig {
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+fn udp(vid: Option<u16>) -> PacketMeta {
+    let mut packet = PacketBuilder::new("10.0.0.2".parse().unwrap(), "10.0.1.2".parse().unwrap())
+        .udp(1000, 2000)
+        .build()
+        .unwrap();
+    packet.vlan = vid.map(VlanTag::new);
+    packet
+}
+
+#[test]
+fn test_tag_round_trip() {
+    let tci = VlanTag {
+        vid: 100,
+        pcp: 5,
+        dei: true,
+    };
+    assert_eq!(VlanTag::from_tci(tci.tci()), tci);
+    assert_eq!(tci.tci(), 0xB064);
+
+    let tap = TapEdge::new(ROUTER_MAC, Ipv4Addr::new(10, 0, 0, 1));
+    let packet = udp(None);
+    let plain = tap.frame(&HOST_MAC, ETHERTYPE_IPV4, &packet.raw);
+    let mut tagged = tag(&plain, tci);
+    assert_eq!(tagged.len(), plain.len() + 4);
+    assert_eq!(&tagged[12..14], &ETHERTYPE_VLAN.to_be_bytes());
+    assert_eq!(&tagged[16..18], &ETHERTYPE_IPV4.to_be_bytes());
+
+    let (found, start) = untag(&mut tagged);
+    assert_eq!(found, Some(tci));
+    assert_eq!(&tagged[start..], &plain[..]);
+    // Untagged frames are left alone.
+    let mut untagged = plain.clone();
+    assert_eq!(untag(&mut untagged), (None, 0));
+    assert_eq!(untagged, plain);
+}
+
+#[test]
+fn test_tap_tagged_frames() {
+    let mut tap = TapEdge::new(ROUTER_MAC, Ipv4Addr::new(10, 0, 0, 1));
+    let packet = udp(None);
+    let mut frame = Vec::new();
+    frame.extend_from_slice(&ROUTER_MAC);
+    frame.extend_from_slice(&HOST_MAC);
+    frame.extend_from_slice(&ETHERTYPE_VLAN.to_be_bytes());
+    frame.extend_from_slice(&VlanTag::new(42).tci().to_be_bytes());
+    frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+    frame.extend_from_slice(&packet.raw);
+
+    let (vlan, start) = untag(&mut frame);
+    assert_eq!(vlan, Some(VlanTag::new(42)));
+    assert_eq!(
+        tap.receive(&frame[start..]),
+        TapInput::Packet(&packet.raw[..])
+    );
+
+    // The reply goes back on the VLAN it came from, to the learned host.
+    let reply = PacketBuilder::new("10.0.1.2".parse().unwrap(), "10.0.0.2".parse().unwrap())
+        .udp(2000, 1000)
+        .build()
+        .unwrap();
+    let out = tap.encapsulate_tagged(&reply.raw, vlan);
+    assert_eq!(&out[0..6], &HOST_MAC);
+    assert_eq!(&out[12..16], &[0x81, 0x00, 0x00, 42]);
+    assert_eq!(&out[16..18], &ETHERTYPE_IPV4.to_be_bytes());
+    assert_eq!(&out[18..], &reply.raw[..]);
+    assert_eq!(
+        tap.encapsulate_tagged(&reply.raw, None),
+        tap.encapsulate(&reply.raw)
+    );
+}
+
+#[tokio::test]
+async fn test_link_filters_and_rewrites_vlans() {
+    let cfg = addressed(common::line(
+        "",
+        &["", "", ""],
+        &[
+            "vlans = [100, 200]",
+            "vlan_rewrite = [{ from = 100, to = 300 }]",
+        ],
+        "",
+    ));
+    assert!(cfg.validate().is_ok());
+    let mut fabric = build_fabric(&cfg);
+    let tables = compute_routing(&fabric, rid("Rx0y0"), rid("Rx0y2"));
+
+    // Carried by the first link, translated on the second.
+    let out = process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp(Some(100)),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(out.vlan.map(|tag| tag.vid), Some(300));
+    assert_eq!(out.annotation.as_ref().unwrap().routers.len(), 3);
+
+    // Untagged packets pass every link.
+    let out = process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp(None),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(out.vlan, None);
+    assert_eq!(out.annotation.as_ref().unwrap().routers.len(), 3);
+
+    // VLAN 7 is not carried by the first link.
+    let out = process_packet(
+        &mut fabric,
+        &tables,
+        rid("Rx0y0"),
+        udp(Some(7)),
+        Destination::TunB,
+    )
+    .await;
+    assert_eq!(out.annotation.as_ref().unwrap().routers.len(), 1);
+    let link = fabric.get_link(&rid("Rx0y0"), &rid("Rx0y1")).unwrap();
+    assert_eq!(link.counter(), 2);
+}
+
+#[test]
+fn test_vlan_config_validation() {
+    let cfg = addressed(common::line("", &["", ""], &["vlans = [0]"], ""));
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("invalid VLAN ID 0"), "{}", err);
+    let cfg = addressed(common::line(
+        "",
+        &["", ""],
+        &["vlan_rewrite = [{ from = 10, to = 4095 }]"],
+        "",
+    ));
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("invalid VLAN ID 4095"), "{}", err);
+}
@@ -0,0 +1,78 @@
+use network_simulator::config::SimulatorConfig;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn scenario(warmup_secs: f64, packets: &NamedTempFile) -> SimulatorConfig {
+    toml::from_str(&format!(
+        r#"
+packet_file = "{}"
+
+[simulation]
+warmup_secs = {}
+
+[tun_ingress]
+tun_a_ingress = "Rx0y0"
+tun_b_ingress = "Rx0y1"
+
+[topology.routers]
+Rx0y0 = {{}}
+Rx0y1 = {{}}
+
+[topology.links]
+Rx0y0_Rx0y1 = {{ delay_ms = 100 }}
+"#,
+        packets.path().display(),
+        warmup_secs
+    ))
+    .expect("config parses")
+}
+
+fn packet_file(count: usize) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    for _ in 0..count {
+        writeln!(file, "4500001400000000401100000a0000020a000102").unwrap();
+    }
+    file
+}
+
+fn delivered(fabric: &network_simulator::topology::Fabric) -> u64 {
+    fabric
+        .get_statistics()
+        .values()
+        .map(|s| s.packets_delivered)
+        .sum()
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_warmup_discards_early_statistics() {
+    let packets = packet_file(6);
+    // Each packet spends 100 ms on the link; the warm-up ends while the 4th is pending.
+    let fabric = network_simulator::run(scenario(0.25, &packets))
+        .await
+        .expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+    assert_eq!(delivered(&fabric), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_no_warmup_keeps_all_statistics() {
+    let packets = packet_file(6);
+    let fabric = network_simulator::run(scenario(0.0, &packets))
+        .await
+        .expect("run");
+    let _ = std::fs::remove_file(format!("{}_out.txt", packets.path().display()));
+    // Six mock packets plus the start-up demonstration packet.
+    assert_eq!(delivered(&fabric), 7);
+}
+
+#[test]
+fn test_negative_warmup_rejected() {
+    let packets = packet_file(1);
+    let mut cfg = scenario(-1.0, &packets);
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    let err = cfg.validate().unwrap_err();
+    assert!(err.contains("warmup_secs"), "{}", err);
+}
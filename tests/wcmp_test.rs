@@ -0,0 +1,124 @@
+mod common;
+
+use common::rid;
+use network_simulator::config::SimulatorConfig;
+use network_simulator::forwarding::multipath::select_egress_link_multi;
+use network_simulator::forwarding::weighted_index;
+use network_simulator::packet::builder::PacketBuilder;
+use network_simulator::routing::Destination;
+use network_simulator::{build_fabric, compute_multipath_tables};
+
+/// Square with tun_a at Rx0y0 and tun_b at Rx1y1 and two equal-cost, load-balanced ways:
+/// through Rx0y1 (links with options `via_y1`) and Rx1y0 (`via_x1`).
+fn square(via_y1: &str, via_x1: &str, rest: &str) -> SimulatorConfig {
+    let options = |extra: &str| {
+        if extra.is_empty() {
+            "load_balance = true".to_string()
+        } else {
+            format!("load_balance = true, {}", extra)
+        }
+    };
+    let (via_y1, via_x1) = (options(via_y1), options(via_x1));
+    let mut cfg = common::scenario(
+        "enable_multipath = true",
+        &[("Rx0y0", ""), ("Rx0y1", ""), ("Rx1y0", ""), ("Rx1y1", "")],
+        &[
+            ("Rx0y0_Rx0y1", &via_y1),
+            ("Rx0y1_Rx1y1", &via_y1),
+            ("Rx0y0_Rx1y0", &via_x1),
+            ("Rx1y0_Rx1y1", &via_x1),
+        ],
+        rest,
+    );
+    cfg.interfaces.real_tun_a.address = "10.0.0.1".to_string();
+    cfg.interfaces.real_tun_b.address = "10.0.1.1".to_string();
+    cfg.interfaces.real_tun_a.netmask = "255.255.255.0".to_string();
+    cfg.interfaces.real_tun_b.netmask = "255.255.255.0".to_string();
+    cfg
+}
+
+/// Weights of Rx1y1's next hops towards tun_a, and how many of 400 UDP flows from tun_b go
+/// through Rx0y1 and Rx1y0.
+fn spread(cfg: &SimulatorConfig) -> (Vec<u32>, (usize, usize)) {
+    cfg.validate().expect("valid");
+    let fabric = build_fabric(cfg);
+    let tables = compute_multipath_tables(cfg);
+    let weights = tables[&rid("Rx1y1")]
+        .tun_b
+        .iter()
+        .map(|e| e.weight)
+        .collect();
+    let links = fabric.incident_links(&rid("Rx1y1"));
+    let mut counts = (0, 0);
+    for port in 1000..1400 {
+        let packet = PacketBuilder::new("10.0.1.2".parse().unwrap(), "10.0.0.2".parse().unwrap())
+            .udp(port, 2000)
+            .build()
+            .unwrap();
+        let link = select_egress_link_multi(
+            &rid("Rx1y1"),
+            &packet,
+            &links,
+            &tables,
+            Destination::TunB,
+            &fabric.ecmp_hash,
+        )
+        .expect("link");
+        if link.id.a == rid("Rx0y1") {
+            counts.0 += 1;
+        } else {
+            counts.1 += 1;
+        }
+    }
+    (weights, counts)
+}
+
+#[test]
+fn test_weighted_index_proportional() {
+    assert_eq!(weighted_index(5, &[1, 1, 1]), 2);
+    let mut counts = [0; 2];
+    for hash in 0..400 {
+        counts[weighted_index(hash, &[3, 1])] += 1;
+    }
+    assert_eq!(counts, [300, 100]);
+}
+
+#[test]
+fn test_explicit_weights_skew_flows() {
+    let (weights, (y1, x1)) = spread(&square("", "", ""));
+    assert_eq!(weights, [1, 1]);
+    assert!(y1 > 150 && x1 > 150, "uniform: {} / {}", y1, x1);
+
+    let (weights, (y1, x1)) = spread(&square("weight = 3", "weight = 1", ""));
+    assert_eq!(weights, [3, 1]);
+    let ratio = y1 as f64 / x1 as f64;
+    assert!(ratio > 2.2 && ratio < 4.0, "3:1 weights: {} / {}", y1, x1);
+}
+
+#[test]
+fn test_bandwidth_weights() {
+    let links = ("bandwidth_mbps = 1000", "bandwidth_mbps = 250");
+    // Bandwidth alone does not weigh the next hops.
+    let (weights, _) = spread(&square(links.0, links.1, ""));
+    assert_eq!(weights, [1, 1]);
+
+    let cfg = square(links.0, links.1, "[ecmp_hash]\nbandwidth_weights = true\n");
+    let (weights, (y1, x1)) = spread(&cfg);
+    assert_eq!(weights, [1000, 250]);
+    let ratio = y1 as f64 / x1 as f64;
+    assert!(ratio > 3.0 && ratio < 5.5, "4:1 bandwidth: {} / {}", y1, x1);
+
+    // An explicit weight wins over the bandwidth.
+    let cfg = square(
+        "bandwidth_mbps = 1000, weight = 1",
+        links.1,
+        "[ecmp_hash]\nbandwidth_weights = true\n",
+    );
+    assert_eq!(spread(&cfg).0, [1, 250]);
+}
+
+#[test]
+fn test_weight_validation() {
+    let err = square("weight = 0", "", "").validate().unwrap_err();
+    assert!(err.contains("weight must be at least 1"), "{}", err);
+}
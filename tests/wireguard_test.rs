@@ -0,0 +1,121 @@
+mod common;
+
+use network_simulator::config::{WireguardConfig, WireguardPeerConfig};
+use network_simulator::packet::parse;
+use network_simulator::routing::Destination;
+use network_simulator::wireguard::{parse_key, Reject, Wireguard};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const INNER: &str = "4500001400000000401100000a0000020a000102";
+const KEY_A: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+const KEY_B: &str = "0202020202020202020202020202020202020202020202020202020202020202";
+
+fn peer(key: &str, index: u32) -> WireguardPeerConfig {
+    WireguardPeerConfig {
+        key: key.to_string(),
+        index,
+        peer_key: None,
+    }
+}
+
+fn overlay(a: WireguardPeerConfig, b: WireguardPeerConfig) -> Wireguard {
+    let cfg = WireguardConfig {
+        a,
+        b,
+        udp_port: 51820,
+    };
+    Wireguard::new(
+        &cfg,
+        "10.100.0.1".parse().unwrap(),
+        "10.100.0.2".parse().unwrap(),
+    )
+    .expect("overlay")
+}
+
+#[test]
+fn test_encrypt_round_trip_pads_and_hides_payload() {
+    let mut wg = overlay(peer(KEY_A, 1), peer(KEY_B, 2));
+    let inner = parse(&hex::decode(INNER).unwrap()).unwrap();
+    let tunnel = wg.tunnel_mut(Destination::TunB);
+    // 20 bytes pad to 32: 28 outer + 16 header + 12 padding + 16 tag.
+    assert_eq!(tunnel.overhead(20), 72);
+    let outer = tunnel.encrypt(&inner);
+    assert_eq!(outer.raw.len(), 92);
+    assert_eq!((outer.protocol, outer.dst_port), (17, 51820));
+    // Message type 4 and the receiver's index, little-endian.
+    assert_eq!(&outer.raw[28..36], &[4, 0, 0, 0, 2, 0, 0, 0]);
+    assert!(!outer.raw.windows(8).any(|w| w == &inner.raw[12..20]));
+    let back = tunnel.decrypt(&outer).expect("opened");
+    assert_eq!(back.raw, inner.raw);
+    assert_eq!(tunnel.stats().encrypted, 1);
+    assert_eq!(tunnel.stats().decrypted, 1);
+    assert!(tunnel.stats().summary().contains("us/packet"));
+}
+
+#[test]
+fn test_tampering_and_wrong_key_fail_authentication() {
+    let mut wg = overlay(peer(KEY_A, 1), peer(KEY_B, 2));
+    let inner = parse(&hex::decode(INNER).unwrap()).unwrap();
+    let tunnel = wg.tunnel_mut(Destination::TunB);
+    let mut outer = tunnel.encrypt(&inner);
+    outer.raw_mut()[50] ^= 1;
+    assert_eq!(tunnel.decrypt(&outer).unwrap_err(), Reject::AuthFailed);
+
+    // b holds a stale key for a.
+    let mut b = peer(KEY_B, 2);
+    b.peer_key = Some(KEY_B.to_string());
+    let mut wg = overlay(peer(KEY_A, 1), b);
+    let tunnel = wg.tunnel_mut(Destination::TunB);
+    let outer = tunnel.encrypt(&inner);
+    assert_eq!(tunnel.decrypt(&outer).unwrap_err(), Reject::AuthFailed);
+    assert_eq!(tunnel.stats().auth_failures, 1);
+}
+
+#[test]
+fn test_replays_are_rejected_but_reordering_within_window_is_not() {
+    let mut wg = overlay(peer(KEY_A, 1), peer(KEY_B, 2));
+    let inner = parse(&hex::decode(INNER).unwrap()).unwrap();
+    let tunnel = wg.tunnel_mut(Destination::TunA);
+    let first = tunnel.encrypt(&inner);
+    let second = tunnel.encrypt(&inner);
+    assert!(tunnel.decrypt(&second).is_ok());
+    assert!(tunnel.decrypt(&first).is_ok());
+    assert_eq!(tunnel.decrypt(&first).unwrap_err(), Reject::Replay(0));
+    assert_eq!(tunnel.stats().replayed, 1);
+}
+
+#[test]
+fn test_key_must_be_32_hex_bytes() {
+    assert!(parse_key(KEY_A).is_ok());
+    assert!(parse_key("0102").unwrap_err().contains("32 bytes"));
+    assert!(parse_key("zz").is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_overlay_carries_edge_traffic_end_to_end() {
+    let mut packets = NamedTempFile::new().unwrap();
+    writeln!(packets, "{}", INNER).unwrap();
+    let path = packets.path().display().to_string();
+    let cfg = common::line(
+        &format!("packet_file = \"{}\"", path),
+        &["", "", ""],
+        // The 92-byte outer packet must fit.
+        &["mtu = 100", "mtu = 92"],
+        &format!(
+            r#"
+[wireguard]
+a = {{ key = "{}", index = 1 }}
+b = {{ key = "{}", index = 2 }}
+"#,
+            KEY_A, KEY_B
+        ),
+    );
+    let fabric = network_simulator::run(cfg).await.expect("run");
+    let out_path = format!("{}_out.txt", path);
+    let out = std::fs::read_to_string(&out_path).unwrap();
+    let _ = std::fs::remove_file(&out_path);
+    assert_eq!(out, format!("{}\n", INNER));
+    let wg = fabric.wireguard.as_ref().expect("overlay");
+    assert_eq!(wg.a_to_b.stats().decrypted, 1);
+}